@@ -0,0 +1,5 @@
+-- Keep the previous token hash after rotation so requests already in flight
+-- with the old token are still accepted for a short grace period
+ALTER TABLE user_sessions ADD COLUMN previous_token_hash TEXT;
+
+CREATE INDEX idx_user_sessions_previous_token_hash ON user_sessions(previous_token_hash);
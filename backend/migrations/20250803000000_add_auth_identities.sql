@@ -0,0 +1,17 @@
+PRAGMA foreign_keys = ON;
+
+-- Non-GitHub login identities (local accounts and OIDC subjects).
+-- Users created through these providers get a negative placeholder github_id,
+-- which can never collide with a real (positive) GitHub account ID.
+CREATE TABLE auth_identities (
+    id BLOB PRIMARY KEY,
+    user_id BLOB NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    provider TEXT NOT NULL CHECK (provider IN ('local', 'oidc')),
+    subject TEXT NOT NULL, -- login name for local accounts, `sub` claim for OIDC
+    password_hash TEXT,    -- argon2 PHC string, local accounts only
+    created_at TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    updated_at TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    UNIQUE (provider, subject)
+);
+
+CREATE INDEX idx_auth_identities_user_id ON auth_identities(user_id);
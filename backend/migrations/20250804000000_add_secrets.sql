@@ -0,0 +1,17 @@
+PRAGMA foreign_keys = ON;
+
+-- Encrypted secrets (GitHub tokens, API keys, ...). Values are sealed with
+-- AES-256-GCM under a versioned key from the keyring file, never stored here.
+-- `key_version` records which key sealed the row so rotation can find stale rows.
+CREATE TABLE secrets (
+    id BLOB PRIMARY KEY,
+    scope TEXT NOT NULL,      -- 'config' for install-wide secrets, 'user:<uuid>' for per-user ones
+    name TEXT NOT NULL,
+    key_version INTEGER NOT NULL,
+    ciphertext TEXT NOT NULL, -- base64(nonce || ciphertext), scope/name bound as AAD
+    created_at TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    updated_at TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    UNIQUE (scope, name)
+);
+
+CREATE INDEX idx_secrets_key_version ON secrets(key_version);
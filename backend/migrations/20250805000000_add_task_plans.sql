@@ -0,0 +1,15 @@
+PRAGMA foreign_keys = ON;
+
+-- Executor new attempts of a task use when none is requested, set by plans
+ALTER TABLE tasks ADD COLUMN executor TEXT;
+
+-- Tasks that have to be done before another task can start
+CREATE TABLE task_dependencies (
+    task_id BLOB NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+    depends_on_task_id BLOB NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+    created_at TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    PRIMARY KEY (task_id, depends_on_task_id),
+    CHECK (task_id != depends_on_task_id)
+);
+
+CREATE INDEX idx_task_dependencies_depends_on ON task_dependencies(depends_on_task_id);
@@ -0,0 +1,3 @@
+-- Opt-in: give every coding agent of the project a per-attempt config for the
+-- Forge MCP server instead of relying on the agent's global config file
+ALTER TABLE projects ADD COLUMN inject_mcp_server BOOLEAN NOT NULL DEFAULT FALSE;
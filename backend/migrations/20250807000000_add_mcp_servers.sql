@@ -0,0 +1,25 @@
+PRAGMA foreign_keys = ON;
+
+-- MCP servers given to the coding agents of a project. Rows without a user
+-- apply to everyone on the project; a user's row overrides the project server
+-- of the same name for that user's attempts. Environment values and HTTP
+-- headers are secrets, kept encrypted in `secrets` under 'mcp_server:<id>'.
+CREATE TABLE mcp_servers (
+    id BLOB PRIMARY KEY,
+    project_id BLOB NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+    user_id BLOB REFERENCES users(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    transport TEXT NOT NULL DEFAULT 'stdio' CHECK (transport IN ('stdio', 'http', 'sse')),
+    command TEXT,
+    args TEXT NOT NULL DEFAULT '[]', -- JSON array of strings
+    url TEXT,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    updated_at TEXT NOT NULL DEFAULT (datetime('now', 'subsec'))
+);
+
+-- One server per name for the project, and one override per name per user
+CREATE UNIQUE INDEX idx_mcp_servers_project_name
+    ON mcp_servers(project_id, name) WHERE user_id IS NULL;
+CREATE UNIQUE INDEX idx_mcp_servers_user_name
+    ON mcp_servers(project_id, user_id, name) WHERE user_id IS NOT NULL;
@@ -0,0 +1,17 @@
+PRAGMA foreign_keys = ON;
+
+-- Channels a user is notified on about their own tasks. `settings` is a JSON
+-- object of the kind's plain settings (SMTP host, ntfy topic, ...); webhook
+-- URLs, passwords and tokens are kept encrypted in `secrets` under
+-- 'notification_channel:<id>'.
+CREATE TABLE user_notification_channels (
+    id BLOB PRIMARY KEY,
+    user_id BLOB NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    kind TEXT NOT NULL CHECK (kind IN ('slack', 'discord', 'email', 'ntfy', 'gotify', 'whatsapp')),
+    settings TEXT NOT NULL DEFAULT '{}',
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    updated_at TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    UNIQUE(user_id, name)
+);
@@ -0,0 +1,33 @@
+PRAGMA foreign_keys = ON;
+
+-- Which events a user is notified about and how. `events` and `channels` are
+-- JSON arrays; an empty `channels` means every enabled channel. Quiet hours
+-- are 'HH:MM' in the user's local time, `utc_offset_minutes` ahead of UTC.
+CREATE TABLE user_notification_rules (
+    id BLOB PRIMARY KEY,
+    user_id BLOB NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    events TEXT NOT NULL DEFAULT '[]',
+    scope TEXT NOT NULL DEFAULT 'involved' CHECK (scope IN ('created', 'assigned', 'involved')),
+    channels TEXT NOT NULL DEFAULT '[]',
+    quiet_hours_start TEXT,
+    quiet_hours_end TEXT,
+    utc_offset_minutes INTEGER NOT NULL DEFAULT 0,
+    digest_minutes INTEGER,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    updated_at TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    UNIQUE(user_id, name)
+);
+
+-- Notifications held back for a digest or until quiet hours end
+CREATE TABLE notification_digest_items (
+    id BLOB PRIMARY KEY,
+    rule_id BLOB NOT NULL REFERENCES user_notification_rules(id) ON DELETE CASCADE,
+    title TEXT NOT NULL,
+    message TEXT NOT NULL,
+    url TEXT,
+    created_at TEXT NOT NULL DEFAULT (datetime('now', 'subsec'))
+);
+
+CREATE INDEX idx_notification_digest_items_rule_id ON notification_digest_items(rule_id, created_at);
@@ -0,0 +1,22 @@
+PRAGMA foreign_keys = ON;
+
+-- WhatsApp messages sent, or to be sent, through the Evolution API. Pending
+-- messages are sent once `next_attempt_at` has passed and retried with
+-- exponential backoff until they are sent or run out of attempts.
+CREATE TABLE whatsapp_outbox (
+    id BLOB PRIMARY KEY,
+    -- Whose notification channel the message is for; NULL for install-wide
+    -- notifications
+    user_id BLOB REFERENCES users(id) ON DELETE SET NULL,
+    recipient TEXT NOT NULL,
+    message TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'sent', 'failed')),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    last_error TEXT,
+    created_at TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    sent_at TEXT
+);
+
+CREATE INDEX idx_whatsapp_outbox_due ON whatsapp_outbox(status, next_attempt_at);
+CREATE INDEX idx_whatsapp_outbox_user ON whatsapp_outbox(user_id, created_at);
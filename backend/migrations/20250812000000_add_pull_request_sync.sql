@@ -0,0 +1,49 @@
+PRAGMA foreign_keys = ON;
+
+-- Pull request state synced from GitHub webhooks for attempts with a PR.
+-- Rows are keyed by GitHub's own IDs, so redelivered events update them in
+-- place.
+CREATE TABLE pr_reviews (
+    id BLOB PRIMARY KEY,
+    task_attempt_id BLOB NOT NULL REFERENCES task_attempts(id) ON DELETE CASCADE,
+    github_id INTEGER NOT NULL,
+    reviewer TEXT NOT NULL,
+    state TEXT NOT NULL CHECK (state IN ('approved', 'changes_requested', 'commented', 'dismissed')),
+    body TEXT,
+    submitted_at TEXT NOT NULL,
+    UNIQUE(task_attempt_id, github_id)
+);
+
+-- Inline review comments. `line` is the line of the PR's head the comment is
+-- on; NULL once the code it was on has changed.
+CREATE TABLE pr_review_comments (
+    id BLOB PRIMARY KEY,
+    task_attempt_id BLOB NOT NULL REFERENCES task_attempts(id) ON DELETE CASCADE,
+    github_id INTEGER NOT NULL,
+    review_github_id INTEGER,
+    in_reply_to_github_id INTEGER,
+    author TEXT NOT NULL,
+    body TEXT NOT NULL,
+    path TEXT NOT NULL,
+    line INTEGER,
+    diff_hunk TEXT,
+    url TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    UNIQUE(task_attempt_id, github_id)
+);
+
+-- Latest run of each CI check on the PR's head commit
+CREATE TABLE pr_check_runs (
+    id BLOB PRIMARY KEY,
+    task_attempt_id BLOB NOT NULL REFERENCES task_attempts(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    head_sha TEXT NOT NULL,
+    status TEXT NOT NULL,
+    conclusion TEXT,
+    url TEXT,
+    updated_at TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    UNIQUE(task_attempt_id, name)
+);
+
+CREATE INDEX idx_task_attempts_pr_url ON task_attempts(lower(pr_url));
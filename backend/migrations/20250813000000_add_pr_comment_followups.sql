@@ -0,0 +1,21 @@
+PRAGMA foreign_keys = ON;
+
+-- Whether review comments on a project's pull requests are addressed as soon
+-- as a review is submitted, instead of when someone asks
+ALTER TABLE projects ADD COLUMN auto_address_review_comments BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Follow-up executions started to address PR review threads. `threads` is a
+-- JSON array of the threads' first comment, which replies go to, and their
+-- latest comment; a thread is picked up again only once someone comments
+-- after that.
+CREATE TABLE pr_comment_followups (
+    id BLOB PRIMARY KEY,
+    task_attempt_id BLOB NOT NULL REFERENCES task_attempts(id) ON DELETE CASCADE,
+    status TEXT NOT NULL DEFAULT 'running' CHECK (status IN ('running', 'pushed', 'failed')),
+    threads TEXT NOT NULL DEFAULT '[]',
+    error TEXT,
+    created_at TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    completed_at TEXT
+);
+
+CREATE INDEX idx_pr_comment_followups_task_attempt_id ON pr_comment_followups(task_attempt_id);
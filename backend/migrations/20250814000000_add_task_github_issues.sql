@@ -0,0 +1,9 @@
+PRAGMA foreign_keys = ON;
+
+-- GitHub issue of the project's repository a task was imported from. PRs of
+-- the task close it, and merging the task comments on it.
+ALTER TABLE tasks ADD COLUMN github_issue_number INTEGER;
+
+CREATE UNIQUE INDEX idx_tasks_github_issue_number
+    ON tasks(project_id, github_issue_number)
+    WHERE github_issue_number IS NOT NULL;
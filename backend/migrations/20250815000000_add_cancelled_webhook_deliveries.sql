@@ -0,0 +1,29 @@
+PRAGMA foreign_keys = ON;
+
+-- Deliveries to a webhook that was disabled or deleted before they were sent
+-- are cancelled rather than retried. SQLite cannot alter a CHECK constraint,
+-- so the table is recreated with 'cancelled' allowed.
+CREATE TABLE webhook_deliveries_new (
+    id BLOB PRIMARY KEY,
+    webhook_id BLOB NOT NULL REFERENCES webhooks(id) ON DELETE CASCADE,
+    event TEXT NOT NULL,
+    payload TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'delivered', 'failed', 'cancelled')),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    last_status_code INTEGER,
+    last_error TEXT,
+    created_at TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    delivered_at TEXT
+);
+
+INSERT INTO webhook_deliveries_new (id, webhook_id, event, payload, status, attempts, next_attempt_at, last_status_code, last_error, created_at, delivered_at)
+SELECT id, webhook_id, event, payload, status, attempts, next_attempt_at, last_status_code, last_error, created_at, delivered_at
+FROM webhook_deliveries;
+
+DROP TABLE webhook_deliveries;
+
+ALTER TABLE webhook_deliveries_new RENAME TO webhook_deliveries;
+
+CREATE INDEX idx_webhook_deliveries_due ON webhook_deliveries(status, next_attempt_at);
+CREATE INDEX idx_webhook_deliveries_webhook ON webhook_deliveries(webhook_id, created_at);
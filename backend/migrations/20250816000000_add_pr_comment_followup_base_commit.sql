@@ -0,0 +1,5 @@
+PRAGMA foreign_keys = ON;
+
+-- The attempt's HEAD when a review follow-up started, so replies only cite a
+-- commit when the follow-up actually made one
+ALTER TABLE pr_comment_followups ADD COLUMN base_commit TEXT;
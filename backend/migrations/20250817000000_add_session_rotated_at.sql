@@ -0,0 +1,4 @@
+-- When a session's token was last rotated, written with datetime('now', 'subsec')
+-- like created_at. The rotation clock and the grace period of the replaced
+-- token run from here, so created_at keeps the time the user logged in.
+ALTER TABLE user_sessions ADD COLUMN rotated_at TEXT;
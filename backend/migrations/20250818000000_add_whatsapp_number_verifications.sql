@@ -0,0 +1,20 @@
+PRAGMA foreign_keys = ON;
+
+-- WhatsApp numbers users proved are theirs by entering a one-time code Forge
+-- sent to the number. Only a verified number authorizes the commands sent
+-- from it, and a number is verified for one user at most. `number` holds the
+-- number's digits only.
+CREATE TABLE whatsapp_number_verifications (
+    id BLOB PRIMARY KEY,
+    user_id BLOB NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    number TEXT NOT NULL,
+    code_hash TEXT NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    expires_at TEXT NOT NULL,
+    verified_at TEXT,
+    created_at TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    UNIQUE (user_id, number)
+);
+
+CREATE UNIQUE INDEX idx_whatsapp_number_verifications_verified_number
+    ON whatsapp_number_verifications(number) WHERE verified_at IS NOT NULL;
@@ -0,0 +1,13 @@
+-- Local login names are matched trimmed and lowercased. Names that would
+-- collide with another account once lowercased are left alone for an admin
+-- to rename.
+UPDATE auth_identities
+SET subject = lower(trim(subject))
+WHERE provider = 'local'
+  AND subject != lower(trim(subject))
+  AND NOT EXISTS (
+      SELECT 1 FROM auth_identities other
+      WHERE other.provider = 'local'
+        AND other.id != auth_identities.id
+        AND lower(trim(other.subject)) = lower(trim(auth_identities.subject))
+  );
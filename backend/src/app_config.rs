@@ -4,12 +4,24 @@ use std::path::PathBuf;
 use std::env;
 use uuid::Uuid;
 
+use crate::{
+    auth_providers::{AuthProvidersConfig, OidcConfig},
+    security::rate_limiter::RateLimitConfig,
+};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppConfig {
     pub jwt_secret: String,
     pub github_client_id: Option<String>,
     pub github_client_secret: Option<String>,
     pub database_url: Option<String>,
+    #[serde(default)]
+    pub rate_limits: RateLimitConfig,
+    #[serde(default)]
+    pub auth: AuthProvidersConfig,
+    /// MCP access token the STDIO MCP server runs its tools as
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mcp_token: Option<String>,
 }
 
 impl Default for AppConfig {
@@ -19,6 +31,9 @@ impl Default for AppConfig {
             github_client_id: Some("Ov23li2nd1KF5nCPbgoj".to_string()),
             github_client_secret: None,
             database_url: None,
+            rate_limits: RateLimitConfig::default(),
+            auth: AuthProvidersConfig::default(),
+            mcp_token: None,
         }
     }
 }
@@ -62,6 +77,31 @@ impl AppConfig {
             }
         }
         
+        if let Some(enabled) = env_flag("AUTH_GITHUB_ENABLED") {
+            config.auth.github_enabled = enabled;
+        }
+
+        if let Some(enabled) = env_flag("AUTH_LOCAL_ENABLED") {
+            config.auth.local_enabled = enabled;
+        }
+
+        if let (Ok(issuer_url), Ok(client_id)) = (env::var("OIDC_ISSUER_URL"), env::var("OIDC_CLIENT_ID")) {
+            if !issuer_url.is_empty() && !client_id.is_empty() {
+                let oidc = config
+                    .auth
+                    .oidc
+                    .get_or_insert_with(|| OidcConfig::new(issuer_url.clone(), client_id.clone()));
+                oidc.issuer_url = issuer_url;
+                oidc.client_id = client_id;
+            }
+        }
+
+        if let (Some(oidc), Ok(client_secret)) = (config.auth.oidc.as_mut(), env::var("OIDC_CLIENT_SECRET")) {
+            if !client_secret.is_empty() {
+                oidc.client_secret = Some(client_secret);
+            }
+        }
+        
         // Validate JWT secret length
         if config.jwt_secret.len() < 32 {
             tracing::warn!("JWT_SECRET too short, generating new secure secret");
@@ -129,6 +169,15 @@ impl AppConfig {
     }
 }
 
+/// Parse a boolean environment variable ("true"/"1" or "false"/"0")
+fn env_flag(key: &str) -> Option<bool> {
+    match env::var(key).ok()?.to_lowercase().as_str() {
+        "true" | "1" => Some(true),
+        "false" | "0" => Some(false),
+        _ => None,
+    }
+}
+
 /// Get the path to the configuration file
 fn get_config_file_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
     let home_dir = dirs::home_dir()
@@ -196,12 +245,37 @@ mod tests {
             github_client_id: Some("test-client-id".to_string()),
             github_client_secret: Some("test-client-secret".to_string()),
             database_url: Some("sqlite:test.db".to_string()),
+            rate_limits: RateLimitConfig::default(),
+            auth: AuthProvidersConfig::default(),
+            mcp_token: None,
         };
         
         let toml_str = toml::to_string(&config).unwrap();
-        let deserialized: Config = toml::from_str(&toml_str).unwrap();
+        let deserialized: AppConfig = toml::from_str(&toml_str).unwrap();
         
         assert_eq!(config.jwt_secret, deserialized.jwt_secret);
         assert_eq!(config.github_client_id, deserialized.github_client_id);
     }
+
+    #[test]
+    fn test_auth_section_defaults() {
+        let toml_str = r#"
+            jwt_secret = "test-jwt-secret-with-32-characters"
+
+            [auth]
+            local_enabled = true
+
+            [auth.oidc]
+            issuer_url = "https://sso.example.internal/realms/forge"
+            client_id = "forge"
+        "#;
+        let config: AppConfig = toml::from_str(toml_str).unwrap();
+
+        assert!(config.auth.github_enabled);
+        assert!(config.auth.local_enabled);
+        let oidc = config.auth.oidc.unwrap();
+        assert_eq!(oidc.scopes, vec!["openid", "email", "profile"]);
+        assert_eq!(oidc.username_claim, "preferred_username");
+        assert!(!oidc.auto_provision);
+    }
 }
\ No newline at end of file
@@ -1,4 +1,8 @@
-use std::{collections::HashMap, sync::Arc, time::Duration};
+use std::{
+    collections::HashMap,
+    sync::{Arc, OnceLock},
+    time::Duration,
+};
 
 #[cfg(unix)]
 use nix::{sys::signal::Signal, unistd::Pid};
@@ -7,8 +11,13 @@ use uuid::Uuid;
 
 use crate::{
     auth::JwtConfig,
-    services::{generate_user_id, AnalyticsConfig, AnalyticsService},
-    security::audit_logger::AuditLogger,
+    auth_providers::AuthProviders,
+    services::{execution_env, generate_user_id, AnalyticsConfig, AnalyticsService},
+    app_config::AppConfig,
+    security::{
+        audit_logger::AuditLogger, rate_limiter::RateLimiter, secret_store::SecretStore,
+        session_security::SessionSecurity,
+    },
 };
 
 #[derive(Debug)]
@@ -32,16 +41,22 @@ pub struct AppState {
     pub db_pool: sqlx::SqlitePool,
     config: Arc<tokio::sync::RwLock<crate::models::config::Config>>,
     pub analytics: Arc<TokioRwLock<AnalyticsService>>,
-    #[allow(dead_code)] // Security audit system for future compliance features
     pub audit_logger: AuditLogger,
+    session_security: Arc<SessionSecurity>,
+    rate_limiter: Arc<RateLimiter>,
+    auth_providers: Arc<AuthProviders>,
+    secret_store: Arc<SecretStore>,
     user_id: String,
     jwt_config: Arc<JwtConfig>,
+    /// Where this backend can be reached locally, known once it listens
+    server_url: Arc<OnceLock<String>>,
 }
 
 impl AppState {
     pub async fn new(
         db_pool: sqlx::SqlitePool,
         config: Arc<tokio::sync::RwLock<crate::models::config::Config>>,
+        secret_store: Arc<SecretStore>,
     ) -> Self {
         // Initialize analytics with user preferences
         let user_enabled = {
@@ -58,14 +73,35 @@ impl AppState {
         // Load JWT config ONCE at startup to prevent race conditions
         let jwt_config = Arc::new(JwtConfig::from_config());
 
+        // Session rotation, caps and suspicious-activity checks for auth_middleware
+        let session_security = Arc::new(SessionSecurity::new(
+            db_pool.clone(),
+            (*jwt_config).clone(),
+            audit_logger.clone(),
+            None,
+        ));
+
+        let app_config = AppConfig::load().unwrap_or_default();
+
+        // Per-route-group limits for the login and OAuth endpoints
+        let rate_limiter = Arc::new(RateLimiter::new(app_config.rate_limits));
+
+        // GitHub, local account and OIDC login providers enabled for this install
+        let auth_providers = Arc::new(AuthProviders::from_config(&app_config.auth));
+
         Self {
             running_executions: Arc::new(Mutex::new(HashMap::new())),
             db_pool,
             config,
             analytics,
             audit_logger,
+            session_security,
+            rate_limiter,
+            auth_providers,
+            secret_store,
             user_id: generate_user_id(),
             jwt_config,
+            server_url: Arc::new(OnceLock::new()),
         }
     }
 
@@ -129,6 +165,7 @@ impl AppState {
         // Remove completed executions from the map
         for (execution_id, _, _, _) in &completed_executions {
             executions.remove(execution_id);
+            execution_env::release_redactions(*execution_id);
         }
 
         completed_executions
@@ -170,6 +207,7 @@ impl AppState {
 
         // only NOW remove it
         executions.remove(&execution_id);
+        execution_env::release_redactions(execution_id);
         Ok(true)
     }
 
@@ -241,8 +279,43 @@ impl AppState {
     }
 
     /// Access to audit logger for security events
-    #[allow(dead_code)]
     pub fn audit_logger(&self) -> &AuditLogger {
         &self.audit_logger
     }
+
+    /// Access to the session security manager used by authentication
+    pub fn session_security(&self) -> &Arc<SessionSecurity> {
+        &self.session_security
+    }
+
+    /// Access to the rate limiter guarding authentication endpoints
+    pub fn rate_limiter(&self) -> &Arc<RateLimiter> {
+        &self.rate_limiter
+    }
+
+    /// Access to the configured login providers
+    pub fn auth_providers(&self) -> &Arc<AuthProviders> {
+        &self.auth_providers
+    }
+
+    pub fn secret_store(&self) -> &Arc<SecretStore> {
+        &self.secret_store
+    }
+
+    /// Record the address the server listens on, as a URL local processes
+    /// such as coding agents can reach it by
+    pub fn set_server_address(&self, address: std::net::SocketAddr) {
+        let host = match address.ip() {
+            ip if ip.is_unspecified() => std::net::Ipv4Addr::LOCALHOST.into(),
+            ip => ip,
+        };
+        let _ = self
+            .server_url
+            .set(format!("http://{}", std::net::SocketAddr::new(host, address.port())));
+    }
+
+    /// Base URL of this backend, once it listens
+    pub fn server_url(&self) -> Option<&str> {
+        self.server_url.get().map(String::as_str)
+    }
 }
@@ -1,6 +1,6 @@
 use axum::{
     extract::{Request, State},
-    http::{header::AUTHORIZATION, StatusCode},
+    http::{header::AUTHORIZATION, HeaderValue, StatusCode},
     middleware::Next,
     response::Response,
 };
@@ -16,6 +16,7 @@ use crate::{
         user::User,
         user_session::{SessionType, UserSession},
     },
+    security::audit_logger::extract_request_context,
 };
 use super::app_config::AppConfig;
 
@@ -119,6 +120,10 @@ pub fn extract_bearer_token(auth_header: &str) -> Option<&str> {
     }
 }
 
+/// Response header carrying a freshly rotated session token. Clients should
+/// replace their stored bearer token with this value when it is present.
+pub const REFRESHED_TOKEN_HEADER: &str = "x-refreshed-token";
+
 /// User context for authenticated requests
 #[derive(Debug, Clone)]
 pub struct UserContext {
@@ -159,14 +164,26 @@ pub async fn auth_middleware(
     // Hash token for database lookup
     let token_hash = hash_token(token);
 
-    // Verify session exists and is valid
-    let session = UserSession::find_valid_by_token_hash(&app_state.db_pool, &token_hash)
+    let session_security = app_state.session_security();
+
+    // Verify session exists and is valid, falling back to a token that was
+    // just rotated away by a concurrent request
+    let session = match UserSession::find_valid_by_token_hash(&app_state.db_pool, &token_hash)
         .await
         .map_err(|e| {
             tracing::error!("Database error during session lookup: {}", e);
             StatusCode::INTERNAL_SERVER_ERROR
-        })?
-        .ok_or(StatusCode::UNAUTHORIZED)?;
+        })? {
+        Some(session) => session,
+        None => session_security
+            .find_session_by_rotated_token(token)
+            .await
+            .map_err(|e| {
+                tracing::error!("Database error during session lookup: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?
+            .ok_or(StatusCode::UNAUTHORIZED)?,
+    };
 
     // Verify session belongs to the user in the JWT
     if session.user_id != user_id || session.id != session_id {
@@ -189,6 +206,34 @@ pub async fn auth_middleware(
         return Err(StatusCode::FORBIDDEN);
     }
 
+    let (ip_address, user_agent) = extract_request_context(req.headers());
+
+    // Rotate long-lived tokens, then look for suspicious activity of the
+    // user; the check runs on rotation and login rather than every request
+    let mut refreshed_token = None;
+    if session_security.needs_token_rotation(&session).await {
+        match session_security
+            .rotate_token(token, ip_address.clone(), user_agent.clone())
+            .await
+        {
+            Ok(new_token) => {
+                refreshed_token = Some(new_token);
+                match session_security
+                    .screen_session(&session, ip_address, user_agent)
+                    .await
+                {
+                    Ok(true) => {
+                        tracing::warn!("Session {} revoked after security alert", session.id);
+                        return Err(StatusCode::UNAUTHORIZED);
+                    }
+                    Ok(false) => {}
+                    Err(e) => tracing::warn!("Suspicious activity check failed: {}", e),
+                }
+            }
+            Err(e) => tracing::warn!("Failed to rotate token for session {}: {}", session.id, e),
+        }
+    }
+
     // Update last login time
     if let Err(e) = User::update_last_login(&app_state.db_pool, user_id).await {
         tracing::warn!("Failed to update last login time: {}", e);
@@ -205,7 +250,16 @@ pub async fn auth_middleware(
     // Insert user context into request extensions
     req.extensions_mut().insert(user_context);
 
-    Ok(next.run(req).await)
+    let mut response = next.run(req).await;
+
+    // Hand the rotated token back to the client
+    if let Some(new_token) = refreshed_token {
+        if let Ok(value) = HeaderValue::from_str(&new_token) {
+            response.headers_mut().insert(REFRESHED_TOKEN_HEADER, value);
+        }
+    }
+
+    Ok(response)
 }
 
 /// Optional authentication middleware (doesn't fail on missing auth)
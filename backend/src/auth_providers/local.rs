@@ -0,0 +1,298 @@
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
+use serde::Deserialize;
+use sqlx::SqlitePool;
+use ts_rs::TS;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use super::{
+    placeholder_github_id, AuthProvider, AuthProviderError, AuthProviderInfo, AuthProviderKind,
+    LoginFlow,
+};
+use crate::models::{
+    auth_identity::{AuthIdentity, CreateAuthIdentity, IdentityProvider},
+    user::{CreateUser, User},
+};
+
+pub const MIN_PASSWORD_LENGTH: usize = 12;
+
+/// The form a local login name is stored, looked up and rate limited in, so
+/// `Admin` and ` admin` are the same account
+pub fn normalize_username(username: &str) -> String {
+    username.trim().to_lowercase()
+}
+
+/// Admin request to create a local account
+#[derive(Debug, Deserialize, TS, ToSchema)]
+#[ts(export)]
+pub struct CreateLocalUser {
+    pub username: String,
+    pub password: String,
+    pub email: Option<String>,
+    pub display_name: Option<String>,
+    pub is_admin: Option<bool>,
+}
+
+/// Username/password accounts stored in `auth_identities` with argon2 hashes
+#[derive(Debug, Clone, Default)]
+pub struct LocalAuthProvider;
+
+impl AuthProvider for LocalAuthProvider {
+    fn info(&self) -> AuthProviderInfo {
+        AuthProviderInfo {
+            kind: AuthProviderKind::Local,
+            display_name: "Username and password".to_string(),
+            login_flow: LoginFlow::Password,
+        }
+    }
+}
+
+impl LocalAuthProvider {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub fn hash_password(password: &str) -> Result<String, AuthProviderError> {
+        let salt = SaltString::generate(&mut OsRng);
+        Argon2::default()
+            .hash_password(password.as_bytes(), &salt)
+            .map(|hash| hash.to_string())
+            .map_err(|e| AuthProviderError::Hashing(e.to_string()))
+    }
+
+    pub fn verify_password(password: &str, password_hash: &str) -> bool {
+        let Ok(parsed) = PasswordHash::new(password_hash) else {
+            return false;
+        };
+        Argon2::default()
+            .verify_password(password.as_bytes(), &parsed)
+            .is_ok()
+    }
+
+    fn validate_password(password: &str) -> Result<(), AuthProviderError> {
+        if password.chars().count() < MIN_PASSWORD_LENGTH {
+            return Err(AuthProviderError::WeakPassword(MIN_PASSWORD_LENGTH));
+        }
+        Ok(())
+    }
+
+    /// Create a user together with its local identity
+    pub async fn create_user(
+        &self,
+        pool: &SqlitePool,
+        data: &CreateLocalUser,
+    ) -> Result<User, AuthProviderError> {
+        let username = data.username.trim();
+        let login_name = normalize_username(username);
+        if login_name.is_empty() {
+            return Err(AuthProviderError::InvalidCredentials);
+        }
+        Self::validate_password(&data.password)?;
+
+        if AuthIdentity::find_by_provider_subject(pool, IdentityProvider::Local, &login_name)
+            .await?
+            .is_some()
+        {
+            return Err(AuthProviderError::UsernameTaken);
+        }
+
+        let password_hash = Self::hash_password(&data.password)?;
+        let user_id = Uuid::new_v4();
+        let user = User::create(
+            pool,
+            &CreateUser {
+                github_id: placeholder_github_id(user_id),
+                username: username.to_string(),
+                email: data.email.clone().unwrap_or_default(),
+                display_name: data.display_name.clone(),
+                avatar_url: None,
+                github_token: None,
+                is_admin: data.is_admin,
+            },
+            user_id,
+        )
+        .await?;
+
+        let identity = CreateAuthIdentity {
+            user_id: user.id,
+            provider: IdentityProvider::Local,
+            subject: login_name,
+            password_hash: Some(password_hash),
+        };
+        if let Err(e) = AuthIdentity::create(pool, &identity, Uuid::new_v4()).await {
+            // Don't leave a user behind that nobody can log in as
+            if let Err(cleanup) = User::delete(pool, user.id).await {
+                tracing::error!(
+                    "Failed to remove user {} after identity error: {}",
+                    user.id,
+                    cleanup
+                );
+            }
+            return Err(e.into());
+        }
+
+        Ok(user)
+    }
+
+    /// Check a username/password pair and return the matching user
+    pub async fn authenticate(
+        &self,
+        pool: &SqlitePool,
+        username: &str,
+        password: &str,
+    ) -> Result<User, AuthProviderError> {
+        let identity = AuthIdentity::find_by_provider_subject(
+            pool,
+            IdentityProvider::Local,
+            &normalize_username(username),
+        )
+        .await?;
+
+        let Some(identity) = identity else {
+            // Spend the same time as a real check so usernames can't be probed
+            let _ = Self::hash_password(password);
+            return Err(AuthProviderError::InvalidCredentials);
+        };
+
+        let verified = identity
+            .password_hash
+            .as_deref()
+            .is_some_and(|hash| Self::verify_password(password, hash));
+        if !verified {
+            return Err(AuthProviderError::InvalidCredentials);
+        }
+
+        let user = User::find_by_id(pool, identity.user_id)
+            .await?
+            .ok_or(AuthProviderError::InvalidCredentials)?;
+        if !user.is_whitelisted {
+            return Err(AuthProviderError::UserNotAuthorized);
+        }
+
+        Ok(user)
+    }
+
+    /// Replace a user's password after checking the current one
+    pub async fn change_password(
+        &self,
+        pool: &SqlitePool,
+        user_id: Uuid,
+        current_password: &str,
+        new_password: &str,
+    ) -> Result<(), AuthProviderError> {
+        let identity =
+            AuthIdentity::find_by_user_and_provider(pool, user_id, IdentityProvider::Local)
+                .await?
+                .ok_or(AuthProviderError::ProviderDisabled)?;
+
+        let verified = identity
+            .password_hash
+            .as_deref()
+            .is_some_and(|hash| Self::verify_password(current_password, hash));
+        if !verified {
+            return Err(AuthProviderError::InvalidCredentials);
+        }
+
+        Self::validate_password(new_password)?;
+        let password_hash = Self::hash_password(new_password)?;
+        AuthIdentity::update_password_hash(pool, identity.id, &password_hash).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_and_verify_password() {
+        let hash = LocalAuthProvider::hash_password("correct horse battery").unwrap();
+        assert!(hash.starts_with("$argon2"));
+        assert!(LocalAuthProvider::verify_password(
+            "correct horse battery",
+            &hash
+        ));
+        assert!(!LocalAuthProvider::verify_password(
+            "wrong horse battery",
+            &hash
+        ));
+    }
+
+    #[test]
+    fn test_hashes_are_salted() {
+        let first = LocalAuthProvider::hash_password("correct horse battery").unwrap();
+        let second = LocalAuthProvider::hash_password("correct horse battery").unwrap();
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn test_verify_rejects_malformed_hash() {
+        assert!(!LocalAuthProvider::verify_password(
+            "anything",
+            "not-a-phc-string"
+        ));
+    }
+
+    #[test]
+    fn test_short_passwords_are_rejected() {
+        assert!(matches!(
+            LocalAuthProvider::validate_password("short"),
+            Err(AuthProviderError::WeakPassword(MIN_PASSWORD_LENGTH))
+        ));
+        assert!(LocalAuthProvider::validate_password("long enough password").is_ok());
+    }
+
+    #[test]
+    fn test_normalize_username() {
+        assert_eq!(normalize_username(" Jane.Doe "), "jane.doe");
+        assert_eq!(normalize_username("   "), "");
+    }
+
+    #[tokio::test]
+    async fn test_usernames_match_regardless_of_case() {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+        let local = LocalAuthProvider::new();
+        let password = "correct horse battery";
+
+        let user = local
+            .create_user(
+                &pool,
+                &CreateLocalUser {
+                    username: " Jane ".to_string(),
+                    password: password.to_string(),
+                    email: None,
+                    display_name: None,
+                    is_admin: None,
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(user.username, "Jane");
+
+        let found = local.authenticate(&pool, "JANE", password).await.unwrap();
+        assert_eq!(found.id, user.id);
+        assert!(matches!(
+            local
+                .create_user(
+                    &pool,
+                    &CreateLocalUser {
+                        username: "jane".to_string(),
+                        password: password.to_string(),
+                        email: None,
+                        display_name: None,
+                        is_admin: None,
+                    },
+                )
+                .await,
+            Err(AuthProviderError::UsernameTaken)
+        ));
+    }
+}
@@ -0,0 +1,320 @@
+//! Login providers that sit in front of the shared `User`/`UserSession` model.
+//!
+//! GitHub (device flow and the MCP OAuth bridge) remains the default. Local
+//! accounts and a generic OIDC provider let air-gapped installs log in
+//! without reaching github.com; all providers end in the same JWT session.
+
+pub mod local;
+pub mod oidc;
+
+pub use local::LocalAuthProvider;
+pub use oidc::{OidcAuthProvider, OidcConfig};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use thiserror::Error;
+use ts_rs::TS;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{
+    app_state::AppState,
+    auth::{generate_jwt_token, hash_token},
+    models::{
+        user::User,
+        user_session::{CreateUserSession, SessionType, UserSession},
+    },
+};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, TS, ToSchema)]
+#[serde(rename_all = "lowercase")]
+#[ts(export)]
+pub enum AuthProviderKind {
+    Github,
+    Local,
+    Oidc,
+}
+
+/// How the frontend should drive a provider's login
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, TS, ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum LoginFlow {
+    /// GitHub device code flow
+    DeviceCode,
+    /// Username and password form
+    Password,
+    /// Browser redirect to an external identity provider
+    Redirect,
+}
+
+#[derive(Debug, Clone, Serialize, TS, ToSchema)]
+#[ts(export)]
+pub struct AuthProviderInfo {
+    pub kind: AuthProviderKind,
+    pub display_name: String,
+    pub login_flow: LoginFlow,
+}
+
+/// Common interface of the configured login providers
+pub trait AuthProvider: Send + Sync {
+    fn info(&self) -> AuthProviderInfo;
+}
+
+/// The existing GitHub login, exposed through the provider list
+#[derive(Debug, Clone, Default)]
+pub struct GithubAuthProvider;
+
+impl AuthProvider for GithubAuthProvider {
+    fn info(&self) -> AuthProviderInfo {
+        AuthProviderInfo {
+            kind: AuthProviderKind::Github,
+            display_name: "GitHub".to_string(),
+            login_flow: LoginFlow::DeviceCode,
+        }
+    }
+}
+
+/// Which login providers are enabled, stored in `AppConfig` as `[auth]`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct AuthProvidersConfig {
+    pub github_enabled: bool,
+    pub local_enabled: bool,
+    pub oidc: Option<OidcConfig>,
+}
+
+impl Default for AuthProvidersConfig {
+    fn default() -> Self {
+        Self {
+            github_enabled: true,
+            local_enabled: false,
+            oidc: None,
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum AuthProviderError {
+    #[error("Login provider is not enabled")]
+    ProviderDisabled,
+    #[error("Invalid username or password")]
+    InvalidCredentials,
+    #[error("User not authorized to access this application")]
+    UserNotAuthorized,
+    #[error("Username is already taken")]
+    UsernameTaken,
+    #[error("Password must be at least {0} characters long")]
+    WeakPassword(usize),
+    #[error("Invalid or expired login state")]
+    InvalidState,
+    #[error("Identity provider error: {0}")]
+    Upstream(String),
+    #[error("Password hashing failed: {0}")]
+    Hashing(String),
+    #[error("Failed to issue session: {0}")]
+    Session(String),
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+/// A user as reported by an external identity provider
+#[derive(Debug, Clone)]
+pub struct ExternalIdentity {
+    pub subject: String,
+    pub username: String,
+    pub email: Option<String>,
+    pub display_name: Option<String>,
+    pub avatar_url: Option<String>,
+}
+
+/// `users.github_id` is NOT NULL and UNIQUE, so users without a GitHub account
+/// get a negative id derived from their user id. Real GitHub ids are positive.
+pub fn placeholder_github_id(user_id: Uuid) -> i64 {
+    let bytes = user_id.as_bytes();
+    let mut high = [0u8; 8];
+    high.copy_from_slice(&bytes[..8]);
+    -((i64::from_be_bytes(high) & i64::MAX).max(1))
+}
+
+/// Create a web session for a user that has just logged in, the same way the
+/// GitHub device flow does, and return the bearer token with the session
+pub async fn issue_web_session(
+    app_state: &AppState,
+    user: &User,
+    client_info: &str,
+) -> Result<(String, UserSession), AuthProviderError> {
+    // Make room under the per-type session cap before adding another session
+    app_state
+        .session_security()
+        .enforce_concurrent_session_limits(user.id, SessionType::Web)
+        .await
+        .map_err(|e| AuthProviderError::Session(e.to_string()))?;
+
+    let session_id = Uuid::new_v4();
+    let jwt_token = generate_jwt_token(
+        user.id,
+        session_id,
+        SessionType::Web,
+        app_state.get_jwt_config(),
+    )
+    .map_err(|e| AuthProviderError::Session(e.to_string()))?;
+
+    let expires_at =
+        chrono::Utc::now() + chrono::Duration::hours(UserSession::WEB_SESSION_DURATION_HOURS);
+    let session_data = CreateUserSession {
+        user_id: user.id,
+        token_hash: hash_token(&jwt_token),
+        session_type: SessionType::Web,
+        client_info: Some(client_info.to_string()),
+        expires_at,
+    };
+    let session = UserSession::create(&app_state.db_pool, &session_data, session_id).await?;
+
+    // Suspicious activity is looked for on login and token rotation
+    match app_state
+        .session_security()
+        .screen_session(&session, None, None)
+        .await
+    {
+        Ok(true) => {
+            return Err(AuthProviderError::Session(
+                "the session was revoked after a security alert".to_string(),
+            ))
+        }
+        Ok(false) => {}
+        Err(e) => tracing::warn!(
+            "Suspicious activity check failed for {}: {}",
+            user.username,
+            e
+        ),
+    }
+
+    if let Err(e) = User::update_last_login(&app_state.db_pool, user.id).await {
+        tracing::warn!("Failed to update last login for {}: {}", user.username, e);
+    }
+
+    Ok((jwt_token, session))
+}
+
+/// The login providers enabled for this install
+#[derive(Debug)]
+pub struct AuthProviders {
+    github: Option<GithubAuthProvider>,
+    local: Option<LocalAuthProvider>,
+    oidc: Option<OidcAuthProvider>,
+}
+
+impl AuthProviders {
+    pub fn from_config(config: &AuthProvidersConfig) -> Self {
+        Self {
+            github: config.github_enabled.then_some(GithubAuthProvider),
+            local: config.local_enabled.then(LocalAuthProvider::new),
+            oidc: config.oidc.clone().map(OidcAuthProvider::new),
+        }
+    }
+
+    /// Providers in the order the login dialog should offer them
+    pub fn list(&self) -> Vec<AuthProviderInfo> {
+        let mut providers: Vec<&dyn AuthProvider> = Vec::new();
+        if let Some(github) = &self.github {
+            providers.push(github);
+        }
+        if let Some(oidc) = &self.oidc {
+            providers.push(oidc);
+        }
+        if let Some(local) = &self.local {
+            providers.push(local);
+        }
+        providers.iter().map(|provider| provider.info()).collect()
+    }
+
+    pub fn github_enabled(&self) -> bool {
+        self.github.is_some()
+    }
+
+    pub fn local(&self) -> Result<&LocalAuthProvider, AuthProviderError> {
+        self.local
+            .as_ref()
+            .ok_or(AuthProviderError::ProviderDisabled)
+    }
+
+    pub fn oidc(&self) -> Result<&OidcAuthProvider, AuthProviderError> {
+        self.oidc
+            .as_ref()
+            .ok_or(AuthProviderError::ProviderDisabled)
+    }
+
+    /// Create the first local admin from `AUTH_LOCAL_ADMIN_USERNAME` and
+    /// `AUTH_LOCAL_ADMIN_PASSWORD`, so an install without GitHub has someone
+    /// who can create the remaining accounts. No-op once the account exists.
+    pub async fn bootstrap_local_admin(&self, pool: &SqlitePool) -> Result<(), AuthProviderError> {
+        let Some(local) = &self.local else {
+            return Ok(());
+        };
+        let env_value = |key| std::env::var(key).ok().filter(|v: &String| !v.is_empty());
+        let (Some(username), Some(password)) = (
+            env_value("AUTH_LOCAL_ADMIN_USERNAME"),
+            env_value("AUTH_LOCAL_ADMIN_PASSWORD"),
+        ) else {
+            return Ok(());
+        };
+
+        match local
+            .create_user(
+                pool,
+                &local::CreateLocalUser {
+                    username: username.clone(),
+                    password,
+                    email: None,
+                    display_name: None,
+                    is_admin: Some(true),
+                },
+            )
+            .await
+        {
+            Ok(_) => {
+                tracing::info!("Created local admin account '{}'", username);
+                Ok(())
+            }
+            Err(AuthProviderError::UsernameTaken) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_placeholder_github_id_is_negative_and_stable() {
+        let user_id = Uuid::new_v4();
+        let id = placeholder_github_id(user_id);
+        assert!(id < 0);
+        assert_eq!(id, placeholder_github_id(user_id));
+        assert!(placeholder_github_id(Uuid::nil()) < 0);
+    }
+
+    #[test]
+    fn test_default_config_keeps_github_only() {
+        let providers = AuthProviders::from_config(&AuthProvidersConfig::default());
+        let kinds: Vec<_> = providers.list().into_iter().map(|p| p.kind).collect();
+        assert_eq!(kinds, vec![AuthProviderKind::Github]);
+        assert!(providers.local().is_err());
+        assert!(providers.oidc().is_err());
+    }
+
+    #[test]
+    fn test_air_gapped_config_lists_local_only() {
+        let providers = AuthProviders::from_config(&AuthProvidersConfig {
+            github_enabled: false,
+            local_enabled: true,
+            oidc: None,
+        });
+        let list = providers.list();
+        assert_eq!(list.len(), 1);
+        assert_eq!(list[0].kind, AuthProviderKind::Local);
+        assert_eq!(list[0].login_flow, LoginFlow::Password);
+        assert!(!providers.github_enabled());
+    }
+}
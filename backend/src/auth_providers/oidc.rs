@@ -0,0 +1,683 @@
+use std::{collections::HashMap, sync::Mutex, time::Duration};
+
+use axum::http::{header::COOKIE, HeaderMap};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use jsonwebtoken::{decode, decode_header, jwk::JwkSet, Algorithm, DecodingKey, Validation};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
+use tokio::sync::OnceCell;
+use uuid::Uuid;
+
+use super::{
+    placeholder_github_id, AuthProvider, AuthProviderError, AuthProviderInfo, AuthProviderKind,
+    ExternalIdentity, LoginFlow,
+};
+use crate::models::{
+    auth_identity::{AuthIdentity, CreateAuthIdentity, IdentityProvider},
+    user::{CreateUser, User},
+};
+
+/// How long a started login may take before its state is discarded
+const PENDING_LOGIN_TTL_MINUTES: i64 = 10;
+
+/// Cookie holding the nonce that ties a started login to the browser that
+/// started it, so a login link can't be completed in someone else's browser
+pub const LOGIN_COOKIE: &str = "forge_oidc_login";
+
+/// A fresh nonce to bind a login to the browser with
+pub fn new_browser_binding() -> String {
+    Uuid::new_v4().simple().to_string()
+}
+
+/// `Set-Cookie` value handing `binding` to the browser for the callback
+/// under `path`
+pub fn login_cookie(path: &str, binding: &str) -> String {
+    let secure = std::env::var("BASE_URL").is_ok_and(|url| url.starts_with("https://"));
+    format!(
+        "{}={}; Path={}; Max-Age={}; HttpOnly; SameSite=Lax{}",
+        LOGIN_COOKIE,
+        binding,
+        path,
+        PENDING_LOGIN_TTL_MINUTES * 60,
+        if secure { "; Secure" } else { "" }
+    )
+}
+
+/// `Set-Cookie` value removing the login cookie once the callback ran
+pub fn cleared_login_cookie(path: &str) -> String {
+    format!(
+        "{}=; Path={}; Max-Age=0; HttpOnly; SameSite=Lax",
+        LOGIN_COOKIE, path
+    )
+}
+
+/// The browser binding the request carries, if any
+pub fn browser_binding(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get_all(COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|cookie| cookie.trim().split_once('='))
+        .find(|(name, _)| *name == LOGIN_COOKIE)
+        .map(|(_, value)| value.to_string())
+        .filter(|value| !value.is_empty())
+}
+
+fn hash_binding(binding: &str) -> Vec<u8> {
+    Sha256::digest(binding.as_bytes()).to_vec()
+}
+
+fn default_scopes() -> Vec<String> {
+    vec![
+        "openid".to_string(),
+        "email".to_string(),
+        "profile".to_string(),
+    ]
+}
+
+fn default_display_name() -> String {
+    "Single sign-on".to_string()
+}
+
+fn default_username_claim() -> String {
+    "preferred_username".to_string()
+}
+
+/// Generic OpenID Connect provider settings (`[auth.oidc]` in config.toml).
+///
+/// Register both `{BASE_URL}/auth/oidc/callback` (web login, unless
+/// `redirect_url` overrides it) and `{BASE_URL}/oauth/oidc/callback` (MCP
+/// clients) as redirect URIs with the identity provider.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OidcConfig {
+    pub issuer_url: String,
+    pub client_id: String,
+    #[serde(default)]
+    pub client_secret: Option<String>,
+    #[serde(default = "default_scopes")]
+    pub scopes: Vec<String>,
+    #[serde(default = "default_display_name")]
+    pub display_name: String,
+    /// Create users on first login instead of requiring an existing identity
+    #[serde(default)]
+    pub auto_provision: bool,
+    /// Claim used as the Forge username
+    #[serde(default = "default_username_claim")]
+    pub username_claim: String,
+    #[serde(default)]
+    pub redirect_url: Option<String>,
+}
+
+impl OidcConfig {
+    pub fn new(issuer_url: String, client_id: String) -> Self {
+        Self {
+            issuer_url,
+            client_id,
+            client_secret: None,
+            scopes: default_scopes(),
+            display_name: default_display_name(),
+            auto_provision: false,
+            username_claim: default_username_claim(),
+            redirect_url: None,
+        }
+    }
+
+    /// Redirect URI used by the web login dialog
+    pub fn web_redirect_url(&self) -> String {
+        self.redirect_url.clone().unwrap_or_else(|| {
+            let base_url =
+                std::env::var("BASE_URL").unwrap_or_else(|_| "http://localhost:3001".to_string());
+            format!("{}/auth/oidc/callback", base_url)
+        })
+    }
+}
+
+/// Subset of `/.well-known/openid-configuration` used by the login flow
+#[derive(Debug, Clone, Deserialize)]
+struct OidcDiscovery {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    jwks_uri: String,
+    userinfo_endpoint: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OidcTokenResponse {
+    id_token: Option<String>,
+    access_token: Option<String>,
+}
+
+#[derive(Debug)]
+struct PendingLogin {
+    code_verifier: String,
+    nonce: String,
+    redirect_uri: String,
+    /// SHA-256 of the nonce in the starting browser's login cookie
+    browser_binding_hash: Vec<u8>,
+    created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug)]
+pub struct OidcAuthProvider {
+    config: OidcConfig,
+    client: reqwest::Client,
+    discovery: OnceCell<OidcDiscovery>,
+    pending: Mutex<HashMap<String, PendingLogin>>,
+}
+
+impl AuthProvider for OidcAuthProvider {
+    fn info(&self) -> AuthProviderInfo {
+        AuthProviderInfo {
+            kind: AuthProviderKind::Oidc,
+            display_name: self.config.display_name.clone(),
+            login_flow: LoginFlow::Redirect,
+        }
+    }
+}
+
+impl OidcAuthProvider {
+    pub fn new(config: OidcConfig) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(15))
+            .build()
+            .unwrap_or_default();
+        Self {
+            config,
+            client,
+            discovery: OnceCell::new(),
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn config(&self) -> &OidcConfig {
+        &self.config
+    }
+
+    async fn discovery(&self) -> Result<&OidcDiscovery, AuthProviderError> {
+        self.discovery
+            .get_or_try_init(|| async {
+                let url = format!(
+                    "{}/.well-known/openid-configuration",
+                    self.config.issuer_url.trim_end_matches('/')
+                );
+                let discovery: OidcDiscovery = self
+                    .client
+                    .get(&url)
+                    .send()
+                    .await
+                    .and_then(|res| res.error_for_status())
+                    .map_err(|e| AuthProviderError::Upstream(format!("discovery failed: {e}")))?
+                    .json()
+                    .await
+                    .map_err(|e| {
+                        AuthProviderError::Upstream(format!("invalid discovery document: {e}"))
+                    })?;
+                Ok(discovery)
+            })
+            .await
+    }
+
+    /// Start a login and return the identity provider URL to send the browser to.
+    /// `state` must be unguessable; it is the key handed back to `complete_login`.
+    /// `browser_binding` goes to the browser in the login cookie and has to
+    /// come back with the state.
+    pub async fn begin_login(
+        &self,
+        state: &str,
+        redirect_uri: &str,
+        browser_binding: &str,
+    ) -> Result<String, AuthProviderError> {
+        let discovery = self.discovery().await?;
+
+        let code_verifier = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+        let code_challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()));
+        let nonce = Uuid::new_v4().simple().to_string();
+
+        {
+            let mut pending = self.pending.lock().unwrap();
+            let cutoff = chrono::Utc::now() - chrono::Duration::minutes(PENDING_LOGIN_TTL_MINUTES);
+            pending.retain(|_, login| login.created_at > cutoff);
+            pending.insert(
+                state.to_string(),
+                PendingLogin {
+                    code_verifier,
+                    nonce: nonce.clone(),
+                    redirect_uri: redirect_uri.to_string(),
+                    browser_binding_hash: hash_binding(browser_binding),
+                    created_at: chrono::Utc::now(),
+                },
+            );
+        }
+
+        let separator = if discovery.authorization_endpoint.contains('?') {
+            "&"
+        } else {
+            "?"
+        };
+        Ok(format!(
+            "{}{}response_type=code&client_id={}&redirect_uri={}&scope={}&state={}&nonce={}&code_challenge={}&code_challenge_method=S256",
+            discovery.authorization_endpoint,
+            separator,
+            urlencoding::encode(&self.config.client_id),
+            urlencoding::encode(redirect_uri),
+            urlencoding::encode(&self.config.scopes.join(" ")),
+            urlencoding::encode(state),
+            urlencoding::encode(&nonce),
+            urlencoding::encode(&code_challenge),
+        ))
+    }
+
+    /// Exchange the authorization code for a verified ID token. The login has
+    /// to be completed by the browser that started it.
+    pub async fn complete_login(
+        &self,
+        state: &str,
+        code: &str,
+        browser_binding: Option<&str>,
+    ) -> Result<ExternalIdentity, AuthProviderError> {
+        let pending = self.take_pending(state, browser_binding)?;
+
+        let discovery = self.discovery().await?;
+
+        let mut params = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", pending.redirect_uri.as_str()),
+            ("client_id", self.config.client_id.as_str()),
+            ("code_verifier", pending.code_verifier.as_str()),
+        ];
+        if let Some(secret) = &self.config.client_secret {
+            params.push(("client_secret", secret.as_str()));
+        }
+
+        let tokens: OidcTokenResponse = self
+            .client
+            .post(&discovery.token_endpoint)
+            .header("Accept", "application/json")
+            .form(&params)
+            .send()
+            .await
+            .and_then(|res| res.error_for_status())
+            .map_err(|e| AuthProviderError::Upstream(format!("token exchange failed: {e}")))?
+            .json()
+            .await
+            .map_err(|e| AuthProviderError::Upstream(format!("invalid token response: {e}")))?;
+
+        let id_token = tokens.id_token.ok_or_else(|| {
+            AuthProviderError::Upstream("no id_token in token response".to_string())
+        })?;
+        let mut claims = self.verify_id_token(discovery, &id_token).await?;
+
+        if claims.get("nonce").and_then(|v| v.as_str()) != Some(pending.nonce.as_str()) {
+            return Err(AuthProviderError::Upstream(
+                "id_token nonce mismatch".to_string(),
+            ));
+        }
+
+        // Some providers only put profile claims in the userinfo response
+        if let (Some(endpoint), Some(access_token)) =
+            (&discovery.userinfo_endpoint, &tokens.access_token)
+        {
+            match self.fetch_userinfo(endpoint, access_token).await {
+                Ok(serde_json::Value::Object(userinfo))
+                    if userinfo.get("sub") == claims.get("sub") =>
+                {
+                    for (key, value) in userinfo {
+                        claims.entry(key).or_insert(value);
+                    }
+                }
+                Ok(_) => tracing::warn!("Ignoring OIDC userinfo with a mismatched subject"),
+                Err(e) => tracing::warn!("Failed to fetch OIDC userinfo: {}", e),
+            }
+        }
+
+        identity_from_claims(&claims, &self.config.username_claim)
+    }
+
+    /// Remove and return the login started under `state`, provided it hasn't
+    /// expired and `browser_binding` is the one it was started with
+    fn take_pending(
+        &self,
+        state: &str,
+        browser_binding: Option<&str>,
+    ) -> Result<PendingLogin, AuthProviderError> {
+        let pending = self
+            .pending
+            .lock()
+            .unwrap()
+            .remove(state)
+            .ok_or(AuthProviderError::InvalidState)?;
+        if pending.created_at
+            < chrono::Utc::now() - chrono::Duration::minutes(PENDING_LOGIN_TTL_MINUTES)
+        {
+            return Err(AuthProviderError::InvalidState);
+        }
+        let Some(browser_binding) = browser_binding else {
+            return Err(AuthProviderError::InvalidState);
+        };
+        let matches = hash_binding(browser_binding)
+            .iter()
+            .zip(pending.browser_binding_hash.iter())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0;
+        if !matches {
+            return Err(AuthProviderError::InvalidState);
+        }
+        Ok(pending)
+    }
+
+    async fn verify_id_token(
+        &self,
+        discovery: &OidcDiscovery,
+        id_token: &str,
+    ) -> Result<serde_json::Map<String, serde_json::Value>, AuthProviderError> {
+        let header = decode_header(id_token)
+            .map_err(|e| AuthProviderError::Upstream(format!("invalid id_token: {e}")))?;
+        if matches!(
+            header.alg,
+            Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512
+        ) {
+            return Err(AuthProviderError::Upstream(
+                "symmetric id_token signatures are not supported".to_string(),
+            ));
+        }
+
+        let jwks: JwkSet = self
+            .client
+            .get(&discovery.jwks_uri)
+            .send()
+            .await
+            .and_then(|res| res.error_for_status())
+            .map_err(|e| AuthProviderError::Upstream(format!("failed to fetch JWKS: {e}")))?
+            .json()
+            .await
+            .map_err(|e| AuthProviderError::Upstream(format!("invalid JWKS: {e}")))?;
+
+        let jwk = match &header.kid {
+            Some(kid) => jwks.find(kid),
+            None => jwks.keys.first(),
+        }
+        .ok_or_else(|| AuthProviderError::Upstream("no matching signing key".to_string()))?;
+        let key = DecodingKey::from_jwk(jwk)
+            .map_err(|e| AuthProviderError::Upstream(format!("unusable signing key: {e}")))?;
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_audience(&[&self.config.client_id]);
+        validation.set_issuer(&[&discovery.issuer]);
+
+        decode::<serde_json::Map<String, serde_json::Value>>(id_token, &key, &validation)
+            .map(|data| data.claims)
+            .map_err(|e| AuthProviderError::Upstream(format!("id_token rejected: {e}")))
+    }
+
+    async fn fetch_userinfo(
+        &self,
+        endpoint: &str,
+        access_token: &str,
+    ) -> Result<serde_json::Value, reqwest::Error> {
+        self.client
+            .get(endpoint)
+            .bearer_auth(access_token)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+    }
+
+    /// Map an external identity onto a Forge user, provisioning one if allowed
+    pub async fn resolve_user(
+        &self,
+        pool: &SqlitePool,
+        identity: &ExternalIdentity,
+    ) -> Result<User, AuthProviderError> {
+        let existing =
+            AuthIdentity::find_by_provider_subject(pool, IdentityProvider::Oidc, &identity.subject)
+                .await?;
+
+        let user = match existing {
+            Some(existing) => User::find_by_id(pool, existing.user_id)
+                .await?
+                .ok_or(AuthProviderError::UserNotAuthorized)?,
+            None if self.config.auto_provision => self.provision_user(pool, identity).await?,
+            None => {
+                tracing::warn!(
+                    "OIDC subject {} ({}) has no linked account",
+                    identity.subject,
+                    identity.username
+                );
+                return Err(AuthProviderError::UserNotAuthorized);
+            }
+        };
+
+        if !user.is_whitelisted {
+            return Err(AuthProviderError::UserNotAuthorized);
+        }
+        Ok(user)
+    }
+
+    async fn provision_user(
+        &self,
+        pool: &SqlitePool,
+        identity: &ExternalIdentity,
+    ) -> Result<User, AuthProviderError> {
+        let user_id = Uuid::new_v4();
+        let username = available_username(pool, &identity.username).await?;
+        let user = User::create(
+            pool,
+            &CreateUser {
+                github_id: placeholder_github_id(user_id),
+                username,
+                email: identity.email.clone().unwrap_or_default(),
+                display_name: identity.display_name.clone(),
+                avatar_url: identity.avatar_url.clone(),
+                github_token: None,
+                is_admin: Some(false),
+            },
+            user_id,
+        )
+        .await?;
+
+        let link = CreateAuthIdentity {
+            user_id: user.id,
+            provider: IdentityProvider::Oidc,
+            subject: identity.subject.clone(),
+            password_hash: None,
+        };
+        if let Err(e) = AuthIdentity::create(pool, &link, Uuid::new_v4()).await {
+            if let Err(cleanup) = User::delete(pool, user.id).await {
+                tracing::error!(
+                    "Failed to remove user {} after identity error: {}",
+                    user.id,
+                    cleanup
+                );
+            }
+            return Err(e.into());
+        }
+
+        tracing::info!(
+            "Provisioned user {} from OIDC subject {}",
+            user.username,
+            identity.subject
+        );
+        Ok(user)
+    }
+}
+
+/// `username`, or the first of `username-2`, `username-3`, ... nobody has
+/// taken, since local logins look users up by username
+async fn available_username(pool: &SqlitePool, username: &str) -> Result<String, sqlx::Error> {
+    let mut candidate = username.to_string();
+    let mut suffix = 2;
+    while User::find_by_username(pool, &candidate).await?.is_some() {
+        candidate = format!("{}-{}", username, suffix);
+        suffix += 1;
+    }
+    Ok(candidate)
+}
+
+fn identity_from_claims(
+    claims: &serde_json::Map<String, serde_json::Value>,
+    username_claim: &str,
+) -> Result<ExternalIdentity, AuthProviderError> {
+    let claim = |name: &str| {
+        claims
+            .get(name)
+            .and_then(|v| v.as_str())
+            .map(str::to_string)
+    };
+
+    let subject = claim("sub")
+        .filter(|sub| !sub.is_empty())
+        .ok_or_else(|| AuthProviderError::Upstream("id_token has no subject".to_string()))?;
+    // Unverified addresses are dropped rather than trusted
+    let email = claim("email").filter(|_| {
+        claims
+            .get("email_verified")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+    });
+    let username = claim(username_claim)
+        .or_else(|| {
+            email
+                .as_deref()
+                .and_then(|e| e.split('@').next())
+                .map(str::to_string)
+        })
+        .unwrap_or_else(|| subject.clone());
+
+    Ok(ExternalIdentity {
+        subject,
+        username,
+        email,
+        display_name: claim("name"),
+        avatar_url: claim("picture"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn claims(value: serde_json::Value) -> serde_json::Map<String, serde_json::Value> {
+        value.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn test_identity_uses_configured_username_claim() {
+        let identity = identity_from_claims(
+            &claims(serde_json::json!({
+                "sub": "abc-123",
+                "preferred_username": "jdoe",
+                "email": "jdoe@example.com",
+                "email_verified": true,
+                "name": "J. Doe"
+            })),
+            "preferred_username",
+        )
+        .unwrap();
+        assert_eq!(identity.subject, "abc-123");
+        assert_eq!(identity.username, "jdoe");
+        assert_eq!(identity.email.as_deref(), Some("jdoe@example.com"));
+        assert_eq!(identity.display_name.as_deref(), Some("J. Doe"));
+    }
+
+    #[test]
+    fn test_identity_drops_unverified_email() {
+        let identity = identity_from_claims(
+            &claims(serde_json::json!({
+                "sub": "abc-123",
+                "email": "someone@example.com",
+            })),
+            "preferred_username",
+        )
+        .unwrap();
+        assert!(identity.email.is_none());
+        assert_eq!(identity.username, "abc-123");
+    }
+
+    #[test]
+    fn test_identity_requires_subject() {
+        assert!(
+            identity_from_claims(&claims(serde_json::json!({"email": "x@y"})), "email").is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_complete_login_rejects_unknown_state() {
+        let provider = OidcAuthProvider::new(OidcConfig::new(
+            "https://idp.invalid".to_string(),
+            "forge".to_string(),
+        ));
+        assert!(matches!(
+            provider
+                .complete_login("never-issued", "code", Some("binding"))
+                .await,
+            Err(AuthProviderError::InvalidState)
+        ));
+    }
+
+    fn start_pending(provider: &OidcAuthProvider, state: &str, binding: &str) {
+        provider.pending.lock().unwrap().insert(
+            state.to_string(),
+            PendingLogin {
+                code_verifier: "verifier".to_string(),
+                nonce: "nonce".to_string(),
+                redirect_uri: "http://localhost:3001/auth/oidc/callback".to_string(),
+                browser_binding_hash: hash_binding(binding),
+                created_at: chrono::Utc::now(),
+            },
+        );
+    }
+
+    #[test]
+    fn test_login_must_finish_in_the_starting_browser() {
+        let provider = OidcAuthProvider::new(OidcConfig::new(
+            "https://idp.invalid".to_string(),
+            "forge".to_string(),
+        ));
+
+        start_pending(&provider, "state-1", "mine");
+        assert!(matches!(
+            provider.take_pending("state-1", Some("theirs")),
+            Err(AuthProviderError::InvalidState)
+        ));
+        // A rejected attempt uses the state up
+        assert!(matches!(
+            provider.take_pending("state-1", Some("mine")),
+            Err(AuthProviderError::InvalidState)
+        ));
+
+        start_pending(&provider, "state-2", "mine");
+        assert!(matches!(
+            provider.take_pending("state-2", None),
+            Err(AuthProviderError::InvalidState)
+        ));
+
+        start_pending(&provider, "state-3", "mine");
+        let pending = provider.take_pending("state-3", Some("mine")).unwrap();
+        assert_eq!(pending.code_verifier, "verifier");
+    }
+
+    #[test]
+    fn test_login_cookie() {
+        let cookie = login_cookie("/api/auth/oidc", "abc");
+        assert!(cookie.starts_with("forge_oidc_login=abc; Path=/api/auth/oidc; Max-Age=600;"));
+        assert!(cookie.contains("HttpOnly"));
+        assert!(cookie.contains("SameSite=Lax"));
+        assert!(cleared_login_cookie("/oauth").contains("Max-Age=0"));
+
+        let mut headers = HeaderMap::new();
+        assert_eq!(browser_binding(&headers), None);
+        headers.insert(
+            COOKIE,
+            "theme=dark; forge_oidc_login=abc; other=1".parse().unwrap(),
+        );
+        assert_eq!(browser_binding(&headers).as_deref(), Some("abc"));
+        headers.insert(COOKIE, "forge_oidc_login=".parse().unwrap());
+        assert_eq!(browser_binding(&headers), None);
+    }
+}
@@ -100,6 +100,8 @@ fn generate_types_content() -> String {
         automagik_forge::models::project::SearchMatchType::decl(),
         automagik_forge::models::project::GitBranch::decl(),
         automagik_forge::models::project::CreateBranch::decl(),
+        automagik_forge::models::project::McpInjectionSettings::decl(),
+        automagik_forge::models::project::ReviewAutomationSettings::decl(),
         automagik_forge::models::task::CreateTask::decl(),
         automagik_forge::models::task::CreateTaskAndStart::decl(),
         automagik_forge::models::task::TaskStatus::decl(),
@@ -107,6 +109,19 @@ fn generate_types_content() -> String {
         automagik_forge::models::task::TaskWithAttemptStatus::decl(),
         automagik_forge::models::task::TaskWithUsers::decl(),
         automagik_forge::models::task::UpdateTask::decl(),
+        automagik_forge::models::task_dependency::TaskDependency::decl(),
+        automagik_forge::services::task_plan::TaskPlanFormat::decl(),
+        automagik_forge::services::task_plan::PlannedTask::decl(),
+        automagik_forge::routes::tasks::CreateTasksFromPlan::decl(),
+        automagik_forge::routes::tasks::TaskPlanResult::decl(),
+        automagik_forge::services::github_issues::ImportGitHubIssues::decl(),
+        automagik_forge::services::github_issues::GitHubIssueImport::decl(),
+        automagik_forge::services::github_issues::LinkedGitHubIssue::decl(),
+        automagik_forge::services::forge::ForgeKind::decl(),
+        automagik_forge::services::forge::ForgeReviewComment::decl(),
+        automagik_forge::services::forge::ForgeTokenSummary::decl(),
+        automagik_forge::services::forge::SaveForgeToken::decl(),
+        automagik_forge::services::pr_description::GeneratedPrDescription::decl(),
         automagik_forge::models::task_template::TaskTemplate::decl(),
         automagik_forge::models::task_template::CreateTaskTemplate::decl(),
         automagik_forge::models::task_template::UpdateTaskTemplate::decl(),
@@ -120,6 +135,45 @@ fn generate_types_content() -> String {
         automagik_forge::routes::auth::DeviceStartResponse::decl(),
         automagik_forge::routes::auth::AuthResponse::decl(),
         automagik_forge::routes::auth::UserInfoResponse::decl(),
+        automagik_forge::auth_providers::AuthProviderKind::decl(),
+        automagik_forge::auth_providers::LoginFlow::decl(),
+        automagik_forge::auth_providers::AuthProviderInfo::decl(),
+        automagik_forge::auth_providers::local::CreateLocalUser::decl(),
+        automagik_forge::routes::auth_providers::LocalLoginRequest::decl(),
+        automagik_forge::routes::auth_providers::ChangePasswordRequest::decl(),
+        automagik_forge::routes::auth_providers::OidcStartResponse::decl(),
+        automagik_forge::routes::auth_providers::OidcCallbackRequest::decl(),
+        automagik_forge::routes::secrets::SetSecretRequest::decl(),
+        automagik_forge::security::secret_store::SecretSummary::decl(),
+        automagik_forge::routes::project_env::EnvVarScope::decl(),
+        automagik_forge::routes::project_env::ProjectEnvVars::decl(),
+        automagik_forge::routes::project_env::SetEnvVarRequest::decl(),
+        automagik_forge::models::mcp_server::McpTransport::decl(),
+        automagik_forge::models::mcp_server::McpServer::decl(),
+        automagik_forge::routes::project_mcp_servers::McpServerScope::decl(),
+        automagik_forge::routes::project_mcp_servers::McpServerDetails::decl(),
+        automagik_forge::routes::project_mcp_servers::ProjectMcpServers::decl(),
+        automagik_forge::routes::project_mcp_servers::SaveMcpServerRequest::decl(),
+        automagik_forge::models::webhook::WebhookEvent::decl(),
+        automagik_forge::models::webhook::Webhook::decl(),
+        automagik_forge::models::webhook::WebhookDeliveryStatus::decl(),
+        automagik_forge::models::webhook::WebhookDelivery::decl(),
+        automagik_forge::routes::project_webhooks::ProjectWebhooks::decl(),
+        automagik_forge::routes::project_webhooks::WebhookWithSecret::decl(),
+        automagik_forge::routes::project_webhooks::CreateWebhookRequest::decl(),
+        automagik_forge::routes::project_webhooks::UpdateWebhookRequest::decl(),
+        automagik_forge::models::user_notification_channel::NotificationChannelKind::decl(),
+        automagik_forge::models::user_notification_channel::UserNotificationChannel::decl(),
+        automagik_forge::routes::notification_channels::NotificationChannelDetails::decl(),
+        automagik_forge::routes::notification_channels::SaveNotificationChannelRequest::decl(),
+        automagik_forge::routes::notification_channels::ConfirmWhatsAppNumberRequest::decl(),
+        automagik_forge::models::whatsapp_message::WhatsAppMessageStatus::decl(),
+        automagik_forge::models::whatsapp_message::WhatsAppMessage::decl(),
+        automagik_forge::models::user_notification_rule::NotificationEvent::decl(),
+        automagik_forge::models::user_notification_rule::NotificationScope::decl(),
+        automagik_forge::models::user_notification_rule::UserNotificationRule::decl(),
+        automagik_forge::routes::notification_rules::NotificationRules::decl(),
+        automagik_forge::routes::notification_rules::SaveNotificationRuleRequest::decl(),
         automagik_forge::routes::task_attempts::ProcessLogsResponse::decl(),
         automagik_forge::models::task_attempt::DiffChunkType::decl(),
         automagik_forge::models::task_attempt::DiffChunk::decl(),
@@ -128,6 +182,16 @@ fn generate_types_content() -> String {
         automagik_forge::models::task_attempt::BranchStatus::decl(),
         automagik_forge::models::task_attempt::ExecutionState::decl(),
         automagik_forge::models::task_attempt::TaskAttemptState::decl(),
+        automagik_forge::models::pull_request::PrReviewState::decl(),
+        automagik_forge::models::pull_request::PrReviewDecision::decl(),
+        automagik_forge::models::pull_request::PrChecksStatus::decl(),
+        automagik_forge::models::pull_request::PrReview::decl(),
+        automagik_forge::models::pull_request::PrReviewComment::decl(),
+        automagik_forge::models::pull_request::PrCheckRun::decl(),
+        automagik_forge::models::pull_request::PullRequestState::decl(),
+        automagik_forge::models::pull_request::PrCommentFollowupStatus::decl(),
+        automagik_forge::models::pull_request::AddressedThread::decl(),
+        automagik_forge::models::pull_request::PrCommentFollowup::decl(),
         automagik_forge::models::execution_process::ExecutionProcess::decl(),
         automagik_forge::models::execution_process::ExecutionProcessSummary::decl(),
         automagik_forge::models::execution_process::ExecutionProcessStatus::decl(),
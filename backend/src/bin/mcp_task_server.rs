@@ -1,18 +1,22 @@
 use std::{net::SocketAddr, str::FromStr, sync::Arc};
 
 use rmcp::{
-    transport::{stdio, sse_server::SseServer}, 
+    transport::{sse_server::{SseServer, SseServerConfig}, stdio},
     ServiceExt
 };
 use sqlx::{sqlite::SqliteConnectOptions, SqlitePool};
 use tokio_util::sync::CancellationToken;
 use tracing_subscriber::{prelude::*, EnvFilter};
+use uuid::Uuid;
 use automagik_forge::{
+    app_config::AppConfig,
+    auth::JwtConfig,
     mcp::{
-        task_server::{TaskServer, AuthenticatedTaskServer},
-        oauth_middleware::oauth_sse_authentication_middleware,
-    }, 
-    sentry_layer, 
+        task_server::TaskServer,
+        oauth_middleware::{oauth_sse_authentication_middleware, McpAuthState},
+    },
+    sentry_layer,
+    services::execution_env::FORGE_ATTEMPT_ID_VAR,
     utils::asset_dir
 };
 
@@ -85,19 +89,33 @@ fn main() -> anyhow::Result<()> {
             let options = SqliteConnectOptions::from_str(&database_url)?.create_if_missing(false);
             let pool = SqlitePool::connect_with(options).await?;
 
-            // Use different servers for different transport modes
-            let stdio_task_server = TaskServer::new(pool.clone());
-            let stdio_service = Arc::new(stdio_task_server);
-            
-            let auth_task_server = AuthenticatedTaskServer::new(pool.clone());
-            let auth_service = Arc::new(auth_task_server);
-            
+            // STDIO has no HTTP request to carry a bearer token, so its tool
+            // calls run as the owner of a configured MCP access token
+            let stdio_token = std::env::var("FORGE_MCP_TOKEN")
+                .ok()
+                .filter(|token| !token.is_empty())
+                .or_else(|| AppConfig::load().ok().and_then(|config| config.mcp_token));
+            if stdio_mode && stdio_token.is_none() {
+                tracing::warn!("No MCP access token configured: set FORGE_MCP_TOKEN or mcp_token in config.toml, tool calls will be rejected");
+            }
+
+            // Set by Forge for everything a coding agent spawns, including this
+            // server when the agent launches it
+            let current_attempt = std::env::var(FORGE_ATTEMPT_ID_VAR)
+                .ok()
+                .and_then(|id| Uuid::parse_str(&id).ok());
+
+            let task_server = TaskServer::new(pool.clone())
+                .with_stdio_token(stdio_token)
+                .with_current_attempt(current_attempt);
+            let service = Arc::new(task_server);
+
             let mut join_set = tokio::task::JoinSet::new();
             let shutdown_token = CancellationToken::new();
             
-            // Start STDIO transport if requested (uses basic TaskServer)
+            // Start STDIO transport if requested
             if stdio_mode {
-                let service_clone = stdio_service.clone();
+                let service_clone = service.clone();
                 let token = shutdown_token.clone();
                 join_set.spawn(async move {
                     tokio::select! {
@@ -113,9 +131,9 @@ fn main() -> anyhow::Result<()> {
                 });
             }
             
-            // Start SSE transport if requested (uses AuthenticatedTaskServer with OAuth)
+            // Start SSE transport if requested (bearer tokens checked per request)
             if sse_mode {
-                let service_clone = auth_service.clone();
+                let service_clone = service.clone();
                 let token = shutdown_token.clone();
                 let sse_port = get_sse_port();
                 let pool_clone = pool.clone();
@@ -171,94 +189,67 @@ async fn run_stdio_server(service: Arc<TaskServer>) -> anyhow::Result<()> {
     Ok(())
 }
 
-async fn run_sse_server_authenticated(service: Arc<AuthenticatedTaskServer>, port: u16, pool: SqlitePool) -> anyhow::Result<()> {
+async fn run_sse_server_authenticated(service: Arc<TaskServer>, port: u16, pool: SqlitePool) -> anyhow::Result<()> {
     let bind_addr = SocketAddr::from(([0, 0, 0, 0], port));
-    
-    match SseServer::serve(bind_addr).await {
-        Ok(sse_server) => {
-            tracing::info!("MCP SSE server with OAuth authentication listening on http://{}/sse", bind_addr);
-            
-            let base_url = std::env::var("BASE_URL")
-                .unwrap_or_else(|_| "http://localhost:3001".to_string());
-            tracing::info!("OAuth 2.1 authentication endpoints:");
-            tracing::info!("  - Discovery: {}/.well-known/oauth-authorization-server", base_url);
-            tracing::info!("  - Authorize: {}/oauth/authorize", base_url);
-            tracing::info!("  - Token: {}/oauth/token", base_url);
-            
-            // Get token store for middleware
-            let token_store = service.get_token_store();
-            
-            // Create OAuth middleware closure
-            let _oauth_middleware = {
-                let token_store = token_store.clone();
-                let pool = pool.clone();
-                move |headers: axum::http::HeaderMap, req: axum::http::Request<axum::body::Body>, next: axum::middleware::Next| {
-                    let token_store = token_store.clone();
-                    let pool = pool.clone();
-                    async move {
-                        oauth_sse_authentication_middleware(token_store, pool, headers, req, next).await
-                    }
-                }
-            };
-            
-            // Apply OAuth middleware to service via task-local storage injection
-            let protected_service = move || {
-                // OAuth middleware injects Bearer token into REQUEST_CONTEXT task-local storage
-                // which is then accessible to MCP tools via get_user_context() methods
-                service.as_ref().clone()
-            };
-            
-            let cancellation_token = sse_server.with_service(protected_service);
-            tracing::info!("MCP SSE server started with OAuth 2.1 authentication ready");
-            cancellation_token.cancelled().await;
-            Ok(())
-        }
+    let (sse_server, router) = SseServer::new(SseServerConfig {
+        bind: bind_addr,
+        sse_path: "/sse".to_string(),
+        post_path: "/message".to_string(),
+        ct: CancellationToken::new(),
+        sse_keep_alive: None,
+    });
+
+    // Reject requests without a valid MCP access token before they reach the server.
+    // rmcp builds its router on a newer axum, so it is mounted as a plain service
+    let auth_state = McpAuthState {
+        db_pool: pool,
+        jwt_config: Arc::new(JwtConfig::from_config()),
+    };
+    let router = axum::Router::new()
+        .fallback_service(router)
+        .layer(axum::middleware::from_fn_with_state(
+            auth_state,
+            oauth_sse_authentication_middleware,
+        ));
+
+    let listener = match tokio::net::TcpListener::bind(bind_addr).await {
+        Ok(listener) => listener,
         Err(e) => {
             tracing::error!("Failed to start authenticated SSE server on port {}: {}", port, e);
             // Don't fail the entire application if SSE fails
             if std::env::var("MCP_SSE_REQUIRED").is_ok() {
-                Err(e.into())
-            } else {
-                tracing::warn!("SSE server disabled due to startup failure");
-                Ok(())
+                return Err(e.into());
             }
+            tracing::warn!("SSE server disabled due to startup failure");
+            return Ok(());
         }
-    }
-}
+    };
 
-// Legacy SSE server function for backward compatibility
-#[allow(dead_code)]
-async fn run_sse_server(service: Arc<TaskServer>, port: u16) -> anyhow::Result<()> {
-    let bind_addr = SocketAddr::from(([0, 0, 0, 0], port));
-    
-    match SseServer::serve(bind_addr).await {
-        Ok(sse_server) => {
-            tracing::info!("MCP SSE server (basic) listening on http://{}/sse", bind_addr);
-            
-            let base_url = std::env::var("BASE_URL")
-                .unwrap_or_else(|_| "http://localhost:3001".to_string());
-            tracing::info!("OAuth authentication endpoints available at:");
-            tracing::info!("  - Discovery: {}/.well-known/oauth-authorization-server", base_url);
-            tracing::info!("  - Authorize: {}/oauth/authorize", base_url);
-            tracing::info!("  - Token: {}/oauth/token", base_url);
-            
-            // Use the service directly for basic functionality
-            let cancellation_token = sse_server.with_service(move || service.as_ref().clone());
-            tracing::info!("MCP SSE server started - OAuth endpoints ready for external clients");
-            cancellation_token.cancelled().await;
-            Ok(())
-        }
-        Err(e) => {
-            tracing::error!("Failed to start SSE server on port {}: {}", port, e);
-            // Don't fail the entire application if SSE fails
-            if std::env::var("MCP_SSE_REQUIRED").is_ok() {
-                Err(e.into())
-            } else {
-                tracing::warn!("SSE server disabled due to startup failure");
-                Ok(())
-            }
+    tracing::info!("MCP SSE server with OAuth authentication listening on http://{}/sse", bind_addr);
+
+    let base_url = std::env::var("BASE_URL")
+        .unwrap_or_else(|_| "http://localhost:3001".to_string());
+    tracing::info!("OAuth 2.1 authentication endpoints:");
+    tracing::info!("  - Discovery: {}/.well-known/oauth-authorization-server", base_url);
+    tracing::info!("  - Authorize: {}/oauth/authorize", base_url);
+    tracing::info!("  - Token: {}/oauth/token", base_url);
+
+    // Each connection gets its own server; tools resolve the user from the
+    // bearer token of the request that carried the call
+    let cancellation_token = sse_server.with_service(move || service.as_ref().clone());
+    let shutdown = cancellation_token.clone();
+    tokio::spawn(async move {
+        if let Err(e) = axum::serve(listener, router)
+            .with_graceful_shutdown(async move { shutdown.cancelled().await })
+            .await
+        {
+            tracing::error!("MCP SSE server error: {}", e);
         }
-    }
+    });
+
+    tracing::info!("MCP SSE server started with OAuth 2.1 authentication ready");
+    cancellation_token.cancelled().await;
+    Ok(())
 }
 
 fn get_sse_port() -> u16 {
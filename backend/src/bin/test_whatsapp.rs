@@ -1,5 +1,5 @@
 use automagik_forge::services::whatsapp_config::WhatsAppConfig;
-use automagik_forge::services::whatsapp_notifier::WhatsAppNotifier;
+use automagik_forge::services::whatsapp_notifier::WhatsAppClient;
 use anyhow::Result;
 
 #[tokio::main]
@@ -22,23 +22,30 @@ async fn main() -> Result<()> {
             println!("   Instance: {}", config.instance);
             println!("   Timeout: {}ms", config.timeout_ms);
             
-            // Create notifier
-            let notifier = WhatsAppNotifier::new(config).await?;
+            let Some(recipient) = config.fixed_recipient.clone() else {
+                println!("❌ EVOLUTION_API_FIXED_RECIPIENT is not set");
+                println!("💡 Set it to the number that should receive the test message");
+                return Err(anyhow::anyhow!("EVOLUTION_API_FIXED_RECIPIENT not set"));
+            };
             
-            // Send test notification
-            println!("📱 Sending test WhatsApp notification...");
+            // Send test message over a fresh MCP session
+            let client = WhatsAppClient::new();
+            println!("📱 Sending test WhatsApp message to {}...", recipient);
             println!("   Message: 'Automagik-Forge Test: 🚀 WhatsApp integration is working!'");
             
-            match notifier.send_notification(
-                "Automagik-Forge Test", 
-                "🚀 WhatsApp integration is working! This is a test message from automagik-forge with rmcp 0.3.2"
-            ).await {
+            let result = client.send_text(
+                &config,
+                &recipient,
+                "*Automagik-Forge Test*\n\n🚀 WhatsApp integration is working! This is a test message from automagik-forge with rmcp 0.3.2"
+            ).await;
+            client.disconnect().await;
+            match result {
                 Ok(()) => {
                     println!("✅ Test completed successfully!");
                     println!("📱 Check your WhatsApp for the message!");
                 }
                 Err(e) => {
-                    println!("❌ Failed to send WhatsApp notification: {}", e);
+                    println!("❌ Failed to send WhatsApp message: {}", e);
                     return Err(e);
                 }
             }
@@ -49,7 +56,7 @@ async fn main() -> Result<()> {
             println!("   - EVOLUTION_API_BASE_URL");
             println!("   - EVOLUTION_API_API_KEY");
             println!("   - EVOLUTION_API_INSTANCE");
-            println!("   - EVOLUTION_API_FIXED_RECIPIENT (optional)");
+            println!("   - EVOLUTION_API_FIXED_RECIPIENT (recipient of the test message)");
             return Err(e);
         }
     }
@@ -7,8 +7,14 @@ use crate::{
         execution_process::{ExecutionProcess, ExecutionProcessStatus, ExecutionProcessType},
         task::{Task, TaskStatus},
         task_attempt::TaskAttempt,
+        user_notification_rule::NotificationEvent,
+        webhook::WebhookEvent,
+    },
+    security::secret_store::{CONFIG_SCOPE, EVOLUTION_API_KEY_SECRET},
+    services::{
+        notification_channels::Notification, notification_rules, review_followups, webhooks,
+        NotificationConfig, NotificationService, ProcessService,
     },
-    services::{NotificationConfig, NotificationService, ProcessService},
     utils::worktree_manager::WorktreeManager,
 };
 
@@ -290,6 +296,7 @@ pub async fn cleanup_task_worktrees(
                     e
                 );
             } else {
+                crate::services::mcp_injection::remove_attempt_config(pool, attempt_id).await;
                 cleaned_count += 1;
             }
         }
@@ -692,7 +699,16 @@ pub async fn execution_monitor(app_state: AppState) {
                                 if let Ok(Some(task)) =
                                     Task::find_by_id(&app_state.db_pool, task_attempt.task_id).await
                                 {
-                                    if let Err(e) = Task::update_status(
+                                    webhooks::emit_attempt_event(
+                                        &app_state.db_pool,
+                                        WebhookEvent::AttemptFailed,
+                                        task.project_id,
+                                        task.id,
+                                        task_attempt.id,
+                                        serde_json::json!({ "reason": "orphaned" }),
+                                    )
+                                    .await;
+                                    match Task::update_status(
                                         &app_state.db_pool,
                                         task.id,
                                         task.project_id,
@@ -700,7 +716,10 @@ pub async fn execution_monitor(app_state: AppState) {
                                     )
                                     .await
                                     {
-                                        tracing::error!("Failed to update task status to InReview for orphaned attempt: {}", e);
+                                        Ok(()) => {
+                                            webhooks::emit_task_status(&app_state.db_pool, task.id, task.project_id, TaskStatus::InReview).await
+                                        }
+                                        Err(e) => tracing::error!("Failed to update task status to InReview for orphaned attempt: {}", e),
                                     }
                                 }
                             }
@@ -732,6 +751,7 @@ pub async fn execution_monitor(app_state: AppState) {
                                     if let Err(e) = crate::models::task_attempt::TaskAttempt::mark_worktree_deleted(&app_state.db_pool, attempt_id).await {
                                         tracing::error!("Failed to mark worktree as deleted in database for attempt {}: {}", attempt_id, e);
                                     } else {
+                                        crate::services::mcp_injection::remove_attempt_config(&app_state.db_pool, attempt_id).await;
                                         tracing::info!("Successfully marked worktree as deleted for attempt {}", attempt_id);
                                     }
                                 }
@@ -812,7 +832,16 @@ async fn handle_setup_completion(
         {
             if let Ok(Some(task)) = Task::find_by_id(&app_state.db_pool, task_attempt.task_id).await
             {
-                if let Err(e) = Task::update_status(
+                webhooks::emit_attempt_event(
+                    &app_state.db_pool,
+                    WebhookEvent::AttemptFailed,
+                    task.project_id,
+                    task.id,
+                    task_attempt.id,
+                    serde_json::json!({ "reason": "setup_failed" }),
+                )
+                .await;
+                match Task::update_status(
                     &app_state.db_pool,
                     task.id,
                     task.project_id,
@@ -820,10 +849,19 @@ async fn handle_setup_completion(
                 )
                 .await
                 {
-                    tracing::error!(
+                    Ok(()) => {
+                        webhooks::emit_task_status(
+                            &app_state.db_pool,
+                            task.id,
+                            task.project_id,
+                            TaskStatus::InReview,
+                        )
+                        .await
+                    }
+                    Err(e) => tracing::error!(
                         "Failed to update task status to InReview after setup failure: {}",
                         e
-                    );
+                    ),
                 }
             }
         }
@@ -961,46 +999,79 @@ async fn finalize_task_completion(
     success: bool,
     exit_code: Option<i64>,
 ) {
-    // Send notifications if enabled
-    let sound_enabled = app_state.get_sound_alerts_enabled().await;
-    let desktop_enabled = app_state.get_desktop_notifications_enabled().await;
-    let whatsapp_enabled = app_state.get_whatsapp_notifications_enabled().await;
-
-    if sound_enabled || desktop_enabled || whatsapp_enabled {
-        let sound_file = app_state.get_sound_file().await;
-        let notification_config = NotificationConfig {
-            sound_enabled,
-            push_enabled: desktop_enabled,
-            whatsapp_enabled,
+    // Get task attempt for notification details
+    if let Ok(Some(task_attempt)) =
+        TaskAttempt::find_by_id(&app_state.db_pool, task_attempt_id).await
+    {
+        let title = format!("Task Complete: {}", task.title);
+        let message = if success {
+            format!(
+                "✅ '{}' completed successfully\nBranch: {}\nExecutor: {}",
+                task.title,
+                task_attempt.branch,
+                task_attempt.executor.as_deref().unwrap_or("default")
+            )
+        } else {
+            format!(
+                "❌ '{}' execution failed\nBranch: {}\nExecutor: {}",
+                task.title,
+                task_attempt.branch,
+                task_attempt.executor.as_deref().unwrap_or("default")
+            )
         };
 
-        let notification_service = NotificationService::new(notification_config).await;
-
-        // Get task attempt for notification details
-        if let Ok(Some(task_attempt)) =
-            TaskAttempt::find_by_id(&app_state.db_pool, task_attempt_id).await
-        {
-            let title = format!("Task Complete: {}", task.title);
-            let message = if success {
-                format!(
-                    "✅ '{}' completed successfully\nBranch: {}\nExecutor: {}",
-                    task.title,
-                    task_attempt.branch,
-                    task_attempt.executor.as_deref().unwrap_or("default")
-                )
+        // Send notifications if enabled
+        let sound_enabled = app_state.get_sound_alerts_enabled().await;
+        let desktop_enabled = app_state.get_desktop_notifications_enabled().await;
+        let whatsapp_enabled = app_state.get_whatsapp_notifications_enabled().await;
+
+        if sound_enabled || desktop_enabled || whatsapp_enabled {
+            let sound_file = app_state.get_sound_file().await;
+            let whatsapp_api_key = if whatsapp_enabled {
+                app_state
+                    .secret_store()
+                    .get(CONFIG_SCOPE, EVOLUTION_API_KEY_SECRET)
+                    .await
+                    .unwrap_or_else(|e| {
+                        tracing::warn!("Failed to read WhatsApp API key from secret store: {}", e);
+                        None
+                    })
             } else {
-                format!(
-                    "❌ '{}' execution failed\nBranch: {}\nExecutor: {}",
-                    task.title,
-                    task_attempt.branch,
-                    task_attempt.executor.as_deref().unwrap_or("default")
-                )
+                None
+            };
+            let notification_config = NotificationConfig {
+                sound_enabled,
+                push_enabled: desktop_enabled,
+                whatsapp_enabled,
+                whatsapp_api_key,
             };
 
+            let notification_service =
+                NotificationService::new(app_state.db_pool.clone(), notification_config).await;
             notification_service
                 .notify(&title, &message, &sound_file)
                 .await;
         }
+
+        // Each user involved in the task also hears about it, as their rules say
+        let event = if success {
+            NotificationEvent::AttemptCompleted
+        } else {
+            NotificationEvent::AttemptFailed
+        };
+        notification_rules::dispatch(
+            &app_state.db_pool,
+            app_state.secret_store(),
+            event,
+            task,
+            task_attempt.created_by,
+            &Notification {
+                title,
+                message,
+                url: Some(notification_rules::task_url(task.project_id, task.id)),
+            },
+        )
+        .await;
     }
 
     // Track analytics event
@@ -1017,8 +1088,29 @@ async fn finalize_task_completion(
         )
         .await;
 
+    let event = if success {
+        WebhookEvent::AttemptCompleted
+    } else {
+        WebhookEvent::AttemptFailed
+    };
+    webhooks::emit_attempt_event(
+        &app_state.db_pool,
+        event,
+        task.project_id,
+        task.id,
+        task_attempt_id,
+        serde_json::json!({ "exit_code": exit_code }),
+    )
+    .await;
+
+    // A follow-up addressing PR review comments pushes and replies on GitHub
+    let followup_state = app_state.clone();
+    tokio::spawn(async move {
+        review_followups::finish(&followup_state, task_attempt_id, success).await;
+    });
+
     // Update task status to InReview
-    if let Err(e) = Task::update_status(
+    match Task::update_status(
         &app_state.db_pool,
         task.id,
         task.project_id,
@@ -1026,10 +1118,19 @@ async fn finalize_task_completion(
     )
     .await
     {
-        tracing::error!(
+        Ok(()) => {
+            webhooks::emit_task_status(
+                &app_state.db_pool,
+                task.id,
+                task.project_id,
+                TaskStatus::InReview,
+            )
+            .await
+        }
+        Err(e) => tracing::error!(
             "Failed to update task status to InReview for completed attempt: {}",
             e
-        );
+        ),
     }
 }
 
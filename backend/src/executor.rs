@@ -11,6 +11,7 @@ use crate::executors::{
     AmpExecutor, CCRExecutor, CharmOpencodeExecutor, ClaudeExecutor, EchoExecutor, GeminiExecutor,
     OpencodeAiExecutor, SetupScriptExecutor, SstOpencodeExecutor,
 };
+use crate::services::ExecutionEnv;
 
 // Constants for database streaming - fast for near-real-time updates
 const STDOUT_UPDATE_THRESHOLD: usize = 1;
@@ -163,6 +164,7 @@ pub enum ExecutorError {
     GitError(String),
     InvalidSessionId(String),
     FollowUpNotSupported,
+    PromptNotSupported,
 }
 
 impl std::fmt::Display for ExecutorError {
@@ -205,6 +207,9 @@ impl std::fmt::Display for ExecutorError {
             ExecutorError::FollowUpNotSupported => {
                 write!(f, "This executor does not support follow-up sessions")
             }
+            ExecutorError::PromptNotSupported => {
+                write!(f, "This executor does not support one-off prompts")
+            }
         }
     }
 }
@@ -244,6 +249,9 @@ impl From<crate::models::task_attempt::TaskAttemptError> for ExecutorError {
             crate::models::task_attempt::TaskAttemptError::GitHubService(e) => {
                 ExecutorError::GitError(format!("GitHub service error: {}", e))
             }
+            crate::models::task_attempt::TaskAttemptError::Forge(e) => {
+                ExecutorError::GitError(format!("Forge error: {}", e))
+            }
         }
     }
 }
@@ -264,6 +272,7 @@ pub trait Executor: Send + Sync {
         pool: &sqlx::SqlitePool,
         task_id: Uuid,
         worktree_path: &str,
+        env: &ExecutionEnv,
     ) -> Result<command_group::AsyncGroupChild, ExecutorError>;
 
     /// Spawn a follow-up session for executors that support it
@@ -278,10 +287,27 @@ pub trait Executor: Send + Sync {
         _session_id: &str,
         _prompt: &str,
         _worktree_path: &str,
+        _env: &ExecutionEnv,
     ) -> Result<command_group::AsyncGroupChild, ExecutorError> {
         Err(ExecutorError::FollowUpNotSupported)
     }
 
+    /// Spawn a one-off, non-interactive run on a prompt outside any task
+    ///
+    /// Used for short helper calls such as writing PR descriptions, run in a
+    /// scratch `working_dir` rather than a worktree. Executors with a
+    /// read-only mode use it. The final assistant message can be read from
+    /// the output with `parse_assistant_message_from_logs`. The default
+    /// implementation returns an error.
+    async fn spawn_prompt(
+        &self,
+        _prompt: &str,
+        _working_dir: &str,
+        _env: &ExecutionEnv,
+    ) -> Result<command_group::AsyncGroupChild, ExecutorError> {
+        Err(ExecutorError::PromptNotSupported)
+    }
+
     /// Normalize executor logs into a standard format
     fn normalize_logs(
         &self,
@@ -346,8 +372,9 @@ pub trait Executor: Send + Sync {
         attempt_id: Uuid,
         execution_process_id: Uuid,
         worktree_path: &str,
+        env: &ExecutionEnv,
     ) -> Result<command_group::AsyncGroupChild, ExecutorError> {
-        let mut child = self.spawn(pool, task_id, worktree_path).await?;
+        let mut child = self.spawn(pool, task_id, worktree_path, env).await?;
         Self::setup_streaming(self, &mut child, pool, attempt_id, execution_process_id)?;
         Ok(child)
     }
@@ -363,9 +390,10 @@ pub trait Executor: Send + Sync {
         session_id: &str,
         prompt: &str,
         worktree_path: &str,
+        env: &ExecutionEnv,
     ) -> Result<command_group::AsyncGroupChild, ExecutorError> {
         let mut child = self
-            .spawn_followup(pool, task_id, session_id, prompt, worktree_path)
+            .spawn_followup(pool, task_id, session_id, prompt, worktree_path, env)
             .await?;
         Self::setup_streaming(self, &mut child, pool, attempt_id, execution_process_id)?;
         Ok(child)
@@ -868,7 +896,10 @@ fn parse_session_id_from_line(line: &str) -> Option<String> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::executors::{AmpExecutor, ClaudeExecutor};
+    use crate::{
+        executors::{AmpExecutor, ClaudeExecutor},
+        services::ExecutionEnv,
+    };
 
     #[test]
     fn test_parse_claude_session_id() {
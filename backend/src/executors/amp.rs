@@ -11,6 +11,7 @@ use crate::{
         NormalizedEntryType,
     },
     models::task::Task,
+    services::ExecutionEnv,
     utils::shell::get_shell_command,
 };
 
@@ -193,6 +194,7 @@ impl Executor for AmpExecutor {
         pool: &sqlx::SqlitePool,
         task_id: Uuid,
         worktree_path: &str,
+        env: &ExecutionEnv,
     ) -> Result<AsyncGroupChild, ExecutorError> {
         // Get the task to fetch its description
         let task = Task::find_by_id(pool, task_id)
@@ -235,6 +237,8 @@ Task title: {}"#,
             .arg(shell_arg)
             .arg(amp_command);
 
+        env.apply(&mut command);
+
         let mut child = command
             .group_spawn() // Create new process group so we can kill entire tree
             .map_err(|e| {
@@ -260,6 +264,7 @@ Task title: {}"#,
         session_id: &str,
         prompt: &str,
         worktree_path: &str,
+        env: &ExecutionEnv,
     ) -> Result<AsyncGroupChild, ExecutorError> {
         use std::process::Stdio;
 
@@ -282,6 +287,8 @@ Task title: {}"#,
             .arg(shell_arg)
             .arg(&amp_command);
 
+        env.apply(&mut command);
+
         let mut child = command.group_spawn().map_err(|e| {
             crate::executor::SpawnContext::from_command(&command, "Amp")
                 .with_context(format!(
@@ -314,6 +321,55 @@ Task title: {}"#,
         Ok(child)
     }
 
+    async fn spawn_prompt(
+        &self,
+        prompt: &str,
+        working_dir: &str,
+        env: &ExecutionEnv,
+    ) -> Result<AsyncGroupChild, ExecutorError> {
+        use std::process::Stdio;
+
+        use tokio::{io::AsyncWriteExt, process::Command};
+
+        // Use shell command for cross-platform compatibility
+        let (shell_cmd, shell_arg) = get_shell_command();
+        let amp_command = "npx @sourcegraph/amp@0.0.1752148945-gd8844f --format=jsonl";
+
+        let mut command = Command::new(shell_cmd);
+        command
+            .kill_on_drop(true)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .current_dir(working_dir)
+            .arg(shell_arg)
+            .arg(amp_command);
+
+        env.apply(&mut command);
+
+        let mut child = command.group_spawn().map_err(|e| {
+            crate::executor::SpawnContext::from_command(&command, "Amp")
+                .with_context("Amp CLI one-off prompt")
+                .spawn_error(e)
+        })?;
+
+        // Feed the prompt in, then close the pipe so amp sees EOF
+        if let Some(mut stdin) = child.inner().stdin.take() {
+            stdin.write_all(prompt.as_bytes()).await.map_err(|e| {
+                crate::executor::SpawnContext::from_command(&command, "Amp")
+                    .with_context("Failed to write prompt to Amp CLI stdin")
+                    .spawn_error(e)
+            })?;
+            stdin.shutdown().await.map_err(|e| {
+                crate::executor::SpawnContext::from_command(&command, "Amp")
+                    .with_context("Failed to close Amp CLI stdin")
+                    .spawn_error(e)
+            })?;
+        }
+
+        Ok(child)
+    }
+
     fn normalize_logs(
         &self,
         logs: &str,
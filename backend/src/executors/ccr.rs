@@ -5,6 +5,7 @@ use uuid::Uuid;
 use crate::{
     executor::{Executor, ExecutorError, NormalizedConversation},
     executors::ClaudeExecutor,
+    services::ExecutionEnv,
 };
 
 /// An executor that uses Claude Code Router (CCR) to process tasks
@@ -33,8 +34,9 @@ impl Executor for CCRExecutor {
         pool: &sqlx::SqlitePool,
         task_id: Uuid,
         worktree_path: &str,
+        env: &ExecutionEnv,
     ) -> Result<AsyncGroupChild, ExecutorError> {
-        self.0.spawn(pool, task_id, worktree_path).await
+        self.0.spawn(pool, task_id, worktree_path, env).await
     }
 
     async fn spawn_followup(
@@ -44,12 +46,22 @@ impl Executor for CCRExecutor {
         session_id: &str,
         prompt: &str,
         worktree_path: &str,
+        env: &ExecutionEnv,
     ) -> Result<AsyncGroupChild, ExecutorError> {
         self.0
-            .spawn_followup(pool, task_id, session_id, prompt, worktree_path)
+            .spawn_followup(pool, task_id, session_id, prompt, worktree_path, env)
             .await
     }
 
+    async fn spawn_prompt(
+        &self,
+        prompt: &str,
+        working_dir: &str,
+        env: &ExecutionEnv,
+    ) -> Result<AsyncGroupChild, ExecutorError> {
+        self.0.spawn_prompt(prompt, working_dir, env).await
+    }
+
     fn normalize_logs(
         &self,
         logs: &str,
@@ -11,6 +11,7 @@ use crate::{
         NormalizedEntryType,
     },
     models::task::Task,
+    services::{mcp_injection::mcp_config_arg, ExecutionEnv},
     utils::shell::get_shell_command,
 };
 
@@ -59,12 +60,11 @@ impl ClaudeExecutor {
         }
     }
 
+    /// Plan mode wraps the command in a watchkill script when it is spawned
     pub fn new_plan_mode() -> Self {
-        let command = "npx -y @anthropic-ai/claude-code@latest -p --permission-mode=plan --verbose --output-format=stream-json";
-        let script = create_watchkill_script(command);
         Self {
             executor_type: "ClaudePlan".to_string(),
-            command: script,
+            command: "npx -y @anthropic-ai/claude-code@latest -p --permission-mode=plan --verbose --output-format=stream-json".to_string(),
         }
     }
 
@@ -75,6 +75,20 @@ impl ClaudeExecutor {
             command,
         }
     }
+
+    /// The shell command to run: the CLI invocation plus the attempt's MCP
+    /// config, wrapped in the watchkill script in plan mode
+    fn shell_command(&self, command: String, env: &ExecutionEnv) -> String {
+        let command = match env.mcp_config() {
+            Some(path) => format!("{} {}", command, mcp_config_arg(path)),
+            None => command,
+        };
+        if self.executor_type == "ClaudePlan" {
+            create_watchkill_script(&command)
+        } else {
+            command
+        }
+    }
 }
 
 #[async_trait]
@@ -84,6 +98,7 @@ impl Executor for ClaudeExecutor {
         pool: &sqlx::SqlitePool,
         task_id: Uuid,
         worktree_path: &str,
+        env: &ExecutionEnv,
     ) -> Result<AsyncGroupChild, ExecutorError> {
         // Get the task to fetch its description
         let task = Task::find_by_id(pool, task_id)
@@ -110,7 +125,7 @@ Task title: {}"#,
         // Use shell command for cross-platform compatibility
         let (shell_cmd, shell_arg) = get_shell_command();
         // Pass prompt via stdin instead of command line to avoid shell escaping issues
-        let claude_command = &self.command;
+        let claude_command = self.shell_command(self.command.clone(), env);
 
         let mut command = Command::new(shell_cmd);
         command
@@ -120,9 +135,11 @@ Task title: {}"#,
             .stderr(std::process::Stdio::piped())
             .current_dir(worktree_path)
             .arg(shell_arg)
-            .arg(claude_command)
+            .arg(&claude_command)
             .env("NODE_NO_WARNINGS", "1");
 
+        env.apply(&mut command);
+
         let mut child = command
             .group_spawn() // Create new process group so we can kill entire tree
             .map_err(|e| {
@@ -169,20 +186,13 @@ Task title: {}"#,
         session_id: &str,
         prompt: &str,
         worktree_path: &str,
+        env: &ExecutionEnv,
     ) -> Result<AsyncGroupChild, ExecutorError> {
         // Use shell command for cross-platform compatibility
         let (shell_cmd, shell_arg) = get_shell_command();
 
-        // Determine the command based on whether this is plan mode or not
-        let claude_command = if self.executor_type == "ClaudePlan" {
-            let command = format!(
-                "npx -y @anthropic-ai/claude-code@latest -p --permission-mode=plan --verbose --output-format=stream-json --resume={}",
-                session_id
-            );
-            create_watchkill_script(&command)
-        } else {
-            format!("{} --resume={}", self.command, session_id)
-        };
+        let claude_command =
+            self.shell_command(format!("{} --resume={}", self.command, session_id), env);
 
         let mut command = Command::new(shell_cmd);
         command
@@ -195,6 +205,8 @@ Task title: {}"#,
             .arg(&claude_command)
             .env("NODE_NO_WARNINGS", "1");
 
+        env.apply(&mut command);
+
         let mut child = command.group_spawn().map_err(|e| {
             crate::executor::SpawnContext::from_command(&command, &self.executor_type)
                 .with_context(format!(
@@ -236,6 +248,62 @@ Task title: {}"#,
         Ok(child)
     }
 
+    async fn spawn_prompt(
+        &self,
+        prompt: &str,
+        working_dir: &str,
+        env: &ExecutionEnv,
+    ) -> Result<AsyncGroupChild, ExecutorError> {
+        // Use shell command for cross-platform compatibility
+        let (shell_cmd, shell_arg) = get_shell_command();
+        // One-off prompts only answer, so they never get to act
+        let read_only_command = self
+            .command
+            .replace("--dangerously-skip-permissions", "--permission-mode=plan");
+        let claude_command = self.shell_command(read_only_command, env);
+
+        let mut command = Command::new(shell_cmd);
+        command
+            .kill_on_drop(true)
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .current_dir(working_dir)
+            .arg(shell_arg)
+            .arg(&claude_command)
+            .env("NODE_NO_WARNINGS", "1");
+
+        env.apply(&mut command);
+
+        let mut child = command.group_spawn().map_err(|e| {
+            crate::executor::SpawnContext::from_command(&command, &self.executor_type)
+                .with_context(format!("{} CLI one-off prompt", self.executor_type))
+                .spawn_error(e)
+        })?;
+
+        // Write prompt to stdin safely
+        if let Some(mut stdin) = child.inner().stdin.take() {
+            use tokio::io::AsyncWriteExt;
+            stdin.write_all(prompt.as_bytes()).await.map_err(|e| {
+                let context =
+                    crate::executor::SpawnContext::from_command(&command, &self.executor_type)
+                        .with_context(format!(
+                            "Failed to write prompt to {} CLI stdin",
+                            self.executor_type
+                        ));
+                ExecutorError::spawn_failed(e, context)
+            })?;
+            stdin.shutdown().await.map_err(|e| {
+                let context =
+                    crate::executor::SpawnContext::from_command(&command, &self.executor_type)
+                        .with_context(format!("Failed to close {} CLI stdin", self.executor_type));
+                ExecutorError::spawn_failed(e, context)
+            })?;
+        }
+
+        Ok(child)
+    }
+
     fn normalize_logs(
         &self,
         logs: &str,
@@ -6,6 +6,7 @@ use uuid::Uuid;
 use crate::{
     executor::{Executor, ExecutorError},
     models::{project::Project, task::Task},
+    services::ExecutionEnv,
     utils::shell::get_shell_command,
 };
 
@@ -21,6 +22,7 @@ impl Executor for DevServerExecutor {
         pool: &sqlx::SqlitePool,
         task_id: Uuid,
         worktree_path: &str,
+        env: &ExecutionEnv,
     ) -> Result<AsyncGroupChild, ExecutorError> {
         // Validate the task and project exist
         let task = Task::find_by_id(pool, task_id)
@@ -41,6 +43,8 @@ impl Executor for DevServerExecutor {
             .arg(&self.script)
             .current_dir(worktree_path);
 
+        env.apply(&mut command);
+
         let child = command.group_spawn().map_err(|e| {
             crate::executor::SpawnContext::from_command(&command, "DevServer")
                 .with_task(task_id, Some(task.title.clone()))
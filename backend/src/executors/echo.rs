@@ -6,6 +6,7 @@ use uuid::Uuid;
 use crate::{
     executor::{Executor, ExecutorError},
     models::task::Task,
+    services::ExecutionEnv,
     utils::shell::get_shell_command,
 };
 
@@ -19,6 +20,7 @@ impl Executor for EchoExecutor {
         pool: &sqlx::SqlitePool,
         task_id: Uuid,
         _worktree_path: &str,
+        env: &ExecutionEnv,
     ) -> Result<AsyncGroupChild, ExecutorError> {
         // Get the task to fetch its description
         let task = Task::find_by_id(pool, task_id)
@@ -65,6 +67,8 @@ echo "Task completed: {}""#,
             .arg(shell_arg)
             .arg(&script);
 
+        env.apply(&mut command);
+
         let child = command
             .group_spawn() // Create new process group so we can kill entire tree
             .map_err(|e| {
@@ -22,6 +22,7 @@ use crate::{
         Executor, ExecutorError, NormalizedConversation, NormalizedEntry, NormalizedEntryType,
     },
     models::task::Task,
+    services::ExecutionEnv,
     utils::shell::get_shell_command,
 };
 
@@ -35,6 +36,7 @@ impl Executor for GeminiExecutor {
         pool: &sqlx::SqlitePool,
         task_id: Uuid,
         worktree_path: &str,
+        env: &ExecutionEnv,
     ) -> Result<AsyncGroupChild, ExecutorError> {
         // Get the task to fetch its description
         let task = Task::find_by_id(pool, task_id)
@@ -59,6 +61,7 @@ Task title: {}"#,
         };
 
         let mut command = Self::create_gemini_command(worktree_path);
+        env.apply(&mut command);
 
         let mut child = command
             .group_spawn() // Create new process group so we can kill entire tree
@@ -104,6 +107,7 @@ Task title: {}"#,
         attempt_id: Uuid,
         execution_process_id: Uuid,
         worktree_path: &str,
+        env: &ExecutionEnv,
     ) -> Result<AsyncGroupChild, ExecutorError> {
         tracing::info!(
             "Starting Gemini execution for task {} attempt {}",
@@ -113,7 +117,7 @@ Task title: {}"#,
 
         Self::update_session_id(pool, execution_process_id, &attempt_id.to_string()).await;
 
-        let mut child = self.spawn(pool, task_id, worktree_path).await?;
+        let mut child = self.spawn(pool, task_id, worktree_path, env).await?;
 
         tracing::info!(
             "Gemini process spawned successfully for attempt {}, PID: {:?}",
@@ -133,6 +137,7 @@ Task title: {}"#,
         session_id: &str,
         prompt: &str,
         worktree_path: &str,
+        env: &ExecutionEnv,
     ) -> Result<AsyncGroupChild, ExecutorError> {
         // For Gemini, session_id is the attempt_id
         let attempt_id = Uuid::parse_str(session_id)
@@ -141,7 +146,7 @@ Task title: {}"#,
         let task = self.load_task(pool, task_id).await?;
         let resume_context = self.collect_resume_context(pool, &task, attempt_id).await?;
         let comprehensive_prompt = self.build_comprehensive_prompt(&task, &resume_context, prompt);
-        self.spawn_process(worktree_path, &comprehensive_prompt, attempt_id)
+        self.spawn_process(worktree_path, env, &comprehensive_prompt, attempt_id)
             .await
     }
 
@@ -154,6 +159,7 @@ Task title: {}"#,
         session_id: &str,
         prompt: &str,
         worktree_path: &str,
+        env: &ExecutionEnv,
     ) -> Result<AsyncGroupChild, ExecutorError> {
         tracing::info!(
             "Starting Gemini follow-up execution for attempt {} (session {})",
@@ -165,7 +171,7 @@ Task title: {}"#,
         Self::update_session_id(pool, execution_process_id, session_id).await;
 
         let mut child = self
-            .spawn_followup(pool, task_id, session_id, prompt, worktree_path)
+            .spawn_followup(pool, task_id, session_id, prompt, worktree_path, env)
             .await?;
 
         tracing::info!(
@@ -510,6 +516,7 @@ You are continuing work on the above task. The execution history shows what has
     async fn spawn_process(
         &self,
         worktree_path: &str,
+        env: &ExecutionEnv,
         comprehensive_prompt: &str,
         attempt_id: Uuid,
     ) -> Result<AsyncGroupChild, ExecutorError> {
@@ -520,6 +527,7 @@ You are continuing work on the above task. The execution history shows what has
         );
 
         let mut command = GeminiExecutor::create_gemini_command(worktree_path);
+        env.apply(&mut command);
 
         let mut child = command.group_spawn().map_err(|e| {
             crate::executor::SpawnContext::from_command(&command, "Gemini")
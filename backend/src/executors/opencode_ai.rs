@@ -5,6 +5,7 @@ use uuid::Uuid;
 use crate::{
     executor::{Executor, ExecutorError},
     models::task::Task,
+    services::ExecutionEnv,
     utils::shell::get_shell_command,
 };
 
@@ -18,6 +19,7 @@ impl Executor for OpencodeAiExecutor {
         pool: &sqlx::SqlitePool,
         task_id: Uuid,
         worktree_path: &str,
+        env: &ExecutionEnv,
     ) -> Result<AsyncGroupChild, ExecutorError> {
         // Get the task to fetch its description
         let task = Task::find_by_id(pool, task_id)
@@ -61,6 +63,8 @@ Task title: {}"#,
             .arg(shell_arg)
             .arg(opencode_command);
 
+        env.apply(&mut command);
+
         let child = command
             .group_spawn() // Create new process group so we can kill entire tree
             .map_err(|e| {
@@ -80,6 +84,7 @@ Task title: {}"#,
         _session_id: &str,
         prompt: &str,
         worktree_path: &str,
+        env: &ExecutionEnv,
     ) -> Result<AsyncGroupChild, ExecutorError> {
         use std::process::Stdio;
 
@@ -102,6 +107,8 @@ Task title: {}"#,
             .arg(shell_arg)
             .arg(&opencode_command);
 
+        env.apply(&mut command);
+
         let child = command.group_spawn().map_err(|e| {
             crate::executor::SpawnContext::from_command(&command, "OpenCode AI")
                 .with_context("OpenCode AI CLI followup execution")
@@ -10,6 +10,7 @@ use uuid::Uuid;
 use crate::{
     executor::{Executor, ExecutorError, NormalizedConversation, NormalizedEntry},
     models::{execution_process::ExecutionProcess, executor_session::ExecutorSession, task::Task},
+    services::ExecutionEnv,
     utils::shell::get_shell_command,
 };
 
@@ -244,6 +245,7 @@ impl Executor for SstOpencodeExecutor {
         pool: &sqlx::SqlitePool,
         task_id: Uuid,
         worktree_path: &str,
+        env: &ExecutionEnv,
     ) -> Result<AsyncGroupChild, ExecutorError> {
         // Get the task to fetch its description
         let task = Task::find_by_id(pool, task_id)
@@ -281,6 +283,7 @@ Task title: {}"#,
             .arg(shell_arg)
             .arg(opencode_command)
             .env("NODE_NO_WARNINGS", "1");
+        env.apply(&mut command);
 
         let mut child = command
             .group_spawn() // Create new process group so we can kill entire tree
@@ -329,8 +332,9 @@ Task title: {}"#,
         attempt_id: Uuid,
         execution_process_id: Uuid,
         worktree_path: &str,
+        env: &ExecutionEnv,
     ) -> Result<command_group::AsyncGroupChild, ExecutorError> {
-        let mut child = self.spawn(pool, task_id, worktree_path).await?;
+        let mut child = self.spawn(pool, task_id, worktree_path, env).await?;
 
         // Take stderr pipe for OpenCode filtering
         let stderr = child
@@ -391,9 +395,10 @@ Task title: {}"#,
         session_id: &str,
         prompt: &str,
         worktree_path: &str,
+        env: &ExecutionEnv,
     ) -> Result<command_group::AsyncGroupChild, ExecutorError> {
         let mut child = self
-            .spawn_followup(pool, task_id, session_id, prompt, worktree_path)
+            .spawn_followup(pool, task_id, session_id, prompt, worktree_path, env)
             .await?;
 
         // Take stderr pipe for OpenCode filtering
@@ -424,6 +429,7 @@ Task title: {}"#,
         session_id: &str,
         prompt: &str,
         worktree_path: &str,
+        env: &ExecutionEnv,
     ) -> Result<AsyncGroupChild, ExecutorError> {
         use std::process::Stdio;
 
@@ -443,6 +449,7 @@ Task title: {}"#,
             .arg(shell_arg)
             .arg(&opencode_command)
             .env("NODE_NO_WARNINGS", "1");
+        env.apply(&mut command);
 
         let mut child = command.group_spawn().map_err(|e| {
             crate::executor::SpawnContext::from_command(&command, &self.executor_type)
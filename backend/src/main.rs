@@ -5,7 +5,7 @@ use axum::{
     http::{header, HeaderValue, StatusCode},
     middleware::from_fn_with_state,
     response::{IntoResponse, Json as ResponseJson, Response},
-    routing::{get, post},
+    routing::{get, post, put},
     Json, Router,
 };
 use sentry_tower::NewSentryLayer;
@@ -17,6 +17,7 @@ use automagik_forge::{sentry_layer, Assets, ScriptAssets, SoundAssets};
 
 mod app_state;
 mod auth;
+mod auth_providers;
 mod app_config;
 mod execution_monitor;
 mod executor;
@@ -34,15 +35,20 @@ use execution_monitor::execution_monitor;
 use middleware::{
     load_execution_process_simple_middleware, load_project_middleware,
     load_task_attempt_middleware, load_task_middleware, load_task_template_middleware,
+    load_webhook_middleware,
 };
 use security::{
-    security_headers_middleware, security_monitoring_middleware, create_secure_cors_layer,
+    rate_limiter::rate_limit_middleware, secret_store::SecretStore, security_headers_middleware,
+    security_monitoring_middleware, create_secure_cors_layer,
 };
 use models::{ApiResponse, Config};
 use routes::{
-    auth as routes_auth, config as routes_config, filesystem, health, oauth, projects, task_attempts, task_templates, tasks,
+    auth as routes_auth, auth_providers as routes_auth_providers, config as routes_config, filesystem, forge_tokens, github_webhook, health, notification_channels, notification_rules, oauth, project_env, project_github_issues, project_mcp_servers, project_webhooks, projects, secrets as routes_secrets, task_attempts, task_templates, tasks, whatsapp_webhook,
+};
+use services::{
+    notification_rules::NotificationDigestService, webhooks::WebhookDispatcher,
+    whatsapp_outbox::WhatsAppOutboxService, PrMonitorService,
 };
-use services::PrMonitorService;
 use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
 
@@ -238,13 +244,33 @@ fn main() -> anyhow::Result<()> {
             let pool = SqlitePool::connect_with(options).await?;
             sqlx::migrate!("./migrations").run(&pool).await?;
 
-            // Load configuration
+            // Encrypted secret storage, keyed from the keyring file next to the database
+            let secret_store = Arc::new(SecretStore::open(pool.clone())?);
+
+            // `automagik-forge secrets rotate-key|status` manages the keyring and exits
+            let args: Vec<String> = std::env::args().collect();
+            if args.get(1).map(String::as_str) == Some("secrets") {
+                return security::secret_store::run_cli(&secret_store, &args[2..]).await;
+            }
+
+            // Load configuration; its secret fields come from the secret store
             let config_path = utils::config_path();
-            let config = Config::load(&config_path)?;
+            let mut config = Config::load(&config_path)?;
+            secret_store.load_config_secrets(&mut config, &config_path).await?;
             let config_arc = Arc::new(RwLock::new(config));
 
+            match secret_store.migrate_user_github_tokens().await {
+                Ok(0) => {}
+                Ok(count) => tracing::info!("Moved {} user GitHub token(s) into the secret store", count),
+                Err(e) => tracing::error!("Failed to migrate user GitHub tokens: {}", e),
+            }
+
             // Create app state
-            let app_state = AppState::new(pool.clone(), config_arc.clone()).await;
+            let app_state = AppState::new(pool.clone(), config_arc.clone(), secret_store).await;
+
+            if let Err(e) = app_state.auth_providers().bootstrap_local_admin(&pool).await {
+                tracing::error!("Failed to create local admin account: {}", e);
+            }
 
             app_state.update_sentry_scope().await;
 
@@ -257,11 +283,31 @@ fn main() -> anyhow::Result<()> {
             });
 
             // Start PR monitoring service
-            let pr_monitor = PrMonitorService::new(pool.clone());
-            let config_for_monitor = config_arc.clone();
+            let pr_monitor = PrMonitorService::new(pool.clone(), app_state.secret_store().clone());
+
+            tokio::spawn(async move {
+                pr_monitor.start().await;
+            });
+
+            // Start delivering queued webhook events
+            let webhook_dispatcher =
+                WebhookDispatcher::new(pool.clone(), app_state.secret_store().clone());
+            tokio::spawn(async move {
+                webhook_dispatcher.start().await;
+            });
+
+            // Start sending notification digests and notifications held back by quiet hours
+            let digest_service =
+                NotificationDigestService::new(pool.clone(), app_state.secret_store().clone());
+            tokio::spawn(async move {
+                digest_service.start().await;
+            });
 
+            // Start sending queued WhatsApp messages over one long-lived MCP session
+            let whatsapp_outbox =
+                WhatsAppOutboxService::new(pool.clone(), app_state.secret_store().clone());
             tokio::spawn(async move {
-                pr_monitor.start_with_config(config_for_monitor).await;
+                whatsapp_outbox.start().await;
             });
 
             // Public routes (no auth required)
@@ -272,9 +318,19 @@ fn main() -> anyhow::Result<()> {
                     Router::new()
                         .route("/api/auth/github/device/start", post(routes_auth::device_start))
                         .route("/api/auth/github/device/poll", post(routes_auth::device_poll))
+                        .route("/api/auth/providers", get(routes_auth_providers::list_auth_providers))
+                        .route("/api/auth/local/login", post(routes_auth_providers::local_login))
+                        .route("/api/auth/oidc/start", post(routes_auth_providers::oidc_start))
+                        .route("/api/auth/oidc/callback", post(routes_auth_providers::oidc_callback))
                 )
                 .nest("/api", routes_config::config_router())
-                .merge(oauth::oauth_router());
+                // Evolution API authenticates with the webhook token instead
+                .nest("/api", whatsapp_webhook::whatsapp_webhook_router())
+                // GitHub signs its deliveries with the webhook secret instead
+                .nest("/api", github_webhook::github_webhook_router())
+                .merge(oauth::oauth_router())
+                // Throttle login polling and OAuth endpoints per IP/user
+                .layer(from_fn_with_state(app_state.clone(), rate_limit_middleware));
 
             // Protected routes (require authentication)
             let protected_routes = Router::new()
@@ -283,7 +339,17 @@ fn main() -> anyhow::Result<()> {
                 .route("/auth/me", get(routes_auth::get_current_user_info))
                 .route("/auth/logout", post(routes_auth::logout))
                 .route("/auth/logout-all", post(routes_auth::logout_all))
+                .route("/auth/local/users", post(routes_auth_providers::create_local_user))
+                .route("/auth/local/password", put(routes_auth_providers::change_local_password))
+                .route("/secrets", get(routes_secrets::list_secrets))
+                .route(
+                    "/secrets/:name",
+                    put(routes_secrets::set_secret).delete(routes_secrets::delete_secret),
+                )
                 .route("/sounds/:filename", get(serve_sound_file))
+                .merge(forge_tokens::forge_tokens_router())
+                .merge(notification_channels::notification_channels_router())
+                .merge(notification_rules::notification_rules_router())
                 // Enhanced health check endpoints
                 .route("/health/detailed", get(health::detailed_health_check))
                 .route("/health/security", get(health::security_health_check))
@@ -319,6 +385,16 @@ fn main() -> anyhow::Result<()> {
                 .merge(projects::projects_base_router())
                 .merge(projects::projects_with_id_router()
                     .layer(from_fn_with_state(app_state.clone(), load_project_middleware)))
+                .merge(project_env::project_env_router()
+                    .layer(from_fn_with_state(app_state.clone(), load_project_middleware)))
+                .merge(project_mcp_servers::project_mcp_servers_router()
+                    .layer(from_fn_with_state(app_state.clone(), load_project_middleware)))
+                .merge(project_webhooks::project_webhooks_router()
+                    .layer(from_fn_with_state(app_state.clone(), load_project_middleware)))
+                .merge(project_webhooks::project_webhook_with_id_router()
+                    .layer(from_fn_with_state(app_state.clone(), load_webhook_middleware)))
+                .merge(project_github_issues::project_github_issues_router()
+                    .layer(from_fn_with_state(app_state.clone(), load_project_middleware)))
                 .layer(from_fn_with_state(app_state.clone(), crate::auth::auth_middleware));
 
             // Task routes with appropriate middleware (protected)
@@ -337,6 +413,11 @@ fn main() -> anyhow::Result<()> {
                     .layer(from_fn_with_state(app_state.clone(), load_task_attempt_middleware)))
                 .layer(from_fn_with_state(app_state.clone(), crate::auth::auth_middleware));
 
+            // MCP server over streamable HTTP, sharing the app state (protected)
+            let mcp_routes = Router::new()
+                .nest_service("/mcp", mcp::streamable_http::mcp_service(app_state.clone()))
+                .layer(from_fn_with_state(app_state.clone(), crate::auth::auth_middleware));
+
             // All routes with authentication applied where needed
             let app_routes = Router::new()
                 .nest(
@@ -350,9 +431,13 @@ fn main() -> anyhow::Result<()> {
                         .layer(from_fn_with_state(app_state.clone(), routes_auth::sentry_user_context_middleware)),
                 );
 
+            // Told where the listener ended up once it is bound
+            let listening_state = app_state.clone();
+
             let app = Router::new()
                 .merge(public_routes)
                 .merge(app_routes)
+                .merge(mcp_routes)
                 .merge(SwaggerUi::new("/docs").url("/api-docs/openapi.json", ApiDoc::openapi()))
                 // Static file serving routes
                 .route("/", get(index_handler))
@@ -382,6 +467,7 @@ fn main() -> anyhow::Result<()> {
             let host = std::env::var("HOST").unwrap_or_else(|_| "127.0.0.1".to_string());
             let listener = tokio::net::TcpListener::bind(format!("{host}:{port}")).await?;
             let actual_port = listener.local_addr()?.port(); // get → 53427 (example)
+            listening_state.set_server_address(listener.local_addr()?);
 
             tracing::info!("Server running on http://{host}:{actual_port}");
 
@@ -392,7 +478,12 @@ fn main() -> anyhow::Result<()> {
                 }
             }
 
-            axum::serve(listener, app).await?;
+            // Peer addresses feed the per-IP rate limiter
+            axum::serve(
+                listener,
+                app.into_make_service_with_connect_info::<std::net::SocketAddr>(),
+            )
+            .await?;
 
             Ok(())
         })
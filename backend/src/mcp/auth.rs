@@ -0,0 +1,113 @@
+//! User resolution for MCP requests.
+//!
+//! Every MCP tool call runs as a Forge user. HTTP transports send the user's
+//! MCP access token (issued by the OAuth endpoints) as a bearer token with each
+//! request; the STDIO transport is started with a token taken from
+//! `FORGE_MCP_TOKEN` or `mcp_token` in config.toml.
+
+use axum::http::{header::AUTHORIZATION, request::Parts};
+use sqlx::SqlitePool;
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::{
+    auth::{extract_bearer_token, hash_token, validate_jwt_token, JwtConfig, UserContext},
+    models::{
+        user::User,
+        user_session::{SessionType, UserSession},
+    },
+};
+
+#[derive(Debug, Error)]
+pub enum McpAuthError {
+    #[error("Authentication required: pass an MCP access token as a Bearer token or set FORGE_MCP_TOKEN")]
+    MissingToken,
+    #[error("Invalid or expired MCP access token")]
+    InvalidToken,
+    #[error("User {0} is not whitelisted")]
+    NotWhitelisted(String),
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+/// The bearer token of an HTTP request, if any
+pub fn request_bearer_token(parts: &Parts) -> Option<&str> {
+    parts
+        .headers
+        .get(AUTHORIZATION)
+        .and_then(|header| header.to_str().ok())
+        .and_then(extract_bearer_token)
+}
+
+/// Resolve the user behind an MCP access token, applying the same session and
+/// whitelist checks as `auth_middleware`. Only MCP sessions are accepted.
+pub async fn authenticate_mcp_token(
+    pool: &SqlitePool,
+    jwt_config: &JwtConfig,
+    token: &str,
+) -> Result<UserContext, McpAuthError> {
+    let claims = validate_jwt_token(token, jwt_config).map_err(|_| McpAuthError::InvalidToken)?;
+    if claims.session_type != SessionType::Mcp {
+        return Err(McpAuthError::InvalidToken);
+    }
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| McpAuthError::InvalidToken)?;
+    let session_id = Uuid::parse_str(&claims.session_id).map_err(|_| McpAuthError::InvalidToken)?;
+
+    let session = UserSession::find_valid_by_token_hash(pool, &hash_token(token))
+        .await?
+        .ok_or(McpAuthError::InvalidToken)?;
+    if session.user_id != user_id
+        || session.id != session_id
+        || session.session_type != SessionType::Mcp
+    {
+        return Err(McpAuthError::InvalidToken);
+    }
+
+    let user = User::find_by_id(pool, user_id)
+        .await?
+        .ok_or(McpAuthError::InvalidToken)?;
+    if !user.is_whitelisted {
+        return Err(McpAuthError::NotWhitelisted(user.username));
+    }
+
+    Ok(UserContext { user, session })
+}
+
+#[cfg(test)]
+mod tests {
+    use jsonwebtoken::Algorithm;
+
+    use super::*;
+    use crate::auth::generate_jwt_token;
+
+    fn jwt_config() -> JwtConfig {
+        JwtConfig {
+            secret: "an-mcp-auth-test-secret-of-at-least-32-bytes".to_string(),
+            algorithm: Algorithm::HS256,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rejects_web_session_tokens() {
+        // Rejected from the claims alone, before any database lookup
+        let pool = SqlitePool::connect_lazy("sqlite::memory:").unwrap();
+        let jwt_config = jwt_config();
+        let token = generate_jwt_token(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            SessionType::Web,
+            &jwt_config,
+        )
+        .unwrap();
+
+        let result = authenticate_mcp_token(&pool, &jwt_config, &token).await;
+        assert!(matches!(result, Err(McpAuthError::InvalidToken)));
+    }
+
+    #[tokio::test]
+    async fn test_rejects_malformed_tokens() {
+        let pool = SqlitePool::connect_lazy("sqlite::memory:").unwrap();
+        let result = authenticate_mcp_token(&pool, &jwt_config(), "not-a-jwt").await;
+        assert!(matches!(result, Err(McpAuthError::InvalidToken)));
+    }
+}
@@ -1,2 +1,6 @@
 pub mod task_server;
 pub mod oauth_middleware;
+pub mod resources;
+pub mod prompts;
+pub mod auth;
+pub mod streamable_http;
@@ -1,168 +1,73 @@
 use std::sync::Arc;
-use std::collections::HashMap;
-use tokio::sync::RwLock;
 use axum::{
     extract::State,
-    http::{HeaderMap, Request, Response, StatusCode},
+    http::{Request, Response, StatusCode},
     middleware::Next,
     body::Body,
 };
 use sqlx::SqlitePool;
-use chrono;
 
 use crate::{
-    auth::{validate_jwt_token, hash_token, JwtConfig},
-    models::{
-        user::User,
-        user_session::{SessionType, UserSession},
-    },
-    mcp::task_server::{McpToken, REQUEST_CONTEXT},
+    auth::JwtConfig,
+    mcp::auth::{authenticate_mcp_token, request_bearer_token, McpAuthError},
 };
 
-/// OAuth validation middleware for MCP SSE connections
-/// This middleware validates Bearer tokens and injects user context into requests
-#[allow(dead_code)] // OAuth middleware for future MCP SSE authentication
-pub async fn validate_oauth_token_middleware(
-    State(token_store): State<Arc<RwLock<HashMap<String, McpToken>>>>,
-    State(db_pool): State<SqlitePool>,
-    headers: HeaderMap,
-    mut req: Request<Body>,
-    next: Next,
-) -> Response<Body> {
-    // Extract Authorization header
-    if let Some(auth_header) = headers.get("authorization") {
-        if let Ok(auth_str) = auth_header.to_str() {
-            if let Some(token) = auth_str.strip_prefix("Bearer ") {
-                // First try MCP token store
-                {
-                    let store = token_store.read().await;
-                    if let Some(mcp_token) = store.get(token) {
-                        if mcp_token.expires_at > chrono::Utc::now() {
-                            if let Ok(Some(user)) = User::find_by_id(&db_pool, mcp_token.user_id).await {
-                                if let Ok(Some(session)) = UserSession::find_by_token_hash(&db_pool, &hash_token(token)).await {
-                                    if user.is_whitelisted && session.session_type == SessionType::Mcp {
-                                        // Inject user context into request
-                                        req.extensions_mut().insert(user);
-                                        req.extensions_mut().insert(session);
-                                        
-                                        // Inject Bearer token into task-local storage for rmcp tools
-                                        let bearer_token = format!("Bearer {}", token);
-                                        return REQUEST_CONTEXT.scope(Some(bearer_token), next.run(req)).await;
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-                
-                // Fallback to JWT validation for existing OAuth endpoints compatibility
-                let jwt_config = JwtConfig::default();
-                if let Ok(claims) = validate_jwt_token(token, &jwt_config) {
-                    if let Ok(Some(session)) = UserSession::find_by_token_hash(&db_pool, &hash_token(token)).await {
-                        if let Ok(claims_uuid) = claims.sub.parse::<uuid::Uuid>() {
-                            if let Ok(Some(user)) = User::find_by_id(&db_pool, claims_uuid).await {
-                                if user.is_whitelisted && session.session_type == SessionType::Mcp {
-                                    // Inject user context into request
-                                    req.extensions_mut().insert(user);
-                                    req.extensions_mut().insert(session);
-                                    
-                                    // Inject Bearer token into task-local storage for rmcp tools
-                                    let bearer_token = format!("Bearer {}", token);
-                                    return REQUEST_CONTEXT.scope(Some(bearer_token), next.run(req)).await;
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
-    }
-    
-    // Return OAuth challenge response for unauthenticated requests
-    Response::builder()
-        .status(StatusCode::UNAUTHORIZED)
-        .header("WWW-Authenticate", "Bearer realm=\"MCP\", error=\"invalid_token\"")
-        .header("Content-Type", "application/json")
-        .body(Body::from(r#"{"error":"invalid_token","error_description":"Valid OAuth Bearer token required for MCP access"}"#))
-        .unwrap_or_else(|_| {
-            Response::builder()
-                .status(StatusCode::INTERNAL_SERVER_ERROR)
-                .body(Body::from("Internal Server Error"))
-                .unwrap()
-        })
+/// What the MCP authentication middleware needs to resolve tokens
+#[derive(Clone)]
+#[allow(dead_code)] // Used by the standalone MCP binary
+pub struct McpAuthState {
+    pub db_pool: SqlitePool,
+    pub jwt_config: Arc<JwtConfig>,
 }
 
-/// Enhanced OAuth middleware that also handles automatic browser opening workflow
-/// This is used by the SSE server to provide OAuth 2.1 authentication for external clients
-#[allow(dead_code)] // OAuth SSE middleware for future MCP authentication
+/// OAuth middleware for the MCP SSE transport.
+/// Rejects requests without a valid MCP access token with an OAuth 2.1 challenge
+/// so clients can start the browser flow. Tools resolve the user again from the
+/// same bearer token for every call.
+#[allow(dead_code)] // Used by the standalone MCP binary
 pub async fn oauth_sse_authentication_middleware(
-    token_store: Arc<RwLock<HashMap<String, McpToken>>>,
-    db_pool: SqlitePool,
-    headers: HeaderMap,
-    mut req: Request<Body>,
+    State(state): State<McpAuthState>,
+    req: Request<Body>,
     next: Next,
 ) -> Response<Body> {
-    // Check for existing valid authentication first
-    if let Some(auth_header) = headers.get("authorization") {
-        if let Ok(auth_str) = auth_header.to_str() {
-            if let Some(token) = auth_str.strip_prefix("Bearer ") {
-                // Validate token using the same logic as validate_oauth_token_middleware
-                {
-                    let store = token_store.read().await;
-                    if let Some(mcp_token) = store.get(token) {
-                        if mcp_token.expires_at > chrono::Utc::now() {
-                            if let Ok(Some(user)) = User::find_by_id(&db_pool, mcp_token.user_id).await {
-                                if let Ok(Some(session)) = UserSession::find_by_token_hash(&db_pool, &hash_token(token)).await {
-                                    if user.is_whitelisted && session.session_type == SessionType::Mcp {
-                                        req.extensions_mut().insert(user);
-                                        req.extensions_mut().insert(session);
-                                        
-                                        // Inject Bearer token into task-local storage for rmcp tools
-                                        let bearer_token = format!("Bearer {}", token);
-                                        return REQUEST_CONTEXT.scope(Some(bearer_token), next.run(req)).await;
-                                    }
-                                }
-                            }
-                        }
-                    }
+    let (parts, body) = req.into_parts();
+    let token = request_bearer_token(&parts).map(str::to_string);
+    let req = Request::from_parts(parts, body);
+
+    let error = match token {
+        Some(token) => {
+            match authenticate_mcp_token(&state.db_pool, &state.jwt_config, &token).await {
+                Ok(user_context) => {
+                    tracing::debug!("MCP request authenticated as {}", user_context.user.username);
+                    return next.run(req).await;
                 }
-                
-                // JWT fallback
-                let jwt_config = JwtConfig::default();
-                if let Ok(claims) = validate_jwt_token(token, &jwt_config) {
-                    if let Ok(Some(session)) = UserSession::find_by_token_hash(&db_pool, &hash_token(token)).await {
-                        if let Ok(claims_uuid) = claims.sub.parse::<uuid::Uuid>() {
-                            if let Ok(Some(user)) = User::find_by_id(&db_pool, claims_uuid).await {
-                                if user.is_whitelisted && session.session_type == SessionType::Mcp {
-                                    req.extensions_mut().insert(user);
-                                    req.extensions_mut().insert(session);
-                                    
-                                    // Inject Bearer token into task-local storage for rmcp tools
-                                    let bearer_token = format!("Bearer {}", token);
-                                    return REQUEST_CONTEXT.scope(Some(bearer_token), next.run(req)).await;
-                                }
-                            }
-                        }
-                    }
+                Err(McpAuthError::Database(e)) => {
+                    tracing::error!("Database error during MCP authentication: {}", e);
+                    return Response::builder()
+                        .status(StatusCode::INTERNAL_SERVER_ERROR)
+                        .body(Body::from("Internal Server Error"))
+                        .unwrap();
                 }
+                Err(_) => "invalid_token",
             }
         }
-    }
-    
+        None => "insufficient_scope",
+    };
+
     // For unauthenticated requests, return OAuth 2.1 challenge with discovery information
     let base_url = std::env::var("BASE_URL").unwrap_or_else(|_| "http://localhost:3001".to_string());
     let oauth_challenge = format!(
-        r#"Bearer realm="MCP", authorization_uri="{}/oauth/authorize", token_uri="{}/oauth/token", error="insufficient_scope""#,
-        base_url, base_url
+        r#"Bearer realm="MCP", authorization_uri="{}/oauth/authorize", token_uri="{}/oauth/token", error="{}""#,
+        base_url, base_url, error
     );
-    
+
     Response::builder()
         .status(StatusCode::UNAUTHORIZED)
         .header("WWW-Authenticate", &oauth_challenge)
         .header("Content-Type", "application/json")
         .body(Body::from(format!(
-            r#"{{"error":"insufficient_scope","error_description":"OAuth 2.1 authentication required","authorization_endpoint":"{}/.well-known/oauth-authorization-server"}}"#,
-            base_url
+            r#"{{"error":"{}","error_description":"OAuth 2.1 authentication required","authorization_endpoint":"{}/.well-known/oauth-authorization-server"}}"#,
+            error, base_url
         )))
         .unwrap_or_else(|_| {
             Response::builder()
@@ -170,4 +75,4 @@ pub async fn oauth_sse_authentication_middleware(
                 .body(Body::from("Internal Server Error"))
                 .unwrap()
         })
-}
\ No newline at end of file
+}
@@ -0,0 +1,375 @@
+//! Prompts for common Forge workflows.
+//!
+//! Each prompt is parameterized by project, task or attempt IDs and pre-filled
+//! with what Forge knows about them (tasks, templates, the attempt diff and
+//! the coding agent's summary), so planners don't have to re-type the same
+//! instructions and context.
+
+use std::fmt::Write as _;
+
+use rmcp::model::{
+    GetPromptResult, JsonObject, Prompt, PromptArgument, PromptMessage, PromptMessageRole,
+};
+use sqlx::SqlitePool;
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::{
+    mcp::task_server::{render_patch, truncate_to},
+    models::{
+        execution_process::{ExecutionProcess, ExecutionProcessType},
+        project::Project,
+        task::Task,
+        task_attempt::TaskAttempt,
+        task_template::TaskTemplate,
+    },
+    routes::task_attempts::normalize_process_logs,
+};
+
+/// Upper bound on the diff pasted into a prompt
+const PROMPT_DIFF_MAX_BYTES: usize = 60_000;
+
+#[derive(Debug, Error)]
+pub enum PromptError {
+    #[error("Unknown prompt: {0}")]
+    UnknownPrompt(String),
+    #[error("Invalid argument: {0}")]
+    InvalidArgument(String),
+    #[error("Not found: {0}")]
+    NotFound(String),
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("Failed to get attempt diff: {0}")]
+    Diff(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ForgePrompt {
+    /// Break a feature down into tasks for a wish
+    PlanFeature,
+    /// Review the changes of a task attempt
+    ReviewAttempt,
+    /// Write the pull request title and description for a task attempt
+    WritePrDescription,
+}
+
+impl ForgePrompt {
+    pub const ALL: [ForgePrompt; 3] = [
+        ForgePrompt::PlanFeature,
+        ForgePrompt::ReviewAttempt,
+        ForgePrompt::WritePrDescription,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::PlanFeature => "plan_feature",
+            Self::ReviewAttempt => "review_attempt",
+            Self::WritePrDescription => "write_pr_description",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Result<Self, PromptError> {
+        Self::ALL
+            .into_iter()
+            .find(|prompt| prompt.name() == name)
+            .ok_or_else(|| PromptError::UnknownPrompt(name.to_string()))
+    }
+
+    fn description(&self) -> &'static str {
+        match self {
+            Self::PlanFeature => {
+                "Break down a feature into tasks for a wish, given the wish's existing tasks and the project's task templates"
+            }
+            Self::ReviewAttempt => {
+                "Review a task attempt using its task, diff and the coding agent's summary"
+            }
+            Self::WritePrDescription => {
+                "Write a pull request title and description for a task attempt"
+            }
+        }
+    }
+
+    fn arguments(&self) -> Vec<PromptArgument> {
+        let argument = |name: &str, description: &str| PromptArgument {
+            name: name.to_string(),
+            description: Some(description.to_string()),
+            required: Some(true),
+        };
+        match self {
+            Self::PlanFeature => vec![
+                argument("project_id", "Project to plan the tasks in"),
+                argument("wish_id", "Wish the tasks are grouped under"),
+                argument("feature", "Description of the feature to break down"),
+            ],
+            Self::ReviewAttempt | Self::WritePrDescription => {
+                vec![argument("attempt_id", "Task attempt to use")]
+            }
+        }
+    }
+
+    pub fn prompt(&self) -> Prompt {
+        Prompt::new(
+            self.name(),
+            Some(self.description()),
+            Some(self.arguments()),
+        )
+    }
+
+    /// The prompt filled in with Forge data for `arguments`
+    pub async fn render(
+        &self,
+        pool: &SqlitePool,
+        arguments: Option<&JsonObject>,
+    ) -> Result<GetPromptResult, PromptError> {
+        let text = match self {
+            Self::PlanFeature => {
+                let project_id = uuid_argument(arguments, "project_id")?;
+                let wish_id = string_argument(arguments, "wish_id")?;
+                let feature = string_argument(arguments, "feature")?;
+                plan_feature(pool, project_id, &wish_id, &feature).await?
+            }
+            Self::ReviewAttempt => {
+                review_attempt(pool, uuid_argument(arguments, "attempt_id")?).await?
+            }
+            Self::WritePrDescription => {
+                write_pr_description(pool, uuid_argument(arguments, "attempt_id")?).await?
+            }
+        };
+
+        Ok(GetPromptResult {
+            description: Some(self.description().to_string()),
+            messages: vec![PromptMessage::new_text(PromptMessageRole::User, text)],
+        })
+    }
+}
+
+pub fn list_prompts() -> Vec<Prompt> {
+    ForgePrompt::ALL.iter().map(ForgePrompt::prompt).collect()
+}
+
+fn string_argument(arguments: Option<&JsonObject>, name: &str) -> Result<String, PromptError> {
+    arguments
+        .and_then(|arguments| arguments.get(name))
+        .and_then(|value| value.as_str())
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+        .ok_or_else(|| PromptError::InvalidArgument(format!("`{}` is required", name)))
+}
+
+fn uuid_argument(arguments: Option<&JsonObject>, name: &str) -> Result<Uuid, PromptError> {
+    let value = string_argument(arguments, name)?;
+    Uuid::parse_str(&value)
+        .map_err(|_| PromptError::InvalidArgument(format!("`{}` must be a valid UUID", name)))
+}
+
+async fn plan_feature(
+    pool: &SqlitePool,
+    project_id: Uuid,
+    wish_id: &str,
+    feature: &str,
+) -> Result<String, PromptError> {
+    let project = Project::find_by_id(pool, project_id)
+        .await?
+        .ok_or_else(|| PromptError::NotFound(format!("project {}", project_id)))?;
+    let tasks: Vec<_> = Task::find_by_project_id_with_attempt_status(pool, project_id)
+        .await?
+        .into_iter()
+        .filter(|task| task.wish_id == wish_id)
+        .collect();
+    let mut templates = TaskTemplate::find_by_project_id(pool, Some(project_id)).await?;
+    templates.extend(TaskTemplate::find_by_project_id(pool, None).await?);
+
+    let mut text = format!(
+        "Break down the following feature into tasks for wish `{}` of project \"{}\" (`{}`).\n\n## Feature\n\n{}\n",
+        wish_id, project.name, project.id, feature
+    );
+
+    text.push_str("\n## Existing tasks for this wish\n\n");
+    if tasks.is_empty() {
+        text.push_str("None yet.\n");
+    }
+    for task in &tasks {
+        let _ = writeln!(text, "- [{:?}] {} (`{}`)", task.status, task.title, task.id);
+        if let Some(description) = task.description.as_deref().filter(|d| !d.is_empty()) {
+            let _ = writeln!(text, "  {}", description.replace('\n', "\n  "));
+        }
+    }
+
+    if !templates.is_empty() {
+        text.push_str(
+            "\n## Task templates\n\nFollow these templates where a task fits one of them:\n\n",
+        );
+        for template in &templates {
+            let _ = writeln!(
+                text,
+                "### {} ({})\n",
+                template.template_name, template.title
+            );
+            if let Some(description) = template.description.as_deref() {
+                let _ = writeln!(text, "{}\n", description);
+            }
+        }
+    }
+
+    let _ = write!(
+        text,
+        "\n## Instructions\n\n\
+         Split the feature into small, independently reviewable tasks that a coding agent can \
+         complete in one attempt. Do not repeat work covered by existing tasks. Give every task \
+         a short imperative title and a description with the context, the expected change and \
+         how to verify it. Create each task with the `create_task` tool using project_id `{}` \
+         and wish_id `{}`.\n",
+        project.id, wish_id
+    );
+    Ok(text)
+}
+
+async fn review_attempt(pool: &SqlitePool, attempt_id: Uuid) -> Result<String, PromptError> {
+    let (attempt, task) = load_attempt(pool, attempt_id).await?;
+    let summary = agent_summary(pool, attempt.id).await?;
+    let diff = render_diff(pool, &attempt, &task).await?;
+
+    let mut text = format!(
+        "Review the changes a coding agent made for task attempt `{}` on branch `{}` (based on `{}`).\n",
+        attempt.id, attempt.branch, attempt.base_branch
+    );
+    push_task(&mut text, &task);
+    push_summary(&mut text, summary.as_deref());
+    text.push_str(&diff);
+    let _ = write!(
+        text,
+        "\n## Instructions\n\n\
+         Check that the changes implement the task completely and correctly. Look for bugs, \
+         missing error handling, missing or weakened tests, and changes unrelated to the task. \
+         List concrete findings with file paths. If changes are needed, send them to the agent \
+         with `send_follow_up` using attempt_id `{}`; if the attempt is ready, say so.\n",
+        attempt.id
+    );
+    Ok(text)
+}
+
+async fn write_pr_description(pool: &SqlitePool, attempt_id: Uuid) -> Result<String, PromptError> {
+    let (attempt, task) = load_attempt(pool, attempt_id).await?;
+    let summary = agent_summary(pool, attempt.id).await?;
+    let diff = render_diff(pool, &attempt, &task).await?;
+
+    let mut text = format!(
+        "Write a pull request title and description for task attempt `{}`, merging branch `{}` into `{}`.\n",
+        attempt.id, attempt.branch, attempt.base_branch
+    );
+    push_task(&mut text, &task);
+    push_summary(&mut text, summary.as_deref());
+    text.push_str(&diff);
+    let _ = write!(
+        text,
+        "\n## Instructions\n\n\
+         Write a title of at most 72 characters in the imperative mood. Start the description \
+         with one or two sentences on what the change does and why, then list the notable \
+         changes and how they were verified. Only describe what the diff shows. Open the pull \
+         request with `create_pull_request` using attempt_id `{}` and your title and body.\n",
+        attempt.id
+    );
+    Ok(text)
+}
+
+async fn load_attempt(
+    pool: &SqlitePool,
+    attempt_id: Uuid,
+) -> Result<(TaskAttempt, Task), PromptError> {
+    let not_found = || PromptError::NotFound(format!("task attempt {}", attempt_id));
+    let attempt = TaskAttempt::find_by_id(pool, attempt_id)
+        .await?
+        .ok_or_else(not_found)?;
+    let task = Task::find_by_id(pool, attempt.task_id)
+        .await?
+        .ok_or_else(not_found)?;
+    Ok((attempt, task))
+}
+
+/// The final summary of the attempt's latest coding agent run
+async fn agent_summary(pool: &SqlitePool, attempt_id: Uuid) -> Result<Option<String>, PromptError> {
+    let processes = ExecutionProcess::find_by_task_attempt_id(pool, attempt_id).await?;
+    let Some(process) = processes
+        .iter()
+        .rev()
+        .find(|p| p.process_type == ExecutionProcessType::CodingAgent)
+    else {
+        return Ok(None);
+    };
+    Ok(normalize_process_logs(pool, process).await.summary)
+}
+
+/// The attempt's diff as a markdown section, capped at `PROMPT_DIFF_MAX_BYTES`
+async fn render_diff(
+    pool: &SqlitePool,
+    attempt: &TaskAttempt,
+    task: &Task,
+) -> Result<String, PromptError> {
+    let diff = TaskAttempt::get_diff(pool, attempt.id, task.id, task.project_id)
+        .await
+        .map_err(|e| PromptError::Diff(e.to_string()))?;
+
+    let mut stat = String::new();
+    let mut patches = String::new();
+    for file in &diff.files {
+        let (patch, additions, deletions) = render_patch(&file.chunks);
+        let _ = writeln!(stat, "- {} (+{} -{})", file.path, additions, deletions);
+        let _ = write!(patches, "--- {}\n{}", file.path, patch);
+    }
+    let truncated = patches.len() > PROMPT_DIFF_MAX_BYTES;
+    truncate_to(&mut patches, PROMPT_DIFF_MAX_BYTES);
+
+    let mut text = String::from("\n## Changed files\n\n");
+    if diff.files.is_empty() {
+        text.push_str("No changes.\n");
+        return Ok(text);
+    }
+    text.push_str(&stat);
+    let _ = write!(text, "\n## Diff\n\n```diff\n{}```\n", patches);
+    if truncated {
+        text.push_str("\nThe diff was truncated; use `get_attempt_diff` to read specific files.\n");
+    }
+    Ok(text)
+}
+
+fn push_task(text: &mut String, task: &Task) {
+    let _ = write!(text, "\n## Task\n\n{}\n", task.title);
+    if let Some(description) = task.description.as_deref().filter(|d| !d.is_empty()) {
+        let _ = write!(text, "\n{}\n", description);
+    }
+}
+
+fn push_summary(text: &mut String, summary: Option<&str>) {
+    if let Some(summary) = summary {
+        let _ = write!(text, "\n## Coding agent summary\n\n{}\n", summary);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prompt_names_round_trip() {
+        for prompt in ForgePrompt::ALL {
+            assert_eq!(ForgePrompt::from_name(prompt.name()).unwrap(), prompt);
+        }
+        assert!(ForgePrompt::from_name("unknown").is_err());
+    }
+
+    #[test]
+    fn test_arguments_are_validated() {
+        let mut arguments = JsonObject::new();
+        arguments.insert("attempt_id".to_string(), "not-a-uuid".into());
+        arguments.insert("wish_id".to_string(), "  ".into());
+
+        assert!(uuid_argument(Some(&arguments), "attempt_id").is_err());
+        assert!(string_argument(Some(&arguments), "wish_id").is_err());
+        assert!(string_argument(None, "feature").is_err());
+
+        let id = Uuid::new_v4();
+        arguments.insert("attempt_id".to_string(), id.to_string().into());
+        assert_eq!(uuid_argument(Some(&arguments), "attempt_id").unwrap(), id);
+    }
+}
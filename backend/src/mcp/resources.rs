@@ -0,0 +1,349 @@
+//! Forge state published as MCP resources.
+//!
+//! Clients can read tasks, attempts and execution logs by URI and subscribe
+//! to them instead of polling `list_tasks`. Each subscription is backed by a
+//! watcher that polls the database and sends `notifications/resources/updated`
+//! when the resource's state changes.
+
+use std::{collections::HashMap, sync::Mutex, time::Duration};
+
+use rmcp::{
+    model::{
+        AnnotateAble, RawResource, RawResourceTemplate, Resource, ResourceContents,
+        ResourceTemplate, ResourceUpdatedNotificationParam,
+    },
+    service::Peer,
+    RoleServer,
+};
+use serde_json::json;
+use sqlx::SqlitePool;
+use thiserror::Error;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+use crate::{
+    models::{
+        execution_process::ExecutionProcess, project::Project, task::Task,
+        task_attempt::TaskAttempt,
+    },
+    routes::task_attempts::{normalize_process_logs, ProcessLogsResponse},
+};
+
+const URI_SCHEME: &str = "forge://";
+const JSON_MIME_TYPE: &str = "application/json";
+
+/// How often subscribed resources are checked for changes
+const WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Error)]
+pub enum ResourceError {
+    #[error("Unknown resource URI: {0}")]
+    InvalidUri(String),
+    #[error("Resource not found: {0}")]
+    NotFound(String),
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("Failed to read resource: {0}")]
+    Read(String),
+}
+
+/// A resource addressable by a `forge://` URI
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ForgeResource {
+    /// `forge://project/{id}/tasks`: the project's tasks with attempt status
+    ProjectTasks(Uuid),
+    /// `forge://task/{id}`: a task and its attempts
+    Task(Uuid),
+    /// `forge://attempt/{id}`: an attempt and its execution processes
+    Attempt(Uuid),
+    /// `forge://attempt/{id}/logs`: normalized logs of every process of an attempt
+    AttemptLogs(Uuid),
+}
+
+impl ForgeResource {
+    pub fn parse(uri: &str) -> Result<Self, ResourceError> {
+        let invalid = || ResourceError::InvalidUri(uri.to_string());
+        let path = uri.strip_prefix(URI_SCHEME).ok_or_else(invalid)?;
+        let segments: Vec<&str> = path.trim_end_matches('/').split('/').collect();
+        let id = |segment: &str| Uuid::parse_str(segment).map_err(|_| invalid());
+
+        match segments.as_slice() {
+            ["project", project_id, "tasks"] => Ok(Self::ProjectTasks(id(project_id)?)),
+            ["task", task_id] => Ok(Self::Task(id(task_id)?)),
+            ["attempt", attempt_id] => Ok(Self::Attempt(id(attempt_id)?)),
+            ["attempt", attempt_id, "logs"] => Ok(Self::AttemptLogs(id(attempt_id)?)),
+            _ => Err(invalid()),
+        }
+    }
+
+    pub fn uri(&self) -> String {
+        match self {
+            Self::ProjectTasks(id) => format!("{}project/{}/tasks", URI_SCHEME, id),
+            Self::Task(id) => format!("{}task/{}", URI_SCHEME, id),
+            Self::Attempt(id) => format!("{}attempt/{}", URI_SCHEME, id),
+            Self::AttemptLogs(id) => format!("{}attempt/{}/logs", URI_SCHEME, id),
+        }
+    }
+
+    /// The resource as pretty-printed JSON
+    pub async fn read(&self, pool: &SqlitePool) -> Result<ResourceContents, ResourceError> {
+        let value = match self {
+            Self::ProjectTasks(project_id) => {
+                if !Project::exists(pool, *project_id).await? {
+                    return Err(ResourceError::NotFound(self.uri()));
+                }
+                json!(Task::find_by_project_id_with_attempt_status(pool, *project_id).await?)
+            }
+            Self::Task(task_id) => {
+                let task = Task::find_by_id(pool, *task_id)
+                    .await?
+                    .ok_or_else(|| ResourceError::NotFound(self.uri()))?;
+                let attempts = TaskAttempt::find_by_task_id(pool, *task_id).await?;
+                json!({ "task": task, "attempts": attempts })
+            }
+            Self::Attempt(attempt_id) => {
+                let attempt = TaskAttempt::find_by_id(pool, *attempt_id)
+                    .await?
+                    .ok_or_else(|| ResourceError::NotFound(self.uri()))?;
+                let processes =
+                    ExecutionProcess::find_summaries_by_task_attempt_id(pool, *attempt_id).await?;
+                json!({ "attempt": attempt, "processes": processes })
+            }
+            Self::AttemptLogs(attempt_id) => {
+                if TaskAttempt::find_by_id(pool, *attempt_id).await?.is_none() {
+                    return Err(ResourceError::NotFound(self.uri()));
+                }
+                let mut logs = Vec::new();
+                for process in ExecutionProcess::find_by_task_attempt_id(pool, *attempt_id).await? {
+                    let normalized_conversation = normalize_process_logs(pool, &process).await;
+                    logs.push(ProcessLogsResponse {
+                        id: process.id,
+                        process_type: process.process_type,
+                        command: process.command,
+                        executor_type: process.executor_type,
+                        status: process.status,
+                        normalized_conversation,
+                    });
+                }
+                json!(logs)
+            }
+        };
+
+        let text =
+            serde_json::to_string_pretty(&value).map_err(|e| ResourceError::Read(e.to_string()))?;
+        Ok(ResourceContents::TextResourceContents {
+            uri: self.uri(),
+            mime_type: Some(JSON_MIME_TYPE.to_string()),
+            text,
+        })
+    }
+
+    /// A cheap summary of the resource's state; subscribers are notified when
+    /// it changes. Tasks change with their status or attempts, attempts when a
+    /// process starts or finishes, logs whenever a process writes output.
+    async fn fingerprint(&self, pool: &SqlitePool) -> Result<String, ResourceError> {
+        let value = match self {
+            Self::ProjectTasks(project_id) => {
+                let tasks = Task::find_by_project_id_with_attempt_status(pool, *project_id).await?;
+                json!(tasks
+                    .iter()
+                    .map(|t| json!([
+                        t.id,
+                        t.status,
+                        t.has_in_progress_attempt,
+                        t.has_merged_attempt,
+                        t.last_attempt_failed
+                    ]))
+                    .collect::<Vec<_>>())
+            }
+            Self::Task(task_id) => {
+                let task = Task::find_by_id(pool, *task_id).await?;
+                let attempts = TaskAttempt::find_by_task_id(pool, *task_id).await?;
+                json!([
+                    task.map(|t| t.status),
+                    attempts
+                        .iter()
+                        .map(|a| json!([a.id, a.merge_commit, a.pr_status]))
+                        .collect::<Vec<_>>()
+                ])
+            }
+            Self::Attempt(attempt_id) => {
+                let processes =
+                    ExecutionProcess::find_summaries_by_task_attempt_id(pool, *attempt_id).await?;
+                json!(processes
+                    .iter()
+                    .map(|p| json!([p.id, p.status, p.exit_code]))
+                    .collect::<Vec<_>>())
+            }
+            Self::AttemptLogs(attempt_id) => {
+                let processes =
+                    ExecutionProcess::find_summaries_by_task_attempt_id(pool, *attempt_id).await?;
+                json!(processes
+                    .iter()
+                    .map(|p| json!([p.id, p.status, p.updated_at]))
+                    .collect::<Vec<_>>())
+            }
+        };
+        Ok(value.to_string())
+    }
+}
+
+/// One `forge://project/{id}/tasks` resource per project
+pub async fn list_resources(pool: &SqlitePool) -> Result<Vec<Resource>, ResourceError> {
+    Ok(Project::find_all(pool)
+        .await?
+        .into_iter()
+        .map(|project| {
+            let mut resource = RawResource::new(
+                ForgeResource::ProjectTasks(project.id).uri(),
+                format!("{} tasks", project.name),
+            );
+            resource.description = Some(format!(
+                "Tasks of project '{}' with their attempt status",
+                project.name
+            ));
+            resource.mime_type = Some(JSON_MIME_TYPE.to_string());
+            resource.no_annotation()
+        })
+        .collect())
+}
+
+pub fn resource_templates() -> Vec<ResourceTemplate> {
+    [
+        (
+            "forge://project/{project_id}/tasks",
+            "project-tasks",
+            "Tasks of a project with their attempt status",
+        ),
+        ("forge://task/{task_id}", "task", "A task and its attempts"),
+        (
+            "forge://attempt/{attempt_id}",
+            "attempt",
+            "A task attempt and the status of its execution processes",
+        ),
+        (
+            "forge://attempt/{attempt_id}/logs",
+            "attempt-logs",
+            "Normalized logs of every execution process of a task attempt",
+        ),
+    ]
+    .into_iter()
+    .map(|(uri_template, name, description)| {
+        RawResourceTemplate {
+            uri_template: uri_template.to_string(),
+            name: name.to_string(),
+            description: Some(description.to_string()),
+            mime_type: Some(JSON_MIME_TYPE.to_string()),
+        }
+        .no_annotation()
+    })
+    .collect()
+}
+
+/// The resources one MCP connection is subscribed to. Dropping it (when the
+/// connection's server is dropped) stops all of its watchers.
+#[derive(Debug, Default)]
+pub struct ResourceSubscriptions {
+    watchers: Mutex<HashMap<String, CancellationToken>>,
+}
+
+impl ResourceSubscriptions {
+    /// Watch `resource` and notify `peer` whenever it changes. Subscribing
+    /// to a resource twice keeps the existing watcher.
+    pub fn subscribe(&self, pool: SqlitePool, resource: ForgeResource, peer: Peer<RoleServer>) {
+        let uri = resource.uri();
+        let mut watchers = self.watchers.lock().unwrap();
+        if watchers.contains_key(&uri) {
+            return;
+        }
+        let token = CancellationToken::new();
+        watchers.insert(uri.clone(), token.clone());
+        tokio::spawn(watch_resource(pool, resource, uri, peer, token));
+    }
+
+    /// Returns whether there was a subscription to remove
+    pub fn unsubscribe(&self, uri: &str) -> bool {
+        match self.watchers.lock().unwrap().remove(uri) {
+            Some(token) => {
+                token.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl Drop for ResourceSubscriptions {
+    fn drop(&mut self) {
+        if let Ok(watchers) = self.watchers.get_mut() {
+            for token in watchers.values() {
+                token.cancel();
+            }
+        }
+    }
+}
+
+async fn watch_resource(
+    pool: SqlitePool,
+    resource: ForgeResource,
+    uri: String,
+    peer: Peer<RoleServer>,
+    token: CancellationToken,
+) {
+    let mut last = resource.fingerprint(&pool).await.ok();
+    loop {
+        tokio::select! {
+            _ = token.cancelled() => return,
+            _ = tokio::time::sleep(WATCH_INTERVAL) => {}
+        }
+
+        let current = match resource.fingerprint(&pool).await {
+            Ok(fingerprint) => Some(fingerprint),
+            Err(e) => {
+                tracing::warn!("Failed to check MCP resource {}: {}", uri, e);
+                continue;
+            }
+        };
+        if current == last {
+            continue;
+        }
+        last = current;
+
+        if let Err(e) = peer
+            .notify_resource_updated(ResourceUpdatedNotificationParam { uri: uri.clone() })
+            .await
+        {
+            // The client went away; nobody is left to notify
+            tracing::debug!("Stopping MCP resource watcher for {}: {}", uri, e);
+            token.cancel();
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resource_uris_round_trip() {
+        let id = Uuid::new_v4();
+        for resource in [
+            ForgeResource::ProjectTasks(id),
+            ForgeResource::Task(id),
+            ForgeResource::Attempt(id),
+            ForgeResource::AttemptLogs(id),
+        ] {
+            assert_eq!(ForgeResource::parse(&resource.uri()).unwrap(), resource);
+        }
+    }
+
+    #[test]
+    fn test_invalid_resource_uris() {
+        let id = Uuid::new_v4();
+        assert!(ForgeResource::parse(&format!("file://task/{}", id)).is_err());
+        assert!(ForgeResource::parse("forge://task/not-a-uuid").is_err());
+        assert!(ForgeResource::parse(&format!("forge://project/{}", id)).is_err());
+        assert!(ForgeResource::parse(&format!("forge://attempt/{}/diff", id)).is_err());
+    }
+}
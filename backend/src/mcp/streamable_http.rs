@@ -0,0 +1,25 @@
+//! The MCP server mounted in the main app over the streamable HTTP transport.
+//!
+//! Unlike the standalone binary, this server shares the backend's `AppState`,
+//! so attempts started through MCP are run and monitored by the same execution
+//! monitor as those started from the UI.
+
+use std::sync::Arc;
+
+use rmcp::transport::streamable_http_server::{
+    session::local::LocalSessionManager, StreamableHttpServerConfig, StreamableHttpService,
+};
+
+use crate::{app_state::AppState, mcp::task_server::TaskServer};
+
+/// Tower service for `/mcp`. Requests must already have passed
+/// `auth_middleware`; tool calls run as the user it resolved.
+pub fn mcp_service(app_state: AppState) -> StreamableHttpService<TaskServer, LocalSessionManager> {
+    // Every MCP session gets its own server, and with it its own subscriptions
+    let server = TaskServer::with_app_state(app_state);
+    StreamableHttpService::new(
+        move || Ok(server.clone()),
+        Arc::new(LocalSessionManager::default()),
+        StreamableHttpServerConfig::default(),
+    )
+}
@@ -1,15 +1,23 @@
 use std::future::Future;
 use std::sync::Arc;
-use tokio::sync::RwLock;
-use std::collections::HashMap;
 use tokio::task_local;
 
+use axum::http::request::Parts;
 use rmcp::{
-    handler::server::tool::{Parameters, ToolRouter},
+    handler::server::{
+        tool::{Parameters, ToolCallContext, ToolRouter},
+        wrapper::Json,
+    },
     model::{
-        CallToolResult, Content, Implementation, ProtocolVersion, ServerCapabilities, ServerInfo,
+        CallToolRequestParam, CallToolResult, Content, GetPromptRequestParam, GetPromptResult,
+        Implementation, ListPromptsResult, ListResourceTemplatesResult, ListResourcesResult,
+        ListToolsResult, PaginatedRequestParam, ProtocolVersion, ReadResourceRequestParam,
+        ReadResourceResult, ServerCapabilities, ServerInfo, SubscribeRequestParam,
+        UnsubscribeRequestParam,
     },
-    schemars, tool, tool_handler, tool_router, ErrorData as RmcpError, ServerHandler,
+    schemars,
+    service::RequestContext,
+    tool, tool_router, ErrorData as RmcpError, RoleServer, ServerHandler,
 };
 use serde::{Deserialize, Serialize};
 use serde_json;
@@ -17,16 +25,62 @@ use sqlx::SqlitePool;
 use uuid::Uuid;
 
 use crate::models::{
+    execution_process::{ExecutionProcess, ExecutionProcessStatus, ExecutionProcessType},
     project::Project,
     task::{CreateTask, Task, TaskStatus},
-    user::User,
-    user_session::{SessionType, UserSession},
+    task_attempt::{
+        CreatePrParams, CreateTaskAttempt, DiffChunk, DiffChunkType, ExecutionState, TaskAttempt,
+        TaskAttemptState,
+    },
+    webhook::WebhookEvent,
+};
+use crate::app_state::AppState;
+use crate::auth::{JwtConfig, UserContext};
+use crate::executor::{ExecutorConfig, NormalizedEntry};
+use crate::mcp::auth::{authenticate_mcp_token, request_bearer_token, McpAuthError};
+use crate::mcp::prompts::{self, ForgePrompt, PromptError};
+use crate::mcp::resources::{self, ForgeResource, ResourceError, ResourceSubscriptions};
+use crate::security::audit_logger::{
+    AuditEventType, AuditLogger, AuditResult, AuditSeverity, CreateAuditEvent,
+};
+use crate::routes::task_attempts::normalize_process_logs;
+use crate::services::task_plan::{
+    create_planned_tasks, preview_plan, PlannedTask, TaskPlanError, TaskPlanFormat,
 };
-use crate::auth::{validate_jwt_token, JwtConfig};
+use crate::services::{
+    forge::{self, ForgeError, ForgeKind},
+    github_issues, webhooks, ProcessService,
+};
+
+/// Header naming the attempt whose coding agent makes a request to `/mcp`,
+/// sent by the MCP config Forge injects into agents
+pub const FORGE_ATTEMPT_HEADER: &str = "x-forge-attempt-id";
 
-// Task-local storage for request context (workaround for rmcp middleware limitation)
+// The user a tool call runs as and the attempt whose coding agent made it, set
+// by `call_tool` around the tool router
 task_local! {
-    pub static REQUEST_CONTEXT: Option<String>;
+    static CURRENT_USER: UserContext;
+    static CALLING_ATTEMPT: Option<Uuid>;
+}
+
+/// Tools that change state; every call to them is audit-logged
+const MUTATING_TOOLS: &[&str] = &[
+    "create_task",
+    "create_tasks_from_plan",
+    "update_task",
+    "delete_task",
+    "start_attempt",
+    "create_subtask",
+    "stop_attempt",
+    "send_follow_up",
+    "rebase_attempt",
+    "merge_attempt",
+    "create_pull_request",
+];
+
+/// ID of the user the current tool call runs as
+fn current_user_id() -> Option<Uuid> {
+    CURRENT_USER.try_with(|context| context.user.id).ok()
 }
 
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
@@ -48,6 +102,31 @@ pub struct CreateTaskResponse {
     pub message: String,
 }
 
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct CreateTasksFromPlanRequest {
+    #[schemars(description = "The ID of the project to create the tasks in")]
+    pub project_id: String,
+    #[schemars(description = "The plan. Markdown: every '## ' heading is a task, optionally followed by 'Key:', 'Executor:', 'Wish:' and 'Depends on:' lines, then its description. YAML: 'wish_id' and a 'tasks' list with 'title', 'key', 'description', 'executor', 'wish_id' and 'depends_on'. Dependencies name a task of the plan by key or title, or an existing task by ID")]
+    pub plan: String,
+    #[schemars(description = "'markdown' (default) or 'yaml'")]
+    pub format: Option<String>,
+    #[schemars(description = "Wish identifier for tasks the plan doesn't assign one")]
+    pub wish_id: Option<String>,
+    #[schemars(description = "Only validate the plan and return the tasks it would create")]
+    pub dry_run: Option<bool>,
+}
+
+#[derive(Debug, Serialize, schemars::JsonSchema)]
+pub struct CreateTasksFromPlanResponse {
+    pub success: bool,
+    pub dry_run: bool,
+    #[schemars(with = "serde_json::Value")]
+    pub planned: Vec<PlannedTask>,
+    #[schemars(description = "IDs of the created tasks, in the order of `planned`")]
+    pub task_ids: Vec<String>,
+    pub message: String,
+}
+
 #[derive(Debug, Serialize, schemars::JsonSchema)]
 pub struct ProjectSummary {
     #[schemars(description = "The unique identifier of the project")]
@@ -209,147 +288,731 @@ pub struct GetTaskResponse {
     pub project_name: Option<String>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct StartAttemptRequest {
+    #[schemars(description = "The ID of the task to start an attempt for")]
+    pub task_id: String,
+    #[schemars(
+        description = "Optional executor: 'claude', 'claude-plan', 'amp', 'gemini', 'charm-opencode', 'claude-code-router', 'sst-opencode', 'opencode-ai' or 'echo' (default: the configured executor)"
+    )]
+    pub executor: Option<String>,
+    #[schemars(description = "Optional branch to base the attempt on (default: the repository's current branch)")]
+    pub base_branch: Option<String>,
+}
+
+#[derive(Debug, Serialize, schemars::JsonSchema)]
+pub struct StartAttemptResponse {
+    pub success: bool,
+    pub attempt_id: String,
+    pub branch: String,
+    pub base_branch: String,
+    pub executor: Option<String>,
+    pub message: String,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct CreateSubtaskRequest {
+    #[schemars(description = "The title of the sub-task")]
+    pub title: String,
+    #[schemars(description = "Instructions for the agent working on the sub-task")]
+    pub description: Option<String>,
+    #[schemars(
+        description = "The attempt spawning the sub-task (default: the attempt this MCP server was launched for)"
+    )]
+    pub parent_attempt_id: Option<String>,
+    #[schemars(description = "Optional executor for the sub-task (default: the configured executor)")]
+    pub executor: Option<String>,
+    #[schemars(
+        description = "Start an attempt for the sub-task right away, based on the parent attempt's branch (default: false)"
+    )]
+    pub start: Option<bool>,
+}
+
+#[derive(Debug, Serialize, schemars::JsonSchema)]
+pub struct CreateSubtaskResponse {
+    pub success: bool,
+    pub task_id: String,
+    pub parent_attempt_id: String,
+    #[schemars(description = "The sub-task's attempt when `start` was set")]
+    pub attempt_id: Option<String>,
+    pub branch: Option<String>,
+    pub base_branch: String,
+    pub message: String,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct WaitForSubtasksRequest {
+    #[schemars(
+        description = "The attempt whose sub-tasks to wait for (default: the attempt this MCP server was launched for)"
+    )]
+    pub parent_attempt_id: Option<String>,
+    #[schemars(description = "Maximum time to wait in seconds (default: 600, max: 3600)")]
+    pub timeout_seconds: Option<u64>,
+}
+
+#[derive(Debug, Serialize, schemars::JsonSchema)]
+pub struct SubtaskStatus {
+    pub task_id: String,
+    pub title: String,
+    pub status: String,
+    #[schemars(description = "The sub-task's latest attempt, if it was started")]
+    pub attempt_id: Option<String>,
+    pub branch: Option<String>,
+    #[schemars(description = "True once the task is done or cancelled, or its latest attempt has stopped running")]
+    pub finished: bool,
+}
+
+#[derive(Debug, Serialize, schemars::JsonSchema)]
+pub struct WaitForSubtasksResponse {
+    pub success: bool,
+    pub parent_attempt_id: String,
+    pub all_finished: bool,
+    pub timed_out: bool,
+    pub waited_seconds: u64,
+    pub subtasks: Vec<SubtaskStatus>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct AttemptRequest {
+    #[schemars(description = "The ID of the task attempt")]
+    pub attempt_id: String,
+}
+
+#[derive(Debug, Serialize, schemars::JsonSchema)]
+pub struct StopAttemptResponse {
+    pub success: bool,
+    pub attempt_id: String,
+    pub stopped_processes: usize,
+    pub message: String,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct SendFollowUpRequest {
+    #[schemars(description = "The ID of the task attempt to continue")]
+    pub attempt_id: String,
+    #[schemars(description = "Instructions for the coding agent, sent in the same session")]
+    pub prompt: String,
+}
+
+#[derive(Debug, Serialize, schemars::JsonSchema)]
+pub struct SendFollowUpResponse {
+    pub success: bool,
+    pub attempt_id: String,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize, schemars::JsonSchema)]
+pub struct AttemptStatusResponse {
+    pub success: bool,
+    pub attempt_id: String,
+    pub task_id: String,
+    #[schemars(with = "serde_json::Value")]
+    pub state: TaskAttemptState,
+    #[schemars(description = "True once setup, coding agent and follow-ups have all stopped running")]
+    pub finished: bool,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct WaitForAttemptRequest {
+    #[schemars(description = "The ID of the task attempt to wait for")]
+    pub attempt_id: String,
+    #[schemars(description = "Maximum time to wait in seconds (default: 600, max: 3600)")]
+    pub timeout_seconds: Option<u64>,
+}
+
+#[derive(Debug, Serialize, schemars::JsonSchema)]
+pub struct WaitForAttemptResponse {
+    pub success: bool,
+    pub attempt_id: String,
+    pub finished: bool,
+    pub timed_out: bool,
+    pub waited_seconds: u64,
+    #[schemars(with = "serde_json::Value")]
+    pub state: TaskAttemptState,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct GetAttemptDiffRequest {
+    #[schemars(description = "The ID of the task attempt")]
+    pub attempt_id: String,
+    #[schemars(description = "Optional path prefixes; only files under one of them are returned")]
+    pub paths: Option<Vec<String>>,
+    #[schemars(description = "Maximum total size of the returned patches in bytes (default: 100000)")]
+    pub max_bytes: Option<usize>,
+}
+
+#[derive(Debug, Serialize, schemars::JsonSchema)]
+pub struct AttemptFileDiff {
+    #[schemars(description = "Path of the file relative to the repository root")]
+    pub path: String,
+    pub additions: usize,
+    pub deletions: usize,
+    #[schemars(description = "Changed lines prefixed with '+' or '-', with three lines of context")]
+    pub patch: String,
+    #[schemars(description = "Whether the patch was cut short by the size cap")]
+    pub truncated: bool,
+}
+
+#[derive(Debug, Serialize, schemars::JsonSchema)]
+pub struct AttemptDiffResponse {
+    pub attempt_id: String,
+    pub files: Vec<AttemptFileDiff>,
+    #[schemars(description = "Number of changed files matching the filter, including any left out by the size cap")]
+    pub total_files: usize,
+    pub truncated: bool,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct GetAttemptSummaryRequest {
+    #[schemars(description = "The ID of the task attempt")]
+    pub attempt_id: String,
+    #[schemars(description = "Number of trailing conversation entries to return (default: 20, max: 200)")]
+    pub max_entries: Option<usize>,
+}
+
+#[derive(Debug, Serialize, schemars::JsonSchema)]
+pub struct ConversationEntry {
+    #[schemars(description = "user_message, assistant_message, tool_use, system_message, error_message or thinking")]
+    pub entry_type: String,
+    pub content: String,
+    pub timestamp: Option<String>,
+}
+
+#[derive(Debug, Serialize, schemars::JsonSchema)]
+pub struct AttemptSummaryResponse {
+    pub attempt_id: String,
+    #[schemars(description = "Executor of the latest coding agent run")]
+    pub executor: Option<String>,
+    #[schemars(description = "Status of the latest coding agent run: running, completed, failed or killed")]
+    pub status: Option<String>,
+    #[schemars(description = "Prompt of the latest coding agent run")]
+    pub prompt: Option<String>,
+    #[schemars(description = "The agent's final message, once it has finished")]
+    pub summary: Option<String>,
+    #[schemars(description = "The last entries of the agent's conversation, oldest first")]
+    pub entries: Vec<ConversationEntry>,
+}
+
+#[derive(Debug, Serialize, schemars::JsonSchema)]
+pub struct BranchStatusResponse {
+    pub attempt_id: String,
+    pub branch: String,
+    pub base_branch: String,
+    pub commits_ahead: usize,
+    pub commits_behind: usize,
+    pub is_behind: bool,
+    pub up_to_date: bool,
+    pub merged: bool,
+    pub has_uncommitted_changes: bool,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct RebaseAttemptRequest {
+    #[schemars(description = "The ID of the task attempt")]
+    pub attempt_id: String,
+    #[schemars(description = "Optional new base branch (default: the attempt's current base branch)")]
+    pub new_base_branch: Option<String>,
+}
+
+#[derive(Debug, Serialize, schemars::JsonSchema)]
+pub struct RebaseAttemptResponse {
+    pub attempt_id: String,
+    pub base_branch: String,
+    pub new_base_commit: String,
+}
+
+#[derive(Debug, Serialize, schemars::JsonSchema)]
+pub struct MergeAttemptResponse {
+    pub attempt_id: String,
+    pub merge_commit: String,
+    pub task_status: String,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct CreatePullRequestRequest {
+    #[schemars(description = "The ID of the task attempt")]
+    pub attempt_id: String,
+    #[schemars(description = "Title of the pull request")]
+    pub title: String,
+    #[schemars(description = "Optional pull request description")]
+    pub body: Option<String>,
+    #[schemars(description = "Optional branch to merge into (default: the attempt's base branch)")]
+    pub base_branch: Option<String>,
+}
+
+#[derive(Debug, Serialize, schemars::JsonSchema)]
+pub struct CreatePullRequestResponse {
+    pub attempt_id: String,
+    pub pr_url: String,
+    pub base_branch: String,
+}
+
+const DEFAULT_DIFF_MAX_BYTES: usize = 100_000;
+const DEFAULT_SUMMARY_ENTRIES: usize = 20;
+const MAX_SUMMARY_ENTRIES: usize = 200;
+const PATCH_CONTEXT_LINES: usize = 3;
+
+/// Render diff chunks as '+'/'-'/' ' prefixed lines, keeping only
+/// `PATCH_CONTEXT_LINES` of unchanged context around each change.
+/// Returns the patch with its addition and deletion counts.
+pub(crate) fn render_patch(chunks: &[DiffChunk]) -> (String, usize, usize) {
+    let lines: Vec<(char, &str)> = chunks
+        .iter()
+        .flat_map(|chunk| {
+            let prefix = match chunk.chunk_type {
+                DiffChunkType::Equal => ' ',
+                DiffChunkType::Insert => '+',
+                DiffChunkType::Delete => '-',
+            };
+            chunk.content.lines().map(move |line| (prefix, line))
+        })
+        .collect();
+
+    let changed: Vec<usize> = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, (prefix, _))| *prefix != ' ')
+        .map(|(index, _)| index)
+        .collect();
+    let additions = lines.iter().filter(|(prefix, _)| *prefix == '+').count();
+    let deletions = changed.len() - additions;
+
+    let mut patch = String::new();
+    let mut last_written: Option<usize> = None;
+    for (index, (prefix, line)) in lines.iter().enumerate() {
+        let near_change = changed
+            .iter()
+            .any(|&c| index + PATCH_CONTEXT_LINES >= c && index <= c + PATCH_CONTEXT_LINES);
+        if !near_change {
+            continue;
+        }
+        if last_written.is_some_and(|last| index > last + 1) || (last_written.is_none() && index > 0) {
+            patch.push_str("@@\n");
+        }
+        patch.push(*prefix);
+        patch.push_str(line);
+        patch.push('\n');
+        last_written = Some(index);
+    }
+    (patch, additions, deletions)
+}
+
+/// Cut `text` to at most `max_bytes` on a char boundary
+pub(crate) fn truncate_to(text: &mut String, max_bytes: usize) {
+    if text.len() > max_bytes {
+        let mut end = max_bytes;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        text.truncate(end);
+    }
+}
+
+fn conversation_entry(entry: NormalizedEntry) -> ConversationEntry {
+    let entry_type = serde_json::to_value(&entry.entry_type)
+        .ok()
+        .and_then(|value| value.get("type").and_then(|t| t.as_str()).map(str::to_string))
+        .unwrap_or_else(|| "unknown".to_string());
+    ConversationEntry {
+        entry_type,
+        content: entry.content,
+        timestamp: entry.timestamp,
+    }
+}
+
+const DEFAULT_WAIT_TIMEOUT_SECS: u64 = 600;
+const MAX_WAIT_TIMEOUT_SECS: u64 = 3600;
+const WAIT_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(2);
+
+fn tool_error(error: serde_json::Value) -> CallToolResult {
+    CallToolResult::error(vec![Content::text(
+        serde_json::to_string_pretty(&error).unwrap_or_else(|_| error.to_string()),
+    )])
+}
+
+fn tool_success<T: Serialize>(response: &T) -> CallToolResult {
+    CallToolResult::success(vec![Content::text(
+        serde_json::to_string_pretty(response).unwrap_or_else(|_| "{}".to_string()),
+    )])
+}
+
+#[derive(Debug)]
 pub struct TaskServer {
     pub pool: SqlitePool,
+    /// Present when the server runs inside the Forge backend; attempt tools
+    /// need it to start and track processes
+    app_state: Option<AppState>,
+    jwt_config: Arc<JwtConfig>,
+    audit_logger: AuditLogger,
+    /// Token tool calls are authenticated with when the transport carries no
+    /// HTTP request (STDIO)
+    stdio_token: Option<String>,
+    /// The attempt whose coding agent launched this server, from
+    /// `FORGE_ATTEMPT_ID`; the default parent of sub-tasks on STDIO. HTTP
+    /// requests name theirs with `FORGE_ATTEMPT_HEADER`.
+    current_attempt: Option<Uuid>,
+    /// Resource subscriptions of the connection this server instance serves
+    subscriptions: Arc<ResourceSubscriptions>,
     tool_router: ToolRouter<TaskServer>,
 }
 
-// Simple token store for MCP OAuth tokens (can be enhanced with rmcp auth later)
-#[derive(Debug, Clone)]
-#[allow(dead_code)] // OAuth token storage for future MCP authentication
-pub struct McpToken {
-    #[allow(dead_code)]
-    pub user_id: Uuid,
-    #[allow(dead_code)]
-    pub scopes: Vec<String>,
-    #[allow(dead_code)]
-    pub expires_at: chrono::DateTime<chrono::Utc>,
+/// Transports clone the server for every connection; each clone starts with
+/// no resource subscriptions of its own
+impl Clone for TaskServer {
+    fn clone(&self) -> Self {
+        Self {
+            pool: self.pool.clone(),
+            app_state: self.app_state.clone(),
+            jwt_config: self.jwt_config.clone(),
+            audit_logger: self.audit_logger.clone(),
+            stdio_token: self.stdio_token.clone(),
+            current_attempt: self.current_attempt,
+            subscriptions: Arc::new(ResourceSubscriptions::default()),
+            tool_router: self.tool_router.clone(),
+        }
+    }
 }
 
-#[derive(Debug, Clone)]
-#[allow(dead_code)] // OAuth-enabled MCP server for future authentication
-pub struct AuthenticatedTaskServer {
-    #[allow(dead_code)]
-    pub pool: SqlitePool,
-    #[allow(dead_code)]
-    pub token_store: Arc<RwLock<HashMap<String, McpToken>>>,
-    tool_router: ToolRouter<AuthenticatedTaskServer>,
+fn resource_error(error: ResourceError) -> RmcpError {
+    match error {
+        ResourceError::InvalidUri(_) => RmcpError::invalid_params(error.to_string(), None),
+        ResourceError::NotFound(_) => RmcpError::resource_not_found(error.to_string(), None),
+        ResourceError::Database(_) | ResourceError::Read(_) => {
+            RmcpError::internal_error(error.to_string(), None)
+        }
+    }
+}
+
+fn prompt_error(error: PromptError) -> RmcpError {
+    match error {
+        PromptError::UnknownPrompt(_) | PromptError::InvalidArgument(_) => {
+            RmcpError::invalid_params(error.to_string(), None)
+        }
+        PromptError::NotFound(_) => RmcpError::resource_not_found(error.to_string(), None),
+        PromptError::Database(_) | PromptError::Diff(_) => {
+            RmcpError::internal_error(error.to_string(), None)
+        }
+    }
 }
 
 impl TaskServer {
     #[allow(dead_code)]
     pub fn new(pool: SqlitePool) -> Self {
         Self {
+            audit_logger: AuditLogger::new(pool.clone()),
             pool,
+            app_state: None,
+            jwt_config: Arc::new(JwtConfig::from_config()),
+            stdio_token: None,
+            current_attempt: None,
+            subscriptions: Arc::new(ResourceSubscriptions::default()),
             tool_router: Self::tool_router(),
         }
     }
 
-    /// Extract user context from MCP request (if authenticated)
-    /// Uses task-local storage as workaround for rmcp middleware limitations
-    async fn get_user_context(&self, _request_context: Option<&str>) -> Option<(User, UserSession)> {
-        // Try to get Bearer token from task-local storage (SSE middleware injection)
-        if let Some(bearer_token) = REQUEST_CONTEXT.try_with(|ctx| ctx.clone()).unwrap_or(None) {
-            if let Some(token) = bearer_token.strip_prefix("Bearer ") {
-                // Use JWT validation for compatibility with existing OAuth endpoints
-                let jwt_config = JwtConfig::default();
-                if let Ok(claims) = validate_jwt_token(token, &jwt_config) {
-                    if let Ok(Some(session)) = UserSession::find_by_token_hash(&self.pool, &crate::auth::hash_token(token)).await {
-                        if let Ok(claims_uuid) = claims.sub.parse::<Uuid>() {
-                            if let Ok(Some(user)) = User::find_by_id(&self.pool, claims_uuid).await {
-                                if user.is_whitelisted && session.session_type == SessionType::Mcp {
-                                    return Some((user, session));
-                                }
-                            }
-                        }
-                    }
+    /// A server sharing the backend's state, so attempts it starts are run and
+    /// monitored exactly like those started through the REST API
+    pub fn with_app_state(app_state: AppState) -> Self {
+        Self {
+            pool: app_state.db_pool.clone(),
+            jwt_config: app_state.get_jwt_config().clone(),
+            audit_logger: app_state.audit_logger().clone(),
+            app_state: Some(app_state),
+            stdio_token: None,
+            current_attempt: None,
+            subscriptions: Arc::new(ResourceSubscriptions::default()),
+            tool_router: Self::tool_router(),
+        }
+    }
+
+    /// Run STDIO tool calls as the owner of this MCP access token
+    #[allow(dead_code)]
+    pub fn with_stdio_token(mut self, token: Option<String>) -> Self {
+        self.stdio_token = token;
+        self
+    }
+
+    /// Spawn sub-tasks from this attempt unless a tool names another one
+    #[allow(dead_code)]
+    pub fn with_current_attempt(mut self, attempt_id: Option<Uuid>) -> Self {
+        self.current_attempt = attempt_id;
+        self
+    }
+
+    /// Resolve the user a request runs as: the user `auth_middleware` already
+    /// resolved for `/mcp`, the bearer token of the HTTP request, or the
+    /// configured token on STDIO. HTTP requests never fall back to the STDIO
+    /// token.
+    async fn authenticate(
+        &self,
+        context: &RequestContext<RoleServer>,
+    ) -> Result<UserContext, McpAuthError> {
+        let token = match context.extensions.get::<Parts>() {
+            Some(parts) => {
+                if let Some(user_context) = parts.extensions.get::<UserContext>() {
+                    return Ok(user_context.clone());
                 }
+                request_bearer_token(parts)
             }
+            None => self.stdio_token.as_deref(),
         }
-        
-        // Fallback: MCP tools work without user attribution (STDIO mode compatibility)
-        // Note: rmcp 0.3.2 architectural limitation prevents direct OAuth integration
-        None
+        .ok_or(McpAuthError::MissingToken)?;
+        authenticate_mcp_token(&self.pool, &self.jwt_config, token).await
     }
-}
 
-impl AuthenticatedTaskServer {
-    #[allow(dead_code)] // Constructor for OAuth-enabled MCP server
-    pub fn new(pool: SqlitePool) -> Self {
-        Self {
-            pool,
-            token_store: Arc::new(RwLock::new(HashMap::new())),
-            tool_router: Self::tool_router(),
+    /// `authenticate` for handlers that return protocol errors
+    async fn require_user(
+        &self,
+        context: &RequestContext<RoleServer>,
+    ) -> Result<UserContext, RmcpError> {
+        match self.authenticate(context).await {
+            Ok(user_context) => Ok(user_context),
+            Err(McpAuthError::Database(e)) => Err(RmcpError::internal_error(
+                format!("Failed to authenticate: {}", e),
+                None,
+            )),
+            Err(e) => {
+                self.audit_tool_call(None, "authenticate", None, AuditResult::Blocked)
+                    .await;
+                Err(RmcpError::invalid_request(e.to_string(), None))
+            }
         }
     }
 
-    /// Extract user context from OAuth-authenticated MCP request
-    /// Uses task-local storage as workaround for rmcp middleware limitations
-    pub async fn get_user_context(&self, bearer_token: Option<&str>) -> Option<(User, UserSession)> {
-        // Try task-local storage first (SSE middleware injection)
-        let token_string = REQUEST_CONTEXT.try_with(|ctx| ctx.clone()).unwrap_or(None);
-        let token = if let Some(token) = bearer_token {
-            token
-        } else if let Some(ref bearer_token) = token_string {
-            if let Some(token) = bearer_token.strip_prefix("Bearer ") {
-                token
-            } else {
-                return None;
-            }
-        } else {
-            return None;
+    async fn audit_tool_call(
+        &self,
+        user_id: Option<Uuid>,
+        tool: &str,
+        arguments: Option<serde_json::Value>,
+        result: AuditResult,
+    ) {
+        let (event_type, severity) = match (&result, user_id) {
+            (_, None) => (AuditEventType::Authentication, AuditSeverity::Medium),
+            (AuditResult::Success, _) => (AuditEventType::DataAccess, AuditSeverity::Low),
+            _ => (AuditEventType::DataAccess, AuditSeverity::Medium),
         };
-        
-        // First try to get user from our token store (MCP managed tokens)
+        if let Err(e) = self
+            .audit_logger
+            .log_event(CreateAuditEvent {
+                event_type,
+                user_id,
+                ip_address: None,
+                user_agent: Some("mcp".to_string()),
+                resource: "mcp_tool".to_string(),
+                action: tool.to_string(),
+                result,
+                details: arguments,
+                severity,
+            })
+            .await
         {
-            let store = self.token_store.read().await;
-            if let Some(mcp_token) = store.get(token) {
-                if mcp_token.expires_at > chrono::Utc::now() {
-                    if let Ok(Some(user)) = User::find_by_id(&self.pool, mcp_token.user_id).await {
-                        if let Ok(Some(session)) = UserSession::find_by_token_hash(&self.pool, &crate::auth::hash_token(token)).await {
-                            if user.is_whitelisted && session.session_type == SessionType::Mcp {
-                                return Some((user, session));
-                            }
-                        }
-                    }
-                }
-            }
+            tracing::error!("Failed to audit MCP tool call {}: {}", tool, e);
         }
-        
-        // Fallback to JWT validation (for compatibility with existing OAuth endpoints)
-        let jwt_config = JwtConfig::default();
-        if let Ok(claims) = validate_jwt_token(token, &jwt_config) {
-            if let Ok(Some(session)) = UserSession::find_by_token_hash(&self.pool, &crate::auth::hash_token(token)).await {
-                if let Ok(claims_uuid) = claims.sub.parse::<Uuid>() {
-                    if let Ok(Some(user)) = User::find_by_id(&self.pool, claims_uuid).await {
-                        if user.is_whitelisted && session.session_type == SessionType::Mcp {
-                            return Some((user, session));
-                        }
-                    }
-                }
+    }
+
+    fn require_app_state(&self) -> Result<&AppState, CallToolResult> {
+        self.app_state.as_ref().ok_or_else(|| {
+            tool_error(serde_json::json!({
+                "success": false,
+                "error": "Attempt tools are only available on the MCP server mounted at /mcp in the Forge backend"
+            }))
+        })
+    }
+
+    /// Load an attempt and its task from a tool's `attempt_id` argument
+    async fn find_attempt(&self, attempt_id: &str) -> Result<(TaskAttempt, Task), RmcpError> {
+        let attempt_uuid = Uuid::parse_str(attempt_id).map_err(|_| {
+            RmcpError::invalid_params("Invalid attempt ID format. Must be a valid UUID.", None)
+        })?;
+        let database_error =
+            |e: sqlx::Error| RmcpError::internal_error(format!("Failed to load task attempt: {}", e), None);
+
+        let attempt = TaskAttempt::find_by_id(&self.pool, attempt_uuid)
+            .await
+            .map_err(database_error)?
+            .ok_or_else(|| RmcpError::invalid_params("Task attempt not found", None))?;
+        let task = Task::find_by_id(&self.pool, attempt.task_id)
+            .await
+            .map_err(database_error)?
+            .ok_or_else(|| RmcpError::invalid_params("Task attempt not found", None))?;
+        Ok((attempt, task))
+    }
+
+    /// `find_attempt` for tools that report errors as tool results
+    async fn load_attempt(&self, attempt_id: &str) -> Result<(TaskAttempt, Task), CallToolResult> {
+        self.find_attempt(attempt_id).await.map_err(|e| {
+            tool_error(serde_json::json!({
+                "success": false,
+                "error": e.message,
+                "attempt_id": attempt_id
+            }))
+        })
+    }
+
+    /// Create an attempt for a task and start its setup script and coding agent
+    async fn launch_attempt(
+        &self,
+        app_state: &AppState,
+        task: &Task,
+        executor: Option<String>,
+        base_branch: Option<String>,
+    ) -> Result<TaskAttempt, CallToolResult> {
+        let task_id = task.id.to_string();
+        let create_attempt = CreateTaskAttempt {
+            executor: executor.clone(),
+            base_branch,
+            created_by: current_user_id(),
+        };
+
+        let attempt = match TaskAttempt::create(&self.pool, &create_attempt, task.id).await {
+            Ok(attempt) => attempt,
+            Err(e) => {
+                return Err(tool_error(serde_json::json!({
+                    "success": false,
+                    "error": "Failed to create task attempt",
+                    "details": e.to_string(),
+                    "task_id": task_id
+                })));
             }
+        };
+
+        app_state
+            .track_analytics_event(
+                "task_attempt_started",
+                Some(serde_json::json!({
+                    "task_id": task.id.to_string(),
+                    "executor_type": executor.as_deref().unwrap_or("default"),
+                    "attempt_id": attempt.id.to_string(),
+                    "source": "mcp",
+                })),
+            )
+            .await;
+
+        if let Err(e) = ProcessService::start_execution(
+            &self.pool,
+            app_state,
+            attempt.id,
+            task.id,
+            task.project_id,
+        )
+        .await
+        {
+            return Err(tool_error(serde_json::json!({
+                "success": false,
+                "error": "Task attempt was created but its execution failed to start",
+                "details": e.to_string(),
+                "attempt_id": attempt.id.to_string()
+            })));
+        }
+
+        Ok(attempt)
+    }
+
+    /// The attempt whose coding agent made a request: the one named by its
+    /// `FORGE_ATTEMPT_HEADER` over HTTP, or the one this server was launched
+    /// for on STDIO
+    fn calling_attempt(&self, context: &RequestContext<RoleServer>) -> Option<Uuid> {
+        match context.extensions.get::<Parts>() {
+            Some(parts) => parts
+                .headers
+                .get(FORGE_ATTEMPT_HEADER)
+                .and_then(|header| header.to_str().ok())
+                .and_then(|id| Uuid::parse_str(id).ok()),
+            None => self.current_attempt,
         }
-        
-        None
     }
 
-    /// Store OAuth access token with user context
-    #[allow(dead_code)] // OAuth token storage for future MCP authentication
-    pub async fn store_oauth_token(&self, token: &str, user_id: Uuid, scopes: Vec<String>) {
-        let mut store = self.token_store.write().await;
-        let mcp_token = McpToken {
-            user_id,
-            scopes,
-            expires_at: chrono::Utc::now() + chrono::Duration::days(UserSession::MCP_SESSION_DURATION_DAYS),
+    /// The attempt named by a sub-task tool, or the one whose coding agent
+    /// called it
+    async fn load_parent_attempt(
+        &self,
+        parent_attempt_id: Option<&str>,
+    ) -> Result<(TaskAttempt, Task), CallToolResult> {
+        let current_attempt = CALLING_ATTEMPT
+            .try_with(|attempt_id| *attempt_id)
+            .ok()
+            .flatten()
+            .map(|id| id.to_string());
+        let Some(attempt_id) = parent_attempt_id.or(current_attempt.as_deref()) else {
+            return Err(tool_error(serde_json::json!({
+                "success": false,
+                "error": "No parent attempt: pass `parent_attempt_id`, or call this tool from a coding agent started by Forge"
+            })));
+        };
+        self.load_attempt(attempt_id).await
+    }
+
+    async fn subtask_status(&self, task: &Task) -> Result<SubtaskStatus, CallToolResult> {
+        let latest_attempt = TaskAttempt::find_by_task_id(&self.pool, task.id)
+            .await
+            .map_err(|e| {
+                tool_error(serde_json::json!({
+                    "success": false,
+                    "error": "Failed to load sub-task attempts",
+                    "details": e.to_string(),
+                    "task_id": task.id.to_string()
+                }))
+            })?
+            .into_iter()
+            .next();
+
+        let closed = matches!(task.status, TaskStatus::Done | TaskStatus::Cancelled);
+        let finished = match &latest_attempt {
+            _ if closed => true,
+            Some(attempt) => self.attempt_status(attempt, task).await?.1,
+            None => false,
         };
-        store.insert(token.to_string(), mcp_token);
+
+        Ok(SubtaskStatus {
+            task_id: task.id.to_string(),
+            title: task.title.clone(),
+            status: task_status_to_string(&task.status),
+            attempt_id: latest_attempt.as_ref().map(|attempt| attempt.id.to_string()),
+            branch: latest_attempt.map(|attempt| attempt.branch),
+            finished,
+        })
     }
 
-    /// Get token store for external middleware use
-    #[allow(dead_code)] // Token store access for middleware
-    pub fn get_token_store(&self) -> Arc<RwLock<HashMap<String, McpToken>>> {
-        self.token_store.clone()
+    /// The attempt's execution state and whether it has stopped running.
+    /// Dev servers are ignored since they run until stopped.
+    async fn attempt_status(
+        &self,
+        attempt: &TaskAttempt,
+        task: &Task,
+    ) -> Result<(TaskAttemptState, bool), CallToolResult> {
+        let state = TaskAttempt::get_execution_state(&self.pool, attempt.id, task.id, task.project_id)
+            .await
+            .map_err(|e| {
+                tool_error(serde_json::json!({
+                    "success": false,
+                    "error": "Failed to get attempt status",
+                    "details": e.to_string(),
+                    "attempt_id": attempt.id.to_string()
+                }))
+            })?;
+        let processes = ExecutionProcess::find_by_task_attempt_id(&self.pool, attempt.id)
+            .await
+            .map_err(|e| {
+                tool_error(serde_json::json!({
+                    "success": false,
+                    "error": "Failed to load execution processes",
+                    "details": e.to_string(),
+                    "attempt_id": attempt.id.to_string()
+                }))
+            })?;
+
+        let still_running = processes.iter().any(|p| {
+            p.status == ExecutionProcessStatus::Running
+                && p.process_type != ExecutionProcessType::DevServer
+        });
+        let settled = !matches!(
+            state.execution_state,
+            ExecutionState::NotStarted
+                | ExecutionState::SetupRunning
+                | ExecutionState::SetupComplete
+                | ExecutionState::CodingAgentRunning
+        );
+        Ok((state, settled && !still_running))
     }
 }
 
@@ -411,9 +1074,7 @@ impl TaskServer {
             Ok(true) => {}
         }
 
-        // Extract user context if available (from OAuth authentication)
-        let user_context = self.get_user_context(None).await;
-        let created_by = user_context.as_ref().map(|(user, _)| user.id);
+        let created_by = current_user_id();
 
         let task_id = Uuid::new_v4();
         let create_task_data = CreateTask {
@@ -422,12 +1083,13 @@ impl TaskServer {
             description: Some(description.clone()),
             wish_id: wish_id.clone(),
             parent_task_attempt: None,
-            created_by, // Use authenticated user if available
+            created_by,
             assigned_to: None,
         };
 
         match Task::create(&self.pool, &create_task_data, task_id).await {
-            Ok(_task) => {
+            Ok(task) => {
+                webhooks::emit_task_created(&self.pool, &task).await;
                 let success_response = CreateTaskResponse {
                     success: true,
                     task_id: task_id.to_string(),
@@ -458,34 +1120,137 @@ impl TaskServer {
         }
     }
 
-    #[tool(description = "List all the available projects")]
-    async fn list_projects(&self) -> Result<CallToolResult, RmcpError> {
-        match Project::find_all(&self.pool).await {
-            Ok(projects) => {
-                let count = projects.len();
-                let project_summaries: Vec<ProjectSummary> = projects
-                    .into_iter()
-                    .map(|project| {
-                        let project_with_branch = project.with_branch_info();
-                        ProjectSummary {
-                            id: project_with_branch.id.to_string(),
-                            name: project_with_branch.name,
-                            git_repo_path: project_with_branch.git_repo_path,
-                            setup_script: project_with_branch.setup_script,
-                            dev_script: project_with_branch.dev_script,
-                            current_branch: project_with_branch.current_branch,
-                            created_at: project_with_branch.created_at.to_rfc3339(),
-                            updated_at: project_with_branch.updated_at.to_rfc3339(),
-                        }
-                    })
-                    .collect();
-
-                let response = ListProjectsResponse {
-                    success: true,
-                    projects: project_summaries,
-                    count,
-                };
-
+    #[tool(
+        description = "Create all tasks of a markdown or YAML plan at once, with their dependencies and preferred executors. The plan is validated as a whole and either every task is created or none. Use `dry_run` to preview. `project_id` and `plan` are required!"
+    )]
+    async fn create_tasks_from_plan(
+        &self,
+        Parameters(CreateTasksFromPlanRequest {
+            project_id,
+            plan,
+            format,
+            wish_id,
+            dry_run,
+        }): Parameters<CreateTasksFromPlanRequest>,
+    ) -> Result<CallToolResult, RmcpError> {
+        let project_uuid = match Uuid::parse_str(&project_id) {
+            Ok(uuid) => uuid,
+            Err(_) => {
+                return Ok(tool_error(serde_json::json!({
+                    "success": false,
+                    "error": "Invalid project ID format. Must be a valid UUID.",
+                    "project_id": project_id
+                })));
+            }
+        };
+        match Project::exists(&self.pool, project_uuid).await {
+            Ok(true) => {}
+            Ok(false) => {
+                return Ok(tool_error(serde_json::json!({
+                    "success": false,
+                    "error": "Project not found",
+                    "project_id": project_id
+                })));
+            }
+            Err(e) => {
+                return Ok(tool_error(serde_json::json!({
+                    "success": false,
+                    "error": "Failed to check project existence",
+                    "details": e.to_string(),
+                    "project_id": project_id
+                })));
+            }
+        }
+
+        let format = match format.as_deref().map(str::to_lowercase).as_deref() {
+            None | Some("markdown") | Some("md") => TaskPlanFormat::Markdown,
+            Some("yaml") | Some("yml") => TaskPlanFormat::Yaml,
+            Some(other) => {
+                return Ok(tool_error(serde_json::json!({
+                    "success": false,
+                    "error": "Invalid plan format. Valid values: 'markdown', 'yaml'",
+                    "format": other
+                })));
+            }
+        };
+
+        let planned =
+            match preview_plan(&self.pool, project_uuid, &plan, format, wish_id.as_deref()).await {
+                Ok(planned) => planned,
+                Err(TaskPlanError::Invalid(errors)) => {
+                    return Ok(tool_error(serde_json::json!({
+                        "success": false,
+                        "error": "Invalid plan",
+                        "details": errors
+                    })));
+                }
+                Err(e) => {
+                    return Ok(tool_error(serde_json::json!({
+                        "success": false,
+                        "error": e.to_string()
+                    })));
+                }
+            };
+
+        if dry_run.unwrap_or(false) {
+            let message = format!("The plan would create {} tasks", planned.len());
+            return Ok(tool_success(&CreateTasksFromPlanResponse {
+                success: true,
+                dry_run: true,
+                planned,
+                task_ids: Vec::new(),
+                message,
+            }));
+        }
+
+        match create_planned_tasks(&self.pool, project_uuid, &planned, current_user_id()).await {
+            Ok(created) => {
+                let message = format!("Created {} tasks", created.len());
+                Ok(tool_success(&CreateTasksFromPlanResponse {
+                    success: true,
+                    dry_run: false,
+                    planned,
+                    task_ids: created.iter().map(|task| task.id.to_string()).collect(),
+                    message,
+                }))
+            }
+            Err(e) => Ok(tool_error(serde_json::json!({
+                "success": false,
+                "error": "Failed to create tasks; none were created",
+                "details": e.to_string(),
+                "project_id": project_id
+            }))),
+        }
+    }
+
+    #[tool(description = "List all the available projects")]
+    async fn list_projects(&self) -> Result<CallToolResult, RmcpError> {
+        match Project::find_all(&self.pool).await {
+            Ok(projects) => {
+                let count = projects.len();
+                let project_summaries: Vec<ProjectSummary> = projects
+                    .into_iter()
+                    .map(|project| {
+                        let project_with_branch = project.with_branch_info();
+                        ProjectSummary {
+                            id: project_with_branch.id.to_string(),
+                            name: project_with_branch.name,
+                            git_repo_path: project_with_branch.git_repo_path,
+                            setup_script: project_with_branch.setup_script,
+                            dev_script: project_with_branch.dev_script,
+                            current_branch: project_with_branch.current_branch,
+                            created_at: project_with_branch.created_at.to_rfc3339(),
+                            updated_at: project_with_branch.updated_at.to_rfc3339(),
+                        }
+                    })
+                    .collect();
+
+                let response = ListProjectsResponse {
+                    success: true,
+                    projects: project_summaries,
+                    count,
+                };
+
                 Ok(CallToolResult::success(vec![Content::text(
                     serde_json::to_string_pretty(&response)
                         .unwrap_or_else(|_| "Failed to serialize projects".to_string()),
@@ -741,6 +1506,7 @@ impl TaskServer {
             }
         };
 
+        let previous_status = current_task.status.clone();
         let new_title = title.unwrap_or(current_task.title);
         let new_description = description.or(current_task.description);
         let new_status = status_enum.unwrap_or(current_task.status);
@@ -761,6 +1527,15 @@ impl TaskServer {
         .await
         {
             Ok(updated_task) => {
+                if updated_task.status != previous_status {
+                    webhooks::emit_task_status(
+                        &self.pool,
+                        updated_task.id,
+                        updated_task.project_id,
+                        updated_task.status.clone(),
+                    )
+                    .await;
+                }
                 let task_summary = TaskSummary {
                     id: updated_task.id.to_string(),
                     title: updated_task.title,
@@ -977,364 +1752,907 @@ impl TaskServer {
             }
         }
     }
-}
-
-#[tool_router]
-impl AuthenticatedTaskServer {
     #[tool(
-        description = "Create a new task/ticket in a project. Always pass the `project_id` of the project you want to create the task in - it is required!"
+        description = "Start a new execution attempt for a task: creates a git worktree, runs the project's setup script and then the coding agent. Returns the `attempt_id` to use with the other attempt tools. `task_id` is required!"
     )]
-    async fn create_task(
+    async fn start_attempt(
         &self,
-        Parameters(CreateTaskRequest {
-            project_id,
-            title,
-            description,
-            wish_id,
-        }): Parameters<CreateTaskRequest>,
+        Parameters(StartAttemptRequest {
+            task_id,
+            executor,
+            base_branch,
+        }): Parameters<StartAttemptRequest>,
     ) -> Result<CallToolResult, RmcpError> {
-        // Parse project_id from string to UUID
-        let project_uuid = match Uuid::parse_str(&project_id) {
+        let app_state = match self.require_app_state() {
+            Ok(app_state) => app_state,
+            Err(result) => return Ok(result),
+        };
+
+        let task_uuid = match Uuid::parse_str(&task_id) {
             Ok(uuid) => uuid,
             Err(_) => {
-                let error_response = serde_json::json!({
+                return Ok(tool_error(serde_json::json!({
                     "success": false,
-                    "error": "Invalid project ID format. Must be a valid UUID.",
-                    "project_id": project_id
-                });
-                return Ok(CallToolResult::error(vec![Content::text(
-                    serde_json::to_string_pretty(&error_response)
-                        .unwrap_or_else(|_| "Invalid project ID format".to_string()),
-                )]));
+                    "error": "Invalid task ID format. Must be a valid UUID.",
+                    "task_id": task_id
+                })));
             }
         };
 
-        // Check if project exists
-        match Project::exists(&self.pool, project_uuid).await {
-            Ok(false) => {
-                let error_response = serde_json::json!({
+        if let Some(name) = executor.as_deref() {
+            if let Err(e) = name.parse::<ExecutorConfig>() {
+                return Ok(tool_error(serde_json::json!({
                     "success": false,
-                    "error": "Project not found",
-                    "project_id": project_id
-                });
-                return Ok(CallToolResult::error(vec![Content::text(
-                    serde_json::to_string_pretty(&error_response)
-                        .unwrap_or_else(|_| "Project not found".to_string()),
-                )]));
+                    "error": e,
+                    "task_id": task_id
+                })));
+            }
+        }
+
+        let task = match Task::find_by_id(&self.pool, task_uuid).await {
+            Ok(Some(task)) => task,
+            Ok(None) => {
+                return Ok(tool_error(serde_json::json!({
+                    "success": false,
+                    "error": "Task not found",
+                    "task_id": task_id
+                })));
             }
             Err(e) => {
-                let error_response = serde_json::json!({
+                return Ok(tool_error(serde_json::json!({
                     "success": false,
-                    "error": "Failed to check project existence",
+                    "error": "Failed to load task",
                     "details": e.to_string(),
-                    "project_id": project_id
-                });
-                return Ok(CallToolResult::error(vec![Content::text(
-                    serde_json::to_string_pretty(&error_response)
-                        .unwrap_or_else(|_| "Database error".to_string()),
-                )]));
+                    "task_id": task_id
+                })));
             }
-            Ok(true) => {}
-        }
-
-        // Get user context from OAuth authentication using task-local storage workaround
-        let user_context = self.get_user_context(None).await; // Uses REQUEST_CONTEXT task-local storage
-        let created_by = user_context.as_ref().map(|(user, _)| user.id);
+        };
 
-        let task_id = Uuid::new_v4();
-        let create_task_data = CreateTask {
-            project_id: project_uuid,
-            title: title.clone(),
-            description: Some(description.clone()),
-            wish_id: wish_id.clone(),
-            parent_task_attempt: None,
-            created_by, // Use authenticated user
-            assigned_to: None,
+        let attempt = match self.launch_attempt(app_state, &task, executor, base_branch).await {
+            Ok(attempt) => attempt,
+            Err(result) => return Ok(result),
         };
 
-        match Task::create(&self.pool, &create_task_data, task_id).await {
-            Ok(_task) => {
-                let success_response = CreateTaskResponse {
-                    success: true,
-                    task_id: task_id.to_string(),
-                    message: format!("Task created successfully{}", 
-                        if let Some((user, _)) = user_context {
-                            format!(" by {}", user.display_name.unwrap_or(user.username))
-                        } else {
-                            " (anonymous)".to_string()
-                        }
-                    ),
-                };
-                Ok(CallToolResult::success(vec![Content::text(
-                    serde_json::to_string_pretty(&success_response)
-                        .unwrap_or_else(|_| "Task created successfully".to_string()),
-                )]))
-            }
-            Err(e) => {
-                let error_message = e.to_string();
-                let (user_error, details) = ("Failed to create task".to_string(), error_message.as_str());
+        Ok(tool_success(&StartAttemptResponse {
+            success: true,
+            attempt_id: attempt.id.to_string(),
+            branch: attempt.branch,
+            base_branch: attempt.base_branch,
+            executor: attempt.executor,
+            message: "Task attempt started".to_string(),
+        }))
+    }
 
-                let error_response = serde_json::json!({
+    #[tool(
+        description = "Spawn a sub-task of a running attempt. The sub-task is linked to the parent attempt and its attempts are based on the parent attempt's branch. Inside a coding agent the parent defaults to the agent's own attempt. Set `start` to run it right away, then use `wait_for_subtasks`. `title` is required!"
+    )]
+    async fn create_subtask(
+        &self,
+        Parameters(CreateSubtaskRequest {
+            title,
+            description,
+            parent_attempt_id,
+            executor,
+            start,
+        }): Parameters<CreateSubtaskRequest>,
+    ) -> Result<CallToolResult, RmcpError> {
+        let (parent_attempt, parent_task) =
+            match self.load_parent_attempt(parent_attempt_id.as_deref()).await {
+                Ok(loaded) => loaded,
+                Err(result) => return Ok(result),
+            };
+        let start = start.unwrap_or(false);
+        let app_state = if start {
+            match self.require_app_state() {
+                Ok(app_state) => Some(app_state),
+                Err(result) => return Ok(result),
+            }
+        } else {
+            None
+        };
+        if let Some(name) = executor.as_deref() {
+            if let Err(e) = name.parse::<ExecutorConfig>() {
+                return Ok(tool_error(serde_json::json!({
                     "success": false,
-                    "error": user_error,
-                    "details": details,
-                    "project_id": project_id,
-                    "title": title,
-                    "wish_id": wish_id
-                });
-                Ok(CallToolResult::error(vec![Content::text(
-                    serde_json::to_string_pretty(&error_response)
-                        .unwrap_or_else(|_| "Failed to create task".to_string()),
-                )]))
+                    "error": e,
+                    "parent_attempt_id": parent_attempt.id.to_string()
+                })));
             }
         }
-    }
 
-    #[tool(description = "List all the available projects")]
-    async fn list_projects(&self) -> Result<CallToolResult, RmcpError> {
-        match Project::find_all(&self.pool).await {
-            Ok(projects) => {
-                let count = projects.len();
-                let project_summaries: Vec<ProjectSummary> = projects
-                    .into_iter()
-                    .map(|project| {
-                        let project_with_branch = project.with_branch_info();
-                        ProjectSummary {
-                            id: project_with_branch.id.to_string(),
-                            name: project_with_branch.name,
-                            git_repo_path: project_with_branch.git_repo_path,
-                            setup_script: project_with_branch.setup_script,
-                            dev_script: project_with_branch.dev_script,
-                            current_branch: project_with_branch.current_branch,
-                            created_at: project_with_branch.created_at.to_rfc3339(),
-                            updated_at: project_with_branch.updated_at.to_rfc3339(),
-                        }
-                    })
-                    .collect();
-
-                let response = ListProjectsResponse {
-                    success: true,
-                    projects: project_summaries,
-                    count,
-                };
-
-                Ok(CallToolResult::success(vec![Content::text(
-                    serde_json::to_string_pretty(&response)
-                        .unwrap_or_else(|_| "Failed to serialize projects".to_string()),
-                )]))
-            }
+        let create_task = CreateTask {
+            project_id: parent_task.project_id,
+            title,
+            description,
+            wish_id: parent_task.wish_id.clone(),
+            parent_task_attempt: Some(parent_attempt.id),
+            created_by: current_user_id(),
+            assigned_to: parent_task.assigned_to,
+        };
+        let task = match Task::create(&self.pool, &create_task, Uuid::new_v4()).await {
+            Ok(task) => task,
             Err(e) => {
-                let error_response = serde_json::json!({
+                return Ok(tool_error(serde_json::json!({
                     "success": false,
-                    "error": "Failed to retrieve projects",
-                    "details": e.to_string()
-                });
-                Ok(CallToolResult::error(vec![Content::text(
-                    serde_json::to_string_pretty(&error_response)
-                        .unwrap_or_else(|_| "Database error".to_string()),
-                )]))
+                    "error": "Failed to create sub-task",
+                    "details": e.to_string(),
+                    "parent_attempt_id": parent_attempt.id.to_string()
+                })));
             }
+        };
+        webhooks::emit_task_created(&self.pool, &task).await;
+        if let Err(e) = Task::set_preferred_executor(&self.pool, task.id, executor.as_deref()).await {
+            tracing::error!("Failed to store executor of sub-task {}: {}", task.id, e);
+        }
+
+        let Some(app_state) = app_state else {
+            return Ok(tool_success(&CreateSubtaskResponse {
+                success: true,
+                task_id: task.id.to_string(),
+                parent_attempt_id: parent_attempt.id.to_string(),
+                attempt_id: None,
+                branch: None,
+                base_branch: parent_attempt.branch,
+                message: format!("Sub-task '{}' created", task.title),
+            }));
+        };
+
+        match self.launch_attempt(app_state, &task, executor, None).await {
+            Ok(attempt) => Ok(tool_success(&CreateSubtaskResponse {
+                success: true,
+                task_id: task.id.to_string(),
+                parent_attempt_id: parent_attempt.id.to_string(),
+                attempt_id: Some(attempt.id.to_string()),
+                branch: Some(attempt.branch),
+                base_branch: attempt.base_branch,
+                message: format!("Sub-task '{}' created and started", task.title),
+            })),
+            Err(result) => Ok(result),
         }
     }
 
     #[tool(
-        description = "List all the task/tickets in a project with optional filtering and execution status. `project_id` is required!"
+        description = "Wait until every sub-task of an attempt has finished (or the timeout expires) and return their status. Inside a coding agent the attempt defaults to the agent's own."
     )]
-    async fn list_tasks(
+    async fn wait_for_subtasks(
         &self,
-        Parameters(ListTasksRequest {
-            project_id,
-            status,
-            wish_id,
-            limit,
-        }): Parameters<ListTasksRequest>,
+        Parameters(WaitForSubtasksRequest {
+            parent_attempt_id,
+            timeout_seconds,
+        }): Parameters<WaitForSubtasksRequest>,
     ) -> Result<CallToolResult, RmcpError> {
-        // Parse project_id if provided
-        let project_uuid = if let Some(ref project_id_str) = project_id {
-            match Uuid::parse_str(project_id_str) {
-                Ok(uuid) => Some(uuid),
-                Err(_) => {
-                    let error_response = serde_json::json!({
+        let (parent_attempt, _parent_task) =
+            match self.load_parent_attempt(parent_attempt_id.as_deref()).await {
+                Ok(loaded) => loaded,
+                Err(result) => return Ok(result),
+            };
+
+        let timeout = std::time::Duration::from_secs(
+            timeout_seconds
+                .unwrap_or(DEFAULT_WAIT_TIMEOUT_SECS)
+                .min(MAX_WAIT_TIMEOUT_SECS),
+        );
+        let started = std::time::Instant::now();
+
+        loop {
+            let children = match Task::find_children_by_attempt_id(&self.pool, parent_attempt.id).await {
+                Ok(children) => children,
+                Err(e) => {
+                    return Ok(tool_error(serde_json::json!({
                         "success": false,
-                        "error": "Invalid project ID format. Must be a valid UUID.",
-                        "project_id": project_id_str
-                    });
-                    return Ok(CallToolResult::error(vec![Content::text(
-                        serde_json::to_string_pretty(&error_response)
-                            .unwrap_or_else(|_| "Invalid project ID format".to_string()),
-                    )]));
+                        "error": "Failed to load sub-tasks",
+                        "details": e.to_string(),
+                        "parent_attempt_id": parent_attempt.id.to_string()
+                    })));
+                }
+            };
+            let mut subtasks = Vec::with_capacity(children.len());
+            for child in &children {
+                match self.subtask_status(child).await {
+                    Ok(status) => subtasks.push(status),
+                    Err(result) => return Ok(result),
                 }
             }
-        } else {
-            None
-        };
 
-        let status_filter = if let Some(ref status_str) = status {
-            match parse_task_status(status_str) {
-                Some(status) => Some(status),
-                None => {
-                    let error_response = serde_json::json!({
-                        "success": false,
-                        "error": "Invalid status filter. Valid values: 'todo', 'inprogress', 'inreview', 'done', 'cancelled'",
-                        "provided_status": status_str
-                    });
-                    return Ok(CallToolResult::error(vec![Content::text(
-                        serde_json::to_string_pretty(&error_response)
-                            .unwrap_or_else(|_| "Invalid status filter".to_string()),
-                    )]));
-                }
+            let all_finished = subtasks.iter().all(|subtask| subtask.finished);
+            let timed_out = !all_finished && started.elapsed() >= timeout;
+            if all_finished || timed_out {
+                return Ok(tool_success(&WaitForSubtasksResponse {
+                    success: true,
+                    parent_attempt_id: parent_attempt.id.to_string(),
+                    all_finished,
+                    timed_out,
+                    waited_seconds: started.elapsed().as_secs(),
+                    subtasks,
+                }));
             }
-        } else {
-            None
+            tokio::time::sleep(WAIT_POLL_INTERVAL.min(timeout.saturating_sub(started.elapsed())))
+                .await;
+        }
+    }
+
+    #[tool(
+        description = "Stop all running processes (setup script, coding agent, dev server) of a task attempt. `attempt_id` is required!"
+    )]
+    async fn stop_attempt(
+        &self,
+        Parameters(AttemptRequest { attempt_id }): Parameters<AttemptRequest>,
+    ) -> Result<CallToolResult, RmcpError> {
+        let app_state = match self.require_app_state() {
+            Ok(app_state) => app_state,
+            Err(result) => return Ok(result),
+        };
+        let (attempt, _task) = match self.load_attempt(&attempt_id).await {
+            Ok(loaded) => loaded,
+            Err(result) => return Ok(result),
         };
 
-        // For now, require project_id since we only have project-based queries
-        let project_uuid_val = match project_uuid {
-            Some(uuid) => uuid,
-            None => {
-                let error_response = serde_json::json!({
-                    "success": false,
-                    "error": "Project ID is required for listing tasks"
-                });
-                return Ok(CallToolResult::error(vec![Content::text(
-                    serde_json::to_string_pretty(&error_response)
-                        .unwrap_or_else(|_| "Project ID required".to_string()),
-                )]));
+        match ProcessService::stop_attempt_processes(&self.pool, app_state, attempt.id).await {
+            Ok((stopped_processes, errors)) if errors.is_empty() => {
+                Ok(tool_success(&StopAttemptResponse {
+                    success: true,
+                    attempt_id,
+                    stopped_processes,
+                    message: format!("Stopped {} process(es)", stopped_processes),
+                }))
             }
+            Ok((stopped_processes, errors)) => Ok(tool_error(serde_json::json!({
+                "success": false,
+                "error": format!("Stopped {} process(es), but encountered errors", stopped_processes),
+                "details": errors,
+                "attempt_id": attempt_id
+            }))),
+            Err(e) => Ok(tool_error(serde_json::json!({
+                "success": false,
+                "error": "Failed to stop task attempt",
+                "details": e.to_string(),
+                "attempt_id": attempt_id
+            }))),
+        }
+    }
+
+    #[tool(
+        description = "Send follow-up instructions to the coding agent of a task attempt. The agent continues in the same session and worktree. `attempt_id` and `prompt` are required!"
+    )]
+    async fn send_follow_up(
+        &self,
+        Parameters(SendFollowUpRequest { attempt_id, prompt }): Parameters<SendFollowUpRequest>,
+    ) -> Result<CallToolResult, RmcpError> {
+        let app_state = match self.require_app_state() {
+            Ok(app_state) => app_state,
+            Err(result) => return Ok(result),
+        };
+        if prompt.trim().is_empty() {
+            return Ok(tool_error(serde_json::json!({
+                "success": false,
+                "error": "Prompt must not be empty",
+                "attempt_id": attempt_id
+            })));
+        }
+        let (attempt, task) = match self.load_attempt(&attempt_id).await {
+            Ok(loaded) => loaded,
+            Err(result) => return Ok(result),
         };
 
-        let project = match Project::find_by_id(&self.pool, project_uuid_val).await {
-            Ok(Some(project)) => project,
-            Ok(None) => {
-                let error_response = serde_json::json!({
-                    "success": false,
-                    "error": "Project not found",
-                    "project_id": project_id
-                });
-                return Ok(CallToolResult::error(vec![Content::text(
-                    serde_json::to_string_pretty(&error_response)
-                        .unwrap_or_else(|_| "Project not found".to_string()),
-                )]));
+        match ProcessService::start_followup_execution(
+            &self.pool,
+            app_state,
+            attempt.id,
+            task.id,
+            task.project_id,
+            &prompt,
+        )
+        .await
+        {
+            Ok(actual_attempt_id) => Ok(tool_success(&SendFollowUpResponse {
+                success: true,
+                attempt_id: actual_attempt_id.to_string(),
+                message: "Follow-up execution started".to_string(),
+            })),
+            Err(e) => Ok(tool_error(serde_json::json!({
+                "success": false,
+                "error": "Failed to start follow-up execution",
+                "details": e.to_string(),
+                "attempt_id": attempt_id
+            }))),
+        }
+    }
+
+    #[tool(
+        description = "Get the execution state of a task attempt: setup and coding agent status, whether it has changes, and whether it has finished. `attempt_id` is required!"
+    )]
+    async fn get_attempt_status(
+        &self,
+        Parameters(AttemptRequest { attempt_id }): Parameters<AttemptRequest>,
+    ) -> Result<CallToolResult, RmcpError> {
+        let (attempt, task) = match self.load_attempt(&attempt_id).await {
+            Ok(loaded) => loaded,
+            Err(result) => return Ok(result),
+        };
+
+        match self.attempt_status(&attempt, &task).await {
+            Ok((state, finished)) => Ok(tool_success(&AttemptStatusResponse {
+                success: true,
+                attempt_id,
+                task_id: task.id.to_string(),
+                state,
+                finished,
+            })),
+            Err(result) => Ok(result),
+        }
+    }
+
+    #[tool(
+        description = "Wait until a task attempt has finished running (or the timeout expires) and return its final execution state. `attempt_id` is required!"
+    )]
+    async fn wait_for_attempt(
+        &self,
+        Parameters(WaitForAttemptRequest {
+            attempt_id,
+            timeout_seconds,
+        }): Parameters<WaitForAttemptRequest>,
+    ) -> Result<CallToolResult, RmcpError> {
+        let (attempt, task) = match self.load_attempt(&attempt_id).await {
+            Ok(loaded) => loaded,
+            Err(result) => return Ok(result),
+        };
+
+        let timeout = std::time::Duration::from_secs(
+            timeout_seconds
+                .unwrap_or(DEFAULT_WAIT_TIMEOUT_SECS)
+                .min(MAX_WAIT_TIMEOUT_SECS),
+        );
+        let started = std::time::Instant::now();
+
+        loop {
+            let (state, finished) = match self.attempt_status(&attempt, &task).await {
+                Ok(status) => status,
+                Err(result) => return Ok(result),
+            };
+            let timed_out = !finished && started.elapsed() >= timeout;
+            if finished || timed_out {
+                return Ok(tool_success(&WaitForAttemptResponse {
+                    success: true,
+                    attempt_id,
+                    finished,
+                    timed_out,
+                    waited_seconds: started.elapsed().as_secs(),
+                    state,
+                }));
             }
-            Err(e) => {
-                let error_response = serde_json::json!({
-                    "success": false,
-                    "error": "Failed to check project existence",
-                    "details": e.to_string(),
-                    "project_id": project_id
-                });
-                return Ok(CallToolResult::error(vec![Content::text(
-                    serde_json::to_string_pretty(&error_response)
-                        .unwrap_or_else(|_| "Database error".to_string()),
-                )]));
+            tokio::time::sleep(WAIT_POLL_INTERVAL.min(timeout.saturating_sub(started.elapsed())))
+                .await;
+        }
+    }
+    #[tool(
+        description = "Get the changes a task attempt made compared to its base branch, as per-file patches. Filter with `paths` and cap the size with `max_bytes`. `attempt_id` is required!"
+    )]
+    async fn get_attempt_diff(
+        &self,
+        Parameters(GetAttemptDiffRequest {
+            attempt_id,
+            paths,
+            max_bytes,
+        }): Parameters<GetAttemptDiffRequest>,
+    ) -> Result<Json<AttemptDiffResponse>, RmcpError> {
+        let (attempt, task) = self.find_attempt(&attempt_id).await?;
+        let diff = TaskAttempt::get_diff(&self.pool, attempt.id, task.id, task.project_id)
+            .await
+            .map_err(|e| RmcpError::internal_error(format!("Failed to get diff: {}", e), None))?;
+
+        let matching: Vec<_> = diff
+            .files
+            .into_iter()
+            .filter(|file| match &paths {
+                Some(prefixes) if !prefixes.is_empty() => prefixes
+                    .iter()
+                    .any(|prefix| file.path.starts_with(prefix.trim_start_matches("./"))),
+                _ => true,
+            })
+            .collect();
+        let total_files = matching.len();
+
+        let mut remaining = max_bytes.unwrap_or(DEFAULT_DIFF_MAX_BYTES);
+        let mut files = Vec::new();
+        let mut truncated = false;
+        for file in matching {
+            if remaining == 0 {
+                truncated = true;
+                break;
             }
+            let (mut patch, additions, deletions) = render_patch(&file.chunks);
+            let file_truncated = patch.len() > remaining;
+            truncate_to(&mut patch, remaining);
+            remaining -= patch.len();
+            truncated |= file_truncated;
+            files.push(AttemptFileDiff {
+                path: file.path,
+                additions,
+                deletions,
+                patch,
+                truncated: file_truncated,
+            });
+        }
+
+        Ok(Json(AttemptDiffResponse {
+            attempt_id,
+            files,
+            total_files,
+            truncated,
+        }))
+    }
+
+    #[tool(
+        description = "Get what the coding agent of a task attempt reported: its prompt, final summary and the tail of its conversation. `attempt_id` is required!"
+    )]
+    async fn get_attempt_summary(
+        &self,
+        Parameters(GetAttemptSummaryRequest {
+            attempt_id,
+            max_entries,
+        }): Parameters<GetAttemptSummaryRequest>,
+    ) -> Result<Json<AttemptSummaryResponse>, RmcpError> {
+        let (attempt, _task) = self.find_attempt(&attempt_id).await?;
+        let processes = ExecutionProcess::find_by_task_attempt_id(&self.pool, attempt.id)
+            .await
+            .map_err(|e| {
+                RmcpError::internal_error(format!("Failed to load execution processes: {}", e), None)
+            })?;
+
+        let Some(process) = processes
+            .iter()
+            .rev()
+            .find(|p| p.process_type == ExecutionProcessType::CodingAgent)
+        else {
+            return Ok(Json(AttemptSummaryResponse {
+                attempt_id,
+                executor: attempt.executor,
+                status: None,
+                prompt: None,
+                summary: None,
+                entries: Vec::new(),
+            }));
         };
 
-        let task_limit = limit.unwrap_or(50).clamp(1, 200); // Reasonable limits
+        let conversation = normalize_process_logs(&self.pool, process).await;
+        let max_entries = max_entries
+            .unwrap_or(DEFAULT_SUMMARY_ENTRIES)
+            .min(MAX_SUMMARY_ENTRIES);
+        let skip = conversation.entries.len().saturating_sub(max_entries);
+        let entries = conversation
+            .entries
+            .into_iter()
+            .skip(skip)
+            .map(conversation_entry)
+            .collect();
+
+        Ok(Json(AttemptSummaryResponse {
+            attempt_id,
+            executor: process.executor_type.clone(),
+            status: serde_json::to_value(&process.status)
+                .ok()
+                .and_then(|value| value.as_str().map(str::to_string)),
+            prompt: conversation.prompt,
+            summary: conversation.summary,
+            entries,
+        }))
+    }
 
-        let tasks_result =
-            Task::find_by_project_id_with_attempt_status(&self.pool, project_uuid_val).await;
+    #[tool(
+        description = "Get how a task attempt's branch relates to its base branch: commits ahead/behind, uncommitted changes and whether it was merged. `attempt_id` is required!"
+    )]
+    async fn get_branch_status(
+        &self,
+        Parameters(AttemptRequest { attempt_id }): Parameters<AttemptRequest>,
+    ) -> Result<Json<BranchStatusResponse>, RmcpError> {
+        let (attempt, task) = self.find_attempt(&attempt_id).await?;
+        let status = TaskAttempt::get_branch_status(&self.pool, attempt.id, task.id, task.project_id)
+            .await
+            .map_err(|e| {
+                RmcpError::internal_error(format!("Failed to get branch status: {}", e), None)
+            })?;
+
+        Ok(Json(BranchStatusResponse {
+            attempt_id,
+            branch: attempt.branch,
+            base_branch: status.base_branch_name,
+            commits_ahead: status.commits_ahead,
+            commits_behind: status.commits_behind,
+            is_behind: status.is_behind,
+            up_to_date: status.up_to_date,
+            merged: status.merged,
+            has_uncommitted_changes: status.has_uncommitted_changes,
+        }))
+    }
 
-        match tasks_result {
-            Ok(tasks) => {
-                let filtered_tasks: Vec<_> = tasks
-                    .into_iter()
-                    .filter(|task| {
-                        let status_match = if let Some(ref filter_status) = status_filter {
-                            &task.status == filter_status
-                        } else {
-                            true
-                        };
-                        let wish_match = if let Some(ref filter_wish) = wish_id {
-                            task.wish_id == *filter_wish
-                        } else {
-                            true
-                        };
-                        status_match && wish_match
-                    })
-                    .take(task_limit as usize)
-                    .collect();
+    #[tool(
+        description = "Rebase a task attempt's branch onto its base branch, or onto `new_base_branch` which then becomes its base. `attempt_id` is required!"
+    )]
+    async fn rebase_attempt(
+        &self,
+        Parameters(RebaseAttemptRequest {
+            attempt_id,
+            new_base_branch,
+        }): Parameters<RebaseAttemptRequest>,
+    ) -> Result<Json<RebaseAttemptResponse>, RmcpError> {
+        let (attempt, task) = self.find_attempt(&attempt_id).await?;
+        let new_base_commit = TaskAttempt::rebase_attempt(
+            &self.pool,
+            attempt.id,
+            task.id,
+            task.project_id,
+            new_base_branch,
+        )
+        .await
+        .map_err(|e| RmcpError::internal_error(format!("Failed to rebase: {}", e), None))?;
+
+        // Reload to report the base branch as stored after the rebase
+        let base_branch = TaskAttempt::find_by_id(&self.pool, attempt.id)
+            .await
+            .ok()
+            .flatten()
+            .map(|a| a.base_branch)
+            .unwrap_or(attempt.base_branch);
+
+        Ok(Json(RebaseAttemptResponse {
+            attempt_id,
+            base_branch,
+            new_base_commit,
+        }))
+    }
 
-                let task_summaries: Vec<TaskSummary> = filtered_tasks
-                    .into_iter()
-                    .map(|task| TaskSummary {
-                        id: task.id.to_string(),
-                        title: task.title,
-                        description: task.description,
-                        status: task_status_to_string(&task.status),
-                        wish_id: task.wish_id,
-                        created_at: task.created_at.to_rfc3339(),
-                        updated_at: task.updated_at.to_rfc3339(),
-                        has_in_progress_attempt: Some(task.has_in_progress_attempt),
-                        has_merged_attempt: Some(task.has_merged_attempt),
-                        last_attempt_failed: Some(task.last_attempt_failed),
-                    })
-                    .collect();
+    #[tool(
+        description = "Merge a task attempt's changes into its base branch and mark the task done. `attempt_id` is required!"
+    )]
+    async fn merge_attempt(
+        &self,
+        Parameters(AttemptRequest { attempt_id }): Parameters<AttemptRequest>,
+    ) -> Result<Json<MergeAttemptResponse>, RmcpError> {
+        let (attempt, task) = self.find_attempt(&attempt_id).await?;
+        let merge_commit =
+            TaskAttempt::merge_changes(&self.pool, attempt.id, task.id, task.project_id)
+                .await
+                .map_err(|e| RmcpError::internal_error(format!("Failed to merge: {}", e), None))?;
+        webhooks::emit_attempt_event(
+            &self.pool,
+            WebhookEvent::AttemptMerged,
+            task.project_id,
+            task.id,
+            attempt.id,
+            serde_json::json!({
+                "branch": attempt.branch,
+                "base_branch": attempt.base_branch,
+                "merge_commit": merge_commit,
+            }),
+        )
+        .await;
+
+        Task::update_status(&self.pool, task.id, task.project_id, TaskStatus::Done)
+            .await
+            .map_err(|e| {
+                RmcpError::internal_error(
+                    format!("Merged, but failed to update task status: {}", e),
+                    None,
+                )
+            })?;
+        webhooks::emit_task_status(&self.pool, task.id, task.project_id, TaskStatus::Done).await;
+
+        if let Some(app_state) = &self.app_state {
+            github_issues::comment_on_merge(
+                &self.pool,
+                app_state.secret_store(),
+                &task,
+                &github_issues::merged_into(&attempt.base_branch, &merge_commit),
+            )
+            .await;
+            app_state
+                .track_analytics_event(
+                    "task_attempt_merged",
+                    Some(serde_json::json!({
+                        "task_id": task.id.to_string(),
+                        "project_id": task.project_id.to_string(),
+                        "attempt_id": attempt.id.to_string(),
+                        "source": "mcp",
+                    })),
+                )
+                .await;
+        }
 
-                let count = task_summaries.len();
-                let response = ListTasksResponse {
-                    success: true,
-                    tasks: task_summaries,
-                    count,
-                    project_id: project_id.clone().unwrap_or_else(|| project_uuid_val.to_string()),
-                    project_name: Some(project.name),
-                    applied_filters: ListTasksFilters {
-                        status: status.clone(),
-                        limit: task_limit,
-                    },
-                };
+        Ok(Json(MergeAttemptResponse {
+            attempt_id,
+            merge_commit,
+            task_status: task_status_to_string(&TaskStatus::Done),
+        }))
+    }
 
-                Ok(CallToolResult::success(vec![Content::text(
-                    serde_json::to_string_pretty(&response)
-                        .unwrap_or_else(|_| "Failed to serialize tasks".to_string()),
-                )]))
-            }
-            Err(e) => {
-                let error_response = serde_json::json!({
-                    "success": false,
-                    "error": "Failed to retrieve tasks",
-                    "details": e.to_string(),
-                    "project_id": project_id
-                });
-                Ok(CallToolResult::error(vec![Content::text(
-                    serde_json::to_string_pretty(&error_response)
-                        .unwrap_or_else(|_| "Database error".to_string()),
-                )]))
+    #[tool(
+        description = "Push a task attempt's branch and open a pull request on the project's forge (GitHub, or a GitLab merge request / Gitea pull request). `attempt_id` and `title` are required!"
+    )]
+    async fn create_pull_request(
+        &self,
+        Parameters(CreatePullRequestRequest {
+            attempt_id,
+            title,
+            body,
+            base_branch,
+        }): Parameters<CreatePullRequestRequest>,
+    ) -> Result<Json<CreatePullRequestResponse>, RmcpError> {
+        let app_state = self.app_state.as_ref().ok_or_else(|| {
+            RmcpError::invalid_request(
+                "Pull requests can only be created when the MCP server runs inside the Forge backend",
+                None,
+            )
+        })?;
+        let (attempt, task) = self.find_attempt(&attempt_id).await?;
+
+        let project = Project::find_by_id(&self.pool, task.project_id)
+            .await
+            .map_err(|e| RmcpError::internal_error(format!("Database error: {}", e), None))?
+            .ok_or_else(|| RmcpError::invalid_request("Project not found", None))?;
+
+        // The forge is picked from the project's remote, authenticating with
+        // the caller's token for it (or the install's GitHub token)
+        let (repo, token) = forge::credentials(
+            app_state.secret_store(),
+            &project.git_repo_path,
+            current_user_id().or(attempt.created_by),
+        )
+        .await
+        .map_err(|e| match e {
+            ForgeError::NotAuthenticated(ForgeKind::GitHub, _) => RmcpError::invalid_request(
+                "GitHub authentication not configured. Please sign in with GitHub.",
+                None,
+            ),
+            e => RmcpError::invalid_request(e.to_string(), None),
+        })?;
+        let config = app_state.get_config().read().await.clone();
+
+        let base_branch = base_branch.unwrap_or_else(|| {
+            if !attempt.base_branch.trim().is_empty() {
+                attempt.base_branch.clone()
+            } else {
+                config
+                    .github
+                    .default_pr_base
+                    .clone()
+                    .unwrap_or_else(|| "main".to_string())
             }
-        }
+        });
+
+        let pr_url = TaskAttempt::create_pr(
+            &self.pool,
+            CreatePrParams {
+                attempt_id: attempt.id,
+                task_id: task.id,
+                project_id: task.project_id,
+                repo: &repo,
+                token: &token,
+                title: &title,
+                body: body.as_deref(),
+                base_branch: Some(&base_branch),
+            },
+        )
+        .await
+        .map_err(|e| RmcpError::internal_error(format!("Failed to create PR: {}", e), None))?;
+        webhooks::emit_attempt_event(
+            &self.pool,
+            WebhookEvent::PrOpened,
+            task.project_id,
+            task.id,
+            attempt.id,
+            serde_json::json!({ "pr_url": pr_url, "base_branch": base_branch }),
+        )
+        .await;
+
+        app_state
+            .track_analytics_event(
+                "github_pr_created",
+                Some(serde_json::json!({
+                    "task_id": task.id.to_string(),
+                    "project_id": task.project_id.to_string(),
+                    "attempt_id": attempt.id.to_string(),
+                    "forge": repo.kind.as_str(),
+                    "source": "mcp",
+                })),
+            )
+            .await;
+
+        Ok(Json(CreatePullRequestResponse {
+            attempt_id,
+            pr_url,
+            base_branch,
+        }))
     }
 }
 
-#[tool_handler]
 impl ServerHandler for TaskServer {
     fn get_info(&self) -> ServerInfo {
         ServerInfo {
             protocol_version: ProtocolVersion::V_2025_03_26,
             capabilities: ServerCapabilities::builder()
                 .enable_tools()
+                .enable_resources()
+                .enable_resources_subscribe()
+                .enable_prompts()
                 .build(),
             server_info: Implementation {
                 name: "automagik-forge".to_string(),
                 version: "1.0.0".to_string(),
             },
-            instructions: Some("A task and project management server. If you need to create or update tickets or tasks then use these tools. Most of them absolutely require that you pass the `project_id` of the project that you are currently working on. This should be provided to you. Call `list_tasks` to fetch the `task_ids` of all the tasks in a project`. TOOLS: 'list_projects', 'list_tasks', 'create_task', 'get_task', 'update_task', 'delete_task'. Make sure to pass `project_id` or `task_id` where required. You can use list tools to get the available ids.".to_string()),
+            instructions: Some("A task and project management server. If you need to create or update tickets or tasks then use these tools. Most of them absolutely require that you pass the `project_id` of the project that you are currently working on. This should be provided to you. To create many tasks at once, pass a plan to `create_tasks_from_plan` with `dry_run` first. Call `list_tasks` to fetch the `task_ids` of all the tasks in a project`. To run a task, call `start_attempt`, then `wait_for_attempt` or `get_attempt_status`; use `send_follow_up` to give the agent more instructions and `stop_attempt` to cancel it. A coding agent can split its work with `create_subtask` and `wait_for_subtasks`; sub-tasks default to the agent's own attempt as parent. Review the result with `get_attempt_diff`, `get_attempt_summary` and `get_branch_status`, then land it with `rebase_attempt`, `merge_attempt` or `create_pull_request`. Instead of polling, read and subscribe to the resources `forge://project/{project_id}/tasks`, `forge://task/{task_id}`, `forge://attempt/{attempt_id}` and `forge://attempt/{attempt_id}/logs`. PROMPTS: 'plan_feature', 'review_attempt', 'write_pr_description'. TOOLS: 'list_projects', 'list_tasks', 'create_task', 'create_tasks_from_plan', 'get_task', 'update_task', 'delete_task', 'start_attempt', 'create_subtask', 'wait_for_subtasks', 'stop_attempt', 'send_follow_up', 'get_attempt_status', 'wait_for_attempt', 'get_attempt_diff', 'get_attempt_summary', 'get_branch_status', 'rebase_attempt', 'merge_attempt', 'create_pull_request'. Make sure to pass `project_id` or `task_id` where required. You can use list tools to get the available ids. Every call runs as the Forge user owning the MCP access token, sent as a Bearer token over HTTP or configured via `FORGE_MCP_TOKEN` for STDIO.".to_string()),
         }
     }
+
+    async fn list_tools(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, RmcpError> {
+        Ok(ListToolsResult {
+            tools: self.tool_router.list_all(),
+            next_cursor: None,
+        })
+    }
+
+    /// Every tool call is authenticated and runs as the resolved user;
+    /// calls to mutating tools are audit-logged with their outcome
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, RmcpError> {
+        let user_context = self.require_user(&context).await?;
+        let user_id = user_context.user.id;
+        let calling_attempt = self.calling_attempt(&context);
+        let tool = request.name.to_string();
+        let arguments = request.arguments.clone().map(serde_json::Value::Object);
+
+        let result = CALLING_ATTEMPT
+            .scope(
+                calling_attempt,
+                CURRENT_USER.scope(
+                    user_context,
+                    self.tool_router
+                        .call(ToolCallContext::new(self, request, context)),
+                ),
+            )
+            .await;
+
+        if MUTATING_TOOLS.contains(&tool.as_str()) {
+            let audit_result = match &result {
+                Ok(result) if result.is_error != Some(true) => AuditResult::Success,
+                Ok(_) => AuditResult::Failure,
+                Err(_) => AuditResult::Error,
+            };
+            self.audit_tool_call(Some(user_id), &tool, arguments, audit_result)
+                .await;
+        }
+        result
+    }
+
+    async fn list_prompts(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListPromptsResult, RmcpError> {
+        Ok(ListPromptsResult {
+            prompts: prompts::list_prompts(),
+            next_cursor: None,
+        })
+    }
+
+    async fn get_prompt(
+        &self,
+        GetPromptRequestParam { name, arguments }: GetPromptRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<GetPromptResult, RmcpError> {
+        self.require_user(&context).await?;
+        ForgePrompt::from_name(&name)
+            .map_err(prompt_error)?
+            .render(&self.pool, arguments.as_ref())
+            .await
+            .map_err(prompt_error)
+    }
+
+    async fn list_resources(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListResourcesResult, RmcpError> {
+        self.require_user(&context).await?;
+        Ok(ListResourcesResult {
+            resources: resources::list_resources(&self.pool)
+                .await
+                .map_err(resource_error)?,
+            next_cursor: None,
+        })
+    }
+
+    async fn list_resource_templates(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListResourceTemplatesResult, RmcpError> {
+        self.require_user(&context).await?;
+        Ok(ListResourceTemplatesResult {
+            resource_templates: resources::resource_templates(),
+            next_cursor: None,
+        })
+    }
+
+    async fn read_resource(
+        &self,
+        ReadResourceRequestParam { uri }: ReadResourceRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, RmcpError> {
+        self.require_user(&context).await?;
+        let resource = ForgeResource::parse(&uri).map_err(resource_error)?;
+        let contents = resource.read(&self.pool).await.map_err(resource_error)?;
+        Ok(ReadResourceResult {
+            contents: vec![contents],
+        })
+    }
+
+    async fn subscribe(
+        &self,
+        SubscribeRequestParam { uri }: SubscribeRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<(), RmcpError> {
+        self.require_user(&context).await?;
+        let resource = ForgeResource::parse(&uri).map_err(resource_error)?;
+        // Fail early for resources that do not exist
+        resource.read(&self.pool).await.map_err(resource_error)?;
+        self.subscriptions
+            .subscribe(self.pool.clone(), resource, context.peer);
+        Ok(())
+    }
+
+    async fn unsubscribe(
+        &self,
+        UnsubscribeRequestParam { uri }: UnsubscribeRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<(), RmcpError> {
+        self.require_user(&context).await?;
+        let resource = ForgeResource::parse(&uri).map_err(resource_error)?;
+        self.subscriptions.unsubscribe(&resource.uri());
+        Ok(())
+    }
 }
 
-#[tool_handler]
-impl ServerHandler for AuthenticatedTaskServer {
-    fn get_info(&self) -> ServerInfo {
-        ServerInfo {
-            protocol_version: ProtocolVersion::V_2025_03_26,
-            capabilities: ServerCapabilities::builder()
-                .enable_tools()
-                .build(),
-            server_info: Implementation {
-                name: "automagik-forge-authenticated".to_string(),
-                version: "1.0.0".to_string(),
-            },
-            instructions: Some("An authenticated task and project management server with OAuth 2.1 support. All operations are attributed to the authenticated GitHub user. If you need to create or update tickets or tasks then use these tools. Most of them absolutely require that you pass the `project_id` of the project that you are currently working on. TOOLS: 'list_projects', 'list_tasks', 'create_task', 'get_task', 'update_task', 'delete_task'. Make sure to pass `project_id` or `task_id` where required. Authentication is handled automatically via OAuth Bearer tokens.".to_string()),
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(chunk_type: DiffChunkType, content: &str) -> DiffChunk {
+        DiffChunk {
+            chunk_type,
+            content: content.to_string(),
         }
     }
+
+    #[test]
+    fn test_render_patch_keeps_context_around_changes() {
+        let unchanged: String = (1..=10).map(|n| format!("line {}\n", n)).collect();
+        let chunks = vec![
+            chunk(DiffChunkType::Equal, &unchanged),
+            chunk(DiffChunkType::Delete, "old\n"),
+            chunk(DiffChunkType::Insert, "new\nnewer\n"),
+            chunk(DiffChunkType::Equal, "tail 1\ntail 2\n"),
+        ];
+
+        let (patch, additions, deletions) = render_patch(&chunks);
+        assert_eq!(additions, 2);
+        assert_eq!(deletions, 1);
+        assert_eq!(
+            patch,
+            "@@\n line 8\n line 9\n line 10\n-old\n+new\n+newer\n tail 1\n tail 2\n"
+        );
+    }
+
+    #[test]
+    fn test_truncate_to_respects_char_boundaries() {
+        let mut text = "héllo".to_string();
+        truncate_to(&mut text, 2);
+        assert_eq!(text, "h");
+    }
 }
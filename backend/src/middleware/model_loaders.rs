@@ -10,7 +10,7 @@ use crate::{
     app_state::AppState,
     models::{
         execution_process::ExecutionProcess, project::Project, task::Task,
-        task_attempt::TaskAttempt, task_template::TaskTemplate,
+        task_attempt::TaskAttempt, task_template::TaskTemplate, webhook::Webhook,
     },
 };
 
@@ -90,6 +90,52 @@ pub async fn load_task_middleware(
     Ok(next.run(request).await)
 }
 
+/// Middleware that loads and injects both Project and Webhook based on project_id and webhook_id path parameters
+pub async fn load_webhook_middleware(
+    State(app_state): State<AppState>,
+    Path((project_id, webhook_id)): Path<(Uuid, Uuid)>,
+    request: axum::extract::Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    let project = match Project::find_by_id(&app_state.db_pool, project_id).await {
+        Ok(Some(project)) => project,
+        Ok(None) => {
+            tracing::warn!("Project {} not found", project_id);
+            return Err(StatusCode::NOT_FOUND);
+        }
+        Err(e) => {
+            tracing::error!("Failed to fetch project {}: {}", project_id, e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+
+    // Load the webhook and validate it belongs to the project
+    let webhook =
+        match Webhook::find_by_id_and_project_id(&app_state.db_pool, webhook_id, project_id).await
+        {
+            Ok(Some(webhook)) => webhook,
+            Ok(None) => {
+                tracing::warn!("Webhook {} not found in project {}", webhook_id, project_id);
+                return Err(StatusCode::NOT_FOUND);
+            }
+            Err(e) => {
+                tracing::error!(
+                    "Failed to fetch webhook {} in project {}: {}",
+                    webhook_id,
+                    project_id,
+                    e
+                );
+                return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            }
+        };
+
+    let mut request = request;
+    request.extensions_mut().insert(project);
+    request.extensions_mut().insert(webhook);
+
+    Ok(next.run(request).await)
+}
+
 /// Middleware that loads and injects Project, Task, and TaskAttempt based on project_id, task_id, and attempt_id path parameters
 pub async fn load_task_attempt_middleware(
     State(app_state): State<AppState>,
@@ -0,0 +1,130 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, SqlitePool, Type};
+use uuid::Uuid;
+
+/// Login providers that store their identities in `auth_identities`.
+/// GitHub accounts keep using `users.github_id` directly.
+#[derive(Debug, Clone, Copy, Type, Serialize, Deserialize, PartialEq)]
+#[sqlx(type_name = "identity_provider", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum IdentityProvider {
+    Local,
+    Oidc,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct AuthIdentity {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub provider: IdentityProvider,
+    pub subject: String,
+    #[serde(skip_serializing)]
+    pub password_hash: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug)]
+pub struct CreateAuthIdentity {
+    pub user_id: Uuid,
+    pub provider: IdentityProvider,
+    pub subject: String,
+    pub password_hash: Option<String>,
+}
+
+impl AuthIdentity {
+    /// Find an identity by provider and subject (login name or OIDC `sub`)
+    pub async fn find_by_provider_subject(
+        pool: &SqlitePool,
+        provider: IdentityProvider,
+        subject: &str,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        sqlx::query_as!(
+            AuthIdentity,
+            r#"SELECT
+                id as "id!: Uuid",
+                user_id as "user_id!: Uuid",
+                provider as "provider!: IdentityProvider",
+                subject,
+                password_hash,
+                created_at as "created_at!: DateTime<Utc>",
+                updated_at as "updated_at!: DateTime<Utc>"
+            FROM auth_identities
+            WHERE provider = $1 AND subject = $2"#,
+            provider,
+            subject
+        )
+        .fetch_optional(pool)
+        .await
+    }
+
+    /// Find a user's identity for a given provider
+    pub async fn find_by_user_and_provider(
+        pool: &SqlitePool,
+        user_id: Uuid,
+        provider: IdentityProvider,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        sqlx::query_as!(
+            AuthIdentity,
+            r#"SELECT
+                id as "id!: Uuid",
+                user_id as "user_id!: Uuid",
+                provider as "provider!: IdentityProvider",
+                subject,
+                password_hash,
+                created_at as "created_at!: DateTime<Utc>",
+                updated_at as "updated_at!: DateTime<Utc>"
+            FROM auth_identities
+            WHERE user_id = $1 AND provider = $2"#,
+            user_id,
+            provider
+        )
+        .fetch_optional(pool)
+        .await
+    }
+
+    /// Create a new identity
+    pub async fn create(
+        pool: &SqlitePool,
+        data: &CreateAuthIdentity,
+        identity_id: Uuid,
+    ) -> Result<Self, sqlx::Error> {
+        sqlx::query_as!(
+            AuthIdentity,
+            r#"INSERT INTO auth_identities (id, user_id, provider, subject, password_hash)
+               VALUES ($1, $2, $3, $4, $5)
+               RETURNING
+                id as "id!: Uuid",
+                user_id as "user_id!: Uuid",
+                provider as "provider!: IdentityProvider",
+                subject,
+                password_hash,
+                created_at as "created_at!: DateTime<Utc>",
+                updated_at as "updated_at!: DateTime<Utc>""#,
+            identity_id,
+            data.user_id,
+            data.provider,
+            data.subject,
+            data.password_hash
+        )
+        .fetch_one(pool)
+        .await
+    }
+
+    /// Replace the password hash of a local identity
+    pub async fn update_password_hash(
+        pool: &SqlitePool,
+        id: Uuid,
+        password_hash: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "UPDATE auth_identities SET password_hash = $1, updated_at = datetime('now', 'subsec') WHERE id = $2",
+            password_hash,
+            id
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+}
@@ -4,7 +4,10 @@ use serde::{Deserialize, Serialize};
 use ts_rs::TS;
 use utoipa::ToSchema;
 
-use crate::executor::ExecutorConfig;
+use crate::{
+    executor::ExecutorConfig,
+    security::secret_store::{is_masked, mask_secret},
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize, TS, ToSchema)]
 #[ts(export)]
@@ -297,8 +300,11 @@ impl Config {
                         config.analytics_enabled = Some(true);
                     }
 
-                    // Always save back to ensure new fields are written to disk
-                    config.save(config_path)?;
+                    // Always save back to ensure new fields are written to disk,
+                    // unless that would drop secrets not yet moved to the secret store
+                    if !config.has_secrets() {
+                        config.save(config_path)?;
+                    }
                     Ok(config)
                 }
                 Err(_) => {
@@ -346,7 +352,9 @@ impl Config {
         let config: Config = serde_json::from_value(merged_value)?;
 
         // Save the updated config with any missing defaults
-        config.save(config_path)?;
+        if !config.has_secrets() {
+            config.save(config_path)?;
+        }
 
         Ok(config)
     }
@@ -387,9 +395,57 @@ impl Config {
         Ok(())
     }
 
+    /// Write the config to disk. Secret fields are left out; they live in the
+    /// encrypted secret store and are filled back in at startup.
     pub fn save(&self, config_path: &PathBuf) -> anyhow::Result<()> {
-        let content = serde_json::to_string_pretty(self)?;
+        let mut on_disk = self.clone();
+        for (_, field) in on_disk.secret_fields_mut() {
+            *field = None;
+        }
+        let content = serde_json::to_string_pretty(&on_disk)?;
         std::fs::write(config_path, content)?;
         Ok(())
     }
+
+    /// Fields holding secrets, keyed by their name in the secret store
+    pub fn secret_fields(&self) -> [(&'static str, Option<&str>); 2] {
+        [
+            ("github.pat", self.github.pat.as_deref()),
+            ("github.token", self.github.token.as_deref()),
+        ]
+    }
+
+    fn has_secrets(&self) -> bool {
+        self.secret_fields()
+            .iter()
+            .any(|(_, value)| value.is_some_and(|v| !v.is_empty()))
+    }
+
+    pub fn secret_fields_mut(&mut self) -> [(&'static str, &mut Option<String>); 2] {
+        [
+            ("github.pat", &mut self.github.pat),
+            ("github.token", &mut self.github.token),
+        ]
+    }
+
+    /// Copy of the config safe to send to clients, with secrets masked
+    pub fn masked(&self) -> Self {
+        let mut masked = self.clone();
+        for (_, field) in masked.secret_fields_mut() {
+            if let Some(value) = field.as_deref() {
+                *field = Some(mask_secret(value));
+            }
+        }
+        masked
+    }
+
+    /// Undo `masked` for secrets a client sent back without editing them
+    pub fn keep_masked_secrets(&mut self, current: &Config) {
+        let current = current.secret_fields();
+        for ((_, field), (_, current_value)) in self.secret_fields_mut().into_iter().zip(current) {
+            if field.as_deref().is_some_and(is_masked) {
+                *field = current_value.map(str::to_string);
+            }
+        }
+    }
 }
@@ -4,7 +4,7 @@ use sqlx::{FromRow, SqlitePool, Type};
 use ts_rs::TS;
 use uuid::Uuid;
 
-use crate::app_state::ExecutionType;
+use crate::{app_state::ExecutionType, services::execution_env::redact_output};
 
 /// Filter out stderr boundary markers from output
 fn filter_stderr_boundary_markers(stderr: &Option<String>) -> Option<String> {
@@ -366,6 +366,8 @@ impl ExecutionProcess {
         id: Uuid,
         stdout_append: &str,
     ) -> Result<(), sqlx::Error> {
+        let stdout_append = redact_output(id, stdout_append);
+        let stdout_append: &str = &stdout_append;
         sqlx::query!(
             "UPDATE execution_processes SET stdout = COALESCE(stdout, '') || $1, updated_at = datetime('now') WHERE id = $2",
             stdout_append,
@@ -383,6 +385,8 @@ impl ExecutionProcess {
         id: Uuid,
         stderr_append: &str,
     ) -> Result<(), sqlx::Error> {
+        let stderr_append = redact_output(id, stderr_append);
+        let stderr_append: &str = &stderr_append;
         sqlx::query!(
             "UPDATE execution_processes SET stderr = COALESCE(stderr, '') || $1, updated_at = datetime('now') WHERE id = $2",
             stderr_append,
@@ -0,0 +1,186 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{SqlitePool, Type};
+use ts_rs::TS;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// How a coding agent talks to an MCP server
+#[derive(
+    Debug, Clone, Copy, Default, Type, Serialize, Deserialize, PartialEq, Eq, TS, ToSchema,
+)]
+#[sqlx(type_name = "mcp_transport", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+#[ts(export)]
+pub enum McpTransport {
+    #[default]
+    Stdio,
+    Http,
+    Sse,
+}
+
+/// An MCP server given to the coding agents of a project. Without `user_id` it
+/// applies to everyone; otherwise only to that user's attempts, replacing the
+/// project server of the same name. Environment values and headers live in the
+/// secret store.
+#[derive(Debug, Clone, Serialize, Deserialize, TS, ToSchema)]
+#[ts(export)]
+pub struct McpServer {
+    pub id: Uuid,
+    pub project_id: Uuid,
+    pub user_id: Option<Uuid>,
+    pub name: String,
+    pub transport: McpTransport,
+    pub command: Option<String>,
+    pub args: Vec<String>,
+    pub url: Option<String>,
+    pub enabled: bool,
+
+    #[ts(type = "Date")]
+    #[schema(value_type = String, format = DateTime)]
+    pub created_at: DateTime<Utc>,
+    #[ts(type = "Date")]
+    #[schema(value_type = String, format = DateTime)]
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Fields of a server definition, validated by `services::mcp_servers`
+#[derive(Debug, Clone)]
+pub struct UpsertMcpServer {
+    pub name: String,
+    pub transport: McpTransport,
+    pub command: Option<String>,
+    pub args: Vec<String>,
+    pub url: Option<String>,
+    pub enabled: bool,
+}
+
+struct McpServerRow {
+    id: Uuid,
+    project_id: Uuid,
+    user_id: Option<Uuid>,
+    name: String,
+    transport: McpTransport,
+    command: Option<String>,
+    args: String,
+    url: Option<String>,
+    enabled: bool,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl From<McpServerRow> for McpServer {
+    fn from(row: McpServerRow) -> Self {
+        Self {
+            id: row.id,
+            project_id: row.project_id,
+            user_id: row.user_id,
+            name: row.name,
+            transport: row.transport,
+            command: row.command,
+            args: serde_json::from_str(&row.args).unwrap_or_default(),
+            url: row.url,
+            enabled: row.enabled,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        }
+    }
+}
+
+impl McpServer {
+    /// The project's shared servers and `user_id`'s own, ordered by name
+    pub async fn find_for_project(
+        pool: &SqlitePool,
+        project_id: Uuid,
+        user_id: Option<Uuid>,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        let rows = sqlx::query_as!(
+            McpServerRow,
+            r#"SELECT id as "id!: Uuid", project_id as "project_id!: Uuid", user_id as "user_id: Uuid", name, transport as "transport!: McpTransport", command, args, url, enabled as "enabled!: bool", created_at as "created_at!: DateTime<Utc>", updated_at as "updated_at!: DateTime<Utc>"
+               FROM mcp_servers
+               WHERE project_id = $1 AND (user_id IS NULL OR user_id = $2)
+               ORDER BY name ASC, user_id IS NOT NULL"#,
+            project_id,
+            user_id
+        )
+        .fetch_all(pool)
+        .await?;
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    pub async fn find_by_name(
+        pool: &SqlitePool,
+        project_id: Uuid,
+        user_id: Option<Uuid>,
+        name: &str,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        let row = sqlx::query_as!(
+            McpServerRow,
+            r#"SELECT id as "id!: Uuid", project_id as "project_id!: Uuid", user_id as "user_id: Uuid", name, transport as "transport!: McpTransport", command, args, url, enabled as "enabled!: bool", created_at as "created_at!: DateTime<Utc>", updated_at as "updated_at!: DateTime<Utc>"
+               FROM mcp_servers
+               WHERE project_id = $1 AND user_id IS $2 AND name = $3"#,
+            project_id,
+            user_id,
+            name
+        )
+        .fetch_optional(pool)
+        .await?;
+        Ok(row.map(Into::into))
+    }
+
+    /// Create the server, or replace the definition of the one with the same
+    /// name in the same scope
+    pub async fn upsert(
+        pool: &SqlitePool,
+        project_id: Uuid,
+        user_id: Option<Uuid>,
+        data: &UpsertMcpServer,
+    ) -> Result<Self, sqlx::Error> {
+        let args = serde_json::to_string(&data.args).unwrap_or_else(|_| "[]".to_string());
+        if let Some(existing) = Self::find_by_name(pool, project_id, user_id, &data.name).await? {
+            let row = sqlx::query_as!(
+                McpServerRow,
+                r#"UPDATE mcp_servers
+                   SET transport = $2, command = $3, args = $4, url = $5, enabled = $6, updated_at = datetime('now', 'subsec')
+                   WHERE id = $1
+                   RETURNING id as "id!: Uuid", project_id as "project_id!: Uuid", user_id as "user_id: Uuid", name, transport as "transport!: McpTransport", command, args, url, enabled as "enabled!: bool", created_at as "created_at!: DateTime<Utc>", updated_at as "updated_at!: DateTime<Utc>""#,
+                existing.id,
+                data.transport,
+                data.command,
+                args,
+                data.url,
+                data.enabled
+            )
+            .fetch_one(pool)
+            .await?;
+            return Ok(row.into());
+        }
+
+        let id = Uuid::new_v4();
+        let row = sqlx::query_as!(
+            McpServerRow,
+            r#"INSERT INTO mcp_servers (id, project_id, user_id, name, transport, command, args, url, enabled)
+               VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+               RETURNING id as "id!: Uuid", project_id as "project_id!: Uuid", user_id as "user_id: Uuid", name, transport as "transport!: McpTransport", command, args, url, enabled as "enabled!: bool", created_at as "created_at!: DateTime<Utc>", updated_at as "updated_at!: DateTime<Utc>""#,
+            id,
+            project_id,
+            user_id,
+            data.name,
+            data.transport,
+            data.command,
+            args,
+            data.url,
+            data.enabled
+        )
+        .fetch_one(pool)
+        .await?;
+        Ok(row.into())
+    }
+
+    pub async fn delete(pool: &SqlitePool, id: Uuid) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query!("DELETE FROM mcp_servers WHERE id = $1", id)
+            .execute(pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+}
@@ -1,15 +1,25 @@
 pub mod api_response;
+pub mod auth_identity;
 pub mod config;
 pub mod execution_process;
 pub mod executor_session;
 pub mod github_whitelist;
+pub mod mcp_server;
 pub mod project;
+pub mod pull_request;
+pub mod secret;
 pub mod task;
 pub mod task_attempt;
+pub mod task_dependency;
 pub mod task_template;
 pub mod user;
 // pub mod user_preferences;
+pub mod user_notification_channel;
+pub mod user_notification_rule;
 pub mod user_session;
+pub mod webhook;
+pub mod whatsapp_message;
+pub mod whatsapp_number_verification;
 
 pub use api_response::ApiResponse;
 pub use config::Config;
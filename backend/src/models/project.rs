@@ -121,6 +121,26 @@ pub struct CreateBranch {
     pub base_branch: Option<String>,
 }
 
+/// Whether coding agents get a per-attempt config for the Forge MCP server
+#[derive(Debug, Serialize, Deserialize, TS, ToSchema)]
+#[ts(export)]
+pub struct McpInjectionSettings {
+    pub enabled: bool,
+    /// Executors whose CLI accepts the injected config; others keep using
+    /// their global MCP configuration
+    #[serde(default)]
+    pub supported_executors: Vec<String>,
+}
+
+/// Whether Forge addresses PR review comments without being asked
+#[derive(Debug, Serialize, Deserialize, TS, ToSchema)]
+#[ts(export)]
+pub struct ReviewAutomationSettings {
+    /// Start a follow-up for new review comments as soon as a review is
+    /// submitted; needs GitHub webhooks
+    pub auto_address_comments: bool,
+}
+
 impl Project {
     // Helper function to parse UUID from BLOB data
     fn parse_uuid_from_blob(blob: &[u8]) -> Uuid {
@@ -350,6 +370,62 @@ impl Project {
         Ok(result.rows_affected())
     }
 
+    /// Whether coding agents of the project get the Forge MCP server injected
+    pub async fn mcp_injection_enabled(pool: &SqlitePool, id: Uuid) -> Result<bool, sqlx::Error> {
+        let row = sqlx::query!(
+            r#"SELECT inject_mcp_server as "inject_mcp_server!: bool" FROM projects WHERE id = $1"#,
+            id
+        )
+        .fetch_optional(pool)
+        .await?;
+        Ok(row.is_some_and(|row| row.inject_mcp_server))
+    }
+
+    pub async fn set_mcp_injection(
+        pool: &SqlitePool,
+        id: Uuid,
+        enabled: bool,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"UPDATE projects SET inject_mcp_server = $2 WHERE id = $1"#,
+            id,
+            enabled
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Whether review comments on the project's PRs are addressed as soon as
+    /// a review is submitted
+    pub async fn auto_address_review_comments_enabled(
+        pool: &SqlitePool,
+        id: Uuid,
+    ) -> Result<bool, sqlx::Error> {
+        let row = sqlx::query!(
+            r#"SELECT auto_address_review_comments as "auto_address_review_comments!: bool" FROM projects WHERE id = $1"#,
+            id
+        )
+        .fetch_optional(pool)
+        .await?;
+        Ok(row.is_some_and(|row| row.auto_address_review_comments))
+    }
+
+    pub async fn set_auto_address_review_comments(
+        pool: &SqlitePool,
+        id: Uuid,
+        enabled: bool,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"UPDATE projects SET auto_address_review_comments = $2 WHERE id = $1"#,
+            id,
+            enabled
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
     pub async fn exists(pool: &SqlitePool, id: Uuid) -> Result<bool, sqlx::Error> {
         let result = sqlx::query!(
             r#"
@@ -0,0 +1,456 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{SqlitePool, Type};
+use ts_rs::TS;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, Type, Serialize, Deserialize, PartialEq, Eq, TS, ToSchema)]
+#[sqlx(type_name = "pr_review_state", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum PrReviewState {
+    Approved,
+    ChangesRequested,
+    Commented,
+    Dismissed,
+}
+
+/// Overall review outcome of a pull request
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, TS, ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum PrReviewDecision {
+    Approved,
+    ChangesRequested,
+}
+
+/// Combined outcome of a pull request's CI checks
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, TS, ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum PrChecksStatus {
+    Pending,
+    Passing,
+    Failing,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS, ToSchema)]
+#[ts(export)]
+pub struct PrReview {
+    pub id: Uuid,
+    pub task_attempt_id: Uuid,
+    #[ts(type = "number")]
+    pub github_id: i64,
+    pub reviewer: String,
+    pub state: PrReviewState,
+    pub body: Option<String>,
+
+    #[ts(type = "Date")]
+    #[schema(value_type = String, format = DateTime)]
+    pub submitted_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS, ToSchema)]
+#[ts(export)]
+pub struct PrReviewComment {
+    pub id: Uuid,
+    pub task_attempt_id: Uuid,
+    #[ts(type = "number")]
+    pub github_id: i64,
+    #[ts(type = "number | null")]
+    pub review_github_id: Option<i64>,
+    /// The comment this one replies to, in the same thread
+    #[ts(type = "number | null")]
+    pub in_reply_to_github_id: Option<i64>,
+    pub author: String,
+    pub body: String,
+    pub path: String,
+    /// Line in the PR's head; `None` once the code it was on changed
+    #[ts(type = "number | null")]
+    pub line: Option<i64>,
+    pub diff_hunk: Option<String>,
+    pub url: String,
+
+    #[ts(type = "Date")]
+    #[schema(value_type = String, format = DateTime)]
+    pub created_at: DateTime<Utc>,
+    #[ts(type = "Date")]
+    #[schema(value_type = String, format = DateTime)]
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS, ToSchema)]
+#[ts(export)]
+pub struct PrCheckRun {
+    pub id: Uuid,
+    pub task_attempt_id: Uuid,
+    pub name: String,
+    pub head_sha: String,
+    /// queued, in_progress or completed
+    pub status: String,
+    /// GitHub's conclusion once completed, such as success or failure
+    pub conclusion: Option<String>,
+    pub url: Option<String>,
+
+    #[ts(type = "Date")]
+    #[schema(value_type = String, format = DateTime)]
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Everything known about an attempt's pull request
+#[derive(Debug, Clone, Serialize, TS, ToSchema)]
+#[ts(export)]
+pub struct PullRequestState {
+    pub pr_url: Option<String>,
+    #[ts(type = "number | null")]
+    pub pr_number: Option<i64>,
+    pub pr_status: Option<String>,
+    pub review_decision: Option<PrReviewDecision>,
+    pub checks_status: Option<PrChecksStatus>,
+    pub reviews: Vec<PrReview>,
+    pub comments: Vec<PrReviewComment>,
+    pub checks: Vec<PrCheckRun>,
+}
+
+impl PrReview {
+    pub async fn find_by_attempt_id(
+        pool: &SqlitePool,
+        task_attempt_id: Uuid,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as!(
+            PrReview,
+            r#"SELECT id as "id!: Uuid", task_attempt_id as "task_attempt_id!: Uuid", github_id, reviewer, state as "state!: PrReviewState", body, submitted_at as "submitted_at!: DateTime<Utc>"
+               FROM pr_reviews
+               WHERE task_attempt_id = $1
+               ORDER BY submitted_at ASC"#,
+            task_attempt_id
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    pub async fn upsert(
+        pool: &SqlitePool,
+        task_attempt_id: Uuid,
+        github_id: i64,
+        reviewer: &str,
+        state: PrReviewState,
+        body: Option<&str>,
+        submitted_at: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error> {
+        let id = Uuid::new_v4();
+        sqlx::query!(
+            r#"INSERT INTO pr_reviews (id, task_attempt_id, github_id, reviewer, state, body, submitted_at)
+               VALUES ($1, $2, $3, $4, $5, $6, $7)
+               ON CONFLICT(task_attempt_id, github_id) DO UPDATE
+               SET reviewer = excluded.reviewer, state = excluded.state, body = excluded.body, submitted_at = excluded.submitted_at"#,
+            id,
+            task_attempt_id,
+            github_id,
+            reviewer,
+            state,
+            body,
+            submitted_at
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+}
+
+/// Fields of a review comment as GitHub reports them
+#[derive(Debug, Clone)]
+pub struct UpsertPrReviewComment {
+    pub github_id: i64,
+    pub review_github_id: Option<i64>,
+    pub in_reply_to_github_id: Option<i64>,
+    pub author: String,
+    pub body: String,
+    pub path: String,
+    pub line: Option<i64>,
+    pub diff_hunk: Option<String>,
+    pub url: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl PrReviewComment {
+    pub async fn find_by_attempt_id(
+        pool: &SqlitePool,
+        task_attempt_id: Uuid,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as!(
+            PrReviewComment,
+            r#"SELECT id as "id!: Uuid", task_attempt_id as "task_attempt_id!: Uuid", github_id, review_github_id, in_reply_to_github_id, author, body, path, line, diff_hunk, url, created_at as "created_at!: DateTime<Utc>", updated_at as "updated_at!: DateTime<Utc>"
+               FROM pr_review_comments
+               WHERE task_attempt_id = $1
+               ORDER BY created_at ASC"#,
+            task_attempt_id
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    pub async fn upsert(
+        pool: &SqlitePool,
+        task_attempt_id: Uuid,
+        data: &UpsertPrReviewComment,
+    ) -> Result<(), sqlx::Error> {
+        let id = Uuid::new_v4();
+        sqlx::query!(
+            r#"INSERT INTO pr_review_comments (id, task_attempt_id, github_id, review_github_id, in_reply_to_github_id, author, body, path, line, diff_hunk, url, created_at, updated_at)
+               VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+               ON CONFLICT(task_attempt_id, github_id) DO UPDATE
+               SET body = excluded.body, line = excluded.line, diff_hunk = excluded.diff_hunk, updated_at = excluded.updated_at"#,
+            id,
+            task_attempt_id,
+            data.github_id,
+            data.review_github_id,
+            data.in_reply_to_github_id,
+            data.author,
+            data.body,
+            data.path,
+            data.line,
+            data.diff_hunk,
+            data.url,
+            data.created_at,
+            data.updated_at
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn delete_by_github_id(
+        pool: &SqlitePool,
+        task_attempt_id: Uuid,
+        github_id: i64,
+    ) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query!(
+            "DELETE FROM pr_review_comments WHERE task_attempt_id = $1 AND github_id = $2",
+            task_attempt_id,
+            github_id
+        )
+        .execute(pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+}
+
+impl PrCheckRun {
+    pub async fn find_by_attempt_id(
+        pool: &SqlitePool,
+        task_attempt_id: Uuid,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as!(
+            PrCheckRun,
+            r#"SELECT id as "id!: Uuid", task_attempt_id as "task_attempt_id!: Uuid", name, head_sha, status, conclusion, url, updated_at as "updated_at!: DateTime<Utc>"
+               FROM pr_check_runs
+               WHERE task_attempt_id = $1
+               ORDER BY name ASC"#,
+            task_attempt_id
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Record the latest run of the check named `name`
+    pub async fn upsert(
+        pool: &SqlitePool,
+        task_attempt_id: Uuid,
+        name: &str,
+        head_sha: &str,
+        status: &str,
+        conclusion: Option<&str>,
+        url: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        let id = Uuid::new_v4();
+        sqlx::query!(
+            r#"INSERT INTO pr_check_runs (id, task_attempt_id, name, head_sha, status, conclusion, url)
+               VALUES ($1, $2, $3, $4, $5, $6, $7)
+               ON CONFLICT(task_attempt_id, name) DO UPDATE
+               SET head_sha = excluded.head_sha, status = excluded.status, conclusion = excluded.conclusion,
+                   url = excluded.url, updated_at = datetime('now', 'subsec')"#,
+            id,
+            task_attempt_id,
+            name,
+            head_sha,
+            status,
+            conclusion,
+            url
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Forget checks of commits other than the PR's new head
+    pub async fn delete_stale(
+        pool: &SqlitePool,
+        task_attempt_id: Uuid,
+        head_sha: &str,
+    ) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query!(
+            "DELETE FROM pr_check_runs WHERE task_attempt_id = $1 AND head_sha != $2",
+            task_attempt_id,
+            head_sha
+        )
+        .execute(pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Type, Serialize, Deserialize, PartialEq, Eq, TS, ToSchema)]
+#[sqlx(type_name = "pr_comment_followup_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+#[ts(export)]
+pub enum PrCommentFollowupStatus {
+    /// The follow-up execution has not finished yet
+    Running,
+    /// The result was pushed and the threads replied to
+    Pushed,
+    Failed,
+}
+
+/// A review thread a follow-up addressed
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, TS, ToSchema)]
+#[ts(export)]
+pub struct AddressedThread {
+    /// First comment of the thread, which replies go to
+    #[ts(type = "number")]
+    pub root_comment_id: i64,
+    /// Latest comment of the thread once addressed, Forge's reply included
+    #[ts(type = "number")]
+    pub last_comment_id: i64,
+}
+
+/// A follow-up execution started to address an attempt's PR review comments
+#[derive(Debug, Clone, Serialize, Deserialize, TS, ToSchema)]
+#[ts(export)]
+pub struct PrCommentFollowup {
+    pub id: Uuid,
+    pub task_attempt_id: Uuid,
+    pub status: PrCommentFollowupStatus,
+    pub threads: Vec<AddressedThread>,
+    pub error: Option<String>,
+    /// The attempt's HEAD when the follow-up started
+    pub base_commit: Option<String>,
+
+    #[ts(type = "Date")]
+    #[schema(value_type = String, format = DateTime)]
+    pub created_at: DateTime<Utc>,
+    #[ts(type = "Date | null")]
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+struct PrCommentFollowupRow {
+    id: Uuid,
+    task_attempt_id: Uuid,
+    status: PrCommentFollowupStatus,
+    threads: String,
+    error: Option<String>,
+    base_commit: Option<String>,
+    created_at: DateTime<Utc>,
+    completed_at: Option<DateTime<Utc>>,
+}
+
+impl From<PrCommentFollowupRow> for PrCommentFollowup {
+    fn from(row: PrCommentFollowupRow) -> Self {
+        Self {
+            id: row.id,
+            task_attempt_id: row.task_attempt_id,
+            status: row.status,
+            threads: serde_json::from_str(&row.threads).unwrap_or_default(),
+            error: row.error,
+            base_commit: row.base_commit,
+            created_at: row.created_at,
+            completed_at: row.completed_at,
+        }
+    }
+}
+
+impl PrCommentFollowup {
+    pub async fn find_by_attempt_id(
+        pool: &SqlitePool,
+        task_attempt_id: Uuid,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        let rows = sqlx::query_as!(
+            PrCommentFollowupRow,
+            r#"SELECT id as "id!: Uuid", task_attempt_id as "task_attempt_id!: Uuid", status as "status!: PrCommentFollowupStatus", threads, error, base_commit, created_at as "created_at!: DateTime<Utc>", completed_at as "completed_at: DateTime<Utc>"
+               FROM pr_comment_followups
+               WHERE task_attempt_id = $1
+               ORDER BY created_at DESC"#,
+            task_attempt_id
+        )
+        .fetch_all(pool)
+        .await?;
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    /// The attempt's follow-up whose execution has not finished, if any
+    pub async fn find_running_by_attempt_id(
+        pool: &SqlitePool,
+        task_attempt_id: Uuid,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        let row = sqlx::query_as!(
+            PrCommentFollowupRow,
+            r#"SELECT id as "id!: Uuid", task_attempt_id as "task_attempt_id!: Uuid", status as "status!: PrCommentFollowupStatus", threads, error, base_commit, created_at as "created_at!: DateTime<Utc>", completed_at as "completed_at: DateTime<Utc>"
+               FROM pr_comment_followups
+               WHERE task_attempt_id = $1 AND status = 'running'
+               ORDER BY created_at DESC
+               LIMIT 1"#,
+            task_attempt_id
+        )
+        .fetch_optional(pool)
+        .await?;
+        Ok(row.map(Into::into))
+    }
+
+    pub async fn create(
+        pool: &SqlitePool,
+        task_attempt_id: Uuid,
+        threads: &[AddressedThread],
+        base_commit: Option<&str>,
+    ) -> Result<Self, sqlx::Error> {
+        let id = Uuid::new_v4();
+        let threads = serde_json::to_string(threads).unwrap_or_else(|_| "[]".to_string());
+        let row = sqlx::query_as!(
+            PrCommentFollowupRow,
+            r#"INSERT INTO pr_comment_followups (id, task_attempt_id, threads, base_commit)
+               VALUES ($1, $2, $3, $4)
+               RETURNING id as "id!: Uuid", task_attempt_id as "task_attempt_id!: Uuid", status as "status!: PrCommentFollowupStatus", threads, error, base_commit, created_at as "created_at!: DateTime<Utc>", completed_at as "completed_at: DateTime<Utc>""#,
+            id,
+            task_attempt_id,
+            threads,
+            base_commit
+        )
+        .fetch_one(pool)
+        .await?;
+        Ok(row.into())
+    }
+
+    pub async fn complete(
+        pool: &SqlitePool,
+        id: Uuid,
+        status: PrCommentFollowupStatus,
+        threads: &[AddressedThread],
+        error: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        let threads = serde_json::to_string(threads).unwrap_or_else(|_| "[]".to_string());
+        sqlx::query!(
+            r#"UPDATE pr_comment_followups
+               SET status = $2, threads = $3, error = $4, completed_at = datetime('now', 'subsec')
+               WHERE id = $1"#,
+            id,
+            status,
+            threads,
+            error
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,169 @@
+use chrono::{DateTime, Utc};
+use sqlx::{FromRow, Sqlite, SqlitePool};
+use uuid::Uuid;
+
+/// An encrypted value in the `secrets` table. Only `SecretStore` should read
+/// or write these rows; the ciphertext is meaningless without the keyring.
+#[derive(Debug, Clone, FromRow)]
+pub struct Secret {
+    pub id: Uuid,
+    pub scope: String,
+    pub name: String,
+    pub key_version: i64,
+    pub ciphertext: String,
+    #[allow(dead_code)]
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Secret {
+    pub async fn find(
+        pool: &SqlitePool,
+        scope: &str,
+        name: &str,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        sqlx::query_as!(
+            Secret,
+            r#"SELECT
+                id as "id!: Uuid",
+                scope,
+                name,
+                key_version as "key_version!: i64",
+                ciphertext,
+                created_at as "created_at!: DateTime<Utc>",
+                updated_at as "updated_at!: DateTime<Utc>"
+            FROM secrets
+            WHERE scope = $1 AND name = $2"#,
+            scope,
+            name
+        )
+        .fetch_optional(pool)
+        .await
+    }
+
+    /// All secrets in a scope, ordered by name
+    pub async fn find_by_scope(pool: &SqlitePool, scope: &str) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as!(
+            Secret,
+            r#"SELECT
+                id as "id!: Uuid",
+                scope,
+                name,
+                key_version as "key_version!: i64",
+                ciphertext,
+                created_at as "created_at!: DateTime<Utc>",
+                updated_at as "updated_at!: DateTime<Utc>"
+            FROM secrets
+            WHERE scope = $1
+            ORDER BY name ASC"#,
+            scope
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    /// A batch of secrets sealed with a key other than `key_version`
+    pub async fn find_not_at_version(
+        pool: &SqlitePool,
+        key_version: i64,
+        limit: i64,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as!(
+            Secret,
+            r#"SELECT
+                id as "id!: Uuid",
+                scope,
+                name,
+                key_version as "key_version!: i64",
+                ciphertext,
+                created_at as "created_at!: DateTime<Utc>",
+                updated_at as "updated_at!: DateTime<Utc>"
+            FROM secrets
+            WHERE key_version != $1
+            LIMIT $2"#,
+            key_version,
+            limit
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Count secrets per key version, for rotation status
+    pub async fn count_by_key_version<'e, E>(executor: E) -> Result<Vec<(i64, i64)>, sqlx::Error>
+    where
+        E: sqlx::Executor<'e, Database = Sqlite>,
+    {
+        let rows = sqlx::query!(
+            r#"SELECT key_version as "key_version!: i64", COUNT(*) as "count!: i64"
+               FROM secrets
+               GROUP BY key_version
+               ORDER BY key_version ASC"#
+        )
+        .fetch_all(executor)
+        .await?;
+        Ok(rows.into_iter().map(|r| (r.key_version, r.count)).collect())
+    }
+
+    /// Insert a secret or replace the value of an existing one
+    pub async fn upsert<'e, E>(
+        executor: E,
+        scope: &str,
+        name: &str,
+        key_version: i64,
+        ciphertext: &str,
+    ) -> Result<(), sqlx::Error>
+    where
+        E: sqlx::Executor<'e, Database = Sqlite>,
+    {
+        let id = Uuid::new_v4();
+        sqlx::query!(
+            r#"INSERT INTO secrets (id, scope, name, key_version, ciphertext)
+               VALUES ($1, $2, $3, $4, $5)
+               ON CONFLICT (scope, name) DO UPDATE SET
+                key_version = excluded.key_version,
+                ciphertext = excluded.ciphertext,
+                updated_at = datetime('now', 'subsec')"#,
+            id,
+            scope,
+            name,
+            key_version,
+            ciphertext
+        )
+        .execute(executor)
+        .await?;
+        Ok(())
+    }
+
+    /// Re-seal a row under a new key. Only applies if the row is still at
+    /// `old_version`, so a concurrent `upsert` is never overwritten with a
+    /// stale value. Returns whether the row was updated.
+    pub async fn update_ciphertext(
+        pool: &SqlitePool,
+        id: Uuid,
+        old_version: i64,
+        new_version: i64,
+        ciphertext: &str,
+    ) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            "UPDATE secrets SET key_version = $1, ciphertext = $2 WHERE id = $3 AND key_version = $4",
+            new_version,
+            ciphertext,
+            id,
+            old_version
+        )
+        .execute(pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn delete(pool: &SqlitePool, scope: &str, name: &str) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query!(
+            "DELETE FROM secrets WHERE scope = $1 AND name = $2",
+            scope,
+            name
+        )
+        .execute(pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+}
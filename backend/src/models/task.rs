@@ -1,6 +1,6 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use sqlx::{FromRow, SqlitePool, Type};
+use sqlx::{FromRow, Sqlite, SqlitePool, Type};
 use ts_rs::TS;
 use utoipa::ToSchema;
 use uuid::Uuid;
@@ -277,11 +277,14 @@ ORDER BY t.created_at DESC"#,
         .await
     }
 
-    pub async fn create(
-        pool: &SqlitePool,
+    pub async fn create<'e, E>(
+        executor: E,
         data: &CreateTask,
         task_id: Uuid,
-    ) -> Result<Self, sqlx::Error> {
+    ) -> Result<Self, sqlx::Error>
+    where
+        E: sqlx::Executor<'e, Database = Sqlite>,
+    {
         sqlx::query_as!(
             Task,
             r#"INSERT INTO tasks (id, project_id, title, description, status, wish_id, parent_task_attempt, created_by, assigned_to) 
@@ -297,10 +300,106 @@ ORDER BY t.created_at DESC"#,
             data.created_by,
             data.assigned_to
         )
-        .fetch_one(pool)
+        .fetch_one(executor)
+        .await
+    }
+
+    /// Executor new attempts of the task use when none is requested
+    pub async fn preferred_executor(
+        pool: &SqlitePool,
+        id: Uuid,
+    ) -> Result<Option<String>, sqlx::Error> {
+        let row = sqlx::query!(r#"SELECT executor FROM tasks WHERE id = $1"#, id)
+            .fetch_optional(pool)
+            .await?;
+        Ok(row.and_then(|row| row.executor))
+    }
+
+    pub async fn set_preferred_executor<'e, E>(
+        executor: E,
+        id: Uuid,
+        preferred_executor: Option<&str>,
+    ) -> Result<(), sqlx::Error>
+    where
+        E: sqlx::Executor<'e, Database = Sqlite>,
+    {
+        sqlx::query!(
+            r#"UPDATE tasks SET executor = $2 WHERE id = $1"#,
+            id,
+            preferred_executor
+        )
+        .execute(executor)
+        .await?;
+        Ok(())
+    }
+
+    /// Number of the GitHub issue the task was imported from
+    pub async fn github_issue_number(
+        pool: &SqlitePool,
+        id: Uuid,
+    ) -> Result<Option<i64>, sqlx::Error> {
+        let row = sqlx::query!(r#"SELECT github_issue_number FROM tasks WHERE id = $1"#, id)
+            .fetch_optional(pool)
+            .await?;
+        Ok(row.and_then(|row| row.github_issue_number))
+    }
+
+    pub async fn set_github_issue_number<'e, E>(
+        executor: E,
+        id: Uuid,
+        github_issue_number: Option<i64>,
+    ) -> Result<(), sqlx::Error>
+    where
+        E: sqlx::Executor<'e, Database = Sqlite>,
+    {
+        sqlx::query!(
+            r#"UPDATE tasks SET github_issue_number = $2 WHERE id = $1"#,
+            id,
+            github_issue_number
+        )
+        .execute(executor)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn find_by_github_issue_number(
+        pool: &SqlitePool,
+        project_id: Uuid,
+        github_issue_number: i64,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        sqlx::query_as!(
+            Task,
+            r#"SELECT id as "id!: Uuid", project_id as "project_id!: Uuid", title, description, status as "status!: TaskStatus", wish_id, parent_task_attempt as "parent_task_attempt: Uuid", created_by as "created_by: Uuid", assigned_to as "assigned_to: Uuid", created_at as "created_at!: DateTime<Utc>", updated_at as "updated_at!: DateTime<Utc>"
+               FROM tasks
+               WHERE project_id = $1 AND github_issue_number = $2"#,
+            project_id,
+            github_issue_number
+        )
+        .fetch_optional(pool)
         .await
     }
 
+    /// IDs of the project's tasks imported from GitHub issues, with the issue
+    /// numbers
+    pub async fn find_github_issue_numbers_by_project_id(
+        pool: &SqlitePool,
+        project_id: Uuid,
+    ) -> Result<Vec<(Uuid, i64)>, sqlx::Error> {
+        let rows = sqlx::query!(
+            r#"SELECT id as "id!: Uuid", github_issue_number as "github_issue_number!: i64"
+               FROM tasks
+               WHERE project_id = $1 AND github_issue_number IS NOT NULL
+               ORDER BY github_issue_number ASC"#,
+            project_id
+        )
+        .fetch_all(pool)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| (row.id, row.github_issue_number))
+            .collect())
+    }
+
     pub async fn update(
         pool: &SqlitePool,
         id: Uuid,
@@ -376,6 +475,23 @@ ORDER BY t.created_at DESC"#,
         Ok(result.is_some())
     }
 
+    /// Tasks spawned from a task attempt, oldest first
+    pub async fn find_children_by_attempt_id(
+        pool: &SqlitePool,
+        attempt_id: Uuid,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as!(
+            Task,
+            r#"SELECT id as "id!: Uuid", project_id as "project_id!: Uuid", title, description, status as "status!: TaskStatus", wish_id, parent_task_attempt as "parent_task_attempt: Uuid", created_by as "created_by: Uuid", assigned_to as "assigned_to: Uuid", created_at as "created_at!: DateTime<Utc>", updated_at as "updated_at!: DateTime<Utc>"
+               FROM tasks
+               WHERE parent_task_attempt = $1
+               ORDER BY created_at ASC"#,
+            attempt_id
+        )
+        .fetch_all(pool)
+        .await
+    }
+
     pub async fn find_related_tasks_by_attempt_id(
         pool: &SqlitePool,
         attempt_id: Uuid,
@@ -409,5 +525,43 @@ ORDER BY t.created_at DESC"#,
         .await
     }
 
+    /// In-progress and in-review tasks a user created or is assigned to,
+    /// most recently updated first
+    pub async fn find_active_by_user_id(
+        pool: &SqlitePool,
+        user_id: Uuid,
+        limit: i64,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as!(
+            Task,
+            r#"SELECT id as "id!: Uuid", project_id as "project_id!: Uuid", title, description, status as "status!: TaskStatus", wish_id, parent_task_attempt as "parent_task_attempt: Uuid", created_by as "created_by: Uuid", assigned_to as "assigned_to: Uuid", created_at as "created_at!: DateTime<Utc>", updated_at as "updated_at!: DateTime<Utc>"
+               FROM tasks
+               WHERE (created_by = $1 OR assigned_to = $1) AND status IN ('inprogress', 'inreview')
+               ORDER BY updated_at DESC
+               LIMIT $2"#,
+            user_id,
+            limit
+        )
+        .fetch_all(pool)
+        .await
+    }
 
+    /// Tasks whose ID starts with `prefix`, given as lowercase hex digits
+    /// without dashes
+    pub async fn find_by_id_prefix(
+        pool: &SqlitePool,
+        prefix: &str,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        let pattern = format!("{}%", prefix);
+        sqlx::query_as!(
+            Task,
+            r#"SELECT id as "id!: Uuid", project_id as "project_id!: Uuid", title, description, status as "status!: TaskStatus", wish_id, parent_task_attempt as "parent_task_attempt: Uuid", created_by as "created_by: Uuid", assigned_to as "assigned_to: Uuid", created_at as "created_at!: DateTime<Utc>", updated_at as "updated_at!: DateTime<Utc>"
+               FROM tasks
+               WHERE lower(hex(id)) LIKE $1
+               LIMIT 2"#,
+            pattern
+        )
+        .fetch_all(pool)
+        .await
+    }
 }
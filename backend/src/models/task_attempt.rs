@@ -11,8 +11,9 @@ use uuid::Uuid;
 
 use super::{project::Project, task::Task};
 use crate::services::{
-    CreatePrRequest, GitHubRepoInfo, GitHubService, GitHubServiceError, GitService,
-    GitServiceError, ProcessService,
+    forge::{self, ForgeError, ForgeRepo},
+    github_issues, CreatePrRequest, GitHubServiceError, GitService, GitServiceError,
+    ProcessService,
 };
 
 // Constants for git diff operations
@@ -25,6 +26,7 @@ pub enum TaskAttemptError {
     Git(GitError),
     GitService(GitServiceError),
     GitHubService(GitHubServiceError),
+    Forge(ForgeError),
     TaskNotFound,
     ProjectNotFound,
     ValidationError(String),
@@ -38,6 +40,7 @@ impl std::fmt::Display for TaskAttemptError {
             TaskAttemptError::Git(e) => write!(f, "Git error: {}", e),
             TaskAttemptError::GitService(e) => write!(f, "Git service error: {}", e),
             TaskAttemptError::GitHubService(e) => write!(f, "GitHub service error: {}", e),
+            TaskAttemptError::Forge(e) => write!(f, "Forge error: {}", e),
             TaskAttemptError::TaskNotFound => write!(f, "Task not found"),
             TaskAttemptError::ProjectNotFound => write!(f, "Project not found"),
             TaskAttemptError::ValidationError(e) => write!(f, "Validation error: {}", e),
@@ -72,6 +75,12 @@ impl From<GitHubServiceError> for TaskAttemptError {
     }
 }
 
+impl From<ForgeError> for TaskAttemptError {
+    fn from(err: ForgeError) -> Self {
+        TaskAttemptError::Forge(err)
+    }
+}
+
 #[derive(Debug, Clone, Type, Serialize, Deserialize, PartialEq, TS, ToSchema)]
 #[sqlx(type_name = "task_attempt_status", rename_all = "lowercase")]
 #[serde(rename_all = "lowercase")]
@@ -110,7 +119,7 @@ pub struct TaskAttempt {
 #[ts(export)]
 pub struct CreateTaskAttempt {
     pub executor: Option<String>, // Optional executor name (defaults to "echo")
-    pub base_branch: Option<String>, // Optional base branch to checkout (defaults to current HEAD)
+    pub base_branch: Option<String>, // Optional base branch to checkout (defaults to the parent attempt's branch, then current HEAD)
     pub created_by: Option<Uuid>, // User creating this task attempt
 }
 
@@ -125,7 +134,9 @@ pub struct CreatePrParams<'a> {
     pub attempt_id: Uuid,
     pub task_id: Uuid,
     pub project_id: Uuid,
-    pub github_token: &'a str,
+    /// Repository the PR is opened on, from `forge::credentials`
+    pub repo: &'a ForgeRepo,
+    pub token: &'a str,
     pub title: &'a str,
     pub body: Option<&'a str>,
     pub base_branch: Option<&'a str>,
@@ -332,6 +343,39 @@ impl TaskAttempt {
         .await
     }
 
+    /// The attempt whose pull request is at `pr_url`, ignoring case
+    pub async fn find_by_pr_url(
+        pool: &SqlitePool,
+        pr_url: &str,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        sqlx::query_as!(
+            TaskAttempt,
+            r#"SELECT  id                AS "id!: Uuid",
+                       task_id           AS "task_id!: Uuid",
+                       worktree_path,
+                       branch,
+                       merge_commit,
+                       base_branch,
+                       executor,
+                       pr_url,
+                       pr_number,
+                       pr_status,
+                       pr_merged_at      AS "pr_merged_at: DateTime<Utc>",
+                       worktree_deleted  AS "worktree_deleted!: bool",
+                       setup_completed_at AS "setup_completed_at: DateTime<Utc>",
+                       created_by        AS "created_by: Uuid",
+                       created_at        AS "created_at!: DateTime<Utc>",
+                       updated_at        AS "updated_at!: DateTime<Utc>"
+               FROM    task_attempts
+               WHERE   lower(pr_url) = lower($1)
+               ORDER BY created_at DESC
+               LIMIT 1"#,
+            pr_url
+        )
+        .fetch_optional(pool)
+        .await
+    }
+
     pub async fn find_by_task_id(
         pool: &SqlitePool,
         task_id: Uuid,
@@ -469,8 +513,17 @@ impl TaskAttempt {
         // Create GitService instance
         let git_service = GitService::new(&project.git_repo_path)?;
 
+        // Sub-tasks build on their parent attempt's work unless told otherwise
+        let base_branch = match (&data.base_branch, task.parent_task_attempt) {
+            (Some(base_branch), _) => Some(base_branch.clone()),
+            (None, Some(parent_attempt_id)) => TaskAttempt::find_by_id(pool, parent_attempt_id)
+                .await?
+                .map(|parent| parent.branch),
+            (None, None) => None,
+        };
+
         // Determine the resolved base branch name first
-        let resolved_base_branch = if let Some(ref base_branch) = data.base_branch {
+        let resolved_base_branch = if let Some(ref base_branch) = base_branch {
             base_branch.clone()
         } else {
             // Default to current HEAD branch name or "main"
@@ -481,9 +534,15 @@ impl TaskAttempt {
         git_service.create_worktree(
             &task_attempt_branch,
             &worktree_path,
-            data.base_branch.as_deref(),
+            base_branch.as_deref(),
         )?;
 
+        // Fall back to the executor the task was planned with
+        let executor = match &data.executor {
+            Some(executor) => Some(executor.clone()),
+            None => Task::preferred_executor(pool, task_id).await?,
+        };
+
         // Insert the record into the database
         Ok(sqlx::query_as!(
             TaskAttempt,
@@ -496,7 +555,7 @@ impl TaskAttempt {
             task_attempt_branch,
             resolved_base_branch,
             Option::<String>::None, // merge_commit is always None during creation
-            data.executor,
+            executor,
             Option::<String>::None, // pr_url is None during creation
             Option::<i64>::None, // pr_number is None during creation
             Option::<String>::None, // pr_status is None during creation
@@ -883,8 +942,9 @@ impl TaskAttempt {
         Ok(commit_id)
     }
 
-    /// Create a GitHub PR for this task attempt
-    pub async fn create_github_pr(
+    /// Push the attempt's branch and open a pull request (or GitLab merge
+    /// request) for it on the project's forge
+    pub async fn create_pr(
         pool: &SqlitePool,
         params: CreatePrParams<'_>,
     ) -> Result<String, TaskAttemptError> {
@@ -895,36 +955,28 @@ impl TaskAttempt {
 
         // Ensure worktree exists (recreate if needed for cold task support)
         let worktree_path =
-            Self::ensure_worktree_exists(pool, params.attempt_id, params.project_id, "GitHub PR")
-                .await?;
-
-        // Create GitHub service instance
-        let github_service = GitHubService::new(params.github_token)?;
+            Self::ensure_worktree_exists(pool, params.attempt_id, params.project_id, "PR").await?;
 
-        // Use GitService to get the remote URL, then create GitHubRepoInfo
-        let git_service = GitService::new(&ctx.project.git_repo_path)?;
-        let (owner, repo_name) = git_service
-            .get_github_repo_info()
-            .map_err(|e| TaskAttemptError::ValidationError(e.to_string()))?;
-        let repo_info = GitHubRepoInfo { owner, repo_name };
+        let forge = forge::connect(params.repo, params.token)?;
 
-        // Push the branch to GitHub first
-        Self::push_branch_to_github(
-            &ctx.project.git_repo_path,
-            &worktree_path,
+        // Push the branch first
+        GitService::new(&ctx.project.git_repo_path)?.push_to_remote(
+            Path::new(&worktree_path),
             &ctx.task_attempt.branch,
-            params.github_token,
+            params.token,
         )?;
 
-        // Create the PR using GitHub service
+        // PRs of tasks imported from an issue close it once merged
+        let issue_number = Task::github_issue_number(pool, params.task_id).await?;
+
         let pr_request = CreatePrRequest {
             title: params.title.to_string(),
-            body: params.body.map(|s| s.to_string()),
+            body: github_issues::with_closes_reference(params.body, issue_number),
             head_branch: ctx.task_attempt.branch.clone(),
             base_branch: params.base_branch.unwrap_or("main").to_string(),
         };
 
-        let pr_info = github_service.create_pr(&repo_info, &pr_request).await?;
+        let pr_info = forge.create_pull_request(&pr_request).await?;
 
         // Update the task attempt with PR information
         sqlx::query!(
@@ -940,20 +992,6 @@ impl TaskAttempt {
         Ok(pr_info.url)
     }
 
-    /// Push the branch to GitHub remote
-    fn push_branch_to_github(
-        git_repo_path: &str,
-        worktree_path: &str,
-        branch_name: &str,
-        github_token: &str,
-    ) -> Result<(), TaskAttemptError> {
-        // Use GitService to push to GitHub
-        let git_service = GitService::new(git_repo_path)?;
-        git_service
-            .push_to_github(Path::new(worktree_path), branch_name, github_token)
-            .map_err(TaskAttemptError::from)
-    }
-
     /// Update PR status and merge commit
     pub async fn update_pr_status(
         pool: &SqlitePool,
@@ -14,7 +14,11 @@ pub struct User {
     pub email: String,
     pub display_name: Option<String>,
     pub avatar_url: Option<String>,
-    pub github_token: Option<String>, // Encrypted OAuth token
+    /// Legacy plaintext OAuth token. Moved into the secret store at startup
+    /// and never sent to clients.
+    #[serde(skip_serializing)]
+    #[ts(skip)]
+    pub github_token: Option<String>,
     pub is_admin: bool,
     pub is_whitelisted: bool,
     pub last_login_at: Option<DateTime<Utc>>,
@@ -55,7 +59,6 @@ pub struct UpdateUser {
 
 impl User {
     /// Find all users
-    #[allow(dead_code)]
     pub async fn find_all(pool: &SqlitePool) -> Result<Vec<Self>, sqlx::Error> {
         sqlx::query_as!(
             User,
@@ -251,6 +254,17 @@ impl User {
         Ok(())
     }
 
+    /// Drop the plaintext GitHub token once it has been moved to the secret store
+    pub async fn clear_github_token(pool: &SqlitePool, id: Uuid) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "UPDATE users SET github_token = NULL WHERE id = $1",
+            id
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
     /// Delete user
     #[allow(dead_code)]
     pub async fn delete(pool: &SqlitePool, id: Uuid) -> Result<u64, sqlx::Error> {
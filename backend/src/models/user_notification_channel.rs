@@ -0,0 +1,177 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{SqlitePool, Type};
+use ts_rs::TS;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Where a notification channel delivers to
+#[derive(Debug, Clone, Copy, Type, Serialize, Deserialize, PartialEq, Eq, TS, ToSchema)]
+#[sqlx(type_name = "notification_channel_kind", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+#[ts(export)]
+pub enum NotificationChannelKind {
+    Slack,
+    Discord,
+    Email,
+    Ntfy,
+    Gotify,
+    WhatsApp,
+}
+
+impl std::fmt::Display for NotificationChannelKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let kind = match self {
+            NotificationChannelKind::Slack => "slack",
+            NotificationChannelKind::Discord => "discord",
+            NotificationChannelKind::Email => "email",
+            NotificationChannelKind::Ntfy => "ntfy",
+            NotificationChannelKind::Gotify => "gotify",
+            NotificationChannelKind::WhatsApp => "whatsapp",
+        };
+        f.write_str(kind)
+    }
+}
+
+/// A channel a user is notified on. Webhook URLs, passwords and tokens live
+/// in the secret store.
+#[derive(Debug, Clone, Serialize, Deserialize, TS, ToSchema)]
+#[ts(export)]
+pub struct UserNotificationChannel {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    pub kind: NotificationChannelKind,
+    pub settings: BTreeMap<String, String>,
+    pub enabled: bool,
+
+    #[ts(type = "Date")]
+    #[schema(value_type = String, format = DateTime)]
+    pub created_at: DateTime<Utc>,
+    #[ts(type = "Date")]
+    #[schema(value_type = String, format = DateTime)]
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Fields of a channel, validated by `services::notification_channels`
+#[derive(Debug, Clone)]
+pub struct UpsertUserNotificationChannel {
+    pub name: String,
+    pub kind: NotificationChannelKind,
+    pub settings: BTreeMap<String, String>,
+    pub enabled: bool,
+}
+
+struct UserNotificationChannelRow {
+    id: Uuid,
+    user_id: Uuid,
+    name: String,
+    kind: NotificationChannelKind,
+    settings: String,
+    enabled: bool,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl From<UserNotificationChannelRow> for UserNotificationChannel {
+    fn from(row: UserNotificationChannelRow) -> Self {
+        Self {
+            id: row.id,
+            user_id: row.user_id,
+            name: row.name,
+            kind: row.kind,
+            settings: serde_json::from_str(&row.settings).unwrap_or_default(),
+            enabled: row.enabled,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        }
+    }
+}
+
+impl UserNotificationChannel {
+    pub async fn find_by_user_id(
+        pool: &SqlitePool,
+        user_id: Uuid,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        let rows = sqlx::query_as!(
+            UserNotificationChannelRow,
+            r#"SELECT id as "id!: Uuid", user_id as "user_id!: Uuid", name, kind as "kind!: NotificationChannelKind", settings, enabled as "enabled!: bool", created_at as "created_at!: DateTime<Utc>", updated_at as "updated_at!: DateTime<Utc>"
+               FROM user_notification_channels
+               WHERE user_id = $1
+               ORDER BY name ASC"#,
+            user_id
+        )
+        .fetch_all(pool)
+        .await?;
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    pub async fn find_by_name(
+        pool: &SqlitePool,
+        user_id: Uuid,
+        name: &str,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        let row = sqlx::query_as!(
+            UserNotificationChannelRow,
+            r#"SELECT id as "id!: Uuid", user_id as "user_id!: Uuid", name, kind as "kind!: NotificationChannelKind", settings, enabled as "enabled!: bool", created_at as "created_at!: DateTime<Utc>", updated_at as "updated_at!: DateTime<Utc>"
+               FROM user_notification_channels
+               WHERE user_id = $1 AND name = $2"#,
+            user_id,
+            name
+        )
+        .fetch_optional(pool)
+        .await?;
+        Ok(row.map(Into::into))
+    }
+
+    /// Create the channel, or replace the one of the same name
+    pub async fn upsert(
+        pool: &SqlitePool,
+        user_id: Uuid,
+        data: &UpsertUserNotificationChannel,
+    ) -> Result<Self, sqlx::Error> {
+        let settings = serde_json::to_string(&data.settings).unwrap_or_else(|_| "{}".to_string());
+        if let Some(existing) = Self::find_by_name(pool, user_id, &data.name).await? {
+            let row = sqlx::query_as!(
+                UserNotificationChannelRow,
+                r#"UPDATE user_notification_channels
+                   SET kind = $2, settings = $3, enabled = $4, updated_at = datetime('now', 'subsec')
+                   WHERE id = $1
+                   RETURNING id as "id!: Uuid", user_id as "user_id!: Uuid", name, kind as "kind!: NotificationChannelKind", settings, enabled as "enabled!: bool", created_at as "created_at!: DateTime<Utc>", updated_at as "updated_at!: DateTime<Utc>""#,
+                existing.id,
+                data.kind,
+                settings,
+                data.enabled
+            )
+            .fetch_one(pool)
+            .await?;
+            return Ok(row.into());
+        }
+
+        let id = Uuid::new_v4();
+        let row = sqlx::query_as!(
+            UserNotificationChannelRow,
+            r#"INSERT INTO user_notification_channels (id, user_id, name, kind, settings, enabled)
+               VALUES ($1, $2, $3, $4, $5, $6)
+               RETURNING id as "id!: Uuid", user_id as "user_id!: Uuid", name, kind as "kind!: NotificationChannelKind", settings, enabled as "enabled!: bool", created_at as "created_at!: DateTime<Utc>", updated_at as "updated_at!: DateTime<Utc>""#,
+            id,
+            user_id,
+            data.name,
+            data.kind,
+            settings,
+            data.enabled
+        )
+        .fetch_one(pool)
+        .await?;
+        Ok(row.into())
+    }
+
+    pub async fn delete(pool: &SqlitePool, id: Uuid) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query!("DELETE FROM user_notification_channels WHERE id = $1", id)
+            .execute(pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+}
@@ -0,0 +1,301 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{SqlitePool, Type};
+use ts_rs::TS;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Events a user can be notified about
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, TS, ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum NotificationEvent {
+    AttemptCompleted,
+    AttemptFailed,
+    PrMerged,
+}
+
+impl NotificationEvent {
+    pub const ALL: [NotificationEvent; 3] = [
+        NotificationEvent::AttemptCompleted,
+        NotificationEvent::AttemptFailed,
+        NotificationEvent::PrMerged,
+    ];
+}
+
+/// Which tasks a rule applies to, relative to the user
+#[derive(
+    Debug, Clone, Copy, Default, Type, Serialize, Deserialize, PartialEq, Eq, TS, ToSchema,
+)]
+#[sqlx(type_name = "notification_scope", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+#[ts(export)]
+pub enum NotificationScope {
+    /// Tasks the user created or started an attempt of
+    Created,
+    /// Tasks assigned to the user
+    Assigned,
+    /// Either of the above
+    #[default]
+    Involved,
+}
+
+/// A user's rule for which events reach them, on which channels and when
+#[derive(Debug, Clone, Serialize, Deserialize, TS, ToSchema)]
+#[ts(export)]
+pub struct UserNotificationRule {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    pub events: Vec<NotificationEvent>,
+    pub scope: NotificationScope,
+    /// Channel names; empty means every enabled channel
+    pub channels: Vec<String>,
+    /// Local 'HH:MM' from which notifications are held back
+    pub quiet_hours_start: Option<String>,
+    /// Local 'HH:MM' at which held back notifications are sent
+    pub quiet_hours_end: Option<String>,
+    #[ts(type = "number")]
+    pub utc_offset_minutes: i64,
+    /// Send one summary every this many minutes instead of each notification
+    #[ts(type = "number | null")]
+    pub digest_minutes: Option<i64>,
+    pub enabled: bool,
+
+    #[ts(type = "Date")]
+    #[schema(value_type = String, format = DateTime)]
+    pub created_at: DateTime<Utc>,
+    #[ts(type = "Date")]
+    #[schema(value_type = String, format = DateTime)]
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Fields of a rule, validated by `services::notification_rules`
+#[derive(Debug, Clone)]
+pub struct UpsertUserNotificationRule {
+    pub name: String,
+    pub events: Vec<NotificationEvent>,
+    pub scope: NotificationScope,
+    pub channels: Vec<String>,
+    pub quiet_hours_start: Option<String>,
+    pub quiet_hours_end: Option<String>,
+    pub utc_offset_minutes: i64,
+    pub digest_minutes: Option<i64>,
+    pub enabled: bool,
+}
+
+struct UserNotificationRuleRow {
+    id: Uuid,
+    user_id: Uuid,
+    name: String,
+    events: String,
+    scope: NotificationScope,
+    channels: String,
+    quiet_hours_start: Option<String>,
+    quiet_hours_end: Option<String>,
+    utc_offset_minutes: i64,
+    digest_minutes: Option<i64>,
+    enabled: bool,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl From<UserNotificationRuleRow> for UserNotificationRule {
+    fn from(row: UserNotificationRuleRow) -> Self {
+        Self {
+            id: row.id,
+            user_id: row.user_id,
+            name: row.name,
+            events: serde_json::from_str(&row.events).unwrap_or_default(),
+            scope: row.scope,
+            channels: serde_json::from_str(&row.channels).unwrap_or_default(),
+            quiet_hours_start: row.quiet_hours_start,
+            quiet_hours_end: row.quiet_hours_end,
+            utc_offset_minutes: row.utc_offset_minutes,
+            digest_minutes: row.digest_minutes,
+            enabled: row.enabled,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        }
+    }
+}
+
+impl UserNotificationRule {
+    pub async fn find_by_user_id(
+        pool: &SqlitePool,
+        user_id: Uuid,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        let rows = sqlx::query_as!(
+            UserNotificationRuleRow,
+            r#"SELECT id as "id!: Uuid", user_id as "user_id!: Uuid", name, events, scope as "scope!: NotificationScope", channels, quiet_hours_start, quiet_hours_end, utc_offset_minutes, digest_minutes, enabled as "enabled!: bool", created_at as "created_at!: DateTime<Utc>", updated_at as "updated_at!: DateTime<Utc>"
+               FROM user_notification_rules
+               WHERE user_id = $1
+               ORDER BY name ASC"#,
+            user_id
+        )
+        .fetch_all(pool)
+        .await?;
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    pub async fn find_by_id(pool: &SqlitePool, id: Uuid) -> Result<Option<Self>, sqlx::Error> {
+        let row = sqlx::query_as!(
+            UserNotificationRuleRow,
+            r#"SELECT id as "id!: Uuid", user_id as "user_id!: Uuid", name, events, scope as "scope!: NotificationScope", channels, quiet_hours_start, quiet_hours_end, utc_offset_minutes, digest_minutes, enabled as "enabled!: bool", created_at as "created_at!: DateTime<Utc>", updated_at as "updated_at!: DateTime<Utc>"
+               FROM user_notification_rules
+               WHERE id = $1"#,
+            id
+        )
+        .fetch_optional(pool)
+        .await?;
+        Ok(row.map(Into::into))
+    }
+
+    pub async fn find_by_name(
+        pool: &SqlitePool,
+        user_id: Uuid,
+        name: &str,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        let row = sqlx::query_as!(
+            UserNotificationRuleRow,
+            r#"SELECT id as "id!: Uuid", user_id as "user_id!: Uuid", name, events, scope as "scope!: NotificationScope", channels, quiet_hours_start, quiet_hours_end, utc_offset_minutes, digest_minutes, enabled as "enabled!: bool", created_at as "created_at!: DateTime<Utc>", updated_at as "updated_at!: DateTime<Utc>"
+               FROM user_notification_rules
+               WHERE user_id = $1 AND name = $2"#,
+            user_id,
+            name
+        )
+        .fetch_optional(pool)
+        .await?;
+        Ok(row.map(Into::into))
+    }
+
+    /// Create the rule, or replace the one of the same name
+    pub async fn upsert(
+        pool: &SqlitePool,
+        user_id: Uuid,
+        data: &UpsertUserNotificationRule,
+    ) -> Result<Self, sqlx::Error> {
+        let events = serde_json::to_string(&data.events).unwrap_or_else(|_| "[]".to_string());
+        let channels = serde_json::to_string(&data.channels).unwrap_or_else(|_| "[]".to_string());
+        if let Some(existing) = Self::find_by_name(pool, user_id, &data.name).await? {
+            let row = sqlx::query_as!(
+                UserNotificationRuleRow,
+                r#"UPDATE user_notification_rules
+                   SET events = $2, scope = $3, channels = $4, quiet_hours_start = $5, quiet_hours_end = $6,
+                       utc_offset_minutes = $7, digest_minutes = $8, enabled = $9, updated_at = datetime('now', 'subsec')
+                   WHERE id = $1
+                   RETURNING id as "id!: Uuid", user_id as "user_id!: Uuid", name, events, scope as "scope!: NotificationScope", channels, quiet_hours_start, quiet_hours_end, utc_offset_minutes, digest_minutes, enabled as "enabled!: bool", created_at as "created_at!: DateTime<Utc>", updated_at as "updated_at!: DateTime<Utc>""#,
+                existing.id,
+                events,
+                data.scope,
+                channels,
+                data.quiet_hours_start,
+                data.quiet_hours_end,
+                data.utc_offset_minutes,
+                data.digest_minutes,
+                data.enabled
+            )
+            .fetch_one(pool)
+            .await?;
+            return Ok(row.into());
+        }
+
+        let id = Uuid::new_v4();
+        let row = sqlx::query_as!(
+            UserNotificationRuleRow,
+            r#"INSERT INTO user_notification_rules (id, user_id, name, events, scope, channels, quiet_hours_start, quiet_hours_end, utc_offset_minutes, digest_minutes, enabled)
+               VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+               RETURNING id as "id!: Uuid", user_id as "user_id!: Uuid", name, events, scope as "scope!: NotificationScope", channels, quiet_hours_start, quiet_hours_end, utc_offset_minutes, digest_minutes, enabled as "enabled!: bool", created_at as "created_at!: DateTime<Utc>", updated_at as "updated_at!: DateTime<Utc>""#,
+            id,
+            user_id,
+            data.name,
+            events,
+            data.scope,
+            channels,
+            data.quiet_hours_start,
+            data.quiet_hours_end,
+            data.utc_offset_minutes,
+            data.digest_minutes,
+            data.enabled
+        )
+        .fetch_one(pool)
+        .await?;
+        Ok(row.into())
+    }
+
+    pub async fn delete(pool: &SqlitePool, id: Uuid) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query!("DELETE FROM user_notification_rules WHERE id = $1", id)
+            .execute(pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+}
+
+/// A notification held back for a rule's digest or until its quiet hours end
+#[derive(Debug, Clone)]
+pub struct NotificationDigestItem {
+    pub id: Uuid,
+    #[allow(dead_code)]
+    pub rule_id: Uuid,
+    pub title: String,
+    pub message: String,
+    pub url: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl NotificationDigestItem {
+    pub async fn create(
+        pool: &SqlitePool,
+        rule_id: Uuid,
+        title: &str,
+        message: &str,
+        url: Option<&str>,
+    ) -> Result<Uuid, sqlx::Error> {
+        let id = Uuid::new_v4();
+        sqlx::query!(
+            "INSERT INTO notification_digest_items (id, rule_id, title, message, url) VALUES ($1, $2, $3, $4, $5)",
+            id,
+            rule_id,
+            title,
+            message,
+            url
+        )
+        .execute(pool)
+        .await?;
+        Ok(id)
+    }
+
+    /// Rules with held back notifications
+    pub async fn pending_rule_ids(pool: &SqlitePool) -> Result<Vec<Uuid>, sqlx::Error> {
+        let rows = sqlx::query!(
+            r#"SELECT DISTINCT rule_id as "rule_id!: Uuid" FROM notification_digest_items"#
+        )
+        .fetch_all(pool)
+        .await?;
+        Ok(rows.into_iter().map(|row| row.rule_id).collect())
+    }
+
+    /// A rule's held back notifications, oldest first
+    pub async fn find_by_rule_id(
+        pool: &SqlitePool,
+        rule_id: Uuid,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as!(
+            NotificationDigestItem,
+            r#"SELECT id as "id!: Uuid", rule_id as "rule_id!: Uuid", title, message, url, created_at as "created_at!: DateTime<Utc>"
+               FROM notification_digest_items
+               WHERE rule_id = $1
+               ORDER BY created_at ASC"#,
+            rule_id
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    pub async fn delete(pool: &SqlitePool, id: Uuid) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query!("DELETE FROM notification_digest_items WHERE id = $1", id)
+            .execute(pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+}
@@ -103,6 +103,7 @@ impl UserSession {
     }
 
     /// Find session by token hash
+    #[allow(dead_code)]
     pub async fn find_by_token_hash(pool: &SqlitePool, token_hash: &str) -> Result<Option<Self>, sqlx::Error> {
         sqlx::query_as!(
             UserSession,
@@ -144,6 +145,50 @@ impl UserSession {
         .await
     }
 
+    /// Find a valid session whose token was rotated within the last `grace_seconds`,
+    /// looked up by the hash of the token it replaced
+    pub async fn find_recently_rotated_by_previous_token_hash(
+        pool: &SqlitePool,
+        previous_token_hash: &str,
+        grace_seconds: i64,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        let now = Utc::now();
+        let grace_modifier = format!("-{} seconds", grace_seconds);
+        sqlx::query_as!(
+            UserSession,
+            r#"SELECT 
+                id as "id!: Uuid", 
+                user_id as "user_id!: Uuid", 
+                token_hash, 
+                session_type as "session_type!: SessionType", 
+                client_info, 
+                expires_at as "expires_at!: DateTime<Utc>", 
+                created_at as "created_at!: DateTime<Utc>" 
+            FROM user_sessions 
+            WHERE previous_token_hash = $1 AND expires_at > $2
+              AND rotated_at > datetime('now', $3)"#,
+            previous_token_hash,
+            now,
+            grace_modifier
+        )
+        .fetch_optional(pool)
+        .await
+    }
+
+    /// When the session's token was last rotated, if ever
+    pub async fn find_rotated_at(
+        pool: &SqlitePool,
+        id: Uuid,
+    ) -> Result<Option<DateTime<Utc>>, sqlx::Error> {
+        let row = sqlx::query!(
+            r#"SELECT rotated_at as "rotated_at: DateTime<Utc>" FROM user_sessions WHERE id = $1"#,
+            id
+        )
+        .fetch_optional(pool)
+        .await?;
+        Ok(row.and_then(|row| row.rotated_at))
+    }
+
     /// Create a new session
     pub async fn create(
         pool: &SqlitePool,
@@ -0,0 +1,389 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{SqlitePool, Type};
+use ts_rs::TS;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Task and execution lifecycle events webhooks can subscribe to
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, TS, ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum WebhookEvent {
+    TaskCreated,
+    TaskStatusChanged,
+    AttemptStarted,
+    AttemptCompleted,
+    AttemptFailed,
+    AttemptMerged,
+    PrOpened,
+    PrMerged,
+    PrClosed,
+}
+
+impl WebhookEvent {
+    pub const ALL: [WebhookEvent; 9] = [
+        WebhookEvent::TaskCreated,
+        WebhookEvent::TaskStatusChanged,
+        WebhookEvent::AttemptStarted,
+        WebhookEvent::AttemptCompleted,
+        WebhookEvent::AttemptFailed,
+        WebhookEvent::AttemptMerged,
+        WebhookEvent::PrOpened,
+        WebhookEvent::PrMerged,
+        WebhookEvent::PrClosed,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebhookEvent::TaskCreated => "task_created",
+            WebhookEvent::TaskStatusChanged => "task_status_changed",
+            WebhookEvent::AttemptStarted => "attempt_started",
+            WebhookEvent::AttemptCompleted => "attempt_completed",
+            WebhookEvent::AttemptFailed => "attempt_failed",
+            WebhookEvent::AttemptMerged => "attempt_merged",
+            WebhookEvent::PrOpened => "pr_opened",
+            WebhookEvent::PrMerged => "pr_merged",
+            WebhookEvent::PrClosed => "pr_closed",
+        }
+    }
+}
+
+impl std::fmt::Display for WebhookEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// An outgoing webhook of a project. The signing secret lives in the secret
+/// store.
+#[derive(Debug, Clone, Serialize, Deserialize, TS, ToSchema)]
+#[ts(export)]
+pub struct Webhook {
+    pub id: Uuid,
+    pub project_id: Uuid,
+    pub url: String,
+    /// Subscribed events; empty means every event
+    pub events: Vec<WebhookEvent>,
+    pub enabled: bool,
+    pub created_by: Option<Uuid>,
+
+    #[ts(type = "Date")]
+    #[schema(value_type = String, format = DateTime)]
+    pub created_at: DateTime<Utc>,
+    #[ts(type = "Date")]
+    #[schema(value_type = String, format = DateTime)]
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Webhook {
+    pub fn subscribes_to(&self, event: WebhookEvent) -> bool {
+        self.enabled && (self.events.is_empty() || self.events.contains(&event))
+    }
+}
+
+/// Fields of a webhook, validated by `services::webhooks`
+#[derive(Debug, Clone)]
+pub struct UpsertWebhook {
+    pub url: String,
+    pub events: Vec<WebhookEvent>,
+    pub enabled: bool,
+}
+
+struct WebhookRow {
+    id: Uuid,
+    project_id: Uuid,
+    url: String,
+    events: String,
+    enabled: bool,
+    created_by: Option<Uuid>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl From<WebhookRow> for Webhook {
+    fn from(row: WebhookRow) -> Self {
+        Self {
+            id: row.id,
+            project_id: row.project_id,
+            url: row.url,
+            events: serde_json::from_str(&row.events).unwrap_or_default(),
+            enabled: row.enabled,
+            created_by: row.created_by,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        }
+    }
+}
+
+impl Webhook {
+    pub async fn find_by_project_id(
+        pool: &SqlitePool,
+        project_id: Uuid,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        let rows = sqlx::query_as!(
+            WebhookRow,
+            r#"SELECT id as "id!: Uuid", project_id as "project_id!: Uuid", url, events, enabled as "enabled!: bool", created_by as "created_by: Uuid", created_at as "created_at!: DateTime<Utc>", updated_at as "updated_at!: DateTime<Utc>"
+               FROM webhooks
+               WHERE project_id = $1
+               ORDER BY created_at ASC"#,
+            project_id
+        )
+        .fetch_all(pool)
+        .await?;
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    pub async fn find_by_id(pool: &SqlitePool, id: Uuid) -> Result<Option<Self>, sqlx::Error> {
+        let row = sqlx::query_as!(
+            WebhookRow,
+            r#"SELECT id as "id!: Uuid", project_id as "project_id!: Uuid", url, events, enabled as "enabled!: bool", created_by as "created_by: Uuid", created_at as "created_at!: DateTime<Utc>", updated_at as "updated_at!: DateTime<Utc>"
+               FROM webhooks
+               WHERE id = $1"#,
+            id
+        )
+        .fetch_optional(pool)
+        .await?;
+        Ok(row.map(Into::into))
+    }
+
+    pub async fn find_by_id_and_project_id(
+        pool: &SqlitePool,
+        id: Uuid,
+        project_id: Uuid,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        let row = sqlx::query_as!(
+            WebhookRow,
+            r#"SELECT id as "id!: Uuid", project_id as "project_id!: Uuid", url, events, enabled as "enabled!: bool", created_by as "created_by: Uuid", created_at as "created_at!: DateTime<Utc>", updated_at as "updated_at!: DateTime<Utc>"
+               FROM webhooks
+               WHERE id = $1 AND project_id = $2"#,
+            id,
+            project_id
+        )
+        .fetch_optional(pool)
+        .await?;
+        Ok(row.map(Into::into))
+    }
+
+    pub async fn create(
+        pool: &SqlitePool,
+        project_id: Uuid,
+        created_by: Option<Uuid>,
+        data: &UpsertWebhook,
+    ) -> Result<Self, sqlx::Error> {
+        let id = Uuid::new_v4();
+        let events = serde_json::to_string(&data.events).unwrap_or_else(|_| "[]".to_string());
+        let row = sqlx::query_as!(
+            WebhookRow,
+            r#"INSERT INTO webhooks (id, project_id, url, events, enabled, created_by)
+               VALUES ($1, $2, $3, $4, $5, $6)
+               RETURNING id as "id!: Uuid", project_id as "project_id!: Uuid", url, events, enabled as "enabled!: bool", created_by as "created_by: Uuid", created_at as "created_at!: DateTime<Utc>", updated_at as "updated_at!: DateTime<Utc>""#,
+            id,
+            project_id,
+            data.url,
+            events,
+            data.enabled,
+            created_by
+        )
+        .fetch_one(pool)
+        .await?;
+        Ok(row.into())
+    }
+
+    pub async fn update(
+        pool: &SqlitePool,
+        id: Uuid,
+        data: &UpsertWebhook,
+    ) -> Result<Self, sqlx::Error> {
+        let events = serde_json::to_string(&data.events).unwrap_or_else(|_| "[]".to_string());
+        let row = sqlx::query_as!(
+            WebhookRow,
+            r#"UPDATE webhooks
+               SET url = $2, events = $3, enabled = $4, updated_at = datetime('now', 'subsec')
+               WHERE id = $1
+               RETURNING id as "id!: Uuid", project_id as "project_id!: Uuid", url, events, enabled as "enabled!: bool", created_by as "created_by: Uuid", created_at as "created_at!: DateTime<Utc>", updated_at as "updated_at!: DateTime<Utc>""#,
+            id,
+            data.url,
+            events,
+            data.enabled
+        )
+        .fetch_one(pool)
+        .await?;
+        Ok(row.into())
+    }
+
+    pub async fn delete(pool: &SqlitePool, id: Uuid) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query!("DELETE FROM webhooks WHERE id = $1", id)
+            .execute(pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Type, Serialize, Deserialize, PartialEq, Eq, TS, ToSchema)]
+#[sqlx(type_name = "webhook_delivery_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+#[ts(export)]
+pub enum WebhookDeliveryStatus {
+    Pending,
+    Delivered,
+    Failed,
+    Cancelled,
+}
+
+/// One event sent, or to be sent, to a webhook
+#[derive(Debug, Clone, Serialize, Deserialize, TS, ToSchema)]
+#[ts(export)]
+pub struct WebhookDelivery {
+    pub id: Uuid,
+    pub webhook_id: Uuid,
+    pub event: String,
+    pub payload: String,
+    pub status: WebhookDeliveryStatus,
+    pub attempts: i64,
+    pub last_status_code: Option<i64>,
+    pub last_error: Option<String>,
+
+    #[ts(type = "Date")]
+    #[schema(value_type = String, format = DateTime)]
+    pub next_attempt_at: DateTime<Utc>,
+    #[ts(type = "Date")]
+    #[schema(value_type = String, format = DateTime)]
+    pub created_at: DateTime<Utc>,
+    #[ts(type = "Date | null")]
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+impl WebhookDelivery {
+    pub async fn create(
+        pool: &SqlitePool,
+        webhook_id: Uuid,
+        event: WebhookEvent,
+        payload: &str,
+    ) -> Result<Uuid, sqlx::Error> {
+        let id = Uuid::new_v4();
+        let event = event.as_str();
+        sqlx::query!(
+            "INSERT INTO webhook_deliveries (id, webhook_id, event, payload) VALUES ($1, $2, $3, $4)",
+            id,
+            webhook_id,
+            event,
+            payload
+        )
+        .execute(pool)
+        .await?;
+        Ok(id)
+    }
+
+    /// Pending deliveries whose next attempt is due, oldest first
+    pub async fn find_due(pool: &SqlitePool, limit: i64) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as!(
+            WebhookDelivery,
+            r#"SELECT id as "id!: Uuid", webhook_id as "webhook_id!: Uuid", event, payload, status as "status!: WebhookDeliveryStatus", attempts, last_status_code, last_error, next_attempt_at as "next_attempt_at!: DateTime<Utc>", created_at as "created_at!: DateTime<Utc>", delivered_at as "delivered_at: DateTime<Utc>"
+               FROM webhook_deliveries
+               WHERE status = 'pending' AND next_attempt_at <= datetime('now', 'subsec')
+               ORDER BY next_attempt_at ASC
+               LIMIT $1"#,
+            limit
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    /// The webhook's most recent deliveries, newest first
+    pub async fn find_by_webhook_id(
+        pool: &SqlitePool,
+        webhook_id: Uuid,
+        limit: i64,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as!(
+            WebhookDelivery,
+            r#"SELECT id as "id!: Uuid", webhook_id as "webhook_id!: Uuid", event, payload, status as "status!: WebhookDeliveryStatus", attempts, last_status_code, last_error, next_attempt_at as "next_attempt_at!: DateTime<Utc>", created_at as "created_at!: DateTime<Utc>", delivered_at as "delivered_at: DateTime<Utc>"
+               FROM webhook_deliveries
+               WHERE webhook_id = $1
+               ORDER BY created_at DESC
+               LIMIT $2"#,
+            webhook_id,
+            limit
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    pub async fn mark_delivered(
+        pool: &SqlitePool,
+        id: Uuid,
+        status_code: i64,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"UPDATE webhook_deliveries
+               SET status = 'delivered', attempts = attempts + 1, last_status_code = $2, last_error = NULL, delivered_at = datetime('now', 'subsec')
+               WHERE id = $1"#,
+            id,
+            status_code
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Stop a delivery that can no longer be sent, such as one to a disabled
+    /// webhook
+    pub async fn mark_cancelled(
+        pool: &SqlitePool,
+        id: Uuid,
+        reason: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"UPDATE webhook_deliveries
+               SET status = 'cancelled', last_error = $2
+               WHERE id = $1"#,
+            id,
+            reason
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Record a failed attempt and schedule the next one `retry_in_seconds`
+    /// from now, or give up when `retry_in_seconds` is `None`
+    pub async fn mark_attempt_failed(
+        pool: &SqlitePool,
+        id: Uuid,
+        status_code: Option<i64>,
+        error: &str,
+        retry_in_seconds: Option<i64>,
+    ) -> Result<(), sqlx::Error> {
+        match retry_in_seconds {
+            Some(seconds) => {
+                let modifier = format!("+{} seconds", seconds);
+                sqlx::query!(
+                    r#"UPDATE webhook_deliveries
+                       SET attempts = attempts + 1, last_status_code = $2, last_error = $3, next_attempt_at = datetime('now', 'subsec', $4)
+                       WHERE id = $1"#,
+                    id,
+                    status_code,
+                    error,
+                    modifier
+                )
+                .execute(pool)
+                .await?;
+            }
+            None => {
+                sqlx::query!(
+                    r#"UPDATE webhook_deliveries
+                       SET status = 'failed', attempts = attempts + 1, last_status_code = $2, last_error = $3
+                       WHERE id = $1"#,
+                    id,
+                    status_code,
+                    error
+                )
+                .execute(pool)
+                .await?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,160 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{SqlitePool, Type};
+use ts_rs::TS;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, Type, Serialize, Deserialize, PartialEq, Eq, TS, ToSchema)]
+#[sqlx(type_name = "whatsapp_message_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+#[ts(export)]
+pub enum WhatsAppMessageStatus {
+    Pending,
+    Sent,
+    Failed,
+}
+
+/// A WhatsApp message in the outbox
+#[derive(Debug, Clone, Serialize, Deserialize, TS, ToSchema)]
+#[ts(export)]
+pub struct WhatsAppMessage {
+    pub id: Uuid,
+    /// Whose notification channel the message is for; `None` for
+    /// install-wide notifications
+    pub user_id: Option<Uuid>,
+    pub recipient: String,
+    pub message: String,
+    pub status: WhatsAppMessageStatus,
+    pub attempts: i64,
+    pub last_error: Option<String>,
+
+    #[ts(type = "Date")]
+    #[schema(value_type = String, format = DateTime)]
+    pub next_attempt_at: DateTime<Utc>,
+    #[ts(type = "Date")]
+    #[schema(value_type = String, format = DateTime)]
+    pub created_at: DateTime<Utc>,
+    #[ts(type = "Date | null")]
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub sent_at: Option<DateTime<Utc>>,
+}
+
+impl WhatsAppMessage {
+    pub async fn enqueue(
+        pool: &SqlitePool,
+        user_id: Option<Uuid>,
+        recipient: &str,
+        message: &str,
+    ) -> Result<Uuid, sqlx::Error> {
+        let id = Uuid::new_v4();
+        sqlx::query!(
+            "INSERT INTO whatsapp_outbox (id, user_id, recipient, message) VALUES ($1, $2, $3, $4)",
+            id,
+            user_id,
+            recipient,
+            message
+        )
+        .execute(pool)
+        .await?;
+        Ok(id)
+    }
+
+    /// Pending messages whose next attempt is due, oldest first
+    pub async fn find_due(pool: &SqlitePool, limit: i64) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as!(
+            WhatsAppMessage,
+            r#"SELECT id as "id!: Uuid", user_id as "user_id: Uuid", recipient, message, status as "status!: WhatsAppMessageStatus", attempts, last_error, next_attempt_at as "next_attempt_at!: DateTime<Utc>", created_at as "created_at!: DateTime<Utc>", sent_at as "sent_at: DateTime<Utc>"
+               FROM whatsapp_outbox
+               WHERE status = 'pending' AND next_attempt_at <= datetime('now', 'subsec')
+               ORDER BY next_attempt_at ASC
+               LIMIT $1"#,
+            limit
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    /// The most recent messages, newest first
+    pub async fn find_recent(pool: &SqlitePool, limit: i64) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as!(
+            WhatsAppMessage,
+            r#"SELECT id as "id!: Uuid", user_id as "user_id: Uuid", recipient, message, status as "status!: WhatsAppMessageStatus", attempts, last_error, next_attempt_at as "next_attempt_at!: DateTime<Utc>", created_at as "created_at!: DateTime<Utc>", sent_at as "sent_at: DateTime<Utc>"
+               FROM whatsapp_outbox
+               ORDER BY created_at DESC
+               LIMIT $1"#,
+            limit
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    /// A user's most recent messages, newest first
+    pub async fn find_recent_by_user_id(
+        pool: &SqlitePool,
+        user_id: Uuid,
+        limit: i64,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as!(
+            WhatsAppMessage,
+            r#"SELECT id as "id!: Uuid", user_id as "user_id: Uuid", recipient, message, status as "status!: WhatsAppMessageStatus", attempts, last_error, next_attempt_at as "next_attempt_at!: DateTime<Utc>", created_at as "created_at!: DateTime<Utc>", sent_at as "sent_at: DateTime<Utc>"
+               FROM whatsapp_outbox
+               WHERE user_id = $1
+               ORDER BY created_at DESC
+               LIMIT $2"#,
+            user_id,
+            limit
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    pub async fn mark_sent(pool: &SqlitePool, id: Uuid) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"UPDATE whatsapp_outbox
+               SET status = 'sent', attempts = attempts + 1, last_error = NULL, sent_at = datetime('now', 'subsec')
+               WHERE id = $1"#,
+            id
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Record a failed attempt and schedule the next one `retry_in_seconds`
+    /// from now, or give up when `retry_in_seconds` is `None`
+    pub async fn mark_attempt_failed(
+        pool: &SqlitePool,
+        id: Uuid,
+        error: &str,
+        retry_in_seconds: Option<i64>,
+    ) -> Result<(), sqlx::Error> {
+        match retry_in_seconds {
+            Some(seconds) => {
+                let modifier = format!("+{} seconds", seconds);
+                sqlx::query!(
+                    r#"UPDATE whatsapp_outbox
+                       SET attempts = attempts + 1, last_error = $2, next_attempt_at = datetime('now', 'subsec', $3)
+                       WHERE id = $1"#,
+                    id,
+                    error,
+                    modifier
+                )
+                .execute(pool)
+                .await?;
+            }
+            None => {
+                sqlx::query!(
+                    r#"UPDATE whatsapp_outbox
+                       SET status = 'failed', attempts = attempts + 1, last_error = $2
+                       WHERE id = $1"#,
+                    id,
+                    error
+                )
+                .execute(pool)
+                .await?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,127 @@
+use chrono::{DateTime, Utc};
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+/// A user's claim on a WhatsApp number, verified once they entered the code
+/// sent to it. `number` holds digits only.
+#[derive(Debug, Clone)]
+pub struct WhatsAppNumberVerification {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub number: String,
+    pub code_hash: String,
+    pub attempts: i64,
+    pub expires_at: DateTime<Utc>,
+    pub verified_at: Option<DateTime<Utc>>,
+}
+
+impl WhatsAppNumberVerification {
+    pub async fn find(
+        pool: &SqlitePool,
+        user_id: Uuid,
+        number: &str,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        sqlx::query_as!(
+            WhatsAppNumberVerification,
+            r#"SELECT id as "id!: Uuid", user_id as "user_id!: Uuid", number, code_hash, attempts, expires_at as "expires_at!: DateTime<Utc>", verified_at as "verified_at: DateTime<Utc>"
+               FROM whatsapp_number_verifications
+               WHERE user_id = $1 AND number = $2"#,
+            user_id,
+            number
+        )
+        .fetch_optional(pool)
+        .await
+    }
+
+    pub async fn find_by_user_id(
+        pool: &SqlitePool,
+        user_id: Uuid,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as!(
+            WhatsAppNumberVerification,
+            r#"SELECT id as "id!: Uuid", user_id as "user_id!: Uuid", number, code_hash, attempts, expires_at as "expires_at!: DateTime<Utc>", verified_at as "verified_at: DateTime<Utc>"
+               FROM whatsapp_number_verifications
+               WHERE user_id = $1"#,
+            user_id
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    /// The verification of `number`, whichever user it belongs to
+    pub async fn find_verified_by_number(
+        pool: &SqlitePool,
+        number: &str,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        sqlx::query_as!(
+            WhatsAppNumberVerification,
+            r#"SELECT id as "id!: Uuid", user_id as "user_id!: Uuid", number, code_hash, attempts, expires_at as "expires_at!: DateTime<Utc>", verified_at as "verified_at: DateTime<Utc>"
+               FROM whatsapp_number_verifications
+               WHERE number = $1 AND verified_at IS NOT NULL"#,
+            number
+        )
+        .fetch_optional(pool)
+        .await
+    }
+
+    /// Start the user's verification of `number` over with a new code. The
+    /// number stays unverified until the code is entered.
+    pub async fn start(
+        pool: &SqlitePool,
+        user_id: Uuid,
+        number: &str,
+        code_hash: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<Self, sqlx::Error> {
+        let id = Uuid::new_v4();
+        sqlx::query_as!(
+            WhatsAppNumberVerification,
+            r#"INSERT INTO whatsapp_number_verifications (id, user_id, number, code_hash, expires_at)
+               VALUES ($1, $2, $3, $4, $5)
+               ON CONFLICT (user_id, number) DO UPDATE
+               SET code_hash = excluded.code_hash, attempts = 0, expires_at = excluded.expires_at, verified_at = NULL
+               RETURNING id as "id!: Uuid", user_id as "user_id!: Uuid", number, code_hash, attempts, expires_at as "expires_at!: DateTime<Utc>", verified_at as "verified_at: DateTime<Utc>""#,
+            id,
+            user_id,
+            number,
+            code_hash,
+            expires_at
+        )
+        .fetch_one(pool)
+        .await
+    }
+
+    pub async fn record_failed_attempt(pool: &SqlitePool, id: Uuid) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "UPDATE whatsapp_number_verifications SET attempts = attempts + 1 WHERE id = $1",
+            id
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Fails with a unique violation when another user verified the number
+    /// first
+    pub async fn mark_verified(pool: &SqlitePool, id: Uuid) -> Result<(), sqlx::Error> {
+        let now = Utc::now();
+        sqlx::query!(
+            "UPDATE whatsapp_number_verifications SET verified_at = $2 WHERE id = $1",
+            id,
+            now
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn delete(pool: &SqlitePool, id: Uuid) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "DELETE FROM whatsapp_number_verifications WHERE id = $1",
+            id
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+}
@@ -17,14 +17,47 @@ use utoipa::OpenApi;
         crate::routes::projects::get_project,
         crate::routes::projects::create_project,
         crate::routes::projects::update_project,
+        crate::routes::projects::get_mcp_injection,
+        crate::routes::projects::update_mcp_injection,
+        crate::routes::projects::get_review_automation,
+        crate::routes::projects::update_review_automation,
         crate::routes::projects::delete_project,
+        crate::routes::project_env::list_env_vars,
+        crate::routes::project_env::set_env_var,
+        crate::routes::project_env::delete_env_var,
+        crate::routes::project_mcp_servers::list_mcp_servers,
+        crate::routes::project_mcp_servers::save_mcp_server,
+        crate::routes::project_mcp_servers::delete_mcp_server,
+        crate::routes::project_webhooks::list_webhooks,
+        crate::routes::project_webhooks::create_webhook,
+        crate::routes::project_webhooks::update_webhook,
+        crate::routes::project_webhooks::delete_webhook,
+        crate::routes::project_webhooks::list_webhook_deliveries,
+        crate::routes::project_github_issues::list_github_issues,
+        crate::routes::project_github_issues::import_github_issues,
+        crate::routes::notification_channels::list_notification_channels,
+        crate::routes::notification_channels::save_notification_channel,
+        crate::routes::notification_channels::delete_notification_channel,
+        crate::routes::notification_channels::test_notification_channel,
+        crate::routes::notification_channels::list_whatsapp_messages,
+        crate::routes::notification_rules::list_notification_rules,
+        crate::routes::notification_rules::save_notification_rule,
+        crate::routes::notification_rules::delete_notification_rule,
+        crate::routes::whatsapp_webhook::receive_whatsapp_webhook,
+        crate::routes::github_webhook::receive_github_webhook,
         crate::routes::tasks::get_project_tasks,
         crate::routes::tasks::get_task,
         crate::routes::tasks::create_task,
+        crate::routes::tasks::create_tasks_from_plan,
         crate::routes::tasks::update_task,
         crate::routes::tasks::delete_task,
         crate::routes::task_attempts::get_task_attempts,
         crate::routes::task_attempts::create_task_attempt,
+        crate::routes::task_attempts::get_task_attempt_pull_request,
+        crate::routes::task_attempts::address_task_attempt_review_comments,
+        crate::routes::task_attempts::get_task_attempt_comment_followups,
+        crate::routes::task_attempts::get_task_attempt_review_comments,
+        crate::routes::task_attempts::generate_task_attempt_pr_description,
         crate::routes::task_templates::list_templates,
         crate::routes::task_templates::list_project_templates,
         crate::routes::task_templates::list_global_templates,
@@ -39,12 +72,25 @@ use utoipa::OpenApi;
         crate::routes::auth::get_current_user_info,
         crate::routes::auth::logout,
         crate::routes::auth::logout_all,
+        crate::routes::auth_providers::list_auth_providers,
+        crate::routes::auth_providers::local_login,
+        crate::routes::auth_providers::create_local_user,
+        crate::routes::auth_providers::change_local_password,
+        crate::routes::auth_providers::oidc_start,
+        crate::routes::auth_providers::oidc_callback,
         // Config routes
         crate::routes::config::get_config,
         crate::routes::config::update_config,
         crate::routes::config::get_config_constants,
         crate::routes::config::get_mcp_servers,
         crate::routes::config::update_mcp_servers,
+        // Secret routes
+        crate::routes::secrets::list_secrets,
+        crate::routes::secrets::set_secret,
+        crate::routes::secrets::delete_secret,
+        crate::routes::forge_tokens::list_forge_tokens,
+        crate::routes::forge_tokens::save_forge_token,
+        crate::routes::forge_tokens::delete_forge_token,
         // Filesystem routes
         crate::routes::filesystem::list_directory,
         crate::routes::filesystem::validate_git_path,
@@ -55,16 +101,70 @@ use utoipa::OpenApi;
             crate::models::project::Project,
             crate::models::project::CreateProject,
             crate::models::project::UpdateProject,
+            crate::models::project::McpInjectionSettings,
+            crate::models::project::ReviewAutomationSettings,
             crate::models::project::ProjectWithBranch,
             crate::models::project::GitBranch,
+            crate::routes::project_env::EnvVarScope,
+            crate::routes::project_env::ProjectEnvVars,
+            crate::routes::project_env::SetEnvVarRequest,
+            crate::models::mcp_server::McpServer,
+            crate::models::mcp_server::McpTransport,
+            crate::routes::project_mcp_servers::McpServerScope,
+            crate::routes::project_mcp_servers::McpServerDetails,
+            crate::routes::project_mcp_servers::ProjectMcpServers,
+            crate::routes::project_mcp_servers::SaveMcpServerRequest,
+            crate::models::webhook::WebhookEvent,
+            crate::models::webhook::Webhook,
+            crate::models::webhook::WebhookDeliveryStatus,
+            crate::models::webhook::WebhookDelivery,
+            crate::routes::project_webhooks::ProjectWebhooks,
+            crate::routes::project_webhooks::WebhookWithSecret,
+            crate::routes::project_webhooks::CreateWebhookRequest,
+            crate::routes::project_webhooks::UpdateWebhookRequest,
+            crate::models::user_notification_channel::NotificationChannelKind,
+            crate::models::user_notification_channel::UserNotificationChannel,
+            crate::routes::notification_channels::NotificationChannelDetails,
+            crate::routes::notification_channels::SaveNotificationChannelRequest,
+            crate::routes::notification_channels::ConfirmWhatsAppNumberRequest,
+            crate::models::whatsapp_message::WhatsAppMessageStatus,
+            crate::models::whatsapp_message::WhatsAppMessage,
+            crate::models::user_notification_rule::NotificationEvent,
+            crate::models::user_notification_rule::NotificationScope,
+            crate::models::user_notification_rule::UserNotificationRule,
+            crate::routes::notification_rules::NotificationRules,
+            crate::routes::notification_rules::SaveNotificationRuleRequest,
             crate::models::task::Task,
             crate::models::task::TaskStatus,
             crate::models::task::TaskWithAttemptStatus,
             crate::models::task::CreateTask,
             crate::models::task::UpdateTask,
+            crate::models::task_dependency::TaskDependency,
+            crate::routes::tasks::CreateTasksFromPlan,
+            crate::routes::tasks::TaskPlanResult,
+            crate::services::task_plan::TaskPlanFormat,
+            crate::services::task_plan::PlannedTask,
+            crate::services::github_issues::ImportGitHubIssues,
+            crate::services::github_issues::GitHubIssueImport,
+            crate::services::github_issues::LinkedGitHubIssue,
+            crate::services::forge::ForgeKind,
+            crate::services::forge::ForgeReviewComment,
+            crate::services::forge::ForgeTokenSummary,
+            crate::services::forge::SaveForgeToken,
+            crate::services::pr_description::GeneratedPrDescription,
             crate::models::task_attempt::TaskAttempt,
             crate::models::task_attempt::TaskAttemptStatus,
             crate::models::task_attempt::CreateTaskAttempt,
+            crate::models::pull_request::PrReviewState,
+            crate::models::pull_request::PrReviewDecision,
+            crate::models::pull_request::PrChecksStatus,
+            crate::models::pull_request::PrReview,
+            crate::models::pull_request::PrReviewComment,
+            crate::models::pull_request::PrCheckRun,
+            crate::models::pull_request::PullRequestState,
+            crate::models::pull_request::PrCommentFollowupStatus,
+            crate::models::pull_request::AddressedThread,
+            crate::models::pull_request::PrCommentFollowup,
             crate::models::task_template::TaskTemplate,
             crate::models::task_template::CreateTaskTemplate,
             crate::models::task_template::UpdateTaskTemplate,
@@ -77,6 +177,14 @@ use utoipa::OpenApi;
             crate::routes::auth::DeviceStartResponse,
             crate::routes::auth::AuthResponse,
             crate::routes::auth::UserInfoResponse,
+            crate::routes::auth_providers::LocalLoginRequest,
+            crate::routes::auth_providers::ChangePasswordRequest,
+            crate::routes::auth_providers::OidcStartResponse,
+            crate::routes::auth_providers::OidcCallbackRequest,
+            crate::auth_providers::AuthProviderInfo,
+            crate::auth_providers::AuthProviderKind,
+            crate::auth_providers::LoginFlow,
+            crate::auth_providers::local::CreateLocalUser,
             crate::models::user::User,
             crate::models::user::CreateUser,
             crate::models::user::UpdateUser,
@@ -86,6 +194,9 @@ use utoipa::OpenApi;
             crate::models::user_session::UpdateUserSession,
             // Config schemas
             crate::routes::config::ConfigConstants,
+            // Secret schemas
+            crate::routes::secrets::SetSecretRequest,
+            crate::security::secret_store::SecretSummary,
             // Filesystem schemas
             crate::routes::filesystem::DirectoryEntry,
             crate::routes::filesystem::DirectoryListResponse,
@@ -99,6 +210,10 @@ use utoipa::OpenApi;
         (name = "task_templates", description = "Task template operations"),
         (name = "auth", description = "Authentication operations"),
         (name = "config", description = "Configuration operations"),
+        (name = "secrets", description = "Encrypted secret storage"),
+        (name = "forge_tokens", description = "Per-user GitHub, GitLab and Gitea tokens"),
+        (name = "notifications", description = "Per-user notification channels and rules"),
+        (name = "integrations", description = "Webhooks called by external services"),
         (name = "filesystem", description = "File system operations"),
     )
 )]
@@ -1,7 +1,7 @@
 use axum::{
     extract::{Request, State},
     middleware::Next,
-    response::{Json as ResponseJson, Response},
+    response::{IntoResponse, Json as ResponseJson, Response},
     routing::{get, post},
     Json, Router,
 };
@@ -14,11 +14,13 @@ use crate::{
     models::{
         ApiResponse,
         user::{User, CreateUser},
-        user_session::{SessionType, UserSession},
+        user_session::UserSession,
     },
 };
 use super::super::app_config::AppConfig;
-use crate::auth::{generate_jwt_token, hash_token, JwtConfig, get_current_user, get_user_context};
+use crate::auth::{get_current_user, get_user_context};
+use crate::auth_providers::issue_web_session;
+use crate::security::rate_limiter::AuthFailure;
 
 /// Get GitHub client ID from configuration with hardcoded default
 fn get_github_client_id() -> String {
@@ -85,7 +87,13 @@ pub struct UserInfoResponse {
         (status = 500, description = "Failed to contact GitHub or parse response", body = ApiResponse<String>)
     )
 )]
-pub async fn device_start() -> ResponseJson<ApiResponse<DeviceStartResponse>> {
+pub async fn device_start(
+    State(app_state): State<AppState>,
+) -> ResponseJson<ApiResponse<DeviceStartResponse>> {
+    if !app_state.auth_providers().github_enabled() {
+        return ResponseJson(ApiResponse::error("GitHub login is disabled on this server"));
+    }
+
     let client_id = get_github_client_id();
 
     let params = [("client_id", client_id.as_str()), ("scope", "user:email,repo")];
@@ -137,6 +145,14 @@ pub async fn device_start() -> ResponseJson<ApiResponse<DeviceStartResponse>> {
     }
 }
 
+/// Error response for a failed login attempt, flagged so the rate limiter
+/// counts it towards a lockout even though the status is 200
+pub fn auth_failure_response(message: &str) -> Response {
+    let mut response = ResponseJson(ApiResponse::<AuthResponse>::error(message)).into_response();
+    response.extensions_mut().insert(AuthFailure);
+    response
+}
+
 /// POST /auth/github/device/poll
 #[utoipa::path(
     post,
@@ -148,13 +164,21 @@ pub async fn device_start() -> ResponseJson<ApiResponse<DeviceStartResponse>> {
     responses(
         (status = 200, description = "GitHub login successful with JWT token", body = ApiResponse<AuthResponse>),
         (status = 400, description = "OAuth error or invalid device code", body = ApiResponse<String>),
-        (status = 403, description = "User not whitelisted", body = ApiResponse<String>)
+        (status = 403, description = "User not whitelisted", body = ApiResponse<String>),
+        (status = 429, description = "Rate limited or temporarily locked out; see Retry-After", body = ApiResponse<String>)
     )
 )]
 pub async fn device_poll(
     State(app_state): State<AppState>,
     Json(payload): Json<DevicePollRequest>,
-) -> ResponseJson<ApiResponse<AuthResponse>> {
+) -> Response {
+    if !app_state.auth_providers().github_enabled() {
+        return ResponseJson(ApiResponse::<AuthResponse>::error(
+            "GitHub login is disabled on this server",
+        ))
+        .into_response();
+    }
+
     let client_id = get_github_client_id();
 
     let params = [
@@ -174,28 +198,31 @@ pub async fn device_poll(
     let res = match res {
         Ok(r) => r,
         Err(e) => {
-            return ResponseJson(ApiResponse::error(&format!(
+            return ResponseJson(ApiResponse::<AuthResponse>::error(&format!(
                 "Failed to contact GitHub: {e}"
-            )));
+            ))).into_response();
         }
     };
     let json: serde_json::Value = match res.json().await {
         Ok(j) => j,
         Err(e) => {
-            return ResponseJson(ApiResponse::error(&format!(
+            return ResponseJson(ApiResponse::<AuthResponse>::error(&format!(
                 "Failed to parse GitHub response: {e}"
-            )));
+            ))).into_response();
         }
     };
     
     if let Some(error) = json.get("error").and_then(|v| v.as_str()) {
         // Not authorized yet, or other error
-        return ResponseJson(ApiResponse::error(error));
+        if matches!(error, "authorization_pending" | "slow_down") {
+            return ResponseJson(ApiResponse::<AuthResponse>::error(error)).into_response();
+        }
+        return auth_failure_response(error);
     }
     
     let access_token = json.get("access_token").and_then(|v| v.as_str());
     let Some(access_token) = access_token else {
-        return ResponseJson(ApiResponse::error("No access token yet"));
+        return ResponseJson(ApiResponse::<AuthResponse>::error("No access token yet")).into_response();
     };
 
     // Fetch user info from GitHub
@@ -209,15 +236,15 @@ pub async fn device_poll(
         Ok(res) => match res.json().await {
             Ok(json) => json,
             Err(e) => {
-                return ResponseJson(ApiResponse::error(&format!(
+                return ResponseJson(ApiResponse::<AuthResponse>::error(&format!(
                     "Failed to parse GitHub user response: {e}"
-                )));
+                ))).into_response();
             }
         },
         Err(e) => {
-            return ResponseJson(ApiResponse::error(&format!(
+            return ResponseJson(ApiResponse::<AuthResponse>::error(&format!(
                 "Failed to fetch user info: {e}"
-            )));
+            ))).into_response();
         }
     };
 
@@ -237,15 +264,15 @@ pub async fn device_poll(
         Ok(res) => match res.json().await {
             Ok(json) => json,
             Err(e) => {
-                return ResponseJson(ApiResponse::error(&format!(
+                return ResponseJson(ApiResponse::<AuthResponse>::error(&format!(
                     "Failed to parse GitHub emails response: {e}"
-                )));
+                ))).into_response();
             }
         },
         Err(e) => {
-            return ResponseJson(ApiResponse::error(&format!(
+            return ResponseJson(ApiResponse::<AuthResponse>::error(&format!(
                 "Failed to fetch user emails: {e}"
-            )));
+            ))).into_response();
         }
     };
     
@@ -298,14 +325,14 @@ pub async fn device_poll(
                 }
                 Err(e) => {
                     tracing::error!("Failed to check whitelist status: {}", e);
-                    return ResponseJson(ApiResponse::error("Failed to validate user access"));
+                    return ResponseJson(ApiResponse::<AuthResponse>::error("Failed to validate user access")).into_response();
                 }
             }
         }
 
         if !is_whitelisted {
             tracing::warn!("User {} (ID: {}) is not in whitelist", username, github_id);
-            return ResponseJson(ApiResponse::error("User not authorized to access this application"));
+            return auth_failure_response("User not authorized to access this application");
         }
     } else {
         tracing::info!("GitHub whitelist is disabled, allowing user {} (ID: {})", username, github_id);
@@ -320,7 +347,7 @@ pub async fn device_poll(
                 email: Some(primary_email.clone()),
                 display_name: display_name.clone(),
                 avatar_url: avatar_url.clone(),
-                github_token: Some(access_token.to_string()),
+                github_token: None,
                 is_admin: None, // Don't change admin status
                 is_whitelisted: None, // Don't change whitelist status
             };
@@ -328,7 +355,7 @@ pub async fn device_poll(
                 Ok(updated_user) => updated_user,
                 Err(e) => {
                     tracing::error!("Failed to update user: {}", e);
-                    return ResponseJson(ApiResponse::error("Failed to update user information"));
+                    return ResponseJson(ApiResponse::<AuthResponse>::error("Failed to update user information")).into_response();
                 }
             }
         }
@@ -340,55 +367,36 @@ pub async fn device_poll(
                 email: primary_email.clone(),
                 display_name,
                 avatar_url,
-                github_token: Some(access_token.to_string()),
+                github_token: None,
                 is_admin: Some(false), // New users are not admin by default
             };
             match User::create(&app_state.db_pool, &create_data, Uuid::new_v4()).await {
                 Ok(new_user) => new_user,
                 Err(e) => {
                     tracing::error!("Failed to create user: {}", e);
-                    return ResponseJson(ApiResponse::error("Failed to create user account"));
+                    return ResponseJson(ApiResponse::<AuthResponse>::error("Failed to create user account")).into_response();
                 }
             }
         }
         Err(e) => {
             tracing::error!("Database error during user lookup: {}", e);
-            return ResponseJson(ApiResponse::error("Database error"));
-        }
-    };
-
-    // Generate session ID first
-    let session_id = Uuid::new_v4();
-    
-    // Generate JWT token
-    let jwt_config = JwtConfig::default();
-    
-    let jwt_token = match generate_jwt_token(user.id, session_id, SessionType::Web, &jwt_config) {
-        Ok(token) => token,
-        Err(e) => {
-            tracing::error!("Failed to generate JWT token: {}", e);
-            return ResponseJson(ApiResponse::error("Failed to generate session token"));
+            return ResponseJson(ApiResponse::<AuthResponse>::error("Database error")).into_response();
         }
     };
 
-    // Hash token for database storage
-    let token_hash = hash_token(&jwt_token);
-
-    // Create session in database with the same session_id used in JWT
-    let expires_at = chrono::Utc::now() + chrono::Duration::hours(UserSession::WEB_SESSION_DURATION_HOURS);
-    let session_data = crate::models::user_session::CreateUserSession {
-        user_id: user.id,
-        token_hash,
-        session_type: SessionType::Web,
-        client_info: Some("Web Browser".to_string()),
-        expires_at,
-    };
+    if let Err(e) = app_state
+        .secret_store()
+        .set_user_github_token(user.id, access_token)
+        .await
+    {
+        tracing::error!("Failed to store GitHub token for {}: {}", username, e);
+    }
 
-    let session = match UserSession::create(&app_state.db_pool, &session_data, session_id).await {
-        Ok(session) => session,
+    let (jwt_token, session) = match issue_web_session(&app_state, &user, "Web Browser").await {
+        Ok(issued) => issued,
         Err(e) => {
             tracing::error!("Failed to create user session: {}", e);
-            return ResponseJson(ApiResponse::error("Failed to create session"));
+            return ResponseJson(ApiResponse::<AuthResponse>::error("Failed to create session")).into_response();
         }
     };
 
@@ -400,6 +408,9 @@ pub async fn device_poll(
         config.github.token = Some(access_token.to_string());
         config.github_login_acknowledged = true;
  
+        if let Err(e) = app_state.secret_store().save_config_secrets(&config).await {
+            tracing::warn!("Failed to store config secrets (non-critical): {}", e);
+        }
         let config_path = crate::utils::config_path();
         if let Err(e) = config.save(&config_path) {
             tracing::warn!("Failed to save config (non-critical): {}", e);
@@ -422,7 +433,7 @@ pub async fn device_poll(
         session,
     };
 
-    ResponseJson(ApiResponse::success(auth_response))
+    ResponseJson(ApiResponse::success(auth_response)).into_response()
 }
 
 /// GET /auth/github/check
@@ -439,7 +450,7 @@ pub async fn device_poll(
     )
 )]
 pub async fn github_check_token(
-    State(_app_state): State<AppState>,
+    State(app_state): State<AppState>,
     req: Request,
 ) -> ResponseJson<ApiResponse<()>> {
     // Extract user from authenticated request
@@ -448,15 +459,20 @@ pub async fn github_check_token(
     };
 
     // Check if user has a GitHub token
-    let Some(token) = &user.github_token else {
-        return ResponseJson(ApiResponse::error("github_token_invalid"));
+    let token = match app_state.secret_store().user_github_token(user.id).await {
+        Ok(Some(token)) => token,
+        Ok(None) => return ResponseJson(ApiResponse::error("github_token_invalid")),
+        Err(e) => {
+            tracing::error!("Failed to read GitHub token for {}: {}", user.username, e);
+            return ResponseJson(ApiResponse::error("github_token_invalid"));
+        }
     };
 
     // Test the GitHub token
     let client = reqwest::Client::new();
     let res = client
         .get("https://api.github.com/user")
-        .bearer_auth(token)
+        .bearer_auth(token.as_str())
         .header("User-Agent", "automagik-forge-app")
         .send()
         .await;
@@ -0,0 +1,358 @@
+use axum::{
+    extract::State,
+    http::{header::SET_COOKIE, HeaderMap},
+    response::{AppendHeaders, IntoResponse, Json as ResponseJson, Response},
+    Extension, Json,
+};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use super::auth::{auth_failure_response, AuthResponse};
+use crate::{
+    app_state::AppState,
+    auth::UserContext,
+    auth_providers::{
+        issue_web_session, local::CreateLocalUser, oidc, AuthProviderError, AuthProviderInfo,
+    },
+    models::{user::User, ApiResponse},
+    security::audit_logger::{extract_request_context, AuditResult},
+};
+
+/// Path the web login's OIDC cookie is sent to
+const OIDC_COOKIE_PATH: &str = "/api/auth/oidc";
+
+#[derive(Deserialize, TS, ToSchema)]
+#[ts(export)]
+pub struct LocalLoginRequest {
+    pub username: String,
+    pub password: String,
+}
+
+#[derive(Deserialize, TS, ToSchema)]
+#[ts(export)]
+pub struct ChangePasswordRequest {
+    pub current_password: String,
+    pub new_password: String,
+}
+
+#[derive(Serialize, TS, ToSchema)]
+#[ts(export)]
+pub struct OidcStartResponse {
+    pub authorization_url: String,
+}
+
+#[derive(Deserialize, TS, ToSchema)]
+#[ts(export)]
+pub struct OidcCallbackRequest {
+    pub code: String,
+    pub state: String,
+}
+
+/// Map a provider error onto the login response. Credential and authorization
+/// failures are flagged for the rate limiter; anything else is a server error.
+fn login_error_response(error: AuthProviderError) -> Response {
+    match error {
+        AuthProviderError::InvalidCredentials
+        | AuthProviderError::UserNotAuthorized
+        | AuthProviderError::InvalidState => auth_failure_response(&error.to_string()),
+        AuthProviderError::ProviderDisabled => {
+            ResponseJson(ApiResponse::<AuthResponse>::error(&error.to_string())).into_response()
+        }
+        other => {
+            tracing::error!("Login failed: {}", other);
+            ResponseJson(ApiResponse::<AuthResponse>::error("Login failed")).into_response()
+        }
+    }
+}
+
+/// GET /auth/providers
+#[utoipa::path(
+    get,
+    path = "/auth/providers",
+    tag = "auth",
+    summary = "List login providers",
+    description = "Returns the login providers enabled on this server, in the order they should be offered",
+    responses(
+        (status = 200, description = "Enabled login providers", body = ApiResponse<Vec<AuthProviderInfo>>)
+    )
+)]
+pub async fn list_auth_providers(
+    State(app_state): State<AppState>,
+) -> ResponseJson<ApiResponse<Vec<AuthProviderInfo>>> {
+    ResponseJson(ApiResponse::success(app_state.auth_providers().list()))
+}
+
+/// POST /auth/local/login
+#[utoipa::path(
+    post,
+    path = "/auth/local/login",
+    tag = "auth",
+    summary = "Log in with a local account",
+    description = "Checks a username and password against local accounts and creates a JWT session",
+    request_body = LocalLoginRequest,
+    responses(
+        (status = 200, description = "Login successful with JWT token", body = ApiResponse<AuthResponse>),
+        (status = 429, description = "Rate limited or temporarily locked out; see Retry-After", body = ApiResponse<String>)
+    )
+)]
+pub async fn local_login(
+    State(app_state): State<AppState>,
+    Json(payload): Json<LocalLoginRequest>,
+) -> Response {
+    let local = match app_state.auth_providers().local() {
+        Ok(local) => local,
+        Err(e) => return login_error_response(e),
+    };
+
+    let user = match local
+        .authenticate(&app_state.db_pool, &payload.username, &payload.password)
+        .await
+    {
+        Ok(user) => user,
+        Err(e) => {
+            tracing::warn!("Local login failed for {}: {}", payload.username, e);
+            return login_error_response(e);
+        }
+    };
+
+    match issue_web_session(&app_state, &user, "Web Browser (local account)").await {
+        Ok((access_token, session)) => ResponseJson(ApiResponse::success(AuthResponse {
+            access_token,
+            user,
+            session,
+        }))
+        .into_response(),
+        Err(e) => login_error_response(e),
+    }
+}
+
+/// POST /auth/local/users
+#[utoipa::path(
+    post,
+    path = "/auth/local/users",
+    tag = "auth",
+    summary = "Create a local account",
+    description = "Creates a user that logs in with a username and password (admin only)",
+    request_body = CreateLocalUser,
+    responses(
+        (status = 200, description = "Account created", body = ApiResponse<User>),
+        (status = 401, description = "Not authenticated", body = ApiResponse<String>)
+    )
+)]
+pub async fn create_local_user(
+    State(app_state): State<AppState>,
+    Extension(user_context): Extension<UserContext>,
+    headers: HeaderMap,
+    Json(payload): Json<CreateLocalUser>,
+) -> ResponseJson<ApiResponse<User>> {
+    let (ip_address, user_agent) = extract_request_context(&headers);
+    let admin = &user_context.user;
+
+    if !admin.is_admin {
+        let _ = app_state
+            .audit_logger()
+            .log_admin_action(
+                admin.id,
+                ip_address,
+                user_agent,
+                "users",
+                "create_local_user",
+                None,
+                AuditResult::Blocked,
+                Some(serde_json::json!({ "username": payload.username })),
+            )
+            .await;
+        return ResponseJson(ApiResponse::error("Admin access required"));
+    }
+
+    let local = match app_state.auth_providers().local() {
+        Ok(local) => local,
+        Err(e) => return ResponseJson(ApiResponse::error(&e.to_string())),
+    };
+
+    let result = local.create_user(&app_state.db_pool, &payload).await;
+    let (audit_result, target) = match &result {
+        Ok(user) => (AuditResult::Success, Some(user.id)),
+        Err(_) => (AuditResult::Failure, None),
+    };
+    if let Err(e) = app_state
+        .audit_logger()
+        .log_admin_action(
+            admin.id,
+            ip_address,
+            user_agent,
+            "users",
+            "create_local_user",
+            target,
+            audit_result,
+            Some(serde_json::json!({ "username": payload.username })),
+        )
+        .await
+    {
+        tracing::error!("Failed to audit local user creation: {}", e);
+    }
+
+    match result {
+        Ok(user) => ResponseJson(ApiResponse::success(user)),
+        Err(
+            e @ (AuthProviderError::UsernameTaken
+            | AuthProviderError::WeakPassword(_)
+            | AuthProviderError::InvalidCredentials),
+        ) => ResponseJson(ApiResponse::error(&e.to_string())),
+        Err(e) => {
+            tracing::error!("Failed to create local user: {}", e);
+            ResponseJson(ApiResponse::error("Failed to create user account"))
+        }
+    }
+}
+
+/// PUT /auth/local/password
+#[utoipa::path(
+    put,
+    path = "/auth/local/password",
+    tag = "auth",
+    summary = "Change local account password",
+    description = "Changes the password of the current user's local account",
+    request_body = ChangePasswordRequest,
+    responses(
+        (status = 200, description = "Password changed", body = ApiResponse<String>),
+        (status = 401, description = "Not authenticated", body = ApiResponse<String>)
+    )
+)]
+pub async fn change_local_password(
+    State(app_state): State<AppState>,
+    Extension(user_context): Extension<UserContext>,
+    Json(payload): Json<ChangePasswordRequest>,
+) -> ResponseJson<ApiResponse<String>> {
+    let local = match app_state.auth_providers().local() {
+        Ok(local) => local,
+        Err(e) => return ResponseJson(ApiResponse::error(&e.to_string())),
+    };
+
+    match local
+        .change_password(
+            &app_state.db_pool,
+            user_context.user.id,
+            &payload.current_password,
+            &payload.new_password,
+        )
+        .await
+    {
+        Ok(()) => ResponseJson(ApiResponse::success("Password changed".to_string())),
+        Err(AuthProviderError::ProviderDisabled) => {
+            ResponseJson(ApiResponse::error("This account has no local password"))
+        }
+        Err(e @ (AuthProviderError::InvalidCredentials | AuthProviderError::WeakPassword(_))) => {
+            ResponseJson(ApiResponse::error(&e.to_string()))
+        }
+        Err(e) => {
+            tracing::error!("Failed to change password: {}", e);
+            ResponseJson(ApiResponse::error("Failed to change password"))
+        }
+    }
+}
+
+/// POST /auth/oidc/start
+#[utoipa::path(
+    post,
+    path = "/auth/oidc/start",
+    tag = "auth",
+    summary = "Start OIDC login",
+    description = "Returns the identity provider URL the browser should be sent to, and sets a short-lived cookie the callback has to come back with",
+    responses(
+        (status = 200, description = "Authorization URL", body = ApiResponse<OidcStartResponse>)
+    )
+)]
+pub async fn oidc_start(State(app_state): State<AppState>) -> Response {
+    let oidc = match app_state.auth_providers().oidc() {
+        Ok(oidc) => oidc,
+        Err(e) => {
+            return ResponseJson(ApiResponse::<OidcStartResponse>::error(&e.to_string()))
+                .into_response()
+        }
+    };
+
+    let state = Uuid::new_v4().to_string();
+    let binding = oidc::new_browser_binding();
+    match oidc
+        .begin_login(&state, &oidc.config().web_redirect_url(), &binding)
+        .await
+    {
+        Ok(authorization_url) => (
+            AppendHeaders([(SET_COOKIE, oidc::login_cookie(OIDC_COOKIE_PATH, &binding))]),
+            ResponseJson(ApiResponse::success(OidcStartResponse {
+                authorization_url,
+            })),
+        )
+            .into_response(),
+        Err(e) => {
+            tracing::error!("Failed to start OIDC login: {}", e);
+            ResponseJson(ApiResponse::<OidcStartResponse>::error(
+                "Failed to contact identity provider",
+            ))
+            .into_response()
+        }
+    }
+}
+
+/// POST /auth/oidc/callback
+#[utoipa::path(
+    post,
+    path = "/auth/oidc/callback",
+    tag = "auth",
+    summary = "Complete OIDC login",
+    description = "Exchanges the authorization code returned by the identity provider and creates a JWT session. Only the browser that started the login, holding its cookie, can complete it.",
+    request_body = OidcCallbackRequest,
+    responses(
+        (status = 200, description = "Login successful with JWT token", body = ApiResponse<AuthResponse>),
+        (status = 429, description = "Rate limited or temporarily locked out; see Retry-After", body = ApiResponse<String>)
+    )
+)]
+pub async fn oidc_callback(
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<OidcCallbackRequest>,
+) -> Response {
+    let response = complete_oidc_login(&app_state, &headers, &payload).await;
+    (
+        AppendHeaders([(SET_COOKIE, oidc::cleared_login_cookie(OIDC_COOKIE_PATH))]),
+        response,
+    )
+        .into_response()
+}
+
+async fn complete_oidc_login(
+    app_state: &AppState,
+    headers: &HeaderMap,
+    payload: &OidcCallbackRequest,
+) -> Response {
+    let oidc = match app_state.auth_providers().oidc() {
+        Ok(oidc) => oidc,
+        Err(e) => return login_error_response(e),
+    };
+
+    let binding = oidc::browser_binding(headers);
+    let identity = match oidc
+        .complete_login(&payload.state, &payload.code, binding.as_deref())
+        .await
+    {
+        Ok(identity) => identity,
+        Err(e) => return login_error_response(e),
+    };
+    let user = match oidc.resolve_user(&app_state.db_pool, &identity).await {
+        Ok(user) => user,
+        Err(e) => return login_error_response(e),
+    };
+
+    match issue_web_session(app_state, &user, "Web Browser (OIDC)").await {
+        Ok((access_token, session)) => ResponseJson(ApiResponse::success(AuthResponse {
+            access_token,
+            user,
+            session,
+        }))
+        .into_response(),
+        Err(e) => login_error_response(e),
+    }
+}
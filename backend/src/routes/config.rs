@@ -20,6 +20,7 @@ use crate::{
         // user_preferences::{UserPreferences, UpdateUserPreferences},
         ApiResponse,
     },
+    security::secret_store::{is_masked, mask_secret},
     utils,
 };
 
@@ -46,7 +47,7 @@ pub fn config_router() -> Router<AppState> {
 )]
 pub async fn get_config(State(app_state): State<AppState>) -> ResponseJson<ApiResponse<Config>> {
     let config = app_state.get_config().read().await;
-    ResponseJson(ApiResponse::success(config.clone()))
+    ResponseJson(ApiResponse::success(config.masked()))
 }
 
 #[utoipa::path(
@@ -63,10 +64,25 @@ pub async fn get_config(State(app_state): State<AppState>) -> ResponseJson<ApiRe
 )]
 pub async fn update_config(
     State(app_state): State<AppState>,
-    Json(new_config): Json<Config>,
+    Json(mut new_config): Json<Config>,
 ) -> ResponseJson<ApiResponse<Config>> {
     let config_path = utils::config_path();
 
+    // Secrets come back masked unless the user typed a new value
+    {
+        let current = app_state.get_config().read().await;
+        new_config.keep_masked_secrets(&current);
+    }
+
+    if let Err(e) = app_state
+        .secret_store()
+        .save_config_secrets(&new_config)
+        .await
+    {
+        tracing::error!("Failed to store config secrets: {}", e);
+        return ResponseJson(ApiResponse::error("Failed to save config secrets"));
+    }
+
     match new_config.save(&config_path) {
         Ok(_) => {
             let mut config = app_state.get_config().write().await;
@@ -77,7 +93,7 @@ pub async fn update_config(
                 .update_analytics_config(new_config.analytics_enabled.unwrap_or(true))
                 .await;
 
-            ResponseJson(ApiResponse::success(new_config))
+            ResponseJson(ApiResponse::success(new_config.masked()))
         }
         Err(e) => ResponseJson(ApiResponse::error(&format!("Failed to save config: {}", e))),
     }
@@ -240,7 +256,8 @@ pub async fn get_mcp_servers(
     };
 
     match read_mcp_servers_from_config(&config_path, &executor_config).await {
-        Ok(servers) => {
+        Ok(mut servers) => {
+            mask_mcp_server_secrets(&mut servers);
             let response_data = serde_json::json!({
                 "servers": servers,
                 "config_path": config_path.to_string_lossy().to_string()
@@ -259,7 +276,7 @@ pub async fn get_mcp_servers(
     path = "/mcp-servers",
     tag = "config",
     summary = "Update MCP servers configuration",
-    description = "Updates MCP (Model Context Protocol) servers configuration for the specified executor",
+    description = "Updates MCP (Model Context Protocol) servers in the specified executor's global config file, shared by every project and user on this machine. New env or header values are rejected since they would be stored in plaintext; use /api/projects/{id}/mcp-servers, which encrypts them and only reaches the attempts that use them.",
     params(
         ("executor" = Option<String>, Query, description = "Executor type to update MCP servers for")
     ),
@@ -306,7 +323,7 @@ pub async fn update_mcp_servers(
 async fn update_mcp_servers_in_config(
     file_path: &std::path::Path,
     executor_config: &ExecutorConfig,
-    new_servers: HashMap<String, Value>,
+    mut new_servers: HashMap<String, Value>,
 ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
     // Ensure parent directory exists
     if let Some(parent) = file_path.parent() {
@@ -322,8 +339,11 @@ async fn update_mcp_servers_in_config(
     // Get the attribute path for MCP servers
     let mcp_path = executor_config.mcp_attribute_path().unwrap();
 
-    // Get the current server count for comparison
-    let old_servers = get_mcp_servers_from_config_path(&config, &mcp_path).len();
+    // Get the current servers for comparison and to restore masked credentials
+    let existing_servers = get_mcp_servers_from_config_path(&config, &mcp_path);
+    let old_servers = existing_servers.len();
+    restore_mcp_server_secrets(&mut new_servers, &existing_servers);
+    reject_new_mcp_server_secrets(&new_servers, &existing_servers)?;
 
     // Set the MCP servers using the correct attribute path
     set_mcp_servers_in_config_path(&mut config, &mcp_path, &new_servers)?;
@@ -365,6 +385,88 @@ async fn read_mcp_servers_from_config(
     Ok(servers)
 }
 
+/// Server fields holding credentials: environment variables and HTTP headers
+const MCP_SECRET_FIELDS: [&str; 2] = ["env", "headers"];
+
+/// Mask credentials before MCP servers are sent to the browser. The executor's
+/// config file keeps them in plaintext because the agent CLIs read it directly;
+/// see `reject_new_mcp_server_secrets`.
+fn mask_mcp_server_secrets(servers: &mut HashMap<String, Value>) {
+    for server in servers.values_mut() {
+        for field in MCP_SECRET_FIELDS {
+            if let Some(values) = server.get_mut(field).and_then(Value::as_object_mut) {
+                for value in values.values_mut() {
+                    if let Some(secret) = value.as_str() {
+                        *value = Value::String(mask_secret(secret));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Put back the stored credential for every value the client returned masked.
+/// A masked value with nothing stored behind it is dropped.
+fn restore_mcp_server_secrets(
+    servers: &mut HashMap<String, Value>,
+    existing: &HashMap<String, Value>,
+) {
+    for (name, server) in servers.iter_mut() {
+        for field in MCP_SECRET_FIELDS {
+            let Some(values) = server.get_mut(field).and_then(Value::as_object_mut) else {
+                continue;
+            };
+            let stored = existing
+                .get(name)
+                .and_then(|s| s.get(field))
+                .and_then(Value::as_object);
+            values.retain(|key, value| {
+                if !value.as_str().is_some_and(is_masked) {
+                    return true;
+                }
+                match stored.and_then(|stored| stored.get(key)) {
+                    Some(original) => {
+                        *value = original.clone();
+                        true
+                    }
+                    None => false,
+                }
+            });
+        }
+    }
+}
+
+/// The executor's global config file can only hold credentials in plaintext, so
+/// no new ones are written there: project MCP servers keep theirs encrypted in
+/// the secret store and supersede this endpoint for anything needing secrets.
+/// Values already in the file are left as they are.
+fn reject_new_mcp_server_secrets(
+    servers: &HashMap<String, Value>,
+    existing: &HashMap<String, Value>,
+) -> Result<(), String> {
+    for (name, server) in servers {
+        for field in MCP_SECRET_FIELDS {
+            let Some(values) = server.get(field).and_then(Value::as_object) else {
+                continue;
+            };
+            let stored = existing
+                .get(name)
+                .and_then(|s| s.get(field))
+                .and_then(Value::as_object);
+            let is_new = values
+                .iter()
+                .any(|(key, value)| stored.and_then(|stored| stored.get(key)) != Some(value));
+            if is_new {
+                return Err(format!(
+                    "'{}' sets new {} values, which would be stored in plaintext; add it as a project MCP server instead, where they are encrypted",
+                    name, field
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
 /// Helper function to get MCP servers from config using a path
 fn get_mcp_servers_from_config_path(config: &Value, path: &[&str]) -> HashMap<String, Value> {
     // Special handling for AMP - use flat key structure
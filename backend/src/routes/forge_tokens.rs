@@ -0,0 +1,111 @@
+use axum::{
+    extract::{Query, State},
+    response::Json as ResponseJson,
+    routing::get,
+    Extension, Json, Router,
+};
+use serde::Deserialize;
+
+use crate::{
+    app_state::AppState,
+    auth::UserContext,
+    models::ApiResponse,
+    services::forge::{self, ForgeError, ForgeTokenSummary, SaveForgeToken},
+};
+
+#[derive(Deserialize)]
+pub struct ForgeTokenQuery {
+    pub host: String,
+}
+
+/// GET /api/forge-tokens
+#[utoipa::path(
+    get,
+    path = "/api/forge-tokens",
+    tag = "forge_tokens",
+    summary = "List forge tokens",
+    description = "Lists the current user's GitLab, Gitea and GitHub tokens with masked values, one per host",
+    responses(
+        (status = 200, description = "Saved tokens", body = ApiResponse<Vec<ForgeTokenSummary>>)
+    )
+)]
+pub async fn list_forge_tokens(
+    Extension(user_context): Extension<UserContext>,
+    State(app_state): State<AppState>,
+) -> ResponseJson<ApiResponse<Vec<ForgeTokenSummary>>> {
+    match forge::list_user_tokens(app_state.secret_store(), user_context.user.id).await {
+        Ok(tokens) => ResponseJson(ApiResponse::success(tokens)),
+        Err(e) => {
+            tracing::error!("Failed to list forge tokens: {}", e);
+            ResponseJson(ApiResponse::error("Failed to list forge tokens"))
+        }
+    }
+}
+
+/// PUT /api/forge-tokens
+#[utoipa::path(
+    put,
+    path = "/api/forge-tokens",
+    tag = "forge_tokens",
+    summary = "Save a forge token",
+    description = "Stores the current user's token for a forge host, which PRs on projects hosted there are opened and tracked with. Saving a token also tells Forge which kind of forge a self-hosted host runs.",
+    request_body = SaveForgeToken,
+    responses(
+        (status = 200, description = "Token saved", body = ApiResponse<ForgeTokenSummary>)
+    )
+)]
+pub async fn save_forge_token(
+    Extension(user_context): Extension<UserContext>,
+    State(app_state): State<AppState>,
+    Json(payload): Json<SaveForgeToken>,
+) -> ResponseJson<ApiResponse<ForgeTokenSummary>> {
+    match forge::save_user_token(app_state.secret_store(), user_context.user.id, &payload).await {
+        Ok(token) => ResponseJson(ApiResponse::success(token)),
+        Err(ForgeError::SecretStore(e)) => {
+            tracing::error!("Failed to save forge token: {}", e);
+            ResponseJson(ApiResponse::error("Failed to save forge token"))
+        }
+        Err(e) => ResponseJson(ApiResponse::error(&e.to_string())),
+    }
+}
+
+/// DELETE /api/forge-tokens?host=...
+#[utoipa::path(
+    delete,
+    path = "/api/forge-tokens",
+    tag = "forge_tokens",
+    summary = "Delete a forge token",
+    description = "Forgets the current user's token for a forge host",
+    params(
+        ("host" = String, Query, description = "Forge host")
+    ),
+    responses(
+        (status = 200, description = "Token deleted", body = ApiResponse<String>)
+    )
+)]
+pub async fn delete_forge_token(
+    Extension(user_context): Extension<UserContext>,
+    State(app_state): State<AppState>,
+    Query(query): Query<ForgeTokenQuery>,
+) -> ResponseJson<ApiResponse<String>> {
+    match forge::delete_user_token(app_state.secret_store(), user_context.user.id, &query.host)
+        .await
+    {
+        Ok(true) => ResponseJson(ApiResponse::success("Forge token deleted".to_string())),
+        Ok(false) => ResponseJson(ApiResponse::error("No token saved for this host")),
+        Err(ForgeError::SecretStore(e)) => {
+            tracing::error!("Failed to delete forge token: {}", e);
+            ResponseJson(ApiResponse::error("Failed to delete forge token"))
+        }
+        Err(e) => ResponseJson(ApiResponse::error(&e.to_string())),
+    }
+}
+
+pub fn forge_tokens_router() -> Router<AppState> {
+    Router::new().route(
+        "/forge-tokens",
+        get(list_forge_tokens)
+            .put(save_forge_token)
+            .delete(delete_forge_token),
+    )
+}
@@ -0,0 +1,97 @@
+use axum::{
+    body::Bytes, extract::State, http::HeaderMap, response::Json as ResponseJson, routing::post,
+    Router,
+};
+
+use crate::{
+    app_state::AppState,
+    models::ApiResponse,
+    security::audit_logger::{
+        extract_request_context, AuditEventType, AuditResult, AuditSeverity, CreateAuditEvent,
+    },
+    services::{
+        github_webhooks::{self, GitHubWebhookError},
+        review_followups,
+    },
+};
+
+fn header<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|value| value.to_str().ok())
+}
+
+/// POST /api/integrations/github/webhook
+#[utoipa::path(
+    post,
+    path = "/api/integrations/github/webhook",
+    tag = "integrations",
+    summary = "Receive GitHub events",
+    description = "GitHub webhook for pull_request, pull_request_review, pull_request_review_comment and check_run events, signed with the github.webhook_secret secret. Events about pull requests Forge opened update the attempt's PR status, reviews, review comments and CI checks; others are ignored. Submitted reviews start a follow-up addressing their comments in projects that enabled review automation.",
+    params(
+        ("X-GitHub-Event" = String, Header, description = "Event name"),
+        ("X-Hub-Signature-256" = String, Header, description = "sha256=<hex HMAC-SHA256 of the body>")
+    ),
+    request_body = serde_json::Value,
+    responses(
+        (status = 200, description = "Event handled or ignored", body = ApiResponse<String>)
+    )
+)]
+pub async fn receive_github_webhook(
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> ResponseJson<ApiResponse<String>> {
+    let Some(secret) = github_webhooks::webhook_secret(app_state.secret_store()).await else {
+        return ResponseJson(ApiResponse::error("GitHub webhooks are not enabled"));
+    };
+    let signature = header(&headers, "x-hub-signature-256").unwrap_or_default();
+    if !github_webhooks::verify_signature(&secret, &body, signature) {
+        let (ip_address, user_agent) = extract_request_context(&headers);
+        if let Err(e) = app_state
+            .audit_logger()
+            .log_event(CreateAuditEvent {
+                event_type: AuditEventType::Authentication,
+                user_id: None,
+                ip_address,
+                user_agent,
+                resource: "github_webhook".to_string(),
+                action: "verify_signature".to_string(),
+                result: AuditResult::Blocked,
+                details: Some(serde_json::json!({
+                    "delivery": header(&headers, "x-github-delivery"),
+                })),
+                severity: AuditSeverity::Medium,
+            })
+            .await
+        {
+            tracing::error!("Failed to audit GitHub webhook delivery: {}", e);
+        }
+        return ResponseJson(ApiResponse::error("Invalid signature"));
+    }
+
+    let event = header(&headers, "x-github-event").unwrap_or_default();
+    match github_webhooks::handle_event(&app_state.db_pool, app_state.secret_store(), event, &body)
+        .await
+    {
+        Ok(outcome) => {
+            tracing::debug!("GitHub {} event: {}", event, outcome);
+            if let Some(pr_url) = github_webhooks::submitted_review_pr_url(event, &body) {
+                tokio::spawn(async move {
+                    review_followups::on_review_submitted(&app_state, &pr_url).await;
+                });
+            }
+            ResponseJson(ApiResponse::success(outcome))
+        }
+        Err(e @ GitHubWebhookError::Payload { .. }) => {
+            tracing::warn!("Rejected GitHub webhook delivery: {}", e);
+            ResponseJson(ApiResponse::error("Malformed event payload"))
+        }
+        Err(e) => {
+            tracing::error!("Failed to handle GitHub {} event: {}", event, e);
+            ResponseJson(ApiResponse::error("Failed to handle event"))
+        }
+    }
+}
+
+pub fn github_webhook_router() -> Router<AppState> {
+    Router::new().route("/integrations/github/webhook", post(receive_github_webhook))
+}
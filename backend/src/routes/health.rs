@@ -171,7 +171,7 @@ pub async fn security_health_check(
 ) -> Json<ApiResponse<SecurityMetrics>> {
     // Get security metrics from audit logger
     let now = Utc::now();
-    let _one_hour_ago = now - chrono::Duration::hours(1);
+    let one_hour_ago = now - chrono::Duration::hours(1);
 
     // Collect security metrics
     let active_sessions = match sqlx::query!(
@@ -187,6 +187,16 @@ pub async fn security_health_check(
     let failed_auth_attempts = 0u64;
     let security_events = 0u64;
 
+    // Rate limiter rejections are always audited, so count them directly
+    let rate_limit_violations = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM audit_log WHERE event_type = 'security_violation' AND action IN ('rate_limit_exceeded', 'locked_out_request', 'auth_lockout') AND timestamp >= ?1"
+    )
+    .bind(one_hour_ago)
+    .fetch_one(&app_state.db_pool)
+    .await
+    .map(|count| count as u64)
+    .unwrap_or(0);
+
     // Create simplified system health
     let database_healthy = sqlx::query!("SELECT 1 as status").fetch_one(&app_state.db_pool).await.is_ok();
     
@@ -202,6 +212,8 @@ pub async fn security_health_check(
         active_sessions,
         failed_auth_attempts_last_hour: failed_auth_attempts,
         security_events_last_hour: security_events,
+        rate_limit_violations_last_hour: rate_limit_violations,
+        active_lockouts: app_state.rate_limiter().active_lockouts(),
         suspicious_activities: Vec::new(), // Simplified
         system_health,
     };
@@ -1,9 +1,20 @@
 pub mod auth;
+pub mod auth_providers;
 pub mod config;
 pub mod filesystem;
+pub mod forge_tokens;
+pub mod github_webhook;
 pub mod health;
+pub mod notification_channels;
+pub mod notification_rules;
 pub mod oauth;
+pub mod project_env;
+pub mod project_github_issues;
+pub mod project_mcp_servers;
+pub mod project_webhooks;
 pub mod projects;
+pub mod secrets;
 pub mod task_attempts;
 pub mod task_templates;
 pub mod tasks;
+pub mod whatsapp_webhook;
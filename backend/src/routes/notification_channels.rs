@@ -0,0 +1,559 @@
+use std::collections::BTreeMap;
+
+use axum::{
+    extract::{Query, State},
+    http::HeaderMap,
+    response::Json as ResponseJson,
+    routing::{get, post},
+    Extension, Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use utoipa::ToSchema;
+
+use crate::{
+    app_state::AppState,
+    auth::UserContext,
+    models::{
+        user_notification_channel::{
+            NotificationChannelKind, UpsertUserNotificationChannel, UserNotificationChannel,
+        },
+        whatsapp_message::WhatsAppMessage,
+        whatsapp_number_verification::WhatsAppNumberVerification,
+        ApiResponse,
+    },
+    security::{
+        audit_logger::{
+            extract_request_context, AuditEventType, AuditResult, AuditSeverity, CreateAuditEvent,
+        },
+        secret_store::SecretSummary,
+    },
+    services::{
+        notification_channels::{self, Notification, NotificationChannelError},
+        whatsapp_commands::{self, NumberVerificationError},
+    },
+};
+
+/// A channel with its secrets masked
+#[derive(Debug, Serialize, TS, ToSchema)]
+#[ts(export)]
+pub struct NotificationChannelDetails {
+    pub channel: UserNotificationChannel,
+    pub secrets: Vec<SecretSummary>,
+    /// For WhatsApp channels, whether the user verified the number, which
+    /// commands sent from it need
+    pub number_verified: Option<bool>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// Secrets sent back masked keep their stored value
+#[derive(Deserialize, TS, ToSchema)]
+#[ts(export)]
+pub struct SaveNotificationChannelRequest {
+    pub name: String,
+    pub kind: NotificationChannelKind,
+    #[serde(default)]
+    pub settings: BTreeMap<String, String>,
+    #[serde(default)]
+    pub secrets: BTreeMap<String, String>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+#[derive(Deserialize)]
+pub struct NotificationChannelQuery {
+    pub name: String,
+}
+
+/// The code sent to a WhatsApp channel's number
+#[derive(Deserialize, TS, ToSchema)]
+#[ts(export)]
+pub struct ConfirmWhatsAppNumberRequest {
+    pub code: String,
+}
+
+#[derive(Deserialize)]
+pub struct WhatsAppMessagesQuery {
+    pub limit: Option<i64>,
+}
+
+async fn audit_channel_action(
+    app_state: &AppState,
+    user_context: &UserContext,
+    headers: &HeaderMap,
+    action: &str,
+    name: &str,
+    result: AuditResult,
+) {
+    let (ip_address, user_agent) = extract_request_context(headers);
+    if let Err(e) = app_state
+        .audit_logger()
+        .log_event(CreateAuditEvent {
+            event_type: AuditEventType::ConfigChange,
+            user_id: Some(user_context.user.id),
+            ip_address,
+            user_agent,
+            resource: "notification_channels".to_string(),
+            action: action.to_string(),
+            result,
+            details: Some(serde_json::json!({ "name": name })),
+            severity: AuditSeverity::Low,
+        })
+        .await
+    {
+        tracing::error!("Failed to audit notification channel {}: {}", action, e);
+    }
+}
+
+async fn find_channel(
+    app_state: &AppState,
+    user_context: &UserContext,
+    name: &str,
+) -> Result<UserNotificationChannel, &'static str> {
+    match UserNotificationChannel::find_by_name(&app_state.db_pool, user_context.user.id, name)
+        .await
+    {
+        Ok(Some(channel)) => Ok(channel),
+        Ok(None) => Err("Notification channel not found"),
+        Err(e) => {
+            tracing::error!("Failed to load notification channel {}: {}", name, e);
+            Err("Failed to load notification channel")
+        }
+    }
+}
+
+/// GET /api/notifications/channels
+#[utoipa::path(
+    get,
+    path = "/api/notifications/channels",
+    tag = "notifications",
+    summary = "List notification channels",
+    description = "Lists the current user's notification channels with masked secrets",
+    responses(
+        (status = 200, description = "Notification channels", body = ApiResponse<Vec<NotificationChannelDetails>>)
+    )
+)]
+pub async fn list_notification_channels(
+    Extension(user_context): Extension<UserContext>,
+    State(app_state): State<AppState>,
+) -> ResponseJson<ApiResponse<Vec<NotificationChannelDetails>>> {
+    let result = async {
+        let mut details = Vec::new();
+        for channel in
+            UserNotificationChannel::find_by_user_id(&app_state.db_pool, user_context.user.id)
+                .await?
+        {
+            let secrets =
+                notification_channels::masked_secrets(app_state.secret_store(), channel.id).await?;
+            let number_verified = match channel.settings.get("number") {
+                Some(number) if channel.kind == NotificationChannelKind::WhatsApp => Some(
+                    WhatsAppNumberVerification::find(
+                        &app_state.db_pool,
+                        user_context.user.id,
+                        &whatsapp_commands::normalize_number(number),
+                    )
+                    .await?
+                    .is_some_and(|verification| verification.verified_at.is_some()),
+                ),
+                _ => None,
+            };
+            details.push(NotificationChannelDetails {
+                channel,
+                secrets,
+                number_verified,
+            });
+        }
+        Ok::<_, NotificationChannelError>(details)
+    }
+    .await;
+
+    match result {
+        Ok(details) => ResponseJson(ApiResponse::success(details)),
+        Err(e) => {
+            tracing::error!(
+                "Failed to list notification channels for user {}: {}",
+                user_context.user.id,
+                e
+            );
+            ResponseJson(ApiResponse::error("Failed to list notification channels"))
+        }
+    }
+}
+
+/// PUT /api/notifications/channels
+#[utoipa::path(
+    put,
+    path = "/api/notifications/channels",
+    tag = "notifications",
+    summary = "Save a notification channel",
+    description = "Creates or replaces one of the current user's notification channels. Webhook URLs, passwords and tokens are encrypted and never returned.",
+    request_body = SaveNotificationChannelRequest,
+    responses(
+        (status = 200, description = "Channel saved", body = ApiResponse<UserNotificationChannel>)
+    )
+)]
+pub async fn save_notification_channel(
+    Extension(user_context): Extension<UserContext>,
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<SaveNotificationChannelRequest>,
+) -> ResponseJson<ApiResponse<UserNotificationChannel>> {
+    let data = UpsertUserNotificationChannel {
+        name: payload.name.trim().to_string(),
+        kind: payload.kind,
+        settings: payload
+            .settings
+            .into_iter()
+            .filter(|(_, value)| !value.trim().is_empty())
+            .collect(),
+        enabled: payload.enabled,
+    };
+    let secrets: BTreeMap<String, String> = payload
+        .secrets
+        .into_iter()
+        .filter(|(_, value)| !value.trim().is_empty())
+        .collect();
+    let result = notification_channels::save(
+        &app_state.db_pool,
+        app_state.secret_store(),
+        user_context.user.id,
+        &data,
+        &secrets,
+    )
+    .await;
+    if let Err(NotificationChannelError::Invalid(message)) = &result {
+        return ResponseJson(ApiResponse::error(message));
+    }
+
+    let audit_result = if result.is_ok() {
+        AuditResult::Success
+    } else {
+        AuditResult::Failure
+    };
+    audit_channel_action(
+        &app_state,
+        &user_context,
+        &headers,
+        "save",
+        &data.name,
+        audit_result,
+    )
+    .await;
+
+    match result {
+        Ok(channel) => ResponseJson(ApiResponse::success(channel)),
+        Err(e) => {
+            tracing::error!("Failed to save notification channel {}: {}", data.name, e);
+            ResponseJson(ApiResponse::error("Failed to save notification channel"))
+        }
+    }
+}
+
+/// DELETE /api/notifications/channels?name=...
+#[utoipa::path(
+    delete,
+    path = "/api/notifications/channels",
+    tag = "notifications",
+    summary = "Delete a notification channel",
+    params(
+        ("name" = String, Query, description = "Channel name")
+    ),
+    responses(
+        (status = 200, description = "Channel deleted", body = ApiResponse<String>)
+    )
+)]
+pub async fn delete_notification_channel(
+    Extension(user_context): Extension<UserContext>,
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<NotificationChannelQuery>,
+) -> ResponseJson<ApiResponse<String>> {
+    let channel = match find_channel(&app_state, &user_context, &query.name).await {
+        Ok(channel) => channel,
+        Err(message) => return ResponseJson(ApiResponse::error(message)),
+    };
+
+    let result =
+        notification_channels::remove(&app_state.db_pool, app_state.secret_store(), &channel).await;
+    if result.is_ok() {
+        audit_channel_action(
+            &app_state,
+            &user_context,
+            &headers,
+            "delete",
+            &query.name,
+            AuditResult::Success,
+        )
+        .await;
+    }
+
+    match result {
+        Ok(()) => ResponseJson(ApiResponse::success(
+            "Notification channel deleted".to_string(),
+        )),
+        Err(e) => {
+            tracing::error!(
+                "Failed to delete notification channel {}: {}",
+                query.name,
+                e
+            );
+            ResponseJson(ApiResponse::error("Failed to delete notification channel"))
+        }
+    }
+}
+
+/// POST /api/notifications/channels/test?name=...
+#[utoipa::path(
+    post,
+    path = "/api/notifications/channels/test",
+    tag = "notifications",
+    summary = "Send a test notification",
+    description = "Sends a test notification on one of the current user's channels and reports why it failed, if it did",
+    params(
+        ("name" = String, Query, description = "Channel name")
+    ),
+    responses(
+        (status = 200, description = "Test notification sent", body = ApiResponse<String>)
+    )
+)]
+pub async fn test_notification_channel(
+    Extension(user_context): Extension<UserContext>,
+    State(app_state): State<AppState>,
+    Query(query): Query<NotificationChannelQuery>,
+) -> ResponseJson<ApiResponse<String>> {
+    let channel = match find_channel(&app_state, &user_context, &query.name).await {
+        Ok(channel) => channel,
+        Err(message) => return ResponseJson(ApiResponse::error(message)),
+    };
+
+    let whatsapp = match channel.kind {
+        NotificationChannelKind::WhatsApp => {
+            notification_channels::whatsapp_config(app_state.secret_store()).await
+        }
+        _ => None,
+    };
+    let notification = Notification {
+        title: "Automagik Forge".to_string(),
+        message: format!("Test notification for channel '{}'", channel.name),
+        url: None,
+    };
+    let result = match notification_channels::build(
+        &app_state.db_pool,
+        app_state.secret_store(),
+        &channel,
+        whatsapp.as_ref(),
+    )
+    .await
+    {
+        Ok(backend) => backend.send(&notification).await,
+        Err(e) => Err(e),
+    };
+
+    match result {
+        // WhatsApp messages go through the outbox, which records their delivery
+        Ok(()) if channel.kind == NotificationChannelKind::WhatsApp => {
+            ResponseJson(ApiResponse::success("Test notification queued".to_string()))
+        }
+        Ok(()) => ResponseJson(ApiResponse::success("Test notification sent".to_string())),
+        Err(e) => {
+            tracing::warn!(
+                "Test notification on channel {} failed: {}",
+                channel.name,
+                e
+            );
+            ResponseJson(ApiResponse::error(&format!(
+                "Test notification failed: {}",
+                e
+            )))
+        }
+    }
+}
+
+/// The number of one of the user's WhatsApp channels
+fn whatsapp_number(channel: &UserNotificationChannel) -> Result<&str, &'static str> {
+    match channel.settings.get("number") {
+        Some(number) if channel.kind == NotificationChannelKind::WhatsApp => Ok(number),
+        _ => Err("Only WhatsApp channels have a number to verify"),
+    }
+}
+
+fn verification_error(e: NumberVerificationError) -> String {
+    if let NumberVerificationError::Database(e) = &e {
+        tracing::error!("Failed to verify WhatsApp number: {}", e);
+        return "Failed to verify WhatsApp number".to_string();
+    }
+    e.to_string()
+}
+
+/// POST /api/notifications/channels/verify?name=...
+#[utoipa::path(
+    post,
+    path = "/api/notifications/channels/verify",
+    tag = "notifications",
+    summary = "Send a WhatsApp verification code",
+    description = "Sends a one-time code to the number of one of the current user's WhatsApp channels. Commands sent from a number are only accepted once its owner entered the code.",
+    params(
+        ("name" = String, Query, description = "Channel name")
+    ),
+    responses(
+        (status = 200, description = "Code queued, or the number was already verified", body = ApiResponse<String>)
+    )
+)]
+pub async fn verify_whatsapp_number(
+    Extension(user_context): Extension<UserContext>,
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<NotificationChannelQuery>,
+) -> ResponseJson<ApiResponse<String>> {
+    let channel = match find_channel(&app_state, &user_context, &query.name).await {
+        Ok(channel) => channel,
+        Err(message) => return ResponseJson(ApiResponse::error(message)),
+    };
+    let number = match whatsapp_number(&channel) {
+        Ok(number) => number,
+        Err(message) => return ResponseJson(ApiResponse::error(message)),
+    };
+
+    match whatsapp_commands::start_verification(&app_state.db_pool, user_context.user.id, number)
+        .await
+    {
+        Ok(true) => {
+            audit_channel_action(
+                &app_state,
+                &user_context,
+                &headers,
+                "send_verification_code",
+                &channel.name,
+                AuditResult::Success,
+            )
+            .await;
+            ResponseJson(ApiResponse::success("Verification code queued".to_string()))
+        }
+        Ok(false) => ResponseJson(ApiResponse::success("Number already verified".to_string())),
+        Err(e) => ResponseJson(ApiResponse::error(&verification_error(e))),
+    }
+}
+
+/// POST /api/notifications/channels/verify/confirm?name=...
+#[utoipa::path(
+    post,
+    path = "/api/notifications/channels/verify/confirm",
+    tag = "notifications",
+    summary = "Confirm a WhatsApp number",
+    description = "Verifies the number of one of the current user's WhatsApp channels with the code sent to it",
+    params(
+        ("name" = String, Query, description = "Channel name")
+    ),
+    request_body = ConfirmWhatsAppNumberRequest,
+    responses(
+        (status = 200, description = "Number verified", body = ApiResponse<String>)
+    )
+)]
+pub async fn confirm_whatsapp_number(
+    Extension(user_context): Extension<UserContext>,
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<NotificationChannelQuery>,
+    Json(payload): Json<ConfirmWhatsAppNumberRequest>,
+) -> ResponseJson<ApiResponse<String>> {
+    let channel = match find_channel(&app_state, &user_context, &query.name).await {
+        Ok(channel) => channel,
+        Err(message) => return ResponseJson(ApiResponse::error(message)),
+    };
+    let number = match whatsapp_number(&channel) {
+        Ok(number) => number,
+        Err(message) => return ResponseJson(ApiResponse::error(message)),
+    };
+
+    let result = whatsapp_commands::confirm_verification(
+        &app_state.db_pool,
+        user_context.user.id,
+        number,
+        &payload.code,
+    )
+    .await;
+    let audit_result = match &result {
+        Ok(()) => AuditResult::Success,
+        Err(NumberVerificationError::Database(_)) => AuditResult::Error,
+        Err(_) => AuditResult::Failure,
+    };
+    audit_channel_action(
+        &app_state,
+        &user_context,
+        &headers,
+        "verify_number",
+        &channel.name,
+        audit_result,
+    )
+    .await;
+
+    match result {
+        Ok(()) => ResponseJson(ApiResponse::success("Number verified".to_string())),
+        Err(e) => ResponseJson(ApiResponse::error(&verification_error(e))),
+    }
+}
+
+/// GET /api/notifications/whatsapp/messages
+#[utoipa::path(
+    get,
+    path = "/api/notifications/whatsapp/messages",
+    tag = "notifications",
+    summary = "List queued and sent WhatsApp messages",
+    description = "Lists the most recent WhatsApp messages in the outbox with their delivery status and last error. Admins see every message, others only those for their own channels.",
+    params(
+        ("limit" = Option<i64>, Query, description = "Number of messages, at most 200 (default 50)")
+    ),
+    responses(
+        (status = 200, description = "WhatsApp messages, newest first", body = ApiResponse<Vec<WhatsAppMessage>>)
+    )
+)]
+pub async fn list_whatsapp_messages(
+    Extension(user_context): Extension<UserContext>,
+    State(app_state): State<AppState>,
+    Query(query): Query<WhatsAppMessagesQuery>,
+) -> ResponseJson<ApiResponse<Vec<WhatsAppMessage>>> {
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let result = if user_context.user.is_admin {
+        WhatsAppMessage::find_recent(&app_state.db_pool, limit).await
+    } else {
+        WhatsAppMessage::find_recent_by_user_id(&app_state.db_pool, user_context.user.id, limit)
+            .await
+    };
+
+    match result {
+        Ok(messages) => ResponseJson(ApiResponse::success(messages)),
+        Err(e) => {
+            tracing::error!("Failed to list WhatsApp messages: {}", e);
+            ResponseJson(ApiResponse::error("Failed to list WhatsApp messages"))
+        }
+    }
+}
+
+pub fn notification_channels_router() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/notifications/channels",
+            get(list_notification_channels)
+                .put(save_notification_channel)
+                .delete(delete_notification_channel),
+        )
+        .route(
+            "/notifications/channels/test",
+            post(test_notification_channel),
+        )
+        .route(
+            "/notifications/channels/verify",
+            post(verify_whatsapp_number),
+        )
+        .route(
+            "/notifications/channels/verify/confirm",
+            post(confirm_whatsapp_number),
+        )
+        .route(
+            "/notifications/whatsapp/messages",
+            get(list_whatsapp_messages),
+        )
+}
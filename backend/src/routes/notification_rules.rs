@@ -0,0 +1,259 @@
+use axum::{
+    extract::{Query, State},
+    http::HeaderMap,
+    response::Json as ResponseJson,
+    routing::get,
+    Extension, Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use utoipa::ToSchema;
+
+use crate::{
+    app_state::AppState,
+    auth::UserContext,
+    models::{
+        user_notification_rule::{
+            NotificationEvent, NotificationScope, UpsertUserNotificationRule, UserNotificationRule,
+        },
+        ApiResponse,
+    },
+    security::audit_logger::{
+        extract_request_context, AuditEventType, AuditResult, AuditSeverity, CreateAuditEvent,
+    },
+    services::notification_rules::{self, NotificationRuleError},
+};
+
+/// The current user's rules and the events rules can match
+#[derive(Debug, Serialize, TS, ToSchema)]
+#[ts(export)]
+pub struct NotificationRules {
+    pub rules: Vec<UserNotificationRule>,
+    pub events: Vec<NotificationEvent>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Deserialize, TS, ToSchema)]
+#[ts(export)]
+pub struct SaveNotificationRuleRequest {
+    pub name: String,
+    pub events: Vec<NotificationEvent>,
+    #[serde(default)]
+    pub scope: NotificationScope,
+    /// Channel names; empty means every enabled channel
+    #[serde(default)]
+    pub channels: Vec<String>,
+    pub quiet_hours_start: Option<String>,
+    pub quiet_hours_end: Option<String>,
+    #[serde(default)]
+    #[ts(type = "number")]
+    pub utc_offset_minutes: i64,
+    #[ts(type = "number | null")]
+    pub digest_minutes: Option<i64>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+#[derive(Deserialize)]
+pub struct DeleteNotificationRuleQuery {
+    pub name: String,
+}
+
+async fn audit_rule_action(
+    app_state: &AppState,
+    user_context: &UserContext,
+    headers: &HeaderMap,
+    action: &str,
+    name: &str,
+    result: AuditResult,
+) {
+    let (ip_address, user_agent) = extract_request_context(headers);
+    if let Err(e) = app_state
+        .audit_logger()
+        .log_event(CreateAuditEvent {
+            event_type: AuditEventType::ConfigChange,
+            user_id: Some(user_context.user.id),
+            ip_address,
+            user_agent,
+            resource: "notification_rules".to_string(),
+            action: action.to_string(),
+            result,
+            details: Some(serde_json::json!({ "name": name })),
+            severity: AuditSeverity::Low,
+        })
+        .await
+    {
+        tracing::error!("Failed to audit notification rule {}: {}", action, e);
+    }
+}
+
+/// GET /api/notifications/rules
+#[utoipa::path(
+    get,
+    path = "/api/notifications/rules",
+    tag = "notifications",
+    summary = "List notification rules",
+    description = "Lists the current user's notification rules. Users without rules are notified of every completion and failure of their tasks on all their channels.",
+    responses(
+        (status = 200, description = "Notification rules", body = ApiResponse<NotificationRules>)
+    )
+)]
+pub async fn list_notification_rules(
+    Extension(user_context): Extension<UserContext>,
+    State(app_state): State<AppState>,
+) -> ResponseJson<ApiResponse<NotificationRules>> {
+    match UserNotificationRule::find_by_user_id(&app_state.db_pool, user_context.user.id).await {
+        Ok(rules) => ResponseJson(ApiResponse::success(NotificationRules {
+            rules,
+            events: NotificationEvent::ALL.to_vec(),
+        })),
+        Err(e) => {
+            tracing::error!(
+                "Failed to list notification rules for user {}: {}",
+                user_context.user.id,
+                e
+            );
+            ResponseJson(ApiResponse::error("Failed to list notification rules"))
+        }
+    }
+}
+
+/// PUT /api/notifications/rules
+#[utoipa::path(
+    put,
+    path = "/api/notifications/rules",
+    tag = "notifications",
+    summary = "Save a notification rule",
+    description = "Creates or replaces one of the current user's notification rules",
+    request_body = SaveNotificationRuleRequest,
+    responses(
+        (status = 200, description = "Rule saved", body = ApiResponse<UserNotificationRule>)
+    )
+)]
+pub async fn save_notification_rule(
+    Extension(user_context): Extension<UserContext>,
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<SaveNotificationRuleRequest>,
+) -> ResponseJson<ApiResponse<UserNotificationRule>> {
+    let mut events = Vec::new();
+    for event in payload.events {
+        if !events.contains(&event) {
+            events.push(event);
+        }
+    }
+    let data = UpsertUserNotificationRule {
+        name: payload.name.trim().to_string(),
+        events,
+        scope: payload.scope,
+        channels: payload
+            .channels
+            .into_iter()
+            .map(|channel| channel.trim().to_string())
+            .filter(|channel| !channel.is_empty())
+            .collect(),
+        quiet_hours_start: payload
+            .quiet_hours_start
+            .filter(|start| !start.trim().is_empty()),
+        quiet_hours_end: payload.quiet_hours_end.filter(|end| !end.trim().is_empty()),
+        utc_offset_minutes: payload.utc_offset_minutes,
+        digest_minutes: payload.digest_minutes,
+        enabled: payload.enabled,
+    };
+    let result = notification_rules::save(&app_state.db_pool, user_context.user.id, &data).await;
+    if let Err(NotificationRuleError::Invalid(message)) = &result {
+        return ResponseJson(ApiResponse::error(message));
+    }
+
+    let audit_result = if result.is_ok() {
+        AuditResult::Success
+    } else {
+        AuditResult::Failure
+    };
+    audit_rule_action(
+        &app_state,
+        &user_context,
+        &headers,
+        "save",
+        &data.name,
+        audit_result,
+    )
+    .await;
+
+    match result {
+        Ok(rule) => ResponseJson(ApiResponse::success(rule)),
+        Err(e) => {
+            tracing::error!("Failed to save notification rule {}: {}", data.name, e);
+            ResponseJson(ApiResponse::error("Failed to save notification rule"))
+        }
+    }
+}
+
+/// DELETE /api/notifications/rules?name=...
+#[utoipa::path(
+    delete,
+    path = "/api/notifications/rules",
+    tag = "notifications",
+    summary = "Delete a notification rule",
+    description = "Deletes one of the current user's notification rules along with the notifications it held back",
+    params(
+        ("name" = String, Query, description = "Rule name")
+    ),
+    responses(
+        (status = 200, description = "Rule deleted", body = ApiResponse<String>)
+    )
+)]
+pub async fn delete_notification_rule(
+    Extension(user_context): Extension<UserContext>,
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<DeleteNotificationRuleQuery>,
+) -> ResponseJson<ApiResponse<String>> {
+    let rule = match UserNotificationRule::find_by_name(
+        &app_state.db_pool,
+        user_context.user.id,
+        &query.name,
+    )
+    .await
+    {
+        Ok(Some(rule)) => rule,
+        Ok(None) => return ResponseJson(ApiResponse::error("Notification rule not found")),
+        Err(e) => {
+            tracing::error!("Failed to load notification rule {}: {}", query.name, e);
+            return ResponseJson(ApiResponse::error("Failed to delete notification rule"));
+        }
+    };
+
+    match UserNotificationRule::delete(&app_state.db_pool, rule.id).await {
+        Ok(_) => {
+            audit_rule_action(
+                &app_state,
+                &user_context,
+                &headers,
+                "delete",
+                &query.name,
+                AuditResult::Success,
+            )
+            .await;
+            ResponseJson(ApiResponse::success(
+                "Notification rule deleted".to_string(),
+            ))
+        }
+        Err(e) => {
+            tracing::error!("Failed to delete notification rule {}: {}", query.name, e);
+            ResponseJson(ApiResponse::error("Failed to delete notification rule"))
+        }
+    }
+}
+
+pub fn notification_rules_router() -> Router<AppState> {
+    Router::new().route(
+        "/notifications/rules",
+        get(list_notification_rules)
+            .put(save_notification_rule)
+            .delete(delete_notification_rule),
+    )
+}
@@ -1,9 +1,9 @@
 use axum::{
     extract::{Query, State},
-    http::StatusCode,
-    response::{Json as ResponseJson, Redirect, Response},
+    http::{header::SET_COOKIE, HeaderMap, StatusCode},
+    response::{AppendHeaders, Html, IntoResponse, Json as ResponseJson, Redirect, Response},
     routing::get,
-    Json, Router,
+    Form, Json, Router,
 };
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -14,13 +14,18 @@ use uuid::Uuid;
 use crate::{
     app_state::AppState,
     auth::{generate_jwt_token, hash_token, JwtConfig},
+    auth_providers::{oidc, AuthProviderError},
     models::{
         user::{CreateUser, User},
         user_session::{SessionType, UserSession},
     },
+    security::rate_limiter::AuthFailure,
 };
 use super::super::app_config::AppConfig;
 
+/// Path the MCP login's OIDC cookie is sent to
+const OIDC_COOKIE_PATH: &str = "/oauth/oidc";
+
 /// Get GitHub client ID from configuration with hardcoded default
 fn get_github_client_id() -> String {
     let config = AppConfig::load().unwrap_or_default();
@@ -51,6 +56,9 @@ pub struct AuthorizeQuery {
     pub state: Option<String>,
     pub code_challenge: Option<String>,
     pub code_challenge_method: Option<String>,
+    /// Login provider to use: `github`, `oidc` or `local`. Defaults to the
+    /// first enabled one in that order.
+    pub provider: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -61,6 +69,18 @@ pub struct CallbackQuery {
     pub error_description: Option<String>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct LoginPageQuery {
+    pub state: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LoginForm {
+    pub state: String,
+    pub username: String,
+    pub password: String,
+}
+
 #[derive(Debug, Deserialize, ToSchema)]
 pub struct TokenRequest {
     pub grant_type: String,
@@ -124,6 +144,8 @@ pub fn oauth_router() -> Router<AppState> {
         .route("/.well-known/oauth-authorization-server", get(oauth_discovery))
         .route("/oauth/authorize", get(oauth_authorize))
         .route("/oauth/callback", get(oauth_callback))
+        .route("/oauth/oidc/callback", get(oauth_oidc_callback))
+        .route("/oauth/login", get(oauth_login_page).post(oauth_login_submit))
         .route("/oauth/token", get(oauth_token).post(oauth_token))
 }
 
@@ -159,15 +181,17 @@ pub async fn oauth_discovery() -> ResponseJson<OAuthDiscoveryResponse> {
     path = "/oauth/authorize",
     tag = "oauth",
     summary = "OAuth 2.1 Authorization Endpoint",
-    description = "Redirects to GitHub OAuth for MCP client authorization",
+    description = "Redirects to the configured login provider (GitHub, OIDC or local login form) for MCP client authorization",
     responses(
-        (status = 302, description = "Redirect to GitHub OAuth"),
-        (status = 400, description = "Invalid request parameters", body = OAuthErrorResponse)
+        (status = 302, description = "Redirect to the login provider"),
+        (status = 400, description = "Invalid request parameters", body = OAuthErrorResponse),
+        (status = 429, description = "Rate limited or temporarily locked out; see Retry-After")
     )
 )]
 pub async fn oauth_authorize(
+    State(app_state): State<AppState>,
     Query(params): Query<AuthorizeQuery>,
-) -> Result<Redirect, Response> {
+) -> Result<Response, Response> {
     // Validate required parameters
     let client_id = params.client_id.as_deref().unwrap_or("mcp-client");
     let redirect_uri = match params.redirect_uri {
@@ -188,6 +212,26 @@ pub async fn oauth_authorize(
         ));
     }
 
+    let providers = app_state.auth_providers();
+    let provider = match params.provider.as_deref() {
+        Some(provider) => provider,
+        None if providers.github_enabled() => "github",
+        None if providers.oidc().is_ok() => "oidc",
+        None => "local",
+    };
+    let provider_enabled = match provider {
+        "github" => providers.github_enabled(),
+        "oidc" => providers.oidc().is_ok(),
+        "local" => providers.local().is_ok(),
+        _ => false,
+    };
+    if !provider_enabled {
+        return Err(oauth_error_response(
+            "invalid_request",
+            Some("Requested login provider is not enabled"),
+        ));
+    }
+
     // Generate state for CSRF protection
     let oauth_state = Uuid::new_v4().to_string();
     
@@ -206,10 +250,45 @@ pub async fn oauth_authorize(
         );
     }
 
-    // GitHub OAuth configuration
-    let github_client_id = get_github_client_id();
     let base_url = std::env::var("BASE_URL")
         .unwrap_or_else(|_| "http://localhost:3001".to_string());
+
+    if provider == "local" {
+        return Ok(Redirect::temporary(&format!(
+            "/oauth/login?state={}",
+            urlencoding::encode(&oauth_state)
+        ))
+        .into_response());
+    }
+
+    if provider == "oidc" {
+        let callback_uri = format!("{}/oauth/oidc/callback", base_url);
+        let oidc = providers.oidc().map_err(|_| {
+            oauth_error_response("invalid_request", Some("OIDC login is not enabled"))
+        })?;
+        let binding = oidc::new_browser_binding();
+        return match oidc
+            .begin_login(&oauth_state, &callback_uri, &binding)
+            .await
+        {
+            Ok(url) => Ok((
+                AppendHeaders([(SET_COOKIE, oidc::login_cookie(OIDC_COOKIE_PATH, &binding))]),
+                Redirect::temporary(&url),
+            )
+                .into_response()),
+            Err(e) => {
+                tracing::error!("Failed to start OIDC login for MCP client: {}", e);
+                OAUTH_STATES.lock().unwrap().remove(&oauth_state);
+                Err(oauth_error_response(
+                    "server_error",
+                    Some("Failed to contact identity provider"),
+                ))
+            }
+        };
+    }
+
+    // GitHub OAuth configuration
+    let github_client_id = get_github_client_id();
     let callback_uri = format!("{}/oauth/callback", base_url);
 
     // Build GitHub OAuth URL
@@ -220,7 +299,7 @@ pub async fn oauth_authorize(
         urlencoding::encode(&oauth_state)
     );
 
-    Ok(Redirect::temporary(&github_url))
+    Ok(Redirect::temporary(&github_url).into_response())
 }
 
 /// GET /oauth/callback
@@ -239,6 +318,13 @@ pub async fn oauth_callback(
     State(app_state): State<AppState>,
     Query(params): Query<CallbackQuery>,
 ) -> Result<Redirect, Response> {
+    if !app_state.auth_providers().github_enabled() {
+        return Err(oauth_error_response(
+            "access_denied",
+            Some("GitHub login is disabled on this server"),
+        ));
+    }
+
     // Handle GitHub OAuth errors
     if let Some(error) = params.error {
         let error_description = params.error_description
@@ -471,7 +557,7 @@ pub async fn oauth_callback(
                 email: Some(primary_email.clone()),
                 display_name: display_name.clone(),
                 avatar_url: avatar_url.clone(),
-                github_token: Some(github_access_token.to_string()),
+                github_token: None,
                 is_admin: None,
                 is_whitelisted: None,
             };
@@ -493,7 +579,7 @@ pub async fn oauth_callback(
                 email: primary_email.clone(),
                 display_name,
                 avatar_url,
-                github_token: Some(github_access_token.to_string()),
+                github_token: None,
                 is_admin: Some(false),
             };
             match User::create(&app_state.db_pool, &create_data, Uuid::new_v4()).await {
@@ -514,7 +600,20 @@ pub async fn oauth_callback(
         }
     };
 
-    // Generate authorization code
+    if let Err(e) = app_state
+        .secret_store()
+        .set_user_github_token(user.id, github_access_token)
+        .await
+    {
+        tracing::error!("Failed to store GitHub token for {}: {}", user.username, e);
+    }
+
+    Ok(issue_authorization_code(oauth_state, user.id))
+}
+
+/// Issue a one-time authorization code for a logged-in user and redirect back
+/// to the MCP client
+fn issue_authorization_code(oauth_state: OAuthState, user_id: Uuid) -> Redirect {
     let auth_code = Uuid::new_v4().to_string();
     let expires_at = chrono::Utc::now() + chrono::Duration::minutes(10); // 10 minute expiry
 
@@ -525,7 +624,7 @@ pub async fn oauth_callback(
             auth_code.clone(),
             AuthCode {
                 code: auth_code.clone(),
-                user_id: user.id,
+                user_id,
                 client_id: oauth_state.client_id,
                 redirect_uri: oauth_state.redirect_uri.clone(),
                 code_challenge: oauth_state.code_challenge,
@@ -543,7 +642,187 @@ pub async fn oauth_callback(
         urlencoding::encode(&auth_code)
     );
 
-    Ok(Redirect::temporary(&redirect_url))
+    Redirect::temporary(&redirect_url)
+}
+
+/// GET /oauth/oidc/callback
+#[utoipa::path(
+    get,
+    path = "/oauth/oidc/callback",
+    tag = "oauth",
+    summary = "OAuth 2.1 OIDC Callback Endpoint",
+    description = "Handles the OIDC provider callback for MCP clients and issues an authorization code",
+    responses(
+        (status = 302, description = "Redirect back to client with authorization code"),
+        (status = 400, description = "OAuth error", body = OAuthErrorResponse)
+    )
+)]
+pub async fn oauth_oidc_callback(
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<CallbackQuery>,
+) -> Response {
+    let response = match complete_oidc_callback(&app_state, &headers, params).await {
+        Ok(redirect) => redirect.into_response(),
+        Err(response) => response,
+    };
+    (
+        AppendHeaders([(SET_COOKIE, oidc::cleared_login_cookie(OIDC_COOKIE_PATH))]),
+        response,
+    )
+        .into_response()
+}
+
+async fn complete_oidc_callback(
+    app_state: &AppState,
+    headers: &HeaderMap,
+    params: CallbackQuery,
+) -> Result<Redirect, Response> {
+    if let Some(error) = params.error {
+        let error_description = params.error_description
+            .unwrap_or_else(|| "OIDC login failed".to_string());
+        return Err(oauth_error_response(&error, Some(&error_description)));
+    }
+
+    let (Some(code), Some(state)) = (params.code, params.state) else {
+        return Err(oauth_error_response(
+            "invalid_request",
+            Some("Missing code or state parameter"),
+        ));
+    };
+
+    let oauth_state = OAUTH_STATES.lock().unwrap().remove(&state).ok_or_else(|| {
+        oauth_error_response("invalid_request", Some("Invalid or expired state parameter"))
+    })?;
+
+    let oidc = app_state.auth_providers().oidc().map_err(|_| {
+        oauth_error_response("invalid_request", Some("OIDC login is not enabled"))
+    })?;
+
+    let binding = oidc::browser_binding(headers);
+    let identity = oidc
+        .complete_login(&state, &code, binding.as_deref())
+        .await
+        .map_err(|e| {
+            tracing::warn!("OIDC login for MCP client failed: {}", e);
+            oauth_error_response("access_denied", Some("OIDC login failed"))
+        })?;
+
+    let user = match oidc.resolve_user(&app_state.db_pool, &identity).await {
+        Ok(user) => user,
+        Err(AuthProviderError::UserNotAuthorized) => {
+            return Err(oauth_error_response(
+                "access_denied",
+                Some("User not authorized to access this application"),
+            ));
+        }
+        Err(e) => {
+            tracing::error!("Failed to resolve OIDC user: {}", e);
+            return Err(oauth_error_response(
+                "server_error",
+                Some("Failed to resolve user account"),
+            ));
+        }
+    };
+
+    Ok(issue_authorization_code(oauth_state, user.id))
+}
+
+/// Minimal login form for MCP clients when local accounts are enabled
+fn login_page(state: &str, error: Option<&str>) -> Html<String> {
+    let error_html = error
+        .map(|e| format!(r#"<p class="error">{}</p>"#, html_escape(e)))
+        .unwrap_or_default();
+    Html(format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Automagik Forge - Sign in</title>
+<style>
+body {{ font-family: sans-serif; display: flex; justify-content: center; margin-top: 10vh; }}
+form {{ display: flex; flex-direction: column; gap: 0.75rem; width: 18rem; }}
+.error {{ color: #b91c1c; }}
+</style>
+</head>
+<body>
+<form method="post" action="/oauth/login">
+<h2>Sign in to Automagik Forge</h2>
+{error_html}
+<input type="hidden" name="state" value="{state}">
+<input name="username" placeholder="Username" autocomplete="username" required autofocus>
+<input name="password" type="password" placeholder="Password" autocomplete="current-password" required>
+<button type="submit">Sign in</button>
+</form>
+</body>
+</html>"#,
+        error_html = error_html,
+        state = html_escape(state),
+    ))
+}
+
+fn html_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// GET /oauth/login
+pub async fn oauth_login_page(
+    State(app_state): State<AppState>,
+    Query(params): Query<LoginPageQuery>,
+) -> Response {
+    if app_state.auth_providers().local().is_err() {
+        return oauth_error_response("invalid_request", Some("Local login is not enabled"));
+    }
+    if !OAUTH_STATES.lock().unwrap().contains_key(&params.state) {
+        return oauth_error_response("invalid_request", Some("Invalid or expired state parameter"));
+    }
+    login_page(&params.state, None).into_response()
+}
+
+/// POST /oauth/login
+pub async fn oauth_login_submit(
+    State(app_state): State<AppState>,
+    Form(form): Form<LoginForm>,
+) -> Response {
+    let local = match app_state.auth_providers().local() {
+        Ok(local) => local,
+        Err(_) => {
+            return oauth_error_response("invalid_request", Some("Local login is not enabled"));
+        }
+    };
+    if !OAUTH_STATES.lock().unwrap().contains_key(&form.state) {
+        return oauth_error_response("invalid_request", Some("Invalid or expired state parameter"));
+    }
+
+    let user = match local
+        .authenticate(&app_state.db_pool, &form.username, &form.password)
+        .await
+    {
+        Ok(user) => user,
+        Err(e @ (AuthProviderError::InvalidCredentials | AuthProviderError::UserNotAuthorized)) => {
+            tracing::warn!("MCP local login failed for {}: {}", form.username, e);
+            // Keep the state so the user can retry; the rate limiter counts the failure
+            let mut response = login_page(&form.state, Some(&e.to_string())).into_response();
+            response.extensions_mut().insert(AuthFailure);
+            return response;
+        }
+        Err(e) => {
+            tracing::error!("MCP local login error: {}", e);
+            return oauth_error_response("server_error", Some("Login failed"));
+        }
+    };
+
+    // The state may have been consumed by a concurrent submit
+    let Some(oauth_state) = OAUTH_STATES.lock().unwrap().remove(&form.state) else {
+        return oauth_error_response("invalid_request", Some("Invalid or expired state parameter"));
+    };
+
+    issue_authorization_code(oauth_state, user.id).into_response()
 }
 
 /// GET|POST /oauth/token
@@ -556,7 +835,8 @@ pub async fn oauth_callback(
     request_body = TokenRequest,
     responses(
         (status = 200, description = "Access token issued", body = TokenResponse),
-        (status = 400, description = "Invalid token request", body = OAuthErrorResponse)
+        (status = 400, description = "Invalid token request", body = OAuthErrorResponse),
+        (status = 429, description = "Rate limited or temporarily locked out; see Retry-After")
     )
 )]
 pub async fn oauth_token(
@@ -643,6 +923,19 @@ pub async fn oauth_token(
         }
     }
 
+    // Make room under the per-type session cap before adding another session
+    if app_state
+        .session_security()
+        .enforce_concurrent_session_limits(auth_code.user_id, SessionType::Mcp)
+        .await
+        .is_err()
+    {
+        return Err(oauth_error_response(
+            "server_error",
+            Some("Failed to create session"),
+        ));
+    }
+
     // Generate session ID first
     let session_id = Uuid::new_v4();
     
@@ -677,19 +970,33 @@ pub async fn oauth_token(
         expires_at,
     };
 
-    match UserSession::create(&app_state.db_pool, &session_data, session_id).await {
-        Ok(_) => {}
+    let session = match UserSession::create(&app_state.db_pool, &session_data, session_id).await {
+        Ok(session) => session,
         Err(_) => {
             return Err(oauth_error_response(
                 "server_error",
                 Some("Failed to create session"),
             ));
         }
+    };
+
+    // Suspicious activity is looked for on login and token rotation
+    match app_state
+        .session_security()
+        .screen_session(&session, None, None)
+        .await
+    {
+        Ok(true) => {
+            return Err(oauth_error_response(
+                "access_denied",
+                Some("The session was revoked after a security alert"),
+            ));
+        }
+        Ok(false) => {}
+        Err(e) => tracing::warn!("Suspicious activity check failed: {}", e),
     }
 
-    // TODO: Also store token in rmcp OAuth store for direct MCP server integration
-    // This would require access to the AuthenticatedTaskServer's OAuth store
-    // For now, the JWT token will be validated by both JWT validation and OAuth store lookup
+    // The MCP server resolves the user from this token on every tool call
 
     let token_response = TokenResponse {
         access_token: jwt_token,
@@ -0,0 +1,310 @@
+use axum::{
+    extract::{Query, State},
+    http::HeaderMap,
+    response::Json as ResponseJson,
+    routing::get,
+    Extension, Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use utoipa::ToSchema;
+
+use crate::{
+    app_state::AppState,
+    auth::UserContext,
+    models::{project::Project, ApiResponse},
+    security::{
+        audit_logger::{
+            extract_request_context, AuditEventType, AuditResult, AuditSeverity, CreateAuditEvent,
+        },
+        secret_store::{is_masked, SecretSummary},
+    },
+    services::execution_env::{project_env_scope, user_env_scope, validate_env_var_name},
+};
+
+/// Whether a variable applies to everyone running the project or only to the
+/// current user, overriding the project value of the same name
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS, ToSchema)]
+#[serde(rename_all = "lowercase")]
+#[ts(export)]
+pub enum EnvVarScope {
+    #[default]
+    Project,
+    User,
+}
+
+/// Project-wide variables and the current user's overrides
+#[derive(Debug, Serialize, TS, ToSchema)]
+#[ts(export)]
+pub struct ProjectEnvVars {
+    pub project: Vec<SecretSummary>,
+    pub user: Vec<SecretSummary>,
+}
+
+#[derive(Deserialize, TS, ToSchema)]
+#[ts(export)]
+pub struct SetEnvVarRequest {
+    pub name: String,
+    pub value: String,
+    #[serde(default)]
+    pub scope: EnvVarScope,
+}
+
+#[derive(Deserialize)]
+pub struct DeleteEnvVarQuery {
+    pub name: String,
+    #[serde(default)]
+    pub scope: EnvVarScope,
+}
+
+fn env_scope(project: &Project, user_context: &UserContext, scope: EnvVarScope) -> String {
+    match scope {
+        EnvVarScope::Project => project_env_scope(project.id),
+        EnvVarScope::User => user_env_scope(project.id, user_context.user.id),
+    }
+}
+
+/// Project-wide variables can be changed by admins and the project's creator;
+/// anyone may manage their own overrides
+fn can_write(project: &Project, user_context: &UserContext, scope: EnvVarScope) -> bool {
+    scope == EnvVarScope::User
+        || user_context.user.is_admin
+        || project.created_by == Some(user_context.user.id)
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn audit_env_action(
+    app_state: &AppState,
+    user_context: &UserContext,
+    headers: &HeaderMap,
+    project: &Project,
+    action: &str,
+    name: &str,
+    scope: EnvVarScope,
+    result: AuditResult,
+) {
+    let (ip_address, user_agent) = extract_request_context(headers);
+    let severity = match result {
+        AuditResult::Success => AuditSeverity::Low,
+        _ => AuditSeverity::Medium,
+    };
+    if let Err(e) = app_state
+        .audit_logger()
+        .log_event(CreateAuditEvent {
+            event_type: AuditEventType::ConfigChange,
+            user_id: Some(user_context.user.id),
+            ip_address,
+            user_agent,
+            resource: "project_env".to_string(),
+            action: action.to_string(),
+            result,
+            details: Some(serde_json::json!({
+                "project_id": project.id,
+                "name": name,
+                "scope": scope,
+            })),
+            severity,
+        })
+        .await
+    {
+        tracing::error!("Failed to audit project env {}: {}", action, e);
+    }
+}
+
+/// GET /api/projects/{id}/env
+#[utoipa::path(
+    get,
+    path = "/api/projects/{id}/env",
+    tag = "projects",
+    summary = "List project environment variables",
+    description = "Lists the project's environment variables and the current user's overrides with masked values",
+    params(
+        ("id" = String, Path, description = "Project ID")
+    ),
+    responses(
+        (status = 200, description = "Environment variables", body = ApiResponse<ProjectEnvVars>),
+        (status = 404, description = "Project not found")
+    )
+)]
+pub async fn list_env_vars(
+    Extension(project): Extension<Project>,
+    Extension(user_context): Extension<UserContext>,
+    State(app_state): State<AppState>,
+) -> ResponseJson<ApiResponse<ProjectEnvVars>> {
+    let store = app_state.secret_store();
+    let result = async {
+        Ok::<_, crate::security::secret_store::SecretStoreError>(ProjectEnvVars {
+            project: store.list_masked(&project_env_scope(project.id)).await?,
+            user: store
+                .list_masked(&user_env_scope(project.id, user_context.user.id))
+                .await?,
+        })
+    }
+    .await;
+
+    match result {
+        Ok(vars) => ResponseJson(ApiResponse::success(vars)),
+        Err(e) => {
+            tracing::error!("Failed to list env vars for project {}: {}", project.id, e);
+            ResponseJson(ApiResponse::error("Failed to list environment variables"))
+        }
+    }
+}
+
+/// PUT /api/projects/{id}/env
+#[utoipa::path(
+    put,
+    path = "/api/projects/{id}/env",
+    tag = "projects",
+    summary = "Set a project environment variable",
+    description = "Encrypts and stores a variable injected into every process run for the project. The value is never returned.",
+    params(
+        ("id" = String, Path, description = "Project ID")
+    ),
+    request_body = SetEnvVarRequest,
+    responses(
+        (status = 200, description = "Variable stored", body = ApiResponse<String>),
+        (status = 404, description = "Project not found")
+    )
+)]
+pub async fn set_env_var(
+    Extension(project): Extension<Project>,
+    Extension(user_context): Extension<UserContext>,
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<SetEnvVarRequest>,
+) -> ResponseJson<ApiResponse<String>> {
+    if let Err(message) = validate_env_var_name(&payload.name) {
+        return ResponseJson(ApiResponse::error(message));
+    }
+    if !can_write(&project, &user_context, payload.scope) {
+        audit_env_action(
+            &app_state,
+            &user_context,
+            &headers,
+            &project,
+            "set",
+            &payload.name,
+            payload.scope,
+            AuditResult::Blocked,
+        )
+        .await;
+        return ResponseJson(ApiResponse::error(
+            "Only admins and the project creator can change project variables",
+        ));
+    }
+    if is_masked(&payload.value) {
+        return ResponseJson(ApiResponse::error("A new value is required"));
+    }
+
+    let result = app_state
+        .secret_store()
+        .put(
+            &env_scope(&project, &user_context, payload.scope),
+            &payload.name,
+            &payload.value,
+        )
+        .await;
+    let audit_result = if result.is_ok() {
+        AuditResult::Success
+    } else {
+        AuditResult::Failure
+    };
+    audit_env_action(
+        &app_state,
+        &user_context,
+        &headers,
+        &project,
+        "set",
+        &payload.name,
+        payload.scope,
+        audit_result,
+    )
+    .await;
+
+    match result {
+        Ok(()) => ResponseJson(ApiResponse::success("Variable stored".to_string())),
+        Err(e) => {
+            tracing::error!("Failed to store env var {}: {}", payload.name, e);
+            ResponseJson(ApiResponse::error("Failed to store environment variable"))
+        }
+    }
+}
+
+/// DELETE /api/projects/{id}/env?name=...&scope=...
+#[utoipa::path(
+    delete,
+    path = "/api/projects/{id}/env",
+    tag = "projects",
+    summary = "Delete a project environment variable",
+    params(
+        ("id" = String, Path, description = "Project ID"),
+        ("name" = String, Query, description = "Variable name"),
+        ("scope" = Option<EnvVarScope>, Query, description = "project (default) or user")
+    ),
+    responses(
+        (status = 200, description = "Variable deleted", body = ApiResponse<String>),
+        (status = 404, description = "Project not found")
+    )
+)]
+pub async fn delete_env_var(
+    Extension(project): Extension<Project>,
+    Extension(user_context): Extension<UserContext>,
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<DeleteEnvVarQuery>,
+) -> ResponseJson<ApiResponse<String>> {
+    if !can_write(&project, &user_context, query.scope) {
+        audit_env_action(
+            &app_state,
+            &user_context,
+            &headers,
+            &project,
+            "delete",
+            &query.name,
+            query.scope,
+            AuditResult::Blocked,
+        )
+        .await;
+        return ResponseJson(ApiResponse::error(
+            "Only admins and the project creator can change project variables",
+        ));
+    }
+
+    let result = app_state
+        .secret_store()
+        .delete(
+            &env_scope(&project, &user_context, query.scope),
+            &query.name,
+        )
+        .await;
+    if matches!(result, Ok(true)) {
+        audit_env_action(
+            &app_state,
+            &user_context,
+            &headers,
+            &project,
+            "delete",
+            &query.name,
+            query.scope,
+            AuditResult::Success,
+        )
+        .await;
+    }
+
+    match result {
+        Ok(true) => ResponseJson(ApiResponse::success("Variable deleted".to_string())),
+        Ok(false) => ResponseJson(ApiResponse::error("Variable not found")),
+        Err(e) => {
+            tracing::error!("Failed to delete env var {}: {}", query.name, e);
+            ResponseJson(ApiResponse::error("Failed to delete environment variable"))
+        }
+    }
+}
+
+pub fn project_env_router() -> Router<AppState> {
+    Router::new().route(
+        "/projects/:id/env",
+        get(list_env_vars).put(set_env_var).delete(delete_env_var),
+    )
+}
@@ -0,0 +1,117 @@
+use axum::{
+    extract::State,
+    response::Json as ResponseJson,
+    routing::{get, post},
+    Extension, Json, Router,
+};
+
+use crate::{
+    app_state::AppState,
+    auth::UserContext,
+    models::{project::Project, ApiResponse},
+    services::github_issues::{
+        self, GitHubIssueImport, GitHubIssuesError, ImportGitHubIssues, LinkedGitHubIssue,
+    },
+};
+
+/// GET /api/projects/{id}/github-issues
+#[utoipa::path(
+    get,
+    path = "/api/projects/{id}/github-issues",
+    tag = "projects",
+    summary = "List imported GitHub issues",
+    description = "Lists the project's tasks imported from GitHub issues with the issue each came from",
+    params(
+        ("id" = String, Path, description = "Project ID")
+    ),
+    responses(
+        (status = 200, description = "Imported issues", body = ApiResponse<Vec<LinkedGitHubIssue>>),
+        (status = 404, description = "Project not found")
+    )
+)]
+pub async fn list_github_issues(
+    Extension(project): Extension<Project>,
+    State(app_state): State<AppState>,
+) -> ResponseJson<ApiResponse<Vec<LinkedGitHubIssue>>> {
+    match github_issues::linked_issues(&app_state.db_pool, &project).await {
+        Ok(issues) => ResponseJson(ApiResponse::success(issues)),
+        Err(e) => {
+            tracing::error!(
+                "Failed to list GitHub issues of project {}: {}",
+                project.id,
+                e
+            );
+            ResponseJson(ApiResponse::error("Failed to list GitHub issues"))
+        }
+    }
+}
+
+/// POST /api/projects/{id}/github-issues/import
+#[utoipa::path(
+    post,
+    path = "/api/projects/{id}/github-issues/import",
+    tag = "projects",
+    summary = "Import GitHub issues as tasks",
+    description = "Imports the open issues of the project's GitHub repository carrying every given label and belonging to the given milestone. Issues imported before update their task while it is still to do. PRs of imported tasks close their issue, and merging them comments on it.",
+    params(
+        ("id" = String, Path, description = "Project ID")
+    ),
+    request_body = ImportGitHubIssues,
+    responses(
+        (status = 200, description = "Tasks created and updated", body = ApiResponse<GitHubIssueImport>),
+        (status = 404, description = "Project not found")
+    )
+)]
+pub async fn import_github_issues(
+    Extension(project): Extension<Project>,
+    Extension(user_context): Extension<UserContext>,
+    State(app_state): State<AppState>,
+    Json(payload): Json<ImportGitHubIssues>,
+) -> ResponseJson<ApiResponse<GitHubIssueImport>> {
+    tracing::debug!(
+        "User {} importing GitHub issues into project {}",
+        user_context.user.username,
+        project.id
+    );
+    match github_issues::import_issues(
+        &app_state.db_pool,
+        app_state.secret_store(),
+        &project,
+        user_context.user.id,
+        &payload,
+    )
+    .await
+    {
+        Ok(import) => {
+            app_state
+                .track_analytics_event(
+                    "github_issues_imported",
+                    Some(serde_json::json!({
+                        "project_id": project.id.to_string(),
+                        "created": import.created.len(),
+                        "updated": import.updated.len(),
+                    })),
+                )
+                .await;
+            ResponseJson(ApiResponse::success(import))
+        }
+        Err(GitHubIssuesError::Database(e)) => {
+            tracing::error!(
+                "Failed to import GitHub issues into project {}: {}",
+                project.id,
+                e
+            );
+            ResponseJson(ApiResponse::error("Failed to import GitHub issues"))
+        }
+        Err(e) => ResponseJson(ApiResponse::error(&e.to_string())),
+    }
+}
+
+pub fn project_github_issues_router() -> Router<AppState> {
+    Router::new()
+        .route("/projects/:id/github-issues", get(list_github_issues))
+        .route(
+            "/projects/:id/github-issues/import",
+            post(import_github_issues),
+        )
+}
@@ -0,0 +1,379 @@
+use std::collections::BTreeMap;
+
+use axum::{
+    extract::{Query, State},
+    http::HeaderMap,
+    response::Json as ResponseJson,
+    routing::get,
+    Extension, Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use utoipa::ToSchema;
+
+use crate::{
+    app_state::AppState,
+    auth::UserContext,
+    models::{
+        mcp_server::{McpServer, McpTransport, UpsertMcpServer},
+        project::Project,
+        ApiResponse,
+    },
+    security::{
+        audit_logger::{
+            extract_request_context, AuditEventType, AuditResult, AuditSeverity, CreateAuditEvent,
+        },
+        secret_store::SecretSummary,
+    },
+    services::mcp_servers::{self, McpServerError},
+};
+
+/// Whether a server is given to everyone running the project or only to the
+/// current user, replacing the project server of the same name
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS, ToSchema)]
+#[serde(rename_all = "lowercase")]
+#[ts(export)]
+pub enum McpServerScope {
+    #[default]
+    Project,
+    User,
+}
+
+/// A server with its environment values and headers masked
+#[derive(Debug, Serialize, TS, ToSchema)]
+#[ts(export)]
+pub struct McpServerDetails {
+    pub server: McpServer,
+    pub env: Vec<SecretSummary>,
+    pub headers: Vec<SecretSummary>,
+}
+
+/// The project's servers and the current user's own
+#[derive(Debug, Serialize, TS, ToSchema)]
+#[ts(export)]
+pub struct ProjectMcpServers {
+    pub project: Vec<McpServerDetails>,
+    pub user: Vec<McpServerDetails>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// Environment values and headers sent back masked keep their stored value
+#[derive(Deserialize, TS, ToSchema)]
+#[ts(export)]
+pub struct SaveMcpServerRequest {
+    pub name: String,
+    #[serde(default)]
+    pub scope: McpServerScope,
+    #[serde(default)]
+    pub transport: McpTransport,
+    pub command: Option<String>,
+    #[serde(default)]
+    pub args: Vec<String>,
+    pub url: Option<String>,
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+#[derive(Deserialize)]
+pub struct DeleteMcpServerQuery {
+    pub name: String,
+    #[serde(default)]
+    pub scope: McpServerScope,
+}
+
+fn scope_user(user_context: &UserContext, scope: McpServerScope) -> Option<uuid::Uuid> {
+    match scope {
+        McpServerScope::Project => None,
+        McpServerScope::User => Some(user_context.user.id),
+    }
+}
+
+/// Project servers can be changed by admins and the project's creator; anyone
+/// may manage their own
+fn can_write(project: &Project, user_context: &UserContext, scope: McpServerScope) -> bool {
+    scope == McpServerScope::User
+        || user_context.user.is_admin
+        || project.created_by == Some(user_context.user.id)
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn audit_server_action(
+    app_state: &AppState,
+    user_context: &UserContext,
+    headers: &HeaderMap,
+    project: &Project,
+    action: &str,
+    name: &str,
+    scope: McpServerScope,
+    result: AuditResult,
+) {
+    let (ip_address, user_agent) = extract_request_context(headers);
+    let severity = match result {
+        AuditResult::Success => AuditSeverity::Low,
+        _ => AuditSeverity::Medium,
+    };
+    if let Err(e) = app_state
+        .audit_logger()
+        .log_event(CreateAuditEvent {
+            event_type: AuditEventType::ConfigChange,
+            user_id: Some(user_context.user.id),
+            ip_address,
+            user_agent,
+            resource: "project_mcp_servers".to_string(),
+            action: action.to_string(),
+            result,
+            details: Some(serde_json::json!({
+                "project_id": project.id,
+                "name": name,
+                "scope": scope,
+            })),
+            severity,
+        })
+        .await
+    {
+        tracing::error!("Failed to audit MCP server {}: {}", action, e);
+    }
+}
+
+/// GET /api/projects/{id}/mcp-servers
+#[utoipa::path(
+    get,
+    path = "/api/projects/{id}/mcp-servers",
+    tag = "projects",
+    summary = "List project MCP servers",
+    description = "Lists the MCP servers given to the project's coding agents and the current user's own, with masked environment values and headers",
+    params(
+        ("id" = String, Path, description = "Project ID")
+    ),
+    responses(
+        (status = 200, description = "MCP servers", body = ApiResponse<ProjectMcpServers>),
+        (status = 404, description = "Project not found")
+    )
+)]
+pub async fn list_mcp_servers(
+    Extension(project): Extension<Project>,
+    Extension(user_context): Extension<UserContext>,
+    State(app_state): State<AppState>,
+) -> ResponseJson<ApiResponse<ProjectMcpServers>> {
+    let result = async {
+        let mut servers = ProjectMcpServers {
+            project: Vec::new(),
+            user: Vec::new(),
+        };
+        for server in
+            McpServer::find_for_project(&app_state.db_pool, project.id, Some(user_context.user.id))
+                .await?
+        {
+            let (env, headers) =
+                mcp_servers::masked_secrets(app_state.secret_store(), server.id).await?;
+            let list = if server.user_id.is_some() {
+                &mut servers.user
+            } else {
+                &mut servers.project
+            };
+            list.push(McpServerDetails {
+                server,
+                env,
+                headers,
+            });
+        }
+        Ok::<_, McpServerError>(servers)
+    }
+    .await;
+
+    match result {
+        Ok(servers) => ResponseJson(ApiResponse::success(servers)),
+        Err(e) => {
+            tracing::error!(
+                "Failed to list MCP servers for project {}: {}",
+                project.id,
+                e
+            );
+            ResponseJson(ApiResponse::error("Failed to list MCP servers"))
+        }
+    }
+}
+
+/// PUT /api/projects/{id}/mcp-servers
+#[utoipa::path(
+    put,
+    path = "/api/projects/{id}/mcp-servers",
+    tag = "projects",
+    summary = "Save a project MCP server",
+    description = "Creates or replaces an MCP server given to the project's coding agents. Environment values and headers are encrypted and never returned.",
+    params(
+        ("id" = String, Path, description = "Project ID")
+    ),
+    request_body = SaveMcpServerRequest,
+    responses(
+        (status = 200, description = "Server saved", body = ApiResponse<McpServer>),
+        (status = 404, description = "Project not found")
+    )
+)]
+pub async fn save_mcp_server(
+    Extension(project): Extension<Project>,
+    Extension(user_context): Extension<UserContext>,
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<SaveMcpServerRequest>,
+) -> ResponseJson<ApiResponse<McpServer>> {
+    if !can_write(&project, &user_context, payload.scope) {
+        audit_server_action(
+            &app_state,
+            &user_context,
+            &headers,
+            &project,
+            "save",
+            &payload.name,
+            payload.scope,
+            AuditResult::Blocked,
+        )
+        .await;
+        return ResponseJson(ApiResponse::error(
+            "Only admins and the project creator can change project MCP servers",
+        ));
+    }
+
+    let data = UpsertMcpServer {
+        name: payload.name.trim().to_string(),
+        transport: payload.transport,
+        command: payload.command.filter(|command| !command.trim().is_empty()),
+        args: payload.args,
+        url: payload.url.filter(|url| !url.trim().is_empty()),
+        enabled: payload.enabled,
+    };
+    let result = mcp_servers::save(
+        &app_state.db_pool,
+        app_state.secret_store(),
+        project.id,
+        scope_user(&user_context, payload.scope),
+        &data,
+        &payload.env,
+        &payload.headers,
+    )
+    .await;
+    if let Err(McpServerError::Invalid(message)) = &result {
+        return ResponseJson(ApiResponse::error(message));
+    }
+
+    let audit_result = if result.is_ok() {
+        AuditResult::Success
+    } else {
+        AuditResult::Failure
+    };
+    audit_server_action(
+        &app_state,
+        &user_context,
+        &headers,
+        &project,
+        "save",
+        &data.name,
+        payload.scope,
+        audit_result,
+    )
+    .await;
+
+    match result {
+        Ok(server) => ResponseJson(ApiResponse::success(server)),
+        Err(e) => {
+            tracing::error!("Failed to save MCP server {}: {}", data.name, e);
+            ResponseJson(ApiResponse::error("Failed to save MCP server"))
+        }
+    }
+}
+
+/// DELETE /api/projects/{id}/mcp-servers?name=...&scope=...
+#[utoipa::path(
+    delete,
+    path = "/api/projects/{id}/mcp-servers",
+    tag = "projects",
+    summary = "Delete a project MCP server",
+    params(
+        ("id" = String, Path, description = "Project ID"),
+        ("name" = String, Query, description = "Server name"),
+        ("scope" = Option<McpServerScope>, Query, description = "project (default) or user")
+    ),
+    responses(
+        (status = 200, description = "Server deleted", body = ApiResponse<String>),
+        (status = 404, description = "Project not found")
+    )
+)]
+pub async fn delete_mcp_server(
+    Extension(project): Extension<Project>,
+    Extension(user_context): Extension<UserContext>,
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<DeleteMcpServerQuery>,
+) -> ResponseJson<ApiResponse<String>> {
+    if !can_write(&project, &user_context, query.scope) {
+        audit_server_action(
+            &app_state,
+            &user_context,
+            &headers,
+            &project,
+            "delete",
+            &query.name,
+            query.scope,
+            AuditResult::Blocked,
+        )
+        .await;
+        return ResponseJson(ApiResponse::error(
+            "Only admins and the project creator can change project MCP servers",
+        ));
+    }
+
+    let server = match McpServer::find_by_name(
+        &app_state.db_pool,
+        project.id,
+        scope_user(&user_context, query.scope),
+        &query.name,
+    )
+    .await
+    {
+        Ok(Some(server)) => server,
+        Ok(None) => return ResponseJson(ApiResponse::error("MCP server not found")),
+        Err(e) => {
+            tracing::error!("Failed to load MCP server {}: {}", query.name, e);
+            return ResponseJson(ApiResponse::error("Failed to delete MCP server"));
+        }
+    };
+
+    let result = mcp_servers::remove(&app_state.db_pool, app_state.secret_store(), &server).await;
+    if result.is_ok() {
+        audit_server_action(
+            &app_state,
+            &user_context,
+            &headers,
+            &project,
+            "delete",
+            &query.name,
+            query.scope,
+            AuditResult::Success,
+        )
+        .await;
+    }
+
+    match result {
+        Ok(()) => ResponseJson(ApiResponse::success("MCP server deleted".to_string())),
+        Err(e) => {
+            tracing::error!("Failed to delete MCP server {}: {}", query.name, e);
+            ResponseJson(ApiResponse::error("Failed to delete MCP server"))
+        }
+    }
+}
+
+pub fn project_mcp_servers_router() -> Router<AppState> {
+    Router::new().route(
+        "/projects/:id/mcp-servers",
+        get(list_mcp_servers)
+            .put(save_mcp_server)
+            .delete(delete_mcp_server),
+    )
+}
@@ -0,0 +1,446 @@
+use axum::{
+    extract::{Query, State},
+    http::HeaderMap,
+    response::Json as ResponseJson,
+    routing::{get, put},
+    Extension, Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use utoipa::ToSchema;
+
+use crate::{
+    app_state::AppState,
+    auth::UserContext,
+    models::{
+        project::Project,
+        webhook::{UpsertWebhook, Webhook, WebhookDelivery, WebhookEvent},
+        ApiResponse,
+    },
+    security::audit_logger::{
+        extract_request_context, AuditEventType, AuditResult, AuditSeverity, CreateAuditEvent,
+    },
+    services::webhooks::{self, WebhookError},
+};
+
+const DEFAULT_DELIVERY_LIMIT: i64 = 50;
+const MAX_DELIVERY_LIMIT: i64 = 200;
+
+/// The project's webhooks and the events they can subscribe to
+#[derive(Debug, Serialize, TS, ToSchema)]
+#[ts(export)]
+pub struct ProjectWebhooks {
+    pub webhooks: Vec<Webhook>,
+    pub events: Vec<WebhookEvent>,
+}
+
+/// A webhook with its signing secret, only returned when the secret is new
+#[derive(Debug, Serialize, TS, ToSchema)]
+#[ts(export)]
+pub struct WebhookWithSecret {
+    pub webhook: Webhook,
+    pub secret: Option<String>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// An empty event list subscribes the webhook to every event
+#[derive(Deserialize, TS, ToSchema)]
+#[ts(export)]
+pub struct CreateWebhookRequest {
+    pub url: String,
+    #[serde(default)]
+    pub events: Vec<WebhookEvent>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+#[derive(Deserialize, TS, ToSchema)]
+#[ts(export)]
+pub struct UpdateWebhookRequest {
+    pub url: String,
+    #[serde(default)]
+    pub events: Vec<WebhookEvent>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Replace the signing secret, returning the new one
+    #[serde(default)]
+    pub rotate_secret: bool,
+}
+
+#[derive(Deserialize)]
+pub struct DeliveriesQuery {
+    pub limit: Option<i64>,
+}
+
+/// Webhooks can be changed by admins and the project's creator
+fn can_write(project: &Project, user_context: &UserContext) -> bool {
+    user_context.user.is_admin || project.created_by == Some(user_context.user.id)
+}
+
+async fn audit_webhook_action(
+    app_state: &AppState,
+    user_context: &UserContext,
+    headers: &HeaderMap,
+    project: &Project,
+    action: &str,
+    details: serde_json::Value,
+    result: AuditResult,
+) {
+    let (ip_address, user_agent) = extract_request_context(headers);
+    let severity = match result {
+        AuditResult::Success => AuditSeverity::Low,
+        _ => AuditSeverity::Medium,
+    };
+    let mut details = details;
+    details["project_id"] = serde_json::json!(project.id);
+    if let Err(e) = app_state
+        .audit_logger()
+        .log_event(CreateAuditEvent {
+            event_type: AuditEventType::ConfigChange,
+            user_id: Some(user_context.user.id),
+            ip_address,
+            user_agent,
+            resource: "project_webhooks".to_string(),
+            action: action.to_string(),
+            result,
+            details: Some(details),
+            severity,
+        })
+        .await
+    {
+        tracing::error!("Failed to audit webhook {}: {}", action, e);
+    }
+}
+
+fn upsert_data(url: String, events: Vec<WebhookEvent>, enabled: bool) -> UpsertWebhook {
+    let mut unique = Vec::new();
+    for event in events {
+        if !unique.contains(&event) {
+            unique.push(event);
+        }
+    }
+    UpsertWebhook {
+        url: url.trim().to_string(),
+        events: unique,
+        enabled,
+    }
+}
+
+/// GET /api/projects/{id}/webhooks
+#[utoipa::path(
+    get,
+    path = "/api/projects/{id}/webhooks",
+    tag = "projects",
+    summary = "List project webhooks",
+    description = "Lists the project's outgoing webhooks and the lifecycle events they can subscribe to",
+    params(
+        ("id" = String, Path, description = "Project ID")
+    ),
+    responses(
+        (status = 200, description = "Webhooks", body = ApiResponse<ProjectWebhooks>),
+        (status = 404, description = "Project not found")
+    )
+)]
+pub async fn list_webhooks(
+    Extension(project): Extension<Project>,
+    State(app_state): State<AppState>,
+) -> ResponseJson<ApiResponse<ProjectWebhooks>> {
+    match Webhook::find_by_project_id(&app_state.db_pool, project.id).await {
+        Ok(webhooks) => ResponseJson(ApiResponse::success(ProjectWebhooks {
+            webhooks,
+            events: WebhookEvent::ALL.to_vec(),
+        })),
+        Err(e) => {
+            tracing::error!("Failed to list webhooks for project {}: {}", project.id, e);
+            ResponseJson(ApiResponse::error("Failed to list webhooks"))
+        }
+    }
+}
+
+/// POST /api/projects/{id}/webhooks
+#[utoipa::path(
+    post,
+    path = "/api/projects/{id}/webhooks",
+    tag = "projects",
+    summary = "Create a project webhook",
+    description = "Creates an outgoing webhook. The response carries the signing secret, which is not shown again.",
+    params(
+        ("id" = String, Path, description = "Project ID")
+    ),
+    request_body = CreateWebhookRequest,
+    responses(
+        (status = 200, description = "Webhook created", body = ApiResponse<WebhookWithSecret>),
+        (status = 404, description = "Project not found")
+    )
+)]
+pub async fn create_webhook(
+    Extension(project): Extension<Project>,
+    Extension(user_context): Extension<UserContext>,
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<CreateWebhookRequest>,
+) -> ResponseJson<ApiResponse<WebhookWithSecret>> {
+    let data = upsert_data(payload.url, payload.events, payload.enabled);
+    if !can_write(&project, &user_context) {
+        audit_webhook_action(
+            &app_state,
+            &user_context,
+            &headers,
+            &project,
+            "create",
+            serde_json::json!({ "url": data.url }),
+            AuditResult::Blocked,
+        )
+        .await;
+        return ResponseJson(ApiResponse::error(
+            "Only admins and the project creator can change project webhooks",
+        ));
+    }
+
+    let result = webhooks::create(
+        &app_state.db_pool,
+        app_state.secret_store(),
+        project.id,
+        Some(user_context.user.id),
+        &data,
+    )
+    .await;
+    if let Err(WebhookError::Invalid(message)) = &result {
+        return ResponseJson(ApiResponse::error(message));
+    }
+
+    let audit_result = if result.is_ok() {
+        AuditResult::Success
+    } else {
+        AuditResult::Failure
+    };
+    audit_webhook_action(
+        &app_state,
+        &user_context,
+        &headers,
+        &project,
+        "create",
+        serde_json::json!({
+            "webhook_id": result.as_ref().ok().map(|(webhook, _)| webhook.id),
+            "url": data.url,
+        }),
+        audit_result,
+    )
+    .await;
+
+    match result {
+        Ok((webhook, secret)) => ResponseJson(ApiResponse::success(WebhookWithSecret {
+            webhook,
+            secret: Some(secret),
+        })),
+        Err(e) => {
+            tracing::error!("Failed to create webhook for project {}: {}", project.id, e);
+            ResponseJson(ApiResponse::error("Failed to create webhook"))
+        }
+    }
+}
+
+/// PUT /api/projects/{id}/webhooks/{webhook_id}
+#[utoipa::path(
+    put,
+    path = "/api/projects/{id}/webhooks/{webhook_id}",
+    tag = "projects",
+    summary = "Update a project webhook",
+    description = "Updates a webhook's URL, events and state. With rotate_secret the response carries the new signing secret.",
+    params(
+        ("id" = String, Path, description = "Project ID"),
+        ("webhook_id" = String, Path, description = "Webhook ID")
+    ),
+    request_body = UpdateWebhookRequest,
+    responses(
+        (status = 200, description = "Webhook updated", body = ApiResponse<WebhookWithSecret>),
+        (status = 404, description = "Webhook not found")
+    )
+)]
+pub async fn update_webhook(
+    Extension(project): Extension<Project>,
+    Extension(webhook): Extension<Webhook>,
+    Extension(user_context): Extension<UserContext>,
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<UpdateWebhookRequest>,
+) -> ResponseJson<ApiResponse<WebhookWithSecret>> {
+    let details = serde_json::json!({
+        "webhook_id": webhook.id,
+        "rotate_secret": payload.rotate_secret,
+    });
+    if !can_write(&project, &user_context) {
+        audit_webhook_action(
+            &app_state,
+            &user_context,
+            &headers,
+            &project,
+            "update",
+            details,
+            AuditResult::Blocked,
+        )
+        .await;
+        return ResponseJson(ApiResponse::error(
+            "Only admins and the project creator can change project webhooks",
+        ));
+    }
+
+    let data = upsert_data(payload.url, payload.events, payload.enabled);
+    let result = webhooks::update(
+        &app_state.db_pool,
+        app_state.secret_store(),
+        &webhook,
+        &data,
+        payload.rotate_secret,
+    )
+    .await;
+    if let Err(WebhookError::Invalid(message)) = &result {
+        return ResponseJson(ApiResponse::error(message));
+    }
+
+    let audit_result = if result.is_ok() {
+        AuditResult::Success
+    } else {
+        AuditResult::Failure
+    };
+    audit_webhook_action(
+        &app_state,
+        &user_context,
+        &headers,
+        &project,
+        "update",
+        details,
+        audit_result,
+    )
+    .await;
+
+    match result {
+        Ok((webhook, secret)) => {
+            ResponseJson(ApiResponse::success(WebhookWithSecret { webhook, secret }))
+        }
+        Err(e) => {
+            tracing::error!("Failed to update webhook {}: {}", webhook.id, e);
+            ResponseJson(ApiResponse::error("Failed to update webhook"))
+        }
+    }
+}
+
+/// DELETE /api/projects/{id}/webhooks/{webhook_id}
+#[utoipa::path(
+    delete,
+    path = "/api/projects/{id}/webhooks/{webhook_id}",
+    tag = "projects",
+    summary = "Delete a project webhook",
+    description = "Deletes a webhook together with its signing secret and delivery log",
+    params(
+        ("id" = String, Path, description = "Project ID"),
+        ("webhook_id" = String, Path, description = "Webhook ID")
+    ),
+    responses(
+        (status = 200, description = "Webhook deleted", body = ApiResponse<String>),
+        (status = 404, description = "Webhook not found")
+    )
+)]
+pub async fn delete_webhook(
+    Extension(project): Extension<Project>,
+    Extension(webhook): Extension<Webhook>,
+    Extension(user_context): Extension<UserContext>,
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+) -> ResponseJson<ApiResponse<String>> {
+    let details = serde_json::json!({ "webhook_id": webhook.id, "url": webhook.url });
+    if !can_write(&project, &user_context) {
+        audit_webhook_action(
+            &app_state,
+            &user_context,
+            &headers,
+            &project,
+            "delete",
+            details,
+            AuditResult::Blocked,
+        )
+        .await;
+        return ResponseJson(ApiResponse::error(
+            "Only admins and the project creator can change project webhooks",
+        ));
+    }
+
+    let result = webhooks::remove(&app_state.db_pool, app_state.secret_store(), &webhook).await;
+    if result.is_ok() {
+        audit_webhook_action(
+            &app_state,
+            &user_context,
+            &headers,
+            &project,
+            "delete",
+            details,
+            AuditResult::Success,
+        )
+        .await;
+    }
+
+    match result {
+        Ok(()) => ResponseJson(ApiResponse::success("Webhook deleted".to_string())),
+        Err(e) => {
+            tracing::error!("Failed to delete webhook {}: {}", webhook.id, e);
+            ResponseJson(ApiResponse::error("Failed to delete webhook"))
+        }
+    }
+}
+
+/// GET /api/projects/{id}/webhooks/{webhook_id}/deliveries
+#[utoipa::path(
+    get,
+    path = "/api/projects/{id}/webhooks/{webhook_id}/deliveries",
+    tag = "projects",
+    summary = "List webhook deliveries",
+    description = "Lists the webhook's most recent deliveries, newest first, with their status, attempts and last response",
+    params(
+        ("id" = String, Path, description = "Project ID"),
+        ("webhook_id" = String, Path, description = "Webhook ID"),
+        ("limit" = Option<i64>, Query, description = "Maximum number of deliveries (default 50, at most 200)")
+    ),
+    responses(
+        (status = 200, description = "Deliveries", body = ApiResponse<Vec<WebhookDelivery>>),
+        (status = 404, description = "Webhook not found")
+    )
+)]
+pub async fn list_webhook_deliveries(
+    Extension(webhook): Extension<Webhook>,
+    State(app_state): State<AppState>,
+    Query(query): Query<DeliveriesQuery>,
+) -> ResponseJson<ApiResponse<Vec<WebhookDelivery>>> {
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_DELIVERY_LIMIT)
+        .clamp(1, MAX_DELIVERY_LIMIT);
+    match WebhookDelivery::find_by_webhook_id(&app_state.db_pool, webhook.id, limit).await {
+        Ok(deliveries) => ResponseJson(ApiResponse::success(deliveries)),
+        Err(e) => {
+            tracing::error!("Failed to list deliveries of webhook {}: {}", webhook.id, e);
+            ResponseJson(ApiResponse::error("Failed to list webhook deliveries"))
+        }
+    }
+}
+
+pub fn project_webhooks_router() -> Router<AppState> {
+    Router::new().route(
+        "/projects/:id/webhooks",
+        get(list_webhooks).post(create_webhook),
+    )
+}
+
+pub fn project_webhook_with_id_router() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/projects/:id/webhooks/:webhook_id",
+            put(update_webhook).delete(delete_webhook),
+        )
+        .route(
+            "/projects/:id/webhooks/:webhook_id/deliveries",
+            get(list_webhook_deliveries),
+        )
+}
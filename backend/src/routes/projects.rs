@@ -15,12 +15,14 @@ use crate::{
     auth::UserContext,
     models::{
         project::{
-            CreateBranch, CreateProject, GitBranch, Project, ProjectWithBranch, ProjectWithCreator, SearchMatchType,
+            CreateBranch, CreateProject, GitBranch, McpInjectionSettings, Project,
+            ProjectWithBranch, ProjectWithCreator, ReviewAutomationSettings, SearchMatchType,
             SearchResult, UpdateProject,
         },
         // user_preferences::UserPreferences,
         ApiResponse,
     },
+    services::mcp_injection,
 };
 
 #[utoipa::path(
@@ -601,6 +603,153 @@ async fn search_files_in_repo(
     Ok(results)
 }
 
+fn mcp_injection_settings(enabled: bool) -> McpInjectionSettings {
+    McpInjectionSettings {
+        enabled,
+        supported_executors: mcp_injection::supported_executors(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/projects/{id}/mcp-injection",
+    params(
+        ("id" = String, Path, description = "Project ID")
+    ),
+    responses(
+        (status = 200, description = "Whether the Forge MCP server is injected into coding agents", body = ApiResponse<McpInjectionSettings>),
+        (status = 404, description = "Project not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "projects"
+)]
+pub async fn get_mcp_injection(
+    Extension(project): Extension<Project>,
+    State(app_state): State<AppState>,
+) -> Result<ResponseJson<ApiResponse<McpInjectionSettings>>, StatusCode> {
+    match Project::mcp_injection_enabled(&app_state.db_pool, project.id).await {
+        Ok(enabled) => Ok(ResponseJson(ApiResponse::success(mcp_injection_settings(enabled)))),
+        Err(e) => {
+            tracing::error!("Failed to read MCP injection for project {}: {}", project.id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/projects/{id}/mcp-injection",
+    params(
+        ("id" = String, Path, description = "Project ID")
+    ),
+    request_body = McpInjectionSettings,
+    responses(
+        (status = 200, description = "MCP injection updated", body = ApiResponse<McpInjectionSettings>),
+        (status = 404, description = "Project not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "projects"
+)]
+pub async fn update_mcp_injection(
+    Extension(project): Extension<Project>,
+    State(app_state): State<AppState>,
+    Extension(user_context): Extension<UserContext>,
+    Json(payload): Json<McpInjectionSettings>,
+) -> Result<ResponseJson<ApiResponse<McpInjectionSettings>>, StatusCode> {
+    tracing::debug!(
+        "User {} setting MCP injection of project {} to {}",
+        user_context.user.username,
+        project.id,
+        payload.enabled
+    );
+    match Project::set_mcp_injection(&app_state.db_pool, project.id, payload.enabled).await {
+        Ok(()) => Ok(ResponseJson(ApiResponse::success(mcp_injection_settings(
+            payload.enabled,
+        )))),
+        Err(e) => {
+            tracing::error!("Failed to update MCP injection for project {}: {}", project.id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/projects/{id}/review-automation",
+    params(
+        ("id" = String, Path, description = "Project ID")
+    ),
+    responses(
+        (status = 200, description = "Whether PR review comments are addressed as soon as a review is submitted", body = ApiResponse<ReviewAutomationSettings>),
+        (status = 404, description = "Project not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "projects"
+)]
+pub async fn get_review_automation(
+    Extension(project): Extension<Project>,
+    State(app_state): State<AppState>,
+) -> Result<ResponseJson<ApiResponse<ReviewAutomationSettings>>, StatusCode> {
+    match Project::auto_address_review_comments_enabled(&app_state.db_pool, project.id).await {
+        Ok(enabled) => Ok(ResponseJson(ApiResponse::success(ReviewAutomationSettings {
+            auto_address_comments: enabled,
+        }))),
+        Err(e) => {
+            tracing::error!(
+                "Failed to read review automation for project {}: {}",
+                project.id,
+                e
+            );
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/projects/{id}/review-automation",
+    params(
+        ("id" = String, Path, description = "Project ID")
+    ),
+    request_body = ReviewAutomationSettings,
+    responses(
+        (status = 200, description = "Review automation updated", body = ApiResponse<ReviewAutomationSettings>),
+        (status = 404, description = "Project not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "projects"
+)]
+pub async fn update_review_automation(
+    Extension(project): Extension<Project>,
+    State(app_state): State<AppState>,
+    Extension(user_context): Extension<UserContext>,
+    Json(payload): Json<ReviewAutomationSettings>,
+) -> Result<ResponseJson<ApiResponse<ReviewAutomationSettings>>, StatusCode> {
+    tracing::debug!(
+        "User {} setting review automation of project {} to {}",
+        user_context.user.username,
+        project.id,
+        payload.auto_address_comments
+    );
+    match Project::set_auto_address_review_comments(
+        &app_state.db_pool,
+        project.id,
+        payload.auto_address_comments,
+    )
+    .await
+    {
+        Ok(()) => Ok(ResponseJson(ApiResponse::success(payload))),
+        Err(e) => {
+            tracing::error!(
+                "Failed to update review automation for project {}: {}",
+                project.id,
+                e
+            );
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
 pub fn projects_base_router() -> Router<AppState> {
     Router::new().route("/projects", get(get_projects).post(create_project))
 }
@@ -617,5 +766,13 @@ pub fn projects_with_id_router() -> Router<AppState> {
             get(get_project_branches).post(create_project_branch),
         )
         .route("/projects/:id/search", get(search_project_files))
+        .route(
+            "/projects/:id/mcp-injection",
+            get(get_mcp_injection).put(update_mcp_injection),
+        )
+        .route(
+            "/projects/:id/review-automation",
+            get(get_review_automation).put(update_review_automation),
+        )
         // .route("/projects/:id/open-editor", post(open_project_in_editor))
 }
@@ -0,0 +1,232 @@
+use axum::{
+    extract::{Path, State},
+    http::HeaderMap,
+    response::Json as ResponseJson,
+    Extension, Json,
+};
+use serde::Deserialize;
+use ts_rs::TS;
+use utoipa::ToSchema;
+
+use crate::{
+    app_state::AppState,
+    auth::UserContext,
+    models::{config::Config, ApiResponse},
+    security::{
+        audit_logger::{extract_request_context, AuditResult},
+        secret_store::{is_masked, SecretSummary, CONFIG_SCOPE},
+    },
+};
+
+#[derive(Deserialize, TS, ToSchema)]
+#[ts(export)]
+pub struct SetSecretRequest {
+    pub value: String,
+}
+
+/// Secret names are short identifiers such as `evolution_api.api_key`. Names
+/// backing `config.json` fields are managed through `/config` instead.
+fn validate_secret_name(name: &str) -> Result<(), &'static str> {
+    let valid_chars = name
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '_' | '.' | '-'));
+    if name.is_empty() || name.len() > 64 || !valid_chars {
+        return Err("Secret names may only contain a-z, 0-9, '_', '.' and '-'");
+    }
+    if Config::default()
+        .secret_fields()
+        .iter()
+        .any(|(field, _)| *field == name)
+    {
+        return Err("This secret is managed through the configuration settings");
+    }
+    Ok(())
+}
+
+async fn audit_secret_action(
+    app_state: &AppState,
+    user_context: &UserContext,
+    headers: &HeaderMap,
+    action: &str,
+    name: &str,
+    result: AuditResult,
+) {
+    let (ip_address, user_agent) = extract_request_context(headers);
+    if let Err(e) = app_state
+        .audit_logger()
+        .log_admin_action(
+            user_context.user.id,
+            ip_address,
+            user_agent,
+            "secrets",
+            action,
+            None,
+            result,
+            Some(serde_json::json!({ "name": name })),
+        )
+        .await
+    {
+        tracing::error!("Failed to audit secret {}: {}", action, e);
+    }
+}
+
+/// GET /secrets
+#[utoipa::path(
+    get,
+    path = "/secrets",
+    tag = "secrets",
+    summary = "List stored secrets",
+    description = "Lists install-wide secrets with masked values (admin only)",
+    responses(
+        (status = 200, description = "Stored secrets", body = ApiResponse<Vec<SecretSummary>>),
+        (status = 401, description = "Not authenticated", body = ApiResponse<String>)
+    )
+)]
+pub async fn list_secrets(
+    State(app_state): State<AppState>,
+    Extension(user_context): Extension<UserContext>,
+) -> ResponseJson<ApiResponse<Vec<SecretSummary>>> {
+    if !user_context.user.is_admin {
+        return ResponseJson(ApiResponse::error("Admin access required"));
+    }
+
+    match app_state.secret_store().list_masked(CONFIG_SCOPE).await {
+        Ok(secrets) => ResponseJson(ApiResponse::success(secrets)),
+        Err(e) => {
+            tracing::error!("Failed to list secrets: {}", e);
+            ResponseJson(ApiResponse::error("Failed to list secrets"))
+        }
+    }
+}
+
+/// PUT /secrets/{name}
+#[utoipa::path(
+    put,
+    path = "/secrets/{name}",
+    tag = "secrets",
+    summary = "Store a secret",
+    description = "Encrypts and stores an install-wide secret, replacing any previous value (admin only). The value is never returned.",
+    params(
+        ("name" = String, Path, description = "Secret name")
+    ),
+    request_body = SetSecretRequest,
+    responses(
+        (status = 200, description = "Secret stored", body = ApiResponse<String>),
+        (status = 401, description = "Not authenticated", body = ApiResponse<String>)
+    )
+)]
+pub async fn set_secret(
+    State(app_state): State<AppState>,
+    Extension(user_context): Extension<UserContext>,
+    Path(name): Path<String>,
+    headers: HeaderMap,
+    Json(payload): Json<SetSecretRequest>,
+) -> ResponseJson<ApiResponse<String>> {
+    if !user_context.user.is_admin {
+        audit_secret_action(
+            &app_state,
+            &user_context,
+            &headers,
+            "set",
+            &name,
+            AuditResult::Blocked,
+        )
+        .await;
+        return ResponseJson(ApiResponse::error("Admin access required"));
+    }
+    if let Err(message) = validate_secret_name(&name) {
+        return ResponseJson(ApiResponse::error(message));
+    }
+    if payload.value.is_empty() || is_masked(&payload.value) {
+        return ResponseJson(ApiResponse::error("A new secret value is required"));
+    }
+
+    let result = app_state
+        .secret_store()
+        .put(CONFIG_SCOPE, &name, &payload.value)
+        .await;
+    let audit_result = if result.is_ok() {
+        AuditResult::Success
+    } else {
+        AuditResult::Failure
+    };
+    audit_secret_action(
+        &app_state,
+        &user_context,
+        &headers,
+        "set",
+        &name,
+        audit_result,
+    )
+    .await;
+
+    match result {
+        Ok(()) => ResponseJson(ApiResponse::success("Secret stored".to_string())),
+        Err(e) => {
+            tracing::error!("Failed to store secret {}: {}", name, e);
+            ResponseJson(ApiResponse::error("Failed to store secret"))
+        }
+    }
+}
+
+/// DELETE /secrets/{name}
+#[utoipa::path(
+    delete,
+    path = "/secrets/{name}",
+    tag = "secrets",
+    summary = "Delete a secret",
+    description = "Removes an install-wide secret (admin only)",
+    params(
+        ("name" = String, Path, description = "Secret name")
+    ),
+    responses(
+        (status = 200, description = "Secret deleted", body = ApiResponse<String>),
+        (status = 401, description = "Not authenticated", body = ApiResponse<String>)
+    )
+)]
+pub async fn delete_secret(
+    State(app_state): State<AppState>,
+    Extension(user_context): Extension<UserContext>,
+    Path(name): Path<String>,
+    headers: HeaderMap,
+) -> ResponseJson<ApiResponse<String>> {
+    if !user_context.user.is_admin {
+        audit_secret_action(
+            &app_state,
+            &user_context,
+            &headers,
+            "delete",
+            &name,
+            AuditResult::Blocked,
+        )
+        .await;
+        return ResponseJson(ApiResponse::error("Admin access required"));
+    }
+    if let Err(message) = validate_secret_name(&name) {
+        return ResponseJson(ApiResponse::error(message));
+    }
+
+    let result = app_state.secret_store().delete(CONFIG_SCOPE, &name).await;
+    let audit_result = match &result {
+        Ok(true) => AuditResult::Success,
+        _ => AuditResult::Failure,
+    };
+    audit_secret_action(
+        &app_state,
+        &user_context,
+        &headers,
+        "delete",
+        &name,
+        audit_result,
+    )
+    .await;
+
+    match result {
+        Ok(true) => ResponseJson(ApiResponse::success("Secret deleted".to_string())),
+        Ok(false) => ResponseJson(ApiResponse::error("Secret not found")),
+        Err(e) => {
+            tracing::error!("Failed to delete secret {}: {}", name, e);
+            ResponseJson(ApiResponse::error("Failed to delete secret"))
+        }
+    }
+}
@@ -20,19 +20,27 @@ use crate::{
     },
     middleware::{load_execution_process_with_context_middleware, load_task_attempt_middleware},
     models::{
-        config::Config,
         execution_process::{
             ExecutionProcess, ExecutionProcessStatus, ExecutionProcessSummary, ExecutionProcessType,
         },
         project::Project,
+        pull_request::{PrCommentFollowup, PullRequestState},
         task::{Task, TaskStatus},
         task_attempt::{
             BranchStatus, CreateFollowUpAttempt, CreatePrParams, CreateTaskAttempt, TaskAttempt,
             TaskAttemptState, WorktreeDiff,
         },
         // user_preferences::UserPreferences,
+        webhook::WebhookEvent,
         ApiResponse,
     },
+    services::{
+        forge::{self, ForgeError, ForgeKind, ForgeReviewComment},
+        github_issues, github_webhooks,
+        pr_description::{self, GeneratedPrDescription},
+        review_followups::{self, ReviewFollowupError},
+        webhooks, ProcessService,
+    },
 };
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -42,9 +50,16 @@ pub struct RebaseTaskAttemptRequest {
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct CreateGitHubPRRequest {
+    /// May be left empty when `generate_description` is set
+    #[serde(default)]
     pub title: String,
     pub body: Option<String>,
     pub base_branch: Option<String>,
+    /// Have the attempt's executor write the title and body the request
+    /// leaves out. If it cannot within a few seconds, the task's title and
+    /// description are used.
+    #[serde(default)]
+    pub generate_description: bool,
 }
 
 #[derive(Debug, Serialize)]
@@ -66,7 +81,8 @@ pub struct ProcessLogsResponse {
 }
 
 // Helper to normalize logs for a process (extracted from get_execution_process_normalized_logs)
-async fn normalize_process_logs(
+// Also used by the MCP attempt summary tool
+pub(crate) async fn normalize_process_logs(
     db_pool: &SqlitePool,
     process: &ExecutionProcess,
 ) -> NormalizedConversation {
@@ -356,7 +372,21 @@ pub async fn merge_task_attempt(
 ) -> Result<ResponseJson<ApiResponse<()>>, StatusCode> {
     match TaskAttempt::merge_changes(&app_state.db_pool, task_attempt.id, task.id, project.id).await
     {
-        Ok(_) => {
+        Ok(merge_commit) => {
+            webhooks::emit_attempt_event(
+                &app_state.db_pool,
+                WebhookEvent::AttemptMerged,
+                project.id,
+                task.id,
+                task_attempt.id,
+                serde_json::json!({
+                    "branch": task_attempt.branch,
+                    "base_branch": task_attempt.base_branch,
+                    "merge_commit": merge_commit,
+                }),
+            )
+            .await;
+
             // Update task status to Done
             if let Err(e) = Task::update_status(
                 &app_state.db_pool,
@@ -369,6 +399,15 @@ pub async fn merge_task_attempt(
                 tracing::error!("Failed to update task status to Done after merge: {}", e);
                 return Err(StatusCode::INTERNAL_SERVER_ERROR);
             }
+            webhooks::emit_task_status(&app_state.db_pool, task.id, project.id, TaskStatus::Done)
+                .await;
+            github_issues::comment_on_merge(
+                &app_state.db_pool,
+                app_state.secret_store(),
+                &task,
+                &github_issues::merged_into(&task_attempt.base_branch, &merge_commit),
+            )
+            .await;
 
             // Track task attempt merged event
             app_state
@@ -398,26 +437,28 @@ pub async fn create_github_pr(
     Extension(project): Extension<Project>,
     Extension(task): Extension<Task>,
     Extension(task_attempt): Extension<TaskAttempt>,
+    Extension(user_context): Extension<UserContext>,
     State(app_state): State<AppState>,
     Json(request): Json<CreateGitHubPRRequest>,
 ) -> Result<ResponseJson<ApiResponse<String>>, StatusCode> {
-    // Load the user's GitHub configuration
-    let config = match Config::load(&crate::utils::config_path()) {
-        Ok(config) => config,
-        Err(e) => {
-            tracing::error!("Failed to load config: {}", e);
-            return Err(StatusCode::INTERNAL_SERVER_ERROR);
-        }
-    };
-
-    let github_token = match config.github.token {
-        Some(token) => token,
-        None => {
+    // The forge is picked from the project's remote, authenticating with the
+    // user's token for it (or the install's GitHub token)
+    let (repo, token) = match forge::credentials(
+        app_state.secret_store(),
+        &project.git_repo_path,
+        Some(user_context.user.id),
+    )
+    .await
+    {
+        Ok(credentials) => credentials,
+        Err(ForgeError::NotAuthenticated(ForgeKind::GitHub, _)) => {
             return Ok(ResponseJson(ApiResponse::error(
                 "GitHub authentication not configured. Please sign in with GitHub.",
             )));
         }
+        Err(e) => return Ok(ResponseJson(ApiResponse::error(&e.to_string()))),
     };
+    let config = app_state.get_config().read().await.clone();
 
     // Get the task attempt to access the stored base branch
     let attempt = &task_attempt;
@@ -435,21 +476,68 @@ pub async fn create_github_pr(
         }
     });
 
-    match TaskAttempt::create_github_pr(
+    // A description that cannot be written quickly is not worth holding the
+    // request for: the PR is opened with the given title and body instead
+    let (title, body) = if request.generate_description {
+        match pr_description::generate(
+            &app_state,
+            &project,
+            &task,
+            &task_attempt,
+            pr_description::INLINE_GENERATION_TIMEOUT,
+        )
+        .await
+        {
+            Ok(generated) => (
+                Some(request.title.trim())
+                    .filter(|title| !title.is_empty())
+                    .map_or(generated.title, str::to_string),
+                request.body.or(Some(generated.body)),
+            ),
+            Err(e) => {
+                tracing::warn!(
+                    "Failed to generate PR description for attempt {}, using the plain one: {}",
+                    task_attempt.id,
+                    e
+                );
+                let title = Some(request.title.trim())
+                    .filter(|title| !title.is_empty())
+                    .map_or_else(|| task.title.clone(), str::to_string);
+                (title, request.body.or_else(|| task.description.clone()))
+            }
+        }
+    } else {
+        (request.title, request.body)
+    };
+    if title.trim().is_empty() {
+        return Ok(ResponseJson(ApiResponse::error("PR title is required")));
+    }
+
+    match TaskAttempt::create_pr(
         &app_state.db_pool,
         CreatePrParams {
             attempt_id: task_attempt.id,
             task_id: task.id,
             project_id: project.id,
-            github_token: &config.github.pat.unwrap_or(github_token),
-            title: &request.title,
-            body: request.body.as_deref(),
+            repo: &repo,
+            token: &token,
+            title: &title,
+            body: body.as_deref(),
             base_branch: Some(&base_branch),
         },
     )
     .await
     {
         Ok(pr_url) => {
+            webhooks::emit_attempt_event(
+                &app_state.db_pool,
+                WebhookEvent::PrOpened,
+                project.id,
+                task.id,
+                task_attempt.id,
+                serde_json::json!({ "pr_url": pr_url, "base_branch": base_branch }),
+            )
+            .await;
             app_state
                 .track_analytics_event(
                     "github_pr_created",
@@ -457,6 +545,7 @@ pub async fn create_github_pr(
                         "task_id": task.id.to_string(),
                         "project_id": project.id.to_string(),
                         "attempt_id": task_attempt.id.to_string(),
+                        "forge": repo.kind.as_str(),
                     })),
                 )
                 .await;
@@ -465,14 +554,15 @@ pub async fn create_github_pr(
         }
         Err(e) => {
             tracing::error!(
-                "Failed to create GitHub PR for attempt {}: {}",
+                "Failed to create {} PR for attempt {}: {}",
+                repo.kind,
                 task_attempt.id,
                 e
             );
             let message = match &e {
-                crate::models::task_attempt::TaskAttemptError::GitHubService(
-                    crate::services::GitHubServiceError::TokenInvalid,
-                ) => Some("github_token_invalid".to_string()),
+                crate::models::task_attempt::TaskAttemptError::Forge(ForgeError::TokenInvalid(
+                    ForgeKind::GitHub,
+                )) => Some("github_token_invalid".to_string()),
                 crate::models::task_attempt::TaskAttemptError::GitService(
                     crate::services::git_service::GitServiceError::Git(err),
                 ) if err
@@ -605,6 +695,218 @@ pub async fn get_task_attempt_branch_status(
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/projects/{project_id}/tasks/{task_id}/attempts/{attempt_id}/pr",
+    params(
+        ("project_id" = String, Path, description = "Project ID"),
+        ("task_id" = String, Path, description = "Task ID"),
+        ("attempt_id" = String, Path, description = "Task attempt ID")
+    ),
+    responses(
+        (status = 200, description = "Pull request status, reviews, review comments and CI checks synced from GitHub", body = ApiResponse<PullRequestState>),
+        (status = 404, description = "Task attempt not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "task_attempts"
+)]
+pub async fn get_task_attempt_pull_request(
+    Extension(task_attempt): Extension<TaskAttempt>,
+    State(app_state): State<AppState>,
+) -> Result<ResponseJson<ApiResponse<PullRequestState>>, StatusCode> {
+    match github_webhooks::pull_request_state(&app_state.db_pool, &task_attempt).await {
+        Ok(state) => Ok(ResponseJson(ApiResponse::success(state))),
+        Err(e) => {
+            tracing::error!(
+                "Failed to load pull request state for task attempt {}: {}",
+                task_attempt.id,
+                e
+            );
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/projects/{project_id}/tasks/{task_id}/attempts/{attempt_id}/pr/address-comments",
+    params(
+        ("project_id" = String, Path, description = "Project ID"),
+        ("task_id" = String, Path, description = "Task ID"),
+        ("attempt_id" = String, Path, description = "Task attempt ID")
+    ),
+    responses(
+        (status = 200, description = "Follow-up started for the PR's unresolved review comments; once it finishes the branch is pushed and the threads replied to", body = ApiResponse<PrCommentFollowup>),
+        (status = 404, description = "Task attempt not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "task_attempts"
+)]
+pub async fn address_task_attempt_review_comments(
+    Extension(project): Extension<Project>,
+    Extension(task_attempt): Extension<TaskAttempt>,
+    State(app_state): State<AppState>,
+    Extension(user_context): Extension<UserContext>,
+) -> Result<ResponseJson<ApiResponse<PrCommentFollowup>>, StatusCode> {
+    tracing::debug!(
+        "User {} addressing review comments of task attempt {}",
+        user_context.user.username,
+        task_attempt.id
+    );
+    match review_followups::address_review_comments(&app_state, &task_attempt, &project, false)
+        .await
+    {
+        Ok(followup) => {
+            app_state
+                .track_analytics_event(
+                    "review_comments_addressed",
+                    Some(serde_json::json!({
+                        "task_id": task_attempt.task_id.to_string(),
+                        "project_id": project.id.to_string(),
+                        "attempt_id": task_attempt.id.to_string(),
+                        "threads": followup.threads.len(),
+                    })),
+                )
+                .await;
+            Ok(ResponseJson(ApiResponse::success(followup)))
+        }
+        Err(ReviewFollowupError::Database(e)) => {
+            tracing::error!(
+                "Failed to address review comments of task attempt {}: {}",
+                task_attempt.id,
+                e
+            );
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+        Err(e) => Ok(ResponseJson(ApiResponse::error(&e.to_string()))),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/projects/{project_id}/tasks/{task_id}/attempts/{attempt_id}/pr/comment-followups",
+    params(
+        ("project_id" = String, Path, description = "Project ID"),
+        ("task_id" = String, Path, description = "Task ID"),
+        ("attempt_id" = String, Path, description = "Task attempt ID")
+    ),
+    responses(
+        (status = 200, description = "Follow-ups started for the PR's review comments, newest first", body = ApiResponse<Vec<PrCommentFollowup>>),
+        (status = 404, description = "Task attempt not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "task_attempts"
+)]
+pub async fn get_task_attempt_comment_followups(
+    Extension(task_attempt): Extension<TaskAttempt>,
+    State(app_state): State<AppState>,
+) -> Result<ResponseJson<ApiResponse<Vec<PrCommentFollowup>>>, StatusCode> {
+    match PrCommentFollowup::find_by_attempt_id(&app_state.db_pool, task_attempt.id).await {
+        Ok(followups) => Ok(ResponseJson(ApiResponse::success(followups))),
+        Err(e) => {
+            tracing::error!(
+                "Failed to load review follow-ups of task attempt {}: {}",
+                task_attempt.id,
+                e
+            );
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/projects/{project_id}/tasks/{task_id}/attempts/{attempt_id}/pr/generate-description",
+    params(
+        ("project_id" = String, Path, description = "Project ID"),
+        ("task_id" = String, Path, description = "Task ID"),
+        ("attempt_id" = String, Path, description = "Task attempt ID")
+    ),
+    responses(
+        (status = 200, description = "PR title and body written by the attempt's executor from the task, the squashed diff, the session summary and the cleanup script output", body = ApiResponse<GeneratedPrDescription>),
+        (status = 404, description = "Task attempt not found")
+    ),
+    tag = "task_attempts"
+)]
+pub async fn generate_task_attempt_pr_description(
+    Extension(project): Extension<Project>,
+    Extension(task): Extension<Task>,
+    Extension(task_attempt): Extension<TaskAttempt>,
+    State(app_state): State<AppState>,
+) -> Result<ResponseJson<ApiResponse<GeneratedPrDescription>>, StatusCode> {
+    match pr_description::generate(
+        &app_state,
+        &project,
+        &task,
+        &task_attempt,
+        pr_description::GENERATION_TIMEOUT,
+    )
+    .await
+    {
+        Ok(description) => Ok(ResponseJson(ApiResponse::success(description))),
+        Err(e) => {
+            tracing::warn!(
+                "Failed to generate PR description for task attempt {}: {}",
+                task_attempt.id,
+                e
+            );
+            Ok(ResponseJson(ApiResponse::error(&e.to_string())))
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/projects/{project_id}/tasks/{task_id}/attempts/{attempt_id}/pr/review-comments",
+    params(
+        ("project_id" = String, Path, description = "Project ID"),
+        ("task_id" = String, Path, description = "Task ID"),
+        ("attempt_id" = String, Path, description = "Task attempt ID")
+    ),
+    responses(
+        (status = 200, description = "Unresolved review comments fetched from the PR's forge (GitHub, GitLab or Gitea)", body = ApiResponse<Vec<ForgeReviewComment>>),
+        (status = 404, description = "Task attempt not found")
+    ),
+    tag = "task_attempts"
+)]
+pub async fn get_task_attempt_review_comments(
+    Extension(project): Extension<Project>,
+    Extension(task_attempt): Extension<TaskAttempt>,
+    Extension(user_context): Extension<UserContext>,
+    State(app_state): State<AppState>,
+) -> Result<ResponseJson<ApiResponse<Vec<ForgeReviewComment>>>, StatusCode> {
+    let Some(pr_number) = task_attempt.pr_number else {
+        return Ok(ResponseJson(ApiResponse::error(
+            "The attempt has no pull request",
+        )));
+    };
+
+    let result = async {
+        let (repo, token) = forge::credentials(
+            app_state.secret_store(),
+            &project.git_repo_path,
+            Some(user_context.user.id),
+        )
+        .await?;
+        forge::connect(&repo, &token)?
+            .review_comments(pr_number)
+            .await
+    }
+    .await;
+
+    match result {
+        Ok(comments) => Ok(ResponseJson(ApiResponse::success(comments))),
+        Err(e) => {
+            tracing::warn!(
+                "Failed to fetch review comments of task attempt {}: {}",
+                task_attempt.id,
+                e
+            );
+            Ok(ResponseJson(ApiResponse::error(&e.to_string())))
+        }
+    }
+}
+
 #[axum::debug_handler]
 pub async fn rebase_task_attempt(
     Extension(project): Extension<Project>,
@@ -667,14 +969,14 @@ pub async fn stop_all_execution_processes(
     Extension(task_attempt): Extension<TaskAttempt>,
     State(app_state): State<AppState>,
 ) -> Result<ResponseJson<ApiResponse<()>>, StatusCode> {
-    // Get all execution processes for the task attempt
-    let processes = match ExecutionProcess::find_by_task_attempt_id(
+    let (stopped_count, errors) = match ProcessService::stop_attempt_processes(
         &app_state.db_pool,
+        &app_state,
         task_attempt.id,
     )
     .await
     {
-        Ok(processes) => processes,
+        Ok(result) => result,
         Err(e) => {
             tracing::error!(
                 "Failed to fetch execution processes for attempt {}: {}",
@@ -685,40 +987,6 @@ pub async fn stop_all_execution_processes(
         }
     };
 
-    let mut stopped_count = 0;
-    let mut errors = Vec::new();
-
-    // Stop all running processes
-    for process in processes {
-        match app_state.stop_running_execution_by_id(process.id).await {
-            Ok(true) => {
-                stopped_count += 1;
-
-                // Update the execution process status in the database
-                if let Err(e) = ExecutionProcess::update_completion(
-                    &app_state.db_pool,
-                    process.id,
-                    crate::models::execution_process::ExecutionProcessStatus::Killed,
-                    None,
-                )
-                .await
-                {
-                    tracing::error!("Failed to update execution process status: {}", e);
-                    errors.push(format!("Failed to update process {} status", process.id));
-                } else {
-                    // Process stopped successfully
-                }
-            }
-            Ok(false) => {
-                // Process was not running, which is fine
-            }
-            Err(e) => {
-                tracing::error!("Failed to stop execution process {}: {}", process.id, e);
-                errors.push(format!("Failed to stop process {}: {}", process.id, e));
-            }
-        }
-    }
-
     if !errors.is_empty() {
         return Ok(ResponseJson(ApiResponse::error(&format!(
             "Stopped {} processes, but encountered errors: {}",
@@ -957,7 +1225,7 @@ pub async fn get_task_attempt_execution_state(
 }
 
 /// Find plan content with context by searching through multiple processes in the same attempt
-async fn find_plan_content_with_context(
+pub(crate) async fn find_plan_content_with_context(
     pool: &SqlitePool,
     attempt_id: Uuid,
 ) -> Result<String, StatusCode> {
@@ -1069,6 +1337,7 @@ pub async fn approve_plan(
             return Err(StatusCode::INTERNAL_SERVER_ERROR);
         }
     };
+    webhooks::emit_task_created(&app_state.db_pool, &new_task).await;
 
     // Mark original task as completed since it now has children
     if let Err(e) =
@@ -1077,6 +1346,7 @@ pub async fn approve_plan(
         tracing::error!("Failed to update original task status to Done: {}", e);
         return Err(StatusCode::INTERNAL_SERVER_ERROR);
     } else {
+        webhooks::emit_task_status(&app_state.db_pool, task.id, project.id, TaskStatus::Done).await;
         tracing::info!(
             "Original task {} marked as Done after plan approval (has children)",
             task.id
@@ -1139,6 +1409,26 @@ pub fn task_attempts_with_id_router(_state: AppState) -> Router<AppState> {
             "/projects/:project_id/tasks/:task_id/attempts/:attempt_id/branch-status",
             get(get_task_attempt_branch_status),
         )
+        .route(
+            "/projects/:project_id/tasks/:task_id/attempts/:attempt_id/pr",
+            get(get_task_attempt_pull_request),
+        )
+        .route(
+            "/projects/:project_id/tasks/:task_id/attempts/:attempt_id/pr/address-comments",
+            post(address_task_attempt_review_comments),
+        )
+        .route(
+            "/projects/:project_id/tasks/:task_id/attempts/:attempt_id/pr/comment-followups",
+            get(get_task_attempt_comment_followups),
+        )
+        .route(
+            "/projects/:project_id/tasks/:task_id/attempts/:attempt_id/pr/review-comments",
+            get(get_task_attempt_review_comments),
+        )
+        .route(
+            "/projects/:project_id/tasks/:task_id/attempts/:attempt_id/pr/generate-description",
+            post(generate_task_attempt_pr_description),
+        )
         .route(
             "/projects/:project_id/tasks/:task_id/attempts/:attempt_id/rebase",
             post(rebase_task_attempt),
@@ -2,7 +2,9 @@ use axum::{
     extract::State, http::StatusCode, response::Json as ResponseJson, routing::get, Extension,
     Json, Router,
 };
-use utoipa;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use utoipa::{self, ToSchema};
 use uuid::Uuid;
 
 use crate::{
@@ -15,8 +17,35 @@ use crate::{
         task_attempt::{CreateTaskAttempt, TaskAttempt},
         ApiResponse,
     },
+    services::{
+        task_plan::{
+            create_planned_tasks, preview_plan, PlannedTask, TaskPlanError, TaskPlanFormat,
+        },
+        webhooks,
+    },
 };
 
+#[derive(Debug, Deserialize, TS, ToSchema)]
+#[ts(export)]
+pub struct CreateTasksFromPlan {
+    pub plan: String,
+    #[serde(default)]
+    pub format: TaskPlanFormat,
+    /// Wish for tasks the plan doesn't assign one
+    pub wish_id: Option<String>,
+    /// Only validate the plan and return the tasks it would create
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Serialize, TS, ToSchema)]
+#[ts(export)]
+pub struct TaskPlanResult {
+    pub dry_run: bool,
+    pub planned: Vec<PlannedTask>,
+    pub created: Vec<Task>,
+}
+
 #[utoipa::path(
     get,
     path = "/api/projects/{project_id}/tasks",
@@ -117,7 +146,7 @@ pub async fn create_task(
                     })),
                 )
                 .await;
-
+            webhooks::emit_task_created(&app_state.db_pool, &task).await;
 
             Ok(ResponseJson(ApiResponse::success(task)))
         }
@@ -168,6 +197,7 @@ pub async fn create_task_and_start(
             return Err(StatusCode::INTERNAL_SERVER_ERROR);
         }
     };
+    webhooks::emit_task_created(&app_state.db_pool, &task).await;
 
     // Create task attempt
     let executor_string = payload.executor.as_ref().map(|exec| exec.to_string());
@@ -233,6 +263,90 @@ pub async fn create_task_and_start(
     }
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/projects/{project_id}/tasks/plan",
+    params(
+        ("project_id" = String, Path, description = "Project ID")
+    ),
+    request_body = CreateTasksFromPlan,
+    responses(
+        (status = 200, description = "Plan previewed or all of its tasks created", body = ApiResponse<TaskPlanResult>),
+        (status = 404, description = "Project not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "tasks"
+)]
+pub async fn create_tasks_from_plan(
+    Extension(project): Extension<Project>,
+    State(app_state): State<AppState>,
+    Extension(user_context): Extension<UserContext>,
+    Json(payload): Json<CreateTasksFromPlan>,
+) -> Result<ResponseJson<ApiResponse<TaskPlanResult>>, StatusCode> {
+    let planned = match preview_plan(
+        &app_state.db_pool,
+        project.id,
+        &payload.plan,
+        payload.format,
+        payload.wish_id.as_deref(),
+    )
+    .await
+    {
+        Ok(planned) => planned,
+        Err(TaskPlanError::Database(e)) => {
+            tracing::error!("Failed to validate plan: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+        Err(e) => return Ok(ResponseJson(ApiResponse::error(&e.to_string()))),
+    };
+
+    if payload.dry_run {
+        return Ok(ResponseJson(ApiResponse::success(TaskPlanResult {
+            dry_run: true,
+            planned,
+            created: Vec::new(),
+        })));
+    }
+
+    tracing::debug!(
+        "Creating {} planned tasks in project {} by user {}",
+        planned.len(),
+        project.id,
+        user_context.user.username
+    );
+
+    match create_planned_tasks(
+        &app_state.db_pool,
+        project.id,
+        &planned,
+        Some(user_context.user.id),
+    )
+    .await
+    {
+        Ok(created) => {
+            app_state
+                .track_analytics_event(
+                    "task_plan_created",
+                    Some(serde_json::json!({
+                        "project_id": project.id.to_string(),
+                        "task_count": created.len(),
+                    })),
+                )
+                .await;
+
+            Ok(ResponseJson(ApiResponse::success(TaskPlanResult {
+                dry_run: false,
+                planned,
+                created,
+            })))
+        }
+        Err(e) => {
+            tracing::error!("Failed to create planned tasks: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
 #[utoipa::path(
     put,
     path = "/api/projects/{project_id}/tasks/{task_id}",
@@ -285,6 +399,7 @@ pub async fn update_task(
         existing_task.description.clone()
     };
     
+    let previous_status = existing_task.status.clone();
     let status = if let Some(new_status) = payload.status {
         if new_status != existing_task.status {
             changes.push("status".to_string());
@@ -333,7 +448,15 @@ pub async fn update_task(
     .await
     {
         Ok(task) => {
-            
+            if task.status != previous_status {
+                webhooks::emit_task_status(
+                    &app_state.db_pool,
+                    task.id,
+                    project.id,
+                    task.status.clone(),
+                )
+                .await;
+            }
             Ok(ResponseJson(ApiResponse::success(task)))
         }
         Err(e) => {
@@ -433,6 +556,10 @@ pub fn tasks_project_router() -> Router<AppState> {
             "/projects/:project_id/tasks/create-and-start",
             post(create_task_and_start),
         )
+        .route(
+            "/projects/:project_id/tasks/plan",
+            post(create_tasks_from_plan),
+        )
 }
 
 pub fn tasks_with_id_router() -> Router<AppState> {
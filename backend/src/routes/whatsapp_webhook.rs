@@ -0,0 +1,173 @@
+use axum::{
+    extract::{Query, State},
+    http::HeaderMap,
+    response::Json as ResponseJson,
+    routing::post,
+    Json, Router,
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::{
+    app_state::AppState,
+    models::{whatsapp_message::WhatsAppMessage, ApiResponse},
+    security::audit_logger::{
+        extract_request_context, AuditEventType, AuditResult, AuditSeverity, CreateAuditEvent,
+    },
+    services::whatsapp_commands::{self, ChatCommandError, HELP},
+};
+
+#[derive(Deserialize)]
+pub struct WhatsAppWebhookQuery {
+    pub token: Option<String>,
+}
+
+async fn audit_command(
+    app_state: &AppState,
+    headers: &HeaderMap,
+    user_id: Option<Uuid>,
+    action: &str,
+    result: AuditResult,
+    details: serde_json::Value,
+) {
+    let (event_type, severity) = match (&result, user_id) {
+        (_, None) => (AuditEventType::Authentication, AuditSeverity::Medium),
+        (AuditResult::Success, _) => (AuditEventType::DataAccess, AuditSeverity::Low),
+        (AuditResult::Blocked, _) => (AuditEventType::Authorization, AuditSeverity::Medium),
+        _ => (AuditEventType::DataAccess, AuditSeverity::Medium),
+    };
+    let (ip_address, user_agent) = extract_request_context(headers);
+    if let Err(e) = app_state
+        .audit_logger()
+        .log_event(CreateAuditEvent {
+            event_type,
+            user_id,
+            ip_address,
+            user_agent,
+            resource: "whatsapp_command".to_string(),
+            action: action.to_string(),
+            result,
+            details: Some(details),
+            severity,
+        })
+        .await
+    {
+        tracing::error!("Failed to audit WhatsApp command {}: {}", action, e);
+    }
+}
+
+/// POST /api/integrations/whatsapp/webhook?token=...
+#[utoipa::path(
+    post,
+    path = "/api/integrations/whatsapp/webhook",
+    tag = "integrations",
+    summary = "Receive WhatsApp messages",
+    description = "Evolution API webhook for incoming WhatsApp messages. Messages from a number on a user's WhatsApp notification channel are run as commands on that user's behalf and answered on WhatsApp. Other events and senders are ignored.",
+    params(
+        ("token" = String, Query, description = "The evolution_api.webhook_token secret")
+    ),
+    request_body = serde_json::Value,
+    responses(
+        (status = 200, description = "Event handled or ignored", body = ApiResponse<String>)
+    )
+)]
+pub async fn receive_whatsapp_webhook(
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<WhatsAppWebhookQuery>,
+    Json(event): Json<serde_json::Value>,
+) -> ResponseJson<ApiResponse<String>> {
+    let Some(expected) = whatsapp_commands::webhook_token(app_state.secret_store()).await else {
+        return ResponseJson(ApiResponse::error("WhatsApp commands are not enabled"));
+    };
+    let token_valid = query
+        .token
+        .as_deref()
+        .is_some_and(|token| whatsapp_commands::tokens_match(&expected, token));
+    if !token_valid {
+        audit_command(
+            &app_state,
+            &headers,
+            None,
+            "webhook",
+            AuditResult::Blocked,
+            serde_json::json!({ "reason": "invalid token" }),
+        )
+        .await;
+        return ResponseJson(ApiResponse::error("Invalid webhook token"));
+    }
+
+    let Some((sender, text)) = whatsapp_commands::incoming_text(&event) else {
+        return ResponseJson(ApiResponse::success("Ignored".to_string()));
+    };
+    let pool = &app_state.db_pool;
+    let user = match whatsapp_commands::find_sender(pool, &sender).await {
+        Ok(Some(user)) if user.is_whitelisted => user,
+        Ok(_) => {
+            // Unknown numbers get no reply, so the install's number can't be
+            // probed for who uses it
+            audit_command(
+                &app_state,
+                &headers,
+                None,
+                "identify_sender",
+                AuditResult::Blocked,
+                serde_json::json!({ "sender": sender }),
+            )
+            .await;
+            return ResponseJson(ApiResponse::success("Ignored".to_string()));
+        }
+        Err(e) => {
+            tracing::error!("Failed to identify WhatsApp sender {}: {}", sender, e);
+            return ResponseJson(ApiResponse::error("Failed to handle message"));
+        }
+    };
+
+    let Some(command) = whatsapp_commands::parse(&text) else {
+        let reply = format!("Sorry, I didn't understand that.\n\n{}", HELP);
+        if let Err(e) = WhatsAppMessage::enqueue(pool, Some(user.id), &sender, &reply).await {
+            tracing::error!("Failed to queue WhatsApp reply to {}: {}", sender, e);
+        }
+        return ResponseJson(ApiResponse::success("Not a command".to_string()));
+    };
+
+    let result = whatsapp_commands::execute(&app_state, &user, &command).await;
+    let audit_result = match &result {
+        Ok(_) => AuditResult::Success,
+        Err(ChatCommandError::Forbidden(_)) => AuditResult::Blocked,
+        Err(ChatCommandError::NotFound(_)) | Err(ChatCommandError::Failed(_)) => {
+            AuditResult::Failure
+        }
+        Err(ChatCommandError::Database(_)) => AuditResult::Error,
+    };
+    audit_command(
+        &app_state,
+        &headers,
+        Some(user.id),
+        command.name(),
+        audit_result,
+        serde_json::json!({ "sender": sender, "message": text }),
+    )
+    .await;
+
+    let reply = match result {
+        Ok(reply) => reply,
+        Err(e) => {
+            if let ChatCommandError::Database(e) = &e {
+                tracing::error!("WhatsApp command {} failed: {}", command.name(), e);
+            }
+            e.to_string()
+        }
+    };
+    if let Err(e) = WhatsAppMessage::enqueue(pool, Some(user.id), &sender, &reply).await {
+        tracing::error!("Failed to queue WhatsApp reply to {}: {}", sender, e);
+    }
+    ResponseJson(ApiResponse::success("Command handled".to_string()))
+}
+
+pub fn whatsapp_webhook_router() -> Router<AppState> {
+    Router::new().route(
+        "/integrations/whatsapp/webhook",
+        post(receive_whatsapp_webhook),
+    )
+}
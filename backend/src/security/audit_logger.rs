@@ -82,7 +82,6 @@ pub struct CreateAuditEvent {
 
 /// Audit logger for security-relevant events
 #[derive(Debug, Clone)]
-#[allow(dead_code)]
 pub struct AuditLogger {
     db_pool: SqlitePool,
 }
@@ -93,7 +92,6 @@ impl AuditLogger {
     }
 
     /// Log an audit event
-    #[allow(dead_code)]
     pub async fn log_event(&self, event: CreateAuditEvent) -> Result<Uuid, sqlx::Error> {
         let event_id = Uuid::new_v4();
         let details_json = event.details.map(|d| d.to_string());
@@ -230,7 +228,6 @@ impl AuditLogger {
     }
 
     /// Log authentication event
-    #[allow(dead_code)]
     pub async fn log_authentication(
         &self,
         user_id: Option<Uuid>,
@@ -261,7 +258,6 @@ impl AuditLogger {
     }
 
     /// Log admin action
-    #[allow(dead_code)]
     pub async fn log_admin_action(
         &self,
         admin_user_id: Uuid,
@@ -373,6 +369,29 @@ impl AuditLogger {
         }).await
     }
 
+    /// Log a rate limit rejection or lockout on an authentication endpoint
+    pub async fn log_rate_limit_event(
+        &self,
+        ip_address: Option<String>,
+        user_agent: Option<String>,
+        route_group: &str,
+        action: &str, // rate_limit_exceeded, locked_out_request, auth_lockout
+        severity: AuditSeverity,
+        details: Option<serde_json::Value>,
+    ) -> Result<Uuid, sqlx::Error> {
+        self.log_event(CreateAuditEvent {
+            event_type: AuditEventType::SecurityViolation,
+            user_id: None,
+            ip_address,
+            user_agent,
+            resource: route_group.to_string(),
+            action: action.to_string(),
+            result: AuditResult::Blocked,
+            details,
+            severity,
+        }).await
+    }
+
     /// Log token access (when GitHub tokens are decrypted for use)
     #[allow(dead_code)]
     pub async fn log_token_access(
@@ -523,7 +542,12 @@ impl AuditLogger {
         .fetch_one(&self.db_pool)
         .await?;
 
-        let rate_limit_violations = 0i64;
+        let rate_limit_violations = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM audit_log WHERE timestamp >= ?1 AND event_type = 'security_violation' AND action IN ('rate_limit_exceeded', 'locked_out_request', 'auth_lockout')"
+        )
+        .bind(since_date)
+        .fetch_one(&self.db_pool)
+        .await?;
 
         let security_violations = sqlx::query_scalar::<_, i64>(
             "SELECT COUNT(*) FROM audit_log WHERE timestamp >= ?1 AND event_type = 'security_violation'"
@@ -561,7 +585,6 @@ pub struct AuditStatistics {
 }
 
 /// Helper function to extract request context for audit logging
-#[allow(dead_code)]
 pub fn extract_request_context(
     headers: &axum::http::HeaderMap,
 ) -> (Option<String>, Option<String>) {
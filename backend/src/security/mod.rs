@@ -3,5 +3,7 @@ pub mod audit_logger;
 pub mod security_headers;
 pub mod session_security;
 pub mod monitoring;
+pub mod rate_limiter;
+pub mod secret_store;
 
 pub use security_headers::*;
\ No newline at end of file
@@ -1,6 +1,7 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::SqlitePool;
+use std::sync::Arc;
 use tokio::time::{interval, Duration};
 use tracing::{error, info, warn};
 use ts_rs::TS;
@@ -9,6 +10,7 @@ use utoipa::ToSchema;
 use crate::{
     security::{
         audit_logger::{AuditLogger, AuditResult, AuditSeverity},
+        rate_limiter::RateLimiter,
         session_security::{SessionSecurity, SecurityAlert},
     },
 };
@@ -19,6 +21,8 @@ pub struct SecurityMonitor {
     db_pool: SqlitePool,
     audit_logger: AuditLogger,
     session_security: SessionSecurity,
+    /// Source of the active lockout count in metrics
+    rate_limiter: Arc<RateLimiter>,
     config: SecurityMonitorConfig,
 }
 
@@ -53,6 +57,8 @@ pub struct SecurityMetrics {
     pub active_sessions: u64,
     pub failed_auth_attempts_last_hour: u64,
     pub security_events_last_hour: u64,
+    pub rate_limit_violations_last_hour: u64,
+    pub active_lockouts: u64,
     pub suspicious_activities: Vec<SecurityAlert>,
     pub system_health: SystemHealth,
 }
@@ -81,12 +87,14 @@ impl SecurityMonitor {
         db_pool: SqlitePool,
         audit_logger: AuditLogger,
         session_security: SessionSecurity,
+        rate_limiter: Arc<RateLimiter>,
         config: Option<SecurityMonitorConfig>,
     ) -> Self {
         Self {
             db_pool,
             audit_logger,
             session_security,
+            rate_limiter,
             config: config.unwrap_or_default(),
         }
     }
@@ -166,7 +174,7 @@ impl SecurityMonitor {
         .await?
         .is_some();
 
-        let (failed_auth_attempts, security_events) = if table_exists {
+        let (failed_auth_attempts, security_events, rate_limit_violations) = if table_exists {
             // Get failed authentication attempts in last hour
             let failed_auth_attempts = sqlx::query_scalar::<_, i64>(
                 "SELECT COUNT(*) FROM audit_log WHERE event_type = 'authentication' AND result = 'failure' AND timestamp >= ?1"
@@ -183,11 +191,21 @@ impl SecurityMonitor {
             .fetch_one(&self.db_pool)
             .await? as u64;
 
-            (failed_auth_attempts, security_events)
+            // Get rate limit rejections and lockouts in last hour
+            let rate_limit_violations = sqlx::query_scalar::<_, i64>(
+                "SELECT COUNT(*) FROM audit_log WHERE event_type = 'security_violation' AND action IN ('rate_limit_exceeded', 'locked_out_request', 'auth_lockout') AND timestamp >= ?1"
+            )
+            .bind(one_hour_ago)
+            .fetch_one(&self.db_pool)
+            .await? as u64;
+
+            (failed_auth_attempts, security_events, rate_limit_violations)
         } else {
-            (0u64, 0u64)
+            (0u64, 0u64, 0u64)
         };
 
+        let active_lockouts = self.rate_limiter.active_lockouts();
+
         // Collect suspicious activities (this is a simplified version)
         let suspicious_activities = Vec::new(); // TODO: Implement suspicious activity detection
 
@@ -199,6 +217,8 @@ impl SecurityMonitor {
             active_sessions,
             failed_auth_attempts_last_hour: failed_auth_attempts,
             security_events_last_hour: security_events,
+            rate_limit_violations_last_hour: rate_limit_violations,
+            active_lockouts,
             suspicious_activities,
             system_health,
         })
@@ -227,6 +247,21 @@ impl SecurityMonitor {
             });
         }
 
+        // Check for clients locked out by the authentication rate limiter
+        if metrics.active_lockouts > 0 {
+            threats.push(SecurityThreat {
+                threat_type: ThreatType::ExcessiveFailedAuth,
+                severity: ThreatSeverity::Medium,
+                description: format!(
+                    "{} client(s) locked out after repeated failed logins ({} rate limit violations in the last hour)",
+                    metrics.active_lockouts,
+                    metrics.rate_limit_violations_last_hour
+                ),
+                affected_resource: "authentication".to_string(),
+                recommended_action: "Review rate limit events in the audit log".to_string(),
+            });
+        }
+
         // Check system health
         match metrics.system_health.overall_status {
             HealthStatus::Critical => {
@@ -424,9 +459,16 @@ pub async fn start_security_monitoring(
     db_pool: SqlitePool,
     audit_logger: AuditLogger,
     session_security: SessionSecurity,
+    rate_limiter: Arc<RateLimiter>,
     config: Option<SecurityMonitorConfig>,
 ) {
-    let monitor = SecurityMonitor::new(db_pool, audit_logger, session_security, config);
+    let monitor = SecurityMonitor::new(
+        db_pool,
+        audit_logger,
+        session_security,
+        rate_limiter,
+        config,
+    );
     monitor.start().await;
 }
 
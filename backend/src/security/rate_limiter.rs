@@ -0,0 +1,987 @@
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use axum::{
+    body::Body,
+    extract::{ConnectInfo, Request, State},
+    http::{
+        header::{AUTHORIZATION, CONTENT_TYPE, RETRY_AFTER},
+        HeaderMap, HeaderValue, Method, StatusCode,
+    },
+    middleware::Next,
+    response::{IntoResponse, Json as ResponseJson, Response},
+};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::{
+    app_state::AppState,
+    auth::{extract_bearer_token, validate_jwt_token, UserContext},
+    auth_providers::local::normalize_username,
+    models::ApiResponse,
+    security::audit_logger::{extract_request_context, AuditResult, AuditSeverity},
+};
+
+/// Hard cap on tracked buckets
+const MAX_TRACKED_BUCKETS: usize = 10_000;
+/// Buckets evicted at once when the cap is hit and a sweep freed too little
+const EVICTION_BATCH: usize = MAX_TRACKED_BUCKETS / 10;
+/// Minimum time between sweeps for idle buckets
+const SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+/// Largest login body read to find the submitted username
+const MAX_LOGIN_BODY_BYTES: usize = 16 * 1024;
+
+/// Marker inserted into response extensions by handlers that return a failed
+/// authentication attempt with a 2xx status (e.g. the device flow poll)
+#[derive(Debug, Clone, Copy)]
+pub struct AuthFailure;
+
+/// Route groups with independent rate limits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RateLimitGroup {
+    DevicePoll,
+    OAuthAuthorize,
+    OAuthToken,
+    LocalLogin,
+}
+
+impl RateLimitGroup {
+    /// Map a request path onto its rate limit group
+    pub fn from_path(path: &str) -> Option<Self> {
+        match path {
+            "/api/auth/github/device/poll" => Some(Self::DevicePoll),
+            "/oauth/authorize" => Some(Self::OAuthAuthorize),
+            "/oauth/token" | "/api/auth/oidc/callback" | "/oauth/oidc/callback" => {
+                Some(Self::OAuthToken)
+            }
+            "/api/auth/local/login" | "/oauth/login" => Some(Self::LocalLogin),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::DevicePoll => "device_poll",
+            Self::OAuthAuthorize => "oauth_authorize",
+            Self::OAuthToken => "oauth_token",
+            Self::LocalLogin => "local_login",
+        }
+    }
+}
+
+/// Token bucket and lockout settings for one route group
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RouteRateLimit {
+    /// Maximum number of requests that can be made in a burst
+    pub burst: u32,
+    /// Tokens added back to the bucket per minute
+    pub refill_per_minute: u32,
+    /// Failed attempts within the window that trigger a lockout
+    pub max_failures: u32,
+    pub failure_window_seconds: u64,
+    pub lockout_seconds: u64,
+}
+
+/// Rate limit configuration per route group, stored in `AppConfig`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct RateLimitConfig {
+    pub enabled: bool,
+    pub device_poll: RouteRateLimit,
+    pub oauth_authorize: RouteRateLimit,
+    pub oauth_token: RouteRateLimit,
+    pub local_login: RouteRateLimit,
+    /// Reverse proxies whose `X-Forwarded-For` / `X-Real-IP` headers are
+    /// believed; requests from anywhere else are keyed by their TCP peer
+    pub trusted_proxies: Vec<IpAddr>,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            // GitHub asks clients to poll every 5 seconds, leave headroom for that
+            device_poll: RouteRateLimit {
+                burst: 20,
+                refill_per_minute: 20,
+                max_failures: 10,
+                failure_window_seconds: 600,
+                lockout_seconds: 900,
+            },
+            oauth_authorize: RouteRateLimit {
+                burst: 10,
+                refill_per_minute: 10,
+                max_failures: 5,
+                failure_window_seconds: 900,
+                lockout_seconds: 900,
+            },
+            oauth_token: RouteRateLimit {
+                burst: 10,
+                refill_per_minute: 10,
+                max_failures: 5,
+                failure_window_seconds: 900,
+                lockout_seconds: 900,
+            },
+            // Password guessing is the main threat here, so lock out early
+            local_login: RouteRateLimit {
+                burst: 5,
+                refill_per_minute: 5,
+                max_failures: 5,
+                failure_window_seconds: 900,
+                lockout_seconds: 1800,
+            },
+            trusted_proxies: Vec::new(),
+        }
+    }
+}
+
+impl RateLimitConfig {
+    pub fn for_group(&self, group: RateLimitGroup) -> &RouteRateLimit {
+        match group {
+            RateLimitGroup::DevicePoll => &self.device_poll,
+            RateLimitGroup::OAuthAuthorize => &self.oauth_authorize,
+            RateLimitGroup::OAuthToken => &self.oauth_token,
+            RateLimitGroup::LocalLogin => &self.local_login,
+        }
+    }
+}
+
+/// Why a request was rejected
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RejectionReason {
+    RateLimited,
+    LockedOut,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimitRejection {
+    pub reason: RejectionReason,
+    pub retry_after: Duration,
+    /// True for the first rejection since the bucket was last allowed through,
+    /// so callers can record one event per burst instead of one per request
+    pub first_rejection: bool,
+}
+
+#[derive(Debug)]
+struct BucketState {
+    tokens: f64,
+    last_refill: Instant,
+    failures: Vec<Instant>,
+    locked_until: Option<Instant>,
+    rejecting: bool,
+    last_seen: Instant,
+}
+
+impl BucketState {
+    fn new(limit: &RouteRateLimit, now: Instant) -> Self {
+        Self {
+            tokens: limit.burst as f64,
+            last_refill: now,
+            failures: Vec::new(),
+            locked_until: None,
+            rejecting: false,
+            last_seen: now,
+        }
+    }
+
+    fn refill(&mut self, limit: &RouteRateLimit, now: Instant) {
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        let rate_per_second = limit.refill_per_minute as f64 / 60.0;
+        self.tokens = (self.tokens + elapsed * rate_per_second).min(limit.burst as f64);
+        self.last_refill = now;
+    }
+
+    /// Why a request can't take a token from this bucket right now, if it
+    /// can't. Takes nothing.
+    fn rejection(&mut self, limit: &RouteRateLimit, now: Instant) -> Option<RateLimitRejection> {
+        if let Some(until) = self.locked_until {
+            if until > now {
+                let first_rejection = !self.rejecting;
+                self.rejecting = true;
+                return Some(RateLimitRejection {
+                    reason: RejectionReason::LockedOut,
+                    retry_after: until - now,
+                    first_rejection,
+                });
+            }
+            self.locked_until = None;
+        }
+
+        self.refill(limit, now);
+        if self.tokens >= 1.0 {
+            return None;
+        }
+
+        let rate_per_second = (limit.refill_per_minute.max(1) as f64) / 60.0;
+        let wait = (1.0 - self.tokens) / rate_per_second;
+        let first_rejection = !self.rejecting;
+        self.rejecting = true;
+        Some(RateLimitRejection {
+            reason: RejectionReason::RateLimited,
+            retry_after: Duration::from_secs_f64(wait),
+            first_rejection,
+        })
+    }
+
+    fn is_idle(&self, limit: &RouteRateLimit, now: Instant) -> bool {
+        let locked = self.locked_until.is_some_and(|until| until > now);
+        !locked && self.failures.is_empty() && self.tokens >= limit.burst as f64
+    }
+}
+
+/// Bucket states keyed by route group and client key
+#[derive(Debug)]
+struct Buckets {
+    states: HashMap<(RateLimitGroup, String), BucketState>,
+    last_sweep: Option<Instant>,
+}
+
+impl Buckets {
+    /// The bucket for `key`, making room for it first when the cap is reached
+    fn entry(
+        &mut self,
+        config: &RateLimitConfig,
+        group: RateLimitGroup,
+        key: &str,
+        now: Instant,
+    ) -> &mut BucketState {
+        let id = (group, key.to_string());
+        if !self.states.contains_key(&id) && self.states.len() >= MAX_TRACKED_BUCKETS {
+            self.make_room(config, now);
+        }
+        let state = self
+            .states
+            .entry(id)
+            .or_insert_with(|| BucketState::new(config.for_group(group), now));
+        state.last_seen = now;
+        state
+    }
+
+    fn make_room(&mut self, config: &RateLimitConfig, now: Instant) {
+        // Sweeping scans every bucket, so a full map sweeps at most once per
+        // interval instead of on every new key
+        if self
+            .last_sweep
+            .is_none_or(|last| now.duration_since(last) >= SWEEP_INTERVAL)
+        {
+            self.last_sweep = Some(now);
+            self.states.retain(|(group, _), state| {
+                let limit = config.for_group(*group);
+                state.refill(limit, now);
+                !state.is_idle(limit, now)
+            });
+        }
+        if self.states.len() < MAX_TRACKED_BUCKETS {
+            return;
+        }
+
+        // Still full: drop the least recently seen buckets, lockouts last
+        let mut by_age: Vec<_> = self
+            .states
+            .iter()
+            .map(|(id, state)| {
+                let locked = state.locked_until.is_some_and(|until| until > now);
+                (locked, state.last_seen, id.clone())
+            })
+            .collect();
+        by_age.sort_unstable_by_key(|(locked, last_seen, _)| (*locked, *last_seen));
+        for (_, _, id) in by_age.into_iter().take(EVICTION_BATCH) {
+            self.states.remove(&id);
+        }
+    }
+}
+
+/// In-memory token bucket rate limiter with failure-based lockouts
+#[derive(Debug)]
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: Mutex<Buckets>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            buckets: Mutex::new(Buckets {
+                states: HashMap::new(),
+                last_sweep: None,
+            }),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    pub fn trusted_proxies(&self) -> &[IpAddr] {
+        &self.config.trusted_proxies
+    }
+
+    #[cfg(test)]
+    fn check_at(
+        &self,
+        group: RateLimitGroup,
+        key: &str,
+        now: Instant,
+    ) -> Result<(), RateLimitRejection> {
+        self.check_all_at(group, &[key], now)
+            .map_err(|(_, rejection)| rejection)
+    }
+
+    /// Take one token from each of `keys`' buckets. If any bucket rejects,
+    /// none is taken and the rejection comes back with that key's index.
+    pub fn check_all(
+        &self,
+        group: RateLimitGroup,
+        keys: &[&str],
+    ) -> Result<(), (usize, RateLimitRejection)> {
+        self.check_all_at(group, keys, Instant::now())
+    }
+
+    fn check_all_at(
+        &self,
+        group: RateLimitGroup,
+        keys: &[&str],
+        now: Instant,
+    ) -> Result<(), (usize, RateLimitRejection)> {
+        let limit = self.config.for_group(group);
+        let mut buckets = self.buckets.lock().unwrap();
+
+        for (index, key) in keys.iter().enumerate() {
+            let state = buckets.entry(&self.config, group, key, now);
+            if let Some(rejection) = state.rejection(limit, now) {
+                return Err((index, rejection));
+            }
+        }
+        for key in keys {
+            let state = buckets.entry(&self.config, group, key, now);
+            state.tokens -= 1.0;
+            state.rejecting = false;
+        }
+        Ok(())
+    }
+
+    /// Record a failed attempt. Returns the lockout duration when this failure
+    /// pushes `key` over the configured threshold.
+    pub fn record_failure(&self, group: RateLimitGroup, key: &str) -> Option<Duration> {
+        self.record_failure_at(group, key, Instant::now())
+    }
+
+    fn record_failure_at(
+        &self,
+        group: RateLimitGroup,
+        key: &str,
+        now: Instant,
+    ) -> Option<Duration> {
+        let limit = self.config.for_group(group);
+        let window = Duration::from_secs(limit.failure_window_seconds);
+        let mut buckets = self.buckets.lock().unwrap();
+        let state = buckets.entry(&self.config, group, key, now);
+
+        state.failures.retain(|at| now.duration_since(*at) < window);
+        state.failures.push(now);
+
+        if state.failures.len() as u32 >= limit.max_failures {
+            let lockout = Duration::from_secs(limit.lockout_seconds);
+            state.failures.clear();
+            state.locked_until = Some(now + lockout);
+            state.rejecting = false;
+            return Some(lockout);
+        }
+
+        None
+    }
+
+    /// Number of keys currently locked out across all route groups
+    pub fn active_lockouts(&self) -> u64 {
+        let now = Instant::now();
+        self.buckets
+            .lock()
+            .unwrap()
+            .states
+            .values()
+            .filter(|state| state.locked_until.is_some_and(|until| until > now))
+            .count() as u64
+    }
+}
+
+/// The client's address: the TCP peer, unless that peer is a trusted proxy,
+/// in which case the nearest untrusted hop it forwarded for
+fn client_ip(
+    peer: Option<IpAddr>,
+    headers: &HeaderMap,
+    trusted_proxies: &[IpAddr],
+) -> Option<IpAddr> {
+    let peer = peer?;
+    if !trusted_proxies.contains(&peer) {
+        return Some(peer);
+    }
+
+    // Each proxy appends the address it received from, so walk back from
+    // the end past our own proxies; anything further left is client-supplied
+    let forwarded: Vec<IpAddr> = headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|hop| hop.trim().parse().ok())
+        .collect();
+    forwarded
+        .into_iter()
+        .rev()
+        .find(|hop| !trusted_proxies.contains(hop))
+        .or_else(|| {
+            headers
+                .get("x-real-ip")
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.trim().parse().ok())
+        })
+        .or(Some(peer))
+}
+
+/// A bucket a request is counted against
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RateLimitKey {
+    key: String,
+    /// Whether failed attempts can lock the key out. Keys anyone can name,
+    /// like a submitted username, are only throttled, so strangers can't lock
+    /// someone out of their account.
+    locks_out: bool,
+}
+
+impl RateLimitKey {
+    fn ip(ip: Option<IpAddr>) -> Self {
+        let ip = ip
+            .map(|ip| ip.to_string())
+            .unwrap_or_else(|| "unknown".to_string());
+        Self {
+            key: format!("ip:{}", ip),
+            locks_out: true,
+        }
+    }
+
+    /// An authenticated user, by ID
+    fn user(user_id: &str) -> Self {
+        Self {
+            key: format!("user:{}", user_id),
+            locks_out: true,
+        }
+    }
+
+    /// A login name submitted to a login form, normalized
+    fn login(username: &str) -> Self {
+        Self {
+            key: format!("login:{}", username),
+            locks_out: false,
+        }
+    }
+}
+
+/// Resolve the bucket keys for a request: always the client IP, plus the user
+/// when the request carries a valid bearer token
+fn rate_limit_keys(app_state: &AppState, req: &Request, ip: Option<IpAddr>) -> Vec<RateLimitKey> {
+    let mut keys = vec![RateLimitKey::ip(ip)];
+
+    let user_id = req
+        .extensions()
+        .get::<UserContext>()
+        .map(|ctx| ctx.user.id.to_string())
+        .or_else(|| {
+            req.headers()
+                .get(AUTHORIZATION)
+                .and_then(|header| header.to_str().ok())
+                .and_then(extract_bearer_token)
+                .and_then(|token| validate_jwt_token(token, app_state.get_jwt_config()).ok())
+                .map(|claims| claims.sub)
+        });
+    if let Some(user_id) = user_id {
+        keys.push(RateLimitKey::user(&user_id));
+    }
+
+    keys
+}
+
+/// The username a login form or JSON body submits, normalized the way the
+/// local provider looks it up so variations of a name share a bucket
+fn submitted_username(headers: &HeaderMap, body: &[u8]) -> Option<String> {
+    let is_form = headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/x-www-form-urlencoded"));
+    let username = if is_form {
+        std::str::from_utf8(body)
+            .ok()?
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(name, _)| *name == "username")
+            .and_then(|(_, value)| {
+                urlencoding::decode(&value.replace('+', " "))
+                    .ok()
+                    .map(|value| value.into_owned())
+            })
+    } else {
+        serde_json::from_slice::<serde_json::Value>(body)
+            .ok()?
+            .get("username")?
+            .as_str()
+            .map(str::to_string)
+    }?;
+
+    let username = normalize_username(&username);
+    (!username.is_empty()).then_some(username)
+}
+
+/// Buffer a login request's body to read the submitted username, handing
+/// back an equivalent request for the handler
+async fn with_login_username(req: Request) -> Result<(Request, Option<String>), Response> {
+    let (parts, body) = req.into_parts();
+    let bytes = axum::body::to_bytes(body, MAX_LOGIN_BODY_BYTES)
+        .await
+        .map_err(|_| StatusCode::PAYLOAD_TOO_LARGE.into_response())?;
+    let username = submitted_username(&parts.headers, &bytes);
+    Ok((Request::from_parts(parts, Body::from(bytes)), username))
+}
+
+fn too_many_requests(rejection: &RateLimitRejection) -> Response {
+    let retry_after_secs = rejection.retry_after.as_secs_f64().ceil().max(1.0) as u64;
+    let message = match rejection.reason {
+        RejectionReason::RateLimited => "Too many requests, please slow down",
+        RejectionReason::LockedOut => "Too many failed attempts, temporarily locked out",
+    };
+
+    let mut response = (
+        StatusCode::TOO_MANY_REQUESTS,
+        ResponseJson(ApiResponse::<()>::error(message)),
+    )
+        .into_response();
+    response
+        .headers_mut()
+        .insert(RETRY_AFTER, HeaderValue::from(retry_after_secs));
+    response
+}
+
+/// Rate limiting middleware for authentication endpoints.
+///
+/// Requests are matched to a `RateLimitGroup` by path; anything else passes
+/// straight through. Failed attempts (4xx responses or an `AuthFailure`
+/// marker) count towards a temporary lockout, and rejections and lockouts are
+/// written to the audit log so `SecurityMonitor` can report them.
+pub async fn rate_limit_middleware(
+    State(app_state): State<AppState>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let limiter = app_state.rate_limiter().clone();
+    let Some(group) = RateLimitGroup::from_path(req.uri().path()) else {
+        return next.run(req).await;
+    };
+    if !limiter.is_enabled() {
+        return next.run(req).await;
+    }
+
+    // Forwarding headers are client-controlled, so only the connection's
+    // peer address (or a trusted proxy's word) decides the IP bucket
+    let (_, user_agent) = extract_request_context(req.headers());
+    let peer = req
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip());
+    let ip = client_ip(peer, req.headers(), limiter.trusted_proxies());
+    let ip_address = ip.map(|ip| ip.to_string());
+    let mut keys = rate_limit_keys(&app_state, &req, ip);
+
+    // Unauthenticated logins are also throttled per submitted account, so
+    // many addresses can't guess one quickly. Only the address gets locked out.
+    let req = if group == RateLimitGroup::LocalLogin && req.method() == Method::POST {
+        let (req, username) = match with_login_username(req).await {
+            Ok(request) => request,
+            Err(response) => return response,
+        };
+        if let Some(username) = username {
+            keys.push(RateLimitKey::login(&username));
+        }
+        req
+    } else {
+        req
+    };
+
+    let key_names: Vec<&str> = keys.iter().map(|key| key.key.as_str()).collect();
+    if let Err((index, rejection)) = limiter.check_all(group, &key_names) {
+        let key = key_names[index];
+        if rejection.first_rejection {
+            warn!(
+                "Rate limit hit on {} for {} ({:?}, retry after {:?})",
+                group.as_str(),
+                key,
+                rejection.reason,
+                rejection.retry_after
+            );
+            let action = match rejection.reason {
+                RejectionReason::RateLimited => "rate_limit_exceeded",
+                RejectionReason::LockedOut => "locked_out_request",
+            };
+            if let Err(e) = app_state
+                .audit_logger()
+                .log_rate_limit_event(
+                    ip_address.clone(),
+                    user_agent.clone(),
+                    group.as_str(),
+                    action,
+                    AuditSeverity::Medium,
+                    Some(serde_json::json!({
+                        "key": key,
+                        "retry_after_seconds": rejection.retry_after.as_secs(),
+                    })),
+                )
+                .await
+            {
+                warn!("Failed to audit rate limit event: {}", e);
+            }
+        }
+        return too_many_requests(&rejection);
+    }
+
+    let response = next.run(req).await;
+
+    let failed = response.extensions().get::<AuthFailure>().is_some()
+        || matches!(
+            response.status(),
+            StatusCode::BAD_REQUEST | StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN
+        );
+
+    if failed {
+        if let Err(e) = app_state
+            .audit_logger()
+            .log_authentication(
+                None,
+                ip_address.clone(),
+                user_agent.clone(),
+                &format!("{}_failed", group.as_str()),
+                AuditResult::Failure,
+                Some(serde_json::json!({ "status": response.status().as_u16() })),
+            )
+            .await
+        {
+            warn!("Failed to audit authentication failure: {}", e);
+        }
+
+        for key in keys.iter().filter(|key| key.locks_out) {
+            let key = key.key.as_str();
+            if let Some(lockout) = limiter.record_failure(group, key) {
+                warn!(
+                    "Locking out {} on {} for {:?} after repeated failures",
+                    key,
+                    group.as_str(),
+                    lockout
+                );
+                if let Err(e) = app_state
+                    .audit_logger()
+                    .log_rate_limit_event(
+                        ip_address.clone(),
+                        user_agent.clone(),
+                        group.as_str(),
+                        "auth_lockout",
+                        AuditSeverity::High,
+                        Some(serde_json::json!({
+                            "key": key,
+                            "lockout_seconds": lockout.as_secs(),
+                        })),
+                    )
+                    .await
+                {
+                    warn!("Failed to audit lockout: {}", e);
+                }
+            }
+        }
+    }
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter() -> RateLimiter {
+        let mut config = RateLimitConfig::default();
+        config.oauth_token = RouteRateLimit {
+            burst: 2,
+            refill_per_minute: 60,
+            max_failures: 3,
+            failure_window_seconds: 60,
+            lockout_seconds: 120,
+        };
+        RateLimiter::new(config)
+    }
+
+    #[test]
+    fn test_group_from_path() {
+        assert_eq!(
+            RateLimitGroup::from_path("/api/auth/github/device/poll"),
+            Some(RateLimitGroup::DevicePoll)
+        );
+        assert_eq!(
+            RateLimitGroup::from_path("/oauth/token"),
+            Some(RateLimitGroup::OAuthToken)
+        );
+        assert_eq!(
+            RateLimitGroup::from_path("/api/auth/local/login"),
+            Some(RateLimitGroup::LocalLogin)
+        );
+        assert_eq!(
+            RateLimitGroup::from_path("/oauth/login"),
+            Some(RateLimitGroup::LocalLogin)
+        );
+        assert_eq!(
+            RateLimitGroup::from_path("/oauth/oidc/callback"),
+            Some(RateLimitGroup::OAuthToken)
+        );
+        assert_eq!(RateLimitGroup::from_path("/oauth/callback"), None);
+    }
+
+    #[test]
+    fn test_client_ip_ignores_forwarding_headers_from_untrusted_peers() {
+        let peer: IpAddr = "203.0.113.7".parse().unwrap();
+        let proxy: IpAddr = "10.0.0.2".parse().unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert("x-forwarded-for", HeaderValue::from_static("1.2.3.4"));
+        headers.insert("x-real-ip", HeaderValue::from_static("5.6.7.8"));
+
+        assert_eq!(client_ip(Some(peer), &headers, &[]), Some(peer));
+        assert_eq!(client_ip(Some(peer), &headers, &[proxy]), Some(peer));
+        assert_eq!(client_ip(None, &headers, &[proxy]), None);
+    }
+
+    #[test]
+    fn test_client_ip_behind_trusted_proxies() {
+        let proxy: IpAddr = "10.0.0.2".parse().unwrap();
+        let inner_proxy: IpAddr = "10.0.0.3".parse().unwrap();
+        let mut headers = HeaderMap::new();
+        // The client made up the first hop; the proxies appended the rest
+        headers.insert(
+            "x-forwarded-for",
+            HeaderValue::from_static("9.9.9.9, 198.51.100.4, 10.0.0.3"),
+        );
+
+        assert_eq!(
+            client_ip(Some(proxy), &headers, &[proxy, inner_proxy]),
+            Some("198.51.100.4".parse().unwrap())
+        );
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-real-ip", HeaderValue::from_static("198.51.100.9"));
+        assert_eq!(
+            client_ip(Some(proxy), &headers, &[proxy]),
+            Some("198.51.100.9".parse().unwrap())
+        );
+        assert_eq!(
+            client_ip(Some(proxy), &HeaderMap::new(), &[proxy]),
+            Some(proxy)
+        );
+    }
+
+    #[test]
+    fn test_submitted_username() {
+        let mut json = HeaderMap::new();
+        json.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        assert_eq!(
+            submitted_username(&json, br#"{"username":" Admin ","password":"x"}"#),
+            Some("admin".to_string())
+        );
+        assert_eq!(submitted_username(&json, br#"{"password":"x"}"#), None);
+        assert_eq!(submitted_username(&json, b"not json"), None);
+
+        let mut form = HeaderMap::new();
+        form.insert(
+            CONTENT_TYPE,
+            HeaderValue::from_static("application/x-www-form-urlencoded"),
+        );
+        assert_eq!(
+            submitted_username(&form, b"state=abc&username=jane%2Edoe+x&password=x"),
+            Some("jane.doe x".to_string())
+        );
+        assert_eq!(
+            submitted_username(&form, b"state=abc&username=&password=x"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_bucket_exhausts_and_refills() {
+        let limiter = limiter();
+        let start = Instant::now();
+
+        assert!(limiter
+            .check_at(RateLimitGroup::OAuthToken, "ip:1", start)
+            .is_ok());
+        assert!(limiter
+            .check_at(RateLimitGroup::OAuthToken, "ip:1", start)
+            .is_ok());
+
+        let rejection = limiter
+            .check_at(RateLimitGroup::OAuthToken, "ip:1", start)
+            .unwrap_err();
+        assert_eq!(rejection.reason, RejectionReason::RateLimited);
+        assert!(rejection.first_rejection);
+        assert!(rejection.retry_after <= Duration::from_secs(1));
+
+        // Only the first rejection in a run is flagged
+        let again = limiter
+            .check_at(RateLimitGroup::OAuthToken, "ip:1", start)
+            .unwrap_err();
+        assert!(!again.first_rejection);
+
+        // Other keys and groups have their own buckets
+        assert!(limiter
+            .check_at(RateLimitGroup::OAuthToken, "ip:2", start)
+            .is_ok());
+        assert!(limiter
+            .check_at(RateLimitGroup::OAuthAuthorize, "ip:1", start)
+            .is_ok());
+
+        // One token per second comes back
+        let later = start + Duration::from_secs(1);
+        assert!(limiter
+            .check_at(RateLimitGroup::OAuthToken, "ip:1", later)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_rejected_requests_take_no_tokens() {
+        let limiter = limiter();
+        let start = Instant::now();
+
+        // Exhaust the account's bucket from another address
+        for _ in 0..2 {
+            assert!(limiter
+                .check_all_at(RateLimitGroup::OAuthToken, &["ip:2", "login:jane"], start)
+                .is_ok());
+        }
+
+        let (index, rejection) = limiter
+            .check_all_at(RateLimitGroup::OAuthToken, &["ip:1", "login:jane"], start)
+            .unwrap_err();
+        assert_eq!(index, 1);
+        assert_eq!(rejection.reason, RejectionReason::RateLimited);
+
+        // The address kept both of its tokens
+        for _ in 0..2 {
+            assert!(limiter
+                .check_at(RateLimitGroup::OAuthToken, "ip:1", start)
+                .is_ok());
+        }
+    }
+
+    #[test]
+    fn test_login_names_are_throttled_but_never_locked_out() {
+        let login = RateLimitKey::login("jane");
+        let user = RateLimitKey::user("jane");
+        assert_ne!(login.key, user.key);
+        assert!(!login.locks_out);
+        assert!(user.locks_out);
+        assert!(RateLimitKey::ip(None).locks_out);
+    }
+
+    #[test]
+    fn test_repeated_failures_lock_out() {
+        let limiter = limiter();
+        let start = Instant::now();
+
+        assert!(limiter
+            .record_failure_at(RateLimitGroup::OAuthToken, "ip:1", start)
+            .is_none());
+        assert!(limiter
+            .record_failure_at(RateLimitGroup::OAuthToken, "ip:1", start)
+            .is_none());
+        assert_eq!(
+            limiter.record_failure_at(RateLimitGroup::OAuthToken, "ip:1", start),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(limiter.active_lockouts(), 1);
+
+        let rejection = limiter
+            .check_at(
+                RateLimitGroup::OAuthToken,
+                "ip:1",
+                start + Duration::from_secs(10),
+            )
+            .unwrap_err();
+        assert_eq!(rejection.reason, RejectionReason::LockedOut);
+        assert_eq!(rejection.retry_after, Duration::from_secs(110));
+
+        // Lockout expires
+        assert!(limiter
+            .check_at(
+                RateLimitGroup::OAuthToken,
+                "ip:1",
+                start + Duration::from_secs(121)
+            )
+            .is_ok());
+    }
+
+    #[test]
+    fn test_failures_outside_window_are_forgotten() {
+        let limiter = limiter();
+        let start = Instant::now();
+
+        limiter.record_failure_at(RateLimitGroup::OAuthToken, "ip:1", start);
+        limiter.record_failure_at(RateLimitGroup::OAuthToken, "ip:1", start);
+        let later = start + Duration::from_secs(61);
+        assert!(limiter
+            .record_failure_at(RateLimitGroup::OAuthToken, "ip:1", later)
+            .is_none());
+    }
+
+    #[test]
+    fn test_bucket_count_is_capped() {
+        let mut config = RateLimitConfig::default();
+        config.oauth_token = RouteRateLimit {
+            burst: 2,
+            refill_per_minute: 0,
+            max_failures: 3,
+            failure_window_seconds: 60,
+            lockout_seconds: 120,
+        };
+        let limiter = RateLimiter::new(config);
+        let start = Instant::now();
+        for _ in 0..3 {
+            limiter.record_failure_at(RateLimitGroup::OAuthToken, "ip:locked", start);
+        }
+
+        // Spent tokens never refill here, so sweeping frees nothing and the
+        // oldest buckets have to go
+        for i in 0..MAX_TRACKED_BUCKETS + 10 {
+            let now = start + Duration::from_millis(i as u64);
+            let _ = limiter.check_at(RateLimitGroup::OAuthToken, &format!("ip:{}", i), now);
+        }
+
+        let buckets = limiter.buckets.lock().unwrap();
+        assert!(buckets.states.len() <= MAX_TRACKED_BUCKETS);
+        assert!(buckets
+            .states
+            .contains_key(&(RateLimitGroup::OAuthToken, "ip:locked".to_string())));
+        assert!(!buckets
+            .states
+            .contains_key(&(RateLimitGroup::OAuthToken, "ip:0".to_string())));
+        assert!(buckets.states.contains_key(&(
+            RateLimitGroup::OAuthToken,
+            format!("ip:{}", MAX_TRACKED_BUCKETS + 9)
+        )));
+    }
+
+    #[test]
+    fn test_too_many_requests_sets_retry_after() {
+        let response = too_many_requests(&RateLimitRejection {
+            reason: RejectionReason::RateLimited,
+            retry_after: Duration::from_millis(1500),
+            first_rejection: true,
+        });
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers().get(RETRY_AFTER).unwrap(), "2");
+    }
+}
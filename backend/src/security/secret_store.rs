@@ -0,0 +1,712 @@
+//! Encrypted storage for every secret the server holds: GitHub tokens (install
+//! wide and per user), third-party API keys and anything added through the
+//! admin secrets API.
+//!
+//! Values are sealed with AES-256-GCM and kept in the `secrets` table. The keys
+//! live outside the database in a keyring file, each with a version number, so
+//! a stolen `db.sqlite` alone reveals nothing and keys can be rotated with
+//! `automagik-forge secrets rotate-key`.
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::RwLock,
+    time::SystemTime,
+};
+
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+    Aes256Gcm, Key, Nonce,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use thiserror::Error;
+use ts_rs::TS;
+use utoipa::ToSchema;
+use uuid::Uuid;
+use zeroize::Zeroizing;
+
+use super::token_encryption::SecureString;
+use crate::{
+    models::{config::Config, secret::Secret, user::User},
+    utils,
+};
+
+/// Scope of install-wide secrets, such as the values behind `config.json`
+pub const CONFIG_SCOPE: &str = "config";
+
+/// Name of a user's GitHub OAuth token within their scope
+pub const GITHUB_TOKEN_SECRET: &str = "github_token";
+
+/// Install-wide Evolution API key for WhatsApp notifications
+pub const EVOLUTION_API_KEY_SECRET: &str = "evolution_api.api_key";
+
+/// Install-wide token Evolution API must pass to deliver incoming WhatsApp
+/// messages to `/api/integrations/whatsapp/webhook`
+pub const EVOLUTION_API_WEBHOOK_TOKEN_SECRET: &str = "evolution_api.webhook_token";
+
+/// Install-wide secret GitHub signs deliveries to
+/// `/api/integrations/github/webhook` with
+pub const GITHUB_WEBHOOK_SECRET: &str = "github.webhook_secret";
+
+/// Prefix of every masked value. Clients send masked values back unchanged
+/// when they don't edit a secret, so anything starting with this is treated as
+/// "keep the current value" rather than a new secret.
+pub const MASK_PREFIX: &str = "********";
+
+/// Rows re-encrypted per query during rotation
+const ROTATION_BATCH_SIZE: i64 = 100;
+
+const NONCE_LEN: usize = 12;
+
+/// Scope of a user's personal secrets
+pub fn user_scope(user_id: Uuid) -> String {
+    format!("user:{}", user_id)
+}
+
+/// Hide a secret, keeping the last four characters of long values so users can
+/// tell which key is configured
+pub fn mask_secret(value: &str) -> String {
+    let chars: Vec<char> = value.chars().collect();
+    if chars.len() < 16 {
+        return MASK_PREFIX.to_string();
+    }
+    let tail: String = chars[chars.len() - 4..].iter().collect();
+    format!("{}{}", MASK_PREFIX, tail)
+}
+
+pub fn is_masked(value: &str) -> bool {
+    value.starts_with(MASK_PREFIX)
+}
+
+#[derive(Debug, Error)]
+pub enum SecretStoreError {
+    #[error("Keyring error: {0}")]
+    Keyring(String),
+    #[error("No key with version {0} in the keyring")]
+    UnknownKeyVersion(i64),
+    #[error("Failed to encrypt secret")]
+    Encryption,
+    #[error("Failed to decrypt secret {0}")]
+    Decryption(String),
+    #[error("Keyring I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+/// A stored secret as shown to clients. The value is always masked.
+#[derive(Debug, Clone, Serialize, TS, ToSchema)]
+#[ts(export)]
+pub struct SecretSummary {
+    pub name: String,
+    pub masked_value: String,
+    pub key_version: i64,
+    #[ts(type = "Date")]
+    #[schema(value_type = String, format = DateTime)]
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Result of `SecretStore::rotate_key`
+#[derive(Debug, Clone)]
+pub struct RotationReport {
+    pub previous_version: i64,
+    pub new_version: i64,
+    pub reencrypted: usize,
+    pub retired_versions: Vec<i64>,
+}
+
+/// On-disk format of the keyring file
+#[derive(Serialize, Deserialize)]
+struct KeyringFile {
+    active_version: i64,
+    keys: BTreeMap<i64, String>,
+}
+
+struct Keyring {
+    active_version: i64,
+    keys: BTreeMap<i64, Zeroizing<[u8; 32]>>,
+    modified: Option<SystemTime>,
+}
+
+impl Keyring {
+    fn generate() -> Self {
+        let mut keys = BTreeMap::new();
+        keys.insert(1, Self::random_key());
+        Self {
+            active_version: 1,
+            keys,
+            modified: None,
+        }
+    }
+
+    fn random_key() -> Zeroizing<[u8; 32]> {
+        let key = Aes256Gcm::generate_key(&mut OsRng);
+        let mut bytes = Zeroizing::new([0u8; 32]);
+        bytes.copy_from_slice(key.as_slice());
+        bytes
+    }
+
+    fn decode_key(encoded: &str) -> Result<Zeroizing<[u8; 32]>, SecretStoreError> {
+        let decoded = Zeroizing::new(
+            STANDARD
+                .decode(encoded.trim())
+                .map_err(|e| SecretStoreError::Keyring(e.to_string()))?,
+        );
+        if decoded.len() != 32 {
+            return Err(SecretStoreError::Keyring(
+                "keys must be 32 bytes (256 bits)".to_string(),
+            ));
+        }
+        let mut bytes = Zeroizing::new([0u8; 32]);
+        bytes.copy_from_slice(&decoded);
+        Ok(bytes)
+    }
+
+    fn read(path: &Path) -> Result<Self, SecretStoreError> {
+        let content = Zeroizing::new(std::fs::read_to_string(path)?);
+        let file: KeyringFile = serde_json::from_str(&content)
+            .map_err(|e| SecretStoreError::Keyring(format!("{}: {}", path.display(), e)))?;
+
+        let mut keys = BTreeMap::new();
+        for (version, encoded) in &file.keys {
+            keys.insert(*version, Self::decode_key(encoded)?);
+        }
+        if !keys.contains_key(&file.active_version) {
+            return Err(SecretStoreError::UnknownKeyVersion(file.active_version));
+        }
+
+        Ok(Self {
+            active_version: file.active_version,
+            keys,
+            modified: std::fs::metadata(path).and_then(|m| m.modified()).ok(),
+        })
+    }
+
+    /// Write the keyring readable by the owner only, replacing the old file
+    /// in one step so a crash never leaves a half-written keyring behind
+    fn write(&mut self, path: &Path) -> Result<(), SecretStoreError> {
+        let file = KeyringFile {
+            active_version: self.active_version,
+            keys: self
+                .keys
+                .iter()
+                .map(|(version, key)| (*version, STANDARD.encode(key.as_slice())))
+                .collect(),
+        };
+        let content = Zeroizing::new(
+            serde_json::to_string_pretty(&file)
+                .map_err(|e| SecretStoreError::Keyring(e.to_string()))?,
+        );
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let tmp_path = path.with_extension("tmp");
+        {
+            use std::io::Write;
+
+            let mut options = std::fs::OpenOptions::new();
+            options.write(true).create(true).truncate(true);
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::OpenOptionsExt;
+                options.mode(0o600);
+            }
+            let mut out = options.open(&tmp_path)?;
+            out.write_all(content.as_bytes())?;
+            out.sync_all()?;
+        }
+        std::fs::rename(&tmp_path, path)?;
+
+        self.modified = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+        Ok(())
+    }
+
+    fn cipher(&self, version: i64) -> Result<Aes256Gcm, SecretStoreError> {
+        let key = self
+            .keys
+            .get(&version)
+            .ok_or(SecretStoreError::UnknownKeyVersion(version))?;
+        Ok(Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key.as_slice())))
+    }
+}
+
+/// Bind a ciphertext to its row so values can't be swapped between secrets
+fn associated_data(scope: &str, name: &str) -> Vec<u8> {
+    format!("{}\0{}", scope, name).into_bytes()
+}
+
+fn seal(cipher: &Aes256Gcm, aad: &[u8], plaintext: &str) -> Result<String, SecretStoreError> {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(
+            &nonce,
+            Payload {
+                msg: plaintext.as_bytes(),
+                aad,
+            },
+        )
+        .map_err(|_| SecretStoreError::Encryption)?;
+
+    let mut combined = Vec::with_capacity(NONCE_LEN + ciphertext.len());
+    combined.extend_from_slice(&nonce);
+    combined.extend_from_slice(&ciphertext);
+    Ok(STANDARD.encode(&combined))
+}
+
+fn open(cipher: &Aes256Gcm, aad: &[u8], sealed: &str) -> Option<Zeroizing<String>> {
+    let combined = STANDARD.decode(sealed).ok()?;
+    if combined.len() < NONCE_LEN {
+        return None;
+    }
+    let (nonce, ciphertext) = combined.split_at(NONCE_LEN);
+    let plaintext = cipher
+        .decrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad,
+            },
+        )
+        .ok()?;
+    String::from_utf8(plaintext).ok().map(Zeroizing::new)
+}
+
+/// Encrypted secret storage backed by the `secrets` table and a keyring file
+pub struct SecretStore {
+    pool: SqlitePool,
+    keyring_path: PathBuf,
+    keyring: RwLock<Keyring>,
+}
+
+impl std::fmt::Debug for SecretStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SecretStore")
+            .field("keyring_path", &self.keyring_path)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SecretStore {
+    /// Keyring location: `FORGE_SECRET_KEYRING`, or `secret_keys.json` next to
+    /// the database. Keep it out of database backups.
+    pub fn default_keyring_path() -> PathBuf {
+        std::env::var("FORGE_SECRET_KEYRING")
+            .ok()
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from)
+            .unwrap_or_else(|| utils::asset_dir().join("secret_keys.json"))
+    }
+
+    pub fn open(pool: SqlitePool) -> Result<Self, SecretStoreError> {
+        Self::open_with_keyring(pool, Self::default_keyring_path())
+    }
+
+    /// Open the store, creating the keyring on first start. An existing
+    /// `GITHUB_TOKEN_ENCRYPTION_KEY` becomes key version 1 so installs that
+    /// already manage that key keep controlling it.
+    pub fn open_with_keyring(
+        pool: SqlitePool,
+        keyring_path: PathBuf,
+    ) -> Result<Self, SecretStoreError> {
+        let keyring = if keyring_path.exists() {
+            Keyring::read(&keyring_path)?
+        } else {
+            let mut keyring = Keyring::generate();
+            if let Ok(legacy_key) = std::env::var("GITHUB_TOKEN_ENCRYPTION_KEY") {
+                keyring.keys.insert(1, Keyring::decode_key(&legacy_key)?);
+            }
+            keyring.write(&keyring_path)?;
+            tracing::info!("Created secret keyring at {}", keyring_path.display());
+            keyring
+        };
+
+        Ok(Self {
+            pool,
+            keyring_path,
+            keyring: RwLock::new(keyring),
+        })
+    }
+
+    /// Pick up a keyring rewritten by `secrets rotate-key` in another process
+    fn refresh_keyring(&self) -> Result<(), SecretStoreError> {
+        let on_disk = std::fs::metadata(&self.keyring_path)
+            .and_then(|m| m.modified())
+            .ok();
+        let cached = self.keyring.read().unwrap().modified;
+        if on_disk.is_some() && on_disk != cached {
+            let reloaded = Keyring::read(&self.keyring_path)?;
+            *self.keyring.write().unwrap() = reloaded;
+        }
+        Ok(())
+    }
+
+    /// Active key version and its cipher, taken under one lock
+    fn active_cipher(&self) -> Result<(i64, Aes256Gcm), SecretStoreError> {
+        self.refresh_keyring()?;
+        let keyring = self.keyring.read().unwrap();
+        Ok((
+            keyring.active_version,
+            keyring.cipher(keyring.active_version)?,
+        ))
+    }
+
+    fn decrypt(&self, secret: &Secret) -> Result<Zeroizing<String>, SecretStoreError> {
+        self.refresh_keyring()?;
+        let cipher = self.keyring.read().unwrap().cipher(secret.key_version)?;
+        open(
+            &cipher,
+            &associated_data(&secret.scope, &secret.name),
+            &secret.ciphertext,
+        )
+        .ok_or_else(|| SecretStoreError::Decryption(format!("{}/{}", secret.scope, secret.name)))
+    }
+
+    /// Store a secret, replacing any existing value
+    pub async fn put(&self, scope: &str, name: &str, value: &str) -> Result<(), SecretStoreError> {
+        // Seal under the database write lock: `rotate_key` holds it while it
+        // retires old keys, so it either counts this row or this picks up its
+        // new keyring
+        let mut tx = self.pool.begin_with("BEGIN IMMEDIATE").await?;
+        let (version, cipher) = self.active_cipher()?;
+        let ciphertext = seal(&cipher, &associated_data(scope, name), value)?;
+        Secret::upsert(&mut *tx, scope, name, version, &ciphertext).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    pub async fn get(
+        &self,
+        scope: &str,
+        name: &str,
+    ) -> Result<Option<SecureString>, SecretStoreError> {
+        let Some(secret) = Secret::find(&self.pool, scope, name).await? else {
+            return Ok(None);
+        };
+        let plaintext = self.decrypt(&secret)?;
+        Ok(Some(SecureString::new(plaintext.to_string())))
+    }
+
+    /// Every secret in a scope, decrypted, ordered by name
+    pub async fn get_all(
+        &self,
+        scope: &str,
+    ) -> Result<Vec<(String, SecureString)>, SecretStoreError> {
+        let mut secrets = Vec::new();
+        for secret in Secret::find_by_scope(&self.pool, scope).await? {
+            let plaintext = self.decrypt(&secret)?;
+            secrets.push((secret.name, SecureString::new(plaintext.to_string())));
+        }
+        Ok(secrets)
+    }
+
+    /// Remove a secret. Returns whether it existed.
+    pub async fn delete(&self, scope: &str, name: &str) -> Result<bool, SecretStoreError> {
+        Ok(Secret::delete(&self.pool, scope, name).await? > 0)
+    }
+
+    /// Secrets in a scope with masked values, for listing in the UI
+    pub async fn list_masked(&self, scope: &str) -> Result<Vec<SecretSummary>, SecretStoreError> {
+        let mut summaries = Vec::new();
+        for secret in Secret::find_by_scope(&self.pool, scope).await? {
+            let masked_value = match self.decrypt(&secret) {
+                Ok(plaintext) => mask_secret(&plaintext),
+                Err(e) => {
+                    tracing::warn!("{}", e);
+                    MASK_PREFIX.to_string()
+                }
+            };
+            summaries.push(SecretSummary {
+                name: secret.name,
+                masked_value,
+                key_version: secret.key_version,
+                updated_at: secret.updated_at,
+            });
+        }
+        Ok(summaries)
+    }
+
+    /// Active key version and the number of secrets sealed with each version
+    pub async fn status(&self) -> Result<(i64, Vec<(i64, i64)>), SecretStoreError> {
+        self.refresh_keyring()?;
+        let active_version = self.keyring.read().unwrap().active_version;
+        Ok((
+            active_version,
+            Secret::count_by_key_version(&self.pool).await?,
+        ))
+    }
+
+    /// Rotate to a fresh key:
+    /// 1. add a new key to the keyring and make it the active one,
+    /// 2. re-encrypt every secret sealed with an older key,
+    /// 3. drop the old keys from the keyring.
+    ///
+    /// Each step is safe to interrupt; running the command again finishes the
+    /// job because an old key is only dropped once no row uses it, counted in
+    /// the same write transaction that drops it.
+    pub async fn rotate_key(&self) -> Result<RotationReport, SecretStoreError> {
+        self.refresh_keyring()?;
+        let (previous_version, new_version) = {
+            let mut keyring = self.keyring.write().unwrap();
+            let previous_version = keyring.active_version;
+            let new_version = keyring.keys.keys().max().copied().unwrap_or(0) + 1;
+            keyring.keys.insert(new_version, Keyring::random_key());
+            keyring.active_version = new_version;
+            keyring.write(&self.keyring_path)?;
+            (previous_version, new_version)
+        };
+
+        let mut reencrypted = 0;
+        loop {
+            let batch =
+                Secret::find_not_at_version(&self.pool, new_version, ROTATION_BATCH_SIZE).await?;
+            if batch.is_empty() {
+                break;
+            }
+            let new_cipher = self.keyring.read().unwrap().cipher(new_version)?;
+            for secret in &batch {
+                let plaintext = self.decrypt(secret)?;
+                let ciphertext = seal(
+                    &new_cipher,
+                    &associated_data(&secret.scope, &secret.name),
+                    &plaintext,
+                )?;
+                if Secret::update_ciphertext(
+                    &self.pool,
+                    secret.id,
+                    secret.key_version,
+                    new_version,
+                    &ciphertext,
+                )
+                .await?
+                {
+                    reencrypted += 1;
+                }
+            }
+        }
+
+        // Count under the write lock so no `put` can seal a row with an old
+        // key between the count and the keyring write; a version still in use
+        // is kept for the next run
+        let mut tx = self.pool.begin_with("BEGIN IMMEDIATE").await?;
+        let in_use: Vec<i64> = Secret::count_by_key_version(&mut *tx)
+            .await?
+            .into_iter()
+            .filter(|(_, count)| *count > 0)
+            .map(|(version, _)| version)
+            .collect();
+        let retired_versions = {
+            let mut keyring = self.keyring.write().unwrap();
+            let retired: Vec<i64> = keyring
+                .keys
+                .keys()
+                .copied()
+                .filter(|version| *version != new_version && !in_use.contains(version))
+                .collect();
+            keyring.keys.retain(|version, _| !retired.contains(version));
+            keyring.write(&self.keyring_path)?;
+            retired
+        };
+        tx.commit().await?;
+
+        Ok(RotationReport {
+            previous_version,
+            new_version,
+            reencrypted,
+            retired_versions,
+        })
+    }
+
+    /// Fill the secret fields of a freshly loaded `config.json` from the store.
+    /// Values still present in the file (written before secrets moved into the
+    /// store) are imported first, and the file is rewritten without them.
+    pub async fn load_config_secrets(
+        &self,
+        config: &mut Config,
+        config_path: &PathBuf,
+    ) -> Result<(), SecretStoreError> {
+        let mut imported = false;
+        for (name, field) in config.secret_fields_mut() {
+            match field.as_deref().filter(|value| !value.is_empty()) {
+                Some(value) => {
+                    self.put(CONFIG_SCOPE, name, value).await?;
+                    imported = true;
+                }
+                None => {
+                    *field = self
+                        .get(CONFIG_SCOPE, name)
+                        .await?
+                        .map(SecureString::into_string);
+                }
+            }
+        }
+
+        if imported {
+            tracing::info!("Moved plaintext secrets from config.json into the secret store");
+            if let Err(e) = config.save(config_path) {
+                tracing::warn!("Failed to rewrite config without secrets: {}", e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Persist the secret fields of the config, removing cleared ones
+    pub async fn save_config_secrets(&self, config: &Config) -> Result<(), SecretStoreError> {
+        for (name, value) in config.secret_fields() {
+            match value.filter(|value| !value.is_empty()) {
+                Some(value) => self.put(CONFIG_SCOPE, name, value).await?,
+                None => {
+                    self.delete(CONFIG_SCOPE, name).await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn user_github_token(
+        &self,
+        user_id: Uuid,
+    ) -> Result<Option<SecureString>, SecretStoreError> {
+        self.get(&user_scope(user_id), GITHUB_TOKEN_SECRET).await
+    }
+
+    pub async fn set_user_github_token(
+        &self,
+        user_id: Uuid,
+        token: &str,
+    ) -> Result<(), SecretStoreError> {
+        self.put(&user_scope(user_id), GITHUB_TOKEN_SECRET, token)
+            .await
+    }
+
+    /// Move GitHub tokens still stored in plaintext on `users` into the store
+    pub async fn migrate_user_github_tokens(&self) -> Result<usize, SecretStoreError> {
+        let mut migrated = 0;
+        for user in User::find_all(&self.pool).await? {
+            let Some(token) = user.github_token.as_deref().filter(|t| !t.is_empty()) else {
+                continue;
+            };
+            self.set_user_github_token(user.id, token).await?;
+            User::clear_github_token(&self.pool, user.id).await?;
+            migrated += 1;
+        }
+        Ok(migrated)
+    }
+}
+
+/// `automagik-forge secrets <command>`: key management run against the
+/// server's database and keyring
+pub async fn run_cli(store: &SecretStore, args: &[String]) -> anyhow::Result<()> {
+    match args.first().map(String::as_str) {
+        Some("rotate-key") => {
+            let report = store.rotate_key().await?;
+            println!(
+                "Rotated secret key v{} -> v{}: re-encrypted {} secret(s), retired key version(s) {:?}",
+                report.previous_version,
+                report.new_version,
+                report.reencrypted,
+                report.retired_versions
+            );
+            println!(
+                "Keyring: {}. Running servers pick up the new key automatically.",
+                store.keyring_path.display()
+            );
+            Ok(())
+        }
+        Some("status") => {
+            let (active_version, counts) = store.status().await?;
+            println!("Keyring: {}", store.keyring_path.display());
+            println!("Active key version: {}", active_version);
+            for (version, count) in counts {
+                println!("  v{}: {} secret(s)", version, count);
+            }
+            Ok(())
+        }
+        _ => {
+            eprintln!("Usage: automagik-forge secrets <rotate-key|status>");
+            std::process::exit(2);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_cipher() -> Aes256Gcm {
+        let keyring = Keyring::generate();
+        keyring.cipher(1).unwrap()
+    }
+
+    #[test]
+    fn test_seal_and_open_round_trip() {
+        let cipher = test_cipher();
+        let aad = associated_data(CONFIG_SCOPE, "github.pat");
+        let sealed = seal(&cipher, &aad, "ghp_example_token_value").unwrap();
+        assert!(!sealed.contains("ghp_"));
+        assert_eq!(
+            open(&cipher, &aad, &sealed).unwrap().as_str(),
+            "ghp_example_token_value"
+        );
+    }
+
+    #[test]
+    fn test_ciphertext_is_bound_to_its_name() {
+        let cipher = test_cipher();
+        let sealed = seal(
+            &cipher,
+            &associated_data(CONFIG_SCOPE, "github.pat"),
+            "value",
+        )
+        .unwrap();
+        assert!(open(
+            &cipher,
+            &associated_data(CONFIG_SCOPE, "github.token"),
+            &sealed
+        )
+        .is_none());
+        assert!(open(
+            &test_cipher(),
+            &associated_data(CONFIG_SCOPE, "github.pat"),
+            &sealed
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn test_mask_secret() {
+        assert_eq!(mask_secret("short"), MASK_PREFIX);
+        assert_eq!(
+            mask_secret("ghp_0123456789abcdefWXYZ"),
+            format!("{}WXYZ", MASK_PREFIX)
+        );
+        assert!(is_masked(&mask_secret("anything at all")));
+        assert!(!is_masked("ghp_0123456789abcdefWXYZ"));
+    }
+
+    #[test]
+    fn test_keyring_file_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("keys.json");
+
+        let mut keyring = Keyring::generate();
+        keyring.keys.insert(2, Keyring::random_key());
+        keyring.active_version = 2;
+        keyring.write(&path).unwrap();
+
+        let loaded = Keyring::read(&path).unwrap();
+        assert_eq!(loaded.active_version, 2);
+        assert_eq!(loaded.keys.len(), 2);
+        assert_eq!(loaded.keys[&1].as_slice(), keyring.keys[&1].as_slice());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+}
@@ -146,6 +146,9 @@ pub fn create_secure_cors_layer() -> tower_http::cors::CorsLayer {
             header::ACCEPT,
             "x-requested-with".parse::<header::HeaderName>().unwrap(),
         ])
+        .expose_headers([crate::auth::REFRESHED_TOKEN_HEADER
+            .parse::<header::HeaderName>()
+            .unwrap()])
         .allow_credentials(true)
         .max_age(Duration::from_secs(86400)); // 24 hours
 
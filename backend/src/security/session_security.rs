@@ -41,6 +41,7 @@ pub struct SessionSecurityConfig {
     pub max_concurrent_web_sessions: u32,
     pub max_concurrent_mcp_sessions: u32,
     pub token_rotation_threshold_hours: i64,
+    pub token_rotation_grace_seconds: i64,
     pub cleanup_interval_hours: i64,
     pub force_logout_on_security_event: bool,
 }
@@ -51,6 +52,7 @@ impl Default for SessionSecurityConfig {
             max_concurrent_web_sessions: 3,
             max_concurrent_mcp_sessions: 5,
             token_rotation_threshold_hours: 12, // Rotate tokens after 12 hours
+            token_rotation_grace_seconds: 60, // Accept the replaced token for a minute
             cleanup_interval_hours: 1, // Clean up expired sessions every hour
             force_logout_on_security_event: true,
         }
@@ -58,7 +60,7 @@ impl Default for SessionSecurityConfig {
 }
 
 /// Enhanced session security manager
-#[allow(dead_code)]
+#[derive(Debug)]
 pub struct SessionSecurity {
     db_pool: SqlitePool,
     jwt_config: JwtConfig,
@@ -66,7 +68,6 @@ pub struct SessionSecurity {
     config: SessionSecurityConfig,
 }
 
-#[allow(dead_code)]
 impl SessionSecurity {
     pub fn new(
         db_pool: SqlitePool,
@@ -83,6 +84,7 @@ impl SessionSecurity {
     }
 
     /// Create a new secure session with concurrent session limits
+    #[allow(dead_code)]
     pub async fn create_session(
         &self,
         user_id: Uuid,
@@ -149,14 +151,24 @@ impl SessionSecurity {
         )?;
         let new_token_hash = hash_token(&new_jwt_token);
 
-        // Update session with new token hash
-        sqlx::query!(
-            "UPDATE user_sessions SET token_hash = $1 WHERE id = $2",
+        // Update session with new token hash and restart the rotation clock.
+        // The old hash is kept so in-flight requests survive the grace period.
+        // Matching on the current hash makes concurrent rotations race-free.
+        let rows_affected = sqlx::query!(
+            "UPDATE user_sessions SET token_hash = $1, previous_token_hash = $2, rotated_at = datetime('now', 'subsec') WHERE id = $3 AND token_hash = $2",
             new_token_hash,
+            token_hash,
             session.id
         )
         .execute(&self.db_pool)
-        .await?;
+        .await?
+        .rows_affected();
+
+        if rows_affected == 0 {
+            return Err(SessionSecurityError::TokenRotationFailed(
+                "session token was rotated concurrently".to_string(),
+            ));
+        }
 
         // Log token rotation
         self.audit_logger.log_event(CreateAuditEvent {
@@ -179,11 +191,36 @@ impl SessionSecurity {
         Ok(new_jwt_token)
     }
 
+    /// Resolve a session from a token that was replaced by a recent rotation
+    pub async fn find_session_by_rotated_token(
+        &self,
+        token: &str,
+    ) -> Result<Option<UserSession>, SessionSecurityError> {
+        Ok(UserSession::find_recently_rotated_by_previous_token_hash(
+            &self.db_pool,
+            &hash_token(token),
+            self.config.token_rotation_grace_seconds,
+        ).await?)
+    }
+
     /// Check if a session needs token rotation
+    ///
+    /// Only web sessions are rotated: MCP clients hold their bearer token in
+    /// static configuration and cannot pick up a refreshed token from a
+    /// response header.
     pub async fn needs_token_rotation(&self, session: &UserSession) -> bool {
+        if session.session_type != SessionType::Web {
+            return false;
+        }
+        let rotated_at = UserSession::find_rotated_at(&self.db_pool, session.id)
+            .await
+            .unwrap_or_else(|e| {
+                warn!("Failed to read rotation time of session {}: {}", session.id, e);
+                None
+            });
         let rotation_threshold = Duration::hours(self.config.token_rotation_threshold_hours);
-        let session_age = Utc::now() - session.created_at;
-        session_age >= rotation_threshold
+        let token_age = Utc::now() - rotated_at.unwrap_or(session.created_at);
+        token_age >= rotation_threshold
     }
 
     /// Revoke a specific session
@@ -295,16 +332,14 @@ impl SessionSecurity {
         Ok(sessions_deleted)
     }
 
-    /// Enforce concurrent session limits
-    async fn enforce_concurrent_session_limits(
+    /// Enforce concurrent session limits, revoking the oldest sessions of the
+    /// same type so a new one fits under the cap
+    pub async fn enforce_concurrent_session_limits(
         &self,
         user_id: Uuid,
         session_type: SessionType,
     ) -> Result<(), SessionSecurityError> {
-        let max_sessions = match session_type {
-            SessionType::Web => self.config.max_concurrent_web_sessions,
-            SessionType::Mcp => self.config.max_concurrent_mcp_sessions,
-        };
+        let max_sessions = self.max_sessions_for(session_type);
 
         let current_session_count = UserSession::count_active_by_user_and_type(
             &self.db_pool,
@@ -315,19 +350,25 @@ impl SessionSecurity {
         if current_session_count >= max_sessions {
             // Remove oldest sessions to make room
             let sessions_to_remove = current_session_count - max_sessions + 1;
-            self.remove_oldest_sessions(user_id, session_type, sessions_to_remove).await?;
+            self.remove_oldest_sessions(
+                user_id,
+                session_type,
+                sessions_to_remove,
+                "concurrent_session_limit",
+            ).await?;
         }
 
         Ok(())
     }
 
-    /// Remove oldest sessions for a user
+    /// Remove oldest sessions for a user, returning the revoked session IDs
     async fn remove_oldest_sessions(
         &self,
         user_id: Uuid,
         session_type: SessionType,
         count: u32,
-    ) -> Result<(), SessionSecurityError> {
+        reason: &str,
+    ) -> Result<Vec<Uuid>, SessionSecurityError> {
         let session_type_str = match session_type {
             SessionType::Web => "web",
             SessionType::Mcp => "mcp",
@@ -346,17 +387,13 @@ impl SessionSecurity {
         .fetch_all(&self.db_pool)
         .await?;
 
+        let mut revoked = Vec::with_capacity(oldest_sessions.len());
         for session in oldest_sessions {
-            self.revoke_session(
-                session.id,
-                "concurrent_session_limit",
-                None,
-                None,
-                None,
-            ).await?;
+            self.revoke_session(session.id, reason, None, None, None).await?;
+            revoked.push(session.id);
         }
 
-        Ok(())
+        Ok(revoked)
     }
 
     /// Clean up expired sessions and log cleanup activity
@@ -371,6 +408,7 @@ impl SessionSecurity {
     }
 
     /// Get session security metrics
+    #[allow(dead_code)]
     pub async fn get_session_metrics(&self) -> Result<SessionMetrics, SessionSecurityError> {
         let total_active_sessions = sqlx::query!(
             r#"SELECT COUNT(*) as "count!: i64" FROM user_sessions 
@@ -416,25 +454,34 @@ impl SessionSecurity {
     pub async fn detect_suspicious_activity(&self, user_id: Uuid) -> Result<Vec<SecurityAlert>, SessionSecurityError> {
         let mut alerts = Vec::new();
 
-        // Check for too many sessions from different IPs
+        // Check for more sessions than the per-type cap allows
         // This would require storing IP addresses in sessions - for now, just check session count
-        let session_count = UserSession::count_active_by_user_and_type(&self.db_pool, user_id, SessionType::Web).await?;
-        
-        if session_count > self.config.max_concurrent_web_sessions as i64 {
-            alerts.push(SecurityAlert {
-                alert_type: SecurityAlertType::ExcessiveSessions,
-                description: format!("User has {} active sessions", session_count),
-                severity: AuditSeverity::Medium,
-                user_id,
-            });
+        for session_type in [SessionType::Web, SessionType::Mcp] {
+            let max_sessions = self.max_sessions_for(session_type) as i64;
+            let session_count =
+                UserSession::count_active_by_user_and_type(&self.db_pool, user_id, session_type).await?;
+
+            if session_count > max_sessions {
+                alerts.push(SecurityAlert {
+                    alert_type: SecurityAlertType::ExcessiveSessions,
+                    description: format!(
+                        "User has {} active {:?} sessions (limit {})",
+                        session_count, session_type, max_sessions
+                    ),
+                    severity: AuditSeverity::Medium,
+                    user_id,
+                });
+            }
         }
 
-        // Check for very old sessions that haven't been rotated
+        // Check for very old web sessions that haven't been rotated
+        let now = Utc::now();
         let old_sessions = sqlx::query!(
             r#"SELECT COUNT(*) as "count!: i64" FROM user_sessions 
-               WHERE user_id = $1 AND expires_at > datetime('now', 'subsec') 
-               AND created_at < datetime('now', '-7 days')"#,
-            user_id
+               WHERE user_id = $1 AND session_type = 'web' AND expires_at > $2 
+               AND COALESCE(rotated_at, created_at) < datetime('now', '-7 days')"#,
+            user_id,
+            now
         )
         .fetch_one(&self.db_pool)
         .await?
@@ -451,6 +498,123 @@ impl SessionSecurity {
 
         Ok(alerts)
     }
+
+    /// Record each alert as a security violation and revoke the offending
+    /// sessions. Returns `true` when `current_session` was among those revoked.
+    pub async fn respond_to_alerts(
+        &self,
+        current_session: &UserSession,
+        alerts: &[SecurityAlert],
+        ip_address: Option<String>,
+        user_agent: Option<String>,
+    ) -> Result<bool, SessionSecurityError> {
+        let mut revoked = Vec::new();
+
+        for alert in alerts {
+            self.audit_logger.log_event(CreateAuditEvent {
+                event_type: AuditEventType::SecurityViolation,
+                user_id: Some(alert.user_id),
+                ip_address: ip_address.clone(),
+                user_agent: user_agent.clone(),
+                resource: "user_session".to_string(),
+                action: format!("{:?}", alert.alert_type),
+                result: AuditResult::Blocked,
+                details: Some(serde_json::json!({
+                    "session_id": current_session.id,
+                    "description": alert.description,
+                    "auto_revoke": self.config.force_logout_on_security_event
+                })),
+                severity: alert.severity.clone(),
+            }).await?;
+
+            warn!(
+                "Security alert for user {}: {} ({:?})",
+                alert.user_id, alert.description, alert.alert_type
+            );
+
+            if !self.config.force_logout_on_security_event {
+                continue;
+            }
+
+            match alert.alert_type {
+                SecurityAlertType::ExcessiveSessions => {
+                    for session_type in [SessionType::Web, SessionType::Mcp] {
+                        let max_sessions = self.max_sessions_for(session_type);
+                        let count = UserSession::count_active_by_user_and_type(
+                            &self.db_pool,
+                            alert.user_id,
+                            session_type,
+                        ).await? as u32;
+
+                        if count > max_sessions {
+                            revoked.extend(
+                                self.remove_oldest_sessions(
+                                    alert.user_id,
+                                    session_type,
+                                    count - max_sessions,
+                                    "excessive_sessions",
+                                ).await?,
+                            );
+                        }
+                    }
+                }
+                SecurityAlertType::StaleTokens => {
+                    let now = Utc::now();
+                    let stale_sessions = sqlx::query!(
+                        r#"SELECT id as "id!: Uuid" FROM user_sessions 
+                           WHERE user_id = $1 AND session_type = 'web' AND expires_at > $2 
+                           AND COALESCE(rotated_at, created_at) < datetime('now', '-7 days')"#,
+                        alert.user_id,
+                        now
+                    )
+                    .fetch_all(&self.db_pool)
+                    .await?;
+
+                    for session in stale_sessions {
+                        self.revoke_session(session.id, "stale_token", None, ip_address.clone(), user_agent.clone()).await?;
+                        revoked.push(session.id);
+                    }
+                }
+                SecurityAlertType::SuspiciousActivity => {
+                    self.revoke_all_user_sessions(
+                        alert.user_id,
+                        "suspicious_activity",
+                        None,
+                        ip_address.clone(),
+                        user_agent.clone(),
+                    ).await?;
+                    return Ok(true);
+                }
+            }
+        }
+
+        Ok(revoked.contains(&current_session.id))
+    }
+
+    /// Look for suspicious activity of the user owning a session that was
+    /// just issued or rotated and respond to what is found. Returns `true`
+    /// when `session` itself was revoked. Only run at those points, not on
+    /// every request.
+    pub async fn screen_session(
+        &self,
+        session: &UserSession,
+        ip_address: Option<String>,
+        user_agent: Option<String>,
+    ) -> Result<bool, SessionSecurityError> {
+        let alerts = self.detect_suspicious_activity(session.user_id).await?;
+        if alerts.is_empty() {
+            return Ok(false);
+        }
+        self.respond_to_alerts(session, &alerts, ip_address, user_agent)
+            .await
+    }
+
+    fn max_sessions_for(&self, session_type: SessionType) -> u32 {
+        match session_type {
+            SessionType::Web => self.config.max_concurrent_web_sessions,
+            SessionType::Mcp => self.config.max_concurrent_mcp_sessions,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -464,7 +628,6 @@ pub struct SessionMetrics {
 
 #[derive(Debug, Serialize, Deserialize, TS, ToSchema)]
 #[ts(export)]
-#[allow(dead_code)]
 pub struct SecurityAlert {
     pub alert_type: SecurityAlertType,
     pub description: String,
@@ -474,7 +637,7 @@ pub struct SecurityAlert {
 
 #[derive(Debug, Serialize, Deserialize, TS, ToSchema)]
 #[ts(export)]
-#[allow(dead_code)]
+#[allow(dead_code)] // SuspiciousActivity is reserved for IP/user-agent heuristics
 pub enum SecurityAlertType {
     ExcessiveSessions,
     StaleTokens,
@@ -500,4 +663,94 @@ mod tests {
         let config = SessionSecurityConfig::default();
         assert!(config.token_rotation_threshold_hours > 0);
     }
+
+    #[tokio::test]
+    async fn test_rotation_keeps_created_at() {
+        use crate::models::{
+            user::{CreateUser, User},
+            user_session::CreateUserSession,
+        };
+
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+        let jwt_config = JwtConfig {
+            secret: "a-session-security-test-secret-of-32-bytes".to_string(),
+            algorithm: jsonwebtoken::Algorithm::HS256,
+        };
+        let security = SessionSecurity::new(
+            pool.clone(),
+            jwt_config.clone(),
+            AuditLogger::new(pool.clone()),
+            None,
+        );
+        let user = User::create(
+            &pool,
+            &CreateUser {
+                github_id: 1,
+                username: "rotating".to_string(),
+                email: "rotating@example.com".to_string(),
+                display_name: None,
+                avatar_url: None,
+                github_token: None,
+                is_admin: None,
+            },
+            Uuid::new_v4(),
+        )
+        .await
+        .unwrap();
+        let session_id = Uuid::new_v4();
+        let token = generate_jwt_token(user.id, session_id, SessionType::Web, &jwt_config).unwrap();
+        UserSession::create(
+            &pool,
+            &CreateUserSession {
+                user_id: user.id,
+                token_hash: hash_token(&token),
+                session_type: SessionType::Web,
+                client_info: None,
+                expires_at: Utc::now() + Duration::hours(UserSession::WEB_SESSION_DURATION_HOURS),
+            },
+            session_id,
+        )
+        .await
+        .unwrap();
+        sqlx::query(
+            "UPDATE user_sessions SET created_at = datetime('now', '-13 hours') WHERE id = $1",
+        )
+        .bind(session_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+        let session = UserSession::find_valid_by_token_hash(&pool, &hash_token(&token))
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(security.needs_token_rotation(&session).await);
+
+        let new_token = security.rotate_token(&token, None, None).await.unwrap();
+        let rotated = UserSession::find_valid_by_token_hash(&pool, &hash_token(&new_token))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(rotated.created_at, session.created_at);
+        assert!(!security.needs_token_rotation(&rotated).await);
+
+        // The replaced token is still accepted within the grace period
+        let by_old_token = security
+            .find_session_by_rotated_token(&token)
+            .await
+            .unwrap();
+        assert_eq!(by_old_token.map(|session| session.id), Some(session_id));
+    }
+
+    #[test]
+    fn test_rotation_grace_period_is_short() {
+        // The replaced token should only outlive the rotation for in-flight requests
+        let config = SessionSecurityConfig::default();
+        assert!(config.token_rotation_grace_seconds > 0);
+        assert!(config.token_rotation_grace_seconds < config.token_rotation_threshold_hours * 3600);
+    }
 }
\ No newline at end of file
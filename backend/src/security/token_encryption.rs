@@ -167,17 +167,14 @@ pub struct SecureString {
 }
 
 impl SecureString {
-    #[allow(dead_code)]
     pub fn new(data: String) -> Self {
         Self { data }
     }
 
-    #[allow(dead_code)]
     pub fn as_str(&self) -> &str {
         &self.data
     }
 
-    #[allow(dead_code)]
     pub fn into_string(self) -> String {
         self.data.clone()
     }
@@ -0,0 +1,333 @@
+//! Environment variables configured per project (with per-user overrides) and
+//! injected into every process Forge spawns for that project: setup, dev server,
+//! cleanup and coding agents.
+//!
+//! Forge also tells every process which attempt it belongs to through
+//! `FORGE_ATTEMPT_ID`, `FORGE_TASK_ID` and `FORGE_PROJECT_ID`, so MCP servers
+//! launched by a coding agent can act on the agent's own attempt.
+//!
+//! Values live encrypted in the secret store. While a process runs, its values
+//! are registered here so anything it prints is redacted before the output is
+//! written to `execution_processes`.
+
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use tokio::process::Command;
+use uuid::Uuid;
+
+use crate::security::{
+    secret_store::{SecretStore, SecretStoreError},
+    token_encryption::SecureString,
+};
+
+pub const FORGE_ATTEMPT_ID_VAR: &str = "FORGE_ATTEMPT_ID";
+pub const FORGE_TASK_ID_VAR: &str = "FORGE_TASK_ID";
+pub const FORGE_PROJECT_ID_VAR: &str = "FORGE_PROJECT_ID";
+
+/// Values shorter than this are injected but not redacted; replacing every
+/// occurrence of e.g. `1` or `on` would make logs unreadable
+const MIN_REDACTED_LENGTH: usize = 4;
+
+/// How long redactions outlive their process, so output still being flushed
+/// after the exit is seen is covered too
+const REDACTION_GRACE_PERIOD: Duration = Duration::from_secs(30);
+
+/// Variable names and the secret values to mask for them, longest first
+type Redactions = Arc<Vec<(String, SecureString)>>;
+
+lazy_static::lazy_static! {
+    /// Secret values per running execution process
+    static ref REDACTIONS: RwLock<HashMap<Uuid, Redactions>> =
+        RwLock::new(HashMap::new());
+}
+
+/// Secret store scope holding a project's variables
+pub fn project_env_scope(project_id: Uuid) -> String {
+    format!("project_env:{}", project_id)
+}
+
+/// Secret store scope holding one user's overrides for a project
+pub fn user_env_scope(project_id: Uuid, user_id: Uuid) -> String {
+    format!("project_env:{}:user:{}", project_id, user_id)
+}
+
+/// Variable names must be valid shell identifiers
+pub fn validate_env_var_name(name: &str) -> Result<(), &'static str> {
+    let mut chars = name.chars();
+    let valid = matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid || name.len() > 128 {
+        return Err("Variable names must start with a letter or '_' and contain only letters, digits and '_'");
+    }
+    Ok(())
+}
+
+/// Decrypted variables for one execution
+#[derive(Clone, Default)]
+pub struct ExecutionEnv {
+    vars: BTreeMap<String, SecureString>,
+    /// Identifiers of the attempt being run; not secret, so never redacted
+    attempt_context: Vec<(&'static str, String)>,
+    /// Per-attempt MCP config for coding agents of projects that opted in
+    mcp_config: Option<PathBuf>,
+}
+
+impl std::fmt::Debug for ExecutionEnv {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ExecutionEnv")
+            .field("vars", &self.vars.keys().collect::<Vec<_>>())
+            .field("attempt_context", &self.attempt_context)
+            .field("mcp_config", &self.mcp_config)
+            .finish()
+    }
+}
+
+impl ExecutionEnv {
+    /// A project's variables, with `user_id`'s overrides taking precedence
+    pub async fn resolve(
+        store: &SecretStore,
+        project_id: Uuid,
+        user_id: Option<Uuid>,
+    ) -> Result<Self, SecretStoreError> {
+        let mut vars: BTreeMap<String, SecureString> = store
+            .get_all(&project_env_scope(project_id))
+            .await?
+            .into_iter()
+            .collect();
+        if let Some(user_id) = user_id {
+            vars.extend(store.get_all(&user_env_scope(project_id, user_id)).await?);
+        }
+        Ok(Self {
+            vars,
+            attempt_context: Vec::new(),
+            mcp_config: None,
+        })
+    }
+
+    /// Tell the process which attempt, task and project it runs for
+    pub fn with_attempt_context(
+        mut self,
+        attempt_id: Uuid,
+        task_id: Uuid,
+        project_id: Uuid,
+    ) -> Self {
+        self.attempt_context = vec![
+            (FORGE_ATTEMPT_ID_VAR, attempt_id.to_string()),
+            (FORGE_TASK_ID_VAR, task_id.to_string()),
+            (FORGE_PROJECT_ID_VAR, project_id.to_string()),
+        ];
+        self
+    }
+
+    /// Point the coding agent at a per-attempt MCP config
+    pub fn with_mcp_config(mut self, path: PathBuf) -> Self {
+        self.mcp_config = Some(path);
+        self
+    }
+
+    pub fn mcp_config(&self) -> Option<&Path> {
+        self.mcp_config.as_deref()
+    }
+
+    /// Add the variables to a command about to be spawned
+    pub fn apply(&self, command: &mut Command) {
+        for (name, value) in &self.vars {
+            command.env(name, value.as_str());
+        }
+        // Applied last so project variables can't impersonate another attempt
+        for (name, value) in &self.attempt_context {
+            command.env(name, value);
+        }
+    }
+
+    /// Redact these values from everything `execution_process_id` writes to
+    /// its stored output until `release_redactions` is called
+    pub fn register_redactions(&self, execution_process_id: Uuid) {
+        let mut values: Vec<(String, SecureString)> = Vec::new();
+        for (name, value) in &self.vars {
+            // Output is stored in batches of lines, so a multi-line value can
+            // be split across writes; each of its lines is masked as well
+            let lines = value
+                .as_str()
+                .lines()
+                .filter(|line| *line != value.as_str());
+            for candidate in std::iter::once(value.as_str()).chain(lines) {
+                if candidate.len() >= MIN_REDACTED_LENGTH
+                    && !values.iter().any(|(_, known)| known.as_str() == candidate)
+                {
+                    values.push((name.clone(), SecureString::new(candidate.to_string())));
+                }
+            }
+        }
+        if values.is_empty() {
+            return;
+        }
+        // Longest first, so a value containing another is replaced whole
+        values.sort_by_key(|(_, value)| std::cmp::Reverse(value.as_str().len()));
+        REDACTIONS
+            .write()
+            .unwrap()
+            .insert(execution_process_id, Arc::new(values));
+    }
+}
+
+/// Forget an execution's values after a short grace period
+pub fn release_redactions(execution_process_id: Uuid) {
+    if !REDACTIONS
+        .read()
+        .unwrap()
+        .contains_key(&execution_process_id)
+    {
+        return;
+    }
+    tokio::spawn(async move {
+        tokio::time::sleep(REDACTION_GRACE_PERIOD).await;
+        REDACTIONS.write().unwrap().remove(&execution_process_id);
+    });
+}
+
+/// Replace any injected secret in a chunk of process output
+pub fn redact_output(execution_process_id: Uuid, output: &str) -> Cow<'_, str> {
+    let Some(values) = REDACTIONS
+        .read()
+        .unwrap()
+        .get(&execution_process_id)
+        .cloned()
+    else {
+        return Cow::Borrowed(output);
+    };
+    redact_values(output, &values)
+}
+
+fn redact_values<'a>(output: &'a str, values: &[(String, SecureString)]) -> Cow<'a, str> {
+    let mut redacted = Cow::Borrowed(output);
+    for (name, value) in values {
+        if redacted.contains(value.as_str()) {
+            redacted =
+                Cow::Owned(redacted.replace(value.as_str(), &format!("[redacted:{}]", name)));
+        }
+    }
+    redacted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(pairs: &[(&str, &str)]) -> Vec<(String, SecureString)> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), SecureString::new(value.to_string())))
+            .collect()
+    }
+
+    #[test]
+    fn test_redact_values() {
+        let secrets = values(&[
+            ("DATABASE_URL", "postgres://u:hunter2@db/app"),
+            ("API_KEY", "sk-abc123"),
+        ]);
+        let output = "connecting to postgres://u:hunter2@db/app with sk-abc123\n";
+        assert_eq!(
+            redact_values(output, &secrets),
+            "connecting to [redacted:DATABASE_URL] with [redacted:API_KEY]\n"
+        );
+        assert!(matches!(
+            redact_values("nothing here", &secrets),
+            Cow::Borrowed(_)
+        ));
+    }
+
+    #[test]
+    fn test_registered_redactions_apply_per_process() {
+        let mut env = ExecutionEnv::default();
+        env.vars.insert(
+            "TOKEN".to_string(),
+            SecureString::new("s3cr3t-token".to_string()),
+        );
+        env.vars
+            .insert("DEBUG".to_string(), SecureString::new("1".to_string()));
+
+        let process_id = Uuid::new_v4();
+        env.register_redactions(process_id);
+
+        assert_eq!(
+            redact_output(process_id, "token=s3cr3t-token debug=1"),
+            "token=[redacted:TOKEN] debug=1"
+        );
+        assert_eq!(
+            redact_output(Uuid::new_v4(), "token=s3cr3t-token"),
+            "token=s3cr3t-token"
+        );
+    }
+
+    #[test]
+    fn test_multi_line_values_are_redacted_across_writes() {
+        let mut env = ExecutionEnv::default();
+        env.vars.insert(
+            "DEPLOY_KEY".to_string(),
+            SecureString::new("key-line-one\nkey-line-two\n".to_string()),
+        );
+
+        let process_id = Uuid::new_v4();
+        env.register_redactions(process_id);
+
+        assert_eq!(
+            redact_output(process_id, "key: key-line-one\nkey-line-two\n"),
+            "key: [redacted:DEPLOY_KEY]"
+        );
+        assert_eq!(
+            redact_output(process_id, "key-line-two\ndone\n"),
+            "[redacted:DEPLOY_KEY]\ndone\n"
+        );
+    }
+
+    #[test]
+    fn test_attempt_context_overrides_project_variables() {
+        let mut env = ExecutionEnv::default();
+        env.vars.insert(
+            FORGE_ATTEMPT_ID_VAR.to_string(),
+            SecureString::new("not-this-attempt".to_string()),
+        );
+        let (attempt_id, task_id, project_id) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let env = env.with_attempt_context(attempt_id, task_id, project_id);
+
+        let mut command = Command::new("true");
+        env.apply(&mut command);
+        let value = |name: &str| {
+            command
+                .as_std()
+                .get_envs()
+                .filter(|(key, _)| *key == name)
+                .last()
+                .and_then(|(_, value)| value)
+                .map(|value| value.to_string_lossy().to_string())
+        };
+        assert_eq!(value(FORGE_ATTEMPT_ID_VAR), Some(attempt_id.to_string()));
+        assert_eq!(value(FORGE_TASK_ID_VAR), Some(task_id.to_string()));
+        assert_eq!(value(FORGE_PROJECT_ID_VAR), Some(project_id.to_string()));
+
+        // Identifiers are not secrets
+        let process_id = Uuid::new_v4();
+        env.register_redactions(process_id);
+        assert_eq!(
+            redact_output(process_id, &attempt_id.to_string()),
+            attempt_id.to_string()
+        );
+    }
+
+    #[test]
+    fn test_env_var_names() {
+        assert!(validate_env_var_name("DATABASE_URL").is_ok());
+        assert!(validate_env_var_name("_private").is_ok());
+        assert!(validate_env_var_name("1PASSWORD").is_err());
+        assert!(validate_env_var_name("WITH-DASH").is_err());
+        assert!(validate_env_var_name("").is_err());
+    }
+}
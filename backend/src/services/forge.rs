@@ -0,0 +1,598 @@
+//! Git forges pull requests are opened on.
+//!
+//! A project's forge is picked from its `origin` remote. GitHub, GitLab and
+//! Gitea (which Forgejo and Codeberg also speak) are supported. Self-hosted
+//! GitLab and Gitea instances are recognised by their host name, or by the
+//! kind of forge a user saved a token for that host as.
+//!
+//! Users keep one token per forge host in their secret scope. GitHub falls
+//! back to the install's token, as PRs always have.
+
+use std::{fmt, str::FromStr, time::Duration};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use thiserror::Error;
+use ts_rs::TS;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{
+    security::secret_store::{is_masked, user_scope, SecretStore, SecretStoreError},
+    services::{
+        gitea_service::GiteaService,
+        github_issues,
+        github_service::{CreatePrRequest, PullRequestInfo},
+        gitlab_service::GitLabService,
+        GitHubRepoInfo, GitHubService, GitHubServiceError, GitService, GitServiceError,
+    },
+};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Prefix of the names of users' forge tokens, followed by `<kind>.<host>`
+const TOKEN_SECRET_PREFIX: &str = "forge_token.";
+
+/// Longest API error body kept in error messages
+const ERROR_BODY_CHARS: usize = 300;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, TS, ToSchema)]
+#[serde(rename_all = "lowercase")]
+#[ts(export)]
+pub enum ForgeKind {
+    GitHub,
+    GitLab,
+    Gitea,
+}
+
+impl ForgeKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ForgeKind::GitHub => "github",
+            ForgeKind::GitLab => "gitlab",
+            ForgeKind::Gitea => "gitea",
+        }
+    }
+
+    /// The forge a host is known to run, from well-known hosts and names such
+    /// as `gitlab.example.com`
+    pub fn for_host(host: &str) -> Option<ForgeKind> {
+        let name = host.split(':').next().unwrap_or(host);
+        match name {
+            "github.com" => return Some(ForgeKind::GitHub),
+            "gitlab.com" => return Some(ForgeKind::GitLab),
+            "gitea.com" | "codeberg.org" => return Some(ForgeKind::Gitea),
+            _ => {}
+        }
+        let label = name.split('.').next().unwrap_or(name);
+        if label.contains("gitlab") {
+            Some(ForgeKind::GitLab)
+        } else if label.contains("gitea") || label.contains("forgejo") {
+            Some(ForgeKind::Gitea)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for ForgeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ForgeKind::GitHub => "GitHub",
+            ForgeKind::GitLab => "GitLab",
+            ForgeKind::Gitea => "Gitea",
+        })
+    }
+}
+
+impl FromStr for ForgeKind {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "github" => Ok(ForgeKind::GitHub),
+            "gitlab" => Ok(ForgeKind::GitLab),
+            "gitea" => Ok(ForgeKind::Gitea),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ForgeError {
+    #[error("Remote {0} is not hosted on a supported forge")]
+    UnsupportedRemote(String),
+    #[error("Unknown forge at {0}. Save a GitLab or Gitea token for this host to use it.")]
+    UnknownHost(String),
+    #[error("{0} authentication not configured for {1}")]
+    NotAuthenticated(ForgeKind, String),
+    #[error("{0} token is invalid or expired.")]
+    TokenInvalid(ForgeKind),
+    #[error("Invalid forge host: {0}")]
+    InvalidHost(String),
+    #[error("A new token is required")]
+    MissingToken,
+    #[error("{kind} API error ({status}): {message}")]
+    Api {
+        kind: ForgeKind,
+        status: u16,
+        message: String,
+    },
+    #[error(transparent)]
+    GitHub(GitHubServiceError),
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+    #[error(transparent)]
+    Git(#[from] GitServiceError),
+    #[error(transparent)]
+    SecretStore(#[from] SecretStoreError),
+}
+
+impl From<GitHubServiceError> for ForgeError {
+    fn from(err: GitHubServiceError) -> Self {
+        match err {
+            GitHubServiceError::TokenInvalid => ForgeError::TokenInvalid(ForgeKind::GitHub),
+            err => ForgeError::GitHub(err),
+        }
+    }
+}
+
+/// A repository on a forge
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForgeRepo {
+    pub kind: ForgeKind,
+    /// Host name, with the port of HTTP(S) remotes
+    pub host: String,
+    /// Scheme and host the forge's web UI and API are served from
+    pub base_url: String,
+    /// Owner, or GitLab namespace including subgroups
+    pub owner: String,
+    pub name: String,
+}
+
+impl ForgeRepo {
+    pub fn github_info(&self) -> GitHubRepoInfo {
+        GitHubRepoInfo {
+            owner: self.owner.clone(),
+            repo_name: self.name.clone(),
+        }
+    }
+}
+
+/// A git remote URL split into where it is hosted and which repository it is
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteUrl {
+    pub host: String,
+    pub base_url: String,
+    pub owner: String,
+    pub name: String,
+}
+
+impl RemoteUrl {
+    /// Parse HTTP(S), `ssh://` and scp-like (`git@host:owner/repo.git`)
+    /// remotes. SSH remotes are assumed to serve HTTPS on the same host.
+    pub fn parse(url: &str) -> Option<RemoteUrl> {
+        let url = url.trim();
+        let (scheme, authority, path) = match url.split_once("://") {
+            Some((scheme, rest)) => {
+                let (authority, path) = rest.split_once('/')?;
+                (scheme, authority, path)
+            }
+            None => {
+                let (authority, path) = url.split_once(':')?;
+                if authority.contains('/') {
+                    return None;
+                }
+                ("ssh", authority, path)
+            }
+        };
+        let host = authority
+            .rsplit_once('@')
+            .map_or(authority, |(_, host)| host)
+            .to_ascii_lowercase();
+        let (scheme, host) = match scheme {
+            "http" | "https" => (scheme, host),
+            // The SSH port says nothing about where HTTPS is served
+            "ssh" | "git" => ("https", host.split(':').next()?.to_string()),
+            _ => return None,
+        };
+
+        let path = path.trim_matches('/');
+        let path = path.strip_suffix(".git").unwrap_or(path);
+        let (owner, name) = path.rsplit_once('/')?;
+        if host.is_empty() || owner.is_empty() || name.is_empty() {
+            return None;
+        }
+        Some(RemoteUrl {
+            base_url: format!("{}://{}", scheme, host),
+            host,
+            owner: owner.to_string(),
+            name: name.to_string(),
+        })
+    }
+
+    /// The HTTP(S) URL to push to with a token
+    pub fn push_url(&self) -> String {
+        format!("{}/{}/{}.git", self.base_url, self.owner, self.name)
+    }
+
+    pub fn into_repo(self, kind: ForgeKind) -> ForgeRepo {
+        ForgeRepo {
+            kind,
+            host: self.host,
+            base_url: self.base_url,
+            owner: self.owner,
+            name: self.name,
+        }
+    }
+}
+
+/// An unresolved review comment on a pull request
+#[derive(Debug, Clone, Serialize, TS, ToSchema)]
+#[ts(export)]
+pub struct ForgeReviewComment {
+    #[ts(type = "number")]
+    pub id: i64,
+    pub author: String,
+    pub body: String,
+    pub path: Option<String>,
+    #[ts(type = "number | null")]
+    pub line: Option<i64>,
+    pub url: Option<String>,
+}
+
+/// Pull request operations every forge supports. GitLab calls them merge
+/// requests; their numbers are the project-scoped IIDs.
+#[async_trait]
+pub trait Forge: Send + Sync {
+    async fn create_pull_request(
+        &self,
+        request: &CreatePrRequest,
+    ) -> Result<PullRequestInfo, ForgeError>;
+
+    async fn pull_request_status(&self, number: i64) -> Result<PullRequestInfo, ForgeError>;
+
+    async fn review_comments(&self, number: i64) -> Result<Vec<ForgeReviewComment>, ForgeError>;
+}
+
+struct GitHubForge {
+    service: GitHubService,
+    repo_info: GitHubRepoInfo,
+}
+
+#[async_trait]
+impl Forge for GitHubForge {
+    async fn create_pull_request(
+        &self,
+        request: &CreatePrRequest,
+    ) -> Result<PullRequestInfo, ForgeError> {
+        Ok(self.service.create_pr(&self.repo_info, request).await?)
+    }
+
+    async fn pull_request_status(&self, number: i64) -> Result<PullRequestInfo, ForgeError> {
+        Ok(self
+            .service
+            .update_pr_status(&self.repo_info, number)
+            .await?)
+    }
+
+    async fn review_comments(&self, number: i64) -> Result<Vec<ForgeReviewComment>, ForgeError> {
+        let threads = self
+            .service
+            .list_unresolved_review_threads(&self.repo_info, number)
+            .await?;
+        Ok(threads
+            .into_iter()
+            .flat_map(|thread| {
+                let (path, line) = (thread.path, thread.line);
+                thread
+                    .comments
+                    .into_iter()
+                    .map(move |comment| ForgeReviewComment {
+                        id: comment.id,
+                        author: comment.author,
+                        body: comment.body,
+                        path: Some(path.clone()),
+                        line,
+                        url: Some(comment.url),
+                    })
+            })
+            .collect())
+    }
+}
+
+/// A client for the repository's forge
+pub fn connect(repo: &ForgeRepo, token: &str) -> Result<Box<dyn Forge>, ForgeError> {
+    Ok(match repo.kind {
+        ForgeKind::GitHub => Box::new(GitHubForge {
+            service: GitHubService::new(token)?,
+            repo_info: repo.github_info(),
+        }),
+        ForgeKind::GitLab => Box::new(GitLabService::new(repo, token)),
+        ForgeKind::Gitea => Box::new(GiteaService::new(repo, token)),
+    })
+}
+
+pub(crate) fn http_client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .unwrap_or_default()
+}
+
+/// Decode a forge API response, turning error statuses into `ForgeError`s
+pub(crate) async fn read_json<T: DeserializeOwned>(
+    kind: ForgeKind,
+    response: reqwest::Response,
+) -> Result<T, ForgeError> {
+    let status = response.status();
+    if status == reqwest::StatusCode::UNAUTHORIZED || status == reqwest::StatusCode::FORBIDDEN {
+        return Err(ForgeError::TokenInvalid(kind));
+    }
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(ForgeError::Api {
+            kind,
+            status: status.as_u16(),
+            message: body.chars().take(ERROR_BODY_CHARS).collect(),
+        });
+    }
+    Ok(response.json().await?)
+}
+
+fn token_secret_name(kind: ForgeKind, host: &str) -> String {
+    format!("{}{}.{}", TOKEN_SECRET_PREFIX, kind.as_str(), host)
+}
+
+/// The forge kind and host a token secret name stands for
+fn parse_token_secret_name(name: &str) -> Option<(ForgeKind, &str)> {
+    let (kind, host) = name.strip_prefix(TOKEN_SECRET_PREFIX)?.split_once('.')?;
+    Some((kind.parse().ok()?, host))
+}
+
+/// Hosts are stored lowercase, with an optional port
+pub fn normalize_host(host: &str) -> Result<String, ForgeError> {
+    let host = host.trim().trim_end_matches('/').to_ascii_lowercase();
+    let host = host
+        .strip_prefix("https://")
+        .or_else(|| host.strip_prefix("http://"))
+        .unwrap_or(&host)
+        .to_string();
+    let valid_chars = host
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '.' | '-' | ':'));
+    if host.is_empty() || host.len() > 253 || !valid_chars {
+        return Err(ForgeError::InvalidHost(host));
+    }
+    Ok(host)
+}
+
+/// A user's saved forge token, masked
+#[derive(Debug, Clone, Serialize, TS, ToSchema)]
+#[ts(export)]
+pub struct ForgeTokenSummary {
+    pub kind: ForgeKind,
+    pub host: String,
+    pub masked_value: String,
+    #[ts(type = "Date")]
+    #[schema(value_type = String, format = DateTime)]
+    pub updated_at: DateTime<Utc>,
+}
+
+/// `host` is a name such as `gitlab.example.com`, with the port if not 443
+#[derive(Debug, Deserialize, TS, ToSchema)]
+#[ts(export)]
+pub struct SaveForgeToken {
+    pub kind: ForgeKind,
+    pub host: String,
+    pub token: String,
+}
+
+pub async fn list_user_tokens(
+    store: &SecretStore,
+    user_id: Uuid,
+) -> Result<Vec<ForgeTokenSummary>, ForgeError> {
+    Ok(store
+        .list_masked(&user_scope(user_id))
+        .await?
+        .into_iter()
+        .filter_map(|secret| {
+            let (kind, host) = parse_token_secret_name(&secret.name)?;
+            Some(ForgeTokenSummary {
+                kind,
+                host: host.to_string(),
+                masked_value: secret.masked_value,
+                updated_at: secret.updated_at,
+            })
+        })
+        .collect())
+}
+
+/// Store a user's token for a forge host, replacing the one saved for it
+/// under any other kind
+pub async fn save_user_token(
+    store: &SecretStore,
+    user_id: Uuid,
+    request: &SaveForgeToken,
+) -> Result<ForgeTokenSummary, ForgeError> {
+    let host = normalize_host(&request.host)?;
+    if let Some(known) = ForgeKind::for_host(&host).filter(|known| *known != request.kind) {
+        return Err(ForgeError::InvalidHost(format!(
+            "{} is a {} host",
+            host, known
+        )));
+    }
+    let token = request.token.trim();
+    if token.is_empty() || is_masked(token) {
+        return Err(ForgeError::MissingToken);
+    }
+
+    let scope = user_scope(user_id);
+    for kind in [ForgeKind::GitHub, ForgeKind::GitLab, ForgeKind::Gitea] {
+        if kind != request.kind {
+            store
+                .delete(&scope, &token_secret_name(kind, &host))
+                .await?;
+        }
+    }
+    store
+        .put(&scope, &token_secret_name(request.kind, &host), token)
+        .await?;
+
+    list_user_tokens(store, user_id)
+        .await?
+        .into_iter()
+        .find(|summary| summary.host == host)
+        .ok_or(ForgeError::NotAuthenticated(request.kind, host))
+}
+
+/// Forget a user's token for a host. Returns whether one was saved.
+pub async fn delete_user_token(
+    store: &SecretStore,
+    user_id: Uuid,
+    host: &str,
+) -> Result<bool, ForgeError> {
+    let host = normalize_host(host)?;
+    let scope = user_scope(user_id);
+    let mut deleted = false;
+    for kind in [ForgeKind::GitHub, ForgeKind::GitLab, ForgeKind::Gitea] {
+        deleted |= store
+            .delete(&scope, &token_secret_name(kind, &host))
+            .await?;
+    }
+    Ok(deleted)
+}
+
+/// The repository behind a project's `origin` remote. Hosts that aren't
+/// recognised take the kind `user_id` saved a token for them as.
+pub async fn project_repo(
+    store: &SecretStore,
+    git_repo_path: &str,
+    user_id: Option<Uuid>,
+) -> Result<ForgeRepo, ForgeError> {
+    let url = GitService::new(git_repo_path)?.origin_url()?;
+    let remote = RemoteUrl::parse(&url).ok_or(ForgeError::UnsupportedRemote(url))?;
+    if let Some(kind) = ForgeKind::for_host(&remote.host) {
+        return Ok(remote.into_repo(kind));
+    }
+
+    let saved = match user_id {
+        Some(user_id) => list_user_tokens(store, user_id).await?,
+        None => Vec::new(),
+    };
+    match saved.iter().find(|token| token.host == remote.host) {
+        Some(token) => Ok(remote.into_repo(token.kind)),
+        None => Err(ForgeError::UnknownHost(remote.host)),
+    }
+}
+
+/// The token to call the forge with for `user_id`: their own for the host,
+/// else the install's for GitHub
+pub async fn token(
+    store: &SecretStore,
+    user_id: Option<Uuid>,
+    repo: &ForgeRepo,
+) -> Result<String, ForgeError> {
+    if let Some(user_id) = user_id {
+        let name = token_secret_name(repo.kind, &repo.host);
+        if let Some(token) = store.get(&user_scope(user_id), &name).await? {
+            return Ok(token.as_str().to_string());
+        }
+    }
+    let install_token = match repo.kind {
+        ForgeKind::GitHub => github_issues::install_token(store).await,
+        ForgeKind::GitLab | ForgeKind::Gitea => None,
+    };
+    install_token.ok_or_else(|| ForgeError::NotAuthenticated(repo.kind, repo.host.clone()))
+}
+
+/// The project's repository and the token to use on it
+pub async fn credentials(
+    store: &SecretStore,
+    git_repo_path: &str,
+    user_id: Option<Uuid>,
+) -> Result<(ForgeRepo, String), ForgeError> {
+    let repo = project_repo(store, git_repo_path, user_id).await?;
+    let token = token(store, user_id, &repo).await?;
+    Ok((repo, token))
+}
+
+#[cfg(test)]
+pub(crate) mod test_server {
+    use axum::Router;
+
+    /// Serve a mock forge API on a local port, returning its base URL
+    pub async fn serve(router: Router) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, router).await.unwrap();
+        });
+        format!("http://{}", address)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_remote_urls() {
+        let scp = RemoteUrl::parse("git@gitlab.com:group/sub/app.git").unwrap();
+        assert_eq!(scp.base_url, "https://gitlab.com");
+        assert_eq!(scp.owner, "group/sub");
+        assert_eq!(scp.name, "app");
+        assert_eq!(scp.push_url(), "https://gitlab.com/group/sub/app.git");
+
+        let ssh = RemoteUrl::parse("ssh://git@Git.Example.com:2222/team/app.git").unwrap();
+        assert_eq!(ssh.host, "git.example.com");
+        assert_eq!(ssh.base_url, "https://git.example.com");
+
+        let http = RemoteUrl::parse("http://user:pw@127.0.0.1:3000/team/app/").unwrap();
+        assert_eq!(http.host, "127.0.0.1:3000");
+        assert_eq!(http.base_url, "http://127.0.0.1:3000");
+        assert_eq!((http.owner.as_str(), http.name.as_str()), ("team", "app"));
+
+        assert!(RemoteUrl::parse("/srv/git/app.git").is_none());
+        assert!(RemoteUrl::parse("https://github.com/app").is_none());
+        assert!(RemoteUrl::parse("file:///srv/git/team/app.git").is_none());
+    }
+
+    #[test]
+    fn test_forge_kind_for_host() {
+        assert_eq!(ForgeKind::for_host("github.com"), Some(ForgeKind::GitHub));
+        assert_eq!(ForgeKind::for_host("gitlab.com"), Some(ForgeKind::GitLab));
+        assert_eq!(
+            ForgeKind::for_host("gitlab.corp.example:8443"),
+            Some(ForgeKind::GitLab)
+        );
+        assert_eq!(ForgeKind::for_host("codeberg.org"), Some(ForgeKind::Gitea));
+        assert_eq!(
+            ForgeKind::for_host("forgejo.example.org"),
+            Some(ForgeKind::Gitea)
+        );
+        assert_eq!(ForgeKind::for_host("git.example.com"), None);
+        // Only the leftmost label names the software
+        assert_eq!(ForgeKind::for_host("code.gitlab-mirror.example"), None);
+    }
+
+    #[test]
+    fn test_token_secret_names() {
+        let name = token_secret_name(ForgeKind::Gitea, "git.example.com:3000");
+        assert_eq!(name, "forge_token.gitea.git.example.com:3000");
+        assert_eq!(
+            parse_token_secret_name(&name),
+            Some((ForgeKind::Gitea, "git.example.com:3000"))
+        );
+        assert_eq!(parse_token_secret_name("github_token"), None);
+        assert_eq!(parse_token_secret_name("forge_token.svn.example.com"), None);
+
+        assert_eq!(
+            normalize_host(" https://GitLab.Example.com/ ").unwrap(),
+            "gitlab.example.com"
+        );
+        assert!(normalize_host("gitlab.example.com/group").is_err());
+        assert!(normalize_host("").is_err());
+    }
+}
@@ -9,6 +9,7 @@ use tracing::{debug, info};
 
 use crate::{
     models::task_attempt::{DiffChunk, DiffChunkType, FileDiff, WorktreeDiff},
+    services::forge::RemoteUrl,
     utils::worktree_manager::WorktreeManager,
 };
 
@@ -477,6 +478,45 @@ impl GitService {
         Ok(WorktreeDiff { files })
     }
 
+    /// The worktree's committed changes since it branched off `base_branch`,
+    /// squashed into one unified patch
+    pub fn get_squashed_patch(
+        &self,
+        worktree_path: &Path,
+        base_branch: &str,
+    ) -> Result<String, GitServiceError> {
+        let worktree_repo = Repository::open(worktree_path)?;
+        let main_repo = self.open_repo()?;
+
+        let base_branch_oid = main_repo
+            .find_branch(base_branch, BranchType::Local)
+            .map_err(|_| GitServiceError::BranchNotFound(base_branch.to_string()))?
+            .get()
+            .peel_to_commit()?
+            .id();
+        let head_commit = worktree_repo.head()?.peel_to_commit()?;
+        let base_oid = worktree_repo.merge_base(base_branch_oid, head_commit.id())?;
+        let base_tree = worktree_repo.find_commit(base_oid)?.tree()?;
+
+        let mut diff_opts = DiffOptions::new();
+        diff_opts.context_lines(3);
+        let diff = worktree_repo.diff_tree_to_tree(
+            Some(&base_tree),
+            Some(&head_commit.tree()?),
+            Some(&mut diff_opts),
+        )?;
+
+        let mut patch = String::new();
+        diff.print(git2::DiffFormat::Patch, |_delta, _hunk, line| {
+            if matches!(line.origin(), '+' | '-' | ' ') {
+                patch.push(line.origin());
+            }
+            patch.push_str(&String::from_utf8_lossy(line.content()));
+            true
+        })?;
+        Ok(patch)
+    }
+
     /// Get diff from a merge commit
     fn get_merged_diff(
         &self,
@@ -1069,20 +1109,13 @@ impl GitService {
 
     /// Extract GitHub owner and repo name from git repo path
     pub fn get_github_repo_info(&self) -> Result<(String, String), GitServiceError> {
-        let repo = self.open_repo()?;
-        let remote = repo.find_remote("origin").map_err(|_| {
-            GitServiceError::InvalidRepository("No 'origin' remote found".to_string())
-        })?;
-
-        let url = remote.url().ok_or_else(|| {
-            GitServiceError::InvalidRepository("Remote origin has no URL".to_string())
-        })?;
+        let url = self.origin_url()?;
 
         // Parse GitHub URL (supports both HTTPS and SSH formats)
         let github_regex = regex::Regex::new(r"github\.com[:/]([^/]+)/(.+?)(?:\.git)?/?$")
             .map_err(|e| GitServiceError::InvalidRepository(format!("Regex error: {}", e)))?;
 
-        if let Some(captures) = github_regex.captures(url) {
+        if let Some(captures) = github_regex.captures(&url) {
             let owner = captures.get(1).unwrap().as_str().to_string();
             let repo_name = captures.get(2).unwrap().as_str().to_string();
             Ok((owner, repo_name))
@@ -1094,12 +1127,25 @@ impl GitService {
         }
     }
 
-    /// Push the branch to GitHub remote
-    pub fn push_to_github(
+    /// URL of the repository's `origin` remote
+    pub fn origin_url(&self) -> Result<String, GitServiceError> {
+        let repo = self.open_repo()?;
+        let remote = repo.find_remote("origin").map_err(|_| {
+            GitServiceError::InvalidRepository("No 'origin' remote found".to_string())
+        })?;
+
+        remote.url().map(str::to_string).ok_or_else(|| {
+            GitServiceError::InvalidRepository("Remote origin has no URL".to_string())
+        })
+    }
+
+    /// Push the branch to the origin's forge over HTTP(S), authenticating
+    /// with a token
+    pub fn push_to_remote(
         &self,
         worktree_path: &Path,
         branch_name: &str,
-        github_token: &str,
+        token: &str,
     ) -> Result<(), GitServiceError> {
         let repo = Repository::open(worktree_path)?;
 
@@ -1109,16 +1155,11 @@ impl GitService {
             GitServiceError::InvalidRepository("Remote origin has no URL".to_string())
         })?;
 
-        // Convert SSH URL to HTTPS URL if necessary
-        let https_url = if remote_url.starts_with("git@github.com:") {
-            // Convert git@github.com:owner/repo.git to https://github.com/owner/repo.git
-            remote_url.replace("git@github.com:", "https://github.com/")
-        } else if remote_url.starts_with("ssh://git@github.com/") {
-            // Convert ssh://git@github.com/owner/repo.git to https://github.com/owner/repo.git
-            remote_url.replace("ssh://git@github.com/", "https://github.com/")
-        } else {
-            remote_url.to_string()
-        };
+        // Tokens only work over HTTP(S), so SSH remotes are pushed to through
+        // the forge's HTTPS URL
+        let https_url = RemoteUrl::parse(remote_url)
+            .map(|remote| remote.push_url())
+            .unwrap_or_else(|| remote_url.to_string());
 
         // Create a temporary remote with HTTPS URL for pushing
         let temp_remote_name = "temp_https_origin";
@@ -1132,10 +1173,10 @@ impl GitService {
         // Create refspec for pushing the branch
         let refspec = format!("refs/heads/{}:refs/heads/{}", branch_name, branch_name);
 
-        // Set up authentication callback using the GitHub token
+        // Set up authentication callback using the token
         let mut callbacks = git2::RemoteCallbacks::new();
         callbacks.credentials(|_url, username_from_url, _allowed_types| {
-            git2::Cred::userpass_plaintext(username_from_url.unwrap_or("git"), github_token)
+            git2::Cred::userpass_plaintext(username_from_url.unwrap_or("git"), token)
         });
 
         // Configure push options
@@ -1151,7 +1192,7 @@ impl GitService {
         // Check push result
         push_result?;
 
-        info!("Pushed branch {} to GitHub using HTTPS", branch_name);
+        info!("Pushed branch {} using HTTPS", branch_name);
         Ok(())
     }
 
@@ -0,0 +1,327 @@
+//! Gitea pull requests through the REST API (v1). Forgejo, and with it
+//! Codeberg, serves the same API.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+use crate::services::{
+    forge::{self, Forge, ForgeError, ForgeKind, ForgeRepo, ForgeReviewComment},
+    github_service::{CreatePrRequest, PullRequestInfo},
+};
+
+/// Reviews fetched per pull request; later ones are not looked at
+const REVIEWS_PER_PAGE: u32 = 50;
+
+pub struct GiteaService {
+    client: reqwest::Client,
+    /// `.../api/v1/repos/<owner>/<repo>`
+    repo_url: String,
+    token: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct PullRequest {
+    number: i64,
+    html_url: String,
+    /// `open` or `closed`; merged pull requests are closed
+    state: String,
+    #[serde(default)]
+    merged: bool,
+    merged_at: Option<DateTime<Utc>>,
+    merge_commit_sha: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Review {
+    id: i64,
+    #[serde(default)]
+    comments_count: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReviewComment {
+    id: i64,
+    body: String,
+    user: Option<CommentUser>,
+    path: Option<String>,
+    /// Line in the PR's head; 0 for comments on removed lines
+    #[serde(default)]
+    position: i64,
+    html_url: Option<String>,
+    resolver: Option<CommentUser>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CommentUser {
+    login: String,
+}
+
+impl PullRequest {
+    fn into_info(self) -> PullRequestInfo {
+        let status = if self.merged {
+            "merged"
+        } else if self.state == "open" {
+            "open"
+        } else {
+            "closed"
+        };
+        PullRequestInfo {
+            number: self.number,
+            url: self.html_url,
+            status: status.to_string(),
+            merged: self.merged,
+            merged_at: self.merged_at,
+            merge_commit_sha: self.merge_commit_sha,
+        }
+    }
+}
+
+impl GiteaService {
+    pub fn new(repo: &ForgeRepo, token: &str) -> Self {
+        Self {
+            client: forge::http_client(),
+            repo_url: format!(
+                "{}/api/v1/repos/{}/{}",
+                repo.base_url,
+                urlencoding::encode(&repo.owner),
+                urlencoding::encode(&repo.name)
+            ),
+            token: token.to_string(),
+        }
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        self.client
+            .request(method, format!("{}{}", self.repo_url, path))
+            .header("Authorization", format!("token {}", self.token))
+    }
+}
+
+#[async_trait]
+impl Forge for GiteaService {
+    async fn create_pull_request(
+        &self,
+        request: &CreatePrRequest,
+    ) -> Result<PullRequestInfo, ForgeError> {
+        let response = self
+            .request(reqwest::Method::POST, "/pulls")
+            .json(&serde_json::json!({
+                "head": request.head_branch,
+                "base": request.base_branch,
+                "title": request.title,
+                "body": request.body.clone().unwrap_or_default(),
+            }))
+            .send()
+            .await?;
+        let pull_request: PullRequest = forge::read_json(ForgeKind::Gitea, response).await?;
+        tracing::info!(
+            "Created Gitea pull request #{} for branch {}",
+            pull_request.number,
+            request.head_branch
+        );
+        Ok(pull_request.into_info())
+    }
+
+    async fn pull_request_status(&self, number: i64) -> Result<PullRequestInfo, ForgeError> {
+        let response = self
+            .request(reqwest::Method::GET, &format!("/pulls/{}", number))
+            .send()
+            .await?;
+        let pull_request: PullRequest = forge::read_json(ForgeKind::Gitea, response).await?;
+        Ok(pull_request.into_info())
+    }
+
+    async fn review_comments(&self, number: i64) -> Result<Vec<ForgeReviewComment>, ForgeError> {
+        let response = self
+            .request(reqwest::Method::GET, &format!("/pulls/{}/reviews", number))
+            .query(&[("limit", REVIEWS_PER_PAGE)])
+            .send()
+            .await?;
+        let reviews: Vec<Review> = forge::read_json(ForgeKind::Gitea, response).await?;
+
+        let mut comments = Vec::new();
+        for review in reviews.iter().filter(|review| review.comments_count > 0) {
+            let response = self
+                .request(
+                    reqwest::Method::GET,
+                    &format!("/pulls/{}/reviews/{}/comments", number, review.id),
+                )
+                .send()
+                .await?;
+            let review_comments: Vec<ReviewComment> =
+                forge::read_json(ForgeKind::Gitea, response).await?;
+            comments.extend(
+                review_comments
+                    .into_iter()
+                    .filter(|comment| comment.resolver.is_none())
+                    .map(|comment| ForgeReviewComment {
+                        id: comment.id,
+                        author: comment.user.map(|user| user.login).unwrap_or_default(),
+                        body: comment.body,
+                        path: comment.path,
+                        line: Some(comment.position).filter(|line| *line > 0),
+                        url: comment.html_url,
+                    }),
+            );
+        }
+        Ok(comments)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use axum::{
+        extract::Path,
+        http::{HeaderMap, StatusCode},
+        routing::{get, post},
+        Json, Router,
+    };
+
+    use super::*;
+    use crate::services::forge::test_server;
+
+    fn repo(base_url: String) -> ForgeRepo {
+        ForgeRepo {
+            kind: ForgeKind::Gitea,
+            host: "git.example.com".to_string(),
+            base_url,
+            owner: "team".to_string(),
+            name: "app".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_creates_pull_request_and_reads_its_state() {
+        let created = Arc::new(Mutex::new(None));
+        let recorded = created.clone();
+        let router = Router::new()
+            .route(
+                "/api/v1/repos/team/app/pulls",
+                post(
+                    move |headers: HeaderMap, Json(body): Json<serde_json::Value>| async move {
+                        *recorded.lock().unwrap() =
+                            Some((headers["authorization"].to_str().unwrap().to_string(), body));
+                        (
+                            StatusCode::CREATED,
+                            Json(serde_json::json!({
+                                "number": 5,
+                                "html_url": "https://git.example.com/team/app/pulls/5",
+                                "state": "open",
+                                "merged": false,
+                            })),
+                        )
+                    },
+                ),
+            )
+            .route(
+                "/api/v1/repos/team/app/pulls/:number",
+                get(|Path(number): Path<i64>| async move {
+                    Json(serde_json::json!({
+                        "number": number,
+                        "html_url": "https://git.example.com/team/app/pulls/5",
+                        "state": "closed",
+                        "merged": true,
+                        "merged_at": "2025-08-15T10:00:00Z",
+                        "merge_commit_sha": "def456",
+                    }))
+                }),
+            );
+        let gitea = GiteaService::new(&repo(test_server::serve(router).await), "gitea-secret");
+
+        let info = gitea
+            .create_pull_request(&CreatePrRequest {
+                title: "Add login".to_string(),
+                body: None,
+                head_branch: "forge/add-login".to_string(),
+                base_branch: "main".to_string(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(info.number, 5);
+        assert_eq!(info.status, "open");
+        let (authorization, body) = created.lock().unwrap().take().unwrap();
+        assert_eq!(authorization, "token gitea-secret");
+        assert_eq!(body["head"], "forge/add-login");
+        assert_eq!(body["base"], "main");
+        assert_eq!(body["body"], "");
+
+        let status = gitea.pull_request_status(5).await.unwrap();
+        assert_eq!(status.status, "merged");
+        assert!(status.merged);
+        assert_eq!(status.merge_commit_sha.as_deref(), Some("def456"));
+    }
+
+    #[tokio::test]
+    async fn test_review_comments_skip_resolved_ones() {
+        let router = Router::new()
+            .route(
+                "/api/v1/repos/team/app/pulls/:number/reviews",
+                get(|| async {
+                    Json(serde_json::json!([
+                        { "id": 1, "comments_count": 2 },
+                        { "id": 2, "comments_count": 0 }
+                    ]))
+                }),
+            )
+            .route(
+                "/api/v1/repos/team/app/pulls/:number/reviews/:review/comments",
+                get(|Path((_, review)): Path<(i64, i64)>| async move {
+                    assert_eq!(review, 1);
+                    Json(serde_json::json!([
+                        {
+                            "id": 21, "body": "Handle the error", "user": { "login": "bo" },
+                            "path": "src/main.rs", "position": 12,
+                            "html_url": "https://git.example.com/team/app/pulls/5#issuecomment-21",
+                            "resolver": null
+                        },
+                        {
+                            "id": 22, "body": "Typo", "user": { "login": "bo" },
+                            "path": "README.md", "position": 3,
+                            "resolver": { "login": "ana" }
+                        }
+                    ]))
+                }),
+            );
+        let gitea = GiteaService::new(&repo(test_server::serve(router).await), "token");
+
+        let comments = gitea.review_comments(5).await.unwrap();
+        assert_eq!(comments.len(), 1);
+        assert_eq!(comments[0].id, 21);
+        assert_eq!(comments[0].author, "bo");
+        assert_eq!(comments[0].path.as_deref(), Some("src/main.rs"));
+        assert_eq!(comments[0].line, Some(12));
+    }
+
+    #[tokio::test]
+    async fn test_missing_pull_request() {
+        let router = Router::new().route(
+            "/api/v1/repos/team/app/pulls/:number",
+            get(|| async {
+                (
+                    StatusCode::NOT_FOUND,
+                    "{\"message\":\"pull request does not exist\"}",
+                )
+            }),
+        );
+        let gitea = GiteaService::new(&repo(test_server::serve(router).await), "token");
+
+        match gitea.pull_request_status(9).await {
+            Err(ForgeError::Api {
+                kind,
+                status,
+                message,
+            }) => {
+                assert_eq!(kind, ForgeKind::Gitea);
+                assert_eq!(status, 404);
+                assert!(message.contains("does not exist"));
+            }
+            other => panic!(
+                "expected an API error, got {:?}",
+                other.map(|info| info.status)
+            ),
+        }
+    }
+}
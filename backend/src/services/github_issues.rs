@@ -0,0 +1,436 @@
+//! GitHub issues as the backlog of a project.
+//!
+//! Open issues of the project's GitHub repository are imported as tasks by
+//! label or milestone, and importing again updates the tasks nobody started
+//! yet. The task keeps the issue number, so its pull requests close the issue
+//! and merging the task comments back on it.
+
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use thiserror::Error;
+use ts_rs::TS;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{
+    models::{
+        project::Project,
+        task::{CreateTask, Task, TaskStatus},
+    },
+    security::secret_store::{SecretStore, CONFIG_SCOPE},
+    services::{
+        github_service::{GitHubIssue, IssueFilter},
+        notification_rules, webhooks, GitHubRepoInfo, GitHubService, GitHubServiceError,
+        GitService, GitServiceError,
+    },
+};
+
+/// Labels starting with this name the wish of imported tasks unless the
+/// import asks for another prefix
+pub const DEFAULT_WISH_LABEL_PREFIX: &str = "wish:";
+
+/// Wish of imported issues without a wish label or any label at all
+const FALLBACK_WISH: &str = "github-issues";
+
+#[derive(Debug, Error)]
+pub enum GitHubIssuesError {
+    #[error("Pick a label or a milestone to import issues from")]
+    NoFilter,
+    #[error("GitHub authentication not configured")]
+    NotAuthenticated,
+    #[error("Milestone '{0}' not found")]
+    MilestoneNotFound(String),
+    #[error(transparent)]
+    GitHub(#[from] GitHubServiceError),
+    #[error(transparent)]
+    Git(#[from] GitServiceError),
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
+}
+
+/// Which open issues to import. Issues must carry every label and, if given,
+/// belong to the milestone.
+#[derive(Debug, Clone, Deserialize, TS, ToSchema)]
+#[ts(export)]
+pub struct ImportGitHubIssues {
+    #[serde(default)]
+    pub labels: Vec<String>,
+    /// Milestone title or number
+    pub milestone: Option<String>,
+    /// Prefix of the label naming a task's wish, "wish:" by default
+    pub wish_label_prefix: Option<String>,
+}
+
+#[derive(Debug, Serialize, TS, ToSchema)]
+#[ts(export)]
+pub struct GitHubIssueImport {
+    pub created: Vec<Task>,
+    /// Tasks not started yet whose issue changed
+    pub updated: Vec<Task>,
+    #[ts(type = "number")]
+    pub unchanged: i64,
+}
+
+/// A task imported from a GitHub issue
+#[derive(Debug, Serialize, TS, ToSchema)]
+#[ts(export)]
+pub struct LinkedGitHubIssue {
+    pub task_id: Uuid,
+    #[ts(type = "number")]
+    pub issue_number: i64,
+    pub issue_url: Option<String>,
+}
+
+/// The install's GitHub token, the PAT taking precedence like for PRs
+pub async fn install_token(store: &SecretStore) -> Option<String> {
+    for name in ["github.pat", "github.token"] {
+        match store.get(CONFIG_SCOPE, name).await {
+            Ok(Some(token)) if !token.as_str().is_empty() => {
+                return Some(token.as_str().to_string())
+            }
+            Ok(_) => {}
+            Err(e) => tracing::warn!("Failed to read {} from secret store: {}", name, e),
+        }
+    }
+    None
+}
+
+fn repo_info(project: &Project) -> Result<GitHubRepoInfo, GitServiceError> {
+    let (owner, repo_name) = GitService::new(&project.git_repo_path)?.get_github_repo_info()?;
+    Ok(GitHubRepoInfo { owner, repo_name })
+}
+
+/// The wish named by the first label with `prefix`, else the issue's first
+/// label
+pub fn wish_for_labels(labels: &[String], prefix: &str) -> String {
+    labels
+        .iter()
+        .find_map(|label| label.strip_prefix(prefix))
+        .map(str::trim)
+        .filter(|wish| !wish.is_empty())
+        .or_else(|| labels.first().map(|label| label.trim()))
+        .filter(|wish| !wish.is_empty())
+        .unwrap_or(FALLBACK_WISH)
+        .to_string()
+}
+
+/// Task description for an issue, linking back to it
+pub fn issue_description(issue: &GitHubIssue) -> String {
+    match issue
+        .body
+        .as_deref()
+        .map(str::trim)
+        .filter(|body| !body.is_empty())
+    {
+        Some(body) => format!("{}\n\nGitHub issue: {}", body, issue.url),
+        None => format!("GitHub issue: {}", issue.url),
+    }
+}
+
+/// PR body that closes the task's issue once merged, unless it already does
+pub fn with_closes_reference(body: Option<&str>, issue_number: Option<i64>) -> Option<String> {
+    let Some(number) = issue_number else {
+        return body.map(str::to_string);
+    };
+    let reference = format!("#{}", number);
+    let body = body.unwrap_or_default().trim_end();
+    let lower = body.to_ascii_lowercase();
+    let already_closes = [
+        "close", "closes", "closed", "fix", "fixes", "fixed", "resolve", "resolves", "resolved",
+    ]
+    .iter()
+    .any(|keyword| {
+        lower
+            .match_indices(&format!("{} {}", keyword, reference))
+            .any(|(at, matched)| {
+                !lower[at + matched.len()..].starts_with(|c: char| c.is_ascii_digit())
+            })
+    });
+    if already_closes {
+        return Some(body.to_string());
+    }
+    if body.is_empty() {
+        Some(format!("Closes {}", reference))
+    } else {
+        Some(format!("{}\n\nCloses {}", body, reference))
+    }
+}
+
+/// Import the open issues matching `request` as tasks of the project created
+/// by `created_by`, updating tasks imported before that were not started yet
+pub async fn import_issues(
+    pool: &SqlitePool,
+    secret_store: &SecretStore,
+    project: &Project,
+    created_by: Uuid,
+    request: &ImportGitHubIssues,
+) -> Result<GitHubIssueImport, GitHubIssuesError> {
+    let labels: Vec<String> = request
+        .labels
+        .iter()
+        .map(|label| label.trim().to_string())
+        .filter(|label| !label.is_empty())
+        .collect();
+    let milestone = request
+        .milestone
+        .as_deref()
+        .map(str::trim)
+        .filter(|milestone| !milestone.is_empty());
+    if labels.is_empty() && milestone.is_none() {
+        return Err(GitHubIssuesError::NoFilter);
+    }
+
+    let token = install_token(secret_store)
+        .await
+        .ok_or(GitHubIssuesError::NotAuthenticated)?;
+    let github_service = GitHubService::new(&token)?;
+    let repo_info = repo_info(project)?;
+    let milestone_number = match milestone {
+        Some(milestone) => Some(match milestone.trim_start_matches('#').parse::<i64>() {
+            Ok(number) => number,
+            Err(_) => github_service
+                .find_milestone_number(&repo_info, milestone)
+                .await?
+                .ok_or_else(|| GitHubIssuesError::MilestoneNotFound(milestone.to_string()))?,
+        }),
+        None => None,
+    };
+    let issues = github_service
+        .list_issues(
+            &repo_info,
+            &IssueFilter {
+                labels,
+                milestone_number,
+            },
+        )
+        .await?;
+
+    let prefix = request
+        .wish_label_prefix
+        .as_deref()
+        .unwrap_or(DEFAULT_WISH_LABEL_PREFIX);
+    let mut result = GitHubIssueImport {
+        created: Vec::new(),
+        updated: Vec::new(),
+        unchanged: 0,
+    };
+    for issue in issues {
+        let description = issue_description(&issue);
+        if let Some(task) =
+            Task::find_by_github_issue_number(pool, project.id, issue.number).await?
+        {
+            let changed = task.title != issue.title
+                || task.description.as_deref() != Some(description.as_str());
+            if !changed || task.status != TaskStatus::Todo {
+                result.unchanged += 1;
+                continue;
+            }
+            let task = Task::update(
+                pool,
+                task.id,
+                project.id,
+                issue.title,
+                Some(description),
+                task.status,
+                task.wish_id,
+                task.parent_task_attempt,
+                task.assigned_to,
+            )
+            .await?;
+            result.updated.push(task);
+            continue;
+        }
+
+        let mut tx = pool.begin().await?;
+        let task = Task::create(
+            &mut *tx,
+            &CreateTask {
+                project_id: project.id,
+                title: issue.title.clone(),
+                description: Some(description),
+                wish_id: wish_for_labels(&issue.labels, prefix),
+                parent_task_attempt: None,
+                created_by: Some(created_by),
+                assigned_to: None,
+            },
+            Uuid::new_v4(),
+        )
+        .await?;
+        Task::set_github_issue_number(&mut *tx, task.id, Some(issue.number)).await?;
+        tx.commit().await?;
+
+        webhooks::emit_task_created(pool, &task).await;
+        result.created.push(task);
+    }
+
+    tracing::info!(
+        "Imported GitHub issues of {}/{} into project {}: {} created, {} updated, {} unchanged",
+        repo_info.owner,
+        repo_info.repo_name,
+        project.id,
+        result.created.len(),
+        result.updated.len(),
+        result.unchanged
+    );
+    Ok(result)
+}
+
+/// The project's tasks imported from GitHub issues
+pub async fn linked_issues(
+    pool: &SqlitePool,
+    project: &Project,
+) -> Result<Vec<LinkedGitHubIssue>, GitHubIssuesError> {
+    let repo_info = repo_info(project).ok();
+    Ok(
+        Task::find_github_issue_numbers_by_project_id(pool, project.id)
+            .await?
+            .into_iter()
+            .map(|(task_id, issue_number)| LinkedGitHubIssue {
+                task_id,
+                issue_number,
+                issue_url: repo_info.as_ref().map(|repo| {
+                    format!(
+                        "https://github.com/{}/{}/issues/{}",
+                        repo.owner, repo.repo_name, issue_number
+                    )
+                }),
+            })
+            .collect(),
+    )
+}
+
+/// How a task merged locally was merged, for `comment_on_merge`
+pub fn merged_into(base_branch: &str, merge_commit: &str) -> String {
+    format!(
+        "into `{}` as {}",
+        base_branch,
+        &merge_commit[..merge_commit.len().min(7)]
+    )
+}
+
+/// Tell the GitHub issue the task was imported from, if any, that the task
+/// was merged. `merged` completes "Merged by Forge …".
+pub async fn comment_on_merge(
+    pool: &SqlitePool,
+    secret_store: &SecretStore,
+    task: &Task,
+    merged: &str,
+) {
+    let issue_number = match Task::github_issue_number(pool, task.id).await {
+        Ok(Some(number)) => number,
+        Ok(None) => return,
+        Err(e) => {
+            tracing::error!("Failed to read the GitHub issue of task {}: {}", task.id, e);
+            return;
+        }
+    };
+    let Some(token) = install_token(secret_store).await else {
+        tracing::debug!(
+            "No GitHub token configured, not commenting on issue #{}",
+            issue_number
+        );
+        return;
+    };
+
+    let project = match Project::find_by_id(pool, task.project_id).await {
+        Ok(Some(project)) => project,
+        Ok(None) => return,
+        Err(e) => {
+            tracing::error!("Failed to load project {}: {}", task.project_id, e);
+            return;
+        }
+    };
+
+    let result = async {
+        let body = format!(
+            "Merged by Forge {}.\n\nTask: {}",
+            merged,
+            notification_rules::task_url(task.project_id, task.id)
+        );
+        GitHubService::new(&token)?
+            .create_issue_comment(&repo_info(&project)?, issue_number, &body)
+            .await?;
+        Ok::<(), GitHubIssuesError>(())
+    }
+    .await;
+    if let Err(e) = result {
+        tracing::warn!(
+            "Failed to comment on GitHub issue #{} of task {}: {}",
+            issue_number,
+            task.id,
+            e
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labels(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn test_wish_for_labels() {
+        assert_eq!(
+            wish_for_labels(&labels(&["bug", "wish: auth"]), DEFAULT_WISH_LABEL_PREFIX),
+            "auth"
+        );
+        assert_eq!(wish_for_labels(&labels(&["bug", "area/ui"]), "area/"), "ui");
+        assert_eq!(
+            wish_for_labels(&labels(&["bug", "backend"]), DEFAULT_WISH_LABEL_PREFIX),
+            "bug"
+        );
+        assert_eq!(
+            wish_for_labels(&[], DEFAULT_WISH_LABEL_PREFIX),
+            FALLBACK_WISH
+        );
+    }
+
+    #[test]
+    fn test_issue_description_links_the_issue() {
+        let mut issue = GitHubIssue {
+            number: 7,
+            title: "Crash on start".to_string(),
+            body: Some("Steps to reproduce\n".to_string()),
+            url: "https://github.com/acme/app/issues/7".to_string(),
+            labels: Vec::new(),
+        };
+        assert_eq!(
+            issue_description(&issue),
+            "Steps to reproduce\n\nGitHub issue: https://github.com/acme/app/issues/7"
+        );
+        issue.body = None;
+        assert_eq!(
+            issue_description(&issue),
+            "GitHub issue: https://github.com/acme/app/issues/7"
+        );
+    }
+
+    #[test]
+    fn test_with_closes_reference() {
+        assert_eq!(
+            with_closes_reference(Some("Body"), None).as_deref(),
+            Some("Body")
+        );
+        assert_eq!(with_closes_reference(None, None), None);
+        assert_eq!(
+            with_closes_reference(None, Some(7)).as_deref(),
+            Some("Closes #7")
+        );
+        assert_eq!(
+            with_closes_reference(Some("Fixes the crash\n"), Some(7)).as_deref(),
+            Some("Fixes the crash\n\nCloses #7")
+        );
+        assert_eq!(
+            with_closes_reference(Some("Fixes #7"), Some(7)).as_deref(),
+            Some("Fixes #7")
+        );
+        // #70 is another issue
+        assert_eq!(
+            with_closes_reference(Some("Closes #70"), Some(7)).as_deref(),
+            Some("Closes #70\n\nCloses #7")
+        );
+    }
+}
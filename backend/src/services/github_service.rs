@@ -75,6 +75,163 @@ pub struct PullRequestInfo {
     pub merge_commit_sha: Option<String>,
 }
 
+/// A comment in a pull request review thread
+#[derive(Debug, Clone)]
+pub struct ReviewThreadComment {
+    pub id: i64,
+    pub author: String,
+    /// The author's relationship to the repository, e.g. `OWNER` or `NONE`
+    pub author_association: String,
+    pub body: String,
+    pub url: String,
+}
+
+/// Whether an `author_association` is one of the repository's owners,
+/// organization members or collaborators, as opposed to anyone with a
+/// GitHub account
+pub fn is_maintainer_association(author_association: &str) -> bool {
+    matches!(author_association, "OWNER" | "MEMBER" | "COLLABORATOR")
+}
+
+/// An inline review thread of a pull request, comments oldest first
+#[derive(Debug, Clone)]
+pub struct ReviewThread {
+    pub path: String,
+    /// Line in the PR's head; `None` once the code it was on changed
+    pub line: Option<i64>,
+    pub is_outdated: bool,
+    pub diff_hunk: Option<String>,
+    pub comments: Vec<ReviewThreadComment>,
+}
+
+const REVIEW_THREADS_QUERY: &str = r#"
+query($owner: String!, $repo: String!, $number: Int!) {
+  repository(owner: $owner, name: $repo) {
+    pullRequest(number: $number) {
+      reviewThreads(first: 100) {
+        nodes {
+          isResolved
+          isOutdated
+          path
+          line
+          comments(first: 50) {
+            nodes { databaseId author { login } authorAssociation body url diffHunk }
+          }
+        }
+      }
+    }
+  }
+}
+"#;
+
+#[derive(Debug, Deserialize)]
+struct GraphQlResponse<T> {
+    data: Option<T>,
+    #[serde(default)]
+    errors: Vec<GraphQlError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQlError {
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQlNodes<T> {
+    nodes: Vec<T>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ReviewThreadsData {
+    repository: Option<ReviewThreadsRepository>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ReviewThreadsRepository {
+    pull_request: Option<ReviewThreadsPullRequest>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ReviewThreadsPullRequest {
+    review_threads: GraphQlNodes<ReviewThreadNode>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ReviewThreadNode {
+    is_resolved: bool,
+    is_outdated: bool,
+    path: String,
+    line: Option<i64>,
+    comments: GraphQlNodes<ReviewThreadCommentNode>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ReviewThreadCommentNode {
+    database_id: Option<i64>,
+    author: Option<GraphQlAuthor>,
+    author_association: String,
+    body: String,
+    url: String,
+    diff_hunk: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQlAuthor {
+    login: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct CreatedComment {
+    id: i64,
+}
+
+/// An issue of a repository, pull requests excluded
+#[derive(Debug, Clone)]
+pub struct GitHubIssue {
+    pub number: i64,
+    pub title: String,
+    pub body: Option<String>,
+    pub url: String,
+    pub labels: Vec<String>,
+}
+
+/// Which open issues `list_issues` returns. Issues must carry every label.
+#[derive(Debug, Clone, Default)]
+pub struct IssueFilter {
+    pub labels: Vec<String>,
+    pub milestone_number: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct IssueLabel {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct IssueMilestone {
+    number: i64,
+    title: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct IssueItem {
+    number: i64,
+    title: String,
+    body: Option<String>,
+    html_url: String,
+    #[serde(default)]
+    labels: Vec<IssueLabel>,
+    pull_request: Option<serde_json::Value>,
+}
+
+/// Pages of 100 issues fetched at most by `list_issues`
+const MAX_ISSUE_PAGES: u32 = 10;
+
 #[derive(Debug, Clone)]
 pub struct GitHubService {
     client: Octocrab,
@@ -269,6 +426,202 @@ impl GitHubService {
         Ok(pr_info)
     }
 
+    /// Unresolved inline review threads of a pull request. Only the first 100
+    /// threads and the first 50 comments of each are looked at.
+    pub async fn list_unresolved_review_threads(
+        &self,
+        repo_info: &GitHubRepoInfo,
+        pr_number: i64,
+    ) -> Result<Vec<ReviewThread>, GitHubServiceError> {
+        self.with_retry(|| async {
+            self.list_unresolved_review_threads_internal(repo_info, pr_number)
+                .await
+        })
+        .await
+    }
+
+    async fn list_unresolved_review_threads_internal(
+        &self,
+        repo_info: &GitHubRepoInfo,
+        pr_number: i64,
+    ) -> Result<Vec<ReviewThread>, GitHubServiceError> {
+        // Whether a thread is resolved is only exposed through GraphQL
+        let response: GraphQlResponse<ReviewThreadsData> = self
+            .client
+            .graphql(&serde_json::json!({
+                "query": REVIEW_THREADS_QUERY,
+                "variables": {
+                    "owner": repo_info.owner,
+                    "repo": repo_info.repo_name,
+                    "number": pr_number,
+                },
+            }))
+            .await?;
+        if let Some(error) = response.errors.first() {
+            return Err(GitHubServiceError::PullRequest(format!(
+                "Failed to get review threads of PR #{}: {}",
+                pr_number, error.message
+            )));
+        }
+        let pull_request = response
+            .data
+            .and_then(|data| data.repository)
+            .and_then(|repository| repository.pull_request)
+            .ok_or_else(|| {
+                GitHubServiceError::PullRequest(format!("PR #{} not found", pr_number))
+            })?;
+
+        let threads = pull_request
+            .review_threads
+            .nodes
+            .into_iter()
+            .filter(|thread| !thread.is_resolved)
+            .map(|thread| ReviewThread {
+                diff_hunk: thread
+                    .comments
+                    .nodes
+                    .first()
+                    .and_then(|comment| comment.diff_hunk.clone()),
+                comments: thread
+                    .comments
+                    .nodes
+                    .into_iter()
+                    .filter_map(|comment| {
+                        Some(ReviewThreadComment {
+                            id: comment.database_id?,
+                            author: comment
+                                .author
+                                .map(|author| author.login)
+                                .unwrap_or_else(|| "ghost".to_string()),
+                            author_association: comment.author_association,
+                            body: comment.body,
+                            url: comment.url,
+                        })
+                    })
+                    .collect(),
+                path: thread.path,
+                line: thread.line,
+                is_outdated: thread.is_outdated,
+            })
+            .filter(|thread| !thread.comments.is_empty())
+            .collect();
+        Ok(threads)
+    }
+
+    /// Reply in the review thread started by `comment_id`, returning the
+    /// reply's ID
+    pub async fn reply_to_review_comment(
+        &self,
+        repo_info: &GitHubRepoInfo,
+        pr_number: i64,
+        comment_id: i64,
+        body: &str,
+    ) -> Result<i64, GitHubServiceError> {
+        let route = format!(
+            "/repos/{}/{}/pulls/{}/comments/{}/replies",
+            repo_info.owner, repo_info.repo_name, pr_number, comment_id
+        );
+        let reply: CreatedComment = self
+            .client
+            .post(route, Some(&serde_json::json!({ "body": body })))
+            .await?;
+        Ok(reply.id)
+    }
+
+    /// Open issues of the repository matching `filter`, oldest first. At
+    /// most 1000 issues are returned.
+    pub async fn list_issues(
+        &self,
+        repo_info: &GitHubRepoInfo,
+        filter: &IssueFilter,
+    ) -> Result<Vec<GitHubIssue>, GitHubServiceError> {
+        let route = format!("/repos/{}/{}/issues", repo_info.owner, repo_info.repo_name);
+        let mut issues = Vec::new();
+        for page in 1..=MAX_ISSUE_PAGES {
+            let mut params = serde_json::json!({
+                "state": "open",
+                "sort": "created",
+                "direction": "asc",
+                "per_page": 100,
+                "page": page,
+            });
+            if !filter.labels.is_empty() {
+                params["labels"] = serde_json::json!(filter.labels.join(","));
+            }
+            if let Some(milestone) = filter.milestone_number {
+                params["milestone"] = serde_json::json!(milestone.to_string());
+            }
+            let items: Vec<IssueItem> = self
+                .with_retry(|| async {
+                    self.client
+                        .get(&route, Some(&params))
+                        .await
+                        .map_err(GitHubServiceError::from)
+                })
+                .await?;
+            let last_page = items.len() < 100;
+            issues.extend(
+                items
+                    .into_iter()
+                    .filter(|item| item.pull_request.is_none())
+                    .map(|item| GitHubIssue {
+                        number: item.number,
+                        title: item.title,
+                        body: item.body,
+                        url: item.html_url,
+                        labels: item.labels.into_iter().map(|label| label.name).collect(),
+                    }),
+            );
+            if last_page {
+                break;
+            }
+        }
+        Ok(issues)
+    }
+
+    /// Number of the repository's milestone titled `title`, ignoring case
+    pub async fn find_milestone_number(
+        &self,
+        repo_info: &GitHubRepoInfo,
+        title: &str,
+    ) -> Result<Option<i64>, GitHubServiceError> {
+        let route = format!(
+            "/repos/{}/{}/milestones",
+            repo_info.owner, repo_info.repo_name
+        );
+        let params = serde_json::json!({ "state": "all", "per_page": 100 });
+        let milestones: Vec<IssueMilestone> = self
+            .with_retry(|| async {
+                self.client
+                    .get(&route, Some(&params))
+                    .await
+                    .map_err(GitHubServiceError::from)
+            })
+            .await?;
+        Ok(milestones
+            .into_iter()
+            .find(|milestone| milestone.title.eq_ignore_ascii_case(title.trim()))
+            .map(|milestone| milestone.number))
+    }
+
+    /// Comment on an issue, returning the comment's ID
+    pub async fn create_issue_comment(
+        &self,
+        repo_info: &GitHubRepoInfo,
+        issue_number: i64,
+        body: &str,
+    ) -> Result<i64, GitHubServiceError> {
+        let route = format!(
+            "/repos/{}/{}/issues/{}/comments",
+            repo_info.owner, repo_info.repo_name, issue_number
+        );
+        let comment: CreatedComment = self
+            .client
+            .post(route, Some(&serde_json::json!({ "body": body })))
+            .await?;
+        Ok(comment.id)
+    }
+
     /// Retry wrapper for GitHub API calls with exponential backoff
     async fn with_retry<F, Fut, T>(&self, operation: F) -> Result<T, GitHubServiceError>
     where
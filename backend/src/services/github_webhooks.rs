@@ -0,0 +1,600 @@
+//! GitHub webhooks for the pull requests Forge opened.
+//!
+//! GitHub delivers `pull_request`, `pull_request_review`,
+//! `pull_request_review_comment` and `check_run` events to
+//! `/api/integrations/github/webhook`, signed with the install's webhook
+//! secret. Each is matched to the attempt whose PR it concerns, so PR status
+//! changes apply immediately and reviews, review comments and CI checks are
+//! kept on the attempt. `PrMonitorService` polling remains as a fallback for
+//! missed deliveries.
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::Sha256;
+use sqlx::SqlitePool;
+use thiserror::Error;
+
+use crate::{
+    models::{
+        pull_request::{
+            PrCheckRun, PrChecksStatus, PrReview, PrReviewComment, PrReviewDecision, PrReviewState,
+            PullRequestState, UpsertPrReviewComment,
+        },
+        task::Task,
+        task_attempt::TaskAttempt,
+    },
+    security::secret_store::{SecretStore, CONFIG_SCOPE, GITHUB_WEBHOOK_SECRET},
+    services::{
+        github_service::{is_maintainer_association, PullRequestInfo},
+        pr_monitor,
+    },
+};
+
+#[derive(Debug, Error)]
+pub enum GitHubWebhookError {
+    #[error("Invalid {event} payload: {source}")]
+    Payload {
+        event: String,
+        source: serde_json::Error,
+    },
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
+}
+
+#[derive(Debug, Deserialize)]
+struct Repository {
+    html_url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Account {
+    login: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct PullRequestHead {
+    sha: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct PullRequest {
+    number: i64,
+    html_url: String,
+    state: String,
+    #[serde(default)]
+    merged: bool,
+    merged_at: Option<DateTime<Utc>>,
+    merge_commit_sha: Option<String>,
+    head: PullRequestHead,
+}
+
+#[derive(Debug, Deserialize)]
+struct PullRequestEvent {
+    action: String,
+    pull_request: PullRequest,
+}
+
+#[derive(Debug, Deserialize)]
+struct Review {
+    id: i64,
+    user: Account,
+    #[serde(default)]
+    author_association: String,
+    state: String,
+    body: Option<String>,
+    submitted_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReviewEvent {
+    #[serde(default)]
+    action: String,
+    review: Review,
+    pull_request: PullRequest,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReviewComment {
+    id: i64,
+    pull_request_review_id: Option<i64>,
+    in_reply_to_id: Option<i64>,
+    user: Account,
+    body: String,
+    path: String,
+    line: Option<i64>,
+    diff_hunk: Option<String>,
+    html_url: String,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReviewCommentEvent {
+    action: String,
+    comment: ReviewComment,
+    pull_request: PullRequest,
+}
+
+#[derive(Debug, Deserialize)]
+struct CheckRunPullRequest {
+    number: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct CheckRun {
+    name: String,
+    head_sha: String,
+    status: String,
+    conclusion: Option<String>,
+    html_url: Option<String>,
+    #[serde(default)]
+    pull_requests: Vec<CheckRunPullRequest>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CheckRunEvent {
+    check_run: CheckRun,
+    repository: Repository,
+}
+
+/// The secret deliveries are signed with, from `GITHUB_WEBHOOK_SECRET` or the
+/// `github.webhook_secret` secret. Deliveries are refused while neither is
+/// set.
+pub async fn webhook_secret(store: &SecretStore) -> Option<String> {
+    if let Ok(secret) = std::env::var("GITHUB_WEBHOOK_SECRET") {
+        return Some(secret).filter(|secret| !secret.is_empty());
+    }
+    store
+        .get(CONFIG_SCOPE, GITHUB_WEBHOOK_SECRET)
+        .await
+        .unwrap_or_else(|e| {
+            tracing::warn!(
+                "Failed to read GitHub webhook secret from secret store: {}",
+                e
+            );
+            None
+        })
+        .map(|secret| secret.as_str().to_string())
+        .filter(|secret| !secret.is_empty())
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Check an `X-Hub-Signature-256` header (`sha256=<hex HMAC of the body>`)
+/// in constant time
+pub fn verify_signature(secret: &str, body: &[u8], signature: &str) -> bool {
+    let Some(expected) = signature.strip_prefix("sha256=").and_then(decode_hex) else {
+        return false;
+    };
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    mac.verify_slice(&expected).is_ok()
+}
+
+fn parse<T: serde::de::DeserializeOwned>(
+    event: &str,
+    body: &[u8],
+) -> Result<T, GitHubWebhookError> {
+    serde_json::from_slice(body).map_err(|source| GitHubWebhookError::Payload {
+        event: event.to_string(),
+        source,
+    })
+}
+
+fn review_state(state: &str) -> Option<PrReviewState> {
+    match state.to_ascii_lowercase().as_str() {
+        "approved" => Some(PrReviewState::Approved),
+        "changes_requested" => Some(PrReviewState::ChangesRequested),
+        "commented" => Some(PrReviewState::Commented),
+        "dismissed" => Some(PrReviewState::Dismissed),
+        _ => None,
+    }
+}
+
+/// Status of a PR in the terms `TaskAttempt::pr_status` uses
+fn pr_status(pull_request: &PullRequest) -> PullRequestInfo {
+    let merged = pull_request.merged || pull_request.merged_at.is_some();
+    let status = match (pull_request.state.as_str(), merged) {
+        ("open", _) => "open",
+        (_, true) => "merged",
+        _ => "closed",
+    };
+    PullRequestInfo {
+        number: pull_request.number,
+        url: pull_request.html_url.clone(),
+        status: status.to_string(),
+        merged,
+        merged_at: pull_request.merged_at,
+        merge_commit_sha: pull_request.merge_commit_sha.clone(),
+    }
+}
+
+/// The review outcome: changes requested while any reviewer's latest verdict
+/// asks for them, otherwise approved once anyone approved. Comments don't
+/// change a reviewer's verdict and dismissals withdraw it.
+pub fn review_decision(reviews: &[PrReview]) -> Option<PrReviewDecision> {
+    let mut verdicts: Vec<(&str, PrReviewState)> = Vec::new();
+    let mut reviews: Vec<&PrReview> = reviews.iter().collect();
+    reviews.sort_by_key(|review| review.submitted_at);
+    for review in reviews {
+        if review.state == PrReviewState::Commented {
+            continue;
+        }
+        verdicts.retain(|(reviewer, _)| *reviewer != review.reviewer);
+        verdicts.push((review.reviewer.as_str(), review.state));
+    }
+    let states: Vec<PrReviewState> = verdicts.into_iter().map(|(_, state)| state).collect();
+    if states.contains(&PrReviewState::ChangesRequested) {
+        Some(PrReviewDecision::ChangesRequested)
+    } else if states.contains(&PrReviewState::Approved) {
+        Some(PrReviewDecision::Approved)
+    } else {
+        None
+    }
+}
+
+/// Failing if any check failed, pending while any is still running, and
+/// passing once all completed without failing
+pub fn checks_status(checks: &[PrCheckRun]) -> Option<PrChecksStatus> {
+    if checks.is_empty() {
+        return None;
+    }
+    let failed = checks.iter().any(|check| {
+        matches!(
+            check.conclusion.as_deref(),
+            Some("failure" | "timed_out" | "cancelled" | "action_required" | "startup_failure")
+        )
+    });
+    if failed {
+        Some(PrChecksStatus::Failing)
+    } else if checks.iter().any(|check| check.status != "completed") {
+        Some(PrChecksStatus::Pending)
+    } else {
+        Some(PrChecksStatus::Passing)
+    }
+}
+
+/// Everything synced about an attempt's pull request
+pub async fn pull_request_state(
+    pool: &SqlitePool,
+    attempt: &TaskAttempt,
+) -> Result<PullRequestState, sqlx::Error> {
+    let reviews = PrReview::find_by_attempt_id(pool, attempt.id).await?;
+    let comments = PrReviewComment::find_by_attempt_id(pool, attempt.id).await?;
+    let checks = PrCheckRun::find_by_attempt_id(pool, attempt.id).await?;
+    Ok(PullRequestState {
+        pr_url: attempt.pr_url.clone(),
+        pr_number: attempt.pr_number,
+        pr_status: attempt.pr_status.clone(),
+        review_decision: review_decision(&reviews),
+        checks_status: checks_status(&checks),
+        reviews,
+        comments,
+        checks,
+    })
+}
+
+/// The PR a delivery reports a review with comments on, as in a review
+/// requesting changes or just commenting, by one of the repository's owners,
+/// members or collaborators. Anyone can review a public repository, so other
+/// reviews never start a follow-up on their own.
+pub fn submitted_review_pr_url(event: &str, body: &[u8]) -> Option<String> {
+    if event != "pull_request_review" {
+        return None;
+    }
+    let payload: ReviewEvent = serde_json::from_slice(body).ok()?;
+    let commented = matches!(
+        review_state(&payload.review.state),
+        Some(PrReviewState::ChangesRequested | PrReviewState::Commented)
+    );
+    let trusted = is_maintainer_association(&payload.review.author_association);
+    (payload.action == "submitted" && commented && trusted).then_some(payload.pull_request.html_url)
+}
+
+/// Apply a delivery and describe what it did
+pub async fn handle_event(
+    pool: &SqlitePool,
+    secret_store: &SecretStore,
+    event: &str,
+    body: &[u8],
+) -> Result<String, GitHubWebhookError> {
+    let not_ours = || Ok("Ignored: not a pull request opened by Forge".to_string());
+    match event {
+        "ping" => Ok("Pong".to_string()),
+        "pull_request" => {
+            let payload: PullRequestEvent = parse(event, body)?;
+            let Some(attempt) =
+                TaskAttempt::find_by_pr_url(pool, &payload.pull_request.html_url).await?
+            else {
+                return not_ours();
+            };
+            if payload.action == "synchronize" {
+                PrCheckRun::delete_stale(pool, attempt.id, &payload.pull_request.head.sha).await?;
+            }
+            let Some(task) = Task::find_by_id(pool, attempt.task_id).await? else {
+                return not_ours();
+            };
+            let status = pr_status(&payload.pull_request);
+            pr_monitor::record_pr_status(pool, secret_store, &attempt, task.project_id, &status)
+                .await?;
+            Ok(format!("Pull request {}", status.status))
+        }
+        "pull_request_review" => {
+            let payload: ReviewEvent = parse(event, body)?;
+            let Some(attempt) =
+                TaskAttempt::find_by_pr_url(pool, &payload.pull_request.html_url).await?
+            else {
+                return not_ours();
+            };
+            let review = payload.review;
+            // Pending reviews are private to their author until submitted
+            let Some(state) = review_state(&review.state) else {
+                return Ok(format!("Ignored: review state {}", review.state));
+            };
+            PrReview::upsert(
+                pool,
+                attempt.id,
+                review.id,
+                &review.user.login,
+                state,
+                review.body.as_deref().filter(|body| !body.is_empty()),
+                review.submitted_at.unwrap_or_else(Utc::now),
+            )
+            .await?;
+            Ok("Review recorded".to_string())
+        }
+        "pull_request_review_comment" => {
+            let payload: ReviewCommentEvent = parse(event, body)?;
+            let Some(attempt) =
+                TaskAttempt::find_by_pr_url(pool, &payload.pull_request.html_url).await?
+            else {
+                return not_ours();
+            };
+            let comment = payload.comment;
+            if payload.action == "deleted" {
+                PrReviewComment::delete_by_github_id(pool, attempt.id, comment.id).await?;
+                return Ok("Review comment deleted".to_string());
+            }
+            PrReviewComment::upsert(
+                pool,
+                attempt.id,
+                &UpsertPrReviewComment {
+                    github_id: comment.id,
+                    review_github_id: comment.pull_request_review_id,
+                    in_reply_to_github_id: comment.in_reply_to_id,
+                    author: comment.user.login,
+                    body: comment.body,
+                    path: comment.path,
+                    line: comment.line,
+                    diff_hunk: comment.diff_hunk,
+                    url: comment.html_url,
+                    created_at: comment.created_at,
+                    updated_at: comment.updated_at,
+                },
+            )
+            .await?;
+            Ok("Review comment recorded".to_string())
+        }
+        "check_run" => {
+            let payload: CheckRunEvent = parse(event, body)?;
+            let check = payload.check_run;
+            let mut recorded = 0;
+            for pull_request in &check.pull_requests {
+                let pr_url = format!(
+                    "{}/pull/{}",
+                    payload.repository.html_url.trim_end_matches('/'),
+                    pull_request.number
+                );
+                if let Some(attempt) = TaskAttempt::find_by_pr_url(pool, &pr_url).await? {
+                    PrCheckRun::upsert(
+                        pool,
+                        attempt.id,
+                        &check.name,
+                        &check.head_sha,
+                        &check.status,
+                        check.conclusion.as_deref(),
+                        check.html_url.as_deref(),
+                    )
+                    .await?;
+                    recorded += 1;
+                }
+            }
+            if recorded == 0 {
+                return not_ours();
+            }
+            Ok(format!("Check {} recorded", check.name))
+        }
+        _ => Ok(format!("Ignored: {} events are not handled", event)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use uuid::Uuid;
+
+    use super::*;
+
+    fn review(reviewer: &str, state: PrReviewState, minute: u32) -> PrReview {
+        PrReview {
+            id: Uuid::new_v4(),
+            task_attempt_id: Uuid::nil(),
+            github_id: minute as i64,
+            reviewer: reviewer.to_string(),
+            state,
+            body: None,
+            submitted_at: Utc.with_ymd_and_hms(2025, 8, 12, 10, minute, 0).unwrap(),
+        }
+    }
+
+    fn check(status: &str, conclusion: Option<&str>) -> PrCheckRun {
+        PrCheckRun {
+            id: Uuid::new_v4(),
+            task_attempt_id: Uuid::nil(),
+            name: "ci".to_string(),
+            head_sha: "abc123".to_string(),
+            status: status.to_string(),
+            conclusion: conclusion.map(str::to_string),
+            url: None,
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_verify_signature() {
+        let body = br#"{"zen":"Design for failure."}"#;
+        let mut mac = Hmac::<Sha256>::new_from_slice(b"s3cret").unwrap();
+        mac.update(body);
+        let signature = format!("sha256={:x}", mac.finalize().into_bytes());
+
+        assert!(verify_signature("s3cret", body, &signature));
+        assert!(!verify_signature("other", body, &signature));
+        assert!(!verify_signature("s3cret", b"{}", &signature));
+        assert!(!verify_signature("s3cret", body, "sha1=abc"));
+        assert!(!verify_signature("s3cret", body, "sha256=zz"));
+    }
+
+    #[test]
+    fn test_review_decision_uses_each_reviewers_latest_verdict() {
+        assert_eq!(review_decision(&[]), None);
+        assert_eq!(
+            review_decision(&[review("ana", PrReviewState::Commented, 1)]),
+            None
+        );
+        assert_eq!(
+            review_decision(&[
+                review("ana", PrReviewState::ChangesRequested, 1),
+                review("ana", PrReviewState::Commented, 2),
+                review("bo", PrReviewState::Approved, 3),
+            ]),
+            Some(PrReviewDecision::ChangesRequested)
+        );
+        assert_eq!(
+            review_decision(&[
+                review("ana", PrReviewState::Approved, 4),
+                review("ana", PrReviewState::ChangesRequested, 1),
+            ]),
+            Some(PrReviewDecision::Approved)
+        );
+        assert_eq!(
+            review_decision(&[
+                review("ana", PrReviewState::Approved, 1),
+                review("ana", PrReviewState::Dismissed, 2),
+            ]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_checks_status() {
+        assert_eq!(checks_status(&[]), None);
+        assert_eq!(
+            checks_status(&[
+                check("completed", Some("success")),
+                check("in_progress", None)
+            ]),
+            Some(PrChecksStatus::Pending)
+        );
+        assert_eq!(
+            checks_status(&[check("completed", Some("failure")), check("queued", None)]),
+            Some(PrChecksStatus::Failing)
+        );
+        assert_eq!(
+            checks_status(&[
+                check("completed", Some("success")),
+                check("completed", Some("skipped"))
+            ]),
+            Some(PrChecksStatus::Passing)
+        );
+    }
+
+    #[test]
+    fn test_pr_status_from_payload() {
+        let payload: PullRequestEvent = serde_json::from_value(serde_json::json!({
+            "action": "closed",
+            "pull_request": {
+                "number": 42,
+                "html_url": "https://github.com/acme/app/pull/42",
+                "state": "closed",
+                "merged": true,
+                "merged_at": "2025-08-12T10:00:00Z",
+                "merge_commit_sha": "abc123",
+                "head": { "sha": "def456" }
+            }
+        }))
+        .unwrap();
+        let status = pr_status(&payload.pull_request);
+        assert_eq!(status.status, "merged");
+        assert!(status.merged);
+        assert_eq!(status.merge_commit_sha.as_deref(), Some("abc123"));
+    }
+
+    #[test]
+    fn test_submitted_review_pr_url() {
+        let delivery = |action: &str, state: &str, association: &str| {
+            serde_json::json!({
+                "action": action,
+                "review": {
+                    "id": 7,
+                    "user": { "login": "ana" },
+                    "author_association": association,
+                    "state": state,
+                    "body": null,
+                    "submitted_at": "2025-08-12T10:00:00Z"
+                },
+                "pull_request": {
+                    "number": 42,
+                    "html_url": "https://github.com/acme/app/pull/42",
+                    "state": "open",
+                    "merged_at": null,
+                    "merge_commit_sha": null,
+                    "head": { "sha": "def456" }
+                }
+            })
+            .to_string()
+        };
+        assert_eq!(
+            submitted_review_pr_url(
+                "pull_request_review",
+                delivery("submitted", "changes_requested", "MEMBER").as_bytes()
+            )
+            .as_deref(),
+            Some("https://github.com/acme/app/pull/42")
+        );
+        assert!(submitted_review_pr_url(
+            "pull_request_review",
+            delivery("submitted", "changes_requested", "NONE").as_bytes()
+        )
+        .is_none());
+        assert!(submitted_review_pr_url(
+            "pull_request_review",
+            delivery("submitted", "commented", "CONTRIBUTOR").as_bytes()
+        )
+        .is_none());
+        assert!(submitted_review_pr_url(
+            "pull_request_review",
+            delivery("submitted", "approved", "OWNER").as_bytes()
+        )
+        .is_none());
+        assert!(submitted_review_pr_url(
+            "pull_request_review",
+            delivery("edited", "commented", "OWNER").as_bytes()
+        )
+        .is_none());
+        assert!(submitted_review_pr_url(
+            "pull_request",
+            delivery("submitted", "commented", "OWNER").as_bytes()
+        )
+        .is_none());
+    }
+}
@@ -0,0 +1,321 @@
+//! GitLab merge requests through the REST API (v4), for gitlab.com and
+//! self-hosted instances.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+use crate::services::{
+    forge::{self, Forge, ForgeError, ForgeKind, ForgeRepo, ForgeReviewComment},
+    github_service::{CreatePrRequest, PullRequestInfo},
+};
+
+/// Discussions fetched per merge request; later ones are not looked at
+const DISCUSSIONS_PER_PAGE: u32 = 100;
+
+pub struct GitLabService {
+    client: reqwest::Client,
+    /// `.../api/v4/projects/<url-encoded path>`
+    project_url: String,
+    token: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct MergeRequest {
+    iid: i64,
+    web_url: String,
+    /// `opened`, `closed`, `locked` or `merged`
+    state: String,
+    merged_at: Option<DateTime<Utc>>,
+    merge_commit_sha: Option<String>,
+    squash_commit_sha: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Discussion {
+    notes: Vec<Note>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Note {
+    id: i64,
+    body: String,
+    author: NoteAuthor,
+    #[serde(default)]
+    system: bool,
+    #[serde(default)]
+    resolvable: bool,
+    #[serde(default)]
+    resolved: bool,
+    position: Option<NotePosition>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NoteAuthor {
+    username: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct NotePosition {
+    new_path: Option<String>,
+    new_line: Option<i64>,
+}
+
+impl MergeRequest {
+    fn into_info(self) -> PullRequestInfo {
+        let status = match self.state.as_str() {
+            "opened" => "open",
+            "merged" => "merged",
+            _ => "closed",
+        };
+        let merged = status == "merged";
+        PullRequestInfo {
+            number: self.iid,
+            url: self.web_url,
+            status: status.to_string(),
+            merged,
+            merged_at: self.merged_at,
+            // Squash merges only record the squashed commit
+            merge_commit_sha: self.merge_commit_sha.or(self.squash_commit_sha),
+        }
+    }
+}
+
+impl GitLabService {
+    pub fn new(repo: &ForgeRepo, token: &str) -> Self {
+        let path = format!("{}/{}", repo.owner, repo.name);
+        Self {
+            client: forge::http_client(),
+            project_url: format!(
+                "{}/api/v4/projects/{}",
+                repo.base_url,
+                urlencoding::encode(&path)
+            ),
+            token: token.to_string(),
+        }
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        self.client
+            .request(method, format!("{}{}", self.project_url, path))
+            .header("PRIVATE-TOKEN", &self.token)
+    }
+
+    async fn merge_request(&self, iid: i64) -> Result<MergeRequest, ForgeError> {
+        let response = self
+            .request(reqwest::Method::GET, &format!("/merge_requests/{}", iid))
+            .send()
+            .await?;
+        forge::read_json(ForgeKind::GitLab, response).await
+    }
+}
+
+#[async_trait]
+impl Forge for GitLabService {
+    async fn create_pull_request(
+        &self,
+        request: &CreatePrRequest,
+    ) -> Result<PullRequestInfo, ForgeError> {
+        let response = self
+            .request(reqwest::Method::POST, "/merge_requests")
+            .json(&serde_json::json!({
+                "source_branch": request.head_branch,
+                "target_branch": request.base_branch,
+                "title": request.title,
+                "description": request.body.clone().unwrap_or_default(),
+            }))
+            .send()
+            .await?;
+        let merge_request: MergeRequest = forge::read_json(ForgeKind::GitLab, response).await?;
+        tracing::info!(
+            "Created GitLab merge request !{} for branch {}",
+            merge_request.iid,
+            request.head_branch
+        );
+        Ok(merge_request.into_info())
+    }
+
+    async fn pull_request_status(&self, number: i64) -> Result<PullRequestInfo, ForgeError> {
+        Ok(self.merge_request(number).await?.into_info())
+    }
+
+    async fn review_comments(&self, number: i64) -> Result<Vec<ForgeReviewComment>, ForgeError> {
+        let web_url = self.merge_request(number).await?.web_url;
+        let response = self
+            .request(
+                reqwest::Method::GET,
+                &format!("/merge_requests/{}/discussions", number),
+            )
+            .query(&[("per_page", DISCUSSIONS_PER_PAGE)])
+            .send()
+            .await?;
+        let discussions: Vec<Discussion> = forge::read_json(ForgeKind::GitLab, response).await?;
+
+        Ok(discussions
+            .into_iter()
+            .flat_map(|discussion| discussion.notes)
+            .filter(|note| !note.system && note.resolvable && !note.resolved)
+            .map(|note| {
+                let (path, line) = match note.position {
+                    Some(position) => (position.new_path, position.new_line),
+                    None => (None, None),
+                };
+                ForgeReviewComment {
+                    id: note.id,
+                    author: note.author.username,
+                    body: note.body,
+                    path,
+                    line,
+                    url: Some(format!("{}#note_{}", web_url, note.id)),
+                }
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use axum::{
+        extract::Path,
+        http::{HeaderMap, StatusCode},
+        routing::{get, post},
+        Json, Router,
+    };
+
+    use super::*;
+    use crate::services::forge::test_server;
+
+    fn repo(base_url: String) -> ForgeRepo {
+        ForgeRepo {
+            kind: ForgeKind::GitLab,
+            host: "gitlab.example.com".to_string(),
+            base_url,
+            owner: "group/sub".to_string(),
+            name: "app".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_creates_merge_request_and_reads_its_state() {
+        let created = Arc::new(Mutex::new(None));
+        let recorded = created.clone();
+        let router = Router::new()
+            .route(
+                "/api/v4/projects/group%2Fsub%2Fapp/merge_requests",
+                post(
+                    move |headers: HeaderMap, Json(body): Json<serde_json::Value>| async move {
+                        *recorded.lock().unwrap() = Some((
+                            headers["private-token"].to_str().unwrap().to_string(),
+                            body,
+                        ));
+                        Json(serde_json::json!({
+                            "iid": 7,
+                            "web_url": "https://gitlab.example.com/group/sub/app/-/merge_requests/7",
+                            "state": "opened",
+                            "merged_at": null,
+                            "merge_commit_sha": null,
+                        }))
+                    },
+                ),
+            )
+            .route(
+                "/api/v4/projects/group%2Fsub%2Fapp/merge_requests/:iid",
+                get(|Path(iid): Path<i64>| async move {
+                    Json(serde_json::json!({
+                        "iid": iid,
+                        "web_url": "https://gitlab.example.com/group/sub/app/-/merge_requests/7",
+                        "state": "merged",
+                        "merged_at": "2025-08-15T10:00:00Z",
+                        "merge_commit_sha": null,
+                        "squash_commit_sha": "abc123",
+                    }))
+                }),
+            );
+        let gitlab = GitLabService::new(&repo(test_server::serve(router).await), "glpat-secret");
+
+        let info = gitlab
+            .create_pull_request(&CreatePrRequest {
+                title: "Add login".to_string(),
+                body: Some("Adds the login page".to_string()),
+                head_branch: "forge/add-login".to_string(),
+                base_branch: "main".to_string(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(info.number, 7);
+        assert_eq!(info.status, "open");
+        let (token, body) = created.lock().unwrap().take().unwrap();
+        assert_eq!(token, "glpat-secret");
+        assert_eq!(body["source_branch"], "forge/add-login");
+        assert_eq!(body["target_branch"], "main");
+        assert_eq!(body["description"], "Adds the login page");
+
+        let status = gitlab.pull_request_status(7).await.unwrap();
+        assert_eq!(status.status, "merged");
+        assert!(status.merged);
+        assert_eq!(status.merge_commit_sha.as_deref(), Some("abc123"));
+    }
+
+    #[tokio::test]
+    async fn test_review_comments_are_unresolved_diff_notes() {
+        let router = Router::new()
+            .route(
+                "/api/v4/projects/group%2Fsub%2Fapp/merge_requests/:iid",
+                get(|| async {
+                    Json(serde_json::json!({
+                        "iid": 3,
+                        "web_url": "https://gitlab.example.com/group/sub/app/-/merge_requests/3",
+                        "state": "opened",
+                    }))
+                }),
+            )
+            .route(
+                "/api/v4/projects/group%2Fsub%2Fapp/merge_requests/:iid/discussions",
+                get(|| async {
+                    Json(serde_json::json!([
+                        { "notes": [{
+                            "id": 11, "body": "Rename this", "author": { "username": "ana" },
+                            "system": false, "resolvable": true, "resolved": false,
+                            "position": { "new_path": "src/lib.rs", "new_line": 4 }
+                        }]},
+                        { "notes": [{
+                            "id": 12, "body": "Done already", "author": { "username": "ana" },
+                            "resolvable": true, "resolved": true
+                        }]},
+                        { "notes": [{
+                            "id": 13, "body": "added 1 commit", "author": { "username": "bot" },
+                            "system": true
+                        }]}
+                    ]))
+                }),
+            );
+        let gitlab = GitLabService::new(&repo(test_server::serve(router).await), "token");
+
+        let comments = gitlab.review_comments(3).await.unwrap();
+        assert_eq!(comments.len(), 1);
+        assert_eq!(comments[0].id, 11);
+        assert_eq!(comments[0].author, "ana");
+        assert_eq!(comments[0].path.as_deref(), Some("src/lib.rs"));
+        assert_eq!(comments[0].line, Some(4));
+        assert_eq!(
+            comments[0].url.as_deref(),
+            Some("https://gitlab.example.com/group/sub/app/-/merge_requests/3#note_11")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rejected_token() {
+        let router = Router::new().route(
+            "/api/v4/projects/group%2Fsub%2Fapp/merge_requests/:iid",
+            get(|| async { (StatusCode::UNAUTHORIZED, "401 Unauthorized") }),
+        );
+        let gitlab = GitLabService::new(&repo(test_server::serve(router).await), "expired");
+
+        assert!(matches!(
+            gitlab.pull_request_status(1).await,
+            Err(ForgeError::TokenInvalid(ForgeKind::GitLab))
+        ));
+    }
+}
@@ -0,0 +1,394 @@
+//! Per-attempt MCP configuration for coding agents.
+//!
+//! Coding agents get their MCP servers without Forge touching the agent's
+//! global config file. Before the agent starts, a config holding the project's
+//! and user's servers (see `mcp_servers`) and, for projects that opted in, the
+//! Forge task server is written to the temp directory and passed with the
+//! CLI's `--mcp-config` flag. The agent reaches the Forge server at the
+//! backend's own `/mcp` endpoint, which shares the backend's state so attempt
+//! tools work, authenticated with an MCP session minted for the attempt's
+//! creator and naming the attempt in a header.
+//!
+//! CLIs without such a flag only get the servers configured in their global
+//! config file through `/api/config/mcp-servers`.
+
+use std::{
+    collections::BTreeMap,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use chrono::Utc;
+use sqlx::SqlitePool;
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::{
+    auth::{extract_bearer_token, generate_jwt_token, hash_token, JwtConfig},
+    executor::ExecutorConfig,
+    mcp::task_server::FORGE_ATTEMPT_HEADER,
+    models::{
+        task_attempt::TaskAttempt,
+        user_session::{CreateUserSession, SessionType, UserSession},
+    },
+};
+
+/// Name of the injected server in the agent's MCP config
+pub const FORGE_MCP_SERVER_NAME: &str = "automagik-forge";
+
+/// Where configs written before the Forge server was reached over HTTP held
+/// its token
+const LEGACY_TOKEN_VAR: &str = "FORGE_MCP_TOKEN";
+
+#[derive(Debug, Error)]
+pub enum McpInjectionError {
+    #[error("Attempt {0} has no creator to run the Forge MCP server as")]
+    NoCreator(Uuid),
+    #[error("The backend's address is not known yet")]
+    NoServerUrl,
+    #[error("Failed to issue an MCP access token: {0}")]
+    Token(#[from] jsonwebtoken::errors::Error),
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("Failed to write MCP config: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Failed to encode MCP config: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// Whether the executor's CLI accepts an MCP config file for a single run
+pub fn supports_injection(executor: &ExecutorConfig) -> bool {
+    matches!(
+        executor,
+        ExecutorConfig::Claude | ExecutorConfig::ClaudePlan | ExecutorConfig::ClaudeCodeRouter
+    )
+}
+
+/// Names of the executors `supports_injection` accepts
+pub fn supported_executors() -> Vec<String> {
+    [
+        ExecutorConfig::Claude,
+        ExecutorConfig::ClaudePlan,
+        ExecutorConfig::ClaudeCodeRouter,
+    ]
+    .iter()
+    .filter(|executor| supports_injection(executor))
+    .map(ToString::to_string)
+    .collect()
+}
+
+/// The CLI argument pointing an agent at its MCP config
+pub fn mcp_config_arg(path: &Path) -> String {
+    let path = path.to_string_lossy();
+    if path.contains(char::is_whitespace) {
+        format!("--mcp-config \"{}\"", path)
+    } else {
+        format!("--mcp-config {}", path)
+    }
+}
+
+fn attempt_config_path(attempt_id: Uuid) -> PathBuf {
+    std::env::temp_dir()
+        .join("automagik-forge-mcp")
+        .join(format!("{}.json", attempt_id))
+}
+
+/// The `mcpServers` entry of the Forge server mounted at `/mcp` of the
+/// backend at `server_url`, called as `attempt_id` with `token`
+fn forge_server_entry(server_url: &str, token: &str, attempt_id: Uuid) -> serde_json::Value {
+    serde_json::json!({
+        "type": "http",
+        "url": format!("{}/mcp", server_url.trim_end_matches('/')),
+        "headers": {
+            "Authorization": format!("Bearer {}", token),
+            FORGE_ATTEMPT_HEADER: attempt_id.to_string(),
+        },
+    })
+}
+
+/// Write the attempt's MCP config and return its path: the project's and
+/// user's servers from `mcp_servers`, plus the Forge server when `forge` is
+/// set, reached through the backend at `server_url`. The config is rewritten
+/// for every run so follow-ups see changes, but the attempt keeps the MCP
+/// session minted for its first run. Returns `None`, removing any earlier
+/// config, when there is nothing to give the agent.
+pub async fn prepare_attempt_config(
+    pool: &SqlitePool,
+    jwt_config: &JwtConfig,
+    server_url: Option<&str>,
+    attempt: &TaskAttempt,
+    forge: bool,
+    mut servers: BTreeMap<String, serde_json::Value>,
+) -> Result<Option<PathBuf>, McpInjectionError> {
+    let path = attempt_config_path(attempt.id);
+    if !forge && servers.is_empty() {
+        remove_attempt_config(pool, attempt.id).await;
+        return Ok(None);
+    }
+
+    if forge {
+        let server_url = server_url.ok_or(McpInjectionError::NoServerUrl)?;
+        let token = match read_attempt_token(&path) {
+            Some(token) => token,
+            None => mint_attempt_token(pool, jwt_config, attempt).await?,
+        };
+        servers.insert(
+            FORGE_MCP_SERVER_NAME.to_string(),
+            forge_server_entry(server_url, &token, attempt.id),
+        );
+    } else if let Some(token) = read_attempt_token(&path) {
+        revoke_token(pool, attempt.id, &token).await;
+    }
+
+    let config = serde_json::to_vec_pretty(&serde_json::json!({ "mcpServers": servers }))?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    write_private(&path, &config)?;
+    Ok(Some(path))
+}
+
+/// An MCP session for the attempt's creator, for the Forge server to run as
+async fn mint_attempt_token(
+    pool: &SqlitePool,
+    jwt_config: &JwtConfig,
+    attempt: &TaskAttempt,
+) -> Result<String, McpInjectionError> {
+    let user_id = attempt
+        .created_by
+        .ok_or(McpInjectionError::NoCreator(attempt.id))?;
+
+    let session_id = Uuid::new_v4();
+    let token = generate_jwt_token(user_id, session_id, SessionType::Mcp, jwt_config)?;
+    UserSession::create(
+        pool,
+        &CreateUserSession {
+            user_id,
+            token_hash: hash_token(&token),
+            session_type: SessionType::Mcp,
+            client_info: Some(format!("Forge attempt {}", attempt.id)),
+            expires_at: Utc::now() + chrono::Duration::days(UserSession::MCP_SESSION_DURATION_DAYS),
+        },
+        session_id,
+    )
+    .await?;
+    Ok(token)
+}
+
+/// The Forge server's token in an attempt's existing config
+fn read_attempt_token(path: &Path) -> Option<String> {
+    let contents = std::fs::read(path).ok()?;
+    let config = serde_json::from_slice::<serde_json::Value>(&contents).ok()?;
+    let server = &config["mcpServers"][FORGE_MCP_SERVER_NAME];
+    server["headers"]["Authorization"]
+        .as_str()
+        .and_then(extract_bearer_token)
+        .or_else(|| server["env"][LEGACY_TOKEN_VAR].as_str())
+        .map(str::to_string)
+}
+
+async fn revoke_token(pool: &SqlitePool, attempt_id: Uuid, token: &str) {
+    if let Err(e) = UserSession::delete_by_token_hash(pool, &hash_token(token)).await {
+        tracing::error!(
+            "Failed to revoke MCP session of attempt {}: {}",
+            attempt_id,
+            e
+        );
+    }
+}
+
+/// Delete the attempt's MCP config and revoke the session it carried, once
+/// the attempt's worktree is gone
+pub async fn remove_attempt_config(pool: &SqlitePool, attempt_id: Uuid) {
+    let path = attempt_config_path(attempt_id);
+    if !path.exists() {
+        return;
+    }
+    if let Some(token) = read_attempt_token(&path) {
+        revoke_token(pool, attempt_id, &token).await;
+    }
+    if let Err(e) = std::fs::remove_file(&path) {
+        tracing::warn!("Failed to delete MCP config {}: {}", path.display(), e);
+    }
+}
+
+/// The config holds an access token, so only the owner may read it
+fn write_private(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        mcp::auth::authenticate_mcp_token,
+        models::user::{CreateUser, User},
+    };
+
+    #[test]
+    fn test_forge_server_entry() {
+        let attempt_id = Uuid::new_v4();
+        let server = forge_server_entry("http://127.0.0.1:4000/", "the-token", attempt_id);
+
+        assert_eq!(server["type"], "http");
+        assert_eq!(server["url"], "http://127.0.0.1:4000/mcp");
+        assert_eq!(server["headers"]["Authorization"], "Bearer the-token");
+        assert_eq!(
+            server["headers"][FORGE_ATTEMPT_HEADER],
+            attempt_id.to_string()
+        );
+    }
+
+    #[test]
+    fn test_read_attempt_token() {
+        let path = std::env::temp_dir().join(format!("forge-mcp-test-{}.json", Uuid::new_v4()));
+        let config = serde_json::json!({
+            "mcpServers": {
+                FORGE_MCP_SERVER_NAME: forge_server_entry("http://127.0.0.1:4000", "the-token", Uuid::new_v4())
+            }
+        });
+        write_private(&path, config.to_string().as_bytes()).unwrap();
+        assert_eq!(read_attempt_token(&path), Some("the-token".to_string()));
+
+        let legacy = serde_json::json!({
+            "mcpServers": {
+                FORGE_MCP_SERVER_NAME: { "env": { LEGACY_TOKEN_VAR: "old-token" } }
+            }
+        });
+        write_private(&path, legacy.to_string().as_bytes()).unwrap();
+        assert_eq!(read_attempt_token(&path), Some("old-token".to_string()));
+
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(read_attempt_token(&path), None);
+    }
+
+    #[tokio::test]
+    async fn test_injected_server_is_accepted_by_mcp_endpoint() {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+        let jwt_config = JwtConfig {
+            secret: "an-mcp-injection-test-secret-of-at-least-32-bytes".to_string(),
+            algorithm: jsonwebtoken::Algorithm::HS256,
+        };
+        let user = User::create(
+            &pool,
+            &CreateUser {
+                github_id: 1,
+                username: "agent-owner".to_string(),
+                email: "owner@example.com".to_string(),
+                display_name: None,
+                avatar_url: None,
+                github_token: None,
+                is_admin: None,
+            },
+            Uuid::new_v4(),
+        )
+        .await
+        .unwrap();
+        let attempt = TaskAttempt {
+            id: Uuid::new_v4(),
+            task_id: Uuid::new_v4(),
+            worktree_path: String::new(),
+            branch: "forge/test".to_string(),
+            base_branch: "main".to_string(),
+            merge_commit: None,
+            executor: Some("claude".to_string()),
+            pr_url: None,
+            pr_number: None,
+            pr_status: None,
+            pr_merged_at: None,
+            worktree_deleted: false,
+            setup_completed_at: None,
+            created_by: Some(user.id),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+
+        assert!(matches!(
+            prepare_attempt_config(&pool, &jwt_config, None, &attempt, true, BTreeMap::new()).await,
+            Err(McpInjectionError::NoServerUrl)
+        ));
+
+        let server_url = Some("http://127.0.0.1:4000");
+        let path = prepare_attempt_config(
+            &pool,
+            &jwt_config,
+            server_url,
+            &attempt,
+            true,
+            BTreeMap::new(),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        let config: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        let server = &config["mcpServers"][FORGE_MCP_SERVER_NAME];
+        assert_eq!(server["type"], "http");
+        assert_eq!(server["url"], "http://127.0.0.1:4000/mcp");
+        assert_eq!(
+            server["headers"][FORGE_ATTEMPT_HEADER],
+            attempt.id.to_string()
+        );
+
+        // The header carries a token `/mcp` runs tool calls as the creator with
+        let token = server["headers"]["Authorization"]
+            .as_str()
+            .and_then(extract_bearer_token)
+            .unwrap()
+            .to_string();
+        let user_context = authenticate_mcp_token(&pool, &jwt_config, &token)
+            .await
+            .unwrap();
+        assert_eq!(user_context.user.id, user.id);
+
+        // Follow-ups keep the session, and cleaning up revokes it
+        prepare_attempt_config(
+            &pool,
+            &jwt_config,
+            server_url,
+            &attempt,
+            true,
+            BTreeMap::new(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(read_attempt_token(&path), Some(token.clone()));
+        remove_attempt_config(&pool, attempt.id).await;
+        assert!(!path.exists());
+        assert!(authenticate_mcp_token(&pool, &jwt_config, &token)
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn test_mcp_config_arg_quotes_paths_with_spaces() {
+        assert_eq!(
+            mcp_config_arg(Path::new("/tmp/forge/a.json")),
+            "--mcp-config /tmp/forge/a.json"
+        );
+        assert_eq!(
+            mcp_config_arg(Path::new("/Users/Jane Doe/tmp/a.json")),
+            "--mcp-config \"/Users/Jane Doe/tmp/a.json\""
+        );
+    }
+
+    #[test]
+    fn test_only_cli_flag_executors_support_injection() {
+        assert!(supports_injection(&ExecutorConfig::Claude));
+        assert!(supports_injection(&ExecutorConfig::ClaudeCodeRouter));
+        assert!(!supports_injection(&ExecutorConfig::Gemini));
+        assert!(!supports_injection(&ExecutorConfig::Echo));
+    }
+}
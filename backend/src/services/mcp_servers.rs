@@ -0,0 +1,342 @@
+//! MCP servers defined per project and per user.
+//!
+//! Definitions live in `mcp_servers`; their environment values and HTTP
+//! headers are secrets, encrypted in the secret store under the server's own
+//! scope. They are only ever written out in plain text into the per-attempt
+//! MCP config of an attempt that uses them (see `mcp_injection`), never into an
+//! agent's global config file.
+
+use std::collections::{BTreeMap, HashSet};
+
+use sqlx::SqlitePool;
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::{
+    models::mcp_server::{McpServer, McpTransport, UpsertMcpServer},
+    security::{
+        secret_store::{is_masked, SecretStore, SecretStoreError, SecretSummary},
+        token_encryption::SecureString,
+    },
+    services::{execution_env::validate_env_var_name, mcp_injection::FORGE_MCP_SERVER_NAME},
+};
+
+const ENV_PREFIX: &str = "env:";
+const HEADER_PREFIX: &str = "header:";
+const MAX_NAME_LENGTH: usize = 64;
+
+#[derive(Debug, Error)]
+pub enum McpServerError {
+    #[error("{0}")]
+    Invalid(String),
+    #[error("Secret store error: {0}")]
+    Secret(#[from] SecretStoreError),
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+/// Secret store scope holding a server's environment values and headers
+pub fn secret_scope(server_id: Uuid) -> String {
+    format!("mcp_server:{}", server_id)
+}
+
+/// Check a definition before it is stored. Values the client sent back masked
+/// are placeholders for stored ones and accepted as is.
+pub fn validate(
+    data: &UpsertMcpServer,
+    env: &BTreeMap<String, String>,
+    headers: &BTreeMap<String, String>,
+) -> Result<(), String> {
+    let name_valid = !data.name.is_empty()
+        && data.name.len() <= MAX_NAME_LENGTH
+        && data
+            .name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !name_valid {
+        return Err(format!(
+            "Server names must be 1-{} letters, digits, '-' or '_'",
+            MAX_NAME_LENGTH
+        ));
+    }
+    if data.name == FORGE_MCP_SERVER_NAME {
+        return Err(format!(
+            "'{}' is reserved for the Forge task server",
+            FORGE_MCP_SERVER_NAME
+        ));
+    }
+
+    match data.transport {
+        McpTransport::Stdio => {
+            if !data
+                .command
+                .as_deref()
+                .is_some_and(|command| !command.trim().is_empty())
+            {
+                return Err("STDIO servers need a command".to_string());
+            }
+            if data.url.is_some() {
+                return Err("STDIO servers take a command, not a URL".to_string());
+            }
+            if !headers.is_empty() {
+                return Err("Headers only apply to HTTP and SSE servers".to_string());
+            }
+        }
+        McpTransport::Http | McpTransport::Sse => {
+            let url = data.url.as_deref().unwrap_or_default();
+            if !(url.starts_with("https://") || url.starts_with("http://")) {
+                return Err("HTTP and SSE servers need an http(s) URL".to_string());
+            }
+            if data.command.is_some() || !data.args.is_empty() {
+                return Err("HTTP and SSE servers take a URL, not a command".to_string());
+            }
+        }
+    }
+
+    for name in env.keys() {
+        validate_env_var_name(name).map_err(str::to_string)?;
+    }
+    for name in headers.keys() {
+        let valid = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(format!("Invalid header name '{}'", name));
+        }
+    }
+    Ok(())
+}
+
+/// Store a definition and its secrets, replacing the server of the same name
+/// in the same scope. Secrets missing from `env` and `headers` are deleted.
+pub async fn save(
+    pool: &SqlitePool,
+    store: &SecretStore,
+    project_id: Uuid,
+    user_id: Option<Uuid>,
+    data: &UpsertMcpServer,
+    env: &BTreeMap<String, String>,
+    headers: &BTreeMap<String, String>,
+) -> Result<McpServer, McpServerError> {
+    validate(data, env, headers).map_err(McpServerError::Invalid)?;
+
+    let server = McpServer::upsert(pool, project_id, user_id, data).await?;
+    let scope = secret_scope(server.id);
+
+    let wanted: Vec<(String, &String)> = env
+        .iter()
+        .map(|(name, value)| (format!("{}{}", ENV_PREFIX, name), value))
+        .chain(
+            headers
+                .iter()
+                .map(|(name, value)| (format!("{}{}", HEADER_PREFIX, name), value)),
+        )
+        .collect();
+    let wanted_names: HashSet<&str> = wanted.iter().map(|(name, _)| name.as_str()).collect();
+
+    for stored in store.list_masked(&scope).await? {
+        if !wanted_names.contains(stored.name.as_str()) {
+            store.delete(&scope, &stored.name).await?;
+        }
+    }
+    for (name, value) in &wanted {
+        // Masked values stand for what is already stored
+        if !is_masked(value) {
+            store.put(&scope, name, value).await?;
+        }
+    }
+    Ok(server)
+}
+
+/// Delete a server and its secrets
+pub async fn remove(
+    pool: &SqlitePool,
+    store: &SecretStore,
+    server: &McpServer,
+) -> Result<(), McpServerError> {
+    let scope = secret_scope(server.id);
+    for stored in store.list_masked(&scope).await? {
+        store.delete(&scope, &stored.name).await?;
+    }
+    McpServer::delete(pool, server.id).await?;
+    Ok(())
+}
+
+/// A server's environment values and headers, masked
+pub async fn masked_secrets(
+    store: &SecretStore,
+    server_id: Uuid,
+) -> Result<(Vec<SecretSummary>, Vec<SecretSummary>), McpServerError> {
+    let mut env = Vec::new();
+    let mut headers = Vec::new();
+    for mut secret in store.list_masked(&secret_scope(server_id)).await? {
+        if let Some(name) = secret.name.strip_prefix(ENV_PREFIX) {
+            secret.name = name.to_string();
+            env.push(secret);
+        } else if let Some(name) = secret.name.strip_prefix(HEADER_PREFIX) {
+            secret.name = name.to_string();
+            headers.push(secret);
+        }
+    }
+    Ok((env, headers))
+}
+
+/// The servers an attempt of `user_id` gets, as `mcpServers` entries: the
+/// project's servers with the user's overrides applied. A disabled override
+/// turns the project server off for that user.
+pub async fn resolve_for_attempt(
+    pool: &SqlitePool,
+    store: &SecretStore,
+    project_id: Uuid,
+    user_id: Option<Uuid>,
+) -> Result<BTreeMap<String, serde_json::Value>, McpServerError> {
+    let mut effective: BTreeMap<String, McpServer> = BTreeMap::new();
+    for server in McpServer::find_for_project(pool, project_id, user_id).await? {
+        // Project rows come before the user's row of the same name
+        effective.insert(server.name.clone(), server);
+    }
+
+    let mut entries = BTreeMap::new();
+    for (name, server) in effective {
+        if !server.enabled {
+            continue;
+        }
+        let mut env = BTreeMap::new();
+        let mut headers = BTreeMap::new();
+        for (secret_name, value) in store.get_all(&secret_scope(server.id)).await? {
+            if let Some(name) = secret_name.strip_prefix(ENV_PREFIX) {
+                env.insert(name.to_string(), value);
+            } else if let Some(name) = secret_name.strip_prefix(HEADER_PREFIX) {
+                headers.insert(name.to_string(), value);
+            }
+        }
+        entries.insert(name, server_entry(&server, &env, &headers));
+    }
+    Ok(entries)
+}
+
+/// A server in the `mcpServers` format of the agent CLIs
+fn server_entry(
+    server: &McpServer,
+    env: &BTreeMap<String, SecureString>,
+    headers: &BTreeMap<String, SecureString>,
+) -> serde_json::Value {
+    let plain =
+        |values: &BTreeMap<String, SecureString>| -> serde_json::Map<String, serde_json::Value> {
+            values
+                .iter()
+                .map(|(name, value)| (name.clone(), serde_json::Value::from(value.as_str())))
+                .collect()
+        };
+    match server.transport {
+        McpTransport::Stdio => serde_json::json!({
+            "type": "stdio",
+            "command": server.command,
+            "args": server.args,
+            "env": plain(env),
+        }),
+        McpTransport::Http | McpTransport::Sse => serde_json::json!({
+            "type": server.transport,
+            "url": server.url,
+            "headers": plain(headers),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::*;
+
+    fn stdio(name: &str, command: Option<&str>) -> UpsertMcpServer {
+        UpsertMcpServer {
+            name: name.to_string(),
+            transport: McpTransport::Stdio,
+            command: command.map(str::to_string),
+            args: vec!["-y".to_string(), "server".to_string()],
+            url: None,
+            enabled: true,
+        }
+    }
+
+    fn http(url: &str) -> UpsertMcpServer {
+        UpsertMcpServer {
+            name: "remote".to_string(),
+            transport: McpTransport::Http,
+            command: None,
+            args: Vec::new(),
+            url: Some(url.to_string()),
+            enabled: true,
+        }
+    }
+
+    fn values(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_validate_definitions() {
+        let none = BTreeMap::new();
+        assert!(validate(
+            &stdio("github", Some("npx")),
+            &values(&[("GITHUB_TOKEN", "x")]),
+            &none
+        )
+        .is_ok());
+        assert!(validate(
+            &http("https://mcp.example.com/mcp"),
+            &none,
+            &values(&[("Authorization", "x")])
+        )
+        .is_ok());
+
+        assert!(validate(&stdio("github", None), &none, &none).is_err());
+        assert!(validate(&stdio("has space", Some("npx")), &none, &none).is_err());
+        assert!(validate(&stdio(FORGE_MCP_SERVER_NAME, Some("npx")), &none, &none).is_err());
+        assert!(validate(
+            &stdio("github", Some("npx")),
+            &values(&[("BAD-NAME", "x")]),
+            &none
+        )
+        .is_err());
+        assert!(validate(
+            &stdio("github", Some("npx")),
+            &none,
+            &values(&[("Authorization", "x")])
+        )
+        .is_err());
+        assert!(validate(&http("ftp://example.com"), &none, &none).is_err());
+    }
+
+    #[test]
+    fn test_server_entries() {
+        let server = McpServer {
+            id: Uuid::new_v4(),
+            project_id: Uuid::new_v4(),
+            user_id: None,
+            name: "remote".to_string(),
+            transport: McpTransport::Sse,
+            command: None,
+            args: Vec::new(),
+            url: Some("https://mcp.example.com/sse".to_string()),
+            enabled: true,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        let headers = BTreeMap::from([(
+            "Authorization".to_string(),
+            SecureString::new("Bearer secret".to_string()),
+        )]);
+
+        let entry = server_entry(&server, &BTreeMap::new(), &headers);
+        assert_eq!(entry["type"], "sse");
+        assert_eq!(entry["url"], "https://mcp.example.com/sse");
+        assert_eq!(entry["headers"]["Authorization"], "Bearer secret");
+        assert!(entry.get("command").is_none());
+    }
+}
@@ -1,17 +1,34 @@
 pub mod analytics;
+pub mod execution_env;
+pub mod forge;
 pub mod git_service;
+pub mod gitea_service;
+pub mod github_issues;
 pub mod github_service;
+pub mod github_webhooks;
+pub mod gitlab_service;
+pub mod mcp_injection;
+pub mod mcp_servers;
+pub mod notification_channels;
+pub mod notification_rules;
 pub mod notification_service;
+pub mod pr_description;
 pub mod pr_monitor;
 pub mod process_service;
+pub mod review_followups;
+pub mod task_plan;
+pub mod webhooks;
+pub mod whatsapp_commands;
 pub mod whatsapp_config;
 pub mod whatsapp_notifier;
+pub mod whatsapp_outbox;
 
 pub use analytics::{generate_user_id, AnalyticsConfig, AnalyticsService};
+pub use execution_env::ExecutionEnv;
 pub use git_service::{GitService, GitServiceError};
 pub use github_service::{CreatePrRequest, GitHubRepoInfo, GitHubService, GitHubServiceError};
 pub use notification_service::{NotificationConfig, NotificationService};
 pub use pr_monitor::PrMonitorService;
 pub use process_service::ProcessService;
 pub use whatsapp_config::WhatsAppConfig;
-pub use whatsapp_notifier::WhatsAppNotifier;
+pub use whatsapp_notifier::{WhatsAppClient, WhatsAppNotifier};
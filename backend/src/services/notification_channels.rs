@@ -0,0 +1,796 @@
+//! Notification channels: where a notification is delivered beyond the local
+//! sound and desktop alerts.
+//!
+//! Every backend implements `NotificationChannel`. The install-wide WhatsApp
+//! notifier is one; the others are configured per user in
+//! `user_notification_channels`, so each teammate is told about their own
+//! tasks on their own channel. A user channel's webhook URLs, passwords and
+//! tokens are secrets, encrypted in the secret store under the channel's own
+//! scope.
+
+use std::{collections::BTreeMap, time::Duration};
+
+use async_trait::async_trait;
+use lettre::{
+    message::Mailbox, transport::smtp::authentication::Credentials, AsyncSmtpTransport,
+    AsyncTransport, Message, Tokio1Executor,
+};
+use sqlx::SqlitePool;
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::{
+    models::user_notification_channel::{
+        NotificationChannelKind, UpsertUserNotificationChannel, UserNotificationChannel,
+    },
+    security::{
+        secret_store::{
+            is_masked, SecretStore, SecretStoreError, SecretSummary, CONFIG_SCOPE,
+            EVOLUTION_API_KEY_SECRET,
+        },
+        token_encryption::SecureString,
+    },
+    services::{whatsapp_commands, WhatsAppConfig, WhatsAppNotifier},
+};
+
+const MAX_NAME_LENGTH: usize = 64;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+const DISCORD_MAX_CONTENT: usize = 2000;
+const DEFAULT_NTFY_SERVER: &str = "https://ntfy.sh";
+const DEFAULT_SMTP_PORT: u16 = 587;
+
+/// A notification, independent of where it is delivered
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub title: String,
+    pub message: String,
+    /// Link to the task or attempt the notification is about
+    pub url: Option<String>,
+}
+
+#[derive(Debug, Error)]
+pub enum NotificationChannelError {
+    #[error("{0}")]
+    Invalid(String),
+    #[error("Request failed: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("{channel} responded with HTTP {status}")]
+    Status { channel: &'static str, status: u16 },
+    #[error("Failed to send email: {0}")]
+    Email(String),
+    #[error("Failed to send WhatsApp message: {0}")]
+    WhatsApp(String),
+    #[error("Secret store error: {0}")]
+    Secret(#[from] SecretStoreError),
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+/// A place notifications can be delivered to
+#[async_trait]
+pub trait NotificationChannel: Send + Sync + std::fmt::Debug {
+    /// Short name of the backend, for logs
+    fn kind(&self) -> &'static str;
+
+    async fn send(&self, notification: &Notification) -> Result<(), NotificationChannelError>;
+}
+
+/// Slack incoming webhook
+#[derive(Debug)]
+pub struct SlackChannel {
+    client: reqwest::Client,
+    webhook_url: SecureString,
+}
+
+#[async_trait]
+impl NotificationChannel for SlackChannel {
+    fn kind(&self) -> &'static str {
+        "slack"
+    }
+
+    async fn send(&self, notification: &Notification) -> Result<(), NotificationChannelError> {
+        let response = self
+            .client
+            .post(self.webhook_url.as_str())
+            .json(&slack_payload(notification))
+            .send()
+            .await?;
+        check_status(self.kind(), response.status())
+    }
+}
+
+/// Discord webhook
+#[derive(Debug)]
+pub struct DiscordChannel {
+    client: reqwest::Client,
+    webhook_url: SecureString,
+}
+
+#[async_trait]
+impl NotificationChannel for DiscordChannel {
+    fn kind(&self) -> &'static str {
+        "discord"
+    }
+
+    async fn send(&self, notification: &Notification) -> Result<(), NotificationChannelError> {
+        let response = self
+            .client
+            .post(self.webhook_url.as_str())
+            .json(&discord_payload(notification))
+            .send()
+            .await?;
+        check_status(self.kind(), response.status())
+    }
+}
+
+/// How the SMTP connection is secured
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SmtpSecurity {
+    StartTls,
+    Tls,
+    None,
+}
+
+impl SmtpSecurity {
+    fn parse(value: Option<&str>) -> Option<Self> {
+        match value.unwrap_or("starttls") {
+            "starttls" => Some(SmtpSecurity::StartTls),
+            "tls" => Some(SmtpSecurity::Tls),
+            "none" => Some(SmtpSecurity::None),
+            _ => None,
+        }
+    }
+}
+
+/// Email through an SMTP server
+#[derive(Debug)]
+pub struct EmailChannel {
+    host: String,
+    port: u16,
+    security: SmtpSecurity,
+    username: Option<String>,
+    password: Option<SecureString>,
+    from: Mailbox,
+    to: Vec<Mailbox>,
+}
+
+#[async_trait]
+impl NotificationChannel for EmailChannel {
+    fn kind(&self) -> &'static str {
+        "email"
+    }
+
+    async fn send(&self, notification: &Notification) -> Result<(), NotificationChannelError> {
+        let mut builder = Message::builder()
+            .from(self.from.clone())
+            .subject(notification.title.clone());
+        for to in &self.to {
+            builder = builder.to(to.clone());
+        }
+        let email = builder
+            .body(plain_text(notification))
+            .map_err(|e| NotificationChannelError::Email(e.to_string()))?;
+
+        let transport = match self.security {
+            SmtpSecurity::StartTls => {
+                AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&self.host)
+                    .map_err(|e| NotificationChannelError::Email(e.to_string()))?
+            }
+            SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&self.host)
+                .map_err(|e| NotificationChannelError::Email(e.to_string()))?,
+            SmtpSecurity::None => {
+                AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&self.host)
+            }
+        };
+        let mut transport = transport.port(self.port).timeout(Some(REQUEST_TIMEOUT));
+        if let Some(username) = &self.username {
+            let password = self
+                .password
+                .as_ref()
+                .map(|password| password.as_str().to_string())
+                .unwrap_or_default();
+            transport = transport.credentials(Credentials::new(username.clone(), password));
+        }
+
+        transport
+            .build()
+            .send(email)
+            .await
+            .map_err(|e| NotificationChannelError::Email(e.to_string()))?;
+        Ok(())
+    }
+}
+
+/// ntfy topic, on ntfy.sh or a self-hosted server
+#[derive(Debug)]
+pub struct NtfyChannel {
+    client: reqwest::Client,
+    server_url: String,
+    topic: String,
+    token: Option<SecureString>,
+}
+
+#[async_trait]
+impl NotificationChannel for NtfyChannel {
+    fn kind(&self) -> &'static str {
+        "ntfy"
+    }
+
+    async fn send(&self, notification: &Notification) -> Result<(), NotificationChannelError> {
+        // JSON publishing keeps non-ASCII titles out of HTTP headers
+        let mut request = self
+            .client
+            .post(self.server_url.trim_end_matches('/'))
+            .json(&ntfy_payload(&self.topic, notification));
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token.as_str());
+        }
+        let response = request.send().await?;
+        check_status(self.kind(), response.status())
+    }
+}
+
+/// Gotify application
+#[derive(Debug)]
+pub struct GotifyChannel {
+    client: reqwest::Client,
+    server_url: String,
+    app_token: SecureString,
+}
+
+#[async_trait]
+impl NotificationChannel for GotifyChannel {
+    fn kind(&self) -> &'static str {
+        "gotify"
+    }
+
+    async fn send(&self, notification: &Notification) -> Result<(), NotificationChannelError> {
+        let response = self
+            .client
+            .post(format!("{}/message", self.server_url.trim_end_matches('/')))
+            .header("X-Gotify-Key", self.app_token.as_str())
+            .json(&gotify_payload(notification))
+            .send()
+            .await?;
+        check_status(self.kind(), response.status())
+    }
+}
+
+fn check_status(
+    channel: &'static str,
+    status: reqwest::StatusCode,
+) -> Result<(), NotificationChannelError> {
+    if status.is_success() {
+        Ok(())
+    } else {
+        Err(NotificationChannelError::Status {
+            channel,
+            status: status.as_u16(),
+        })
+    }
+}
+
+fn plain_text(notification: &Notification) -> String {
+    match &notification.url {
+        Some(url) => format!("{}\n\n{}", notification.message, url),
+        None => notification.message.clone(),
+    }
+}
+
+fn slack_payload(notification: &Notification) -> serde_json::Value {
+    let mut text = format!("*{}*\n{}", notification.title, notification.message);
+    if let Some(url) = &notification.url {
+        text.push_str(&format!("\n<{}|Open in Forge>", url));
+    }
+    serde_json::json!({ "text": text })
+}
+
+fn discord_payload(notification: &Notification) -> serde_json::Value {
+    let mut content = format!("**{}**\n{}", notification.title, notification.message);
+    if let Some(url) = &notification.url {
+        content.push_str(&format!("\n{}", url));
+    }
+    if content.chars().count() > DISCORD_MAX_CONTENT {
+        content = content.chars().take(DISCORD_MAX_CONTENT - 1).collect();
+        content.push('…');
+    }
+    serde_json::json!({ "content": content, "username": "Automagik Forge" })
+}
+
+fn ntfy_payload(topic: &str, notification: &Notification) -> serde_json::Value {
+    let mut payload = serde_json::json!({
+        "topic": topic,
+        "title": notification.title,
+        "message": notification.message,
+    });
+    if let Some(url) = &notification.url {
+        payload["click"] = serde_json::json!(url);
+    }
+    payload
+}
+
+fn gotify_payload(notification: &Notification) -> serde_json::Value {
+    let mut payload = serde_json::json!({
+        "title": notification.title,
+        "message": notification.message,
+        "priority": 5,
+    });
+    if let Some(url) = &notification.url {
+        payload["extras"] = serde_json::json!({
+            "client::notification": { "click": { "url": url } }
+        });
+    }
+    payload
+}
+
+/// Secret store scope holding a user channel's secrets
+pub fn secret_scope(channel_id: Uuid) -> String {
+    format!("notification_channel:{}", channel_id)
+}
+
+/// Field names, each marked required or not
+type FieldSpec = &'static [(&'static str, bool)];
+
+/// Settings and secrets a kind takes
+fn fields(kind: NotificationChannelKind) -> (FieldSpec, FieldSpec) {
+    match kind {
+        NotificationChannelKind::Slack | NotificationChannelKind::Discord => {
+            (&[], &[("webhook_url", true)])
+        }
+        NotificationChannelKind::Email => (
+            &[
+                ("smtp_host", true),
+                ("smtp_port", false),
+                ("security", false),
+                ("username", false),
+                ("from", true),
+                ("to", true),
+            ],
+            &[("password", false)],
+        ),
+        NotificationChannelKind::Ntfy => (
+            &[("server_url", false), ("topic", true)],
+            &[("token", false)],
+        ),
+        NotificationChannelKind::Gotify => (&[("server_url", true)], &[("app_token", true)]),
+        NotificationChannelKind::WhatsApp => (&[("number", true)], &[]),
+    }
+}
+
+fn is_http_url(value: &str) -> bool {
+    value.starts_with("https://") || value.starts_with("http://")
+}
+
+/// Check a channel before it is stored. Secrets the client sent back masked
+/// are placeholders for stored ones and accepted as is.
+pub fn validate(
+    data: &UpsertUserNotificationChannel,
+    secrets: &BTreeMap<String, String>,
+) -> Result<(), String> {
+    let name_valid = !data.name.is_empty()
+        && data.name.len() <= MAX_NAME_LENGTH
+        && data
+            .name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !name_valid {
+        return Err(format!(
+            "Channel names must be 1-{} letters, digits, '-' or '_'",
+            MAX_NAME_LENGTH
+        ));
+    }
+
+    let (setting_fields, secret_fields) = fields(data.kind);
+    for (values, known, what) in [
+        (&data.settings, setting_fields, "setting"),
+        (secrets, secret_fields, "secret"),
+    ] {
+        for name in values.keys() {
+            if !known.iter().any(|(field, _)| *field == name.as_str()) {
+                return Err(format!(
+                    "{} channels take no '{}' {}",
+                    data.kind, name, what
+                ));
+            }
+        }
+        for (field, required) in known {
+            let missing = values
+                .get(*field)
+                .is_none_or(|value| value.trim().is_empty());
+            if *required && missing {
+                return Err(format!("{} channels need '{}'", data.kind, field));
+            }
+        }
+    }
+
+    let setting = |name: &str| data.settings.get(name).map(String::as_str);
+    if let Some(url) = secrets.get("webhook_url").filter(|url| !is_masked(url)) {
+        if !is_http_url(url) {
+            return Err("Webhook URLs must be http(s) URLs".to_string());
+        }
+    }
+    if let Some(url) = setting("server_url") {
+        if !is_http_url(url) {
+            return Err("Server URLs must be http(s) URLs".to_string());
+        }
+    }
+    if let Some(port) = setting("smtp_port") {
+        if port.parse::<u16>().is_err() {
+            return Err("SMTP ports must be numbers".to_string());
+        }
+    }
+    if SmtpSecurity::parse(setting("security")).is_none() {
+        return Err("SMTP security must be 'starttls', 'tls' or 'none'".to_string());
+    }
+    for name in ["from", "to"] {
+        if let Some(addresses) = setting(name) {
+            parse_mailboxes(addresses)?;
+        }
+    }
+    if let Some(number) = setting("number") {
+        let digits = number.strip_prefix('+').unwrap_or(number);
+        if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return Err("WhatsApp numbers must be digits with an optional leading '+'".to_string());
+        }
+    }
+    Ok(())
+}
+
+/// Comma-separated email addresses
+fn parse_mailboxes(addresses: &str) -> Result<Vec<Mailbox>, String> {
+    addresses
+        .split(',')
+        .map(str::trim)
+        .filter(|address| !address.is_empty())
+        .map(|address| {
+            address
+                .parse::<Mailbox>()
+                .map_err(|_| format!("Invalid email address '{}'", address))
+        })
+        .collect()
+}
+
+/// Store a channel and its secrets, replacing the user's channel of the same
+/// name. Secrets missing from `secrets` are deleted. WhatsApp numbers another
+/// user verified are refused.
+pub async fn save(
+    pool: &SqlitePool,
+    store: &SecretStore,
+    user_id: Uuid,
+    data: &UpsertUserNotificationChannel,
+    secrets: &BTreeMap<String, String>,
+) -> Result<UserNotificationChannel, NotificationChannelError> {
+    validate(data, secrets).map_err(NotificationChannelError::Invalid)?;
+    if let Some(number) = data
+        .settings
+        .get("number")
+        .filter(|_| data.kind == NotificationChannelKind::WhatsApp)
+    {
+        if whatsapp_commands::claimed_by_other(pool, user_id, number).await? {
+            return Err(NotificationChannelError::Invalid(
+                "This WhatsApp number is already verified by another user".to_string(),
+            ));
+        }
+    }
+
+    let channel = UserNotificationChannel::upsert(pool, user_id, data).await?;
+    whatsapp_commands::forget_unused_numbers(pool, user_id).await?;
+    let scope = secret_scope(channel.id);
+    for stored in store.list_masked(&scope).await? {
+        if !secrets.contains_key(&stored.name) {
+            store.delete(&scope, &stored.name).await?;
+        }
+    }
+    for (name, value) in secrets {
+        // Masked values stand for what is already stored
+        if !is_masked(value) {
+            store.put(&scope, name, value).await?;
+        }
+    }
+    Ok(channel)
+}
+
+/// Delete a channel and its secrets
+pub async fn remove(
+    pool: &SqlitePool,
+    store: &SecretStore,
+    channel: &UserNotificationChannel,
+) -> Result<(), NotificationChannelError> {
+    let scope = secret_scope(channel.id);
+    for stored in store.list_masked(&scope).await? {
+        store.delete(&scope, &stored.name).await?;
+    }
+    UserNotificationChannel::delete(pool, channel.id).await?;
+    whatsapp_commands::forget_unused_numbers(pool, channel.user_id).await?;
+    Ok(())
+}
+
+/// A channel's secrets, masked
+pub async fn masked_secrets(
+    store: &SecretStore,
+    channel_id: Uuid,
+) -> Result<Vec<SecretSummary>, NotificationChannelError> {
+    Ok(store.list_masked(&secret_scope(channel_id)).await?)
+}
+
+/// The install's Evolution API settings, which users' WhatsApp channels send
+/// through to their own number
+pub async fn whatsapp_config(store: &SecretStore) -> Option<WhatsAppConfig> {
+    let stored_api_key = store
+        .get(CONFIG_SCOPE, EVOLUTION_API_KEY_SECRET)
+        .await
+        .unwrap_or_else(|e| {
+            tracing::warn!("Failed to read WhatsApp API key from secret store: {}", e);
+            None
+        });
+    WhatsAppConfig::from_env_with_api_key(stored_api_key.as_ref().map(SecureString::as_str)).ok()
+}
+
+fn http_client() -> reqwest::Client {
+    reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .unwrap_or_default()
+}
+
+/// The backend for a user channel
+pub async fn build(
+    pool: &SqlitePool,
+    store: &SecretStore,
+    channel: &UserNotificationChannel,
+    whatsapp: Option<&WhatsAppConfig>,
+) -> Result<Box<dyn NotificationChannel>, NotificationChannelError> {
+    let mut secrets: BTreeMap<String, SecureString> = store
+        .get_all(&secret_scope(channel.id))
+        .await?
+        .into_iter()
+        .collect();
+    let setting = |name: &str| {
+        channel
+            .settings
+            .get(name)
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+    let missing = |name: &str| {
+        NotificationChannelError::Invalid(format!(
+            "Channel '{}' has no '{}' configured",
+            channel.name, name
+        ))
+    };
+
+    let backend: Box<dyn NotificationChannel> = match channel.kind {
+        NotificationChannelKind::Slack => Box::new(SlackChannel {
+            client: http_client(),
+            webhook_url: secrets
+                .remove("webhook_url")
+                .ok_or_else(|| missing("webhook_url"))?,
+        }),
+        NotificationChannelKind::Discord => Box::new(DiscordChannel {
+            client: http_client(),
+            webhook_url: secrets
+                .remove("webhook_url")
+                .ok_or_else(|| missing("webhook_url"))?,
+        }),
+        NotificationChannelKind::Email => {
+            let security = SmtpSecurity::parse(setting("security").as_deref())
+                .ok_or_else(|| missing("security"))?;
+            let from = parse_mailboxes(&setting("from").ok_or_else(|| missing("from"))?)
+                .map_err(NotificationChannelError::Invalid)?
+                .into_iter()
+                .next()
+                .ok_or_else(|| missing("from"))?;
+            Box::new(EmailChannel {
+                host: setting("smtp_host").ok_or_else(|| missing("smtp_host"))?,
+                port: setting("smtp_port")
+                    .and_then(|port| port.parse().ok())
+                    .unwrap_or(DEFAULT_SMTP_PORT),
+                security,
+                username: setting("username"),
+                password: secrets.remove("password"),
+                from,
+                to: parse_mailboxes(&setting("to").ok_or_else(|| missing("to"))?)
+                    .map_err(NotificationChannelError::Invalid)?,
+            })
+        }
+        NotificationChannelKind::Ntfy => Box::new(NtfyChannel {
+            client: http_client(),
+            server_url: setting("server_url").unwrap_or_else(|| DEFAULT_NTFY_SERVER.to_string()),
+            topic: setting("topic").ok_or_else(|| missing("topic"))?,
+            token: secrets.remove("token"),
+        }),
+        NotificationChannelKind::Gotify => Box::new(GotifyChannel {
+            client: http_client(),
+            server_url: setting("server_url").ok_or_else(|| missing("server_url"))?,
+            app_token: secrets
+                .remove("app_token")
+                .ok_or_else(|| missing("app_token"))?,
+        }),
+        NotificationChannelKind::WhatsApp => {
+            let config = whatsapp.ok_or_else(|| {
+                NotificationChannelError::Invalid(
+                    "WhatsApp is not configured on this server".to_string(),
+                )
+            })?;
+            Box::new(WhatsAppNotifier::new(
+                pool.clone(),
+                setting("number").ok_or_else(|| missing("number"))?,
+                Some(channel.user_id),
+                config.include_task_url,
+            ))
+        }
+    };
+    Ok(backend)
+}
+
+/// Send `notification` to a user's enabled channels named in `channel_names`,
+/// or to all of them when it is empty. Failures are logged per channel and
+/// never stop the others.
+pub async fn notify_user(
+    pool: &SqlitePool,
+    store: &SecretStore,
+    user_id: Uuid,
+    channel_names: &[String],
+    notification: &Notification,
+) {
+    let channels = match UserNotificationChannel::find_by_user_id(pool, user_id).await {
+        Ok(channels) => channels,
+        Err(e) => {
+            tracing::error!(
+                "Failed to load notification channels of user {}: {}",
+                user_id,
+                e
+            );
+            return;
+        }
+    };
+    let mut whatsapp: Option<Option<WhatsAppConfig>> = None;
+    for channel in channels.iter().filter(|channel| {
+        channel.enabled && (channel_names.is_empty() || channel_names.contains(&channel.name))
+    }) {
+        if channel.kind == NotificationChannelKind::WhatsApp && whatsapp.is_none() {
+            whatsapp = Some(whatsapp_config(store).await);
+        }
+        let result = match build(
+            pool,
+            store,
+            channel,
+            whatsapp.as_ref().and_then(Option::as_ref),
+        )
+        .await
+        {
+            Ok(backend) => backend.send(notification).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            tracing::warn!(
+                "Failed to notify user {} on channel '{}': {}",
+                user_id,
+                channel.name,
+                e
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn notification(url: Option<&str>) -> Notification {
+        Notification {
+            title: "Task Complete: Add login".to_string(),
+            message: "✅ 'Add login' completed successfully".to_string(),
+            url: url.map(str::to_string),
+        }
+    }
+
+    fn channel(
+        kind: NotificationChannelKind,
+        settings: &[(&str, &str)],
+    ) -> UpsertUserNotificationChannel {
+        UpsertUserNotificationChannel {
+            name: "mine".to_string(),
+            kind,
+            settings: values(settings),
+            enabled: true,
+        }
+    }
+
+    fn values(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_validate_channels() {
+        let none = BTreeMap::new();
+        let hook = values(&[("webhook_url", "https://hooks.slack.com/services/T/B/X")]);
+        assert!(validate(&channel(NotificationChannelKind::Slack, &[]), &hook).is_ok());
+        assert!(validate(&channel(NotificationChannelKind::Slack, &[]), &none).is_err());
+        assert!(validate(
+            &channel(NotificationChannelKind::Discord, &[]),
+            &values(&[("webhook_url", "ftp://example.com")])
+        )
+        .is_err());
+
+        let email = [
+            ("smtp_host", "smtp.example.com"),
+            ("from", "Forge <forge@example.com>"),
+            ("to", "me@example.com, you@example.com"),
+        ];
+        assert!(validate(&channel(NotificationChannelKind::Email, &email), &none).is_ok());
+        let mut bad_port = email.to_vec();
+        bad_port.push(("smtp_port", "smtp"));
+        assert!(validate(&channel(NotificationChannelKind::Email, &bad_port), &none).is_err());
+        let mut bad_to = email.to_vec();
+        bad_to[2] = ("to", "not an address");
+        assert!(validate(&channel(NotificationChannelKind::Email, &bad_to), &none).is_err());
+
+        assert!(validate(
+            &channel(NotificationChannelKind::Ntfy, &[("topic", "forge")]),
+            &none
+        )
+        .is_ok());
+        assert!(validate(
+            &channel(
+                NotificationChannelKind::Ntfy,
+                &[("topic", "forge"), ("channel", "x")]
+            ),
+            &none
+        )
+        .is_err());
+        assert!(validate(
+            &channel(
+                NotificationChannelKind::WhatsApp,
+                &[("number", "+5511999999999")]
+            ),
+            &none
+        )
+        .is_ok());
+        assert!(validate(
+            &channel(NotificationChannelKind::WhatsApp, &[("number", "call me")]),
+            &none
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_payloads_carry_title_message_and_link() {
+        let with_url = notification(Some("http://localhost:3000/projects/p/tasks/t"));
+
+        let slack = slack_payload(&with_url);
+        let text = slack["text"].as_str().unwrap();
+        assert!(text.starts_with("*Task Complete: Add login*\n"));
+        assert!(text.ends_with("<http://localhost:3000/projects/p/tasks/t|Open in Forge>"));
+
+        assert_eq!(
+            ntfy_payload("forge", &with_url)["click"],
+            "http://localhost:3000/projects/p/tasks/t"
+        );
+        assert!(ntfy_payload("forge", &notification(None))
+            .get("click")
+            .is_none());
+        assert_eq!(
+            gotify_payload(&with_url)["extras"]["client::notification"]["click"]["url"],
+            "http://localhost:3000/projects/p/tasks/t"
+        );
+    }
+
+    #[test]
+    fn test_discord_content_is_truncated() {
+        let long = Notification {
+            title: "Title".to_string(),
+            message: "x".repeat(3000),
+            url: None,
+        };
+        let content = discord_payload(&long)["content"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        assert_eq!(content.chars().count(), DISCORD_MAX_CONTENT);
+        assert!(content.ends_with('…'));
+    }
+}
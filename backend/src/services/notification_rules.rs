@@ -0,0 +1,593 @@
+//! Routing of task notifications to the users involved in a task.
+//!
+//! Each user decides with `user_notification_rules` which events reach them,
+//! about which tasks and on which channels. A rule can hold notifications back
+//! during quiet hours or batch them into one digest every few minutes; held
+//! back notifications wait in `notification_digest_items` until
+//! `NotificationDigestService` sends them. Users without rules get every
+//! completion and failure of their tasks right away on all their channels.
+
+use std::{sync::Arc, time::Duration};
+
+use chrono::{DateTime, Timelike, Utc};
+use sqlx::SqlitePool;
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::{
+    models::{
+        task::Task,
+        user_notification_channel::UserNotificationChannel,
+        user_notification_rule::{
+            NotificationDigestItem, NotificationEvent, NotificationScope,
+            UpsertUserNotificationRule, UserNotificationRule,
+        },
+    },
+    security::secret_store::SecretStore,
+    services::notification_channels::{self, Notification},
+};
+
+const MAX_NAME_LENGTH: usize = 64;
+const MAX_DIGEST_MINUTES: i64 = 24 * 60;
+const DIGEST_POLL_INTERVAL: Duration = Duration::from_secs(60);
+/// Items listed in a digest before the rest are only counted
+const MAX_DIGEST_LINES: usize = 20;
+
+/// Events users without rules are notified about
+const DEFAULT_EVENTS: [NotificationEvent; 2] = [
+    NotificationEvent::AttemptCompleted,
+    NotificationEvent::AttemptFailed,
+];
+
+#[derive(Debug, Error)]
+pub enum NotificationRuleError {
+    #[error("{0}")]
+    Invalid(String),
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+/// How a user is related to the task an event is about
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TaskRelation {
+    /// Created the task or started the attempt
+    pub created: bool,
+    pub assigned: bool,
+}
+
+/// Link to a task in the web UI
+pub fn task_url(project_id: Uuid, task_id: Uuid) -> String {
+    let base_url =
+        std::env::var("BASE_URL").unwrap_or_else(|_| "http://localhost:3001".to_string());
+    format!(
+        "{}/projects/{}/tasks/{}",
+        base_url.trim_end_matches('/'),
+        project_id,
+        task_id
+    )
+}
+
+/// Users involved in a task and how
+fn recipients(task: &Task, attempt_created_by: Option<Uuid>) -> Vec<(Uuid, TaskRelation)> {
+    let mut recipients: Vec<(Uuid, TaskRelation)> = Vec::new();
+    let involved = [
+        (task.created_by, true),
+        (attempt_created_by, true),
+        (task.assigned_to, false),
+    ];
+    for (user_id, created) in involved {
+        let Some(user_id) = user_id else { continue };
+        let index = match recipients.iter().position(|(id, _)| *id == user_id) {
+            Some(index) => index,
+            None => {
+                recipients.push((user_id, TaskRelation::default()));
+                recipients.len() - 1
+            }
+        };
+        let relation = &mut recipients[index].1;
+        if created {
+            relation.created = true;
+        } else {
+            relation.assigned = true;
+        }
+    }
+    recipients
+}
+
+fn scope_matches(scope: NotificationScope, relation: TaskRelation) -> bool {
+    match scope {
+        NotificationScope::Created => relation.created,
+        NotificationScope::Assigned => relation.assigned,
+        NotificationScope::Involved => relation.created || relation.assigned,
+    }
+}
+
+/// Whether a rule wants `event` for a task the user has `relation` to
+pub fn rule_matches(
+    rule: &UserNotificationRule,
+    event: NotificationEvent,
+    relation: TaskRelation,
+) -> bool {
+    rule.enabled && rule.events.contains(&event) && scope_matches(rule.scope, relation)
+}
+
+/// Minutes since midnight of an 'HH:MM' time
+fn parse_time_of_day(value: &str) -> Option<i64> {
+    let (hours, minutes) = value.split_once(':')?;
+    if hours.len() != 2 || minutes.len() != 2 {
+        return None;
+    }
+    let hours: i64 = hours.parse().ok()?;
+    let minutes: i64 = minutes.parse().ok()?;
+    (hours < 24 && minutes < 60).then_some(hours * 60 + minutes)
+}
+
+/// Whether `now` falls in the rule's quiet hours, which may span midnight
+pub fn in_quiet_hours(rule: &UserNotificationRule, now: DateTime<Utc>) -> bool {
+    let (Some(start), Some(end)) = (
+        rule.quiet_hours_start
+            .as_deref()
+            .and_then(parse_time_of_day),
+        rule.quiet_hours_end.as_deref().and_then(parse_time_of_day),
+    ) else {
+        return false;
+    };
+    let utc_minutes = i64::from(now.hour() * 60 + now.minute());
+    let local = (utc_minutes + rule.utc_offset_minutes).rem_euclid(24 * 60);
+    if start <= end {
+        start <= local && local < end
+    } else {
+        local >= start || local < end
+    }
+}
+
+/// Whether a rule's held back notifications, the oldest from `oldest`, are
+/// to be sent now
+pub fn digest_due(rule: &UserNotificationRule, oldest: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+    if in_quiet_hours(rule, now) {
+        return false;
+    }
+    match rule.digest_minutes {
+        Some(minutes) => now - oldest >= chrono::Duration::minutes(minutes),
+        None => true,
+    }
+}
+
+/// One notification summarizing held back ones
+pub fn digest_notification(items: &[NotificationDigestItem]) -> Notification {
+    if let [item] = items {
+        return Notification {
+            title: item.title.clone(),
+            message: item.message.clone(),
+            url: item.url.clone(),
+        };
+    }
+
+    let mut lines: Vec<String> = items
+        .iter()
+        .take(MAX_DIGEST_LINES)
+        .map(|item| {
+            let summary = item.message.lines().next().unwrap_or_default();
+            format!("• {}: {}", item.title, summary)
+        })
+        .collect();
+    if items.len() > MAX_DIGEST_LINES {
+        lines.push(format!("…and {} more", items.len() - MAX_DIGEST_LINES));
+    }
+    Notification {
+        title: format!("{} Forge notifications", items.len()),
+        message: lines.join("\n"),
+        url: None,
+    }
+}
+
+/// Check a rule before it is stored
+pub fn validate(data: &UpsertUserNotificationRule) -> Result<(), String> {
+    let name_valid = !data.name.is_empty()
+        && data.name.len() <= MAX_NAME_LENGTH
+        && data
+            .name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !name_valid {
+        return Err(format!(
+            "Rule names must be 1-{} letters, digits, '-' or '_'",
+            MAX_NAME_LENGTH
+        ));
+    }
+    if data.events.is_empty() {
+        return Err("Rules need at least one event".to_string());
+    }
+    match (&data.quiet_hours_start, &data.quiet_hours_end) {
+        (None, None) => {}
+        (Some(start), Some(end)) => {
+            if parse_time_of_day(start).is_none() || parse_time_of_day(end).is_none() {
+                return Err("Quiet hours must be given as 'HH:MM'".to_string());
+            }
+        }
+        _ => return Err("Quiet hours need both a start and an end".to_string()),
+    }
+    // UTC-12:00 to UTC+14:00
+    if !(-12 * 60..=14 * 60).contains(&data.utc_offset_minutes) {
+        return Err("UTC offsets must be between -720 and 840 minutes".to_string());
+    }
+    if let Some(minutes) = data.digest_minutes {
+        if !(1..=MAX_DIGEST_MINUTES).contains(&minutes) {
+            return Err(format!(
+                "Digests must be sent every 1-{} minutes",
+                MAX_DIGEST_MINUTES
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Store a rule, replacing the user's rule of the same name. Every channel it
+/// names must exist.
+pub async fn save(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    data: &UpsertUserNotificationRule,
+) -> Result<UserNotificationRule, NotificationRuleError> {
+    validate(data).map_err(NotificationRuleError::Invalid)?;
+    let channels = UserNotificationChannel::find_by_user_id(pool, user_id).await?;
+    for name in &data.channels {
+        if !channels.iter().any(|channel| channel.name == *name) {
+            return Err(NotificationRuleError::Invalid(format!(
+                "No notification channel named '{}'",
+                name
+            )));
+        }
+    }
+    Ok(UserNotificationRule::upsert(pool, user_id, data).await?)
+}
+
+/// Notify everyone involved in `task` about `event`, as their rules say.
+/// Failures are logged and never reach the caller.
+pub async fn dispatch(
+    pool: &SqlitePool,
+    store: &SecretStore,
+    event: NotificationEvent,
+    task: &Task,
+    attempt_created_by: Option<Uuid>,
+    notification: &Notification,
+) {
+    let now = Utc::now();
+    for (user_id, relation) in recipients(task, attempt_created_by) {
+        let rules = match UserNotificationRule::find_by_user_id(pool, user_id).await {
+            Ok(rules) => rules,
+            Err(e) => {
+                tracing::error!(
+                    "Failed to load notification rules of user {}: {}",
+                    user_id,
+                    e
+                );
+                continue;
+            }
+        };
+
+        if rules.is_empty() {
+            if DEFAULT_EVENTS.contains(&event)
+                && scope_matches(NotificationScope::Involved, relation)
+            {
+                notification_channels::notify_user(pool, store, user_id, &[], notification).await;
+            }
+            continue;
+        }
+
+        // Channels of every rule sending right away, where `None` is all
+        let mut immediate: Option<Option<Vec<String>>> = None;
+        for rule in rules
+            .iter()
+            .filter(|rule| rule_matches(rule, event, relation))
+        {
+            if rule.digest_minutes.is_some() || in_quiet_hours(rule, now) {
+                if let Err(e) = NotificationDigestItem::create(
+                    pool,
+                    rule.id,
+                    &notification.title,
+                    &notification.message,
+                    notification.url.as_deref(),
+                )
+                .await
+                {
+                    tracing::error!(
+                        "Failed to hold back notification for rule {}: {}",
+                        rule.id,
+                        e
+                    );
+                }
+                continue;
+            }
+            immediate = Some(match (immediate, rule.channels.is_empty()) {
+                (Some(None), _) | (_, true) => None,
+                (Some(Some(mut channels)), false) => {
+                    for channel in &rule.channels {
+                        if !channels.contains(channel) {
+                            channels.push(channel.clone());
+                        }
+                    }
+                    Some(channels)
+                }
+                (None, false) => Some(rule.channels.clone()),
+            });
+        }
+
+        if let Some(channels) = immediate {
+            notification_channels::notify_user(
+                pool,
+                store,
+                user_id,
+                channels.as_deref().unwrap_or_default(),
+                notification,
+            )
+            .await;
+        }
+    }
+}
+
+/// Sends the notifications rules held back once their digest is due or
+/// their quiet hours are over
+pub struct NotificationDigestService {
+    pool: SqlitePool,
+    secret_store: Arc<SecretStore>,
+}
+
+impl NotificationDigestService {
+    pub fn new(pool: SqlitePool, secret_store: Arc<SecretStore>) -> Self {
+        Self { pool, secret_store }
+    }
+
+    pub async fn start(&self) {
+        tracing::info!(
+            "Starting notification digest service with interval {:?}",
+            DIGEST_POLL_INTERVAL
+        );
+        let mut interval = tokio::time::interval(DIGEST_POLL_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.send_due().await {
+                tracing::error!("Failed to send notification digests: {}", e);
+            }
+        }
+    }
+
+    async fn send_due(&self) -> Result<(), sqlx::Error> {
+        let now = Utc::now();
+        for rule_id in NotificationDigestItem::pending_rule_ids(&self.pool).await? {
+            let Some(rule) = UserNotificationRule::find_by_id(&self.pool, rule_id).await? else {
+                continue;
+            };
+            let items = NotificationDigestItem::find_by_rule_id(&self.pool, rule_id).await?;
+            let Some(oldest) = items.first() else {
+                continue;
+            };
+
+            // Notifications of a rule since disabled are dropped
+            if rule.enabled {
+                if !digest_due(&rule, oldest.created_at, now) {
+                    continue;
+                }
+                notification_channels::notify_user(
+                    &self.pool,
+                    &self.secret_store,
+                    rule.user_id,
+                    &rule.channels,
+                    &digest_notification(&items),
+                )
+                .await;
+            }
+            for item in &items {
+                NotificationDigestItem::delete(&self.pool, item.id).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn rule(quiet_hours: Option<(&str, &str)>, utc_offset_minutes: i64) -> UserNotificationRule {
+        UserNotificationRule {
+            id: Uuid::new_v4(),
+            user_id: Uuid::new_v4(),
+            name: "mine".to_string(),
+            events: vec![NotificationEvent::AttemptFailed],
+            scope: NotificationScope::Assigned,
+            channels: Vec::new(),
+            quiet_hours_start: quiet_hours.map(|(start, _)| start.to_string()),
+            quiet_hours_end: quiet_hours.map(|(_, end)| end.to_string()),
+            utc_offset_minutes,
+            digest_minutes: None,
+            enabled: true,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn at(hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 8, 10, hour, minute, 0).unwrap()
+    }
+
+    fn item(title: &str, message: &str) -> NotificationDigestItem {
+        NotificationDigestItem {
+            id: Uuid::new_v4(),
+            rule_id: Uuid::new_v4(),
+            title: title.to_string(),
+            message: message.to_string(),
+            url: Some("http://localhost:3001/projects/p/tasks/t".to_string()),
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_rule_matches_events_and_scope() {
+        let assigned = TaskRelation {
+            created: false,
+            assigned: true,
+        };
+        let created = TaskRelation {
+            created: true,
+            assigned: false,
+        };
+        let failures = rule(None, 0);
+        assert!(rule_matches(
+            &failures,
+            NotificationEvent::AttemptFailed,
+            assigned
+        ));
+        assert!(!rule_matches(
+            &failures,
+            NotificationEvent::AttemptCompleted,
+            assigned
+        ));
+        assert!(!rule_matches(
+            &failures,
+            NotificationEvent::AttemptFailed,
+            created
+        ));
+
+        let mut disabled = rule(None, 0);
+        disabled.enabled = false;
+        assert!(!rule_matches(
+            &disabled,
+            NotificationEvent::AttemptFailed,
+            assigned
+        ));
+    }
+
+    #[test]
+    fn test_recipients_are_deduplicated() {
+        let creator = Uuid::new_v4();
+        let assignee = Uuid::new_v4();
+        let task = Task {
+            id: Uuid::new_v4(),
+            project_id: Uuid::new_v4(),
+            title: "Add login".to_string(),
+            description: None,
+            status: crate::models::task::TaskStatus::InProgress,
+            wish_id: String::new(),
+            parent_task_attempt: None,
+            created_by: Some(creator),
+            assigned_to: Some(assignee),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+
+        let recipients = recipients(&task, Some(creator));
+        assert_eq!(recipients.len(), 2);
+        assert_eq!(
+            recipients[0],
+            (
+                creator,
+                TaskRelation {
+                    created: true,
+                    assigned: false
+                }
+            )
+        );
+        assert_eq!(
+            recipients[1],
+            (
+                assignee,
+                TaskRelation {
+                    created: false,
+                    assigned: true
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn test_quiet_hours() {
+        let overnight = rule(Some(("22:00", "07:30")), 0);
+        assert!(in_quiet_hours(&overnight, at(23, 0)));
+        assert!(in_quiet_hours(&overnight, at(3, 0)));
+        assert!(!in_quiet_hours(&overnight, at(7, 30)));
+        assert!(!in_quiet_hours(&overnight, at(12, 0)));
+
+        // 22:00-07:30 in UTC-3 is 01:00-10:30 UTC
+        let brasilia = rule(Some(("22:00", "07:30")), -180);
+        assert!(in_quiet_hours(&brasilia, at(10, 0)));
+        assert!(!in_quiet_hours(&brasilia, at(23, 0)));
+
+        let lunch = rule(Some(("12:00", "13:00")), 0);
+        assert!(in_quiet_hours(&lunch, at(12, 30)));
+        assert!(!in_quiet_hours(&lunch, at(13, 0)));
+        assert!(!in_quiet_hours(&rule(None, 0), at(12, 30)));
+    }
+
+    #[test]
+    fn test_digest_due() {
+        let mut digest = rule(None, 0);
+        digest.digest_minutes = Some(30);
+        assert!(!digest_due(&digest, at(12, 0), at(12, 29)));
+        assert!(digest_due(&digest, at(12, 0), at(12, 30)));
+
+        // Held back by quiet hours only: due as soon as they end
+        let quiet = rule(Some(("22:00", "07:00")), 0);
+        assert!(!digest_due(&quiet, at(23, 0), at(6, 59)));
+        assert!(digest_due(&quiet, at(23, 0), at(7, 0)));
+    }
+
+    #[test]
+    fn test_digest_notification() {
+        let single = digest_notification(&[item("Task Complete: A", "✅ 'A' completed")]);
+        assert_eq!(single.title, "Task Complete: A");
+        assert!(single.url.is_some());
+
+        let items: Vec<_> = (0..MAX_DIGEST_LINES + 2)
+            .map(|i| item(&format!("Task Complete: {}", i), "✅ done\nBranch: x"))
+            .collect();
+        let digest = digest_notification(&items);
+        assert_eq!(digest.title, format!("{} Forge notifications", items.len()));
+        assert!(digest.message.starts_with("• Task Complete: 0: ✅ done\n"));
+        assert!(digest.message.ends_with("…and 2 more"));
+        assert!(digest.url.is_none());
+    }
+
+    #[test]
+    fn test_validate_rules() {
+        let valid = UpsertUserNotificationRule {
+            name: "failures".to_string(),
+            events: vec![NotificationEvent::AttemptFailed],
+            scope: NotificationScope::Involved,
+            channels: Vec::new(),
+            quiet_hours_start: Some("22:00".to_string()),
+            quiet_hours_end: Some("07:00".to_string()),
+            utc_offset_minutes: -180,
+            digest_minutes: Some(30),
+            enabled: true,
+        };
+        assert!(validate(&valid).is_ok());
+        assert!(validate(&UpsertUserNotificationRule {
+            events: Vec::new(),
+            ..valid.clone()
+        })
+        .is_err());
+        assert!(validate(&UpsertUserNotificationRule {
+            quiet_hours_end: None,
+            ..valid.clone()
+        })
+        .is_err());
+        assert!(validate(&UpsertUserNotificationRule {
+            quiet_hours_start: Some("24:00".to_string()),
+            ..valid.clone()
+        })
+        .is_err());
+        assert!(validate(&UpsertUserNotificationRule {
+            digest_minutes: Some(0),
+            ..valid.clone()
+        })
+        .is_err());
+        assert!(validate(&UpsertUserNotificationRule {
+            utc_offset_minutes: 900,
+            ..valid
+        })
+        .is_err());
+    }
+}
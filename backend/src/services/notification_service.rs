@@ -1,15 +1,23 @@
 use std::sync::OnceLock;
 
-use crate::models::config::SoundFile;
-use crate::services::{WhatsAppConfig, WhatsAppNotifier};
+use sqlx::SqlitePool;
+
+use crate::{
+    models::config::SoundFile,
+    security::token_encryption::SecureString,
+    services::{
+        notification_channels::{self, NotificationChannel},
+        WhatsAppConfig, WhatsAppNotifier,
+    },
+};
 
 /// Service for handling cross-platform notifications including sound alerts and push notifications
 #[derive(Debug)]
 pub struct NotificationService {
     sound_enabled: bool,
     push_enabled: bool,
-    whatsapp_enabled: bool,
-    whatsapp_notifier: Option<WhatsAppNotifier>,
+    /// Install-wide channels every notification goes to
+    channels: Vec<Box<dyn NotificationChannel>>,
 }
 
 /// Configuration for notifications
@@ -18,6 +26,8 @@ pub struct NotificationConfig {
     pub sound_enabled: bool,
     pub push_enabled: bool,
     pub whatsapp_enabled: bool,
+    /// Evolution API key from the secret store, used when the env var is unset
+    pub whatsapp_api_key: Option<SecureString>,
 }
 
 impl Default for NotificationConfig {
@@ -26,6 +36,7 @@ impl Default for NotificationConfig {
             sound_enabled: true,
             push_enabled: true,
             whatsapp_enabled: true,
+            whatsapp_api_key: None,
         }
     }
 }
@@ -34,37 +45,40 @@ impl Default for NotificationConfig {
 static WSL_ROOT_PATH_CACHE: OnceLock<Option<String>> = OnceLock::new();
 
 impl NotificationService {
-    /// Create a new NotificationService with the given configuration
-    pub async fn new(config: NotificationConfig) -> Self {
-        let whatsapp_notifier = if config.whatsapp_enabled {
-            match WhatsAppConfig::from_env() {
+    /// Create a new NotificationService with the given configuration. WhatsApp
+    /// messages are queued in `pool`'s outbox.
+    pub async fn new(pool: SqlitePool, config: NotificationConfig) -> Self {
+        let mut channels: Vec<Box<dyn NotificationChannel>> = Vec::new();
+        if config.whatsapp_enabled {
+            let stored_api_key = config.whatsapp_api_key.as_ref().map(SecureString::as_str);
+            match WhatsAppConfig::from_env_with_api_key(stored_api_key) {
                 Ok(whatsapp_config) => {
-                    match WhatsAppNotifier::new(whatsapp_config).await {
-                        Ok(notifier) => Some(notifier),
-                        Err(e) => {
-                            tracing::warn!("Failed to initialize WhatsApp notifier: {}. WhatsApp notifications disabled.", e);
-                            None
+                    match whatsapp_config.fixed_recipient.filter(|recipient| !recipient.is_empty()) {
+                        Some(recipient) => channels.push(Box::new(WhatsAppNotifier::new(
+                            pool,
+                            recipient,
+                            None,
+                            whatsapp_config.include_task_url,
+                        ))),
+                        None => {
+                            tracing::debug!("EVOLUTION_API_FIXED_RECIPIENT not set. Install-wide WhatsApp notifications disabled.");
                         }
                     }
                 }
                 Err(e) => {
                     tracing::warn!("WhatsApp configuration not available: {}. WhatsApp notifications disabled.", e);
-                    None
                 }
             }
-        } else {
-            None
-        };
+        }
 
         Self {
             sound_enabled: config.sound_enabled,
             push_enabled: config.push_enabled,
-            whatsapp_enabled: config.whatsapp_enabled && whatsapp_notifier.is_some(),
-            whatsapp_notifier,
+            channels,
         }
     }
 
-    /// Send sound, push, and WhatsApp notifications if enabled
+    /// Send sound, push, and channel notifications if enabled
     pub async fn notify(&self, title: &str, message: &str, sound_file: &SoundFile) {
         if self.sound_enabled {
             self.play_sound_notification(sound_file).await;
@@ -74,11 +88,14 @@ impl NotificationService {
             self.send_push_notification(title, message).await;
         }
 
-        if self.whatsapp_enabled {
-            if let Some(ref whatsapp_notifier) = self.whatsapp_notifier {
-                if let Err(e) = whatsapp_notifier.send_notification(title, message).await {
-                    tracing::error!("Failed to send WhatsApp notification: {}", e);
-                }
+        let notification = notification_channels::Notification {
+            title: title.to_string(),
+            message: message.to_string(),
+            url: None,
+        };
+        for channel in &self.channels {
+            if let Err(e) = channel.send(&notification).await {
+                tracing::error!("Failed to send {} notification: {}", channel.kind(), e);
             }
         }
     }
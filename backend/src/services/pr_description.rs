@@ -0,0 +1,502 @@
+//! Pull request titles and descriptions written from the attempt itself.
+//!
+//! The task, the attempt's squashed diff and the executor's session summary
+//! are handed to a short non-interactive run of the attempt's executor, which
+//! answers with a title, a change list and testing notes. The body is then
+//! assembled here, falling back to the cleanup script's result for testing
+//! notes and always linking back to the Forge task.
+
+use std::{path::Path, time::Duration};
+
+use serde::Serialize;
+use thiserror::Error;
+use tokio::io::AsyncReadExt;
+use ts_rs::TS;
+use utoipa::ToSchema;
+
+use crate::{
+    app_state::AppState,
+    executor::{parse_assistant_message_from_logs, ExecutorConfig, ExecutorError},
+    models::{
+        execution_process::{ExecutionProcess, ExecutionProcessStatus, ExecutionProcessType},
+        executor_session::ExecutorSession,
+        project::Project,
+        task::Task,
+        task_attempt::{TaskAttempt, TaskAttemptError},
+    },
+    services::{execution_env::ExecutionEnv, notification_rules, GitService, GitServiceError},
+};
+
+/// Longest part of the squashed diff shown to the executor
+const MAX_DIFF_CHARS: usize = 30_000;
+/// Longest part of the session summary shown to the executor
+const MAX_SUMMARY_CHARS: usize = 4_000;
+/// Trailing cleanup script output shown to the executor
+const MAX_CLEANUP_OUTPUT_CHARS: usize = 3_000;
+/// Longest title kept from the executor's answer
+const MAX_TITLE_CHARS: usize = 100;
+/// How long the executor gets to answer when asked for a description
+pub const GENERATION_TIMEOUT: Duration = Duration::from_secs(180);
+/// How long creating a PR waits for a description before it falls back to the
+/// one it was given
+pub const INLINE_GENERATION_TIMEOUT: Duration = Duration::from_secs(20);
+
+#[derive(Debug, Error)]
+pub enum PrDescriptionError {
+    #[error("The attempt has no committed changes to describe")]
+    NoChanges,
+    #[error("The executor did not answer within {} seconds", .0.as_secs())]
+    TimedOut(Duration),
+    #[error("The executor's answer did not contain a PR title")]
+    NoAnswer,
+    #[error("{0}")]
+    UnknownExecutor(String),
+    #[error(transparent)]
+    Executor(#[from] ExecutorError),
+    #[error(transparent)]
+    Git(#[from] GitServiceError),
+    #[error(transparent)]
+    TaskAttempt(#[from] TaskAttemptError),
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// A generated PR title and Markdown body
+#[derive(Debug, Clone, Serialize, TS, ToSchema)]
+#[ts(export)]
+pub struct GeneratedPrDescription {
+    pub title: String,
+    pub body: String,
+}
+
+/// Outcome of the attempt's latest cleanup script run
+#[derive(Debug, Clone)]
+pub struct CleanupRun {
+    pub succeeded: bool,
+    pub exit_code: Option<i64>,
+    /// Trailing stdout and stderr
+    pub output: String,
+}
+
+/// The executor's answer, before it becomes a body
+#[derive(Debug, Default, PartialEq)]
+pub struct DescriptionAnswer {
+    pub title: String,
+    pub changes: Vec<String>,
+    pub testing: Vec<String>,
+}
+
+/// First `max` characters of `text`, marked when cut
+fn truncate_head(text: &str, max: usize) -> String {
+    match text.char_indices().nth(max) {
+        Some((end, _)) => format!("{}\n[... truncated]", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+/// Last `max` characters of `text`, marked when cut
+fn truncate_tail(text: &str, max: usize) -> String {
+    let count = text.chars().count();
+    if count <= max {
+        return text.to_string();
+    }
+    let start = text
+        .char_indices()
+        .nth(count - max)
+        .map_or(0, |(index, _)| index);
+    format!("[truncated ...]\n{}", &text[start..])
+}
+
+/// Prompt asking the executor to describe the attempt's changes
+pub fn compose_prompt(
+    task: &Task,
+    diff: &str,
+    summary: Option<&str>,
+    cleanup: Option<&CleanupRun>,
+) -> String {
+    let mut prompt = String::from(
+        "Write the title and description of a pull request for the changes below. \
+         Do not modify any files or run any commands; only answer.\n\n",
+    );
+
+    prompt.push_str(&format!("Task: {}\n", task.title));
+    if let Some(description) = task.description.as_deref().filter(|d| !d.trim().is_empty()) {
+        prompt.push_str(&format!("\nTask description:\n{}\n", description.trim()));
+    }
+    if let Some(summary) = summary.filter(|s| !s.trim().is_empty()) {
+        prompt.push_str(&format!(
+            "\nSummary of the session that made the changes:\n{}\n",
+            truncate_head(summary.trim(), MAX_SUMMARY_CHARS)
+        ));
+    }
+    if let Some(cleanup) = cleanup {
+        prompt.push_str(&format!(
+            "\nThe cleanup script {}. Its output ended with:\n```\n{}\n```\n",
+            cleanup_outcome(cleanup),
+            truncate_tail(cleanup.output.trim(), MAX_CLEANUP_OUTPUT_CHARS)
+        ));
+    }
+    prompt.push_str(&format!(
+        "\nSquashed diff:\n```diff\n{}\n```\n",
+        truncate_head(diff, MAX_DIFF_CHARS)
+    ));
+
+    prompt.push_str(
+        "\nAnswer in exactly this format, with nothing before or after it:\n\
+         TITLE: <one line, imperative mood, at most 72 characters>\n\
+         CHANGES:\n\
+         - <one change per line>\n\
+         TESTING:\n\
+         - <how the changes were tested, based on the cleanup script output; \
+         leave empty if nothing was tested>\n",
+    );
+    prompt
+}
+
+fn cleanup_outcome(cleanup: &CleanupRun) -> String {
+    match (cleanup.succeeded, cleanup.exit_code) {
+        (true, _) => "passed".to_string(),
+        (false, Some(code)) => format!("failed with exit code {}", code),
+        (false, None) => "failed".to_string(),
+    }
+}
+
+/// Read the `TITLE:` / `CHANGES:` / `TESTING:` answer; `None` without a title
+pub fn parse_answer(answer: &str) -> Option<DescriptionAnswer> {
+    enum Section {
+        None,
+        Changes,
+        Testing,
+    }
+
+    let mut parsed = DescriptionAnswer::default();
+    let mut section = Section::None;
+    for line in answer.lines() {
+        let line = line.trim();
+        let upper = line.to_ascii_uppercase();
+        if upper.starts_with("TITLE:") {
+            parsed.title = line["TITLE:".len()..]
+                .trim()
+                .trim_matches(|c| c == '"' || c == '`')
+                .to_string();
+            section = Section::None;
+        } else if upper.starts_with("CHANGES:") {
+            section = Section::Changes;
+        } else if upper.starts_with("TESTING:") {
+            section = Section::Testing;
+        } else if let Some(item) = line
+            .strip_prefix("- ")
+            .or_else(|| line.strip_prefix("* "))
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+        {
+            match section {
+                Section::Changes => parsed.changes.push(item.to_string()),
+                Section::Testing => parsed.testing.push(item.to_string()),
+                Section::None => {}
+            }
+        }
+    }
+
+    if parsed.title.is_empty() {
+        return None;
+    }
+    parsed.title = parsed.title.chars().take(MAX_TITLE_CHARS).collect();
+    Some(parsed)
+}
+
+/// Markdown body from the answer, with the cleanup result standing in for
+/// missing testing notes
+pub fn render_body(
+    answer: &DescriptionAnswer,
+    cleanup: Option<&CleanupRun>,
+    task_url: &str,
+) -> String {
+    let mut body = String::new();
+    if !answer.changes.is_empty() {
+        body.push_str("## Changes\n\n");
+        for change in &answer.changes {
+            body.push_str(&format!("- {}\n", change));
+        }
+        body.push('\n');
+    }
+
+    body.push_str("## Testing\n\n");
+    if !answer.testing.is_empty() {
+        for note in &answer.testing {
+            body.push_str(&format!("- {}\n", note));
+        }
+    } else if let Some(cleanup) = cleanup {
+        body.push_str(&format!("- Cleanup script {}\n", cleanup_outcome(cleanup)));
+    } else {
+        body.push_str("- Not tested\n");
+    }
+
+    body.push_str(&format!("\nForge task: {}\n", task_url));
+    body
+}
+
+/// The latest finished cleanup script run of an attempt
+async fn latest_cleanup(
+    pool: &sqlx::SqlitePool,
+    attempt_id: uuid::Uuid,
+) -> Result<Option<CleanupRun>, sqlx::Error> {
+    let processes = ExecutionProcess::find_by_task_attempt_id(pool, attempt_id).await?;
+    Ok(processes
+        .into_iter()
+        .rev()
+        .find(|process| {
+            process.process_type == ExecutionProcessType::CleanupScript
+                && process.status != ExecutionProcessStatus::Running
+        })
+        .map(|process| {
+            let output = [process.stdout, process.stderr]
+                .into_iter()
+                .flatten()
+                .filter(|output| !output.trim().is_empty())
+                .collect::<Vec<_>>()
+                .join("\n");
+            CleanupRun {
+                succeeded: process.status == ExecutionProcessStatus::Completed
+                    && process.exit_code == Some(0),
+                exit_code: process.exit_code,
+                output,
+            }
+        }))
+}
+
+/// The executor an attempt ran with; attempts without one ran the echo
+/// executor, as in `ProcessService`
+fn attempt_executor(attempt: &TaskAttempt) -> Result<ExecutorConfig, String> {
+    match attempt.executor.as_deref() {
+        None => Ok(ExecutorConfig::Echo),
+        Some(name) => name.parse::<ExecutorConfig>(),
+    }
+}
+
+/// Run the prompt through `executor` (or Claude, when that one cannot answer
+/// one-off prompts) and return its final message
+///
+/// The prompt carries everything the executor needs, so it runs in an empty
+/// scratch directory rather than the attempt's worktree, which it therefore
+/// cannot change. It gets none of the project's variables either: summarizing
+/// a diff needs no secrets.
+async fn run_prompt(
+    executor: &ExecutorConfig,
+    prompt: &str,
+    timeout: Duration,
+) -> Result<Option<String>, PrDescriptionError> {
+    let scratch_dir =
+        std::env::temp_dir().join(format!("forge-pr-description-{}", uuid::Uuid::new_v4()));
+    tokio::fs::create_dir_all(&scratch_dir).await?;
+    let result = run_prompt_in(
+        executor,
+        prompt,
+        &scratch_dir.to_string_lossy(),
+        &ExecutionEnv::default(),
+        timeout,
+    )
+    .await;
+    if let Err(e) = tokio::fs::remove_dir_all(&scratch_dir).await {
+        tracing::warn!(
+            "Failed to remove PR description scratch directory {}: {}",
+            scratch_dir.display(),
+            e
+        );
+    }
+    result
+}
+
+async fn run_prompt_in(
+    executor: &ExecutorConfig,
+    prompt: &str,
+    working_dir: &str,
+    env: &ExecutionEnv,
+    timeout: Duration,
+) -> Result<Option<String>, PrDescriptionError> {
+    let mut child = match executor
+        .create_executor()
+        .spawn_prompt(prompt, working_dir, env)
+        .await
+    {
+        Err(ExecutorError::PromptNotSupported) => {
+            ExecutorConfig::Claude
+                .create_executor()
+                .spawn_prompt(prompt, working_dir, env)
+                .await?
+        }
+        result => result?,
+    };
+
+    // Keep stderr drained so a chatty executor cannot block on a full pipe
+    if let Some(mut stderr) = child.inner().stderr.take() {
+        tokio::spawn(async move {
+            let _ = tokio::io::copy(&mut stderr, &mut tokio::io::sink()).await;
+        });
+    }
+    let mut stdout = child.inner().stdout.take();
+
+    let run = async {
+        let mut output = String::new();
+        if let Some(stdout) = stdout.as_mut() {
+            stdout.read_to_string(&mut output).await?;
+        }
+        child.wait().await?;
+        Ok::<_, std::io::Error>(output)
+    };
+    let output = match tokio::time::timeout(timeout, run).await {
+        Ok(output) => output?,
+        Err(_) => {
+            let _ = child.kill().await;
+            return Err(PrDescriptionError::TimedOut(timeout));
+        }
+    };
+
+    Ok(parse_assistant_message_from_logs(&output))
+}
+
+/// Write a PR title and body for `attempt` with its executor, giving it up to
+/// `timeout` to answer
+pub async fn generate(
+    app_state: &AppState,
+    project: &Project,
+    task: &Task,
+    attempt: &TaskAttempt,
+    timeout: Duration,
+) -> Result<GeneratedPrDescription, PrDescriptionError> {
+    let pool = &app_state.db_pool;
+    let executor = attempt_executor(attempt).map_err(PrDescriptionError::UnknownExecutor)?;
+    let worktree_path =
+        TaskAttempt::ensure_worktree_exists(pool, attempt.id, project.id, "PR description").await?;
+
+    let diff = GitService::new(&project.git_repo_path)?
+        .get_squashed_patch(Path::new(&worktree_path), &attempt.base_branch)?;
+    if diff.trim().is_empty() {
+        return Err(PrDescriptionError::NoChanges);
+    }
+
+    let summary = ExecutorSession::find_by_task_attempt_id(pool, attempt.id)
+        .await?
+        .into_iter()
+        .rev()
+        .find_map(|session| session.summary.filter(|s| !s.trim().is_empty()));
+    let cleanup = latest_cleanup(pool, attempt.id).await?;
+
+    let prompt = compose_prompt(task, &diff, summary.as_deref(), cleanup.as_ref());
+    let answer = run_prompt(&executor, &prompt, timeout)
+        .await?
+        .as_deref()
+        .and_then(parse_answer)
+        .ok_or(PrDescriptionError::NoAnswer)?;
+
+    let body = render_body(
+        &answer,
+        cleanup.as_ref(),
+        &notification_rules::task_url(project.id, task.id),
+    );
+    Ok(GeneratedPrDescription {
+        title: answer.title,
+        body,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::models::task::TaskStatus;
+
+    fn task() -> Task {
+        Task {
+            id: Uuid::new_v4(),
+            project_id: Uuid::new_v4(),
+            title: "Add login page".to_string(),
+            description: Some("Users should be able to sign in".to_string()),
+            status: TaskStatus::InReview,
+            wish_id: "auth".to_string(),
+            parent_task_attempt: None,
+            created_by: None,
+            assigned_to: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn failed_cleanup() -> CleanupRun {
+        CleanupRun {
+            succeeded: false,
+            exit_code: Some(101),
+            output: "test result: FAILED. 3 passed; 1 failed".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_prompt_includes_task_summary_cleanup_and_diff() {
+        let prompt = compose_prompt(
+            &task(),
+            "+fn login() {}\n",
+            Some("Added a login handler"),
+            Some(&failed_cleanup()),
+        );
+
+        assert!(prompt.contains("Task: Add login page"));
+        assert!(prompt.contains("Users should be able to sign in"));
+        assert!(prompt.contains("Added a login handler"));
+        assert!(prompt.contains("failed with exit code 101"));
+        assert!(prompt.contains("3 passed; 1 failed"));
+        assert!(prompt.contains("+fn login() {}"));
+        assert!(prompt.contains("TITLE:"));
+    }
+
+    #[test]
+    fn test_prompt_truncates_long_diffs() {
+        let diff = "+x\n".repeat(MAX_DIFF_CHARS);
+        let prompt = compose_prompt(&task(), &diff, None, None);
+
+        assert!(prompt.len() < diff.len());
+        assert!(prompt.contains("[... truncated]"));
+    }
+
+    #[test]
+    fn test_parse_answer() {
+        let answer = parse_answer(
+            "TITLE: Add a login page\n\
+             CHANGES:\n\
+             - Add the /login route\n\
+             * Store sessions in a cookie\n\
+             TESTING:\n\
+             - cargo test passes\n",
+        )
+        .unwrap();
+
+        assert_eq!(answer.title, "Add a login page");
+        assert_eq!(
+            answer.changes,
+            vec!["Add the /login route", "Store sessions in a cookie"]
+        );
+        assert_eq!(answer.testing, vec!["cargo test passes"]);
+        assert!(parse_answer("I could not describe these changes").is_none());
+    }
+
+    #[test]
+    fn test_body_falls_back_to_cleanup_result() {
+        let answer = DescriptionAnswer {
+            title: "Add a login page".to_string(),
+            changes: vec!["Add the /login route".to_string()],
+            testing: Vec::new(),
+        };
+        let body = render_body(
+            &answer,
+            Some(&failed_cleanup()),
+            "http://localhost:3001/projects/p/tasks/t",
+        );
+
+        assert!(body.contains("## Changes\n\n- Add the /login route\n"));
+        assert!(body.contains("## Testing\n\n- Cleanup script failed with exit code 101\n"));
+        assert!(body.ends_with("Forge task: http://localhost:3001/projects/p/tasks/t\n"));
+    }
+}
@@ -1,86 +1,94 @@
 use std::{sync::Arc, time::Duration};
 
 use sqlx::SqlitePool;
-use tokio::{sync::RwLock, time::interval};
+use tokio::time::{interval, Instant};
 use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
 use crate::{
     models::{
-        config::Config,
         task::{Task, TaskStatus},
         task_attempt::TaskAttempt,
+        user_notification_rule::NotificationEvent,
+        webhook::WebhookEvent,
+    },
+    security::secret_store::SecretStore,
+    services::{
+        forge::{self, ForgeError, ForgeKind, ForgeRepo},
+        github_issues,
+        github_service::PullRequestInfo,
+        github_webhooks,
+        notification_channels::Notification,
+        notification_rules, webhooks,
     },
-    services::{GitHubRepoInfo, GitHubService, GitService},
 };
 
-/// Service to monitor GitHub PRs and update task status when they are merged
+/// How often GitHub PRs are polled while GitHub webhooks keep them up to
+/// date. Polling then only catches deliveries that were missed. GitLab and
+/// Gitea PRs are always polled.
+const FALLBACK_POLL_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// Service to monitor PRs on the projects' forges and update task status when
+/// they are merged
 pub struct PrMonitorService {
     pool: SqlitePool,
+    secret_store: Arc<SecretStore>,
     poll_interval: Duration,
 }
 
 #[derive(Debug)]
 pub struct PrInfo {
     pub attempt_id: Uuid,
+    #[allow(dead_code)]
     pub task_id: Uuid,
     pub project_id: Uuid,
     pub pr_number: i64,
-    pub repo_owner: String,
-    pub repo_name: String,
-    pub github_token: String,
+    pub repo: ForgeRepo,
+    pub token: String,
 }
 
 impl PrMonitorService {
-    pub fn new(pool: SqlitePool) -> Self {
+    pub fn new(pool: SqlitePool, secret_store: Arc<SecretStore>) -> Self {
         Self {
             pool,
+            secret_store,
             poll_interval: Duration::from_secs(60), // Check every minute
         }
     }
 
-    /// Start the PR monitoring service with config
-    #[allow(dead_code)]
-    pub async fn start_with_config(&self, config: Arc<RwLock<Config>>) {
+    /// Start the PR monitoring service
+    pub async fn start(&self) {
         info!(
             "Starting PR monitoring service with interval {:?}",
             self.poll_interval
         );
 
         let mut interval = interval(self.poll_interval);
+        let mut last_github_poll: Option<Instant> = None;
 
         loop {
             interval.tick().await;
 
-            // Get GitHub token from config
-            let github_token = {
-                let config_read = config.read().await;
-                if config_read.github.pat.is_some() {
-                    config_read.github.pat.clone()
-                } else {
-                    config_read.github.token.clone()
-                }
-            };
+            let poll_github = github_webhooks::webhook_secret(&self.secret_store)
+                .await
+                .is_none()
+                || last_github_poll.is_none_or(|at| at.elapsed() >= FALLBACK_POLL_INTERVAL);
+            if poll_github {
+                last_github_poll = Some(Instant::now());
+            }
 
-            match github_token {
-                Some(token) => {
-                    if let Err(e) = self.check_all_open_prs_with_token(&token).await {
-                        error!("Error checking PRs: {}", e);
-                    }
-                }
-                None => {
-                    debug!("No GitHub token configured, skipping PR monitoring");
-                }
+            if let Err(e) = self.check_all_open_prs(poll_github).await {
+                error!("Error checking PRs: {}", e);
             }
         }
     }
 
-    /// Check all open PRs for updates with the provided GitHub token
-    async fn check_all_open_prs_with_token(
+    /// Check all open PRs for updates, skipping GitHub's unless `poll_github`
+    async fn check_all_open_prs(
         &self,
-        github_token: &str,
+        poll_github: bool,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        let open_prs = self.get_open_prs_with_token(github_token).await?;
+        let open_prs = self.get_open_prs(poll_github).await?;
 
         if open_prs.is_empty() {
             debug!("No open PRs to check");
@@ -101,17 +109,16 @@ impl PrMonitorService {
         Ok(())
     }
 
-    /// Get all task attempts with open PRs using the provided GitHub token
-    async fn get_open_prs_with_token(
-        &self,
-        github_token: &str,
-    ) -> Result<Vec<PrInfo>, sqlx::Error> {
+    /// Get all task attempts with open PRs, with the token of the user who
+    /// started each attempt (or the install's GitHub token)
+    async fn get_open_prs(&self, poll_github: bool) -> Result<Vec<PrInfo>, sqlx::Error> {
         let rows = sqlx::query!(
             r#"SELECT 
                 ta.id as "attempt_id!: Uuid",
                 ta.task_id as "task_id!: Uuid",
                 ta.pr_number as "pr_number!: i64",
                 ta.pr_url,
+                ta.created_by as "created_by: Uuid",
                 t.project_id as "project_id!: Uuid",
                 p.git_repo_path
                FROM task_attempts ta
@@ -125,30 +132,29 @@ impl PrMonitorService {
         let mut pr_infos = Vec::new();
 
         for row in rows {
-            // Get GitHub repo info from local git repository
-            match GitService::new(&row.git_repo_path) {
-                Ok(git_service) => match git_service.get_github_repo_info() {
-                    Ok((owner, repo_name)) => {
-                        pr_infos.push(PrInfo {
-                            attempt_id: row.attempt_id,
-                            task_id: row.task_id,
-                            project_id: row.project_id,
-                            pr_number: row.pr_number,
-                            repo_owner: owner,
-                            repo_name,
-                            github_token: github_token.to_string(),
-                        });
-                    }
-                    Err(e) => {
-                        warn!(
-                            "Could not extract repo info from git path {}: {}",
-                            row.git_repo_path, e
-                        );
-                    }
-                },
+            let credentials =
+                forge::credentials(&self.secret_store, &row.git_repo_path, row.created_by).await;
+            match credentials {
+                Ok((repo, _)) if repo.kind == ForgeKind::GitHub && !poll_github => {}
+                Ok((repo, token)) => {
+                    pr_infos.push(PrInfo {
+                        attempt_id: row.attempt_id,
+                        task_id: row.task_id,
+                        project_id: row.project_id,
+                        pr_number: row.pr_number,
+                        repo,
+                        token,
+                    });
+                }
+                Err(ForgeError::NotAuthenticated(kind, host)) => {
+                    debug!(
+                        "No {} token for {}, skipping PR #{} of attempt {}",
+                        kind, host, row.pr_number, row.attempt_id
+                    );
+                }
                 Err(e) => {
                     warn!(
-                        "Could not create git service for path {}: {}",
+                        "Could not find the forge of git path {}: {}",
                         row.git_repo_path, e
                     );
                 }
@@ -163,15 +169,8 @@ impl PrMonitorService {
         &self,
         pr_info: &PrInfo,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        let github_service = GitHubService::new(&pr_info.github_token)?;
-
-        let repo_info = GitHubRepoInfo {
-            owner: pr_info.repo_owner.clone(),
-            repo_name: pr_info.repo_name.clone(),
-        };
-
-        let pr_status = github_service
-            .update_pr_status(&repo_info, pr_info.pr_number)
+        let pr_status = forge::connect(&pr_info.repo, &pr_info.token)?
+            .pull_request_status(pr_info.pr_number)
             .await?;
 
         debug!(
@@ -179,32 +178,14 @@ impl PrMonitorService {
             pr_info.pr_number, pr_status.status
         );
 
-        // Update the PR status in the database
         if pr_status.status != "open" {
-            // Extract merge commit SHA if the PR was merged
-            let merge_commit_sha = pr_status.merge_commit_sha.as_deref();
-
-            TaskAttempt::update_pr_status(
-                &self.pool,
-                pr_info.attempt_id,
-                &pr_status.status,
-                pr_status.merged_at,
-                merge_commit_sha,
-            )
-            .await?;
-
-            // If the PR was merged, update the task status to done
-            if pr_status.merged {
-                info!(
-                    "PR #{} was merged, updating task {} to done",
-                    pr_info.pr_number, pr_info.task_id
-                );
-
-                Task::update_status(
+            if let Some(attempt) = TaskAttempt::find_by_id(&self.pool, pr_info.attempt_id).await? {
+                record_pr_status(
                     &self.pool,
-                    pr_info.task_id,
+                    &self.secret_store,
+                    &attempt,
                     pr_info.project_id,
-                    TaskStatus::Done,
+                    &pr_status,
                 )
                 .await?;
             }
@@ -213,3 +194,110 @@ impl PrMonitorService {
         Ok(())
     }
 }
+
+/// Record a PR's new state on its attempt, whether learned from polling or a
+/// GitHub webhook. Closing or merging the PR emits the matching webhook event,
+/// and a merge also moves the task to done and notifies the users involved.
+/// Does nothing when the attempt already has this state.
+pub async fn record_pr_status(
+    pool: &SqlitePool,
+    secret_store: &SecretStore,
+    attempt: &TaskAttempt,
+    project_id: Uuid,
+    pr_status: &PullRequestInfo,
+) -> Result<(), sqlx::Error> {
+    if attempt.pr_status.as_deref() == Some(pr_status.status.as_str()) {
+        return Ok(());
+    }
+
+    // Extract merge commit SHA if the PR was merged
+    let merge_commit_sha = pr_status
+        .merge_commit_sha
+        .as_deref()
+        .filter(|_| pr_status.merged)
+        .or(attempt.merge_commit.as_deref());
+
+    TaskAttempt::update_pr_status(
+        pool,
+        attempt.id,
+        &pr_status.status,
+        pr_status.merged_at,
+        merge_commit_sha,
+    )
+    .await?;
+
+    let event = match pr_status.status.as_str() {
+        "merged" => WebhookEvent::PrMerged,
+        "closed" => WebhookEvent::PrClosed,
+        _ => return Ok(()),
+    };
+    webhooks::emit_attempt_event(
+        pool,
+        event,
+        project_id,
+        attempt.task_id,
+        attempt.id,
+        serde_json::json!({
+            "pr_number": pr_status.number,
+            "merge_commit": pr_status.merge_commit_sha,
+        }),
+    )
+    .await;
+
+    // If the PR was merged, update the task status to done
+    if pr_status.merged {
+        info!(
+            "PR #{} was merged, updating task {} to done",
+            pr_status.number, attempt.task_id
+        );
+
+        Task::update_status(pool, attempt.task_id, project_id, TaskStatus::Done).await?;
+        webhooks::emit_task_status(pool, attempt.task_id, project_id, TaskStatus::Done).await;
+        notify_merged(pool, secret_store, attempt, pr_status.number).await;
+    }
+
+    Ok(())
+}
+
+/// Tell the users involved in the task that its PR was merged, and the GitHub
+/// issue it was imported from
+async fn notify_merged(
+    pool: &SqlitePool,
+    secret_store: &SecretStore,
+    attempt: &TaskAttempt,
+    pr_number: i64,
+) {
+    let task = match Task::find_by_id(pool, attempt.task_id).await {
+        Ok(Some(task)) => task,
+        Ok(None) => return,
+        Err(e) => {
+            error!(
+                "Failed to load task {} for notifications: {}",
+                attempt.task_id, e
+            );
+            return;
+        }
+    };
+
+    notification_rules::dispatch(
+        pool,
+        secret_store,
+        NotificationEvent::PrMerged,
+        &task,
+        attempt.created_by,
+        &Notification {
+            title: format!("PR Merged: {}", task.title),
+            message: format!("🎉 PR #{} for '{}' was merged", pr_number, task.title),
+            url: Some(notification_rules::task_url(task.project_id, task.id)),
+        },
+    )
+    .await;
+
+    github_issues::comment_on_merge(
+        pool,
+        secret_store,
+        &task,
+        &format!("in pull request #{}", pr_number),
+    )
+    .await;
+}
@@ -5,12 +5,16 @@ use uuid::Uuid;
 use crate::{
     executor::Executor,
     models::{
-        execution_process::{CreateExecutionProcess, ExecutionProcess, ExecutionProcessType},
+        execution_process::{
+            CreateExecutionProcess, ExecutionProcess, ExecutionProcessStatus, ExecutionProcessType,
+        },
         executor_session::{CreateExecutorSession, ExecutorSession},
         project::Project,
         task::Task,
         task_attempt::{TaskAttempt, TaskAttemptError},
+        webhook::WebhookEvent,
     },
+    services::{mcp_injection, mcp_servers, webhooks, ExecutionEnv},
     utils::shell::get_shell_command,
 };
 
@@ -149,6 +153,7 @@ impl ProcessService {
         // Create modified setup script execution with delegation context in args
         let setup_script = project.setup_script.as_ref().unwrap();
         let process_id = Uuid::new_v4();
+        let env = Self::resolve_execution_env(app_state, &task_attempt, project_id).await?;
 
         // Create execution process record with delegation context
         let _execution_process = Self::create_execution_process_record_with_delegation(
@@ -170,6 +175,7 @@ impl ProcessService {
         );
 
         // Execute the setup script
+        env.register_redactions(process_id);
         let child = Self::execute_setup_script_process(
             setup_script,
             pool,
@@ -177,8 +183,10 @@ impl ProcessService {
             attempt_id,
             process_id,
             &task_attempt.worktree_path,
+            &env,
         )
-        .await?;
+        .await
+        .inspect_err(|_| crate::services::execution_env::release_redactions(process_id))?;
 
         // Register for monitoring
         Self::register_for_monitoring(
@@ -214,6 +222,16 @@ impl ProcessService {
 
         // Update task status to indicate execution has started
         Task::update_status(pool, task_id, project_id, TaskStatus::InProgress).await?;
+        webhooks::emit_task_status(pool, task_id, project_id, TaskStatus::InProgress).await;
+        webhooks::emit_attempt_event(
+            pool,
+            WebhookEvent::AttemptStarted,
+            project_id,
+            task_id,
+            attempt_id,
+            serde_json::json!({ "executor": task_attempt.executor, "follow_up": false }),
+        )
+        .await;
 
         // Determine execution sequence based on project configuration
         if Self::should_run_setup_script(&project) {
@@ -376,6 +394,16 @@ impl ProcessService {
 
         // Update task status to indicate follow-up execution has started
         Task::update_status(pool, task_id, project_id, TaskStatus::InProgress).await?;
+        webhooks::emit_task_status(pool, task_id, project_id, TaskStatus::InProgress).await;
+        webhooks::emit_attempt_event(
+            pool,
+            WebhookEvent::AttemptStarted,
+            project_id,
+            task_id,
+            attempt_id,
+            serde_json::json!({ "executor": current_attempt.executor, "follow_up": true }),
+        )
+        .await;
 
         // Ensure worktree exists (recreate if needed for cold task support)
         // This will resurrect the worktree at the exact same path for session continuity
@@ -402,6 +430,49 @@ impl ProcessService {
         Ok(actual_attempt_id)
     }
 
+    /// Stop every running process of a task attempt and mark them killed.
+    /// Returns how many processes were stopped and the errors for those that
+    /// could not be.
+    pub async fn stop_attempt_processes(
+        pool: &SqlitePool,
+        app_state: &crate::app_state::AppState,
+        attempt_id: Uuid,
+    ) -> Result<(usize, Vec<String>), TaskAttemptError> {
+        let processes = ExecutionProcess::find_by_task_attempt_id(pool, attempt_id).await?;
+
+        let mut stopped_count = 0;
+        let mut errors = Vec::new();
+
+        for process in processes {
+            match app_state.stop_running_execution_by_id(process.id).await {
+                Ok(true) => {
+                    stopped_count += 1;
+
+                    if let Err(e) = ExecutionProcess::update_completion(
+                        pool,
+                        process.id,
+                        ExecutionProcessStatus::Killed,
+                        None,
+                    )
+                    .await
+                    {
+                        tracing::error!("Failed to update execution process status: {}", e);
+                        errors.push(format!("Failed to update process {} status", process.id));
+                    }
+                }
+                Ok(false) => {
+                    // Process was not running, which is fine
+                }
+                Err(e) => {
+                    tracing::error!("Failed to stop execution process {}: {}", process.id, e);
+                    errors.push(format!("Failed to stop process {}: {}", process.id, e));
+                }
+            }
+        }
+
+        Ok((stopped_count, errors))
+    }
+
     /// Start a follow-up execution directly without setup check (internal method)
     pub async fn start_followup_execution_direct(
         pool: &SqlitePool,
@@ -560,6 +631,21 @@ impl ProcessService {
     ) -> Result<(), TaskAttemptError> {
         let process_id = Uuid::new_v4();
 
+        let task_attempt = TaskAttempt::find_by_id(pool, attempt_id)
+            .await?
+            .ok_or(TaskAttemptError::TaskNotFound)?;
+        let task = Task::find_by_id(pool, task_id)
+            .await?
+            .ok_or(TaskAttemptError::TaskNotFound)?;
+        let mut env = Self::resolve_execution_env(app_state, &task_attempt, task.project_id).await?;
+        if let crate::executor::ExecutorType::CodingAgent { config, .. } = &executor_type {
+            if let Some(path) =
+                Self::inject_mcp_config(app_state, &task_attempt, task.project_id, config).await
+            {
+                env = env.with_mcp_config(path);
+            }
+        }
+
         // Create execution process record
         let _execution_process = Self::create_execution_process_record(
             pool,
@@ -596,6 +682,7 @@ impl ProcessService {
         tracing::info!("Starting {} for task attempt {}", activity_note, attempt_id);
 
         // Execute the process
+        env.register_redactions(process_id);
         let child = Self::execute_process(
             &executor_type,
             pool,
@@ -603,8 +690,10 @@ impl ProcessService {
             attempt_id,
             process_id,
             worktree_path,
+            &env,
         )
-        .await?;
+        .await
+        .inspect_err(|_| crate::services::execution_env::release_redactions(process_id))?;
 
         // Register for monitoring
         Self::register_for_monitoring(app_state, process_id, attempt_id, &process_type, child)
@@ -618,6 +707,78 @@ impl ProcessService {
         Ok(())
     }
 
+    /// Decrypt the project's environment variables, with the overrides of the
+    /// user who created the attempt, and identify the attempt to the process
+    async fn resolve_execution_env(
+        app_state: &crate::app_state::AppState,
+        task_attempt: &TaskAttempt,
+        project_id: Uuid,
+    ) -> Result<ExecutionEnv, TaskAttemptError> {
+        ExecutionEnv::resolve(app_state.secret_store(), project_id, task_attempt.created_by)
+            .await
+            .map(|env| env.with_attempt_context(task_attempt.id, task_attempt.task_id, project_id))
+            .map_err(|e| {
+                TaskAttemptError::ValidationError(format!(
+                    "Failed to load project environment variables: {}",
+                    e
+                ))
+            })
+    }
+
+    /// The per-attempt MCP config for a coding agent whose CLI can take one:
+    /// the project's MCP servers with the attempt creator's overrides, plus
+    /// the Forge server if the project opted in. Agents still start when it
+    /// can't be written.
+    async fn inject_mcp_config(
+        app_state: &crate::app_state::AppState,
+        task_attempt: &TaskAttempt,
+        project_id: Uuid,
+        executor_config: &crate::executor::ExecutorConfig,
+    ) -> Option<std::path::PathBuf> {
+        if !mcp_injection::supports_injection(executor_config) {
+            return None;
+        }
+        let pool = &app_state.db_pool;
+        let forge = Project::mcp_injection_enabled(pool, project_id)
+            .await
+            .inspect_err(|e| {
+                tracing::error!("Failed to check MCP injection for project {}: {}", project_id, e)
+            })
+            .unwrap_or(false);
+        let servers = mcp_servers::resolve_for_attempt(
+            pool,
+            app_state.secret_store(),
+            project_id,
+            task_attempt.created_by,
+        )
+        .await
+        .inspect_err(|e| {
+            tracing::error!("Failed to load MCP servers for project {}: {}", project_id, e)
+        })
+        .unwrap_or_default();
+
+        match mcp_injection::prepare_attempt_config(
+            pool,
+            app_state.get_jwt_config(),
+            app_state.server_url(),
+            task_attempt,
+            forge,
+            servers,
+        )
+        .await
+        {
+            Ok(path) => path,
+            Err(e) => {
+                tracing::warn!(
+                    "Starting attempt {} without its MCP servers: {}",
+                    task_attempt.id,
+                    e
+                );
+                None
+            }
+        }
+    }
+
     /// Load the execution context (task attempt and project) with validation
     async fn load_execution_context(
         pool: &SqlitePool,
@@ -804,6 +965,7 @@ impl ProcessService {
         attempt_id: Uuid,
         process_id: Uuid,
         worktree_path: &str,
+        env: &ExecutionEnv,
     ) -> Result<command_group::AsyncGroupChild, TaskAttemptError> {
         use crate::executors::{CleanupScriptExecutor, DevServerExecutor, SetupScriptExecutor};
 
@@ -813,7 +975,7 @@ impl ProcessService {
                     script: script.clone(),
                 };
                 executor
-                    .execute_streaming(pool, task_id, attempt_id, process_id, worktree_path)
+                    .execute_streaming(pool, task_id, attempt_id, process_id, worktree_path, env)
                     .await
             }
             crate::executor::ExecutorType::CleanupScript(script) => {
@@ -821,7 +983,7 @@ impl ProcessService {
                     script: script.clone(),
                 };
                 executor
-                    .execute_streaming(pool, task_id, attempt_id, process_id, worktree_path)
+                    .execute_streaming(pool, task_id, attempt_id, process_id, worktree_path, env)
                     .await
             }
             crate::executor::ExecutorType::DevServer(script) => {
@@ -829,7 +991,7 @@ impl ProcessService {
                     script: script.clone(),
                 };
                 executor
-                    .execute_streaming(pool, task_id, attempt_id, process_id, worktree_path)
+                    .execute_streaming(pool, task_id, attempt_id, process_id, worktree_path, env)
                     .await
             }
             crate::executor::ExecutorType::CodingAgent { config, follow_up } => {
@@ -845,11 +1007,12 @@ impl ProcessService {
                             &follow_up_info.session_id,
                             &follow_up_info.prompt,
                             worktree_path,
+                            env,
                         )
                         .await
                 } else {
                     executor
-                        .execute_streaming(pool, task_id, attempt_id, process_id, worktree_path)
+                        .execute_streaming(pool, task_id, attempt_id, process_id, worktree_path, env)
                         .await
                 }
             }
@@ -926,6 +1089,7 @@ impl ProcessService {
         attempt_id: Uuid,
         process_id: Uuid,
         worktree_path: &str,
+        env: &ExecutionEnv,
     ) -> Result<command_group::AsyncGroupChild, TaskAttemptError> {
         use crate::executors::SetupScriptExecutor;
 
@@ -934,7 +1098,7 @@ impl ProcessService {
         };
 
         executor
-            .execute_streaming(pool, task_id, attempt_id, process_id, worktree_path)
+            .execute_streaming(pool, task_id, attempt_id, process_id, worktree_path, env)
             .await
             .map_err(|e| TaskAttemptError::Git(git2::Error::from_str(&e.to_string())))
     }
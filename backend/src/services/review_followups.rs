@@ -0,0 +1,528 @@
+//! Addressing pull request review comments with follow-up executions.
+//!
+//! The unresolved review threads of an attempt's PR are fetched from GitHub
+//! and turned into a follow-up prompt for the attempt's session. Once that
+//! execution (and the cleanup script) finishes, the branch is pushed and each
+//! thread gets a reply saying which commit addressed it. Projects can opt in
+//! to doing this whenever a review is submitted; those automatic follow-ups
+//! only take in comments by the repository's owners, members and
+//! collaborators.
+
+use std::{collections::HashSet, path::Path};
+
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::{
+    app_state::AppState,
+    models::{
+        executor_session::ExecutorSession,
+        project::Project,
+        pull_request::{AddressedThread, PrCommentFollowup, PrCommentFollowupStatus},
+        task::Task,
+        task_attempt::{TaskAttempt, TaskAttemptError},
+    },
+    services::{
+        github_service::{is_maintainer_association, ReviewThread},
+        GitHubRepoInfo, GitHubService, GitHubServiceError, GitService, GitServiceError,
+        ProcessService,
+    },
+};
+
+/// Longest excerpt of the agent's summary quoted in thread replies
+const REPLY_SUMMARY_CHARS: usize = 1000;
+
+#[derive(Debug, Error)]
+pub enum ReviewFollowupError {
+    #[error("The attempt has no pull request")]
+    NoPullRequest,
+    #[error("GitHub authentication not configured")]
+    NotAuthenticated,
+    #[error("The attempt is already running")]
+    AttemptRunning,
+    #[error("No unresolved review comments to address")]
+    NothingToAddress,
+    #[error(transparent)]
+    GitHub(#[from] GitHubServiceError),
+    #[error(transparent)]
+    Git(#[from] GitServiceError),
+    #[error(transparent)]
+    TaskAttempt(#[from] TaskAttemptError),
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
+}
+
+async fn github_token(app_state: &AppState) -> Option<String> {
+    let config = app_state.get_config().read().await;
+    config
+        .github
+        .pat
+        .clone()
+        .or_else(|| config.github.token.clone())
+}
+
+fn repo_info(project: &Project) -> Result<GitHubRepoInfo, GitServiceError> {
+    let (owner, repo_name) = GitService::new(&project.git_repo_path)?.get_github_repo_info()?;
+    Ok(GitHubRepoInfo { owner, repo_name })
+}
+
+/// Threads nobody commented on since a follow-up addressed them
+fn pending_threads(threads: Vec<ReviewThread>, addressed: &HashSet<i64>) -> Vec<ReviewThread> {
+    threads
+        .into_iter()
+        .filter(|thread| {
+            thread
+                .comments
+                .last()
+                .is_some_and(|comment| !addressed.contains(&comment.id))
+        })
+        .collect()
+}
+
+/// Threads keeping only the comments of the repository's maintainers, without
+/// the ones no maintainer commented on
+fn maintainer_threads(threads: Vec<ReviewThread>) -> Vec<ReviewThread> {
+    threads
+        .into_iter()
+        .filter_map(|mut thread| {
+            thread
+                .comments
+                .retain(|comment| is_maintainer_association(&comment.author_association));
+            (!thread.comments.is_empty()).then_some(thread)
+        })
+        .collect()
+}
+
+/// Follow-up prompt asking the agent to address `threads`
+pub fn compose_prompt(threads: &[ReviewThread]) -> String {
+    let mut prompt = String::from(
+        "Reviewers left the following comments on the pull request for this task. \
+         Address each of them in the code. If you disagree with a comment, leave the \
+         code as it is and explain why in your final message.\n",
+    );
+    for (index, thread) in threads.iter().enumerate() {
+        let location = match thread.line {
+            Some(line) if !thread.is_outdated => format!("{}:{}", thread.path, line),
+            _ => format!("{} (the code it was on has since changed)", thread.path),
+        };
+        prompt.push_str(&format!("\n## {}. {}\n", index + 1, location));
+        if let Some(diff_hunk) = &thread.diff_hunk {
+            prompt.push_str(&format!("```diff\n{}\n```\n", diff_hunk.trim_end()));
+        }
+        for comment in &thread.comments {
+            prompt.push_str(&format!(
+                "**{}**: {}\n",
+                comment.author,
+                comment.body.trim()
+            ));
+        }
+    }
+    prompt
+}
+
+/// Reply posted on each addressed thread
+pub fn reply_body(commit: Option<&str>, summary: Option<&str>) -> String {
+    let mut body = match commit {
+        Some(commit) => format!("Addressed by Forge in {}.", commit),
+        None => "Addressed by Forge.".to_string(),
+    };
+    if let Some(summary) = summary.map(str::trim).filter(|summary| !summary.is_empty()) {
+        let excerpt: String = summary.chars().take(REPLY_SUMMARY_CHARS).collect();
+        let ellipsis = if excerpt.len() < summary.len() {
+            "…"
+        } else {
+            ""
+        };
+        body.push_str("\n\n");
+        for line in format!("{}{}", excerpt, ellipsis).lines() {
+            body.push_str(&format!("> {}\n", line));
+        }
+    }
+    body
+}
+
+/// Start a follow-up execution addressing the unresolved review threads of
+/// the attempt's PR that were not addressed before. With `maintainers_only`,
+/// comments by anyone but the repository's owners, members and collaborators
+/// are left out of the prompt.
+pub async fn address_review_comments(
+    app_state: &AppState,
+    attempt: &TaskAttempt,
+    project: &Project,
+    maintainers_only: bool,
+) -> Result<PrCommentFollowup, ReviewFollowupError> {
+    let pool = &app_state.db_pool;
+    let pr_number = attempt
+        .pr_number
+        .ok_or(ReviewFollowupError::NoPullRequest)?;
+    let token = github_token(app_state)
+        .await
+        .ok_or(ReviewFollowupError::NotAuthenticated)?;
+    if app_state.has_running_execution(attempt.id).await {
+        return Err(ReviewFollowupError::AttemptRunning);
+    }
+    // A follow-up still running without an execution was cut short, e.g. by
+    // a restart
+    if let Some(stale) = PrCommentFollowup::find_running_by_attempt_id(pool, attempt.id).await? {
+        PrCommentFollowup::complete(
+            pool,
+            stale.id,
+            PrCommentFollowupStatus::Failed,
+            &stale.threads,
+            Some("The follow-up execution was interrupted"),
+        )
+        .await?;
+    }
+
+    let github_service = GitHubService::new(&token)?;
+    let threads = github_service
+        .list_unresolved_review_threads(&repo_info(project)?, pr_number)
+        .await?;
+    let threads = if maintainers_only {
+        maintainer_threads(threads)
+    } else {
+        threads
+    };
+    let addressed: HashSet<i64> = PrCommentFollowup::find_by_attempt_id(pool, attempt.id)
+        .await?
+        .into_iter()
+        .filter(|followup| followup.status == PrCommentFollowupStatus::Pushed)
+        .flat_map(|followup| followup.threads)
+        .map(|thread| thread.last_comment_id)
+        .collect();
+    let threads = pending_threads(threads, &addressed);
+    if threads.is_empty() {
+        return Err(ReviewFollowupError::NothingToAddress);
+    }
+
+    let addressed_threads: Vec<AddressedThread> = threads
+        .iter()
+        .filter_map(|thread| {
+            Some(AddressedThread {
+                root_comment_id: thread.comments.first()?.id,
+                last_comment_id: thread.comments.last()?.id,
+            })
+        })
+        .collect();
+    let base_commit = head_commit(Path::new(&attempt.worktree_path))
+        .ok()
+        .map(|id| id.to_string());
+    // Recorded first so the execution's completion finds it
+    let followup =
+        PrCommentFollowup::create(pool, attempt.id, &addressed_threads, base_commit.as_deref())
+            .await?;
+    if let Err(e) = ProcessService::start_followup_execution(
+        pool,
+        app_state,
+        attempt.id,
+        attempt.task_id,
+        project.id,
+        &compose_prompt(&threads),
+    )
+    .await
+    {
+        let error = e.to_string();
+        PrCommentFollowup::complete(
+            pool,
+            followup.id,
+            PrCommentFollowupStatus::Failed,
+            &addressed_threads,
+            Some(&error),
+        )
+        .await?;
+        return Err(e.into());
+    }
+
+    tracing::info!(
+        "Addressing {} review threads of PR #{} with a follow-up of attempt {}",
+        threads.len(),
+        pr_number,
+        attempt.id
+    );
+    Ok(followup)
+}
+
+fn head_commit(worktree_path: &Path) -> Result<git2::Oid, git2::Error> {
+    let repo = git2::Repository::open(worktree_path)?;
+    let id = repo.head()?.peel_to_commit()?.id();
+    Ok(id)
+}
+
+/// Short id of the commit a follow-up made, if HEAD moved from `base_commit`
+fn fix_commit(base_commit: Option<&str>, head: Option<git2::Oid>) -> Option<String> {
+    let head = head?.to_string();
+    if base_commit? == head {
+        return None;
+    }
+    head.get(..7).map(str::to_string)
+}
+
+async fn push_and_reply(
+    app_state: &AppState,
+    attempt: &TaskAttempt,
+    project: &Project,
+    base_commit: Option<&str>,
+    threads: &mut [AddressedThread],
+) -> Result<(), ReviewFollowupError> {
+    let pr_number = attempt
+        .pr_number
+        .ok_or(ReviewFollowupError::NoPullRequest)?;
+    let token = github_token(app_state)
+        .await
+        .ok_or(ReviewFollowupError::NotAuthenticated)?;
+    let worktree_path = Path::new(&attempt.worktree_path);
+    GitService::new(&project.git_repo_path)?.push_to_remote(
+        worktree_path,
+        &attempt.branch,
+        &token,
+    )?;
+
+    let commit = fix_commit(base_commit, head_commit(worktree_path).ok());
+    let summary = ExecutorSession::find_by_task_attempt_id(&app_state.db_pool, attempt.id)
+        .await?
+        .pop()
+        .and_then(|session| session.summary);
+    let body = reply_body(commit.as_deref(), summary.as_deref());
+
+    let github_service = GitHubService::new(&token)?;
+    let repo_info = repo_info(project)?;
+    for thread in threads.iter_mut() {
+        match github_service
+            .reply_to_review_comment(&repo_info, pr_number, thread.root_comment_id, &body)
+            .await
+        {
+            // Our own reply must not make the thread look unaddressed
+            Ok(reply_id) => thread.last_comment_id = reply_id,
+            Err(e) => tracing::warn!(
+                "Failed to reply to review comment {} of PR #{}: {}",
+                thread.root_comment_id,
+                pr_number,
+                e
+            ),
+        }
+    }
+    Ok(())
+}
+
+/// Finish the attempt's running review follow-up, if any, once its execution
+/// has completed: push the result and reply on the addressed threads
+pub async fn finish(app_state: &AppState, task_attempt_id: Uuid, success: bool) {
+    let pool = &app_state.db_pool;
+    let followup = match PrCommentFollowup::find_running_by_attempt_id(pool, task_attempt_id).await
+    {
+        Ok(Some(followup)) => followup,
+        Ok(None) => return,
+        Err(e) => {
+            tracing::error!(
+                "Failed to load review follow-up of attempt {}: {}",
+                task_attempt_id,
+                e
+            );
+            return;
+        }
+    };
+
+    let mut threads = followup.threads.clone();
+    let result = if success {
+        match load_attempt(pool, task_attempt_id).await {
+            Ok((attempt, project)) => push_and_reply(
+                app_state,
+                &attempt,
+                &project,
+                followup.base_commit.as_deref(),
+                &mut threads,
+            )
+            .await
+            .map(|()| project),
+            Err(e) => Err(e),
+        }
+    } else {
+        Err(ReviewFollowupError::TaskAttempt(
+            TaskAttemptError::ValidationError("The follow-up execution failed".to_string()),
+        ))
+    };
+
+    let (status, error) = match &result {
+        Ok(_) => (PrCommentFollowupStatus::Pushed, None),
+        Err(e) => {
+            tracing::warn!(
+                "Review follow-up of attempt {} failed: {}",
+                task_attempt_id,
+                e
+            );
+            (PrCommentFollowupStatus::Failed, Some(e.to_string()))
+        }
+    };
+    if let Err(e) =
+        PrCommentFollowup::complete(pool, followup.id, status, &threads, error.as_deref()).await
+    {
+        tracing::error!("Failed to record review follow-up {}: {}", followup.id, e);
+        return;
+    }
+
+    // Reviewers may have commented while the follow-up ran
+    if let Ok(project) = result {
+        if Project::auto_address_review_comments_enabled(pool, project.id)
+            .await
+            .unwrap_or(false)
+        {
+            auto_address(app_state, task_attempt_id, &project).await;
+        }
+    }
+}
+
+async fn load_attempt(
+    pool: &sqlx::SqlitePool,
+    task_attempt_id: Uuid,
+) -> Result<(TaskAttempt, Project), ReviewFollowupError> {
+    let attempt = TaskAttempt::find_by_id(pool, task_attempt_id)
+        .await?
+        .ok_or(TaskAttemptError::TaskNotFound)?;
+    let task = Task::find_by_id(pool, attempt.task_id)
+        .await?
+        .ok_or(TaskAttemptError::TaskNotFound)?;
+    let project = Project::find_by_id(pool, task.project_id)
+        .await?
+        .ok_or(TaskAttemptError::ProjectNotFound)?;
+    Ok((attempt, project))
+}
+
+async fn auto_address(app_state: &AppState, task_attempt_id: Uuid, project: &Project) {
+    let attempt = match TaskAttempt::find_by_id(&app_state.db_pool, task_attempt_id).await {
+        Ok(Some(attempt)) => attempt,
+        Ok(None) => return,
+        Err(e) => {
+            tracing::error!("Failed to load attempt {}: {}", task_attempt_id, e);
+            return;
+        }
+    };
+    match address_review_comments(app_state, &attempt, project, true).await {
+        Ok(_) => {}
+        Err(ReviewFollowupError::NothingToAddress | ReviewFollowupError::AttemptRunning) => {}
+        Err(e) => tracing::warn!(
+            "Failed to address review comments of attempt {} automatically: {}",
+            task_attempt_id,
+            e
+        ),
+    }
+}
+
+/// Address the comments of a just submitted review if the PR's project asks
+/// for it
+pub async fn on_review_submitted(app_state: &AppState, pr_url: &str) {
+    let attempt = match TaskAttempt::find_by_pr_url(&app_state.db_pool, pr_url).await {
+        Ok(Some(attempt)) => attempt,
+        Ok(None) => return,
+        Err(e) => {
+            tracing::error!("Failed to find the attempt of {}: {}", pr_url, e);
+            return;
+        }
+    };
+    let project = match load_attempt(&app_state.db_pool, attempt.id).await {
+        Ok((_, project)) => project,
+        Err(e) => {
+            tracing::error!("Failed to load the project of {}: {}", pr_url, e);
+            return;
+        }
+    };
+    match Project::auto_address_review_comments_enabled(&app_state.db_pool, project.id).await {
+        Ok(true) => auto_address(app_state, attempt.id, &project).await,
+        Ok(false) => {}
+        Err(e) => tracing::error!(
+            "Failed to read review automation of project {}: {}",
+            project.id,
+            e
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::github_service::ReviewThreadComment;
+
+    fn thread(path: &str, line: Option<i64>, comment_ids: &[i64]) -> ReviewThread {
+        ReviewThread {
+            path: path.to_string(),
+            line,
+            is_outdated: line.is_none(),
+            diff_hunk: Some("@@ -1,2 +1,2 @@\n-old\n+new".to_string()),
+            comments: comment_ids
+                .iter()
+                .map(|id| ReviewThreadComment {
+                    id: *id,
+                    author: format!("reviewer{}", id),
+                    author_association: "MEMBER".to_string(),
+                    body: format!("Comment {}", id),
+                    url: format!("https://github.com/o/r/pull/1#discussion_r{}", id),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_pending_threads_skip_addressed_ones() {
+        let addressed = HashSet::from([2, 5]);
+        let pending = pending_threads(
+            vec![
+                thread("a.rs", Some(1), &[1, 2]),
+                thread("b.rs", Some(2), &[3, 4]),
+                // Commented on after Forge's reply
+                thread("c.rs", Some(3), &[5, 6]),
+            ],
+            &addressed,
+        );
+        let paths: Vec<&str> = pending.iter().map(|t| t.path.as_str()).collect();
+        assert_eq!(paths, vec!["b.rs", "c.rs"]);
+    }
+
+    #[test]
+    fn test_maintainer_threads_drop_other_comments() {
+        let mut mixed = thread("a.rs", Some(1), &[1, 2]);
+        mixed.comments[1].author_association = "NONE".to_string();
+        let mut outsiders = thread("b.rs", Some(2), &[3]);
+        outsiders.comments[0].author_association = "CONTRIBUTOR".to_string();
+        let threads = maintainer_threads(vec![mixed, outsiders, thread("c.rs", None, &[4])]);
+        let kept: Vec<(&str, Vec<i64>)> = threads
+            .iter()
+            .map(|t| (t.path.as_str(), t.comments.iter().map(|c| c.id).collect()))
+            .collect();
+        assert_eq!(kept, vec![("a.rs", vec![1]), ("c.rs", vec![4])]);
+    }
+
+    #[test]
+    fn test_fix_commit_only_when_head_moved() {
+        let base = git2::Oid::from_str("1111111111111111111111111111111111111111").unwrap();
+        let head = git2::Oid::from_str("abc1234000000000000000000000000000000000").unwrap();
+        assert_eq!(
+            fix_commit(Some(&base.to_string()), Some(head)).as_deref(),
+            Some("abc1234")
+        );
+        assert_eq!(fix_commit(Some(&base.to_string()), Some(base)), None);
+        assert_eq!(fix_commit(None, Some(head)), None);
+        assert_eq!(fix_commit(Some(&base.to_string()), None), None);
+    }
+
+    #[test]
+    fn test_compose_prompt_includes_location_and_comments() {
+        let prompt = compose_prompt(&[
+            thread("src/lib.rs", Some(42), &[1, 2]),
+            thread("src/old.rs", None, &[3]),
+        ]);
+        assert!(prompt.contains("## 1. src/lib.rs:42"));
+        assert!(prompt.contains("```diff\n@@ -1,2 +1,2 @@\n-old\n+new\n```"));
+        assert!(prompt.contains("**reviewer1**: Comment 1\n**reviewer2**: Comment 2"));
+        assert!(prompt.contains("## 2. src/old.rs (the code it was on has since changed)"));
+    }
+
+    #[test]
+    fn test_reply_body() {
+        assert_eq!(reply_body(None, None), "Addressed by Forge.");
+        assert_eq!(
+            reply_body(Some("abc1234"), Some("Renamed it.\nAdded a test.")),
+            "Addressed by Forge in abc1234.\n\n> Renamed it.\n> Added a test.\n"
+        );
+        let long = "x".repeat(REPLY_SUMMARY_CHARS + 10);
+        assert!(reply_body(None, Some(&long)).ends_with("x…\n"));
+    }
+}
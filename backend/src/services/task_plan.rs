@@ -0,0 +1,511 @@
+//! Bulk task creation from a plan document.
+//!
+//! A plan is either YAML:
+//!
+//! ```yaml
+//! wish_id: auth-refactor
+//! tasks:
+//!   - key: schema
+//!     title: Add the sessions table
+//!     description: ...
+//!     executor: claude
+//!   - title: Rotate session tokens
+//!     depends_on: [schema]
+//! ```
+//!
+//! or markdown, where every `##` heading is a task and the lines right below
+//! it may set its metadata:
+//!
+//! ```markdown
+//! # Auth refactor
+//! Wish: auth-refactor
+//!
+//! ## Add the sessions table
+//! Key: schema
+//! Executor: claude
+//!
+//! Description of the task...
+//!
+//! ## Rotate session tokens
+//! Depends on: schema
+//! ```
+//!
+//! Dependencies name another task of the plan by key or title, or an existing
+//! task of the project by ID. Plans are validated as a whole and their tasks
+//! are created in dependency order in a single transaction.
+
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use thiserror::Error;
+use ts_rs::TS;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{
+    executor::ExecutorConfig,
+    models::{
+        task::{CreateTask, Task},
+        task_dependency::TaskDependency,
+    },
+    services::webhooks,
+};
+
+/// Upper bound on the number of tasks a single plan may create
+pub const MAX_PLAN_TASKS: usize = 100;
+
+#[derive(Debug, Error)]
+pub enum TaskPlanError {
+    #[error("Failed to parse plan: {0}")]
+    Parse(String),
+    #[error("Invalid plan: {}", .0.join("; "))]
+    Invalid(Vec<String>),
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS, ToSchema)]
+#[serde(rename_all = "lowercase")]
+#[ts(export)]
+pub enum TaskPlanFormat {
+    #[default]
+    Markdown,
+    Yaml,
+}
+
+/// A plan as written, before validation
+#[derive(Debug, Default, Deserialize)]
+pub struct TaskPlan {
+    #[serde(default, alias = "wish")]
+    pub wish_id: Option<String>,
+    #[serde(default)]
+    pub tasks: Vec<TaskPlanEntry>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct TaskPlanEntry {
+    #[serde(default, alias = "id")]
+    pub key: Option<String>,
+    pub title: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default, alias = "wish")]
+    pub wish_id: Option<String>,
+    #[serde(default)]
+    pub executor: Option<String>,
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+}
+
+/// A validated task of a plan, in creation order
+#[derive(Debug, Clone, Serialize, TS, ToSchema)]
+#[ts(export)]
+pub struct PlannedTask {
+    pub key: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub wish_id: String,
+    pub executor: Option<String>,
+    /// Keys of planned tasks this task depends on
+    pub depends_on: Vec<String>,
+    /// Existing tasks of the project this task depends on
+    pub depends_on_existing: Vec<Uuid>,
+}
+
+impl TaskPlan {
+    pub fn parse(plan: &str, format: TaskPlanFormat) -> Result<Self, TaskPlanError> {
+        match format {
+            TaskPlanFormat::Yaml => {
+                serde_yaml::from_str(plan).map_err(|e| TaskPlanError::Parse(e.to_string()))
+            }
+            TaskPlanFormat::Markdown => Ok(Self::parse_markdown(plan)),
+        }
+    }
+
+    fn parse_markdown(plan: &str) -> Self {
+        let mut parsed = TaskPlan::default();
+        let mut current: Option<(TaskPlanEntry, Vec<&str>, bool)> = None;
+        let mut in_fence = false;
+
+        for line in plan.lines() {
+            let trimmed = line.trim();
+            if trimmed.starts_with("```") {
+                in_fence = !in_fence;
+            }
+
+            if !in_fence {
+                if let Some(title) = trimmed.strip_prefix("## ") {
+                    if let Some(entry) = current.take() {
+                        parsed.tasks.push(finish_markdown_task(entry));
+                    }
+                    let entry = TaskPlanEntry {
+                        title: title.trim().to_string(),
+                        ..Default::default()
+                    };
+                    current = Some((entry, Vec::new(), true));
+                    continue;
+                }
+            }
+
+            match current.as_mut() {
+                // Metadata lines directly below the heading
+                Some((entry, body, in_metadata)) => {
+                    if *in_metadata && !in_fence {
+                        if trimmed.is_empty() && body.is_empty() {
+                            continue;
+                        }
+                        if let Some((name, value)) = metadata_line(trimmed) {
+                            match name.as_str() {
+                                "key" | "id" => entry.key = Some(value),
+                                "executor" => entry.executor = Some(value),
+                                "wish" | "wish_id" => entry.wish_id = Some(value),
+                                "depends_on" => entry.depends_on.extend(split_list(&value)),
+                                _ => unreachable!(),
+                            }
+                            continue;
+                        }
+                        *in_metadata = false;
+                    }
+                    body.push(line);
+                }
+                // Before the first task only the plan's wish can be set
+                None => {
+                    if let Some((name, value)) = metadata_line(trimmed) {
+                        if name == "wish" || name == "wish_id" {
+                            parsed.wish_id = Some(value);
+                        }
+                    }
+                }
+            }
+        }
+        if let Some(entry) = current.take() {
+            parsed.tasks.push(finish_markdown_task(entry));
+        }
+        parsed
+    }
+
+    /// Validate the plan and order its tasks so dependencies come first.
+    /// `default_wish_id` applies to tasks for which neither the task nor the
+    /// plan names a wish.
+    pub fn validate(
+        self,
+        default_wish_id: Option<&str>,
+    ) -> Result<Vec<PlannedTask>, TaskPlanError> {
+        let mut errors = Vec::new();
+        if self.tasks.is_empty() {
+            errors.push("the plan has no tasks".to_string());
+        }
+        if self.tasks.len() > MAX_PLAN_TASKS {
+            errors.push(format!(
+                "the plan has {} tasks, at most {} are allowed",
+                self.tasks.len(),
+                MAX_PLAN_TASKS
+            ));
+        }
+
+        let plan_wish_id =
+            non_empty(self.wish_id).or_else(|| non_empty(default_wish_id.map(str::to_string)));
+
+        // Keys (the title unless set) and titles both identify a task in `depends_on`
+        let mut keys = Vec::new();
+        let mut lookup: HashMap<String, usize> = HashMap::new();
+        for (index, entry) in self.tasks.iter().enumerate() {
+            let key = entry
+                .key
+                .as_deref()
+                .map(str::trim)
+                .filter(|key| !key.is_empty())
+                .unwrap_or(entry.title.trim())
+                .to_string();
+            if lookup.insert(key.clone(), index).is_some() {
+                errors.push(format!("more than one task has the key '{}'", key));
+            }
+            keys.push(key);
+        }
+        for (index, entry) in self.tasks.iter().enumerate() {
+            lookup
+                .entry(entry.title.trim().to_string())
+                .or_insert(index);
+        }
+
+        let mut planned = Vec::with_capacity(self.tasks.len());
+        let mut edges: Vec<Vec<usize>> = vec![Vec::new(); self.tasks.len()];
+        for (index, entry) in self.tasks.into_iter().enumerate() {
+            let title = entry.title.trim().to_string();
+            let label = if title.is_empty() {
+                format!("task {}", index + 1)
+            } else {
+                format!("task '{}'", title)
+            };
+            if title.is_empty() {
+                errors.push(format!("{} has no title", label));
+            }
+
+            let wish_id = non_empty(entry.wish_id).or_else(|| plan_wish_id.clone());
+            if wish_id.is_none() {
+                errors.push(format!("{} has no wish id", label));
+            }
+
+            let executor = non_empty(entry.executor);
+            if let Some(executor) = &executor {
+                if let Err(e) = ExecutorConfig::from_str(executor) {
+                    errors.push(format!("{}: {}", label, e));
+                }
+            }
+
+            let mut depends_on = Vec::new();
+            let mut depends_on_existing = Vec::new();
+            for dependency in entry.depends_on.iter().map(|d| d.trim()) {
+                if let Some(&other) = lookup.get(dependency) {
+                    if other == index {
+                        errors.push(format!("{} depends on itself", label));
+                    } else if !edges[index].contains(&other) {
+                        edges[index].push(other);
+                        depends_on.push(keys[other].clone());
+                    }
+                } else if let Ok(task_id) = Uuid::parse_str(dependency) {
+                    if !depends_on_existing.contains(&task_id) {
+                        depends_on_existing.push(task_id);
+                    }
+                } else {
+                    errors.push(format!(
+                        "{} depends on unknown task '{}'",
+                        label, dependency
+                    ));
+                }
+            }
+
+            planned.push(PlannedTask {
+                key: keys[index].clone(),
+                title,
+                description: non_empty(entry.description),
+                wish_id: wish_id.unwrap_or_default(),
+                executor,
+                depends_on,
+                depends_on_existing,
+            });
+        }
+
+        let order = match dependency_order(&edges) {
+            Some(order) => order,
+            None => {
+                errors.push("the dependencies of the plan form a cycle".to_string());
+                Vec::new()
+            }
+        };
+        if !errors.is_empty() {
+            return Err(TaskPlanError::Invalid(errors));
+        }
+
+        let mut planned: Vec<Option<PlannedTask>> = planned.into_iter().map(Some).collect();
+        Ok(order
+            .into_iter()
+            .filter_map(|index| planned[index].take())
+            .collect())
+    }
+}
+
+/// Parse and validate a plan, and check that its dependencies on existing
+/// tasks belong to the project. Nothing is written.
+pub async fn preview_plan(
+    pool: &SqlitePool,
+    project_id: Uuid,
+    plan: &str,
+    format: TaskPlanFormat,
+    default_wish_id: Option<&str>,
+) -> Result<Vec<PlannedTask>, TaskPlanError> {
+    let planned = TaskPlan::parse(plan, format)?.validate(default_wish_id)?;
+
+    let mut errors = Vec::new();
+    let existing: HashSet<Uuid> = planned
+        .iter()
+        .flat_map(|task| task.depends_on_existing.iter().copied())
+        .collect();
+    for task_id in existing {
+        if Task::find_by_id_and_project_id(pool, task_id, project_id)
+            .await?
+            .is_none()
+        {
+            errors.push(format!("task {} does not exist in this project", task_id));
+        }
+    }
+    if !errors.is_empty() {
+        return Err(TaskPlanError::Invalid(errors));
+    }
+    Ok(planned)
+}
+
+/// Create the previewed tasks, their preferred executors and dependencies in
+/// one transaction: either the whole plan is created or nothing is.
+pub async fn create_planned_tasks(
+    pool: &SqlitePool,
+    project_id: Uuid,
+    planned: &[PlannedTask],
+    created_by: Option<Uuid>,
+) -> Result<Vec<Task>, TaskPlanError> {
+    let mut tx = pool.begin().await?;
+    let mut created_ids: HashMap<&str, Uuid> = HashMap::new();
+    let mut created = Vec::with_capacity(planned.len());
+
+    for planned_task in planned {
+        let task_id = Uuid::new_v4();
+        let task = Task::create(
+            &mut *tx,
+            &CreateTask {
+                project_id,
+                title: planned_task.title.clone(),
+                description: planned_task.description.clone(),
+                wish_id: planned_task.wish_id.clone(),
+                parent_task_attempt: None,
+                created_by,
+                assigned_to: None,
+            },
+            task_id,
+        )
+        .await?;
+
+        if let Some(executor) = &planned_task.executor {
+            Task::set_preferred_executor(&mut *tx, task_id, Some(executor)).await?;
+        }
+        // Tasks are in dependency order, so planned dependencies already exist
+        for key in &planned_task.depends_on {
+            let depends_on = created_ids[key.as_str()];
+            TaskDependency::create(&mut *tx, task_id, depends_on).await?;
+        }
+        for depends_on in &planned_task.depends_on_existing {
+            TaskDependency::create(&mut *tx, task_id, *depends_on).await?;
+        }
+
+        created_ids.insert(&planned_task.key, task_id);
+        created.push(task);
+    }
+
+    tx.commit().await?;
+    for task in &created {
+        webhooks::emit_task_created(pool, task).await;
+    }
+    Ok(created)
+}
+
+fn finish_markdown_task((mut entry, body, _): (TaskPlanEntry, Vec<&str>, bool)) -> TaskPlanEntry {
+    entry.description = Some(body.join("\n").trim().to_string());
+    entry
+}
+
+/// `Name: value` lines recognized below a markdown task heading
+fn metadata_line(line: &str) -> Option<(String, String)> {
+    let line = line.trim_start_matches(['-', '*']).trim();
+    let (name, value) = line.split_once(':')?;
+    let name = name
+        .trim()
+        .trim_matches('*')
+        .to_lowercase()
+        .replace([' ', '-'], "_");
+    let name = match name.as_str() {
+        "key" | "id" | "executor" | "wish" | "wish_id" => name,
+        "depends_on" | "dependencies" | "after" => "depends_on".to_string(),
+        _ => return None,
+    };
+    Some((name, value.trim().trim_matches('`').to_string()))
+}
+
+fn split_list(value: &str) -> impl Iterator<Item = String> + '_ {
+    value
+        .split(',')
+        .map(|item| item.trim().trim_matches('`').to_string())
+        .filter(|item| !item.is_empty())
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// Indices in an order where every task comes after its dependencies, or
+/// `None` if the dependencies form a cycle. Keeps plan order where possible.
+fn dependency_order(edges: &[Vec<usize>]) -> Option<Vec<usize>> {
+    let mut remaining: Vec<usize> = edges.iter().map(Vec::len).collect();
+    let mut done = vec![false; edges.len()];
+    let mut order = Vec::with_capacity(edges.len());
+
+    while order.len() < edges.len() {
+        let next = (0..edges.len()).find(|&index| !done[index] && remaining[index] == 0)?;
+        done[next] = true;
+        order.push(next);
+        for (index, dependencies) in edges.iter().enumerate() {
+            if dependencies.contains(&next) {
+                remaining[index] -= 1;
+            }
+        }
+    }
+    Some(order)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_markdown_plan() {
+        let plan = "# Auth refactor\nWish: auth\n\n## Add sessions table\nKey: schema\nExecutor: claude\n\nCreate the table.\n\n```sql\n## not a task\n```\n\n## Rotate tokens\nDepends on: schema\n\nRotate them.\n";
+        let parsed = TaskPlan::parse(plan, TaskPlanFormat::Markdown).unwrap();
+
+        assert_eq!(parsed.wish_id.as_deref(), Some("auth"));
+        assert_eq!(parsed.tasks.len(), 2);
+        assert_eq!(parsed.tasks[0].key.as_deref(), Some("schema"));
+        assert_eq!(parsed.tasks[0].executor.as_deref(), Some("claude"));
+        assert_eq!(
+            parsed.tasks[0].description.as_deref(),
+            Some("Create the table.\n\n```sql\n## not a task\n```")
+        );
+        assert_eq!(parsed.tasks[1].depends_on, vec!["schema".to_string()]);
+    }
+
+    #[test]
+    fn test_validate_orders_dependencies_first() {
+        let plan = "wish_id: w\ntasks:\n  - title: Second\n    depends_on: [first]\n  - key: first\n    title: First\n";
+        let planned = TaskPlan::parse(plan, TaskPlanFormat::Yaml)
+            .unwrap()
+            .validate(None)
+            .unwrap();
+
+        let titles: Vec<_> = planned.iter().map(|t| t.title.as_str()).collect();
+        assert_eq!(titles, vec!["First", "Second"]);
+        assert_eq!(planned[1].depends_on, vec!["first".to_string()]);
+        assert!(planned.iter().all(|t| t.wish_id == "w"));
+    }
+
+    #[test]
+    fn test_validate_reports_all_errors() {
+        let plan = "tasks:\n  - title: A\n    executor: nope\n    depends_on: [B, missing]\n  - title: B\n    depends_on: [A]\n";
+        let Err(TaskPlanError::Invalid(errors)) = TaskPlan::parse(plan, TaskPlanFormat::Yaml)
+            .unwrap()
+            .validate(None)
+        else {
+            panic!("plan should be invalid");
+        };
+
+        assert!(errors.iter().any(|e| e.contains("no wish id")));
+        assert!(errors.iter().any(|e| e.contains("Unknown executor type")));
+        assert!(errors.iter().any(|e| e.contains("unknown task 'missing'")));
+        assert!(errors.iter().any(|e| e.contains("cycle")));
+    }
+
+    #[test]
+    fn test_existing_task_dependencies() {
+        let existing = Uuid::new_v4();
+        let plan = format!("## Follow-up\nWish: w\nDepends on: {}\n", existing);
+        let planned = TaskPlan::parse(&plan, TaskPlanFormat::Markdown)
+            .unwrap()
+            .validate(None)
+            .unwrap();
+
+        assert_eq!(planned[0].wish_id, "w");
+        assert_eq!(planned[0].depends_on_existing, vec![existing]);
+    }
+}
@@ -0,0 +1,565 @@
+//! Outgoing webhooks for task and execution lifecycle events.
+//!
+//! Emitting an event only queues a delivery for every subscribed webhook of
+//! the project in `webhook_deliveries`; the `WebhookDispatcher` started with
+//! the server sends due deliveries and retries failed ones with exponential
+//! backoff, so events survive restarts and slow receivers never hold up the
+//! code that emitted them.
+//!
+//! Every request carries the event name, the delivery ID, a Unix timestamp and
+//! an HMAC-SHA256 signature of `<timestamp>.<body>` made with the webhook's
+//! secret, as `sha256=<hex>`.
+//!
+//! Webhooks may only point at public addresses: URLs are checked when they are
+//! saved and again before every delivery, which then connects only to the
+//! addresses that were checked, so the server cannot be used to reach its own
+//! network.
+
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    time::Duration,
+};
+
+use chrono::Utc;
+use futures_util::StreamExt;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use sqlx::SqlitePool;
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::{
+    models::{
+        task::{Task, TaskStatus},
+        webhook::{UpsertWebhook, Webhook, WebhookDelivery, WebhookEvent},
+    },
+    security::secret_store::{SecretStore, SecretStoreError},
+};
+
+pub const EVENT_HEADER: &str = "X-Forge-Event";
+pub const DELIVERY_HEADER: &str = "X-Forge-Delivery";
+pub const TIMESTAMP_HEADER: &str = "X-Forge-Timestamp";
+pub const SIGNATURE_HEADER: &str = "X-Forge-Signature";
+
+/// Attempts before a delivery is given up on
+pub const MAX_ATTEMPTS: i64 = 8;
+
+const SECRET_NAME: &str = "signing_secret";
+const FIRST_RETRY_SECONDS: i64 = 30;
+const MAX_RETRY_SECONDS: i64 = 6 * 60 * 60;
+const MAX_URL_LENGTH: usize = 2048;
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+const BATCH_SIZE: i64 = 50;
+/// Deliveries sent at once, so one slow receiver does not hold up the rest
+const MAX_CONCURRENT_DELIVERIES: usize = 8;
+const MAX_ERROR_LENGTH: usize = 500;
+
+#[derive(Debug, Error)]
+pub enum WebhookError {
+    #[error("{0}")]
+    Invalid(String),
+    #[error("Secret store error: {0}")]
+    Secret(#[from] SecretStoreError),
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+/// Secret store scope holding a webhook's signing secret
+pub fn secret_scope(webhook_id: Uuid) -> String {
+    format!("webhook:{}", webhook_id)
+}
+
+pub fn validate(data: &UpsertWebhook) -> Result<(), String> {
+    if !(data.url.starts_with("https://") || data.url.starts_with("http://")) {
+        return Err("Webhook URLs must be http(s) URLs".to_string());
+    }
+    if data.url.len() > MAX_URL_LENGTH {
+        return Err(format!(
+            "Webhook URLs can be at most {} characters",
+            MAX_URL_LENGTH
+        ));
+    }
+    Ok(())
+}
+
+/// Whether `ip` is reachable from outside this machine and its networks:
+/// loopback, private, link-local, unspecified and broadcast addresses are not
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast())
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public(IpAddr::V4(ip)),
+            None => {
+                let unique_local = ip.segments()[0] & 0xfe00 == 0xfc00;
+                let link_local = ip.segments()[0] & 0xffc0 == 0xfe80;
+                !(ip.is_loopback() || ip.is_unspecified() || unique_local || link_local)
+            }
+        },
+    }
+}
+
+/// Resolve the URL's host, checking every address it has is public
+async fn resolve_destination(url: &str) -> Result<(String, Vec<SocketAddr>), String> {
+    let parsed = reqwest::Url::parse(url).map_err(|e| format!("Invalid webhook URL: {}", e))?;
+    let host = parsed
+        .host_str()
+        .ok_or_else(|| "Webhook URLs need a host".to_string())?;
+    let port = parsed.port_or_known_default().unwrap_or(443);
+    let addresses: Vec<SocketAddr> = match host.trim_matches(|c| c == '[' || c == ']').parse() {
+        Ok(ip) => vec![SocketAddr::new(ip, port)],
+        Err(_) => tokio::net::lookup_host((host, port))
+            .await
+            .map_err(|e| format!("Could not resolve {}: {}", host, e))?
+            .collect(),
+    };
+    if addresses.is_empty() {
+        return Err(format!("Could not resolve {}", host));
+    }
+    if !addresses.iter().all(|address| is_public(address.ip())) {
+        return Err("Webhook URLs must not point to local or private addresses".to_string());
+    }
+    Ok((host.to_string(), addresses))
+}
+
+/// Check a webhook URL only leads to public addresses
+pub async fn check_destination(url: &str) -> Result<(), String> {
+    resolve_destination(url).await.map(|_| ())
+}
+
+fn generate_secret() -> String {
+    format!(
+        "whsec_{}{}",
+        Uuid::new_v4().simple(),
+        Uuid::new_v4().simple()
+    )
+}
+
+/// Create a webhook with a new signing secret, returned in plain text this
+/// once
+pub async fn create(
+    pool: &SqlitePool,
+    store: &SecretStore,
+    project_id: Uuid,
+    created_by: Option<Uuid>,
+    data: &UpsertWebhook,
+) -> Result<(Webhook, String), WebhookError> {
+    validate(data).map_err(WebhookError::Invalid)?;
+    check_destination(&data.url)
+        .await
+        .map_err(WebhookError::Invalid)?;
+
+    let webhook = Webhook::create(pool, project_id, created_by, data).await?;
+    let secret = generate_secret();
+    store
+        .put(&secret_scope(webhook.id), SECRET_NAME, &secret)
+        .await?;
+    Ok((webhook, secret))
+}
+
+/// Update a webhook, returning the new signing secret when it was rotated
+pub async fn update(
+    pool: &SqlitePool,
+    store: &SecretStore,
+    webhook: &Webhook,
+    data: &UpsertWebhook,
+    rotate_secret: bool,
+) -> Result<(Webhook, Option<String>), WebhookError> {
+    validate(data).map_err(WebhookError::Invalid)?;
+    check_destination(&data.url)
+        .await
+        .map_err(WebhookError::Invalid)?;
+
+    let updated = Webhook::update(pool, webhook.id, data).await?;
+    let secret = if rotate_secret {
+        let secret = generate_secret();
+        store
+            .put(&secret_scope(webhook.id), SECRET_NAME, &secret)
+            .await?;
+        Some(secret)
+    } else {
+        None
+    };
+    Ok((updated, secret))
+}
+
+/// Delete a webhook, its deliveries and its signing secret
+pub async fn remove(
+    pool: &SqlitePool,
+    store: &SecretStore,
+    webhook: &Webhook,
+) -> Result<(), WebhookError> {
+    store.delete(&secret_scope(webhook.id), SECRET_NAME).await?;
+    Webhook::delete(pool, webhook.id).await?;
+    Ok(())
+}
+
+/// `sha256=<hex>` HMAC of `<timestamp>.<body>`
+pub fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    format!("sha256={:x}", mac.finalize().into_bytes())
+}
+
+/// Seconds to wait after the `attempts`-th failed attempt, doubling from 30
+/// seconds up to 6 hours, or `None` once the delivery should be given up on
+pub fn retry_delay_seconds(attempts: i64) -> Option<i64> {
+    if attempts >= MAX_ATTEMPTS {
+        return None;
+    }
+    let exponent = (attempts.max(1) - 1).min(30) as u32;
+    Some(
+        FIRST_RETRY_SECONDS
+            .saturating_mul(2_i64.saturating_pow(exponent))
+            .min(MAX_RETRY_SECONDS),
+    )
+}
+
+/// The JSON body sent for an event
+fn payload(project_id: Uuid, event: WebhookEvent, data: serde_json::Value) -> serde_json::Value {
+    serde_json::json!({
+        "event": event,
+        "project_id": project_id,
+        "occurred_at": Utc::now(),
+        "data": data,
+    })
+}
+
+/// Queue `event` for every webhook of the project subscribed to it. Failures
+/// are logged; they never fail the action that emitted the event.
+pub async fn emit(
+    pool: &SqlitePool,
+    project_id: Uuid,
+    event: WebhookEvent,
+    data: serde_json::Value,
+) {
+    let webhooks = match Webhook::find_by_project_id(pool, project_id).await {
+        Ok(webhooks) => webhooks,
+        Err(e) => {
+            tracing::error!("Failed to load webhooks of project {}: {}", project_id, e);
+            return;
+        }
+    };
+    let mut subscribed = webhooks
+        .into_iter()
+        .filter(|webhook| webhook.subscribes_to(event))
+        .peekable();
+    if subscribed.peek().is_none() {
+        return;
+    }
+
+    let body = payload(project_id, event, data).to_string();
+    for webhook in subscribed {
+        if let Err(e) = WebhookDelivery::create(pool, webhook.id, event, &body).await {
+            tracing::error!(
+                "Failed to queue {} for webhook {}: {}",
+                event,
+                webhook.id,
+                e
+            );
+        }
+    }
+}
+
+/// Emit `task_created` for a new task
+pub async fn emit_task_created(pool: &SqlitePool, task: &Task) {
+    emit(
+        pool,
+        task.project_id,
+        WebhookEvent::TaskCreated,
+        serde_json::json!({ "task": task }),
+    )
+    .await;
+}
+
+/// Emit `task_status_changed` for a task moved to `status`
+pub async fn emit_task_status(
+    pool: &SqlitePool,
+    task_id: Uuid,
+    project_id: Uuid,
+    status: TaskStatus,
+) {
+    emit(
+        pool,
+        project_id,
+        WebhookEvent::TaskStatusChanged,
+        serde_json::json!({ "task_id": task_id, "status": status }),
+    )
+    .await;
+}
+
+/// Emit an attempt event, with `details` merged into the attempt's IDs
+pub async fn emit_attempt_event(
+    pool: &SqlitePool,
+    event: WebhookEvent,
+    project_id: Uuid,
+    task_id: Uuid,
+    attempt_id: Uuid,
+    details: serde_json::Value,
+) {
+    let mut data = serde_json::json!({ "task_id": task_id, "attempt_id": attempt_id });
+    if let (Some(data), serde_json::Value::Object(details)) = (data.as_object_mut(), details) {
+        data.extend(details);
+    }
+    emit(pool, project_id, event, data).await;
+}
+
+/// How a delivery attempt ended
+enum Outcome {
+    Delivered(i64),
+    Failed(Option<i64>, String),
+    /// The webhook is gone or disabled, so the delivery is never retried
+    Cancelled(String),
+}
+
+/// Sends queued deliveries until the server stops
+pub struct WebhookDispatcher {
+    pool: SqlitePool,
+    secret_store: Arc<SecretStore>,
+}
+
+impl WebhookDispatcher {
+    pub fn new(pool: SqlitePool, secret_store: Arc<SecretStore>) -> Self {
+        Self { pool, secret_store }
+    }
+
+    pub async fn start(&self) {
+        tracing::info!(
+            "Starting webhook dispatcher with interval {:?}",
+            POLL_INTERVAL
+        );
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.dispatch_due().await {
+                tracing::error!("Failed to dispatch webhook deliveries: {}", e);
+            }
+        }
+    }
+
+    async fn dispatch_due(&self) -> Result<(), sqlx::Error> {
+        let due = WebhookDelivery::find_due(&self.pool, BATCH_SIZE).await?;
+        futures_util::stream::iter(due)
+            .for_each_concurrent(MAX_CONCURRENT_DELIVERIES, |delivery| async move {
+                let outcome = self.deliver(&delivery).await;
+                if let Err(e) = self.record(&delivery, outcome).await {
+                    tracing::error!("Failed to record webhook delivery {}: {}", delivery.id, e);
+                }
+            })
+            .await;
+        Ok(())
+    }
+
+    async fn record(
+        &self,
+        delivery: &WebhookDelivery,
+        outcome: Outcome,
+    ) -> Result<(), sqlx::Error> {
+        match outcome {
+            Outcome::Delivered(status_code) => {
+                WebhookDelivery::mark_delivered(&self.pool, delivery.id, status_code).await
+            }
+            Outcome::Cancelled(reason) => {
+                WebhookDelivery::mark_cancelled(&self.pool, delivery.id, &reason).await
+            }
+            Outcome::Failed(status_code, error) => {
+                let retry_in = retry_delay_seconds(delivery.attempts + 1);
+                if retry_in.is_none() {
+                    tracing::warn!(
+                        "Giving up on webhook delivery {} after {} attempts: {}",
+                        delivery.id,
+                        delivery.attempts + 1,
+                        error
+                    );
+                }
+                WebhookDelivery::mark_attempt_failed(
+                    &self.pool,
+                    delivery.id,
+                    status_code,
+                    &error,
+                    retry_in,
+                )
+                .await
+            }
+        }
+    }
+
+    /// Send one delivery
+    async fn deliver(&self, delivery: &WebhookDelivery) -> Outcome {
+        let webhook = match Webhook::find_by_id(&self.pool, delivery.webhook_id).await {
+            Ok(Some(webhook)) if webhook.enabled => webhook,
+            Ok(Some(_)) => return Outcome::Cancelled("Webhook is disabled".to_string()),
+            Ok(None) => return Outcome::Cancelled("Webhook was deleted".to_string()),
+            Err(e) => return Outcome::Failed(None, format!("Failed to load webhook: {}", e)),
+        };
+        let (host, addresses) = match resolve_destination(&webhook.url).await {
+            Ok(destination) => destination,
+            Err(e) => return Outcome::Failed(None, e),
+        };
+        // Connect only to the addresses just checked, so a DNS change in
+        // between cannot redirect the request, and follow no redirects
+        let client = match reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .redirect(reqwest::redirect::Policy::none())
+            .resolve_to_addrs(&host, &addresses)
+            .user_agent("automagik-forge-webhooks")
+            .build()
+        {
+            Ok(client) => client,
+            Err(e) => return Outcome::Failed(None, format!("Failed to build client: {}", e)),
+        };
+        let secret = match self
+            .secret_store
+            .get(&secret_scope(webhook.id), SECRET_NAME)
+            .await
+        {
+            Ok(Some(secret)) => secret,
+            Ok(None) => return Outcome::Failed(None, "Webhook has no signing secret".to_string()),
+            Err(e) => {
+                return Outcome::Failed(None, format!("Failed to read signing secret: {}", e))
+            }
+        };
+
+        let timestamp = Utc::now().timestamp();
+        let response = client
+            .post(&webhook.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(EVENT_HEADER, &delivery.event)
+            .header(DELIVERY_HEADER, delivery.id.to_string())
+            .header(TIMESTAMP_HEADER, timestamp.to_string())
+            .header(
+                SIGNATURE_HEADER,
+                sign(secret.as_str(), timestamp, &delivery.payload),
+            )
+            .body(delivery.payload.clone())
+            .send()
+            .await;
+
+        match response {
+            Ok(response) if response.status().is_success() => {
+                Outcome::Delivered(response.status().as_u16() as i64)
+            }
+            Ok(response) => {
+                let status = response.status();
+                let body = response.text().await.unwrap_or_default();
+                let mut error = format!("HTTP {}: {}", status, body);
+                truncate(&mut error);
+                Outcome::Failed(Some(status.as_u16() as i64), error)
+            }
+            Err(e) => {
+                let mut error = e.to_string();
+                truncate(&mut error);
+                Outcome::Failed(None, error)
+            }
+        }
+    }
+}
+
+fn truncate(error: &mut String) {
+    if error.len() > MAX_ERROR_LENGTH {
+        let mut end = MAX_ERROR_LENGTH;
+        while !error.is_char_boundary(end) {
+            end -= 1;
+        }
+        error.truncate(end);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign() {
+        // HMAC-SHA256 of `1700000000.{"event":"task_created"}` keyed with `whsec_test`
+        assert_eq!(
+            sign("whsec_test", 1_700_000_000, r#"{"event":"task_created"}"#),
+            "sha256=a9f4b72711200ab8990e84ad519aeacb5b7b56256e3b9c2def7722305fa37f42"
+        );
+        assert_ne!(
+            sign("whsec_test", 1_700_000_001, r#"{"event":"task_created"}"#),
+            sign("whsec_test", 1_700_000_000, r#"{"event":"task_created"}"#)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_local_and_private_destinations_are_rejected() {
+        for url in [
+            "http://127.0.0.1:8080/hook",
+            "http://localhost/hook",
+            "http://169.254.169.254/latest/meta-data",
+            "http://10.0.0.5/hook",
+            "http://192.168.1.10/hook",
+            "http://172.16.0.1/hook",
+            "http://0.0.0.0/hook",
+            "http://[::1]/hook",
+            "http://[fd00::1]/hook",
+            "http://[::ffff:127.0.0.1]/hook",
+        ] {
+            assert!(check_destination(url).await.is_err(), "{}", url);
+        }
+        assert!(check_destination("https://93.184.216.34/hook")
+            .await
+            .is_ok());
+        assert!(check_destination("https://[2606:2800:220:1::]/hook")
+            .await
+            .is_ok());
+    }
+
+    #[test]
+    fn test_retry_delay_backs_off_exponentially() {
+        assert_eq!(retry_delay_seconds(1), Some(30));
+        assert_eq!(retry_delay_seconds(2), Some(60));
+        assert_eq!(retry_delay_seconds(3), Some(120));
+        assert_eq!(retry_delay_seconds(MAX_ATTEMPTS - 1), Some(30 * 64));
+        assert_eq!(retry_delay_seconds(MAX_ATTEMPTS), None);
+    }
+
+    #[test]
+    fn test_subscriptions() {
+        let mut webhook = Webhook {
+            id: Uuid::new_v4(),
+            project_id: Uuid::new_v4(),
+            url: "https://example.com/hook".to_string(),
+            events: Vec::new(),
+            enabled: true,
+            created_by: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        assert!(webhook.subscribes_to(WebhookEvent::PrMerged));
+
+        webhook.events = vec![WebhookEvent::AttemptFailed];
+        assert!(webhook.subscribes_to(WebhookEvent::AttemptFailed));
+        assert!(!webhook.subscribes_to(WebhookEvent::PrMerged));
+
+        webhook.enabled = false;
+        assert!(!webhook.subscribes_to(WebhookEvent::AttemptFailed));
+    }
+
+    #[test]
+    fn test_validate_urls() {
+        let data = |url: &str| UpsertWebhook {
+            url: url.to_string(),
+            events: Vec::new(),
+            enabled: true,
+        };
+        assert!(validate(&data("https://example.com/hook")).is_ok());
+        assert!(validate(&data("ftp://example.com/hook")).is_err());
+        assert!(validate(&data(&format!(
+            "https://example.com/{}",
+            "a".repeat(MAX_URL_LENGTH)
+        )))
+        .is_err());
+    }
+}
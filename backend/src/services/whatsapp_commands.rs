@@ -0,0 +1,849 @@
+//! Commands teammates send to the install's WhatsApp number.
+//!
+//! Evolution API delivers incoming messages to
+//! `/api/integrations/whatsapp/webhook`. The sender is the user who verified
+//! their number by entering a one-time code Forge sent to it through the
+//! outbox, and commands act on that user's behalf: a user may act on tasks
+//! they created or are assigned to, admins on any task. Commands name a task
+//! by the start of its ID (`#3f2a9c1b`) or default to the user's most
+//! recently updated task in progress or in review.
+
+use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::{
+    app_state::AppState,
+    models::{
+        project::Project,
+        task::{CreateTask, Task, TaskStatus},
+        task_attempt::TaskAttempt,
+        user::User,
+        user_notification_channel::{NotificationChannelKind, UserNotificationChannel},
+        webhook::WebhookEvent,
+        whatsapp_message::WhatsAppMessage,
+        whatsapp_number_verification::WhatsAppNumberVerification,
+    },
+    routes::task_attempts::find_plan_content_with_context,
+    security::secret_store::{SecretStore, CONFIG_SCOPE, EVOLUTION_API_WEBHOOK_TOKEN_SECRET},
+    services::{github_issues, notification_rules::task_url, webhooks},
+};
+
+const MIN_TASK_REF_LENGTH: usize = 4;
+const STATUS_TASK_LIMIT: i64 = 5;
+
+/// How long a verification code can be entered
+const VERIFICATION_CODE_TTL_MINUTES: i64 = 10;
+/// How long before another code can be sent to the same number
+const VERIFICATION_RESEND_SECONDS: i64 = 60;
+/// Wrong codes entered before a new one has to be requested
+const MAX_VERIFICATION_ATTEMPTS: i64 = 5;
+
+pub const HELP: &str = "Commands:\n\
+    status\n\
+    approve plan [#task]\n\
+    follow up [#task]: <prompt>\n\
+    merge [#task]\n\
+    create task in <project>: <title>\n\n\
+    Without #task, commands act on your most recently updated task in progress or in review.";
+
+/// A command parsed from a WhatsApp message. `task` is the start of a task
+/// ID, as lowercase hex digits without dashes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChatCommand {
+    Help,
+    Status,
+    ApprovePlan {
+        task: Option<String>,
+    },
+    FollowUp {
+        task: Option<String>,
+        prompt: String,
+    },
+    Merge {
+        task: Option<String>,
+    },
+    CreateTask {
+        project: String,
+        title: String,
+    },
+}
+
+impl ChatCommand {
+    /// Name recorded in the audit log
+    pub fn name(&self) -> &'static str {
+        match self {
+            ChatCommand::Help => "help",
+            ChatCommand::Status => "status",
+            ChatCommand::ApprovePlan { .. } => "approve_plan",
+            ChatCommand::FollowUp { .. } => "follow_up",
+            ChatCommand::Merge { .. } => "merge",
+            ChatCommand::CreateTask { .. } => "create_task",
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ChatCommandError {
+    #[error("{0}")]
+    NotFound(String),
+    #[error("{0}")]
+    Forbidden(String),
+    #[error("{0}")]
+    Failed(String),
+    #[error("Something went wrong, please try again later")]
+    Database(#[from] sqlx::Error),
+}
+
+#[derive(Debug, Error)]
+pub enum NumberVerificationError {
+    #[error("This number is already verified by another user")]
+    Claimed,
+    #[error("A code was just sent to this number, wait a minute before asking for another")]
+    TooSoon,
+    #[error("No code is waiting to be entered for this number, ask for a new one")]
+    NoPendingCode,
+    #[error("Too many wrong codes, ask for a new one")]
+    TooManyAttempts,
+    #[error("Wrong verification code")]
+    WrongCode,
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
+}
+
+/// `text` after `keyword`, if `text` starts with it as a whole word,
+/// ignoring case
+fn strip_keyword<'a>(text: &'a str, keyword: &str) -> Option<&'a str> {
+    let head = text.get(..keyword.len())?;
+    if !head.eq_ignore_ascii_case(keyword) {
+        return None;
+    }
+    let rest = &text[keyword.len()..];
+    match rest.chars().next() {
+        None => Some(rest),
+        Some(c) if c.is_whitespace() || c == ':' => Some(rest),
+        Some(_) => None,
+    }
+}
+
+/// An optional leading `#task` reference and the text after it. `None` when
+/// the reference is malformed.
+fn split_task_ref(text: &str) -> Option<(Option<String>, &str)> {
+    let text = text.trim_start();
+    let Some(reference) = text.strip_prefix('#') else {
+        return Some((None, text));
+    };
+    let end = reference
+        .find(|c: char| c.is_whitespace() || c == ':')
+        .unwrap_or(reference.len());
+    let prefix: String = reference[..end]
+        .chars()
+        .filter(|c| *c != '-')
+        .collect::<String>()
+        .to_ascii_lowercase();
+    let valid = prefix.len() >= MIN_TASK_REF_LENGTH
+        && prefix.len() <= 32
+        && prefix.chars().all(|c| c.is_ascii_hexdigit());
+    valid.then(|| (Some(prefix), &reference[end..]))
+}
+
+/// Parse a message into a command, or `None` when it isn't one
+pub fn parse(text: &str) -> Option<ChatCommand> {
+    let text = text.trim();
+    if text == "?" || text.eq_ignore_ascii_case("help") {
+        return Some(ChatCommand::Help);
+    }
+    if text.eq_ignore_ascii_case("status") {
+        return Some(ChatCommand::Status);
+    }
+    if let Some(rest) = strip_keyword(text, "approve plan") {
+        let (task, rest) = split_task_ref(rest)?;
+        return rest
+            .trim()
+            .is_empty()
+            .then_some(ChatCommand::ApprovePlan { task });
+    }
+    if let Some(rest) = strip_keyword(text, "merge") {
+        let (task, rest) = split_task_ref(rest)?;
+        return rest
+            .trim()
+            .is_empty()
+            .then_some(ChatCommand::Merge { task });
+    }
+    if let Some(rest) = ["follow up", "follow-up", "followup"]
+        .iter()
+        .find_map(|keyword| strip_keyword(text, keyword))
+    {
+        let (task, rest) = split_task_ref(rest)?;
+        let prompt = rest.trim_start().strip_prefix(':')?.trim();
+        return (!prompt.is_empty()).then(|| ChatCommand::FollowUp {
+            task,
+            prompt: prompt.to_string(),
+        });
+    }
+    if let Some(rest) = strip_keyword(text, "create task in") {
+        let (project, title) = rest.split_once(':')?;
+        let (project, title) = (project.trim(), title.trim());
+        return (!project.is_empty() && !title.is_empty()).then(|| ChatCommand::CreateTask {
+            project: project.to_string(),
+            title: title.to_string(),
+        });
+    }
+    None
+}
+
+/// The digits of a phone number
+pub fn normalize_number(number: &str) -> String {
+    number.chars().filter(char::is_ascii_digit).collect()
+}
+
+/// The sender's number from a WhatsApp JID such as
+/// `5511999999999@s.whatsapp.net`. Group chats have no single sender and
+/// yield `None`.
+pub fn sender_number(remote_jid: &str) -> Option<String> {
+    let (user, server) = remote_jid.split_once('@')?;
+    if server != "s.whatsapp.net" && server != "c.us" {
+        return None;
+    }
+    // Linked devices append ':<device>' to the number
+    let number = user.split(':').next().unwrap_or(user);
+    let digits = normalize_number(number);
+    (!digits.is_empty() && digits.len() == number.len()).then_some(digits)
+}
+
+/// The text of an Evolution API `messages.upsert` event and the number it
+/// came from, if it is a text message someone sent to the install
+pub fn incoming_text(event: &serde_json::Value) -> Option<(String, String)> {
+    let name = event.get("event")?.as_str()?;
+    if !name
+        .replace('_', ".")
+        .eq_ignore_ascii_case("messages.upsert")
+    {
+        return None;
+    }
+    let data = event.get("data")?;
+    let key = data.get("key")?;
+    if key.get("fromMe").and_then(|from_me| from_me.as_bool()) == Some(true) {
+        return None;
+    }
+    let sender = sender_number(key.get("remoteJid")?.as_str()?)?;
+    let message = data.get("message")?;
+    let text = message
+        .get("conversation")
+        .or_else(|| message.pointer("/extendedTextMessage/text"))?
+        .as_str()?
+        .trim();
+    (!text.is_empty()).then(|| (sender, text.to_string()))
+}
+
+/// Compare webhook tokens without leaking where they differ
+pub fn tokens_match(expected: &str, given: &str) -> bool {
+    let expected = Sha256::digest(expected.as_bytes());
+    let given = Sha256::digest(given.as_bytes());
+    expected
+        .iter()
+        .zip(given.iter())
+        .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+        == 0
+}
+
+/// The token Evolution API passes as `?token=`, from
+/// `EVOLUTION_API_WEBHOOK_TOKEN` or the `evolution_api.webhook_token`
+/// secret. Incoming messages are refused while neither is set.
+pub async fn webhook_token(store: &SecretStore) -> Option<String> {
+    if let Ok(token) = std::env::var("EVOLUTION_API_WEBHOOK_TOKEN") {
+        return Some(token).filter(|token| !token.is_empty());
+    }
+    store
+        .get(CONFIG_SCOPE, EVOLUTION_API_WEBHOOK_TOKEN_SECRET)
+        .await
+        .unwrap_or_else(|e| {
+            tracing::warn!(
+                "Failed to read WhatsApp webhook token from secret store: {}",
+                e
+            );
+            None
+        })
+        .map(|token| token.as_str().to_string())
+        .filter(|token| !token.is_empty())
+}
+
+/// The user who verified `number`. Numbers that are only on a channel
+/// identify no one.
+pub async fn find_sender(pool: &SqlitePool, number: &str) -> Result<Option<User>, sqlx::Error> {
+    match WhatsAppNumberVerification::find_verified_by_number(pool, number).await? {
+        Some(verification) => User::find_by_id(pool, verification.user_id).await,
+        None => Ok(None),
+    }
+}
+
+/// Whether a user other than `user_id` verified `number`
+pub async fn claimed_by_other(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    number: &str,
+) -> Result<bool, sqlx::Error> {
+    Ok(
+        WhatsAppNumberVerification::find_verified_by_number(pool, &normalize_number(number))
+            .await?
+            .is_some_and(|verification| verification.user_id != user_id),
+    )
+}
+
+fn hash_code(code: &str) -> String {
+    format!("{:x}", Sha256::digest(code.as_bytes()))
+}
+
+/// A random six digit code
+fn verification_code() -> String {
+    let random = Uuid::new_v4();
+    let mut bytes = [0u8; 4];
+    bytes.copy_from_slice(&random.as_bytes()[..4]);
+    format!("{:06}", u32::from_be_bytes(bytes) % 1_000_000)
+}
+
+/// Send a one-time code to `number` through the outbox for the user to enter
+/// in Forge. Returns `false`, sending nothing, when the user already verified
+/// the number.
+pub async fn start_verification(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    number: &str,
+) -> Result<bool, NumberVerificationError> {
+    let number = normalize_number(number);
+    if claimed_by_other(pool, user_id, &number).await? {
+        return Err(NumberVerificationError::Claimed);
+    }
+    let now = chrono::Utc::now();
+    let ttl = chrono::Duration::minutes(VERIFICATION_CODE_TTL_MINUTES);
+    if let Some(existing) = WhatsAppNumberVerification::find(pool, user_id, &number).await? {
+        if existing.verified_at.is_some() {
+            return Ok(false);
+        }
+        let sent_at = existing.expires_at - ttl;
+        if now - sent_at < chrono::Duration::seconds(VERIFICATION_RESEND_SECONDS) {
+            return Err(NumberVerificationError::TooSoon);
+        }
+    }
+
+    let code = verification_code();
+    WhatsAppNumberVerification::start(pool, user_id, &number, &hash_code(&code), now + ttl).await?;
+    let message = format!(
+        "Your Automagik Forge verification code is {}. It expires in {} minutes. \
+         If you did not ask for it, ignore this message.",
+        code, VERIFICATION_CODE_TTL_MINUTES
+    );
+    WhatsAppMessage::enqueue(pool, Some(user_id), &number, &message).await?;
+    Ok(true)
+}
+
+/// Verify the user's `number` with the code sent to it
+pub async fn confirm_verification(
+    pool: &SqlitePool,
+    user_id: Uuid,
+    number: &str,
+    code: &str,
+) -> Result<(), NumberVerificationError> {
+    let number = normalize_number(number);
+    let verification = WhatsAppNumberVerification::find(pool, user_id, &number)
+        .await?
+        .ok_or(NumberVerificationError::NoPendingCode)?;
+    if verification.verified_at.is_some() {
+        return Ok(());
+    }
+    if verification.expires_at <= chrono::Utc::now() {
+        return Err(NumberVerificationError::NoPendingCode);
+    }
+    if verification.attempts >= MAX_VERIFICATION_ATTEMPTS {
+        return Err(NumberVerificationError::TooManyAttempts);
+    }
+    if !tokens_match(&verification.code_hash, &hash_code(code.trim())) {
+        WhatsAppNumberVerification::record_failed_attempt(pool, verification.id).await?;
+        return Err(NumberVerificationError::WrongCode);
+    }
+    match WhatsAppNumberVerification::mark_verified(pool, verification.id).await {
+        Ok(()) => Ok(()),
+        // Another user verified the number since the code was sent
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+            Err(NumberVerificationError::Claimed)
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Drop the user's verifications of numbers none of their WhatsApp channels
+/// has anymore, freeing those numbers for others
+pub async fn forget_unused_numbers(pool: &SqlitePool, user_id: Uuid) -> Result<(), sqlx::Error> {
+    let numbers: Vec<String> = UserNotificationChannel::find_by_user_id(pool, user_id)
+        .await?
+        .into_iter()
+        .filter(|channel| channel.kind == NotificationChannelKind::WhatsApp)
+        .filter_map(|channel| channel.settings.get("number").map(|n| normalize_number(n)))
+        .collect();
+    for verification in WhatsAppNumberVerification::find_by_user_id(pool, user_id).await? {
+        if !numbers.contains(&verification.number) {
+            WhatsAppNumberVerification::delete(pool, verification.id).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Whether a user may act on a task
+pub fn may_act_on(user: &User, task: &Task) -> bool {
+    user.is_admin || task.created_by == Some(user.id) || task.assigned_to == Some(user.id)
+}
+
+fn short_id(id: Uuid) -> String {
+    id.simple().to_string()[..8].to_string()
+}
+
+fn status_label(status: &TaskStatus) -> &'static str {
+    match status {
+        TaskStatus::Todo => "to do",
+        TaskStatus::InProgress => "in progress",
+        TaskStatus::InReview => "in review",
+        TaskStatus::Done => "done",
+        TaskStatus::Cancelled => "cancelled",
+    }
+}
+
+async fn resolve_task(
+    pool: &SqlitePool,
+    user: &User,
+    task_ref: Option<&str>,
+) -> Result<Task, ChatCommandError> {
+    let task = match task_ref {
+        Some(prefix) => {
+            let mut tasks = Task::find_by_id_prefix(pool, prefix).await?;
+            if tasks.len() > 1 {
+                return Err(ChatCommandError::NotFound(format!(
+                    "#{} matches more than one task, send more of its ID",
+                    prefix
+                )));
+            }
+            tasks
+                .pop()
+                .ok_or_else(|| ChatCommandError::NotFound(format!("No task matches #{}", prefix)))?
+        }
+        None => Task::find_active_by_user_id(pool, user.id, 1)
+            .await?
+            .pop()
+            .ok_or_else(|| {
+                ChatCommandError::NotFound(
+                    "You have no task in progress or in review, name one with #<task id>"
+                        .to_string(),
+                )
+            })?,
+    };
+    if !may_act_on(user, &task) {
+        return Err(ChatCommandError::Forbidden(format!(
+            "You can only act on tasks you created or are assigned to, not #{}",
+            short_id(task.id)
+        )));
+    }
+    Ok(task)
+}
+
+async fn latest_attempt(pool: &SqlitePool, task: &Task) -> Result<TaskAttempt, ChatCommandError> {
+    TaskAttempt::find_by_task_id(pool, task.id)
+        .await?
+        .into_iter()
+        .next()
+        .ok_or_else(|| {
+            ChatCommandError::NotFound(format!("#{} has no attempts yet", short_id(task.id)))
+        })
+}
+
+/// Run a command as `user` and return the reply
+pub async fn execute(
+    app_state: &AppState,
+    user: &User,
+    command: &ChatCommand,
+) -> Result<String, ChatCommandError> {
+    let pool = &app_state.db_pool;
+    match command {
+        ChatCommand::Help => Ok(HELP.to_string()),
+        ChatCommand::Status => {
+            let tasks = Task::find_active_by_user_id(pool, user.id, STATUS_TASK_LIMIT).await?;
+            if tasks.is_empty() {
+                return Ok("You have no tasks in progress or in review.".to_string());
+            }
+            let lines: Vec<String> = tasks
+                .iter()
+                .map(|task| {
+                    format!(
+                        "#{} ({}) {}",
+                        short_id(task.id),
+                        status_label(&task.status),
+                        task.title
+                    )
+                })
+                .collect();
+            Ok(format!("*Your active tasks*\n\n{}", lines.join("\n")))
+        }
+        ChatCommand::ApprovePlan { task } => {
+            let task = resolve_task(pool, user, task.as_deref()).await?;
+            let attempt = latest_attempt(pool, &task).await?;
+            let plan = find_plan_content_with_context(pool, attempt.id)
+                .await
+                .map_err(|_| {
+                    ChatCommandError::NotFound(format!(
+                        "The latest attempt of #{} has no plan",
+                        short_id(task.id)
+                    ))
+                })?;
+
+            let new_task_id = Uuid::new_v4();
+            let data = CreateTask {
+                project_id: task.project_id,
+                title: format!("Execute Plan: {}", task.title),
+                description: Some(plan),
+                wish_id: task.wish_id.clone(),
+                parent_task_attempt: Some(attempt.id),
+                created_by: Some(user.id),
+                assigned_to: task.assigned_to,
+            };
+            let new_task = Task::create(pool, &data, new_task_id).await?;
+            webhooks::emit_task_created(pool, &new_task).await;
+
+            Task::update_status(pool, task.id, task.project_id, TaskStatus::Done).await?;
+            webhooks::emit_task_status(pool, task.id, task.project_id, TaskStatus::Done).await;
+
+            Ok(format!(
+                "Plan approved. Created #{}: {}\n{}",
+                short_id(new_task.id),
+                new_task.title,
+                task_url(new_task.project_id, new_task.id)
+            ))
+        }
+        ChatCommand::FollowUp { task, prompt } => {
+            let task = resolve_task(pool, user, task.as_deref()).await?;
+            let attempt = latest_attempt(pool, &task).await?;
+            TaskAttempt::start_followup_execution(
+                pool,
+                app_state,
+                attempt.id,
+                task.id,
+                task.project_id,
+                prompt,
+            )
+            .await
+            .map_err(|e| {
+                ChatCommandError::Failed(format!("Failed to start the follow-up: {}", e))
+            })?;
+            Ok(format!(
+                "Follow-up started on #{}: {}",
+                short_id(task.id),
+                task.title
+            ))
+        }
+        ChatCommand::Merge { task } => {
+            let task = resolve_task(pool, user, task.as_deref()).await?;
+            let attempt = latest_attempt(pool, &task).await?;
+            if attempt.merge_commit.is_some() {
+                return Err(ChatCommandError::Failed(format!(
+                    "The latest attempt of #{} is already merged",
+                    short_id(task.id)
+                )));
+            }
+            let merge_commit =
+                TaskAttempt::merge_changes(pool, attempt.id, task.id, task.project_id)
+                    .await
+                    .map_err(|e| ChatCommandError::Failed(format!("Failed to merge: {}", e)))?;
+            webhooks::emit_attempt_event(
+                pool,
+                WebhookEvent::AttemptMerged,
+                task.project_id,
+                task.id,
+                attempt.id,
+                serde_json::json!({
+                    "branch": attempt.branch,
+                    "base_branch": attempt.base_branch,
+                    "merge_commit": merge_commit,
+                }),
+            )
+            .await;
+
+            Task::update_status(pool, task.id, task.project_id, TaskStatus::Done).await?;
+            webhooks::emit_task_status(pool, task.id, task.project_id, TaskStatus::Done).await;
+            github_issues::comment_on_merge(
+                pool,
+                app_state.secret_store(),
+                &task,
+                &github_issues::merged_into(&attempt.base_branch, &merge_commit),
+            )
+            .await;
+
+            app_state
+                .track_analytics_event(
+                    "task_attempt_merged",
+                    Some(serde_json::json!({
+                        "task_id": task.id.to_string(),
+                        "project_id": task.project_id.to_string(),
+                        "attempt_id": attempt.id.to_string(),
+                        "source": "whatsapp",
+                    })),
+                )
+                .await;
+
+            Ok(format!(
+                "Merged #{} into {} ({})",
+                short_id(task.id),
+                attempt.base_branch,
+                &merge_commit[..merge_commit.len().min(7)]
+            ))
+        }
+        ChatCommand::CreateTask { project, title } => {
+            let mut projects: Vec<Project> = Project::find_all(pool)
+                .await?
+                .into_iter()
+                .filter(|candidate| candidate.name.eq_ignore_ascii_case(project))
+                .collect();
+            if projects.len() > 1 {
+                return Err(ChatCommandError::NotFound(format!(
+                    "More than one project is named '{}'",
+                    project
+                )));
+            }
+            let project = projects.pop().ok_or_else(|| {
+                ChatCommandError::NotFound(format!("No project is named '{}'", project))
+            })?;
+
+            let data = CreateTask {
+                project_id: project.id,
+                title: title.clone(),
+                description: None,
+                wish_id: String::new(),
+                parent_task_attempt: None,
+                created_by: Some(user.id),
+                assigned_to: None,
+            };
+            let task = Task::create(pool, &data, Uuid::new_v4()).await?;
+            webhooks::emit_task_created(pool, &task).await;
+
+            Ok(format!(
+                "Created #{} in {}: {}\n{}",
+                short_id(task.id),
+                project.name,
+                task.title,
+                task_url(project.id, task.id)
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_commands() {
+        assert_eq!(parse(" Status "), Some(ChatCommand::Status));
+        assert_eq!(parse("help"), Some(ChatCommand::Help));
+        assert_eq!(
+            parse("approve plan"),
+            Some(ChatCommand::ApprovePlan { task: None })
+        );
+        assert_eq!(
+            parse("Merge #3F2A-9C1B"),
+            Some(ChatCommand::Merge {
+                task: Some("3f2a9c1b".to_string())
+            })
+        );
+        assert_eq!(
+            parse("follow up #3f2a: also update the docs"),
+            Some(ChatCommand::FollowUp {
+                task: Some("3f2a".to_string()),
+                prompt: "also update the docs".to_string()
+            })
+        );
+        assert_eq!(
+            parse("follow-up: fix the tests"),
+            Some(ChatCommand::FollowUp {
+                task: None,
+                prompt: "fix the tests".to_string()
+            })
+        );
+        assert_eq!(
+            parse("create task in Forge: Add dark mode"),
+            Some(ChatCommand::CreateTask {
+                project: "Forge".to_string(),
+                title: "Add dark mode".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_other_messages() {
+        assert_eq!(parse("thanks!"), None);
+        assert_eq!(parse("merged it already"), None);
+        assert_eq!(parse("merge #xyz"), None);
+        assert_eq!(parse("merge now"), None);
+        assert_eq!(parse("follow up"), None);
+        assert_eq!(parse("follow up:   "), None);
+        assert_eq!(parse("create task in Forge"), None);
+    }
+
+    #[test]
+    fn test_sender_number() {
+        assert_eq!(
+            sender_number("5511999999999@s.whatsapp.net"),
+            Some("5511999999999".to_string())
+        );
+        assert_eq!(
+            sender_number("5511999999999:12@s.whatsapp.net"),
+            Some("5511999999999".to_string())
+        );
+        assert_eq!(sender_number("120363025@g.us"), None);
+        assert_eq!(normalize_number("+55 (11) 99999-9999"), "5511999999999");
+    }
+
+    #[test]
+    fn test_incoming_text() {
+        let event = serde_json::json!({
+            "event": "messages.upsert",
+            "data": {
+                "key": { "remoteJid": "5511999999999@s.whatsapp.net", "fromMe": false },
+                "message": { "extendedTextMessage": { "text": " status " } }
+            }
+        });
+        assert_eq!(
+            incoming_text(&event),
+            Some(("5511999999999".to_string(), "status".to_string()))
+        );
+
+        let mut own = event.clone();
+        own["data"]["key"]["fromMe"] = serde_json::json!(true);
+        assert_eq!(incoming_text(&own), None);
+
+        let mut other = event;
+        other["event"] = serde_json::json!("CONNECTION_UPDATE");
+        assert_eq!(incoming_text(&other), None);
+    }
+
+    #[test]
+    fn test_tokens_match() {
+        assert!(tokens_match("s3cret", "s3cret"));
+        assert!(!tokens_match("s3cret", "s3cre"));
+        assert!(!tokens_match("s3cret", ""));
+    }
+
+    #[test]
+    fn test_verification_code() {
+        for _ in 0..100 {
+            let code = verification_code();
+            assert_eq!(code.len(), 6);
+            assert!(code.chars().all(|c| c.is_ascii_digit()));
+        }
+    }
+
+    async fn create_user(pool: &SqlitePool, github_id: i64, username: &str) -> User {
+        User::create(
+            pool,
+            &crate::models::user::CreateUser {
+                github_id,
+                username: username.to_string(),
+                email: format!("{}@example.com", username),
+                display_name: None,
+                avatar_url: None,
+                github_token: None,
+                is_admin: None,
+            },
+            Uuid::new_v4(),
+        )
+        .await
+        .unwrap()
+    }
+
+    async fn sent_code(pool: &SqlitePool, user_id: Uuid) -> String {
+        let message: String = sqlx::query_scalar(
+            "SELECT message FROM whatsapp_outbox WHERE user_id = $1 ORDER BY created_at DESC LIMIT 1",
+        )
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+        .unwrap();
+        message
+            .split(|c: char| !c.is_ascii_digit())
+            .find(|word| word.len() == 6)
+            .unwrap()
+            .to_string()
+    }
+
+    #[tokio::test]
+    async fn test_number_verification() {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+        let alice = create_user(&pool, 1, "alice").await;
+        let bob = create_user(&pool, 2, "bob").await;
+        let number = "+55 11 99999-0000";
+
+        assert!(start_verification(&pool, alice.id, number).await.unwrap());
+        assert!(find_sender(&pool, "5511999990000").await.unwrap().is_none());
+        assert!(matches!(
+            start_verification(&pool, alice.id, number).await,
+            Err(NumberVerificationError::TooSoon)
+        ));
+        // Bob asks for a code for the same number before Alice enters hers
+        assert!(start_verification(&pool, bob.id, number).await.unwrap());
+
+        assert!(matches!(
+            confirm_verification(&pool, alice.id, number, "nope").await,
+            Err(NumberVerificationError::WrongCode)
+        ));
+        let code = sent_code(&pool, alice.id).await;
+        confirm_verification(&pool, alice.id, number, &code)
+            .await
+            .unwrap();
+        let sender = find_sender(&pool, "5511999990000").await.unwrap().unwrap();
+        assert_eq!(sender.id, alice.id);
+        assert!(!start_verification(&pool, alice.id, number).await.unwrap());
+
+        let code = sent_code(&pool, bob.id).await;
+        assert!(matches!(
+            confirm_verification(&pool, bob.id, number, &code).await,
+            Err(NumberVerificationError::Claimed)
+        ));
+        assert!(claimed_by_other(&pool, bob.id, number).await.unwrap());
+        assert!(matches!(
+            start_verification(&pool, bob.id, number).await,
+            Err(NumberVerificationError::Claimed)
+        ));
+
+        // Alice has no channel with the number, so her claim is dropped
+        forget_unused_numbers(&pool, alice.id).await.unwrap();
+        assert!(find_sender(&pool, "5511999990000").await.unwrap().is_none());
+        assert!(!claimed_by_other(&pool, bob.id, number).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_number_verification_attempts_are_limited() {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+        let alice = create_user(&pool, 1, "alice").await;
+        let number = "5511999990000";
+
+        assert!(start_verification(&pool, alice.id, number).await.unwrap());
+        for _ in 0..MAX_VERIFICATION_ATTEMPTS {
+            assert!(matches!(
+                confirm_verification(&pool, alice.id, number, "nope").await,
+                Err(NumberVerificationError::WrongCode)
+            ));
+        }
+        let code = sent_code(&pool, alice.id).await;
+        assert!(matches!(
+            confirm_verification(&pool, alice.id, number, &code).await,
+            Err(NumberVerificationError::TooManyAttempts)
+        ));
+        assert!(find_sender(&pool, number).await.unwrap().is_none());
+    }
+}
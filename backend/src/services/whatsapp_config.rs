@@ -1,22 +1,33 @@
 use std::env;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct WhatsAppConfig {
     pub base_url: String,
     pub api_key: String,
     pub instance: String,
+    /// Recipient of install-wide notifications. Users get theirs on the
+    /// number of their own WhatsApp notification channel.
     pub fixed_recipient: Option<String>,
     pub timeout_ms: u64,
     pub include_task_url: bool,
 }
 
 impl WhatsAppConfig {
+    #[allow(dead_code)]
     pub fn from_env() -> anyhow::Result<Self> {
+        Self::from_env_with_api_key(None)
+    }
+
+    /// Like `from_env`, but falls back to `stored_api_key` (the
+    /// `evolution_api.api_key` secret) when `EVOLUTION_API_API_KEY` is unset
+    pub fn from_env_with_api_key(stored_api_key: Option<&str>) -> anyhow::Result<Self> {
         Ok(Self {
             base_url: env::var("EVOLUTION_API_BASE_URL")
                 .map_err(|_| anyhow::anyhow!("EVOLUTION_API_BASE_URL not set"))?,
             api_key: env::var("EVOLUTION_API_API_KEY")
-                .map_err(|_| anyhow::anyhow!("EVOLUTION_API_API_KEY not set"))?,
+                .ok()
+                .or_else(|| stored_api_key.map(str::to_string))
+                .ok_or_else(|| anyhow::anyhow!("EVOLUTION_API_API_KEY not set"))?,
             instance: env::var("EVOLUTION_API_INSTANCE")
                 .map_err(|_| anyhow::anyhow!("EVOLUTION_API_INSTANCE not set"))?,
             fixed_recipient: env::var("EVOLUTION_API_FIXED_RECIPIENT").ok(),
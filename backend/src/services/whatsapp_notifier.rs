@@ -1,96 +1,245 @@
-use crate::services::whatsapp_config::WhatsAppConfig;
+use std::time::Duration;
+
 use anyhow::Result;
-use rmcp::{model::CallToolRequestParam, service::ServiceExt, transport::TokioChildProcess, transport::ConfigureCommandExt};
+use async_trait::async_trait;
+use rmcp::{
+    model::CallToolRequestParam,
+    service::{RunningService, ServiceExt},
+    transport::{ConfigureCommandExt, TokioChildProcess},
+    RoleClient,
+};
 use serde_json::json;
-use tokio::process::Command;
-use std::time::Duration;
+use sqlx::SqlitePool;
+use tokio::{process::Command, sync::Mutex};
+use uuid::Uuid;
 
-#[derive(Debug)]
-pub struct WhatsAppNotifier {
+use crate::{
+    models::whatsapp_message::WhatsAppMessage,
+    services::{
+        notification_channels::{Notification, NotificationChannel, NotificationChannelError},
+        whatsapp_config::WhatsAppConfig,
+    },
+};
+
+const CANCEL_TIMEOUT: Duration = Duration::from_secs(5);
+
+struct Session {
     config: WhatsAppConfig,
+    service: RunningService<RoleClient, ()>,
 }
 
-impl WhatsAppNotifier {
-    pub async fn new(config: WhatsAppConfig) -> Result<Self> {
-        tracing::info!("Initializing WhatsApp notifier with base URL: {}", config.base_url);
-        Ok(Self { config })
+/// Long-lived MCP client session with the `automagik-tools` Evolution API
+/// server. The server process is started on first use and restarted after a
+/// failed call or health check, or when the configuration changes.
+#[derive(Default)]
+pub struct WhatsAppClient {
+    session: Mutex<Option<Session>>,
+}
+
+impl std::fmt::Debug for WhatsAppClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WhatsAppClient").finish_non_exhaustive()
     }
-    
-    pub async fn send_notification(&self, title: &str, message: &str) -> Result<()> {
-        tracing::info!("Attempting to send WhatsApp notification: {}", title);
-        
-        // Format message for WhatsApp with optional URL
-        let formatted_message = format!("*{}*\n\n{}", title, message);
-        
-        // Use include_task_url for future URL functionality
-        let _include_url = self.config.include_task_url;
-        
-        // Prepare WhatsApp message parameters
+}
+
+impl WhatsAppClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    async fn connect(config: &WhatsAppConfig) -> Result<RunningService<RoleClient, ()>> {
+        tracing::info!(
+            "Starting WhatsApp MCP session with base URL: {}",
+            config.base_url
+        );
+        // Create STDIO transport using TokioChildProcess for MCP evolution-api tool.
+        // Recipients are passed per message, so no fixed recipient is set.
+        let transport = TokioChildProcess::new(Command::new("uvx").configure(|cmd| {
+            cmd.arg("automagik-tools@0.8.15")
+                .arg("tool")
+                .arg("evolution-api")
+                .env("EVOLUTION_API_BASE_URL", &config.base_url)
+                .env("EVOLUTION_API_API_KEY", &config.api_key)
+                .env("EVOLUTION_API_INSTANCE", &config.instance);
+        }))
+        .map_err(|e| anyhow::anyhow!("Failed to create STDIO transport: {}", e))?;
+
+        let timeout_duration = Duration::from_millis(config.timeout_ms);
+        tokio::time::timeout(timeout_duration, ().serve(transport))
+            .await
+            .map_err(|_| {
+                anyhow::anyhow!(
+                    "MCP service creation timed out after {}ms",
+                    config.timeout_ms
+                )
+            })?
+            .map_err(|e| anyhow::anyhow!("Failed to create MCP service: {}", e))
+    }
+
+    async fn close(session: Session) {
+        let _ = tokio::time::timeout(CANCEL_TIMEOUT, session.service.cancel()).await;
+    }
+
+    /// Send a text message to `number`, starting the session if needed. A
+    /// failed call closes the session so the next one starts afresh.
+    pub async fn send_text(
+        &self,
+        config: &WhatsAppConfig,
+        number: &str,
+        message: &str,
+    ) -> Result<()> {
+        let mut guard = self.session.lock().await;
+        if guard
+            .as_ref()
+            .is_some_and(|session| session.config != *config)
+        {
+            tracing::info!("WhatsApp configuration changed, restarting MCP session");
+            if let Some(session) = guard.take() {
+                Self::close(session).await;
+            }
+        }
+        if guard.is_none() {
+            let service = Self::connect(config).await?;
+            *guard = Some(Session {
+                config: config.clone(),
+                service,
+            });
+        }
+        let Some(session) = guard.as_ref() else {
+            return Err(anyhow::anyhow!("WhatsApp MCP session is not available"));
+        };
+
         let params = json!({
-            "instance": self.config.instance,
-            "message": formatted_message,
-            "number": self.config.fixed_recipient,
+            "instance": config.instance,
+            "message": message,
+            "number": number,
             "linkPreview": true,
             "delay": 0
         });
-        
-        tracing::debug!("WhatsApp message params: {}", serde_json::to_string_pretty(&params).unwrap_or_default());
-        
-        // Create SSE transport and service for this request
-        match self.send_via_mcp(&params).await {
-            Ok(_) => {
-                tracing::info!("WhatsApp notification sent successfully: {}", title);
+        let timeout_duration = Duration::from_millis(config.timeout_ms);
+        let tool_call = session.service.call_tool(CallToolRequestParam {
+            name: "send_text_message".into(),
+            arguments: params.as_object().cloned(),
+        });
+        let result = match tokio::time::timeout(timeout_duration, tool_call).await {
+            Ok(Ok(result)) if result.is_error != Some(true) => Ok(result),
+            Ok(Ok(result)) => Err(anyhow::anyhow!(
+                "MCP tool returned an error: {:?}",
+                result.content
+            )),
+            Ok(Err(e)) => Err(anyhow::anyhow!("Failed to call MCP tool: {}", e)),
+            Err(_) => Err(anyhow::anyhow!(
+                "MCP tool call timed out after {}ms",
+                config.timeout_ms
+            )),
+        };
+
+        match result {
+            Ok(result) => {
+                tracing::debug!("MCP tool result: {:?}", result);
                 Ok(())
             }
             Err(e) => {
-                tracing::warn!("Failed to send WhatsApp notification: {}. Notification will be skipped.", e);
-                Ok(()) // Don't fail the entire operation if notification fails
+                if let Some(session) = guard.take() {
+                    Self::close(session).await;
+                }
+                Err(e)
             }
         }
     }
-    
-    async fn send_via_mcp(&self, params: &serde_json::Value) -> Result<()> {
-        // Create STDIO transport using TokioChildProcess for MCP evolution-api tool
-        let transport = TokioChildProcess::new(Command::new("uvx").configure(|cmd| {
-            cmd.arg("automagik-tools@0.8.15")
-                .arg("tool")
-                .arg("evolution-api")
-                .env("EVOLUTION_API_BASE_URL", &self.config.base_url)
-                .env("EVOLUTION_API_API_KEY", &self.config.api_key)
-                .env("EVOLUTION_API_INSTANCE", &self.config.instance);
-            
-            // Set fixed recipient if configured
-            if let Some(ref fixed_recipient) = self.config.fixed_recipient {
-                if !fixed_recipient.is_empty() {
-                    cmd.env("EVOLUTION_API_FIXED_RECIPIENT", fixed_recipient);
-                }
+
+    /// Check that the running session still answers, closing it if not.
+    /// Succeeds when there is no session to check.
+    pub async fn health_check(&self) -> Result<()> {
+        let mut guard = self.session.lock().await;
+        let Some(session) = guard.as_ref() else {
+            return Ok(());
+        };
+        let timeout_duration = Duration::from_millis(session.config.timeout_ms);
+        let result = match tokio::time::timeout(
+            timeout_duration,
+            session.service.list_tools(Default::default()),
+        )
+        .await
+        {
+            Ok(Ok(_)) => Ok(()),
+            Ok(Err(e)) => Err(anyhow::anyhow!("Failed to list MCP tools: {}", e)),
+            Err(_) => Err(anyhow::anyhow!(
+                "MCP health check timed out after {}ms",
+                session.config.timeout_ms
+            )),
+        };
+        if result.is_err() {
+            if let Some(session) = guard.take() {
+                Self::close(session).await;
             }
-        }))
-        .map_err(|e| anyhow::anyhow!("Failed to create STDIO transport: {}", e))?;
-        
-        // Create service with timeout
-        let timeout_duration = Duration::from_millis(self.config.timeout_ms);
-        let service_future = ().serve(transport);
-        let service = tokio::time::timeout(timeout_duration, service_future).await
-            .map_err(|_| anyhow::anyhow!("MCP service creation timed out after {}ms", self.config.timeout_ms))?
-            .map_err(|e| anyhow::anyhow!("Failed to create MCP service: {}", e))?;
-        
-        // Call WhatsApp MCP tool with timeout
-        let tool_call_future = service.call_tool(CallToolRequestParam {
-            name: "send_text_message".into(),
-            arguments: params.as_object().cloned(),
-        });
-        let result = tokio::time::timeout(timeout_duration, tool_call_future).await
-            .map_err(|_| anyhow::anyhow!("MCP tool call timed out after {}ms", self.config.timeout_ms))?
-            .map_err(|e| anyhow::anyhow!("Failed to call MCP tool: {}", e))?;
-        
-        tracing::debug!("MCP tool result: {:?}", result);
-        
-        // Cancel the service to clean up
-        let cancel_future = service.cancel();
-        let _ = tokio::time::timeout(Duration::from_millis(5000), cancel_future).await;
-        
+        }
+        result
+    }
+
+    /// Stop the session, if any
+    #[allow(dead_code)]
+    pub async fn disconnect(&self) {
+        if let Some(session) = self.session.lock().await.take() {
+            Self::close(session).await;
+        }
+    }
+}
+
+/// Queues notifications for one WhatsApp recipient in the outbox, from where
+/// `WhatsAppOutboxService` sends them
+#[derive(Debug)]
+pub struct WhatsAppNotifier {
+    pool: SqlitePool,
+    recipient: String,
+    user_id: Option<Uuid>,
+    include_task_url: bool,
+}
+
+impl WhatsAppNotifier {
+    /// `user_id` is the user whose channel this is, if any
+    pub fn new(
+        pool: SqlitePool,
+        recipient: String,
+        user_id: Option<Uuid>,
+        include_task_url: bool,
+    ) -> Self {
+        Self {
+            pool,
+            recipient,
+            user_id,
+            include_task_url,
+        }
+    }
+
+    pub async fn send_notification(&self, title: &str, message: &str) -> Result<()> {
+        // Format message for WhatsApp
+        let formatted_message = format!("*{}*\n\n{}", title, message);
+        let id = WhatsAppMessage::enqueue(
+            &self.pool,
+            self.user_id,
+            &self.recipient,
+            &formatted_message,
+        )
+        .await?;
+        tracing::debug!("Queued WhatsApp notification {}: {}", id, title);
         Ok(())
     }
 }
 
+#[async_trait]
+impl NotificationChannel for WhatsAppNotifier {
+    fn kind(&self) -> &'static str {
+        "whatsapp"
+    }
+
+    async fn send(&self, notification: &Notification) -> Result<(), NotificationChannelError> {
+        let message = match (&notification.url, self.include_task_url) {
+            (Some(url), true) => format!("{}\n\n{}", notification.message, url),
+            _ => notification.message.clone(),
+        };
+        self.send_notification(&notification.title, &message)
+            .await
+            .map_err(|e| NotificationChannelError::WhatsApp(e.to_string()))
+    }
+}
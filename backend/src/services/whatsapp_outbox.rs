@@ -0,0 +1,128 @@
+//! Delivery of queued WhatsApp messages.
+//!
+//! Notifiers only write to the `whatsapp_outbox` table; this service sends
+//! what is due over one long-lived MCP session, records each message's
+//! outcome and retries failures with exponential backoff. The session is
+//! health-checked while idle and restarted when it stops answering.
+
+use std::{sync::Arc, time::Duration};
+
+use sqlx::SqlitePool;
+
+use crate::{
+    models::whatsapp_message::WhatsAppMessage,
+    security::secret_store::SecretStore,
+    services::{notification_channels, WhatsAppClient},
+};
+
+/// Attempts before a message is marked failed
+pub const MAX_ATTEMPTS: i64 = 6;
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+const BATCH_SIZE: i64 = 20;
+const BASE_RETRY_SECONDS: i64 = 30;
+const MAX_RETRY_SECONDS: i64 = 60 * 60;
+
+/// Seconds to wait before retrying a message that failed for the
+/// `attempts`-th time, or `None` once it is out of attempts
+pub fn retry_delay_seconds(attempts: i64) -> Option<i64> {
+    if attempts >= MAX_ATTEMPTS {
+        return None;
+    }
+    let exponent = (attempts - 1).clamp(0, 20) as u32;
+    Some((BASE_RETRY_SECONDS * 2i64.pow(exponent)).min(MAX_RETRY_SECONDS))
+}
+
+pub struct WhatsAppOutboxService {
+    pool: SqlitePool,
+    secret_store: Arc<SecretStore>,
+    client: WhatsAppClient,
+}
+
+impl WhatsAppOutboxService {
+    pub fn new(pool: SqlitePool, secret_store: Arc<SecretStore>) -> Self {
+        Self {
+            pool,
+            secret_store,
+            client: WhatsAppClient::new(),
+        }
+    }
+
+    pub async fn start(&self) {
+        tracing::info!(
+            "Starting WhatsApp outbox service with interval {:?}",
+            POLL_INTERVAL
+        );
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        let mut health_check = tokio::time::interval(HEALTH_CHECK_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    if let Err(e) = self.send_due().await {
+                        tracing::error!("Failed to send queued WhatsApp messages: {}", e);
+                    }
+                }
+                _ = health_check.tick() => {
+                    if let Err(e) = self.client.health_check().await {
+                        tracing::warn!("WhatsApp MCP session failed its health check and was closed: {}", e);
+                    }
+                }
+            }
+        }
+    }
+
+    async fn send_due(&self) -> Result<(), sqlx::Error> {
+        let due = WhatsAppMessage::find_due(&self.pool, BATCH_SIZE).await?;
+        if due.is_empty() {
+            return Ok(());
+        }
+
+        // Read on every batch so a rotated API key is picked up
+        let config = notification_channels::whatsapp_config(&self.secret_store).await;
+        for message in due {
+            let result = match &config {
+                Some(config) => {
+                    self.client
+                        .send_text(config, &message.recipient, &message.message)
+                        .await
+                }
+                None => Err(anyhow::anyhow!("WhatsApp is not configured on this server")),
+            };
+            match result {
+                Ok(()) => WhatsAppMessage::mark_sent(&self.pool, message.id).await?,
+                Err(e) => {
+                    let retry_in = retry_delay_seconds(message.attempts + 1);
+                    if retry_in.is_none() {
+                        tracing::warn!(
+                            "Giving up on WhatsApp message {} after {} attempts: {}",
+                            message.id,
+                            message.attempts + 1,
+                            e
+                        );
+                    }
+                    WhatsAppMessage::mark_attempt_failed(
+                        &self.pool,
+                        message.id,
+                        &e.to_string(),
+                        retry_in,
+                    )
+                    .await?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_delay_backs_off_and_gives_up() {
+        assert_eq!(retry_delay_seconds(1), Some(30));
+        assert_eq!(retry_delay_seconds(2), Some(60));
+        assert_eq!(retry_delay_seconds(5), Some(480));
+        assert_eq!(retry_delay_seconds(MAX_ATTEMPTS), None);
+    }
+}
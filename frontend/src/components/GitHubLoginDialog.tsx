@@ -1,4 +1,4 @@
-import { useEffect, useState } from 'react';
+import { FormEvent, useEffect, useState } from 'react';
 import {
   Dialog,
   DialogContent,
@@ -8,11 +8,13 @@ import {
   DialogTitle,
 } from './ui/dialog';
 import { Button } from './ui/button';
+import { Input } from './ui/input';
+import { Label } from './ui/label';
 import { useAuth } from './auth-provider';
-import { Check, Clipboard, Github } from 'lucide-react';
+import { Check, Clipboard, Github, KeyRound, LogIn } from 'lucide-react';
 import { Loader } from './ui/loader';
 import { authApi } from '../lib/api';
-import { DeviceStartResponse } from 'shared/types.ts';
+import { AuthProviderInfo, DeviceStartResponse } from 'shared/types.ts';
 import { Card, CardContent, CardHeader, CardTitle } from './ui/card';
 
 export function GitHubLoginDialog({
@@ -30,6 +32,54 @@ export function GitHubLoginDialog({
   );
   const [polling, setPolling] = useState(false);
   const [copied, setCopied] = useState(false);
+  const [providers, setProviders] = useState<AuthProviderInfo[] | null>(null);
+  const [username, setUsername] = useState('');
+  const [password, setPassword] = useState('');
+
+  useEffect(() => {
+    if (!open) return;
+    authApi
+      .getProviders()
+      .then(setProviders)
+      .catch((e) => {
+        console.error('Failed to load login providers:', e);
+        setProviders(null);
+      });
+  }, [open]);
+
+  // Older servers without the providers endpoint only support GitHub
+  const githubEnabled =
+    providers === null || providers.some((p) => p.kind === 'github');
+  const localProvider = providers?.find((p) => p.kind === 'local');
+  const oidcProvider = providers?.find((p) => p.kind === 'oidc');
+
+  const handleLocalLogin = async (e: FormEvent) => {
+    e.preventDefault();
+    setFetching(true);
+    setError(null);
+    try {
+      const authResponse = await authApi.localLogin(username, password);
+      login(authResponse.access_token, authResponse.user, authResponse.session);
+      setPassword('');
+      onOpenChange(false);
+    } catch (e: any) {
+      setError(e?.message || 'Login failed.');
+    } finally {
+      setFetching(false);
+    }
+  };
+
+  const handleOidcLogin = async () => {
+    setFetching(true);
+    setError(null);
+    try {
+      const { authorization_url } = await authApi.startOidcAuth();
+      window.location.href = authorization_url;
+    } catch (e: any) {
+      setError(e?.message || 'Failed to start single sign-on.');
+      setFetching(false);
+    }
+  };
 
   const handleLogin = async () => {
     setFetching(true);
@@ -125,12 +175,19 @@ export function GitHubLoginDialog({
       <DialogContent>
         <DialogHeader>
           <div className="flex items-center gap-3">
-            <Github className="h-6 w-6 text-primary" />
-            <DialogTitle>Sign in with GitHub</DialogTitle>
+            {githubEnabled ? (
+              <Github className="h-6 w-6 text-primary" />
+            ) : (
+              <LogIn className="h-6 w-6 text-primary" />
+            )}
+            <DialogTitle>
+              {githubEnabled ? 'Sign in with GitHub' : 'Sign in'}
+            </DialogTitle>
           </div>
           <DialogDescription className="text-left pt-1">
-            Connect your GitHub account to create and manage pull requests
-            directly from Automagik Forge.
+            {githubEnabled
+              ? 'Connect your GitHub account to create and manage pull requests directly from Automagik Forge.'
+              : 'Sign in to Automagik Forge.'}
           </DialogDescription>
         </DialogHeader>
         {isLoading ? (
@@ -244,42 +301,86 @@ export function GitHubLoginDialog({
           </div>
         ) : (
           <div className="space-y-4 py-3">
-            <Card>
-              <CardHeader className="pb-3">
-                <CardTitle className="text-base">
-                  Why do you need GitHub access?
-                </CardTitle>
-              </CardHeader>
-              <CardContent className="space-y-3 pt-0">
-                <div className="flex items-start gap-3">
-                  <Check className="h-4 w-4 text-green-500 mt-0.5 flex-shrink-0" />
-                  <div>
-                    <p className="text-sm font-medium">Create pull requests</p>
-                    <p className="text-xs text-muted-foreground">
-                      Generate PRs directly from your task attempts
-                    </p>
-                  </div>
+            {localProvider && (
+              <form onSubmit={handleLocalLogin} className="space-y-3">
+                <div className="space-y-1">
+                  <Label htmlFor="login-username">Username</Label>
+                  <Input
+                    id="login-username"
+                    autoComplete="username"
+                    value={username}
+                    onChange={(e) => setUsername(e.target.value)}
+                    required
+                  />
                 </div>
-                <div className="flex items-start gap-3">
-                  <Check className="h-4 w-4 text-green-500 mt-0.5 flex-shrink-0" />
-                  <div>
-                    <p className="text-sm font-medium">Manage repositories</p>
-                    <p className="text-xs text-muted-foreground">
-                      Access your repos to push changes and create branches
-                    </p>
-                  </div>
+                <div className="space-y-1">
+                  <Label htmlFor="login-password">Password</Label>
+                  <Input
+                    id="login-password"
+                    type="password"
+                    autoComplete="current-password"
+                    value={password}
+                    onChange={(e) => setPassword(e.target.value)}
+                    required
+                  />
                 </div>
-                <div className="flex items-start gap-3">
-                  <Check className="h-4 w-4 text-green-500 mt-0.5 flex-shrink-0" />
-                  <div>
-                    <p className="text-sm font-medium">Streamline workflow</p>
-                    <p className="text-xs text-muted-foreground">
-                      Skip manual PR creation and focus on coding
-                    </p>
+                <Button type="submit" disabled={fetching} className="w-full">
+                  <KeyRound className="h-4 w-4 mr-2" />
+                  {fetching ? 'Signing in…' : 'Sign in'}
+                </Button>
+              </form>
+            )}
+
+            {oidcProvider && (
+              <Button
+                variant="outline"
+                onClick={handleOidcLogin}
+                disabled={fetching}
+                className="w-full"
+              >
+                <LogIn className="h-4 w-4 mr-2" />
+                Continue with {oidcProvider.display_name}
+              </Button>
+            )}
+
+            {githubEnabled && (
+              <Card>
+                <CardHeader className="pb-3">
+                  <CardTitle className="text-base">
+                    Why do you need GitHub access?
+                  </CardTitle>
+                </CardHeader>
+                <CardContent className="space-y-3 pt-0">
+                  <div className="flex items-start gap-3">
+                    <Check className="h-4 w-4 text-green-500 mt-0.5 flex-shrink-0" />
+                    <div>
+                      <p className="text-sm font-medium">Create pull requests</p>
+                      <p className="text-xs text-muted-foreground">
+                        Generate PRs directly from your task attempts
+                      </p>
+                    </div>
                   </div>
-                </div>
-              </CardContent>
-            </Card>
+                  <div className="flex items-start gap-3">
+                    <Check className="h-4 w-4 text-green-500 mt-0.5 flex-shrink-0" />
+                    <div>
+                      <p className="text-sm font-medium">Manage repositories</p>
+                      <p className="text-xs text-muted-foreground">
+                        Access your repos to push changes and create branches
+                      </p>
+                    </div>
+                  </div>
+                  <div className="flex items-start gap-3">
+                    <Check className="h-4 w-4 text-green-500 mt-0.5 flex-shrink-0" />
+                    <div>
+                      <p className="text-sm font-medium">Streamline workflow</p>
+                      <p className="text-xs text-muted-foreground">
+                        Skip manual PR creation and focus on coding
+                      </p>
+                    </div>
+                  </div>
+                </CardContent>
+              </Card>
+            )}
 
             {error && (
               <div className="p-3 bg-red-50 border border-red-200 rounded-lg">
@@ -295,14 +396,16 @@ export function GitHubLoginDialog({
               >
                 Skip
               </Button>
-              <Button
-                onClick={handleLogin}
-                disabled={fetching}
-                className="flex-1"
-              >
-                <Github className="h-4 w-4 mr-2" />
-                {fetching ? 'Starting…' : 'Sign in with GitHub'}
-              </Button>
+              {githubEnabled && (
+                <Button
+                  onClick={handleLogin}
+                  disabled={fetching}
+                  className="flex-1"
+                >
+                  <Github className="h-4 w-4 mr-2" />
+                  {fetching ? 'Starting…' : 'Sign in with GitHub'}
+                </Button>
+              )}
             </DialogFooter>
           </div>
         )}
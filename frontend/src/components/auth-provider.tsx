@@ -88,9 +88,39 @@ export function AuthProvider({ children }: AuthProviderProps) {
     }
   };
 
+  // Finish an OIDC login when the identity provider redirects back to us
+  const completeOidcLogin = async (): Promise<boolean> => {
+    if (window.location.pathname !== '/auth/oidc/callback') {
+      return false;
+    }
+
+    const params = new URLSearchParams(window.location.search);
+    const code = params.get('code');
+    const state = params.get('state');
+    window.history.replaceState(null, '', '/');
+    if (!code || !state) {
+      return false;
+    }
+
+    try {
+      const response = await authApi.completeOidcAuth(code, state);
+      login(response.access_token, response.user, response.session);
+      return true;
+    } catch (error) {
+      console.error('OIDC login failed:', error);
+      return false;
+    }
+  };
+
   // Initialize authentication state on mount
   useEffect(() => {
-    refreshUser();
+    completeOidcLogin().then((loggedIn) => {
+      if (loggedIn) {
+        setIsLoading(false);
+      } else {
+        refreshUser();
+      }
+    });
   }, []);
 
   // Register/unregister the interceptor logout handler
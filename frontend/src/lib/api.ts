@@ -1,5 +1,6 @@
 // Import all necessary types from shared types
 import {
+  AuthProviderInfo,
   AuthResponse,
   BranchStatus,
   Config,
@@ -15,6 +16,7 @@ import {
   ExecutionProcess,
   ExecutionProcessSummary,
   GitBranch,
+  OidcStartResponse,
   ProcessLogsResponse,
   Project,
   ProjectWithBranch,
@@ -103,6 +105,12 @@ export const makeRequest = async (url: string, options: ExtendedRequestInit = {}
     headers,
   });
 
+  // The backend rotates long-lived session tokens and hands back the new one
+  const refreshedToken = response.headers.get('X-Refreshed-Token');
+  if (refreshedToken) {
+    localStorage.setItem('auth_token', refreshedToken);
+  }
+
   // Intercept 401 responses at the lowest level
   if (response.status === 401) {
     // Check if this is a request that should NOT trigger the global logout handler
@@ -715,6 +723,34 @@ export const authApi = {
     return handleApiResponse<AuthResponse>(response);
   },
   
+  getProviders: async (): Promise<AuthProviderInfo[]> => {
+    const response = await makeRequest('/api/auth/providers');
+    return handleApiResponse<AuthProviderInfo[]>(response);
+  },
+
+  localLogin: async (username: string, password: string): Promise<AuthResponse> => {
+    const response = await makeRequest('/api/auth/local/login', {
+      method: 'POST',
+      body: JSON.stringify({ username, password }),
+    });
+    return handleApiResponse<AuthResponse>(response);
+  },
+
+  startOidcAuth: async (): Promise<OidcStartResponse> => {
+    const response = await makeRequest('/api/auth/oidc/start', {
+      method: 'POST',
+    });
+    return handleApiResponse<OidcStartResponse>(response);
+  },
+
+  completeOidcAuth: async (code: string, state: string): Promise<AuthResponse> => {
+    const response = await makeRequest('/api/auth/oidc/callback', {
+      method: 'POST',
+      body: JSON.stringify({ code, state }),
+    });
+    return handleApiResponse<AuthResponse>(response);
+  },
+
   getCurrentUser: async (): Promise<UserInfoResponse> => {
     const response = await makeRequest('/api/auth/me');
     return handleApiResponse<UserInfoResponse>(response);
@@ -46,6 +46,10 @@ export type GitBranch = { name: string, is_current: boolean, is_remote: boolean,
 
 export type CreateBranch = { name: string, base_branch: string | null, };
 
+export type McpInjectionSettings = { enabled: boolean, supported_executors: Array<string>, };
+
+export type ReviewAutomationSettings = { auto_address_comments: boolean, };
+
 export type CreateTask = { project_id: string, title: string, description: string | null, wish_id: string, parent_task_attempt: string | null, created_by: string | null, assigned_to: string | null, };
 
 export type CreateTaskAndStart = { project_id: string, title: string, description: string | null, wish_id: string, parent_task_attempt: string | null, created_by: string | null, assigned_to: string | null, executor: ExecutorConfig | null, };
@@ -60,6 +64,32 @@ export type TaskWithUsers = { id: string, project_id: string, title: string, des
 
 export type UpdateTask = { title: string | null, description: string | null, status: TaskStatus | null, wish_id: string | null, parent_task_attempt: string | null, assigned_to: string | null, };
 
+export type TaskDependency = { task_id: string, depends_on_task_id: string, created_at: string, };
+
+export type TaskPlanFormat = "markdown" | "yaml";
+
+export type PlannedTask = { key: string, title: string, description: string | null, wish_id: string, executor: string | null, depends_on: Array<string>, depends_on_existing: Array<string>, };
+
+export type CreateTasksFromPlan = { plan: string, format: TaskPlanFormat, wish_id: string | null, dry_run: boolean, };
+
+export type TaskPlanResult = { dry_run: boolean, planned: Array<PlannedTask>, created: Array<Task>, };
+
+export type ImportGitHubIssues = { labels: Array<string>, milestone: string | null, wish_label_prefix: string | null, };
+
+export type GitHubIssueImport = { created: Array<Task>, updated: Array<Task>, unchanged: number, };
+
+export type LinkedGitHubIssue = { task_id: string, issue_number: number, issue_url: string | null, };
+
+export type ForgeKind = "github" | "gitlab" | "gitea";
+
+export type ForgeReviewComment = { id: number, author: string, body: string, path: string | null, line: number | null, url: string | null, };
+
+export type ForgeTokenSummary = { kind: ForgeKind, host: string, masked_value: string, updated_at: Date, };
+
+export type SaveForgeToken = { kind: ForgeKind, host: string, token: string, };
+
+export type GeneratedPrDescription = { title: string, body: string, };
+
 export type TaskTemplate = { id: string, project_id: string | null, title: string, description: string | null, template_name: string, created_at: string, updated_at: string, };
 
 export type CreateTaskTemplate = { project_id: string | null, title: string, description: string | null, template_name: string, };
@@ -86,6 +116,84 @@ export type AuthResponse = { access_token: string, user: User, session: UserSess
 
 export type UserInfoResponse = { user: User, session: UserSession | null, };
 
+export type AuthProviderKind = "github" | "local" | "oidc";
+
+export type LoginFlow = "device_code" | "password" | "redirect";
+
+export type AuthProviderInfo = { kind: AuthProviderKind, display_name: string, login_flow: LoginFlow, };
+
+export type CreateLocalUser = { username: string, password: string, email: string | null, display_name: string | null, is_admin: boolean | null, };
+
+export type LocalLoginRequest = { username: string, password: string, };
+
+export type ChangePasswordRequest = { current_password: string, new_password: string, };
+
+export type OidcStartResponse = { authorization_url: string, };
+
+export type OidcCallbackRequest = { code: string, state: string, };
+
+export type SetSecretRequest = { value: string, };
+
+export type SecretSummary = { name: string, masked_value: string, key_version: bigint, updated_at: Date, };
+
+export type EnvVarScope = "project" | "user";
+
+export type ProjectEnvVars = { project: Array<SecretSummary>, user: Array<SecretSummary>, };
+
+export type SetEnvVarRequest = { name: string, value: string, scope: EnvVarScope, };
+
+export type McpTransport = "stdio" | "http" | "sse";
+
+export type McpServer = { id: string, project_id: string, user_id: string | null, name: string, transport: McpTransport, command: string | null, args: Array<string>, url: string | null, enabled: boolean, created_at: Date, updated_at: Date, };
+
+export type McpServerScope = "project" | "user";
+
+export type McpServerDetails = { server: McpServer, env: Array<SecretSummary>, headers: Array<SecretSummary>, };
+
+export type ProjectMcpServers = { project: Array<McpServerDetails>, user: Array<McpServerDetails>, };
+
+export type SaveMcpServerRequest = { name: string, scope: McpServerScope, transport: McpTransport, command: string | null, args: Array<string>, url: string | null, env: Record<string, string>, headers: Record<string, string>, enabled: boolean, };
+
+export type WebhookEvent = "task_created" | "task_status_changed" | "attempt_started" | "attempt_completed" | "attempt_failed" | "attempt_merged" | "pr_opened" | "pr_merged" | "pr_closed";
+
+export type Webhook = { id: string, project_id: string, url: string, events: Array<WebhookEvent>, enabled: boolean, created_by: string | null, created_at: Date, updated_at: Date, };
+
+export type WebhookDeliveryStatus = "pending" | "delivered" | "failed" | "cancelled";
+
+export type WebhookDelivery = { id: string, webhook_id: string, event: string, payload: string, status: WebhookDeliveryStatus, attempts: bigint, last_status_code: bigint | null, last_error: string | null, next_attempt_at: Date, created_at: Date, delivered_at: Date | null, };
+
+export type ProjectWebhooks = { webhooks: Array<Webhook>, events: Array<WebhookEvent>, };
+
+export type WebhookWithSecret = { webhook: Webhook, secret: string | null, };
+
+export type CreateWebhookRequest = { url: string, events: Array<WebhookEvent>, enabled: boolean, };
+
+export type UpdateWebhookRequest = { url: string, events: Array<WebhookEvent>, enabled: boolean, rotate_secret: boolean, };
+
+export type NotificationChannelKind = "slack" | "discord" | "email" | "ntfy" | "gotify" | "whatsapp";
+
+export type UserNotificationChannel = { id: string, user_id: string, name: string, kind: NotificationChannelKind, settings: Record<string, string>, enabled: boolean, created_at: Date, updated_at: Date, };
+
+export type NotificationChannelDetails = { channel: UserNotificationChannel, secrets: Array<SecretSummary>, number_verified: boolean | null, };
+
+export type SaveNotificationChannelRequest = { name: string, kind: NotificationChannelKind, settings: Record<string, string>, secrets: Record<string, string>, enabled: boolean, };
+
+export type ConfirmWhatsAppNumberRequest = { code: string, };
+
+export type WhatsAppMessageStatus = "pending" | "sent" | "failed";
+
+export type WhatsAppMessage = { id: string, user_id: string | null, recipient: string, message: string, status: WhatsAppMessageStatus, attempts: bigint, last_error: string | null, next_attempt_at: Date, created_at: Date, sent_at: Date | null, };
+
+export type NotificationEvent = "attempt_completed" | "attempt_failed" | "pr_merged";
+
+export type NotificationScope = "created" | "assigned" | "involved";
+
+export type UserNotificationRule = { id: string, user_id: string, name: string, events: Array<NotificationEvent>, scope: NotificationScope, channels: Array<string>, quiet_hours_start: string | null, quiet_hours_end: string | null, utc_offset_minutes: number, digest_minutes: number | null, enabled: boolean, created_at: Date, updated_at: Date, };
+
+export type NotificationRules = { rules: Array<UserNotificationRule>, events: Array<NotificationEvent>, };
+
+export type SaveNotificationRuleRequest = { name: string, events: Array<NotificationEvent>, scope: NotificationScope, channels: Array<string>, quiet_hours_start: string | null, quiet_hours_end: string | null, utc_offset_minutes: number, digest_minutes: number | null, enabled: boolean, };
+
 export type ProcessLogsResponse = { id: string, process_type: ExecutionProcessType, command: string, executor_type: string | null, status: ExecutionProcessStatus, normalized_conversation: NormalizedConversation, };
 
 export type DiffChunkType = "Equal" | "Insert" | "Delete";
@@ -102,6 +210,26 @@ export type ExecutionState = "NotStarted" | "SetupRunning" | "SetupComplete" | "
 
 export type TaskAttemptState = { execution_state: ExecutionState, has_changes: boolean, has_setup_script: boolean, setup_process_id: string | null, coding_agent_process_id: string | null, };
 
+export type PrReviewState = "approved" | "changes_requested" | "commented" | "dismissed";
+
+export type PrReviewDecision = "approved" | "changes_requested";
+
+export type PrChecksStatus = "pending" | "passing" | "failing";
+
+export type PrReview = { id: string, task_attempt_id: string, github_id: number, reviewer: string, state: PrReviewState, body: string | null, submitted_at: Date, };
+
+export type PrReviewComment = { id: string, task_attempt_id: string, github_id: number, review_github_id: number | null, in_reply_to_github_id: number | null, author: string, body: string, path: string, line: number | null, diff_hunk: string | null, url: string, created_at: Date, updated_at: Date, };
+
+export type PrCheckRun = { id: string, task_attempt_id: string, name: string, head_sha: string, status: string, conclusion: string | null, url: string | null, updated_at: Date, };
+
+export type PullRequestState = { pr_url: string | null, pr_number: number | null, pr_status: string | null, review_decision: PrReviewDecision | null, checks_status: PrChecksStatus | null, reviews: Array<PrReview>, comments: Array<PrReviewComment>, checks: Array<PrCheckRun>, };
+
+export type PrCommentFollowupStatus = "running" | "pushed" | "failed";
+
+export type AddressedThread = { root_comment_id: number, last_comment_id: number, };
+
+export type PrCommentFollowup = { id: string, task_attempt_id: string, status: PrCommentFollowupStatus, threads: Array<AddressedThread>, error: string | null, base_commit: string | null, created_at: Date, completed_at: Date | null, };
+
 export type ExecutionProcess = { id: string, task_attempt_id: string, process_type: ExecutionProcessType, executor_type: string | null, status: ExecutionProcessStatus, command: string, args: string | null, working_directory: string, stdout: string | null, stderr: string | null, exit_code: bigint | null, started_at: string, completed_at: string | null, created_at: string, updated_at: string, };
 
 export type ExecutionProcessSummary = { id: string, task_attempt_id: string, process_type: ExecutionProcessType, executor_type: string | null, status: ExecutionProcessStatus, command: string, args: string | null, working_directory: string, exit_code: bigint | null, started_at: string, completed_at: string | null, created_at: string, updated_at: string, };
@@ -128,7 +256,7 @@ export type NormalizedEntryType = { "type": "user_message" } | { "type": "assist
 
 export type ActionType = { "action": "file_read", path: string, } | { "action": "file_write", path: string, } | { "action": "command_run", command: string, } | { "action": "search", query: string, } | { "action": "web_fetch", url: string, } | { "action": "task_create", description: string, } | { "action": "plan_presentation", plan: string, } | { "action": "other", description: string, };
 
-export type User = { id: string, github_id: bigint, username: string, email: string, display_name: string | null, avatar_url: string | null, github_token: string | null, is_admin: boolean, is_whitelisted: boolean, last_login_at: string | null, created_at: Date, updated_at: Date, };
+export type User = { id: string, github_id: bigint, username: string, email: string, display_name: string | null, avatar_url: string | null, is_admin: boolean, is_whitelisted: boolean, last_login_at: string | null, created_at: Date, updated_at: Date, };
 
 export type CreateUser = { github_id: bigint, username: string, email: string, display_name: string | null, avatar_url: string | null, github_token: string | null, is_admin: boolean | null, };
 